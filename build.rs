@@ -0,0 +1,19 @@
+// Compiles the protocol buffer definitions of the gRPC service when built with the `grpc`
+// feature.  An installed protoc named by the PROTOC environment variable takes precedence over
+// the vendored binary.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    {
+        let mut config = tonic_prost_build::Config::new();
+        if std::env::var_os("PROTOC").is_none() {
+            config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+        }
+        tonic_prost_build::configure().compile_with_config(
+            config,
+            &["proto/destination.proto"],
+            &["proto"],
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+/*
+ * C interface to the destination address library, built with the `ffi` feature:
+ *
+ *     cargo rustc --release --lib --features ffi --crate-type cdylib
+ *
+ * Functions returning a string allocate it in the library, and the caller releases it with
+ * where_free_string.  Results are JSON objects.  On failure a function returns NULL, and
+ * where_last_error describes the failure on the calling thread.
+ */
+#ifndef WHERE_H
+#define WHERE_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Parses a one-line address into its components. */
+char *where_parse_address(const char *input);
+
+/* Parses a one-line address and applies the built-in standardization rules. */
+char *where_standardize(const char *input);
+
+/* Scores `other` as a match for `address`, with street names agreeing at a similarity of
+ * `threshold` or above, between 0 and 1. */
+char *where_match_pair(const char *address, const char *other, double threshold);
+
+/* Describes the most recent failure on the calling thread, or returns NULL. */
+char *where_last_error(void);
+
+/* Releases a string returned by the library. */
+void where_free_string(char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* WHERE_H */
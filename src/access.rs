@@ -0,0 +1,278 @@
+//! The `access` module associates address points with the driveway and access points where
+//! responders leave the street, and reports addresses whose nearest access point lies on a
+//! different street than the address, a common cause of routing errors in dispatch (CAD) systems.
+use crate::{
+    Address, AddressErrorKind, Cartesian, IntoCsv, IntoJson, Io, SpatialIndex, crs::geolocated,
+    from_csv, normalize_text, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+/// The `ACCESS_DISTANCE` constant holds the default search radius for access points around an
+/// address, in the units of the projected coordinates.
+pub const ACCESS_DISTANCE: f64 = 500.0;
+
+/// The `AccessPoint` struct represents a driveway or access point, read from a csv file with
+/// columns "street", "x" and "y", and an optional "id".  The `street` field holds the complete
+/// name of the street the access point opens onto, such as "NE 7TH ST" or "NORTHEAST 7TH
+/// STREET".  The coordinates share the projection of the address points.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct AccessPoint {
+    /// The `id` field holds an optional identifier for the access point.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// The `street` field holds the complete name of the street the access point opens onto.
+    pub street: String,
+    /// The `x` field represents the cartesian X portion of the projected coordinates.
+    pub x: f64,
+    /// The `y` field represents the cartesian Y portion of the projected coordinates.
+    pub y: f64,
+}
+
+impl Cartesian for AccessPoint {
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+/// The `AccessPoints` struct holds a vector of type [`AccessPoint`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct AccessPoints(Vec<AccessPoint>);
+
+impl IntoCsv<AccessPoints> for AccessPoints {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+/// The `AccessStatus` enum describes how an address relates to the access points around it.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessStatus {
+    /// The nearest access point opens onto the street of the address.
+    #[display("associated")]
+    Associated,
+    /// The nearest access point opens onto a different street than the address.
+    #[display("other_street")]
+    OtherStreet,
+    /// No access point lies within the search radius.
+    #[default]
+    #[display("missing")]
+    Missing,
+}
+
+geolocated! {
+    /// The `AccessRecord` struct pairs an address with its access point on the street of the
+    /// address, and with its nearest access point on any street.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct AccessRecord {
+        /// The `label` field holds the label of the address.
+        pub label: String,
+        /// The `street` field holds the complete street name of the address.
+        pub street: String,
+        /// The `status` field holds the relation of the address to the nearest access point.
+        pub status: AccessStatus,
+        /// The `access_id` field holds the identifier of the nearest access point on the street of
+        /// the address, if any.
+        pub access_id: Option<String>,
+        /// The `access_distance` field holds the distance to the nearest access point on the street
+        /// of the address, if any.
+        pub access_distance: Option<f64>,
+        /// The `nearest_id` field holds the identifier of the nearest access point on any street.
+        pub nearest_id: Option<String>,
+        /// The `nearest_street` field holds the street name of the nearest access point.
+        pub nearest_street: Option<String>,
+        /// The `nearest_distance` field holds the distance to the nearest access point.
+        pub nearest_distance: Option<f64>,
+        /// The `x` field holds the X coordinate of the address.
+        pub x: f64,
+        /// The `y` field holds the Y coordinate of the address.
+        pub y: f64,
+    }
+}
+
+/// The `AccessRecords` struct holds a vector of type [`AccessRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct AccessRecords(Vec<AccessRecord>);
+
+impl AccessRecords {
+    /// The `associate` method pairs each address in `addresses` with the nearest access point in
+    /// `points` on the street of the address, searching within `radius` of the address.  Street
+    /// names compare ignoring case and extra whitespace, with street types either spelled out or
+    /// abbreviated.  Addresses whose nearest access point opens onto another street are reported
+    /// as [`AccessStatus::OtherStreet`], even when an access point on their own street lies
+    /// further away.
+    pub fn associate<T: Address + Cartesian>(
+        addresses: &[T],
+        points: &[AccessPoint],
+        radius: f64,
+    ) -> Self {
+        let index = SpatialIndex::from_cartesian(points, radius);
+        let streets = points
+            .iter()
+            .map(|point| normalize_text(&point.street))
+            .collect::<Vec<String>>();
+        let records = addresses
+            .iter()
+            .map(|address| {
+                let full = normalize_text(&address.complete_street_name(false));
+                let abbreviated = normalize_text(&address.complete_street_name(true));
+                let mut candidates = index
+                    .within([address.x(), address.y()], radius)
+                    .into_iter()
+                    .map(|i| (i, address.distance(&points[i])))
+                    .filter(|(_, d)| *d <= radius)
+                    .collect::<Vec<(usize, f64)>>();
+                candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let on_street = |i: usize| streets[i] == full || streets[i] == abbreviated;
+                let access = candidates.iter().find(|(i, _)| on_street(*i));
+                let nearest = candidates.first();
+                let status = match nearest {
+                    Some((i, _)) if on_street(*i) => AccessStatus::Associated,
+                    Some(_) => AccessStatus::OtherStreet,
+                    None => AccessStatus::Missing,
+                };
+                AccessRecord {
+                    label: address.label(),
+                    street: address.complete_street_name(true),
+                    status,
+                    access_id: access.and_then(|(i, _)| points[*i].id.clone()),
+                    access_distance: access.map(|(_, d)| *d),
+                    nearest_id: nearest.and_then(|(i, _)| points[*i].id.clone()),
+                    nearest_street: nearest.map(|(i, _)| points[*i].street.clone()),
+                    nearest_distance: nearest.map(|(_, d)| *d),
+                    x: address.x(),
+                    y: address.y(),
+                    lat: None,
+                    lon: None,
+                }
+            })
+            .collect::<Vec<AccessRecord>>();
+        let records = Self(records);
+        for status in [
+            AccessStatus::Associated,
+            AccessStatus::OtherStreet,
+            AccessStatus::Missing,
+        ] {
+            info!("{status}: {} addresses.", records.count(status));
+        }
+        records
+    }
+
+    /// The `count` method returns the number of records with the status `status`.
+    pub fn count(&self, status: AccessStatus) -> usize {
+        self.iter().filter(|r| r.status == status).count()
+    }
+
+    /// The `filter` method returns the subset of records with the status named by `filter`,
+    /// either "associated", "other_street" or "missing".
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "associated" => self.retain(|r| r.status == AccessStatus::Associated),
+            "other_street" => self.retain(|r| r.status == AccessStatus::OtherStreet),
+            "missing" => self.retain(|r| r.status == AccessStatus::Missing),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+impl IntoCsv<AccessRecords> for AccessRecords {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for AccessRecords {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommonAddress, SpatialAddress, StreetNamePostType};
+
+    #[test]
+    fn access_points() -> anyhow::Result<()> {
+        let point = |number: i64, street_name: &str, x: f64, y: f64| SpatialAddress {
+            address: CommonAddress {
+                number,
+                street_name: street_name.to_string(),
+                street_type: Some(StreetNamePostType::AVENUE),
+                ..Default::default()
+            },
+            x,
+            y,
+            ..Default::default()
+        };
+        let addresses = [
+            point(101, "RIVERSIDE", 0.0, 0.0),
+            point(105, "RIVERSIDE", 100.0, 0.0),
+            point(200, "PARK", 1000.0, 1000.0),
+        ];
+        let access = |id: &str, street: &str, x: f64, y: f64| AccessPoint {
+            id: Some(id.to_string()),
+            street: street.to_string(),
+            x,
+            y,
+        };
+        let points = [
+            access("1", "Riverside Ave", 0.0, 30.0),
+            // The driveway of 105 opens onto Park Avenue, with Riverside further away.
+            access("2", "PARK AVENUE", 100.0, 20.0),
+            access("3", "RIVERSIDE AVENUE", 100.0, 60.0),
+        ];
+        let records = AccessRecords::associate(&addresses, &points, ACCESS_DISTANCE);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].status, AccessStatus::Associated);
+        assert_eq!(records[0].access_id.as_deref(), Some("1"));
+        assert_eq!(records[0].access_distance, Some(30.0));
+        assert_eq!(records[1].status, AccessStatus::OtherStreet);
+        assert_eq!(records[1].nearest_id.as_deref(), Some("2"));
+        assert_eq!(records[1].nearest_street.as_deref(), Some("PARK AVENUE"));
+        assert_eq!(records[1].access_id.as_deref(), Some("3"));
+        assert_eq!(records[2].status, AccessStatus::Missing);
+        assert!(records[2].nearest_id.is_none());
+
+        assert_eq!(records.count(AccessStatus::OtherStreet), 1);
+        let reported = records.filter("other_street");
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].label, "105 RIVERSIDE AVE");
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+//! The `activity` module summarizes the editing of an address dataset from the editor tracking
+//! fields ArcGIS keeps on each feature, so the GIS coordinator can report addressing workload
+//! without opening ArcGIS.  Only the creation and the latest edit of a feature are recorded, so
+//! earlier edits to a feature edited again are not counted.
+use crate::{
+    AddressErrorKind, GrantsPassSpatialAddress, IntoCsv, IntoJson, Io, from_csv,
+    retired::parse_timestamp, to_csv, to_json, to_ndjson,
+};
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// The `EditorActivity` struct holds the number of features a user created or last edited during
+/// a week.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EditorActivity {
+    /// The `week` field holds the date of the Monday beginning the week.
+    pub week: NaiveDate,
+    /// The `user` field holds the user ID of the editor, in uppercase.
+    pub user: String,
+    /// The `created` field holds the number of features the user created during the week.
+    pub created: usize,
+    /// The `edited` field holds the number of features the user last edited during the week,
+    /// not counting edits made when creating the feature.
+    pub edited: usize,
+}
+
+// Returns the date of the Monday beginning the week of `date`.
+fn week_of(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday().into())
+}
+
+/// The `EditorActivities` struct holds a vector of type [`EditorActivity`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct EditorActivities(Vec<EditorActivity>);
+
+impl EditorActivities {
+    /// The `from_grants_pass` method counts the features in `addresses` created and last edited by
+    /// each user during each week, from the `created_user`, `created_date`, `last_edited_user`
+    /// and `last_edited_date` fields.  User IDs are compared ignoring case.  An edit stamped with
+    /// the same user and time as the creation of the feature is not counted as an edit.  Only
+    /// dates from `since` through `until`, inclusive, are counted.  Dates that cannot be read are
+    /// skipped with a warning.  Records are sorted by week, then by user.
+    pub fn from_grants_pass(
+        addresses: &[GrantsPassSpatialAddress],
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> Self {
+        let in_range = |time: &NaiveDateTime| {
+            since.is_none_or(|since| time.date() >= since)
+                && until.is_none_or(|until| time.date() <= until)
+        };
+        let mut counts: BTreeMap<(NaiveDate, String), (usize, usize)> = BTreeMap::new();
+        let mut unreadable = 0;
+        for address in addresses {
+            let created = match (&address.created_user, &address.created_date) {
+                (Some(user), Some(date)) if !user.trim().is_empty() => {
+                    match parse_timestamp(date) {
+                        Some(time) => Some((user.trim().to_uppercase(), time)),
+                        None => {
+                            unreadable += 1;
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+            if let Some((user, time)) = &created
+                && in_range(time)
+            {
+                counts
+                    .entry((week_of(time.date()), user.clone()))
+                    .or_default()
+                    .0 += 1;
+            }
+            let user = address.last_edited_user.trim().to_uppercase();
+            if user.is_empty() {
+                continue;
+            }
+            let Some(time) = parse_timestamp(&address.last_edited_date) else {
+                unreadable += 1;
+                continue;
+            };
+            if created.as_ref() != Some(&(user.clone(), time)) && in_range(&time) {
+                counts.entry((week_of(time.date()), user)).or_default().1 += 1;
+            }
+        }
+        if unreadable > 0 {
+            warn!("{unreadable} unreadable edit dates skipped.");
+        }
+        let records = counts
+            .into_iter()
+            .map(|((week, user), (created, edited))| EditorActivity {
+                week,
+                user,
+                created,
+                edited,
+            })
+            .collect::<Vec<EditorActivity>>();
+        info!("{} weeks of editor activity found.", records.len());
+        Self(records)
+    }
+
+    /// The `filter` method returns the subset of records for the user named by `filter`,
+    /// ignoring case.
+    pub fn filter(mut self, filter: &str) -> Self {
+        let user = filter.trim().to_uppercase();
+        self.retain(|r| r.user == user);
+        if self.is_empty() {
+            info!("No activity found for user {filter}.");
+        }
+        self
+    }
+}
+
+impl IntoCsv<EditorActivities> for EditorActivities {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for EditorActivities {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GrantsPassSpatialAddresses;
+
+    #[test]
+    fn editor_activity() -> anyhow::Result<()> {
+        let addresses = GrantsPassSpatialAddresses::from_csv("data/city_addresses_20241007.csv")?;
+        let mut sample = addresses[..4].to_vec();
+        // Created and never edited again, on Wednesday, October 2, 2024.
+        sample[0].created_user = Some("gvincent".to_string());
+        sample[0].created_date = Some("10/2/2024 9:00:00".to_string());
+        sample[0].last_edited_user = "GVINCENT".to_string();
+        sample[0].last_edited_date = "10/2/2024 9:00:00".to_string();
+        // Created, then edited by another user the following Monday.
+        sample[1].created_user = Some("GVINCENT".to_string());
+        sample[1].created_date = Some("10/6/2024 12:00:00".to_string());
+        sample[1].last_edited_user = "EROSE".to_string();
+        sample[1].last_edited_date = "10/7/2024 8:30:00".to_string();
+        sample[2].created_user = None;
+        sample[2].created_date = None;
+        sample[2].last_edited_user = "EROSE".to_string();
+        sample[2].last_edited_date = "10/9/2024".to_string();
+        sample[3].created_user = None;
+        sample[3].created_date = None;
+        sample[3].last_edited_user = "GPGIS".to_string();
+        sample[3].last_edited_date = "6/27/2023 17:38:41".to_string();
+        let since = chrono::NaiveDate::from_ymd_opt(2024, 10, 1);
+        let activity = EditorActivities::from_grants_pass(&sample, since, None);
+        let rows = activity
+            .iter()
+            .map(|r| (r.week.to_string(), r.user.as_str(), r.created, r.edited))
+            .collect::<Vec<(String, &str, usize, usize)>>();
+        assert_eq!(
+            rows,
+            vec![
+                ("2024-09-30".to_string(), "GVINCENT", 2, 0),
+                ("2024-10-07".to_string(), "EROSE", 0, 2),
+            ]
+        );
+        assert_eq!(activity.clone().filter("erose").len(), 1);
+        let until = chrono::NaiveDate::from_ymd_opt(2024, 10, 7);
+        let activity = EditorActivities::from_grants_pass(&sample, since, until);
+        assert_eq!(activity[1].edited, 1);
+        Ok(())
+    }
+}
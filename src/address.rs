@@ -2,13 +2,13 @@
 //! implementation blocks to convert data from import types to the valid address format.
 use crate::{
     AddressError, AddressErrorKind, AddressMatch, AddressStatus, Bincode, Builder, Cartesian,
-    FireInspections, Geographic, IntoBin, IntoCsv, Io, LexisNexis, Mismatch, Parse,
-    PostalCommunity, State, StreetNamePostType, StreetNamePreDirectional, StreetNamePreModifier,
-    StreetNamePreType, StreetSeparator, SubaddressType, from_bin, from_csv, to_bin, to_csv,
+    FireInspections, Geographic, Geolocate, IntoBin, IntoCsv, IntoGeoJson, IntoJson, Io,
+    LexisNexis, MatchConfig, Mismatch, Nom, Parse, PostalCommunity, PrefixIndex, Projection,
+    Standardizer, State, StreetNamePostType, StreetNamePreDirectional, StreetNamePreModifier,
+    StreetNamePreType, StreetSeparator, SubaddressType, Units, WGS84, from_bin, from_csv,
+    progress_bar, to_bin, to_csv, to_geojson, to_json, to_ndjson,
 };
 use derive_more::{Deref, DerefMut};
-use indicatif::ProgressBar;
-use nom::bytes::complete::tag;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::ops;
@@ -96,42 +96,62 @@ pub trait Address {
     /// The results are converted to type [`AddressMatch`].
     #[tracing::instrument(skip_all)]
     fn coincident<T: Address>(&self, other: &T) -> AddressMatch {
+        self.coincident_fuzzy(other, 1.0)
+    }
+
+    /// The `coincident_fuzzy` method works like [`Address::coincident`], but tolerates minor
+    /// differences in the street name (e.g. "RIVERSIDE" and "RIVER SIDE").  Street names match if
+    /// their Jaro-Winkler similarity is greater than or equal to `threshold`, a value between 0
+    /// and 1.  A `threshold` of 1.0 requires an exact match.  When the street names are similar
+    /// but not equal, the difference is recorded as a [`Mismatch::StreetName`] and the similarity
+    /// score is reported in the `similarity` field of the [`AddressMatch`].
+    #[tracing::instrument(skip_all)]
+    fn coincident_fuzzy<T: Address>(&self, other: &T, threshold: f64) -> AddressMatch {
+        self.coincident_with(other, threshold, &MatchConfig::default())
+    }
+
+    /// The `coincident_with` method works like [`Address::coincident_fuzzy`], but `config`
+    /// selects which of the zip code, postal community, subaddress identifier and address status
+    /// must agree.  Differences in excluded fields are recorded as a [`Mismatch`] rather than
+    /// preventing the match.
+    #[tracing::instrument(skip_all)]
+    fn coincident_with<T: Address>(
+        &self,
+        other: &T,
+        threshold: f64,
+        config: &MatchConfig,
+    ) -> AddressMatch {
         let mut coincident = false;
+        let mut similarity = None;
         let mut mismatches = Vec::new();
         if self.number() == other.number()
-            && self.number_suffix() == other.number_suffix()
+            && config.optional_agrees(self.number_suffix(), other.number_suffix())
             && self.directional() == other.directional()
             && self.street_name_pre_modifier() == other.street_name_pre_modifier()
             && self.street_name_pre_type() == other.street_name_pre_type()
             && self.street_name_separator() == other.street_name_separator()
-            && self.street_name() == other.street_name()
             && self.street_type() == other.street_type()
-            && self.subaddress_id() == other.subaddress_id()
-            && self.zip() == other.zip()
-            && self.postal_community() == other.postal_community()
+            && (!config.subaddress
+                || config.optional_agrees(self.subaddress_id(), other.subaddress_id()))
+            && (!config.zip || self.zip() == other.zip())
+            && (!config.postal_community
+                || config.text_agrees(self.postal_community(), other.postal_community()))
+            && (!config.status || self.status() == other.status())
             && self.state() == other.state()
+            && let Some(score) =
+                config.street_name_similarity(self.street_name(), other.street_name(), threshold)
         {
             coincident = true;
-            if self.subaddress_type() != other.subaddress_type() {
-                mismatches.push(Mismatch::subaddress_type(
-                    *self.subaddress_type(),
-                    *other.subaddress_type(),
+            if score < 1.0 {
+                similarity = Some(score);
+                mismatches.push(Mismatch::street_name(
+                    self.street_name().clone(),
+                    other.street_name().clone(),
                 ));
             }
-            if self.floor() != other.floor() {
-                mismatches.push(Mismatch::floor(*self.floor(), *other.floor()));
-            }
-            if self.building() != other.building() {
-                mismatches.push(Mismatch::building(
-                    self.building().clone(),
-                    other.building().clone(),
-                ));
-            }
-            if self.status() != other.status() {
-                mismatches.push(Mismatch::status(*self.status(), *other.status()));
-            }
+            mismatches.extend(descriptive_mismatches(self, other, config));
         }
-        AddressMatch::new(coincident, mismatches)
+        AddressMatch::new(coincident, mismatches).with_similarity(similarity)
     }
 
     /// Returns a String representing the address label, consisting of the complete address number,
@@ -269,125 +289,89 @@ pub trait Address {
     }
 
     /// The `standardize` method takes county address naming conventions and converts them to city
-    /// naming conventions.
+    /// naming conventions, using the rules of [`Standardizer::josephine_county`].
     #[tracing::instrument(skip_all)]
-    fn standardize(&mut self) {
-        let comp = self.street_name().clone();
-        if comp == "AZALEA DRIVE" {
-            trace!("Fixing Azalea Drive Cutoff");
-            *self.street_name_mut() = "AZALEA".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::DriveCutoff);
-        }
+    fn standardize(&mut self)
+    where
+        Self: Sized,
+    {
+        Standardizer::josephine_county().apply(self);
+    }
+}
 
-        if let Some(sub) = self.subaddress_id() {
-            if comp == "LEWIS" && sub == "OFFICE" {
-                info!("Fixing Lewis Ave Office");
-                *self.subaddress_id_mut() = None;
-                *self.subaddress_type_mut() = Some(SubaddressType::Office);
-            }
-        }
-        if comp == "BEAVILLA VIEW" {
-            trace!("Fixing Beavilla View");
-            *self.street_name_mut() = "BEAVILLA".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::VIEW);
-        }
-        if comp == "COLUMBIA CREST" {
-            trace!("Fixing Columbia Crest");
-            *self.street_name_mut() = "COLUMBIA".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::CREST);
-        }
-        if comp == "HILLTOP VIEW" {
-            trace!("Fixing Hilltop View");
-            *self.street_name_mut() = "HILLTOP".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::VIEW);
-        }
-        if comp == "TENNESSEE VIEW" {
-            trace!("Fixing Tennessee View");
-            *self.street_name_mut() = "TENNESSEE".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::VIEW);
-        }
-        if comp == "MARILEE ROW" {
-            trace!("Fixing Marilee Row");
-            *self.street_name_mut() = "MARILEE".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::ROW);
-        }
-        if comp == "MEADOW GLEN" {
-            trace!("Fixing Meadow Glen");
-            *self.street_name_mut() = "MEADOW".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::GLEN);
-        }
-        if comp == "GENVERNA GLEN" {
-            trace!("Fixing Genverna Glen");
-            *self.street_name_mut() = "GENVERNA".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::GLEN);
-        }
-        if comp == "ROBERTSON CREST" {
-            trace!("Fixing Robertson Crest");
-            *self.street_name_mut() = "ROBERTSON".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::CREST);
-        }
-        if comp == "QUAIL CROSSING" {
-            trace!("Fixing Quail Crossing");
-            *self.street_name_mut() = "QUAIL".to_string();
-            *self.street_type_mut() = Some(StreetNamePostType::CROSSING);
-        }
-        if comp == "SIDE ROAD" && *self.directional() == Some(StreetNamePreDirectional::WEST) {
-            trace!("Fixing West Side Road");
-            *self.directional_mut() = None;
-            *self.street_name_mut() = "WEST SIDE".to_string();
-        }
-        if comp == "SOUTH SHORE DRIVE"
-            && *self.directional() == Some(StreetNamePreDirectional::SOUTH)
-        {
-            trace!("Fixing South Shore Drive");
-            *self.directional_mut() = None;
-            *self.street_name_mut() = "SOUTH SHORE".to_string();
-        }
+// Returns the differences between the descriptive fields of `a` and `b`, which do not prevent
+// the addresses from referring to the same location, along with the fields excluded from the
+// match by `config`.
+pub(crate) fn descriptive_mismatches<T: Address + ?Sized, U: Address>(
+    a: &T,
+    b: &U,
+    config: &MatchConfig,
+) -> Vec<Mismatch> {
+    let mut mismatches = Vec::new();
+    if a.subaddress_type() != b.subaddress_type() {
+        mismatches.push(Mismatch::subaddress_type(
+            *a.subaddress_type(),
+            *b.subaddress_type(),
+        ));
+    }
+    if a.floor() != b.floor() {
+        mismatches.push(Mismatch::floor(*a.floor(), *b.floor()));
+    }
+    if !config.optional_agrees(a.building(), b.building()) {
+        mismatches.push(Mismatch::building(
+            a.building().clone(),
+            b.building().clone(),
+        ));
+    }
+    if !config.status && a.status() != b.status() {
+        mismatches.push(Mismatch::status(*a.status(), *b.status()));
+    }
+    if !config.zip && a.zip() != b.zip() {
+        mismatches.push(Mismatch::zip(a.zip(), b.zip()));
+    }
+    if !config.postal_community && !config.text_agrees(a.postal_community(), b.postal_community()) {
+        mismatches.push(Mismatch::postal_community(
+            a.postal_community().clone(),
+            b.postal_community().clone(),
+        ));
+    }
+    if !config.subaddress && !config.optional_agrees(a.subaddress_id(), b.subaddress_id()) {
+        mismatches.push(Mismatch::subaddress_id(
+            a.subaddress_id().clone(),
+            b.subaddress_id().clone(),
+        ));
+    }
+    mismatches
+}
 
-        if let Some(comp) = self.subaddress_id().clone() {
-            trace!("Fixing Laundry");
-            if comp == "LAUNDRY" {
-                *self.subaddress_id_mut() = None;
-                *self.subaddress_type_mut() = Some(SubaddressType::Laundry);
-            }
-            trace!("Fixing Office");
-            if comp == "OFFICE" {
-                *self.subaddress_id_mut() = None;
-                *self.subaddress_type_mut() = Some(SubaddressType::Office);
-            }
-            trace!("Fixing Rec");
-            if comp == "REC" {
-                *self.subaddress_id_mut() = None;
-                *self.subaddress_type_mut() = Some(SubaddressType::Rec);
-            }
-            trace!("Fixing Trailer");
-            if comp == "TRLR" {
-                *self.subaddress_id_mut() = None;
-                *self.subaddress_type_mut() = Some(SubaddressType::Trailer);
-            }
-            trace!("Fixing Floor 4");
-            if comp == "FLOOR 4" {
-                *self.subaddress_id_mut() = Some("4".to_string());
-                *self.subaddress_type_mut() = Some(SubaddressType::Floor);
-            }
-            if comp.contains("APT") {
-                let (_, id) =
-                    tag::<&str, &str, nom::error::Error<_>>("APT")(comp.as_str()).unwrap();
-                *self.subaddress_id_mut() = Some(id.to_string());
-            }
-            if comp.contains("RV") {
-                let (_, id) = tag::<&str, &str, nom::error::Error<_>>("RV")(comp.as_str()).unwrap();
-                *self.subaddress_id_mut() = Some(id.to_string());
-            }
-            if comp.contains("CABIN") {
-                let (_, id) =
-                    tag::<&str, &str, nom::error::Error<_>>("CABIN")(comp.as_str()).unwrap();
-                *self.subaddress_id_mut() = Some(id.to_string());
-            }
+/// The `street_name_similarity` function returns the Jaro-Winkler similarity between street names
+/// `a` and `b` if it meets or exceeds `threshold`, otherwise returns `None`.  Equal names return
+/// 1.0 without computing the similarity, so exact matching (a `threshold` of 1.0) stays cheap.
+pub fn street_name_similarity(a: &str, b: &str, threshold: f64) -> Option<f64> {
+    if a == b {
+        Some(1.0)
+    } else if threshold < 1.0 {
+        let score = strsim::jaro_winkler(&a.to_uppercase(), &b.to_uppercase());
+        if score >= threshold {
+            Some(score)
+        } else {
+            None
         }
+    } else {
+        None
     }
 }
 
+/// The `normalize_text` function trims `value`, collapses runs of internal whitespace to a single
+/// space and converts the result to uppercase, so "Rogue  River hwy " reads "ROGUE RIVER HWY".
+pub fn normalize_text(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_uppercase()
+}
+
 /// The `Addresses` trait enables methods that act on vectors of type [`Address`].
 pub trait Addresses<T: Address + Clone + Send + Sync>
 where
@@ -401,13 +385,8 @@ where
         // let values = self.values();
         match filter {
             "duplicate" => {
-                let style = indicatif::ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {'Checking for duplicate addresses.'}",
-        )
-        .unwrap();
                 let mut seen = HashSet::new();
-                let bar = ProgressBar::new(self.len() as u64);
-                bar.set_style(style);
+                let bar = progress_bar(self.len(), "Checking for duplicate addresses.");
                 for address in self.iter() {
                     let label = address.label();
                     if !seen.contains(&label) {
@@ -543,6 +522,18 @@ where
         }
     }
 
+    /// The `suggest` method returns up to `limit` addresses whose complete street address begins
+    /// with `prefix`, ranked as by [`PrefixIndex::complete`].  The method builds the index on
+    /// each call, so callers completing many prefixes against the same addresses should build a
+    /// [`PrefixIndex`] once instead.
+    fn suggest(&self, prefix: &str, limit: usize) -> Vec<T> {
+        PrefixIndex::from_addresses(self)
+            .complete(prefix, limit)
+            .into_iter()
+            .map(|i| self[i].clone())
+            .collect()
+    }
+
     /// The `LexisNexis` method produces the LexisNexis table showing dispatch jurisdiction for
     /// address ranges within the City of Grants Pass.
     #[tracing::instrument(skip_all)]
@@ -551,11 +542,10 @@ where
     }
 
     /// The `standardize` method takes county address naming conventions and converts them to city
-    /// naming conventions.
+    /// naming conventions, using the rules of [`Standardizer::josephine_county`].
     #[tracing::instrument(skip_all)]
     fn standardize(&mut self) {
-        trace!("Running standardize");
-        self.iter_mut().map(|v| v.standardize()).for_each(drop);
+        Standardizer::josephine_county().standardize(self);
     }
 }
 
@@ -788,6 +778,83 @@ impl<T: Address> From<&T> for CommonAddress {
     }
 }
 
+impl CommonAddress {
+    /// The `parse` method reads a one-line address such as "1450 NE 7th St Apt B, Grants Pass,
+    /// OR 97526" into a `CommonAddress`, using [`Parse::address`] to tokenize the input.  The
+    /// address number and street name are required.  Missing zip codes read as zero, missing
+    /// postal communities as an empty string, and the status reads as the default status.
+    ///
+    /// ```
+    /// use destination::{Address, CommonAddress, StreetNamePostType, StreetNamePreDirectional};
+    ///
+    /// let address = CommonAddress::parse("1450 NE 7th St Apt B, Grants Pass, OR 97526").unwrap();
+    /// assert_eq!(address.number, 1450);
+    /// assert_eq!(address.directional, Some(StreetNamePreDirectional::NORTHEAST));
+    /// assert_eq!(address.street_type, Some(StreetNamePostType::STREET));
+    /// assert_eq!(address.postal_community, "GRANTS PASS");
+    /// assert_eq!(address.label(), "1450 NE 7TH ST APT B");
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, AddressErrorKind> {
+        let (rem, partial) = Parse::address(input)
+            .map_err(|source| Nom::new(input.to_string(), source, line!(), file!().into()))?;
+        if !rem.trim().is_empty() {
+            tracing::warn!("Unparsed input remaining: {rem}");
+        }
+        match Self::try_from(&partial) {
+            Ok(address) => Ok(address),
+            Err(source) => {
+                error!("{source}");
+                Err(source.into())
+            }
+        }
+    }
+}
+
+impl TryFrom<&PartialAddress> for CommonAddress {
+    type Error = Builder;
+
+    fn try_from(partial: &PartialAddress) -> Result<Self, Self::Error> {
+        let target = "CommonAddress".to_string();
+        let Some(number) = partial.address_number else {
+            return Err(Builder::new(
+                "address_number field is None".to_string(),
+                target,
+                line!(),
+                file!().to_string(),
+            ));
+        };
+        let Some(street_name) = partial.street_name.clone() else {
+            return Err(Builder::new(
+                "street_name field is None".to_string(),
+                target,
+                line!(),
+                file!().to_string(),
+            ));
+        };
+        Ok(Self {
+            number,
+            number_suffix: partial.address_number_suffix.clone(),
+            directional: partial.street_name_pre_directional,
+            pre_modifier: partial.pre_modifier,
+            pre_type: partial.pre_type,
+            separator: partial.separator,
+            street_name,
+            street_type: partial.street_name_post_type,
+            subaddress_type: partial.subaddress_type,
+            subaddress_id: partial.subaddress_identifier.clone(),
+            floor: partial.floor,
+            building: partial.building.clone(),
+            zip: partial.zip_code.unwrap_or_default(),
+            postal_community: partial
+                .postal_community
+                .map(|community| community.label())
+                .unwrap_or_default(),
+            state: partial.state_name.unwrap_or_default(),
+            status: partial.status.unwrap_or_default(),
+        })
+    }
+}
+
 /// The `CommonAddresses` struct holds a vector of type [`CommonAddress`].
 #[derive(
     Debug,
@@ -828,7 +895,7 @@ impl IntoBin<CommonAddresses> for CommonAddresses {
     }
 
     fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -843,6 +910,16 @@ impl IntoCsv<CommonAddresses> for CommonAddresses {
     }
 }
 
+impl IntoJson for CommonAddresses {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
 /// The `PartialAddress` struct contains optional fields so that incomplete or missing data can be
 /// compared against [`Addresses`] or [`PartialAddresses`] for potential matches.  Used to help
 /// match address information that does not parse into a full valid address.
@@ -1287,7 +1364,7 @@ impl IntoBin<PartialAddresses> for PartialAddresses {
     }
 
     fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -1315,6 +1392,16 @@ pub struct AddressDelta {
     pub latitude: f64,
     /// Reference longitude from the subject address.
     pub longitude: f64,
+    /// Direction of the displacement from the reference point to the subject address, in degrees
+    /// clockwise from grid north, if known.
+    #[serde(default)]
+    pub bearing: Option<f64>,
+    /// WGS84 latitude of the subject address, if computed.
+    #[serde(default)]
+    pub lat: Option<f64>,
+    /// WGS84 longitude of the subject address, if computed.
+    #[serde(default)]
+    pub lon: Option<f64>,
 }
 
 impl AddressDelta {
@@ -1326,8 +1413,20 @@ impl AddressDelta {
             delta,
             latitude: address.y(),
             longitude: address.x(),
+            bearing: None,
+            lat: None,
+            lon: None,
         }
     }
+
+    /// The `between` method measures the displacement of `address` from `reference`, recording
+    /// both the distance and the bearing between the points.
+    pub fn between<T: Cartesian, U: Address + Cartesian>(reference: &T, address: &U) -> Self {
+        let (dx, dy) = (address.x() - reference.x(), address.y() - reference.y());
+        let mut delta = Self::new(address, dx.hypot(dy));
+        delta.bearing = Some(dx.atan2(dy).to_degrees().rem_euclid(360.0));
+        delta
+    }
 }
 
 impl Geographic for AddressDelta {
@@ -1340,6 +1439,17 @@ impl Geographic for AddressDelta {
     }
 }
 
+impl Geolocate for AddressDelta {
+    fn projected(&self) -> Option<(f64, f64)> {
+        Some((self.longitude, self.latitude))
+    }
+
+    fn set_geographic(&mut self, longitude: f64, latitude: f64) {
+        self.lat = Some(latitude);
+        self.lon = Some(longitude);
+    }
+}
+
 // This is a hack to make the distance calculations work until I can migrate to the geo library.
 impl Cartesian for AddressDelta {
     fn y(&self) -> f64 {
@@ -1361,6 +1471,87 @@ impl AddressDeltas {
     pub fn new(records: Vec<AddressDelta>) -> Self {
         Self(records)
     }
+
+    /// The `scale` method multiplies each distance by `factor`, converting the distances to
+    /// different units.
+    pub fn scale(&mut self, factor: f64) {
+        self.iter_mut().for_each(|d| d.delta *= factor);
+    }
+
+    /// The `offenders` method returns up to `count` deltas with the greatest distances, greatest
+    /// first.
+    pub fn offenders(&self, count: usize) -> Vec<AddressDelta> {
+        let mut deltas = self.to_vec();
+        deltas.sort_by(|a, b| b.delta.total_cmp(&a.delta));
+        deltas.truncate(count);
+        deltas
+    }
+
+    /// The `summary` method returns summary statistics for the distances, in the units `units`,
+    /// counting the distances greater than `threshold`.
+    pub fn summary(&self, threshold: f64, units: Units) -> DriftSummary {
+        let mut deltas = self.iter().map(|d| d.delta).collect::<Vec<f64>>();
+        deltas.sort_by(|a, b| a.total_cmp(b));
+        let count = deltas.len();
+        let mut summary = DriftSummary {
+            units,
+            count,
+            threshold,
+            over_threshold: deltas.iter().filter(|d| **d > threshold).count(),
+            ..Default::default()
+        };
+        if count > 0 {
+            summary.mean = deltas.iter().sum::<f64>() / count as f64;
+            summary.median = match count % 2 {
+                0 => (deltas[count / 2 - 1] + deltas[count / 2]) / 2.0,
+                _ => deltas[count / 2],
+            };
+            summary.p95 = percentile(&deltas, 0.95);
+            summary.max = deltas[count - 1];
+        }
+        summary
+    }
+}
+
+// Returns the nearest-rank percentile `p`, between 0 and 1, of the non-empty values `sorted`,
+// sorted in ascending order.
+pub(crate) fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// The `DriftSummary` struct holds summary statistics for the distances between matching
+/// addresses, produced by [`AddressDeltas::summary`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DriftSummary {
+    /// The `units` field holds the units of the distances.
+    pub units: Units,
+    /// The `count` field holds the number of matching address pairs.
+    pub count: usize,
+    /// The `mean` field holds the mean distance.
+    pub mean: f64,
+    /// The `median` field holds the median distance.
+    pub median: f64,
+    /// The `p95` field holds the 95th percentile of distances.
+    pub p95: f64,
+    /// The `max` field holds the maximum distance.
+    pub max: f64,
+    /// The `threshold` field holds the distance above which drift is reported.
+    pub threshold: f64,
+    /// The `over_threshold` field holds the number of distances greater than `threshold`.
+    pub over_threshold: usize,
+}
+
+impl std::fmt::Display for DriftSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Drift summary ({}):", self.units)?;
+        writeln!(f, "  matching addresses: {}", self.count)?;
+        writeln!(f, "  mean: {:.2}", self.mean)?;
+        writeln!(f, "  median: {:.2}", self.median)?;
+        writeln!(f, "  95th percentile: {:.2}", self.p95)?;
+        writeln!(f, "  maximum: {:.2}", self.max)?;
+        write!(f, "  over {:.2}: {}", self.threshold, self.over_threshold)
+    }
 }
 
 impl IntoBin<AddressDeltas> for AddressDeltas {
@@ -1373,7 +1564,7 @@ impl IntoBin<AddressDeltas> for AddressDeltas {
     }
 
     fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -1387,3 +1578,168 @@ impl IntoCsv<AddressDeltas> for AddressDeltas {
         to_csv(&mut self.0, path.as_ref().into())
     }
 }
+
+impl IntoJson for AddressDeltas {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+// The `latitude` and `longitude` fields of a delta hold the projected coordinates of the subject
+// address, so the features are placed at the WGS84 coordinates in `lat` and `lon`, filled in by
+// `Geolocate::geolocate`.  Errors if a delta has not been geolocated.
+impl IntoGeoJson for AddressDeltas {
+    fn to_geojson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        let mut deltas = Vec::with_capacity(self.len());
+        for delta in self.iter() {
+            let (Some(latitude), Some(longitude)) = (delta.lat, delta.lon) else {
+                return Err(Projection::new(
+                    WGS84,
+                    format!(
+                        "no WGS84 coordinates for {}, set the EPSG code of the source",
+                        delta.label
+                    ),
+                    line!(),
+                    file!().into(),
+                )
+                .into());
+            };
+            deltas.push(AddressDelta {
+                latitude,
+                longitude,
+                ..delta.clone()
+            });
+        }
+        to_geojson(&deltas, path)
+    }
+}
+
+// Returns an address numbered `number` on RIVERSIDE AVE, the street used by test fixtures.
+#[cfg(test)]
+pub(crate) fn riverside(number: i64) -> CommonAddress {
+    CommonAddress {
+        number,
+        street_name: "RIVERSIDE".to_string(),
+        street_type: Some(StreetNamePostType::AVENUE),
+        ..Default::default()
+    }
+}
+
+// Returns an address point numbered `number` on RIVERSIDE AVE, at projected coordinates `x`
+// and `y`.
+#[cfg(test)]
+pub(crate) fn riverside_point(number: i64, x: f64, y: f64) -> crate::SpatialAddress {
+    crate::SpatialAddress {
+        address: riverside(number),
+        x,
+        y,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SpatialAddresses, from_geographic, geolocate};
+
+    #[test]
+    fn deltas_to_geojson_in_wgs84() -> anyhow::Result<()> {
+        // Grants Pass City Hall, projected to Oregon South in feet.
+        let (x, y) = from_geographic(2270, -123.3284, 42.4390)?;
+        let delta = AddressDelta {
+            label: "101 NW A ST".to_string(),
+            delta: 12.0,
+            latitude: y,
+            longitude: x,
+            bearing: None,
+            lat: None,
+            lon: None,
+        };
+        let mut deltas = AddressDeltas::new(vec![delta]);
+        let path = std::env::temp_dir().join("destination_address_deltas.geojson");
+        // Without geographic coordinates, the projected values are not written.
+        assert!(deltas.to_geojson(&path).is_err());
+
+        geolocate(&mut deltas, 2270)?;
+        deltas.to_geojson(&path)?;
+        let layer: serde_json::Value = serde_json::from_reader(std::fs::File::open(&path)?)?;
+        let feature = &layer["features"][0];
+        let longitude = feature["geometry"]["coordinates"][0].as_f64().unwrap();
+        let latitude = feature["geometry"]["coordinates"][1].as_f64().unwrap();
+        assert!((-124.0..-123.0).contains(&longitude));
+        assert!((42.0..43.0).contains(&latitude));
+        assert_eq!(feature["properties"]["longitude"].as_f64(), Some(longitude));
+        assert_eq!(feature["properties"]["latitude"].as_f64(), Some(latitude));
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn drift_summary() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let records = (1..=20)
+            .map(|i| AddressDelta::new(&city_addresses[i], i as f64 * 10.0))
+            .collect::<Vec<AddressDelta>>();
+        let mut deltas = AddressDeltas::new(records);
+        let summary = deltas.summary(99.0, Units::Meters);
+        assert_eq!(summary.count, 20);
+        assert_eq!(summary.mean, 105.0);
+        assert_eq!(summary.median, 105.0);
+        assert_eq!(summary.p95, 190.0);
+        assert_eq!(summary.max, 200.0);
+        assert_eq!(summary.over_threshold, 11);
+        assert!(summary.to_string().starts_with("Drift summary (meters):"));
+
+        deltas.scale(Units::Meters.meters() / Units::Feet.meters());
+        let summary = deltas.summary(99.0, Units::Feet);
+        assert!((summary.max - 656.168).abs() < 1e-3);
+        assert_eq!(summary.over_threshold, 17);
+        let path = std::env::temp_dir().join("destination_drift.json");
+        to_json(&summary, &path)?;
+        let read: DriftSummary = serde_json::from_reader(std::fs::File::open(&path)?)?;
+        assert_eq!(read, summary);
+        std::fs::remove_file(&path)?;
+        assert_eq!(AddressDeltas::default().summary(99.0, Units::Feet).count, 0);
+
+        let offenders = deltas.offenders(3);
+        assert_eq!(offenders.len(), 3);
+        assert!(offenders[0].delta > offenders[1].delta);
+        assert_eq!(offenders[0].label, deltas[19].label);
+        Ok(())
+    }
+
+    #[test]
+    fn fuzzy_street_names() -> anyhow::Result<()> {
+        let subject = riverside(1450);
+        let mut other = subject.clone();
+        other.street_name = "RIVER SIDE".to_string();
+        assert!(!subject.coincident(&other).coincident);
+        let fuzzy = subject.coincident_fuzzy(&other, 0.9);
+        assert!(fuzzy.coincident);
+        assert!(fuzzy.similarity.unwrap() >= 0.9);
+        assert!(!subject.coincident_fuzzy(&other, 0.99).coincident);
+        let exact = subject.coincident_fuzzy(&subject, 0.9);
+        assert!(exact.coincident);
+        assert_eq!(exact.similarity, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_common_address() -> anyhow::Result<()> {
+        let address = CommonAddress::parse("1450 NE 7th St Apt B, Grants Pass, OR 97526")?;
+        assert_eq!(address.number, 1450);
+        assert_eq!(address.street_name, "7TH");
+        assert_eq!(address.subaddress_type, Some(SubaddressType::Apartment));
+        assert_eq!(address.subaddress_id, Some("B".to_string()));
+        assert_eq!(address.zip, 97526);
+        let address = CommonAddress::parse("801 SE J St")?;
+        assert_eq!(address.zip, 0);
+        assert_eq!(address.label(), "801 SE J ST");
+        assert!(CommonAddress::parse("Grants Pass, OR 97526").is_err());
+        Ok(())
+    }
+}
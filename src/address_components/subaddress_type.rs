@@ -23,34 +23,60 @@ use std::str::FromStr;
     strum::EnumIter,
 )]
 pub enum SubaddressType {
+    #[serde(alias = "APARTMENT")]
     Apartment,
+    #[serde(alias = "BASEMENT")]
     Basement,
+    #[serde(alias = "BUILDING")]
     Building,
+    #[serde(alias = "DEPARTMENT")]
     Department,
+    #[serde(alias = "FLOOR")]
     Floor,
+    #[serde(alias = "FRONT")]
     Front,
+    #[serde(alias = "HANGER")]
     Hanger,
+    #[serde(alias = "KEY")]
     Key,
+    #[serde(alias = "LOBBY")]
     Lobby,
+    #[serde(alias = "LOT")]
     Lot,
+    #[serde(alias = "LOWER")]
     Lower,
+    #[serde(alias = "OFFICE")]
     Office,
+    #[serde(alias = "PENTHOUSE")]
     Penthouse,
+    #[serde(alias = "PIER")]
     Pier,
+    #[serde(alias = "REAR")]
     Rear,
+    #[serde(alias = "ROOM")]
     Room,
+    #[serde(alias = "SIDE")]
     Side,
+    #[serde(alias = "SLIP")]
     Slip,
+    #[serde(alias = "SPACE")]
     Space,
+    #[serde(alias = "STOP")]
     Stop,
+    #[serde(alias = "SUITE")]
     Suite,
+    #[serde(alias = "TRAILER")]
     Trailer,
     #[default]
+    #[serde(alias = "UNIT")]
     Unit,
+    #[serde(alias = "UPPER")]
     Upper,
     /// Recreation room.  A shared space common to apartment complexes.
+    #[serde(alias = "REC")]
     Rec,
     /// Laundry room.  A shared space common to apartment complexes.
+    #[serde(alias = "LAUNDRY")]
     Laundry,
 }
 
@@ -0,0 +1,262 @@
+//! The `alias` module holds a table of street names known to refer to the same street, such as
+//! "HWY 99" and "ROGUE RIVER HWY", historical names and spelling variants.  Comparison and
+//! geocoding consult the table, so known-equivalent street names match without reporting a
+//! street name mismatch on every run.
+use crate::{
+    Address, AddressErrorKind, IntoCsv, Io, Parse, PartialAddress, StreetNamePostType,
+    StreetNamePreModifier, StreetNamePreType, StreetSeparator, from_csv, normalize_text, to_csv,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// The `StreetAlias` struct pairs a street name with an alias for the same street.  Names are
+/// complete street names without the directional, with street types either spelled out or
+/// abbreviated, as in "HWY 99" or "ROGUE RIVER HIGHWAY".
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct StreetAlias {
+    /// The `street` field holds the street name.
+    pub street: String,
+    /// The `alias` field holds another name for the street.
+    pub alias: String,
+}
+
+/// The `StreetAliases` struct holds a table of [`StreetAlias`] records, grouping the names that
+/// refer to the same street.  Aliases chain, so if "A" is an alias of "B" and "B" an alias of
+/// "C", all three names are equivalent.  Names are compared ignoring case and extra whitespace.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct StreetAliases {
+    aliases: Vec<StreetAlias>,
+    // The key of each name in the table, mapped to the key of the first name in its group.
+    groups: BTreeMap<String, String>,
+    // The street names without types in each group, for looking up aliases by street name.
+    names: BTreeMap<String, Vec<String>>,
+}
+
+impl StreetAliases {
+    /// The `new` method creates a table from the pairs of names in `aliases`.  Names that do not
+    /// parse as a street name are skipped with a warning.
+    pub fn new(aliases: Vec<StreetAlias>) -> Self {
+        let mut groups = BTreeMap::new();
+        let mut names: BTreeMap<String, String> = BTreeMap::new();
+        for alias in &aliases {
+            let (Some(street), Some(other)) =
+                (parse_street(&alias.street), parse_street(&alias.alias))
+            else {
+                warn!("Street alias skipped: {} = {}", alias.street, alias.alias);
+                continue;
+            };
+            let (street_key, other_key) = (street_key(&street), street_key(&other));
+            for (key, partial) in [(&street_key, &street), (&other_key, &other)] {
+                groups.entry(key.clone()).or_insert_with(|| key.clone());
+                if let Some(name) = &partial.street_name {
+                    names.insert(key.clone(), normalize_text(name));
+                }
+            }
+            let (root, other_root) = (find(&groups, &street_key), find(&groups, &other_key));
+            if root != other_root {
+                groups.insert(other_root, root);
+            }
+        }
+        // Point every name directly at the first name of its group.
+        let keys = groups.keys().cloned().collect::<Vec<String>>();
+        for key in keys {
+            let root = find(&groups, &key);
+            groups.insert(key, root);
+        }
+        let mut grouped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (key, root) in &groups {
+            if let Some(name) = names.get(key) {
+                let entry = grouped.entry(root.clone()).or_default();
+                if !entry.contains(name) {
+                    entry.push(name.clone());
+                }
+            }
+        }
+        Self {
+            aliases,
+            groups,
+            names: grouped,
+        }
+    }
+
+    /// The `aliases` method returns the pairs of names in the table.
+    pub fn aliases(&self) -> &[StreetAlias] {
+        &self.aliases
+    }
+
+    /// The `len` method returns the number of pairs of names in the table.
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    /// The `is_empty` method returns `true` if the table holds no aliases.
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// The `equivalent` method returns `true` if the streets of `address` and `other` are named
+    /// in the same group of aliases.  The directionals are not compared.
+    pub fn equivalent<T: Address + ?Sized, U: Address + ?Sized>(
+        &self,
+        address: &T,
+        other: &U,
+    ) -> bool {
+        let group = |key: String| self.groups.get(&key);
+        match group(address_key(address)) {
+            Some(root) => group(address_key(other)) == Some(root),
+            None => false,
+        }
+    }
+
+    /// The `names_street` method returns `true` if the street of `partial` and the street of
+    /// `address` are named in the same group of aliases.
+    pub fn names_street<T: Address + ?Sized>(&self, partial: &PartialAddress, address: &T) -> bool {
+        match self.groups.get(&street_key(partial)) {
+            Some(root) => self.groups.get(&address_key(address)) == Some(root),
+            None => false,
+        }
+    }
+
+    /// The `street_names` method returns the street names, without types, of the aliases of the
+    /// street in `partial`, including the street itself if it is in the table.  The geocoder
+    /// searches these streets alongside the street named in the input.
+    pub fn street_names(&self, partial: &PartialAddress) -> Vec<&str> {
+        self.groups
+            .get(&street_key(partial))
+            .and_then(|root| self.names.get(root))
+            .map(|names| names.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl IntoCsv<StreetAliases> for StreetAliases {
+    /// Reads the `street` and `alias` columns of the csv file at `path`.
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self::new(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.aliases, path.as_ref().into())
+    }
+}
+
+// Parses `name` as the street of an address, returning `None` if no street name is found.
+fn parse_street(name: &str) -> Option<PartialAddress> {
+    match Parse::address(&format!("1 {}", normalize_text(name))) {
+        Ok((_, partial)) if partial.street_name.is_some() => Some(partial),
+        _ => None,
+    }
+}
+
+// Returns the group of `key` in `groups`, following the links between groups.
+fn find(groups: &BTreeMap<String, String>, key: &str) -> String {
+    let mut key = key.to_string();
+    while let Some(parent) = groups.get(&key).filter(|parent| **parent != key) {
+        key = parent.clone();
+    }
+    key
+}
+
+// Joins the parts of a street name other than the directional, so spellings of the street types
+// share a key.
+fn key(
+    pre_modifier: &Option<StreetNamePreModifier>,
+    pre_type: &Option<StreetNamePreType>,
+    separator: &Option<StreetSeparator>,
+    street_name: &str,
+    post_type: &Option<StreetNamePostType>,
+) -> String {
+    format!(
+        "{pre_modifier:?}|{pre_type:?}|{separator:?}|{}|{post_type:?}",
+        normalize_text(street_name)
+    )
+}
+
+fn street_key(partial: &PartialAddress) -> String {
+    key(
+        &partial.pre_modifier,
+        &partial.pre_type,
+        &partial.separator,
+        partial.street_name.as_deref().unwrap_or_default(),
+        &partial.street_name_post_type,
+    )
+}
+
+fn address_key<T: Address + ?Sized>(address: &T) -> String {
+    key(
+        address.street_name_pre_modifier(),
+        address.street_name_pre_type(),
+        address.street_name_separator(),
+        address.street_name(),
+        address.street_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CommonAddress, GEOCODE_THRESHOLD, Geocoder, MatchRecords, MatchScoring, MatchStatus,
+        MatchType, SpatialAddress,
+    };
+
+    #[test]
+    fn street_aliases() -> anyhow::Result<()> {
+        let alias = |street: &str, alias: &str| StreetAlias {
+            street: street.to_string(),
+            alias: alias.to_string(),
+        };
+        let mut aliases = StreetAliases::new(vec![
+            alias("Rogue River Hwy", "HWY 99"),
+            alias("HIGHWAY 99", "REDWOOD HWY"),
+        ]);
+        let path = std::env::temp_dir().join("destination_street_aliases.csv");
+        aliases.to_csv(&path)?;
+        let aliases = StreetAliases::from_csv(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(aliases.len(), 2);
+
+        let address =
+            |pre_type: Option<StreetNamePreType>,
+             street_name: &str,
+             street_type: Option<StreetNamePostType>| SpatialAddress {
+                address: CommonAddress {
+                    number: 1200,
+                    pre_type,
+                    street_name: street_name.to_string(),
+                    street_type,
+                    zip: 97527,
+                    postal_community: "GRANTS PASS".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+        let rogue = address(None, "ROGUE RIVER", Some(StreetNamePostType::HIGHWAY));
+        let county = vec![address(Some(StreetNamePreType::Highway), "99", None)];
+        let redwood = address(None, "REDWOOD", Some(StreetNamePostType::HIGHWAY));
+        let elm = address(None, "ELM", Some(StreetNamePostType::STREET));
+        // Aliases chain through the names they share.
+        assert!(aliases.equivalent(&rogue, &county[0]));
+        assert!(aliases.equivalent(&redwood, &rogue));
+        assert!(!aliases.equivalent(&elm, &rogue));
+
+        let records = MatchRecords::new_scored(&rogue, &county, &MatchScoring::default());
+        assert_eq!(records[0].match_status, MatchStatus::Missing);
+        let scoring = MatchScoring::default().with_aliases(Some(aliases.clone()));
+        let records = MatchRecords::new_scored(&rogue, &county, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Matching);
+        let records = MatchRecords::new_scored(&elm, &county, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Missing);
+
+        // The geocoder searches the aliases of the input street.
+        let geocoder = Geocoder::new(&county, GEOCODE_THRESHOLD);
+        let record = geocoder.geocode("1200 Rogue River Hwy, Grants Pass, OR 97527");
+        assert_eq!(record.match_type, MatchType::Unmatched);
+        let geocoder = geocoder.with_aliases(Some(&aliases));
+        let record = geocoder.geocode("1200 Rogue River Hwy, Grants Pass, OR 97527");
+        assert_eq!(record.match_type, MatchType::Exact);
+        Ok(())
+    }
+}
@@ -0,0 +1,585 @@
+//! The `arcgis` module reads features from a layer of an ArcGIS REST Feature Service, paging
+//! through the `query` endpoint of the layer.  Like the `shapefile` module, feature attributes
+//! are translated into csv records in memory, so import types deserialize from a feature service
+//! using the same field names and deserialization rules as from a csv export.
+//!
+//! The loader is asynchronous and takes the HTTP client as a closure, so callers may supply the
+//! client of their choice.  The [`http_post`] function provides a client built on `reqwest`, and
+//! [`block_on`] drives the loader from synchronous code.  Requests are posted as url-encoded
+//! forms, so access tokens travel in the request body rather than in the url.
+#[cfg(feature = "io")]
+use crate::Io;
+use crate::{
+    AddressErrorKind, MatchRecord, Service,
+    shapefile::{column, field_names, from_rows, match_field, to_wkt},
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::Path;
+use tracing::info;
+
+/// The `ARCGIS_PAGE_SIZE` constant is the number of features requested per page.  Services may
+/// return fewer features per page, according to the `maxRecordCount` setting of the layer.
+pub const ARCGIS_PAGE_SIZE: usize = 1000;
+
+/// The `ARCGIS_EDIT_CHUNK` constant is the number of edits sent per `applyEdits` request.
+pub const ARCGIS_EDIT_CHUNK: usize = 500;
+
+/// The `EditSummary` struct counts the results of the edits applied by [`push_match_records`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EditSummary {
+    /// The `added` field counts the features added to the layer.
+    pub added: usize,
+    /// The `updated` field counts the features updated in the layer.
+    pub updated: usize,
+    /// The `failed` field counts the edits rejected by the service.
+    pub failed: usize,
+}
+
+/// The `FromArcGis` trait indicates the type can be read from a layer of an ArcGIS REST Feature
+/// Service.
+pub trait FromArcGis: Sized {
+    /// The `from_arcgis_service` method reads the features of the layer at `url`, such as
+    /// "https://host/arcgis/rest/services/Addresses/FeatureServer/0".  Query parameters in `url`,
+    /// such as a `token`, are sent in the form of each request.  The `post` closure takes a
+    /// request url and a url-encoded form, and returns the body of the response.
+    fn from_arcgis_service<F, Fut>(
+        url: &str,
+        post: F,
+    ) -> impl Future<Output = Result<Self, AddressErrorKind>>
+    where
+        F: FnMut(String, String) -> Fut,
+        Fut: Future<Output = Result<String, AddressErrorKind>>;
+
+    /// The `from_service` method reads the features of the layer at `url`, using [`http_post`]
+    /// for requests.
+    #[cfg(feature = "io")]
+    fn from_service(url: &str) -> Result<Self, AddressErrorKind> {
+        block_on(Self::from_arcgis_service(url, http_post))?
+    }
+}
+
+/// The `is_service` function returns true if `path` is the url of a web service rather than a
+/// file path.
+pub fn is_service<P: AsRef<Path>>(path: P) -> bool {
+    let path = path.as_ref().to_string_lossy();
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Generic function to deserialize data types from a layer of an ArcGIS REST Feature Service.
+/// Called by methods to avoid code duplication.
+///
+/// Reads the object ID field of the layer from its description, then requests pages of
+/// [`ARCGIS_PAGE_SIZE`] features ordered by object ID, until the service no longer reports that
+/// the transfer limit was exceeded.  Point geometry populates the `x` and `y` fields of the
+/// record, in the spatial reference of the layer, and polyline geometry populates the `wkt`
+/// field as well-known text.  If `T` has `latitude` and `longitude` fields that the layer lacks,
+/// each page is requested a second time in WGS84 to fill them, matching features by object ID.
+/// Records that fail to deserialize are dropped, consistent with [`from_csv`](crate::from_csv).
+pub async fn from_arcgis_service<T, F, Fut>(
+    url: &str,
+    mut post: F,
+) -> Result<Vec<T>, AddressErrorKind>
+where
+    T: DeserializeOwned,
+    F: FnMut(String, String) -> Fut,
+    Fut: Future<Output = Result<String, AddressErrorKind>>,
+{
+    let (layer, parameters) = split_url(url);
+    info!("Reading features from {layer}");
+    let description = parse(
+        &post(
+            layer.to_string(),
+            with_parameters("f=json".into(), parameters),
+        )
+        .await?,
+        layer,
+    )?;
+    if let Some(issue) = service_error(&description) {
+        return Err(Service::new(layer.into(), issue.into(), line!(), file!().into()).into());
+    }
+    let id_field = description
+        .get("objectIdField")
+        .and_then(Value::as_str)
+        .unwrap_or("OBJECTID")
+        .to_string();
+    let fields = field_names::<T>();
+    let geographic = fields.contains(&"latitude") && fields.contains(&"longitude");
+    let mut names = Vec::new();
+    let mut headers = Vec::new();
+    let mut rows = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = query(layer, parameters, &id_field, offset, None, &mut post).await?;
+        let features = page_features(&page, layer)?;
+        if names.is_empty()
+            && let Some(attributes) = features
+                .first()
+                .and_then(|f| f.get("attributes"))
+                .and_then(Value::as_object)
+        {
+            names = attributes.keys().cloned().collect::<Vec<String>>();
+            headers = names
+                .iter()
+                .map(|name| match_field(name, fields))
+                .collect::<Vec<String>>();
+        }
+        // Coordinates in WGS84, if the layer does not record them as attributes.
+        let attributes = &headers[..names.len()];
+        let located = attributes.iter().any(|h| h == "latitude")
+            && attributes.iter().any(|h| h == "longitude");
+        let mut degrees = HashMap::new();
+        if geographic && !located {
+            let page = query(layer, parameters, &id_field, offset, Some(4326), &mut post).await?;
+            for feature in page_features(&page, layer)? {
+                let id = text(&feature["attributes"][&id_field]);
+                degrees.insert(id, feature["geometry"].clone());
+            }
+        }
+        for feature in features {
+            let mut row = names
+                .iter()
+                .map(|name| text(&feature["attributes"][name]))
+                .collect::<Vec<String>>();
+            let mut set = |name: &str, value: String| {
+                let index = column(&mut headers, name);
+                row.resize(headers.len(), String::new());
+                row[index] = value;
+            };
+            let geometry = &feature["geometry"];
+            if let Some(paths) = geometry.get("paths") {
+                let parts: Vec<Vec<[f64; 2]>> =
+                    serde_json::from_value(paths.clone()).unwrap_or_default();
+                set("wkt", to_wkt(&parts));
+            } else if geometry.get("x").is_some() {
+                set("x", text(&geometry["x"]));
+                set("y", text(&geometry["y"]));
+            }
+            if let Some(point) = degrees.get(&text(&feature["attributes"][&id_field])) {
+                set("longitude", text(&point["x"]));
+                set("latitude", text(&point["y"]));
+            }
+            rows.push(row);
+        }
+        offset += features.len();
+        let exceeded = page
+            .get("exceededTransferLimit")
+            .and_then(Value::as_bool)
+            .unwrap_or_default();
+        if !exceeded || features.is_empty() {
+            break;
+        }
+    }
+    info!("{} features read.", rows.len());
+    // Rows read before a new column was added are short.
+    rows.iter_mut()
+        .for_each(|row| row.resize(headers.len(), String::new()));
+    from_rows(&headers, rows, Path::new(layer))
+}
+
+/// The `http_post` function posts the url-encoded form `form` to `url`, returning the body of
+/// the response.  Serves as the HTTP client for [`from_arcgis_service`] and
+/// [`push_match_records`].  Responses with an error status are errors.
+#[cfg(feature = "io")]
+pub async fn http_post(url: String, form: String) -> Result<String, AddressErrorKind> {
+    http_request(url, "application/x-www-form-urlencoded", form).await
+}
+
+// Posts `body` with the content type `content_type` to `url`, returning the body of the
+// response.
+#[cfg(feature = "io")]
+pub(crate) async fn http_request(
+    url: String,
+    content_type: &str,
+    body: String,
+) -> Result<String, AddressErrorKind> {
+    let error = |source: reqwest::Error| {
+        Service::new(url.clone(), describe(&source), line!(), file!().into())
+    };
+    let client = reqwest::Client::builder().build().map_err(error)?;
+    let response = client
+        .post(&url)
+        .header(reqwest::header::CONTENT_TYPE, content_type)
+        .body(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(error)?;
+    Ok(response.text().await.map_err(error)?)
+}
+
+/// The `block_on` function runs `future` to completion on a new single-threaded runtime, so
+/// synchronous callers can drive the asynchronous loaders with [`http_post`].  Must not be
+/// called from within another async runtime.
+#[cfg(feature = "io")]
+pub fn block_on<F: Future>(future: F) -> Result<F::Output, AddressErrorKind> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|source| Io::new("runtime".into(), source, line!(), file!().into()))?;
+    Ok(runtime.block_on(future))
+}
+
+/// The `push_match_records` function writes the match status of `records` to the layer at
+/// `url`, through the `applyEdits` endpoint of the layer.  The layer must have text fields named
+/// `address_label` and `match_status`.  Features are matched to records by address label.
+/// Existing features receive an update to the `match_status` field, and other addresses are
+/// added as point features, with coordinates in WGS84.  When an address has several match
+/// records, the best status is written.
+///
+/// If set, `token` authenticates each request.  The `post` closure takes a request url and a
+/// url-encoded form, and returns the body of the response.  Edits are sent in chunks of
+/// [`ARCGIS_EDIT_CHUNK`].
+pub async fn push_match_records<F, Fut>(
+    records: &[MatchRecord],
+    url: &str,
+    token: Option<&str>,
+    mut post: F,
+) -> Result<EditSummary, AddressErrorKind>
+where
+    F: FnMut(String, String) -> Fut,
+    Fut: Future<Output = Result<String, AddressErrorKind>>,
+{
+    let (layer, parameters) = split_url(url);
+    let mut auth = token
+        .map(|token| format!("&token={}", encode(token)))
+        .unwrap_or_default();
+    if !parameters.is_empty() {
+        auth = format!("&{parameters}{auth}");
+    }
+
+    // Matching sorts before divergent, and divergent before missing.
+    let mut best: BTreeMap<&str, &MatchRecord> = BTreeMap::new();
+    for record in records {
+        best.entry(&record.address_label)
+            .and_modify(|r| {
+                if record.match_status < r.match_status {
+                    *r = record;
+                }
+            })
+            .or_insert(record);
+    }
+
+    info!("Reading existing features from {layer}");
+    let mut id_field = "OBJECTID".to_string();
+    let mut existing = HashMap::new();
+    let mut offset = 0;
+    loop {
+        let form = format!(
+            "where=1%3D1&outFields=address_label&returnGeometry=false&resultOffset={offset}&resultRecordCount={ARCGIS_PAGE_SIZE}&f=json{auth}"
+        );
+        let page = parse(&post(format!("{layer}/query"), form).await?, layer)?;
+        if let Some(field) = page.get("objectIdFieldName").and_then(Value::as_str) {
+            id_field = field.to_string();
+        }
+        let features = page_features(&page, layer)?;
+        for feature in features {
+            let attributes = &feature["attributes"];
+            if let (Some(label), Some(id)) = (
+                attributes["address_label"].as_str(),
+                attributes[&id_field].as_i64(),
+            ) {
+                existing.insert(label.to_string(), id);
+            }
+        }
+        offset += features.len();
+        let exceeded = page
+            .get("exceededTransferLimit")
+            .and_then(Value::as_bool)
+            .unwrap_or_default();
+        if !exceeded || features.is_empty() {
+            break;
+        }
+    }
+    info!("{} existing features read.", existing.len());
+
+    let mut adds = Vec::new();
+    let mut updates = Vec::new();
+    for (label, record) in best {
+        let status = serde_json::to_value(&record.match_status).unwrap_or_default();
+        match existing.get(label) {
+            Some(id) => {
+                let mut attributes = Map::new();
+                attributes.insert(id_field.clone(), json!(id));
+                attributes.insert("match_status".into(), status);
+                updates.push(json!({ "attributes": attributes }));
+            }
+            None => adds.push(json!({
+                "attributes": { "address_label": label, "match_status": status },
+                "geometry": {
+                    "x": record.longitude,
+                    "y": record.latitude,
+                    "spatialReference": { "wkid": 4326 }
+                }
+            })),
+        }
+    }
+
+    let mut summary = EditSummary::default();
+    let chunks = adds
+        .chunks(ARCGIS_EDIT_CHUNK)
+        .map(|chunk| ("adds", chunk))
+        .chain(
+            updates
+                .chunks(ARCGIS_EDIT_CHUNK)
+                .map(|chunk| ("updates", chunk)),
+        )
+        .collect::<Vec<(&str, &[Value])>>();
+    for (i, (kind, chunk)) in chunks.iter().enumerate() {
+        info!("Applying edits: request {} of {}.", i + 1, chunks.len());
+        let edits = Value::Array(chunk.to_vec()).to_string();
+        let form = format!(
+            "{kind}={}&rollbackOnFailure=false&f=json{auth}",
+            encode(&edits)
+        );
+        let response = parse(&post(format!("{layer}/applyEdits"), form).await?, layer)?;
+        if let Some(issue) = service_error(&response) {
+            return Err(Service::new(layer.into(), issue.into(), line!(), file!().into()).into());
+        }
+        let results = |key: &str| {
+            response
+                .get(key)
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+        };
+        for (key, count) in [
+            ("addResults", &mut summary.added),
+            ("updateResults", &mut summary.updated),
+        ] {
+            for result in results(key) {
+                if result["success"].as_bool().unwrap_or_default() {
+                    *count += 1;
+                } else {
+                    summary.failed += 1;
+                }
+            }
+        }
+    }
+    Ok(summary)
+}
+
+// Requests a page of features of `layer` starting at `offset`, ordered by the object ID field
+// `id_field`.  When projected to the spatial reference with WKID `wkid`, only the object ID is
+// requested along with the geometry.
+async fn query<F, Fut>(
+    layer: &str,
+    parameters: &str,
+    id_field: &str,
+    offset: usize,
+    wkid: Option<u32>,
+    post: &mut F,
+) -> Result<Value, AddressErrorKind>
+where
+    F: FnMut(String, String) -> Fut,
+    Fut: Future<Output = Result<String, AddressErrorKind>>,
+{
+    let id_field = encode(id_field);
+    let fields = if wkid.is_some() { &id_field } else { "*" };
+    let mut form = format!(
+        "where=1%3D1&outFields={fields}&returnGeometry=true&orderByFields={id_field}&resultOffset={offset}&resultRecordCount={ARCGIS_PAGE_SIZE}&f=json"
+    );
+    if let Some(wkid) = wkid {
+        form.push_str(&format!("&outSR={wkid}"));
+    }
+    let form = with_parameters(form, parameters);
+    parse(&post(format!("{layer}/query"), form).await?, layer)
+}
+
+// Splits the query parameters, such as a token, from the url of a layer, so they can be sent in
+// the form of each request.
+fn split_url(url: &str) -> (&str, &str) {
+    let (layer, parameters) = url.split_once('?').unwrap_or((url, ""));
+    (layer.trim_end_matches('/'), parameters)
+}
+
+// Appends the query parameters `parameters` to the url-encoded form `form`.
+fn with_parameters(form: String, parameters: &str) -> String {
+    if parameters.is_empty() {
+        form
+    } else {
+        format!("{form}&{parameters}")
+    }
+}
+
+// Returns the message of the error reported by the service, if any.
+fn service_error(response: &Value) -> Option<&str> {
+    response.get("error").map(|error| {
+        error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error")
+    })
+}
+
+// Describes a failed request, along with the chain of underlying causes.
+#[cfg(feature = "io")]
+pub(crate) fn describe(error: &dyn std::error::Error) -> String {
+    let mut issue = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        issue.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+    issue
+}
+
+// Parses the body of a response from the service at `url`.
+fn parse(body: &str, url: &str) -> Result<Value, AddressErrorKind> {
+    serde_json::from_str::<Value>(body)
+        .map_err(|e| Service::new(url.into(), e.to_string(), line!(), file!().into()).into())
+}
+
+// Percent-encodes `value` for a url or form, leaving only unreserved characters.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+// Returns the features of a page, or the error reported by the service.
+fn page_features<'a>(page: &'a Value, url: &str) -> Result<&'a Vec<Value>, AddressErrorKind> {
+    if let Some(issue) = service_error(page) {
+        return Err(Service::new(url.into(), issue.into(), line!(), file!().into()).into());
+    }
+    page.get("features")
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            Service::new(
+                url.into(),
+                "no features in response".into(),
+                line!(),
+                file!().into(),
+            )
+            .into()
+        })
+}
+
+// Formats an attribute value as csv text.  Null values are empty.
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(all(test, feature = "io"))]
+mod tests {
+    use super::*;
+    use crate::{
+        Address, Cartesian, Geographic, MatchStatus, SpatialAddresses, SpatialAddressesRaw,
+        push_match_records,
+    };
+    use tracing::info;
+
+    #[test]
+    fn load_arcgis_service() -> anyhow::Result<()> {
+        let sample: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string("data/arcgis_sample.json")?)?;
+        let description = serde_json::json!({ "objectIdField": "OBJECTID" });
+        let mut requests = Vec::new();
+        let post = |url: String, form: String| {
+            let pages = if form.contains("outSR=4326") {
+                &sample["geographic"]
+            } else {
+                &sample["projected"]
+            };
+            let page = if !url.ends_with("/query") {
+                &description
+            } else if form.contains("resultOffset=0&") {
+                &pages[0]
+            } else {
+                &pages[1]
+            };
+            requests.push((url, form));
+            std::future::ready(Ok(page.to_string()))
+        };
+        let url = "https://example.com/arcgis/rest/services/Addresses/FeatureServer/0?token=abc";
+        let addresses = block_on(SpatialAddressesRaw::from_arcgis_service(url, post))??;
+        assert_eq!(addresses.len(), 3);
+        // The layer description, then each page twice, the second time in WGS84 for the latitude and
+        // longitude.
+        assert_eq!(requests.len(), 5);
+        // The token travels in the form rather than the url.
+        assert!(
+            requests
+                .iter()
+                .all(|(url, form)| !url.contains("token") && form.ends_with("&token=abc"))
+        );
+        assert!(
+            requests[1..]
+                .iter()
+                .all(|(_, form)| form.contains("orderByFields=OBJECTID&"))
+        );
+        assert!(requests[3].1.contains("resultOffset=2&"));
+        let spatial = SpatialAddresses::from(addresses);
+        assert_eq!(spatial[0].label(), "801 SE J ST");
+        assert_eq!(spatial[0].x(), 1105234.5);
+        assert_eq!(spatial[1].subaddress_id(), &Some("2".to_string()));
+        assert_eq!(spatial[2].latitude(), 42.45);
+        info!("Addresses read from feature service.");
+        Ok(())
+    }
+
+    #[test]
+    fn push_match_status() -> anyhow::Result<()> {
+        let record = |label: &str, match_status: MatchStatus| MatchRecord {
+            match_status,
+            address_label: label.to_string(),
+            longitude: -123.3213,
+            latitude: 42.4359,
+            ..Default::default()
+        };
+        let mut records = vec![
+            record("801 SE J ST", MatchStatus::Divergent),
+            record("801 SE J ST", MatchStatus::Matching),
+        ];
+        records.extend((0..600).map(|i| record(&format!("{i} NW F ST"), MatchStatus::Missing)));
+
+        let mut requests = Vec::new();
+        let post = |url: String, form: String| {
+            let response = if url.ends_with("/query") {
+                serde_json::json!({
+                    "objectIdFieldName": "FID",
+                    "features": [{ "attributes": { "FID": 7, "address_label": "801 SE J ST" } }]
+                })
+            } else {
+                let count = form.matches("match_status").count();
+                let results = vec![serde_json::json!({ "success": true }); count];
+                if form.starts_with("adds=") {
+                    serde_json::json!({ "addResults": results })
+                } else {
+                    serde_json::json!({ "updateResults": results })
+                }
+            };
+            requests.push((url, form));
+            std::future::ready(Ok(response.to_string()))
+        };
+        let url = "https://example.com/arcgis/rest/services/Matches/FeatureServer/0";
+        let summary = block_on(push_match_records(&records, url, Some("abc"), post))??;
+        assert_eq!(summary.added, 600);
+        assert_eq!(summary.updated, 1);
+        assert_eq!(summary.failed, 0);
+        // One query, two chunks of adds and one chunk of updates.
+        assert_eq!(requests.len(), 4);
+        assert!(
+            requests
+                .iter()
+                .all(|(_, form)| form.ends_with("&token=abc"))
+        );
+        let (_, update) = &requests[3];
+        assert!(update.contains("%22FID%22%3A7"));
+        assert!(update.contains("Matching"));
+        Ok(())
+    }
+}
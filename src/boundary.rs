@@ -0,0 +1,292 @@
+//! The `boundary` module reads service area polygons, such as city limits, from GeoJSON files
+//! and shapefiles, and tests whether address points fall inside them.
+use crate::{
+    AddressErrorKind, Cartesian, FromShapefile, Geographic, Io, Json,
+    crs::METERS_PER_DEGREE,
+    shapefile::{PolygonFeature, from_polygon_features, from_polygon_shapefile},
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+// The subset of GeoJSON read for a boundary.  Geometries other than polygons are ignored.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum GeoJson {
+    FeatureCollection {
+        features: Vec<GeoJson>,
+    },
+    Feature {
+        geometry: Option<Box<GeoJson>>,
+        #[serde(default)]
+        properties: Option<serde_json::Map<String, serde_json::Value>>,
+    },
+    GeometryCollection {
+        geometries: Vec<GeoJson>,
+    },
+    Polygon {
+        coordinates: Vec<Vec<Vec<f64>>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<Vec<f64>>>>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+impl GeoJson {
+    // Collects the rings of every polygon into `rings`.
+    fn rings(self, rings: &mut Vec<Vec<[f64; 2]>>) {
+        let ring = |positions: Vec<Vec<f64>>| {
+            positions
+                .into_iter()
+                .filter(|p| p.len() >= 2)
+                .map(|p| [p[0], p[1]])
+                .collect::<Vec<[f64; 2]>>()
+        };
+        match self {
+            Self::FeatureCollection { features } => {
+                features.into_iter().for_each(|f| f.rings(rings))
+            }
+            Self::Feature { geometry, .. } => {
+                if let Some(geometry) = geometry {
+                    geometry.rings(rings);
+                }
+            }
+            Self::GeometryCollection { geometries } => {
+                geometries.into_iter().for_each(|g| g.rings(rings))
+            }
+            Self::Polygon { coordinates } => rings.extend(coordinates.into_iter().map(ring)),
+            Self::MultiPolygon { coordinates } => {
+                rings.extend(coordinates.into_iter().flatten().map(ring))
+            }
+            Self::Other => {}
+        }
+    }
+
+    // Collects the rings of each feature into `features`, paired with the value of the property
+    // named `field`.  Geometries outside of a feature take an empty value.
+    fn features(self, field: &str, features: &mut Vec<PolygonFeature>) {
+        match self {
+            Self::FeatureCollection { features: members } => members
+                .into_iter()
+                .for_each(|f| f.features(field, features)),
+            Self::Feature {
+                geometry,
+                properties,
+            } => {
+                let mut rings = Vec::new();
+                if let Some(geometry) = geometry {
+                    geometry.rings(&mut rings);
+                }
+                if !rings.is_empty() {
+                    let value = properties
+                        .iter()
+                        .flatten()
+                        .find(|(key, _)| key.eq_ignore_ascii_case(field))
+                        .map(|(_, value)| match value {
+                            serde_json::Value::String(text) => text.clone(),
+                            serde_json::Value::Null => String::new(),
+                            value => value.to_string(),
+                        })
+                        .unwrap_or_default();
+                    features.push((value, rings));
+                }
+            }
+            geometry => {
+                let mut rings = Vec::new();
+                geometry.rings(&mut rings);
+                if !rings.is_empty() {
+                    features.push((String::new(), rings));
+                }
+            }
+        }
+    }
+
+    // Reads the GeoJSON file at `path`.
+    fn from_path(path: &Path) -> Result<Self, AddressErrorKind> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+        Ok(serde_json::from_str(&text)
+            .map_err(|source| Json::new(path.into(), source, line!(), file!().into()))?)
+    }
+}
+
+/// The `Boundary` struct holds the rings of one or more polygons bounding a service area.  A
+/// point lies inside the boundary if it falls within an odd number of rings, so holes and
+/// separate polygons need no special handling.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Boundary {
+    /// The `rings` field holds the vertices of each polygon ring, as x and y or longitude and
+    /// latitude pairs.
+    pub rings: Vec<Vec<[f64; 2]>>,
+    /// The `geographic` field is true if the vertices are longitude and latitude, and false if
+    /// they are projected coordinates.
+    pub geographic: bool,
+}
+
+impl Boundary {
+    /// The `from_geojson` method reads the polygons and multipolygons in the GeoJSON file at
+    /// `path`.  Following the GeoJSON specification, the coordinates are taken to be longitude
+    /// and latitude.
+    pub fn from_geojson<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        let path = path.as_ref();
+        info!("Reading boundary from {:?}", path);
+        let mut rings = Vec::new();
+        GeoJson::from_path(path)?.rings(&mut rings);
+        info!("Boundary rings read: {}", rings.len());
+        Ok(Self {
+            rings,
+            geographic: true,
+        })
+    }
+
+    /// The `from_path` method reads a boundary from a shapefile if `path` has a `.shp` extension,
+    /// and from a GeoJSON file otherwise.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        if crate::is_shapefile(&path) {
+            Self::from_shapefile(path)
+        } else {
+            Self::from_geojson(path)
+        }
+    }
+
+    /// The `from_features` method reads each polygon feature of the GeoJSON file or shapefile at
+    /// `path` as a separate boundary, paired with the value of its attribute named `field`.  The
+    /// attribute name matches without regard to case, and a feature lacking the attribute takes
+    /// an empty value, as does every feature when `field` is empty.  Returns an error if a
+    /// shapefile has no attribute named `field`.
+    pub fn from_features<P: AsRef<Path>>(
+        path: P,
+        field: &str,
+    ) -> Result<Vec<(String, Self)>, AddressErrorKind> {
+        let path = path.as_ref();
+        let (features, geographic) = if crate::is_shapefile(path) {
+            (from_polygon_features(path, field)?, is_geographic(path))
+        } else {
+            info!("Reading features from {:?}", path);
+            let mut features = Vec::new();
+            GeoJson::from_path(path)?.features(field, &mut features);
+            (features, true)
+        };
+        info!("Boundary features read: {}", features.len());
+        Ok(features
+            .into_iter()
+            .map(|(value, rings)| (value, Self { rings, geographic }))
+            .collect())
+    }
+
+    /// The `contains_point` method returns true if the point at `x` and `y` lies inside the
+    /// boundary, counting the rings crossed by a ray cast from the point.
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        let mut inside = false;
+        for ring in &self.rings {
+            for (i, a) in ring.iter().enumerate() {
+                let b = ring[(i + 1) % ring.len()];
+                if (a[1] > y) != (b[1] > y) && x < (b[0] - a[0]) * (y - a[1]) / (b[1] - a[1]) + a[0]
+                {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    /// The `contains` method returns true if `address` lies inside the boundary, comparing the
+    /// geographic coordinates of the address to a geographic boundary, and the projected
+    /// coordinates otherwise.
+    pub fn contains<T: Cartesian + Geographic>(&self, address: &T) -> bool {
+        if self.geographic {
+            self.contains_point(address.longitude(), address.latitude())
+        } else {
+            self.contains_point(address.x(), address.y())
+        }
+    }
+
+    /// The `edge_distance` method returns the distance from `address` to the nearest edge of the
+    /// boundary, or infinity for an empty boundary.  Distances to a projected boundary are in the
+    /// units of the projected coordinates.  Distances to a geographic boundary are in meters,
+    /// scaling degrees of longitude and latitude to meters at the latitude of the address, which
+    /// is accurate to a fraction of a percent over the width of a county.
+    pub fn edge_distance<T: Cartesian + Geographic>(&self, address: &T) -> f64 {
+        let (px, py, sx, sy) = if self.geographic {
+            let latitude = address.latitude();
+            (
+                address.longitude(),
+                latitude,
+                METERS_PER_DEGREE * latitude.to_radians().cos(),
+                METERS_PER_DEGREE,
+            )
+        } else {
+            (address.x(), address.y(), 1.0, 1.0)
+        };
+        let mut distance = f64::INFINITY;
+        for ring in &self.rings {
+            for (i, a) in ring.iter().enumerate() {
+                let b = ring[(i + 1) % ring.len()];
+                let (ax, ay) = ((a[0] - px) * sx, (a[1] - py) * sy);
+                let (bx, by) = ((b[0] - px) * sx, (b[1] - py) * sy);
+                // Project the address, at the origin, onto the segment from a to b.
+                let (dx, dy) = (bx - ax, by - ay);
+                let length = dx * dx + dy * dy;
+                let t = if length > 0.0 {
+                    (-(ax * dx + ay * dy) / length).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                distance = distance.min((ax + t * dx).hypot(ay + t * dy));
+            }
+        }
+        distance
+    }
+
+    /// The `nearest_point` method returns the point on the edges of the boundary nearest to the
+    /// point at `x` and `y`, with the distance to it, in the coordinates of the boundary.  Returns
+    /// `None` for an empty boundary.
+    pub fn nearest_point(&self, x: f64, y: f64) -> Option<([f64; 2], f64)> {
+        let mut nearest: Option<([f64; 2], f64)> = None;
+        for ring in &self.rings {
+            for (i, a) in ring.iter().enumerate() {
+                let b = ring[(i + 1) % ring.len()];
+                let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+                let length = dx * dx + dy * dy;
+                let t = if length > 0.0 {
+                    (((x - a[0]) * dx + (y - a[1]) * dy) / length).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let point = [a[0] + t * dx, a[1] + t * dy];
+                let distance = (point[0] - x).hypot(point[1] - y);
+                if nearest.is_none_or(|(_, d)| distance < d) {
+                    nearest = Some((point, distance));
+                }
+            }
+        }
+        nearest
+    }
+
+    /// The `partition` method splits `records` into the records inside the boundary and the
+    /// records outside of it.
+    pub fn partition<T: Cartesian + Geographic + Clone>(&self, records: &[T]) -> (Vec<T>, Vec<T>) {
+        records.iter().cloned().partition(|r| self.contains(r))
+    }
+}
+
+impl FromShapefile for Boundary {
+    /// Reads the polygons of the shapefile at `path`.  The coordinates are taken to be longitude
+    /// and latitude if the `.prj` sidecar describes a geographic coordinate system, and projected
+    /// coordinates otherwise.
+    fn from_shapefile<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        let rings = from_polygon_shapefile(&path)?;
+        let geographic = is_geographic(path.as_ref());
+        info!("Boundary rings read: {}", rings.len());
+        Ok(Self { rings, geographic })
+    }
+}
+
+// Returns true if the `.prj` sidecar of the shapefile at `path` describes a geographic coordinate
+// system.
+fn is_geographic(path: &Path) -> bool {
+    std::fs::read_to_string(path.with_extension("prj"))
+        .is_ok_and(|prj| prj.trim_start().starts_with("GEOGCS"))
+}
@@ -1,27 +1,61 @@
 //! The `business` module matches addresses associated with business licenses against a set of known [`Addresses`], producing a record of
 //! matching, divergent and missing addresses.
 use crate::{
-    Address, AddressErrorKind, Geographic, IntoCsv, Io, MatchStatus, Nom, Parse,
-    StreetNamePostType, StreetNamePreDirectional, deserialize_phone_number, from_csv, to_csv,
+    Address, AddressErrorKind, Builder, Geographic, IntoCsv, IntoJson, Io, MatchStatus, Nom, Parse,
+    ParseError, Spreadsheet, StreetNamePostType, StreetNamePreDirectional,
+    deserialize_phone_number, from_csv, progress_bar, to_csv, to_json, to_ndjson,
 };
+use calamine::{Data, Reader};
 use derive_more::{Deref, DerefMut};
 // use galileo::galileo_types::geo::GeoPoint;
 use indicatif::ParallelProgressIterator;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use tracing::info;
 
+/// The `BusinessAddressKind` enum records which address of a business license produced a match
+/// record, the situs address where the business operates or the mailing address.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BusinessAddressKind {
+    /// The `Situs` variant indicates the site address of the business.
+    #[default]
+    #[display("situs")]
+    Situs,
+    /// The `Mailing` variant indicates the mailing address of the business.
+    #[display("mailing")]
+    Mailing,
+}
+
 /// The `BusinessMatchRecord` struct holds match data for a licensed business.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 pub struct BusinessMatchRecord {
     match_status: MatchStatus,
+    #[serde(default)]
+    address_kind: BusinessAddressKind,
     business_address_label: String,
     company_name: Option<String>,
     contact_name: Option<String>,
     business_type: String,
     dba: Option<String>,
     license: String,
+    #[serde(default = "one")]
+    license_count: usize,
     expires: String,
     industry_code: i64,
     community: String,
@@ -37,6 +71,17 @@ impl BusinessMatchRecord {
         self.company_name.clone()
     }
 
+    /// The `match_status` field represents the match status of the business address.
+    pub fn match_status(&self) -> MatchStatus {
+        self.match_status.clone()
+    }
+
+    /// The `address_kind` field represents the address of the business license that produced
+    /// the record.
+    pub fn address_kind(&self) -> BusinessAddressKind {
+        self.address_kind
+    }
+
     /// The `contact_name` field represents the contact name associated with the
     /// active business license.
     pub fn contact_name(&self) -> Option<String> {
@@ -67,12 +112,29 @@ impl BusinessMatchRecord {
         self.license.clone()
     }
 
+    /// The `license_count` field represents the number of active business licenses grouped into
+    /// the record.  See [`BusinessMatchRecords::group`].
+    pub fn license_count(&self) -> usize {
+        self.license_count
+    }
+
     /// The `industry_code` field represents the tax code associated with the
     /// active business license.
     pub fn industry_code(&self) -> i64 {
         self.industry_code
     }
 
+    /// The `business_name` method returns the company name of the record normalized by
+    /// [`normalize_business_name`], falling back to the business name alias and then the contact
+    /// name if the company name is missing.
+    pub fn business_name(&self) -> Option<String> {
+        [&self.company_name, &self.dba, &self.contact_name]
+            .into_iter()
+            .flatten()
+            .map(|name| normalize_business_name(name))
+            .find(|name| !name.is_empty())
+    }
+
     /// The `latitude` method returns the latitude of the address for the
     /// active business license.
     pub fn latitude(&self) -> Option<f64> {
@@ -104,12 +166,14 @@ impl BusinessMatchRecords {
         if records.is_empty() {
             records.push(BusinessMatchRecord {
                 match_status: MatchStatus::Missing,
+                address_kind: BusinessAddressKind::Situs,
                 business_address_label: business.label(),
                 company_name: business.company_name(),
                 contact_name: business.contact_name(),
                 business_type: business.business_type(),
                 dba: business.dba(),
                 license: business.license(),
+                license_count: 1,
                 expires: business.expires(),
                 industry_code: business.industry_code(),
                 community: business.community(),
@@ -172,14 +236,10 @@ impl BusinessMatchRecords {
         businesses: &BusinessLicenses,
         addresses: &[T],
     ) -> Self {
-        let style = indicatif::ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {'Comparing addresses.'}",
-        )
-        .unwrap();
         let record = businesses
             .par_iter()
             .map(|address| BusinessMatchRecords::new(address, addresses))
-            .progress_with_style(style)
+            .progress_with(progress_bar(businesses.len(), "Comparing addresses."))
             .collect::<Vec<BusinessMatchRecords>>();
         let mut records = Vec::new();
         for mut item in record {
@@ -195,19 +255,68 @@ impl BusinessMatchRecords {
         businesses: &BusinessLicenses,
         addresses: &[&[T]],
     ) -> Self {
-        let style = indicatif::ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {'Comparing addresses.'}",
-        )
-        .unwrap();
         let record = businesses
             .par_iter()
             .map(|address| BusinessMatchRecords::chain(address, addresses))
-            .progress_with_style(style)
+            .progress_with(progress_bar(businesses.len(), "Comparing addresses."))
+            .collect::<Vec<BusinessMatchRecords>>();
+        let mut records = Vec::new();
+        for mut item in record {
+            records.append(&mut item);
+        }
+        BusinessMatchRecords(records)
+    }
+
+    /// Compares the situs address of each license in `situs` against the addresses in
+    /// `addresses` using the [`BusinessMatchRecords::chain()`] method.  If the situs address has
+    /// no exact match, the mailing address of the license in `mailing` with the same license
+    /// number is compared in turn, and an exact match of the mailing address replaces the situs
+    /// records, with the `address_kind` field set to [`BusinessAddressKind::Mailing`].  These are
+    /// licenses where only the mailing address is known to the address layer.
+    pub fn compare_mailing<T: Address + Geographic + Send + Sync>(
+        situs: &BusinessLicenses,
+        mailing: &BusinessLicenses,
+        addresses: &[&[T]],
+    ) -> Self {
+        let mut mailing_licenses = HashMap::new();
+        for record in mailing.iter() {
+            mailing_licenses.entry(record.license()).or_insert(record);
+        }
+        let record = situs
+            .par_iter()
+            .map(|business| {
+                let records = BusinessMatchRecords::chain(business, addresses);
+                if records
+                    .iter()
+                    .any(|r| r.match_status == MatchStatus::Matching)
+                {
+                    return records;
+                }
+                if let Some(mail) = mailing_licenses.get(&business.license()) {
+                    let mut mailed =
+                        BusinessMatchRecords::chain(mail, addresses).filter("matching");
+                    if !mailed.is_empty() {
+                        mailed
+                            .iter_mut()
+                            .for_each(|r| r.address_kind = BusinessAddressKind::Mailing);
+                        return mailed;
+                    }
+                }
+                records
+            })
+            .progress_with(progress_bar(situs.len(), "Comparing addresses."))
             .collect::<Vec<BusinessMatchRecords>>();
         let mut records = Vec::new();
         for mut item in record {
             records.append(&mut item);
         }
+        info!(
+            "Licenses matched by mailing address: {}",
+            records
+                .iter()
+                .filter(|r| r.address_kind == BusinessAddressKind::Mailing)
+                .count()
+        );
         BusinessMatchRecords(records)
     }
 
@@ -216,7 +325,9 @@ impl BusinessMatchRecords {
     /// "missing", "nonmissing", "divergent", "matching", "unique" and "multiple". The "unique"
     /// option returns records where the business name is unique.  The "multiple" options returns
     /// records where multiple licenses exist registered under the same business name. The "local"
-    /// option returns records within Grants Pass or Merlin.
+    /// option returns records within Grants Pass or Merlin.  The "mailing" option returns records
+    /// matched by the mailing address of the license.  See
+    /// [`BusinessMatchRecords::compare_mailing()`].
     ///
     /// As a filter, the method must either copy the data in Self to create a subset using the
     /// filter, or it must mutate the data of Self in place.  Here we take ownership of Self and
@@ -233,13 +344,13 @@ impl BusinessMatchRecords {
                 let mut names = HashSet::new();
                 let mut records = Vec::new();
                 for record in self.iter() {
-                    if let Some(name) = record.company_name() {
-                        if !names.contains(&name) {
-                            names.insert(name.clone());
-                            let subset = self.clone().filter_field("name", &name);
-                            if subset.len() == 1 {
-                                records.push(subset[0].clone());
-                            }
+                    if let Some(name) = record.company_name()
+                        && !names.contains(&name)
+                    {
+                        names.insert(name.clone());
+                        let subset = self.clone().filter_field("name", &name);
+                        if subset.len() == 1 {
+                            records.push(subset[0].clone());
                         }
                     }
                 }
@@ -249,19 +360,20 @@ impl BusinessMatchRecords {
                 let mut names = HashSet::new();
                 let mut records = Vec::new();
                 for record in self.iter() {
-                    if let Some(name) = record.company_name() {
-                        if !names.contains(&name) {
-                            names.insert(name.clone());
-                            let mut subset = self.clone().filter_field("name", &name);
-                            if subset.len() > 1 {
-                                records.append(&mut subset);
-                            }
+                    if let Some(name) = record.company_name()
+                        && !names.contains(&name)
+                    {
+                        names.insert(name.clone());
+                        let mut subset = self.clone().filter_field("name", &name);
+                        if subset.len() > 1 {
+                            records.append(&mut subset);
                         }
                     }
                 }
                 self.0 = records;
             }
             "local" => self.retain(|r| r.community == "GRANTS PASS" || r.community == "MERLIN"),
+            "mailing" => self.retain(|r| r.address_kind == BusinessAddressKind::Mailing),
             _ => info!("Invalid filter provided."),
         }
         self
@@ -281,6 +393,42 @@ impl BusinessMatchRecords {
         self
     }
 
+    /// The `group` method collapses records for the same business at the same address into a
+    /// single record, counting the distinct licenses in the `license_count` field.  Businesses
+    /// are compared by the name returned from [`BusinessMatchRecord::business_name`], so
+    /// "Rogue Valley Plumbing, LLC" and "ROGUE VALLEY PLUMBING" are the same business.  Records
+    /// without a name are not grouped.  The first record of each group is kept, in order.
+    pub fn group(self) -> Self {
+        let mut records: Vec<BusinessMatchRecord> = Vec::new();
+        let mut licenses: Vec<HashSet<String>> = Vec::new();
+        let mut index: HashMap<(String, String, Option<String>), usize> = HashMap::new();
+        for record in self.0 {
+            let name = record
+                .business_name()
+                .unwrap_or_else(|| format!("#{}", record.license));
+            let key = (
+                name,
+                record.business_address_label.clone(),
+                record.other_address_label.clone(),
+            );
+            match index.get(&key) {
+                Some(&i) => {
+                    licenses[i].insert(record.license);
+                }
+                None => {
+                    index.insert(key, records.len());
+                    licenses.push(HashSet::from([record.license.clone()]));
+                    records.push(record);
+                }
+            }
+        }
+        for (record, group) in records.iter_mut().zip(licenses) {
+            record.license_count = group.len();
+        }
+        info!("Business records grouped: {} remaining.", records.len());
+        Self(records)
+    }
+
     // /// Writes the contents of `BusinessMatchRecords` to a CSV file at location `title`.  Each element in
     // /// the vector of type [`BusinessMatchRecord`] maps to a row of data on the CSV.
     // pub fn to_csv(&mut self, title: std::path::PathBuf) -> Result<(), std::io::Error> {
@@ -308,6 +456,63 @@ impl IntoCsv<BusinessMatchRecords> for BusinessMatchRecords {
     }
 }
 
+impl IntoJson for BusinessMatchRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+// Default license count for match records written before licenses were grouped.
+fn one() -> usize {
+    1
+}
+
+// Suffixes naming the legal form of a business, removed by [`normalize_business_name`].
+const ENTITY_SUFFIXES: [&str; 15] = [
+    "LLC",
+    "LC",
+    "LLP",
+    "LP",
+    "PLLC",
+    "PC",
+    "INC",
+    "INCORPORATED",
+    "CORP",
+    "CORPORATION",
+    "CO",
+    "COMPANY",
+    "LTD",
+    "LIMITED",
+    "PLC",
+];
+
+/// The `normalize_business_name` function returns `name` in upper case with punctuation removed,
+/// whitespace collapsed and trailing legal forms such as "LLC" and "INC" removed, so that names
+/// of the same business registered on different licenses compare equal.  Periods are dropped
+/// rather than replaced, so "L.L.C." reads as "LLC".
+pub fn normalize_business_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .filter(|c| *c != '.' && *c != '\'')
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>();
+    let mut words = name.split_whitespace().collect::<Vec<&str>>();
+    while words.len() > 1 && words.last().is_some_and(|w| ENTITY_SUFFIXES.contains(w)) {
+        words.pop();
+    }
+    words.join(" ")
+}
+
 /// The `BusinessLicense` struct is designed to deserialize CSV data produced by querying the
 /// EnerGov SQL database for active business licenses.  If the structure of the SQL query changes,
 /// this function will need to change to match the resulting fields in the CSV.
@@ -361,14 +566,14 @@ impl BusinessLicense {
         let mut match_status = MatchStatus::Missing;
         let mut business_match = None;
         let mut subaddress_id = None;
-        if let Some(val) = self.subaddress_identifier.clone() {
-            if !val.is_empty() {
-                // info!("Subaddress not empty: {}", &val);
-                let trim_val = val.trim();
-                if !trim_val.is_empty() {
-                    // info!("Writing subaddress: {}", trim_val);
-                    subaddress_id = Some(trim_val.to_string());
-                }
+        if let Some(val) = self.subaddress_identifier.clone()
+            && !val.is_empty()
+        {
+            // info!("Subaddress not empty: {}", &val);
+            let trim_val = val.trim();
+            if !trim_val.is_empty() {
+                // info!("Writing subaddress: {}", trim_val);
+                subaddress_id = Some(trim_val.to_string());
             }
         }
         let street_name = self.street_name.trim().to_string();
@@ -391,12 +596,14 @@ impl BusinessLicense {
             }
             business_match = Some(BusinessMatchRecord {
                 match_status,
+                address_kind: BusinessAddressKind::Situs,
                 business_address_label: self.label(),
                 company_name: self.company_name(),
                 contact_name: self.contact_name(),
                 business_type: self.business_type(),
                 dba: self.dba(),
                 license: self.license(),
+                license_count: 1,
                 expires: self.expires(),
                 industry_code: self.industry_code(),
                 community: self.community(),
@@ -524,6 +731,36 @@ impl BusinessLicense {
     }
 }
 
+/// The `is_xlsx` function returns true if the file at `path` has an `.xlsx` extension.
+pub fn is_xlsx<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"))
+}
+
+// Returns the text of a spreadsheet cell as it would read in a csv export.  Whole numbers are
+// written without a decimal point, so ZIP codes and phone numbers stored as numbers keep their
+// digits, and dates without a time of day are written as dates.
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Int(value) => value.to_string(),
+        Data::Float(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+            (*value as i64).to_string()
+        }
+        Data::Float(value) => value.to_string(),
+        Data::String(value) | Data::DateTimeIso(value) | Data::DurationIso(value) => {
+            value.trim().to_string()
+        }
+        Data::Bool(value) => value.to_string(),
+        Data::DateTime(value) => match value.as_datetime() {
+            Some(time) if time.time() == chrono::NaiveTime::MIN => time.date().to_string(),
+            Some(time) => time.to_string(),
+            None => value.to_string(),
+        },
+        Data::Error(_) | Data::Empty => String::new(),
+    }
+}
+
 /// The `BusinessLicenses` struct holds a `records` field containing a vector of type
 /// [`BusinessLicense`].
 #[derive(Debug, Clone, Deserialize, Serialize, Deref, DerefMut)]
@@ -536,6 +773,102 @@ impl BusinessLicenses {
         Ok(BusinessLicenses(records))
     }
 
+    /// The `from_xlsx` method reads business licenses from the Excel workbook at `path`, reading
+    /// the sheet named `sheet`, or the first sheet if `None`.  The first row of the sheet holds
+    /// the column headers, which follow the csv export of the EnerGov query.  Each entry in
+    /// `columns` has the form `FIELD=header`, and reads the query field `FIELD`, such as
+    /// "POSTALCODE", from the column named `header`, for workbooks with renamed columns.  Cells
+    /// are read as text, so ZIP codes stored as numbers are not mangled by a csv conversion.
+    /// Rows that fail to deserialize are handled as in [`from_csv`].  Errors if the workbook
+    /// cannot be read, or lacks the sheet or a named column.
+    pub fn from_xlsx<P: AsRef<Path>>(
+        path: P,
+        sheet: Option<&str>,
+        columns: &[String],
+    ) -> Result<Self, AddressErrorKind> {
+        let path = path.as_ref();
+        let spreadsheet =
+            |issue: String| Spreadsheet::new(path.into(), issue, line!(), file!().into());
+        info!("Reading workbook from {:?}", path);
+        let mut workbook =
+            calamine::open_workbook_auto(path).map_err(|source| spreadsheet(source.to_string()))?;
+        let name = match sheet {
+            Some(sheet) => sheet.to_string(),
+            None => workbook
+                .sheet_names()
+                .first()
+                .cloned()
+                .ok_or_else(|| spreadsheet("workbook has no sheets".to_string()))?,
+        };
+        let range = workbook
+            .worksheet_range(&name)
+            .map_err(|source| spreadsheet(format!("sheet '{name}': {source}")))?;
+        let mut rows = range.rows();
+        let mut headers = rows
+            .next()
+            .unwrap_or_default()
+            .iter()
+            .map(cell_text)
+            .collect::<Vec<String>>();
+        for spec in columns {
+            let Some((field, header)) = spec.split_once('=') else {
+                return Err(Builder::new(
+                    format!("expected 'FIELD=header' in '{spec}'"),
+                    "BusinessLicenses".to_string(),
+                    line!(),
+                    file!().to_string(),
+                )
+                .into());
+            };
+            let (field, header) = (field.trim(), header.trim());
+            match headers.iter_mut().find(|h| h.eq_ignore_ascii_case(header)) {
+                Some(column) => *column = field.to_string(),
+                None => {
+                    return Err(
+                        spreadsheet(format!("sheet '{name}' has no column '{header}'")).into(),
+                    );
+                }
+            }
+        }
+        let headers = csv::StringRecord::from(headers);
+        let mut records = Vec::new();
+        let mut dropped = 0;
+        for (row, cells) in rows.enumerate() {
+            let mut record =
+                csv::StringRecord::from(cells.iter().map(cell_text).collect::<Vec<String>>());
+            // Row numbers count the header as the first row, as in a spreadsheet.
+            let mut position = csv::Position::new();
+            position.set_line(row as u64 + 2);
+            record.set_position(Some(position));
+            if record.iter().all(str::is_empty) {
+                continue;
+            }
+            match record.deserialize(Some(&headers)) {
+                Ok(license) => records.push(license),
+                Err(e) => {
+                    ParseError::from_csv_error(path, &e, Some(&headers)).handle()?;
+                    dropped += 1;
+                }
+            }
+        }
+        info!("{} records dropped.", dropped);
+        Ok(BusinessLicenses(records))
+    }
+
+    /// The `from_source` method reads business licenses from an Excel workbook if `path` has an
+    /// `.xlsx` extension, as in [`Self::from_xlsx`], and from a csv file otherwise, ignoring
+    /// `sheet` and `columns`.
+    pub fn from_source<P: AsRef<Path>>(
+        path: P,
+        sheet: Option<&str>,
+        columns: &[String],
+    ) -> Result<Self, AddressErrorKind> {
+        match is_xlsx(&path) {
+            true => Self::from_xlsx(path, sheet, columns),
+            false => Ok(Self::from_csv(path)?),
+        }
+    }
+
     /// Returns the subset of `BusinessLicenses` where the value of the `filter` field is equal to
     /// the test value in `field`.  Currently `filter` can take the value `name`, referring to the
     /// company name.
@@ -583,3 +916,98 @@ impl IntoCsv<BusinessLicenses> for BusinessLicenses {
         to_csv(&mut self.0, path.as_ref().into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GrantsPassSpatialAddresses;
+
+    #[test]
+    fn group_business_records() -> anyhow::Result<()> {
+        assert_eq!(
+            normalize_business_name("Rogue Valley Plumbing, L.L.C."),
+            "ROGUE VALLEY PLUMBING"
+        );
+        assert_eq!(normalize_business_name("  joe's   pizza inc"), "JOES PIZZA");
+        assert_eq!(normalize_business_name("Acme Co., Inc."), "ACME");
+        assert_eq!(normalize_business_name("LLC"), "LLC");
+        let business_path = "data/business_licenses_20240520.csv";
+        let city_path = "data/city_addresses_20241007.csv";
+        let business_addresses = BusinessLicenses::from_csv(business_path)?.deduplicate();
+        let city_addresses = GrantsPassSpatialAddresses::from_csv(city_path)?;
+        let match_records = BusinessMatchRecords::compare(&business_addresses, &city_addresses);
+        let grouped = match_records.clone().group();
+        assert!(grouped.len() <= match_records.len());
+        assert!(grouped.iter().all(|r| r.license_count() >= 1));
+        let matching = grouped.filter("matching");
+        assert_eq!(
+            matching.iter().map(|r| r.license_count()).sum::<usize>(),
+            match_records.filter("matching").len()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn match_business_mailing() -> anyhow::Result<()> {
+        let business_path = "data/business_licenses_20240520.csv";
+        let mailing_path = "data/business_licenses_mailing_sample.csv";
+        let city_path = "data/city_addresses_20241007.csv";
+        let mailing = BusinessLicenses::from_csv(mailing_path)?;
+        let mut situs = BusinessLicenses::from_csv(business_path)?.deduplicate();
+        situs.retain(|r| mailing.iter().any(|m| m.license() == r.license()));
+        assert_eq!(situs.len(), 3);
+        let city_addresses = GrantsPassSpatialAddresses::from_csv(city_path)?;
+        let situs_records = BusinessMatchRecords::compare(&situs, &city_addresses);
+        assert!(situs_records.clone().filter("matching").is_empty());
+        let match_records =
+            BusinessMatchRecords::compare_mailing(&situs, &mailing, &[&city_addresses[..]]);
+        let mailed = match_records.clone().filter("mailing");
+        assert_eq!(mailed.len(), 2);
+        assert!(
+            mailed
+                .iter()
+                .all(|r| r.match_status() == MatchStatus::Matching)
+        );
+        let situs_only = match_records
+            .iter()
+            .filter(|r| r.license() == "BTAX-035085-07-2023")
+            .collect::<Vec<_>>();
+        assert!(!situs_only.is_empty());
+        assert!(
+            situs_only
+                .iter()
+                .all(|r| r.address_kind() == BusinessAddressKind::Situs)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn business_licenses_xlsx() -> anyhow::Result<()> {
+        let path = "data/business_licenses_sample.xlsx";
+        assert!(is_xlsx(path));
+        let columns = vec!["POSTALCODE=Zip Code".to_string()];
+        let licenses = BusinessLicenses::from_xlsx(path, Some("Licenses"), &columns)?;
+        assert_eq!(licenses.len(), 3);
+        // Numeric cells read as the csv export writes them.
+        let csv = BusinessLicenses::from_csv("data/business_licenses_20240520.csv")?;
+        let fields = |licenses: &[BusinessLicense]| -> anyhow::Result<Vec<(String, String)>> {
+            let mut fields = Vec::new();
+            for license in licenses {
+                let value = serde_json::to_value(license)?;
+                fields.push((license.license(), value["ZipCode"].to_string()));
+            }
+            Ok(fields)
+        };
+        assert_eq!(fields(&licenses)?, fields(&csv[..3])?);
+        assert_eq!(fields(&licenses)?[2].1, "97527");
+        let licenses = BusinessLicenses::from_source(path, Some("Licenses"), &columns)?;
+        assert_eq!(licenses[0].license(), csv[0].license());
+        // The first sheet holds no licenses, and the renamed column must be named.
+        assert!(BusinessLicenses::from_xlsx(path, None, &[])?.is_empty());
+        assert!(BusinessLicenses::from_xlsx(path, None, &columns).is_err());
+        assert!(BusinessLicenses::from_xlsx(path, Some("Licenses"), &[])?.is_empty());
+        assert!(BusinessLicenses::from_xlsx(path, Some("Permits"), &columns).is_err());
+        assert!(BusinessLicenses::from_xlsx(path, Some("Licenses"), &["ZIP".to_string()]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,473 @@
+//! The `centerline` module reads road centerlines with address ranges, and checks address points
+//! against the range and parity of the street segment they are assigned to.
+use crate::{
+    Address, AddressErrorKind, Cartesian, FromArcGis, FromShapefile, IntoCsv, IntoJson, Io,
+    StreetNamePostType, StreetNamePreDirectional, crs::geolocated, from_arcgis_service, from_csv,
+    from_shapefile, progress_bar, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::de::Deserializer;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::info;
+
+/// The `Parity` enum describes which address numbers are assigned to one side of a street
+/// segment, following the NENA road centerline standard.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Parity {
+    /// Odd address numbers only.
+    Odd,
+    /// Even address numbers only.
+    Even,
+    /// Both odd and even address numbers.
+    Both,
+    /// No address numbers assigned.
+    #[default]
+    Zero,
+}
+
+impl Parity {
+    /// The `matches` method returns true if the address number `number` is allowed by the parity.
+    pub fn matches(&self, number: i64) -> bool {
+        match self {
+            Self::Odd => number % 2 != 0,
+            Self::Even => number % 2 == 0,
+            Self::Both => true,
+            Self::Zero => false,
+        }
+    }
+
+    /// The `code` method returns the NENA code of the parity: "O", "E", "B" or "Z".
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Odd => "O",
+            Self::Even => "E",
+            Self::Both => "B",
+            Self::Zero => "Z",
+        }
+    }
+
+    /// The `serialize_code` method serializes the parity as its NENA code.  See [`Parity::code`].
+    pub fn serialize_code<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+
+    /// The `deserialize_mixed` method deserializes the parity from the NENA codes "O", "E", "B"
+    /// and "Z", or the full name of the parity.  Empty values deserialize to [`Parity::Both`], so
+    /// that ranges without a recorded parity accept any address number in the range.
+    pub fn deserialize_mixed<'de, D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(de)?;
+        match value.trim().to_uppercase().as_str() {
+            "O" | "ODD" => Ok(Self::Odd),
+            "E" | "EVEN" => Ok(Self::Even),
+            "B" | "BOTH" | "" => Ok(Self::Both),
+            "Z" | "ZERO" => Ok(Self::Zero),
+            _ => Err(serde::de::Error::custom(format!("invalid parity {value}"))),
+        }
+    }
+}
+
+/// The `Centerline` struct represents a road centerline segment with address ranges on each
+/// side, using the field names of the NENA road centerline standard.  Left and right are
+/// relative to the direction the segment is digitized, from the first vertex to the last.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Centerline {
+    /// The `left_from` field holds the address number at the start of the left side.
+    #[serde(rename = "FromAddr_L", deserialize_with = "csv::invalid_option")]
+    pub left_from: Option<i64>,
+    /// The `left_to` field holds the address number at the end of the left side.
+    #[serde(rename = "ToAddr_L", deserialize_with = "csv::invalid_option")]
+    pub left_to: Option<i64>,
+    /// The `right_from` field holds the address number at the start of the right side.
+    #[serde(rename = "FromAddr_R", deserialize_with = "csv::invalid_option")]
+    pub right_from: Option<i64>,
+    /// The `right_to` field holds the address number at the end of the right side.
+    #[serde(rename = "ToAddr_R", deserialize_with = "csv::invalid_option")]
+    pub right_to: Option<i64>,
+    /// The `left_parity` field holds the parity of address numbers on the left side.
+    #[serde(rename = "Parity_L", deserialize_with = "Parity::deserialize_mixed")]
+    pub left_parity: Parity,
+    /// The `right_parity` field holds the parity of address numbers on the right side.
+    #[serde(rename = "Parity_R", deserialize_with = "Parity::deserialize_mixed")]
+    pub right_parity: Parity,
+    /// The `street_name_pre_directional` field represents the street name pre directional
+    /// component of the complete street name.
+    #[serde(
+        rename = "St_PreDir",
+        deserialize_with = "StreetNamePreDirectional::deserialize_mixed"
+    )]
+    pub street_name_pre_directional: Option<StreetNamePreDirectional>,
+    /// The `street_name` field represents the street name component of the complete street name.
+    #[serde(rename = "St_Name")]
+    pub street_name: String,
+    /// The `street_name_post_type` field represents the street name post type component of the
+    /// complete street name.
+    #[serde(
+        rename = "St_PosTyp",
+        deserialize_with = "StreetNamePostType::deserialize_mixed"
+    )]
+    pub street_name_post_type: Option<StreetNamePostType>,
+    /// The `wkt` field holds the segment geometry as a well-known text `LINESTRING` or
+    /// `MULTILINESTRING`, in the same projected coordinates as the address points.
+    #[serde(alias = "WKT", alias = "geometry")]
+    pub wkt: String,
+}
+
+impl Centerline {
+    /// The `label` method returns the abbreviated street name and address ranges of the segment.
+    pub fn label(&self) -> String {
+        let range = |from: Option<i64>, to: Option<i64>| match (from, to) {
+            (Some(from), Some(to)) => format!("{from}-{to}"),
+            _ => "none".to_string(),
+        };
+        format!(
+            "{} (L {}, R {})",
+            self.street(),
+            range(self.left_from, self.left_to),
+            range(self.right_from, self.right_to)
+        )
+    }
+
+    /// The `street` method returns the abbreviated complete street name of the segment.
+    pub fn street(&self) -> String {
+        let mut name = String::new();
+        if let Some(directional) = &self.street_name_pre_directional {
+            name.push_str(&directional.abbreviate());
+            name.push(' ');
+        }
+        name.push_str(&self.street_name);
+        if let Some(post_type) = &self.street_name_post_type {
+            name.push(' ');
+            name.push_str(&post_type.abbreviate());
+        }
+        name
+    }
+
+    /// The `vertices` method parses the `wkt` field into the vertices of each part of the line.
+    /// Returns an empty vector if the geometry cannot be parsed.
+    pub fn vertices(&self) -> Vec<Vec<[f64; 2]>> {
+        let text = self.wkt.trim();
+        let Some(start) = text.find('(') else {
+            return Vec::new();
+        };
+        let inner = text[start..].trim();
+        // Strip the outer parentheses of a multilinestring, leaving "(..), (..)".
+        let inner = if inner.starts_with("((") {
+            &inner[1..inner.len().saturating_sub(1)]
+        } else {
+            inner
+        };
+        inner
+            .split(')')
+            .filter_map(|part| {
+                let part = part.trim_start_matches([',', ' ', '(']);
+                if part.trim().is_empty() {
+                    return None;
+                }
+                part.split(',')
+                    .map(|pair| {
+                        let mut values = pair.split_whitespace().map(str::parse::<f64>);
+                        match (values.next(), values.next()) {
+                            (Some(Ok(x)), Some(Ok(y))) => Some([x, y]),
+                            _ => None,
+                        }
+                    })
+                    .collect::<Option<Vec<[f64; 2]>>>()
+            })
+            .collect()
+    }
+
+    // Returns the range and parity for the left side if `left` is true, or the right side.
+    fn side(&self, left: bool) -> (Option<(i64, i64)>, Parity) {
+        let (from, to, parity) = if left {
+            (self.left_from, self.left_to, self.left_parity)
+        } else {
+            (self.right_from, self.right_to, self.right_parity)
+        };
+        let range = match (from, to) {
+            (Some(from), Some(to)) if from != 0 || to != 0 => Some((from.min(to), from.max(to))),
+            _ => None,
+        };
+        (range, parity)
+    }
+
+    // Returns true if `number` falls within the range and parity of the given side.
+    fn accepts(&self, left: bool, number: i64) -> bool {
+        let (range, parity) = self.side(left);
+        range.is_some_and(|(low, high)| (low..=high).contains(&number)) && parity.matches(number)
+    }
+}
+
+/// The `Centerlines` struct holds a vector of type [`Centerline`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct Centerlines(Vec<Centerline>);
+
+impl IntoCsv<Centerlines> for Centerlines {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl FromShapefile for Centerlines {
+    fn from_shapefile<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        let records = from_shapefile(path)?;
+        Ok(Self(records))
+    }
+}
+
+impl FromArcGis for Centerlines {
+    async fn from_arcgis_service<F, Fut>(url: &str, post: F) -> Result<Self, AddressErrorKind>
+    where
+        F: FnMut(String, String) -> Fut,
+        Fut: Future<Output = Result<String, AddressErrorKind>>,
+    {
+        let records = from_arcgis_service(url, post).await?;
+        Ok(Self(records))
+    }
+}
+
+/// The `RangeViolationKind` enum describes how an address point disagrees with the centerline
+/// segment it is assigned to.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum RangeViolationKind {
+    /// The address number falls outside the range for its side of the segment.
+    #[default]
+    OutOfRange,
+    /// The address number falls within the range for its side, but has the wrong parity.
+    Parity,
+    /// The address number belongs to the range and parity of the opposite side of the segment.
+    WrongSide,
+    /// No centerline segment carries the street name of the address.
+    NoSegment,
+}
+
+geolocated! {
+    /// The `RangeViolation` struct reports an address point that disagrees with the address range
+    /// or parity of its assigned street segment.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct RangeViolation {
+        /// The `label` field holds the address label.
+        pub label: String,
+        /// The `kind` field holds the type of violation.
+        pub kind: RangeViolationKind,
+        /// The `side` field holds the side of the segment the address point lies on, "left" or
+        /// "right".
+        pub side: Option<String>,
+        /// The `segment` field holds the street name and address ranges of the assigned segment.
+        pub segment: Option<String>,
+        /// The `distance` field holds the distance from the address point to the assigned segment,
+        /// in the units of the projected coordinates.
+        pub distance: Option<f64>,
+        /// The `x` field holds the X coordinate of the address point.
+        pub x: f64,
+        /// The `y` field holds the Y coordinate of the address point.
+        pub y: f64,
+    }
+}
+
+/// The `RangeViolations` struct holds a vector of type [`RangeViolation`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct RangeViolations(Vec<RangeViolation>);
+
+// Street name components used to assign address points to centerlines.
+type StreetKey = (
+    Option<StreetNamePreDirectional>,
+    String,
+    Option<StreetNamePostType>,
+);
+// A centerline segment paired with its parsed vertices.
+type Segment<'a> = (&'a Centerline, Vec<Vec<[f64; 2]>>);
+
+impl RangeViolations {
+    /// The `check` method assigns each address in `addresses` to the nearest segment in
+    /// `centerlines` with the same street name, and reports addresses whose number falls outside
+    /// the range or parity for the side of the segment the point lies on.
+    pub fn check<T: Address + Cartesian + Send + Sync>(
+        addresses: &[T],
+        centerlines: &[Centerline],
+    ) -> Self {
+        let mut streets: HashMap<StreetKey, Vec<Segment>> = HashMap::new();
+        for centerline in centerlines {
+            let key = (
+                centerline.street_name_pre_directional,
+                centerline.street_name.to_uppercase(),
+                centerline.street_name_post_type,
+            );
+            streets
+                .entry(key)
+                .or_default()
+                .push((centerline, centerline.vertices()));
+        }
+        info!("Centerlines indexed: {} streets.", streets.len());
+        let records = addresses
+            .par_iter()
+            .progress_with(progress_bar(addresses.len(), "Checking address ranges."))
+            .filter_map(|address| {
+                let key = (
+                    *address.directional(),
+                    address.street_name().to_uppercase(),
+                    *address.street_type(),
+                );
+                let mut violation = RangeViolation {
+                    label: address.label(),
+                    x: address.x(),
+                    y: address.y(),
+                    ..Default::default()
+                };
+                let point = [address.x(), address.y()];
+                let Some((centerline, distance, left)) = streets.get(&key).and_then(|segments| {
+                    segments
+                        .iter()
+                        .filter_map(|(c, parts)| {
+                            nearest(parts, point).map(|(distance, left)| (*c, distance, left))
+                        })
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                }) else {
+                    violation.kind = RangeViolationKind::NoSegment;
+                    return Some(violation);
+                };
+                let number = address.number();
+                if centerline.accepts(left, number) {
+                    return None;
+                }
+                let (range, _) = centerline.side(left);
+                violation.kind = if centerline.accepts(!left, number) {
+                    RangeViolationKind::WrongSide
+                } else if range.is_some_and(|(low, high)| (low..=high).contains(&number)) {
+                    RangeViolationKind::Parity
+                } else {
+                    RangeViolationKind::OutOfRange
+                };
+                violation.side = Some(if left { "left" } else { "right" }.to_string());
+                violation.segment = Some(centerline.label());
+                violation.distance = Some(distance);
+                Some(violation)
+            })
+            .collect::<Vec<RangeViolation>>();
+        info!("{} range violations found.", records.len());
+        Self(records)
+    }
+
+    /// The `filter` method returns the subset of records that match the filter.  Current values
+    /// for the `filter` field include "range", "parity", "side" and "segment", which filter by
+    /// the kind of violation.
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "range" => self.retain(|r| r.kind == RangeViolationKind::OutOfRange),
+            "parity" => self.retain(|r| r.kind == RangeViolationKind::Parity),
+            "side" => self.retain(|r| r.kind == RangeViolationKind::WrongSide),
+            "segment" => self.retain(|r| r.kind == RangeViolationKind::NoSegment),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+impl IntoCsv<RangeViolations> for RangeViolations {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for RangeViolations {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+// Returns the distance from `point` to the nearest part of the line, and whether the point lies
+// to the left of the line at that location.  Returns `None` for a line without segments.
+fn nearest(parts: &[Vec<[f64; 2]>], point: [f64; 2]) -> Option<(f64, bool)> {
+    parts
+        .iter()
+        .flat_map(|part| part.windows(2))
+        .map(|w| {
+            let (a, b) = (w[0], w[1]);
+            let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+            let length = dx * dx + dy * dy;
+            let t = if length > 0.0 {
+                (((point[0] - a[0]) * dx + (point[1] - a[1]) * dy) / length).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let (px, py) = (a[0] + t * dx, a[1] + t * dy);
+            let distance = ((point[0] - px).powi(2) + (point[1] - py).powi(2)).sqrt();
+            let cross = dx * (point[1] - a[1]) - dy * (point[0] - a[0]);
+            (distance, cross > 0.0)
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoBin, SpatialAddresses};
+
+    #[test]
+    fn centerline_ranges() -> anyhow::Result<()> {
+        let centerlines = Centerlines::from_csv("data/centerline_sample.csv")?;
+        assert_eq!(centerlines.len(), 2);
+        let shapes = Centerlines::from_shapefile("data/centerline_sample.shp")?;
+        assert_eq!(shapes.len(), 2);
+        assert_eq!(shapes[0].vertices(), centerlines[0].vertices());
+        assert_eq!(shapes[1].right_parity, Parity::Zero);
+
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let place = |number: i64, x: f64, y: f64| {
+            let mut address = city_addresses[0].clone();
+            *address.number_mut() = number;
+            *address.number_suffix_mut() = None;
+            *address.directional_mut() = Some(StreetNamePreDirectional::SOUTHEAST);
+            *address.street_name_mut() = "K".to_string();
+            *address.street_type_mut() = Some(StreetNamePostType::STREET);
+            *address.subaddress_id_mut() = None;
+            address.x = x;
+            address.y = y;
+            address
+        };
+        let mut unnamed = place(110, 10.0, -10.0);
+        *unnamed.street_name_mut() = "NO SUCH".to_string();
+        let addresses = vec![
+            place(120, 20.0, -10.0),
+            place(121, 20.0, 10.0),
+            place(250, 50.0, -10.0),
+            place(131, 30.0, -10.0),
+            place(250, 150.0, 10.0),
+            unnamed,
+        ];
+        let violations = RangeViolations::check(&addresses, &centerlines);
+        let kinds = violations.iter().map(|v| v.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                RangeViolationKind::OutOfRange,
+                RangeViolationKind::WrongSide,
+                RangeViolationKind::Parity,
+                RangeViolationKind::NoSegment,
+            ]
+        );
+        assert_eq!(violations[0].side, Some("right".to_string()));
+        Ok(())
+    }
+}
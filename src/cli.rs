@@ -1,51 +1,117 @@
-use clap::Parser;
+use crate::{
+    AddressErrorKind, Arguments, Builder, Compression, RunConfig, is_service, source_files,
+};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
 
-/// The `Cli` struct provides the command-line interface for the `address` library.
-#[derive(Parser)]
+/// The `Cli` struct provides the command-line interface for the `address` library.  The
+/// arguments serialize for the provenance of output files, omitting connection strings and access
+/// tokens.  See [`crate::Provenance`].
+#[derive(Parser, Clone, Debug, serde::Serialize)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
-    /// The `command` field specifies the command for the program to run. Currently accepts
-    /// 'compare', 'drift', 'filter', 'save', 'orphan_streets' and 'lexisnexis' as values.
-    ///
-    /// * filter
-    ///   * takes [`crate::MatchRecords`] as input
-    ///   * takes [`crate::BusinessMatchRecords`] with the `-b` flag
+    /// The `command` field specifies the command for the program to run.  See [`Command`].
+    #[command(subcommand)]
+    pub command: Command,
+    /// The `source` field specifies the path the source address file.  Spatial address formats
+    /// accept a csv file, a point shapefile with a `.shp` extension, or the url of an ArcGIS
+    /// Feature Service layer.  See [`crate::FromArcGis`].  A directory or glob pattern of csv
+    /// files is read as one dataset, see [`crate::source_files`].  When a configuration file is
+    /// used, the source may also name a dataset from the configuration.
     #[arg(
-        short = 'c',
+        global = true,
+        short = 's',
         long,
-        help = "Command to execute.  Valid commands include 'compare', 'drift', 'filter', 'orphan_streets', 'lexisnexis' and 'save'"
+        help = "Path to source addresses (csv, directory or glob of csv, shp or feature service url)."
     )]
-    pub command: String,
-    /// The `source` field specifies the path the source address file.
-    #[arg(short = 's', long, help = "Path to source addresses.")]
-    pub source: std::path::PathBuf,
+    pub source: Option<std::path::PathBuf>,
     /// The `source_type` field contains a designator for the address source.  Currently accepts
-    /// 'grants_pass' and 'josephine_county' as values.
-    #[arg(short = 'k', long, help = "Address format for source.")]
+    /// 'grants_pass', 'josephine_county', 'jackson_county', 'mapped' and 'data' as values, where
+    /// 'mapped' reads a csv file using the column map at `mapping`, and 'data' reads addresses
+    /// written by the 'save' command.  The 'compare' command also accepts
+    /// 'postgis', reading the table named by the source from the database at `dsn`, and 'parquet',
+    /// reading common addresses from a Parquet file.
+    #[arg(global = true, short = 'k', long, help = "Address format for source.")]
     pub source_type: Option<String>,
-    /// The `target` field specifies the path the target address file.
-    #[arg(short = 't', long, help = "Path to target addresses.")]
+    /// The `target` field specifies the path the target address file.  Spatial address formats
+    /// accept a csv file, a point shapefile with a `.shp` extension, or the url of an ArcGIS
+    /// Feature Service layer.  See [`crate::FromArcGis`].  When a configuration file is used, the
+    /// target may also name a dataset from the configuration.
+    #[arg(
+        global = true,
+        short = 't',
+        long,
+        help = "Path to target addresses (csv, shp or feature service url)."
+    )]
     pub target: Option<std::path::PathBuf>,
     /// The `target_type` field contains a designator for the address target.  Currently accepts
-    /// 'grants_pass' and 'josephine_county' as values.
-    #[arg(short = 'z', long, help = "Address format for target.")]
+    /// 'grants_pass', 'josephine_county', 'jackson_county', 'mapped' and 'data' as values, where
+    /// 'mapped' reads a csv file using the column map at `mapping`, and 'data' reads addresses
+    /// written by the 'save' command.  The 'compare' command also accepts
+    /// 'postgis', reading the table named by the target from the database at `dsn`, and 'parquet',
+    /// reading common addresses from a Parquet file.
+    #[arg(global = true, short = 'z', long, help = "Address format for target.")]
     pub target_type: Option<String>,
     /// The `filter` field contains a value to filter the target data.  Currently accepts
     /// `missing`, `divergent`, `matching`, `subaddress`, `floor`, `building` and `status` as
     /// values.
-    #[arg(short = 'f', long, help = "Filter records by value.")]
+    #[arg(global = true, short = 'f', long, help = "Filter records by value.")]
     pub filter: Option<String>,
-    /// The `duplicates` flag instructs the program to search for duplicate addresses.
+    /// The `quiet` flag hides the progress bars drawn during long-running steps, such as reading
+    /// csv files and comparing addresses.  See [`crate::set_quiet`].
     #[arg(
-        short = 'd',
+        global = true,
+        short = 'q',
         long,
-        help = "Search addresses for duplicates.",
+        help = "Hide progress bars.",
         default_value = "false",
         default_missing_value = "true"
     )]
-    pub duplicates: bool,
+    pub quiet: bool,
+    /// The `on_error` field selects how csv readers respond to rows that fail to parse.  Accepts
+    /// 'skip', which drops the row, 'abort', which stops with an error, and 'collect', which drops
+    /// the row and writes it to `parse_errors.csv` beside the output file, with the row number,
+    /// column and reason, when any rows fail.  Defaults to 'skip'.  See [`crate::OnError`].
+    #[arg(
+        global = true,
+        long,
+        help = "Response to rows that fail to parse.  Valid values include 'skip', 'abort' and 'collect'."
+    )]
+    pub on_error: Option<String>,
+    /// The `compress` field selects the compression of csv output, either 'gzip' or 'zstd'.  The
+    /// extension of the compression is appended to the output path, as `output.csv.gz`.  Output
+    /// paths ending in `.gz` or `.zst` are compressed without the flag, and csv input is
+    /// decompressed by its extension.  See [`crate::Compression`].
+    #[arg(
+        global = true,
+        long,
+        help = "Compression of csv output.  Valid values include 'gzip' and 'zstd'."
+    )]
+    pub compress: Option<String>,
+    /// The `select` field holds the names of the columns kept in csv output, as a
+    /// comma-separated list, in the order written.  Only valid with the 'csv' format.  See
+    /// [`crate::OutputColumns`].
+    #[arg(
+        global = true,
+        long,
+        value_delimiter = ',',
+        help = "Columns kept in csv output, in order."
+    )]
+    pub select: Vec<String>,
+    /// The `rename` field holds the columns of csv output written under another header, each
+    /// given as `column=HEADER`, as a comma-separated list.  Only valid with the 'csv' format.
+    /// See [`crate::OutputColumns`].
+    #[arg(
+        global = true,
+        long,
+        value_delimiter = ',',
+        help = "Columns of csv output renamed, as 'column=HEADER'."
+    )]
+    pub rename: Vec<String>,
     /// The `output` field specifies the path for the output file.
     #[arg(
+        global = true,
         short = 'o',
         default_value = "output.csv",
         default_missing_value = "output.csv",
@@ -53,20 +119,1678 @@ pub struct Cli {
         help = "Path for output records."
     )]
     pub output: std::path::PathBuf,
-    /// The `business` flag indicates the source addresses are from business licenses.
+    /// The `fuzzy` field sets a similarity threshold between 0 and 1 for fuzzy matching of street
+    /// names in the 'compare' and 'geocode' commands.  When absent, street names must match
+    /// exactly for 'compare', and 'geocode' uses a threshold of 0.9.
     #[arg(
-        short = 'b',
-        default_value = "false",
-        default_missing_value = "true",
-        long,
-        help = "Flag for business licenses."
-    )]
-    pub business: bool,
-    /// The `alternate` field specifies an alternate target path for addresses.
-    #[arg(short = 'a', long, help = "Alternate target for search addresses.")]
-    pub alternate: Option<std::path::PathBuf>,
-    /// The `alternate_type` field contains a designator for the target addresses.  Currently
-    /// accepts 'grants_pass' and 'josephine_county'.
-    #[arg(short = 'y', long, help = "Address format for alternate target.")]
-    pub alternate_type: Option<String>,
+        global = true,
+        long,
+        help = "Similarity threshold (0-1) for fuzzy street name matching."
+    )]
+    pub fuzzy: Option<f64>,
+    /// The `radius` field limits comparisons to addresses within the given distance, using a
+    /// spatial index to select candidates.  The radius is in meters for every command.  Commands
+    /// measuring between projected coordinates, such as 'drift', convert it to the units of
+    /// `source_epsg`, and take the coordinates to be meters when `source_epsg` is unset.  For the
+    /// 'reverse' command, the radius is the maximum distance to the nearest address.  For the
+    /// 'nearest' command, the radius is the search distance for neighbors.  For the 'access'
+    /// command, the radius is the search distance for access points.  When absent, these
+    /// commands use their default distances, in the units of the projected coordinates.
+    #[arg(
+        global = true,
+        short = 'r',
+        long,
+        help = "Search radius for candidate addresses, in meters."
+    )]
+    pub radius: Option<f64>,
+    /// The `rules` field holds an optional path to a TOML file of standardization rules, applied
+    /// to 'josephine_county' addresses in place of the built-in rules.  See
+    /// [`crate::Standardizer`].
+    #[arg(
+        global = true,
+        long,
+        help = "Path to a TOML file of standardization rules."
+    )]
+    pub rules: Option<std::path::PathBuf>,
+    /// The `aliases` field holds an optional path to a csv table of street name aliases, with the
+    /// columns 'street' and 'alias'.  The 'compare' and 'geocode' commands treat streets named in
+    /// the same group of aliases as the same street.  See [`crate::StreetAliases`].
+    #[arg(
+        global = true,
+        long,
+        help = "Path to a csv table of street name aliases."
+    )]
+    pub aliases: Option<std::path::PathBuf>,
+    /// The `grid` field holds an optional path to a map-book grid polygon layer, read from a
+    /// GeoJSON file or polygon shapefile.  The 'enrich' command adds the grid reference of each
+    /// address as the 'grid' column, and the 'geocode' and 'serve' commands report the grid
+    /// reference of each located point.  See [`crate::MapGrid`].
+    #[arg(
+        global = true,
+        long,
+        help = "Path to the map grid polygons (GeoJSON or shp)."
+    )]
+    pub grid: Option<std::path::PathBuf>,
+    /// The `grid_field` field holds the name of the attribute holding the grid reference of each
+    /// cell.  Defaults to [`crate::GRID_FIELD`].
+    #[arg(
+        global = true,
+        long,
+        help = "Attribute of the map grid polygons holding the grid reference."
+    )]
+    pub grid_field: Option<String>,
+    /// The `mapping` field holds an optional path to a TOML column map, used to read 'mapped'
+    /// source and target addresses from a csv file with an arbitrary schema.  See
+    /// [`crate::ColumnMap`].
+    #[arg(
+        global = true,
+        long,
+        help = "Path to a TOML column map for 'mapped' addresses."
+    )]
+    pub mapping: Option<std::path::PathBuf>,
+    /// The `jobs` field limits the number of threads used for parallel comparisons.  When absent,
+    /// the program uses one thread per available core.
+    #[arg(
+        global = true,
+        short = 'j',
+        long,
+        help = "Number of threads for parallel comparison."
+    )]
+    pub jobs: Option<usize>,
+    /// The `format` field specifies the file format for the output records.  Currently accepts
+    /// 'csv', 'geojson', 'json', 'ndjson', 'parquet', 'postgis' and 'sqlite' as values.  JSON
+    /// output writes the records as a single array, and 'ndjson' writes one record per line, for
+    /// every command that writes records.  GeoJSON output is
+    /// available for spatial results from the 'compare', 'drift', 'filter', 'geocode', 'reverse'
+    /// and 'save' commands.  Parquet output is available for the 'compare' and 'save' commands.
+    /// PostGIS output is available for the 'compare' and 'save' commands, and writes to the table
+    /// named by `output` in the database at `dsn`.  SQLite output is available for the 'save'
+    /// command, and writes to a table named by the source type in the database file at `output`.
+    #[arg(
+        global = true,
+        long,
+        default_value = "csv",
+        help = "Output format.  Valid formats include 'csv', 'geojson', 'json', 'ndjson', 'parquet', 'postgis' and 'sqlite'."
+    )]
+    pub format: String,
+    /// The `dsn` field holds the connection string for a PostGIS database, such as
+    /// "postgresql://user@host/gis".  See [`crate::IntoPostgis`].
+    #[arg(
+        global = true,
+        long,
+        help = "Connection string for a PostGIS database."
+    )]
+    #[serde(skip)]
+    pub dsn: Option<String>,
+    /// The `source_epsg` field holds the EPSG code of the coordinate reference system for the
+    /// projected coordinates of the source addresses, such as 2270 for Oregon South in feet.  When
+    /// both `source_epsg` and `target_epsg` are set, the 'drift' command reprojects the target
+    /// addresses into the system of the source, so distances are in the units of the source.
+    /// When set, spatial outputs carry WGS84 latitude and longitude computed from the projected
+    /// coordinates, and GeoJSON output from the 'drift' command requires it.  See
+    /// [`crate::EPSG_CODES`] for supported systems.
+    #[arg(global = true, long, help = "EPSG code of the source coordinates.")]
+    pub source_epsg: Option<u32>,
+    /// The `target_epsg` field holds the EPSG code of the coordinate reference system for the
+    /// projected coordinates of the target addresses.
+    #[arg(global = true, long, help = "EPSG code of the target coordinates.")]
+    pub target_epsg: Option<u32>,
+    /// The `config` field specifies the path to a TOML configuration file.  Values in the
+    /// configuration apply to any flag not passed on the command line.  See [`RunConfig`].
+    #[arg(global = true, long, help = "Path to a TOML configuration file.")]
+    pub config: Option<std::path::PathBuf>,
+}
+
+/// The `Command` enum selects the command for the program to run.  Arguments specific to a
+/// command follow the command name, and shared arguments may appear before or after it.
+#[derive(Subcommand, Clone, Debug, PartialEq, derive_more::Display, serde::Serialize)]
+#[command(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    /// Summarizes the editing of the source, a 'grants_pass' dataset, writing
+    /// [`crate::EditorActivities`] with the number of features each user created or last edited
+    /// during each week, from the editor tracking fields.  Filtered by user with `-f`.
+    #[display("activity")]
+    Activity {
+        /// The `since` field holds the first date counted, as "2024-10-01".
+        #[arg(long, help = "First date counted (YYYY-MM-DD).")]
+        since: Option<chrono::NaiveDate>,
+        /// The `until` field holds the last date counted, as "2024-10-31".
+        #[arg(long, help = "Last date counted (YYYY-MM-DD).")]
+        until: Option<chrono::NaiveDate>,
+    },
+    /// Takes [`crate::ParcelPoints`] as the source, and addresses as the target, and writes
+    /// [`crate::AddressSuggestions`].
+    #[display("assign")]
+    Assign {
+        /// The `registry` field holds an optional path to a registry of retired addresses, as
+        /// written by the 'retired' command.  Proposed numbers matching an address retired within
+        /// `years` are noted.  See [`crate::RetiredAddresses`].
+        #[arg(long, help = "Path to the registry of retired addresses.")]
+        registry: Option<std::path::PathBuf>,
+        /// The `years` field holds the number of years after retirement during which an address
+        /// may not be reissued.  Defaults to [`crate::RETIRED_YEARS`].
+        #[arg(long, help = "Years before a retired address may be reissued.")]
+        years: Option<u32>,
+    },
+    /// Takes a point layer of assets as the source, such as fire hydrants or utility valves, and
+    /// attaches the nearest valid target address to each asset as its location description,
+    /// writing [`crate::AssetAssignments`] filtered by confidence with `-f`.  Assets are read as
+    /// [`crate::ReversePoints`] from a csv file, a point shapefile or a feature service.  The
+    /// search radius defaults to [`crate::ASSET_DISTANCE`].
+    #[display("assign_nearest")]
+    AssignNearest {
+        /// The `confident_distance` field holds the distance within which the nearest address is
+        /// assigned with high confidence.  Defaults to [`crate::ASSET_CONFIDENT_DISTANCE`].
+        #[arg(
+            long,
+            help = "Distance within which the nearest address is assigned with high confidence."
+        )]
+        confident_distance: Option<f64>,
+    },
+    /// Geocodes a truth set of addresses with known coordinates, as [`crate::TruthRecords`],
+    /// against the target addresses, writing [`crate::BenchRecords`] filtered by match type or
+    /// "correct" and "incorrect" with `-f`.  The geocoder uses the `fuzzy` threshold.  A
+    /// [`crate::BenchSummary`] of precision, recall and positional error by match type is printed
+    /// to stderr.
+    #[display("bench")]
+    Bench {
+        /// The `tolerance` field holds the distance in meters from the true location within which
+        /// a geocoded address counts as correct.  Defaults to [`crate::BENCH_TOLERANCE`].
+        #[arg(
+            long,
+            help = "Distance in meters from the true location counted as correct."
+        )]
+        tolerance: Option<f64>,
+        /// The `stats` field holds an optional path for the benchmark summary, written as JSON.
+        #[arg(long, help = "Path for the benchmark summary in JSON.")]
+        stats: Option<std::path::PathBuf>,
+    },
+    /// Matches business licenses in the source against the target addresses, writing
+    /// [`crate::BusinessMatchRecords`].  Licenses are read from a csv export, or from an xlsx
+    /// workbook selected with `--sheet` and `--column`.
+    #[display("business")]
+    Business {
+        /// The `alternate` field specifies an alternate target path for addresses.
+        #[arg(short = 'a', long, help = "Alternate target for search addresses.")]
+        alternate: Option<std::path::PathBuf>,
+        /// The `alternate_type` field contains a designator for the target addresses.  Accepts
+        /// 'grants_pass', 'josephine_county', 'jackson_county', 'mapped' and 'common'.
+        #[arg(short = 'y', long, help = "Address format for alternate target.")]
+        alternate_type: Option<String>,
+        /// The `mailing` field holds an optional path to the mailing addresses of the business
+        /// licenses, in the same format as the source.  Licenses whose situs address has no exact
+        /// match are matched by their mailing address, noted in the `address_kind` column.  See
+        /// [`crate::BusinessMatchRecords::compare_mailing`].
+        #[arg(long, help = "Path to mailing addresses for business licenses.")]
+        mailing: Option<std::path::PathBuf>,
+        /// The `sheet` field holds the name of the sheet read from business licenses in an Excel
+        /// workbook.  Defaults to the first sheet.  See [`crate::BusinessLicenses::from_xlsx`].
+        #[arg(long, help = "Sheet of business licenses in an xlsx workbook.")]
+        sheet: Option<String>,
+        /// The `columns` field holds the columns read from business licenses in an Excel
+        /// workbook, each given as `FIELD=header`, reading the field `FIELD` of the EnerGov query
+        /// from the column named `header`.  See [`crate::BusinessLicenses::from_xlsx`].
+        #[arg(
+            long = "column",
+            help = "Workbook column for a license field as 'FIELD=header', repeated for each column."
+        )]
+        columns: Vec<String>,
+    },
+    /// Compares the source addresses against the target addresses, writing
+    /// [`crate::MatchRecords`].
+    #[display("compare")]
+    Compare {
+        /// The `business` flag indicates the source addresses are from business licenses, writing
+        /// [`crate::BusinessMatchRecords`] in place of match records.
+        #[arg(
+            short = 'b',
+            default_value = "false",
+            default_missing_value = "true",
+            long,
+            help = "Flag for business licenses."
+        )]
+        business: bool,
+        /// The `alternate` field holds further target paths, repeated for each target.  Business
+        /// licenses not matching the target are compared against each alternate target in turn.
+        /// Other addresses are compared against the target and every alternate target, writing
+        /// [`crate::TargetMatchRecords`] that report the targets holding a match for each address.
+        /// Targets are named for their file stem in the output.
+        #[arg(
+            short = 'a',
+            long,
+            help = "Alternate target for search addresses, repeated for each target."
+        )]
+        alternate: Vec<std::path::PathBuf>,
+        /// The `alternate_type` field contains a designator for the alternate target addresses,
+        /// repeated in the order of the alternate targets.  A single designator applies to every
+        /// alternate target.  Alternate targets named from the configuration file take the address
+        /// format of the dataset.
+        #[arg(short = 'y', long, help = "Address format for alternate target.")]
+        alternate_type: Vec<String>,
+        /// The `mailing` field holds an optional path to the mailing addresses of the business
+        /// licenses, in the same format as the source.  Licenses whose situs address has no exact
+        /// match are matched by their mailing address, noted in the `address_kind` column.  See
+        /// [`crate::BusinessMatchRecords::compare_mailing`].
+        #[arg(long, help = "Path to mailing addresses for business licenses.")]
+        mailing: Option<std::path::PathBuf>,
+        /// The `sheet` field holds the name of the sheet read from business licenses in an Excel
+        /// workbook.  Defaults to the first sheet.  See [`crate::BusinessLicenses::from_xlsx`].
+        #[arg(long, help = "Sheet of business licenses in an xlsx workbook.")]
+        sheet: Option<String>,
+        /// The `columns` field holds the columns read from business licenses in an Excel
+        /// workbook, each given as `FIELD=header`, reading the field `FIELD` of the EnerGov query
+        /// from the column named `header`.  See [`crate::BusinessLicenses::from_xlsx`].
+        #[arg(
+            long = "column",
+            help = "Workbook column for a license field as 'FIELD=header', repeated for each column."
+        )]
+        columns: Vec<String>,
+        /// The `incremental` flag directs the command to reuse the match records of the previous
+        /// run for unchanged addresses.  The records are cached in a binary file next to the
+        /// output file, with the extension '.cache' appended.  See [`crate::MatchCache`].
+        #[arg(
+            short = 'i',
+            default_value = "false",
+            default_missing_value = "true",
+            long,
+            help = "Flag to reuse match records from the previous comparison."
+        )]
+        incremental: bool,
+        /// The `resume` flag directs the command to save the match cache every
+        /// [`crate::CHECKPOINT_INTERVAL`] addresses while comparing, so that an interrupted run
+        /// resumes from the last checkpoint when run again.  Implies `incremental`.  See
+        /// [`crate::MatchRecords::compare_resumable`].
+        #[arg(
+            default_value = "false",
+            default_missing_value = "true",
+            long,
+            help = "Flag to checkpoint the comparison, resuming an interrupted run."
+        )]
+        resume: bool,
+        /// The `accept` field holds the composite score between 0 and 1 at or above which a
+        /// candidate address matches.  Defaults to 1, accepting only candidates where every
+        /// identifying field agrees.  See [`crate::MatchScoring`].
+        #[arg(long, help = "Score (0-1) at or above which a candidate matches.")]
+        accept: Option<f64>,
+        /// The `review` field holds the composite score between 0 and 1 at or above which an
+        /// unmatched address is reported for review against its best candidate.  Candidates
+        /// scoring below the threshold are rejected.  If unset, there is no review band.
+        #[arg(long, help = "Score (0-1) at or above which a candidate needs review.")]
+        review: Option<f64>,
+        /// The `blocking` field names the blocking strategy used to select the candidates for each
+        /// source address, either 'zip_soundex', 'zip_metaphone' or 'street_bucket', with an optional bucket width
+        /// as in 'street_bucket:50'.  See [`crate::Blocking`].  Ignored by incremental runs.
+        #[arg(
+            long,
+            help = "Blocking strategy: 'zip_soundex', 'zip_metaphone' or 'street_bucket[:width]'."
+        )]
+        blocking: Option<String>,
+        /// The `phonetic` flag directs the command to match street names that fall below the
+        /// fuzzy threshold but sound alike, such as 'THOMPSON' and 'THOMSEN'.  Phonetic matches
+        /// are reported as divergent, with a street name mismatch.  See [`crate::metaphone`].
+        #[arg(
+            default_value = "false",
+            default_missing_value = "true",
+            long,
+            help = "Flag to match street names that sound alike."
+        )]
+        phonetic: bool,
+        /// The `include` field names fields that must agree for addresses to match, beyond the
+        /// defaults, as a comma-separated list of 'zip', 'postal_community', 'subaddress' and
+        /// 'status'.  See [`crate::MatchConfig`].
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Fields that must agree to match: zip, postal_community, subaddress, status."
+        )]
+        include: Vec<String>,
+        /// The `exclude` field names fields whose differences are reported as mismatches rather
+        /// than preventing a match, in the same form as `include`.  Excluding 'zip' matches on
+        /// the civic address, reporting zip code differences on divergent records.
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Fields reported as mismatches rather than preventing a match."
+        )]
+        exclude: Vec<String>,
+        /// The `normalize` flag directs the command to compare text fields ignoring case and
+        /// extra whitespace, so "RIVERSIDE " matches "Riverside".  The values written are
+        /// unchanged.  See [`crate::normalize_text`].
+        #[arg(
+            default_value = "false",
+            default_missing_value = "true",
+            long,
+            help = "Flag to compare text fields ignoring case and extra whitespace."
+        )]
+        normalize: bool,
+        /// The `bidirectional` flag directs the command to also compare the target against the
+        /// source, writing a single reconciliation report that lists each address as found in
+        /// both, only in the source, only in the target, or in both with differences.  The filter
+        /// accepts 'both', 'divergent', 'only_source', 'only_target' and 'unmatched'.  See
+        /// [`crate::ReconcileRecords`].
+        #[arg(
+            default_value = "false",
+            default_missing_value = "true",
+            long,
+            help = "Flag to compare in both directions, writing a reconciliation report."
+        )]
+        bidirectional: bool,
+        /// The `watch` flag directs the command to run again each time the source or target files
+        /// change, until interrupted.  The output file holds the latest result, and a copy of
+        /// each result is kept beside it with the time of the run appended to the file name.  See
+        /// [`crate::watch_paths`].
+        #[arg(
+            short = 'w',
+            default_value = "false",
+            default_missing_value = "true",
+            long,
+            help = "Flag to run again when the input files change."
+        )]
+        watch: bool,
+    },
+    /// Detects the changes between two vintages of the same csv layer, writing
+    /// [`crate::LayerChanges`] for the records added, deleted and modified, with a row for each
+    /// modified field, filtered by change with `-f`.  Records are paired by the `key` column.
+    /// Unlike 'compare', no address matching is done, so any layer can be audited.
+    #[display("diff")]
+    Diff {
+        /// The `old` field holds the path to the earlier vintage.  Defaults to the source.
+        #[arg(long, help = "Path to the earlier vintage (csv).")]
+        old: Option<std::path::PathBuf>,
+        /// The `new` field holds the path to the later vintage.
+        #[arg(long, help = "Path to the later vintage (csv).")]
+        new: Option<std::path::PathBuf>,
+        /// The `key` field holds the name of the column pairing the records of the vintages,
+        /// matched ignoring case and underscores.  Defaults to [`crate::DIFF_KEY`].
+        #[arg(long, help = "Column pairing the records of the vintages.")]
+        key: Option<String>,
+        /// The `ignore` field holds the names of columns left out of the comparison, such as
+        /// edit timestamps.
+        #[arg(
+            long,
+            help = "Column left out of the comparison, repeated for each column."
+        )]
+        ignore: Vec<String>,
+    },
+    /// Measures the distance between matching source and target addresses, writing
+    /// [`crate::SpatialAddresses`] that drift beyond the threshold, with the distance and
+    /// bearing of each displacement.
+    #[display("drift")]
+    Drift {
+        /// The `threshold` field holds the distance above which the command reports an address,
+        /// in the units of the report.  Defaults to 99.
+        #[arg(long, help = "Minimum drift distance to report.")]
+        threshold: Option<f64>,
+        /// The `min_threshold` field holds the distance at or below which a displacement is
+        /// taken as jitter, in the units of the report.  Jitter is left out of the summary
+        /// statistics, the report and the output.  Defaults to 0, keeping every pair.
+        #[arg(
+            long,
+            help = "Drift distance at or below which displacement is ignored."
+        )]
+        min_threshold: Option<f64>,
+        /// The `stats` field holds an optional path for the summary statistics, written as JSON.
+        /// The summary is always printed to stderr.  See [`crate::DriftSummary`].
+        #[arg(long, help = "Path for drift summary statistics in JSON.")]
+        stats: Option<std::path::PathBuf>,
+        /// The `report` field holds an optional path for a drift report, with summary tables,
+        /// percentiles and a histogram of distances.  Reports with the extension 'html' are
+        /// written as HTML, and other reports as markdown.  The histogram is also printed to
+        /// stderr.  See [`crate::DriftReport`].
+        #[arg(long, help = "Path for a drift report in markdown or HTML.")]
+        report: Option<std::path::PathBuf>,
+        /// The `clusters` field holds an optional path for the clusters of nearby addresses
+        /// displaced alike, written as csv, or as JSON with the 'json' extension.  Clusters are
+        /// found among all matching addresses, including those below the threshold.  See
+        /// [`crate::DriftClusters`].
+        #[arg(long, help = "Path for clusters of systematic drift.")]
+        clusters: Option<std::path::PathBuf>,
+        /// The `units` field specifies the units of the distances reported.  Currently accepts
+        /// 'feet' and 'meters' as values.  The units of the projected coordinates follow from
+        /// `source_epsg` when set, and are otherwise taken to be meters.  When absent, distances
+        /// are reported in the units of the projected coordinates.
+        #[arg(
+            long,
+            help = "Units for drift distances.  Valid units include 'feet' and 'meters'."
+        )]
+        units: Option<String>,
+        /// The `stacks` flag groups source addresses sharing a location into
+        /// [`crate::Stacks`], measuring one delta per structure from the stack to the matching
+        /// target units, rather than one delta per unit.
+        #[arg(
+            default_value = "false",
+            default_missing_value = "true",
+            long,
+            help = "Flag to measure drift per structure for stacked units."
+        )]
+        stacks: bool,
+        /// The `watch` flag directs the command to run again each time the source or target files
+        /// change, until interrupted.  The output file holds the latest result, and a copy of
+        /// each result is kept beside it with the time of the run appended to the file name.  See
+        /// [`crate::watch_paths`].
+        #[arg(
+            short = 'w',
+            default_value = "false",
+            default_missing_value = "true",
+            long,
+            help = "Flag to run again when the input files change."
+        )]
+        watch: bool,
+    },
+    /// Writes the source addresses that share a label with another address.
+    #[display("duplicates")]
+    Duplicates {
+        /// The `near` flag extends the command to group near-duplicate addresses, which differ
+        /// only in formatting or by a one-character typo in the street name.
+        #[arg(
+            long,
+            help = "Include near duplicates when searching for duplicates.",
+            default_value = "false",
+            default_missing_value = "true"
+        )]
+        near: bool,
+        /// The `resolve` field selects a strategy for choosing the record to keep from each
+        /// group, writing a keep, drop or review disposition for every member in place of the
+        /// duplicate report.  Accepts 'keep-newest', which keeps the most recently edited record,
+        /// 'keep-lowest-oid', which keeps the record with the lowest Object ID, and 'manual',
+        /// which marks every record for review.  Reads the edit metadata of 'grants_pass' and
+        /// 'josephine_county' sources.  See [`crate::Resolution`].
+        #[arg(
+            long,
+            help = "Strategy for resolving duplicates.  Valid values include 'keep-newest', 'keep-lowest-oid' and 'manual'."
+        )]
+        resolve: Option<String>,
+    },
+    /// Tags each source address with the attribute of the polygon containing it in each layer,
+    /// writing [`crate::EnrichedAddresses`].  See [`crate::ZoneLayer`].  With a map grid, the
+    /// grid reference of each address is written to the 'grid' column.
+    #[display("enrich")]
+    Enrich {
+        /// The `layers` field holds the polygon layers, each given as `name=path` or
+        /// `name:field=path`, where `path` is a GeoJSON file or polygon shapefile and `field`
+        /// names the attribute written to the column `name`.  Without a field, the column holds
+        /// "true" for addresses inside the layer.
+        #[arg(
+            long = "layer",
+            help = "Polygon layer as 'name:field=path', repeated for each layer."
+        )]
+        layers: Vec<String>,
+    },
+    /// Checks the source addresses against ESN polygons, writing a ranked review list of
+    /// [`crate::EsnConflicts`], filtered by conflict kind with `-f`.  The ESN recorded for each
+    /// address is read from 'josephine_county' addresses.
+    #[display("esn")]
+    Esn {
+        /// The `esn` field holds the path to the ESN polygon layer, read from a GeoJSON file or a
+        /// polygon shapefile.
+        #[arg(long, help = "Path to the ESN polygons (GeoJSON or shp).")]
+        esn: Option<std::path::PathBuf>,
+        /// The `esn_field` field holds the name of the attribute holding the ESN of each polygon.
+        /// Defaults to [`crate::MSAG_ESN_FIELD`].
+        #[arg(long, help = "Attribute of the ESN polygons holding the ESN.")]
+        esn_field: Option<String>,
+        /// The `buffer` field holds the distance from the edge of another ESN within which an
+        /// address needs verification.  Defaults to [`crate::ESN_BUFFER`].
+        #[arg(long, help = "Distance from another ESN needing verification.")]
+        buffer: Option<f64>,
+    },
+    /// Takes [`crate::MatchRecords`] as input, or [`crate::BusinessMatchRecords`] with the
+    /// 'business' source type, and writes the records passing the filter.
+    #[display("filter")]
+    Filter,
+    /// Checks the source addresses against building footprints, writing
+    /// [`crate::FootprintRecords`] for the addresses outside of every footprint with the offset to
+    /// the nearest footprint within the search radius, filtered by status with `-f`.  The search
+    /// radius defaults to [`crate::FOOTPRINT_DISTANCE`].
+    #[display("footprints")]
+    Footprints {
+        /// The `footprints` field holds the path to the building footprint layer, read from a
+        /// GeoJSON file or a polygon shapefile in the projected coordinates of the addresses.
+        #[arg(
+            long,
+            help = "Path to the building footprint polygons (GeoJSON or shp)."
+        )]
+        footprints: Option<std::path::PathBuf>,
+        /// The `footprint_field` field holds the name of the attribute holding the identifier of
+        /// each footprint.  Without it, footprints are numbered in file order.
+        #[arg(
+            long,
+            help = "Attribute of the footprints holding the footprint identifier."
+        )]
+        footprint_field: Option<String>,
+        /// The `snap` flag adds the nearest point on the edge of the nearest footprint to each
+        /// record, as the 'snapped_x' and 'snapped_y' columns, for editors to review.
+        #[arg(
+            long,
+            help = "Flag to write coordinates snapped to the nearest footprint.",
+            default_value = "false",
+            default_missing_value = "true"
+        )]
+        snap: bool,
+    },
+    /// Writes [`crate::AddressGaps`], filtered by street name with `-f`.
+    #[display("gaps")]
+    Gaps,
+    /// Geocodes the source addresses against the target addresses.  Inputs naming the
+    /// intersection of two streets, as in "6th St & M St", are located where the streets meet.
+    #[display("geocode")]
+    Geocode {
+        /// The `centerlines` field holds the path to the road centerlines used to locate
+        /// intersections, in the projected coordinates given by `target_epsg`.  Without
+        /// centerlines, intersections are located between the nearest addresses on the two
+        /// streets.
+        #[arg(long, help = "Path to road centerlines for locating intersections.")]
+        centerlines: Option<std::path::PathBuf>,
+    },
+    /// Takes the oldest snapshot of an address dataset as the source, and later snapshots in
+    /// order, writing [`crate::LifecycleRecords`] for the addresses created, retired, renumbered,
+    /// renamed or moved between snapshots.  Filtered by event name with `-f`.  Addresses are
+    /// followed by their Global ID, so the snapshots must be 'grants_pass' addresses.
+    #[display("history")]
+    History {
+        /// The `snapshots` field holds the paths to the snapshots taken after the source, oldest
+        /// first.
+        #[arg(help = "Paths to later snapshots, oldest first.")]
+        snapshots: Vec<std::path::PathBuf>,
+        /// The `threshold` field holds the distance an address must move between snapshots to
+        /// report it as moved, in the units of the projected coordinates.  Defaults to
+        /// [`crate::HISTORY_THRESHOLD`].
+        #[arg(long, help = "Minimum distance moved to report.")]
+        threshold: Option<f64>,
+    },
+    /// Takes an earlier snapshot of an address dataset as the source and a later snapshot with
+    /// `--new`, writing [`crate::IdChanges`] for the addresses whose civic address persisted but
+    /// whose Global ID or Object ID changed, and for Object IDs reused by another address, as
+    /// after an accidental delete and recreate in ArcGIS.  Filtered by kind with `-f`.  The
+    /// snapshots must be 'grants_pass' addresses.
+    #[display("id_audit")]
+    IdAudit {
+        /// The `new` field holds the path to the later snapshot.
+        #[arg(long, help = "Path to the later snapshot.")]
+        new: Option<std::path::PathBuf>,
+    },
+    /// Prints the [`crate::DataHeader`] of a binary data file written by the 'save' command, such
+    /// as the format version and record count, without reading the records.
+    #[display("inspect")]
+    Inspect,
+    /// Formats labels for each source address from templates, writing
+    /// [`crate::LabeledAddresses`] with a column for each template.  See
+    /// [`crate::LabelTemplate`] for the template syntax.
+    #[display("labels")]
+    Labels {
+        /// The `labels` field holds the label columns, each given as `name=template`, where
+        /// `template` is a template or one of the built-in templates "mailing", "map" or "cad".
+        /// A built-in template given alone writes to a column of the same name.
+        #[arg(
+            long = "label",
+            help = "Label column as 'name=template' or 'mailing', 'map' or 'cad', repeated for each column."
+        )]
+        labels: Vec<String>,
+    },
+    /// Writes a [`crate::LexisNexis`] table of address ranges, excluding the target addresses.
+    #[display("lexisnexis")]
+    Lexisnexis {
+        /// The `boundary` field holds an optional path to a service area polygon, read from a
+        /// GeoJSON file or a polygon shapefile.  When set, the source addresses are read with
+        /// coordinates, and addresses outside the boundary are excluded along with the target
+        /// addresses, which become optional.  See [`crate::Boundary`].
+        #[arg(long, help = "Path to a service area polygon (GeoJSON or shp).")]
+        boundary: Option<std::path::PathBuf>,
+        /// The `previous` field holds an optional path to the table submitted last, as written
+        /// by this command.  When set, the output holds the rows added, removed and changed since
+        /// the previous submission, and the full table is written beside it, with '_full'
+        /// appended to the file name.  See [`crate::LexisNexis::diff`].
+        #[arg(
+            long,
+            help = "Path to the previous LexisNexis submission, to write a change log."
+        )]
+        previous: Option<std::path::PathBuf>,
+        /// The `template` field holds an optional path to a TOML output template, selecting the
+        /// order, headers and inclusion of the columns written to csv.  See
+        /// [`crate::LexisNexisTemplate`].
+        #[arg(
+            long,
+            help = "Path to a TOML output template for LexisNexis csv files."
+        )]
+        template: Option<std::path::PathBuf>,
+    },
+    /// Aggregates the source addresses into the street ranges of a Master Street Address Guide,
+    /// writing [`crate::Msag`] rows filtered by ESN with `-f`.
+    #[display("msag")]
+    Msag {
+        /// The `esn` field holds the path to the ESN polygon layer, read from a GeoJSON file or a
+        /// polygon shapefile.
+        #[arg(long, help = "Path to the ESN polygons (GeoJSON or shp).")]
+        esn: Option<std::path::PathBuf>,
+        /// The `esn_field` field holds the name of the attribute holding the ESN of each polygon.
+        /// Defaults to [`crate::MSAG_ESN_FIELD`].
+        #[arg(long, help = "Attribute of the ESN polygons holding the ESN.")]
+        esn_field: Option<String>,
+    },
+    /// Lists the nearest target addresses to each source address, writing
+    /// [`crate::NearestRecords`] with the distance to each neighbor and whether the civic
+    /// addresses agree, filtered by status with `-f`.  The radius defaults to
+    /// [`crate::NEAREST_DISTANCE`].
+    #[display("nearest")]
+    Nearest {
+        /// The `neighbors` field holds the number of target addresses listed for each source
+        /// address.  Defaults to [`crate::NEAREST_NEIGHBORS`].
+        #[arg(long, help = "Number of nearest target addresses to list.")]
+        neighbors: Option<usize>,
+    },
+    /// Reads the notes and notification fields of the source, a 'grants_pass' dataset, writing a
+    /// [`crate::NoteQueue`] of the addresses with either field, classified by keyword as retire,
+    /// renumber, verify or other and sorted by the date of the last edit.  Filtered by action
+    /// with `-f`.  See [`crate::NOTE_RULES`].
+    #[display("notes")]
+    Notes,
+    /// Writes [`crate::OrphanStreets`] in the source that are absent from the target.
+    #[display("orphan_streets")]
+    OrphanStreets,
+    /// Matches the city addresses in the source against the county addresses in the target,
+    /// writing [`crate::OverlapRecords`] for the addresses found in both, with the dataset
+    /// stewarding each, filtered by stewardship with `-f`.  Addresses inside the boundary are
+    /// stewarded by the city, and those outside by the county.  See [`crate::Stewardship`].
+    #[display("overlap")]
+    Overlap {
+        /// The `boundary` field holds the path to the city limits or urban growth boundary, read
+        /// from a GeoJSON file or a polygon shapefile.  See [`crate::Boundary`].
+        #[arg(
+            long,
+            help = "Path to the city limits or UGB polygon (GeoJSON or shp)."
+        )]
+        boundary: Option<std::path::PathBuf>,
+    },
+    /// Prints the provenance recorded beside the output file at the source path, and checks the
+    /// output and its inputs against their recorded checksums.  See [`crate::Provenance`].
+    #[display("provenance")]
+    Provenance,
+    /// Joins the source addresses to the parcels containing them, writing
+    /// [`crate::ParcelIssues`] for parcels without an address, addresses outside of every parcel,
+    /// and situs addresses disagreeing with the addresses on the parcel, filtered by issue kind
+    /// with `-f`.  With the 'josephine_county_legacy' source type, addresses in the county schema
+    /// prior to April of 2024 are read from csv, and their recorded tax lots are checked against
+    /// the parcels.
+    #[display("parcels")]
+    Parcels {
+        /// The `parcels` field holds the path to the parcel layer, read from a GeoJSON file or a
+        /// polygon shapefile.
+        #[arg(long, help = "Path to the parcel polygons (GeoJSON or shp).")]
+        parcels: Option<std::path::PathBuf>,
+        /// The `taxlot_field` field holds the name of the attribute holding the map tax lot
+        /// number of each parcel.  Defaults to [`crate::PARCEL_TAXLOT_FIELD`].
+        #[arg(
+            long,
+            help = "Attribute of the parcels holding the map tax lot number."
+        )]
+        taxlot_field: Option<String>,
+        /// The `situs_field` field holds the name of the attribute holding the situs address of
+        /// each parcel.  Defaults to [`crate::PARCEL_SITUS_FIELD`].
+        #[arg(long, help = "Attribute of the parcels holding the situs address.")]
+        situs_field: Option<String>,
+    },
+    /// Takes [`crate::MatchRecords`] as the source, and a feature service layer url as the
+    /// target.  See [`crate::push_match_records`].
+    #[display("push")]
+    Push {
+        /// The `token` field holds an access token for ArcGIS Feature Services.
+        #[arg(long, help = "Access token for ArcGIS Feature Services.")]
+        #[serde(skip)]
+        token: Option<String>,
+    },
+    /// Takes [`crate::AccessPoints`] as the target, and writes [`crate::AccessRecords`] pairing
+    /// each source address with its nearest driveway or access point.  The radius defaults to
+    /// [`crate::ACCESS_DISTANCE`].
+    #[display("access")]
+    Access,
+    /// Takes road [`crate::Centerlines`] as the target, and writes [`crate::RangeViolations`].
+    #[display("ranges")]
+    Ranges,
+    /// Links the source addresses to matching target addresses by their Global IDs, writing
+    /// [`crate::RelationshipRecords`] for loading as a relationship class in ArcGIS Pro.  The
+    /// source and target must be 'grants_pass' or 'josephine_county' datasets.
+    #[display("relate")]
+    Relate,
+    /// Adds the retired addresses of the source, a 'grants_pass' dataset, to the registry of
+    /// [`crate::RetiredAddresses`] at the output path, keeping the records already there.
+    #[display("retired")]
+    Retired,
+    /// Finds the nearest target address to each of the source points.
+    #[display("reverse")]
+    Reverse,
+    /// Takes a [`crate::Manifest`] as the source.
+    #[display("run")]
+    Run,
+    /// Reads the source addresses and writes them in the output format.  The default binary
+    /// format can be read back with the 'data' source type.
+    #[display("save")]
+    Save,
+    /// Reads the source addresses once as reference addresses, and answers match, geocode,
+    /// validation, address completion and street name suggestion queries against them over HTTP.  See [`crate::AddressService`].
+    #[display("serve")]
+    Serve {
+        /// The `bind` field holds the socket address for the server to listen on.
+        #[arg(
+            long,
+            default_value = "127.0.0.1:3000",
+            help = "Socket address for the server to listen on."
+        )]
+        bind: String,
+        /// The `grpc` field holds the socket address for gRPC match requests, served alongside
+        /// the HTTP endpoints.  Requires the `grpc` feature.
+        #[arg(
+            long,
+            help = "Socket address for gRPC match requests, served alongside the HTTP endpoints."
+        )]
+        grpc: Option<String>,
+    },
+    /// Cross-references a street name sign inventory against the streets of the source
+    /// addresses, writing [`crate::SignGaps`] for streets with addresses but no sign, and signs
+    /// naming a street without addresses, filtered by kind with `-f`.
+    #[display("signs")]
+    Signs {
+        /// The `signs` field holds the path to the sign inventory, a csv file with a row for each
+        /// street name sign.  See [`crate::SignInventory`].
+        #[arg(long, help = "Path to the street name sign inventory (csv).")]
+        signs: Option<std::path::PathBuf>,
+        /// The `street_field` field holds the name of the column of the sign inventory holding
+        /// the street named on each sign.  Defaults to [`crate::SIGN_STREET_FIELD`].
+        #[arg(long, help = "Column of the sign inventory holding the street name.")]
+        street_field: Option<String>,
+    },
+    /// Writes [`crate::StandardizedAddresses`] spelled according to `profile`.
+    #[display("standardize")]
+    Standardize {
+        /// The `profile` field specifies the spelling profile of the labels.  Currently accepts
+        /// 'fgdc' and 'usps' as values.  Defaults to 'fgdc', which spells out directionals, post
+        /// types and subaddress types.  See [`crate::Profile`].
+        #[arg(
+            long,
+            help = "Spelling profile for standardized labels.  Valid profiles include 'fgdc' and 'usps'."
+        )]
+        profile: Option<String>,
+    },
+    /// Writes the [`crate::StreetDictionary`] of the source, or with `-f`, the
+    /// [`crate::StreetSuggestions`] for the filter value.
+    #[display("streets")]
+    Streets,
+    /// Checks the subaddress identifiers of the source against the [`crate::UnitPolicy`] of the
+    /// first `unit_format` rule in `--rules`, or the default policy, writing
+    /// [`crate::UnitFormatRecords`] for each nonconforming identifier with its proposed
+    /// correction.
+    #[display("unit_format")]
+    UnitFormat,
+    /// Takes the address point of a multi-unit building from the source, and writes a record for
+    /// each unit in `range` in the 'common' format, with the street fields and location of the
+    /// building and a status of pending.  See [`crate::UnitRange`].
+    #[display("units")]
+    Units {
+        /// The `range` field holds the unit range specification, such as "A-F" or "101-110 odd".
+        #[arg(long, help = "Unit range, such as 'A-F' or '101-110 odd'.")]
+        range: Option<String>,
+        /// The `building` field holds the label of the building address in the source, such as
+        /// "1450 NE 7TH ST".
+        #[arg(long, help = "Label of the building address in the source.")]
+        building: Option<String>,
+        /// The `unit_type` field holds the subaddress type of the units, such as "apartment" or
+        /// "suite".  When unset, units are labeled with '#'.
+        #[arg(
+            long,
+            help = "Subaddress type of the units, such as 'apartment' or 'suite'."
+        )]
+        unit_type: Option<String>,
+    },
+    /// Joins the source addresses to the parcels containing them, and infers whether each
+    /// parcel is developed from the building footprints on it, writing [`crate::VacancyRecords`]
+    /// that flag address statuses disagreeing with the inference.  Filtered by occupancy with
+    /// `-f`, or by "stale" for the flagged records.
+    #[display("vacancy")]
+    Vacancy {
+        /// The `parcels` field holds the path to the parcel layer, read from a GeoJSON file or a
+        /// polygon shapefile.
+        #[arg(long, help = "Path to the parcel polygons (GeoJSON or shp).")]
+        parcels: Option<std::path::PathBuf>,
+        /// The `taxlot_field` field holds the name of the attribute holding the map tax lot
+        /// number of each parcel.  Defaults to [`crate::PARCEL_TAXLOT_FIELD`].
+        #[arg(
+            long,
+            help = "Attribute of the parcels holding the map tax lot number."
+        )]
+        taxlot_field: Option<String>,
+        /// The `footprints` field holds the path to the building footprint layer, read from a
+        /// GeoJSON file or a polygon shapefile in the coordinates of the parcels.
+        #[arg(
+            long,
+            help = "Path to the building footprint polygons (GeoJSON or shp)."
+        )]
+        footprints: Option<std::path::PathBuf>,
+    },
+    /// Writes [`crate::ValidationRecords`], filtered by rule ID with `-f`.  The stored complete
+    /// fields of 'grants_pass' addresses are checked against their components.
+    #[display("validate")]
+    Validate {
+        /// The `registry` field holds an optional path to a registry of retired addresses, as
+        /// written by the 'retired' command.  Addresses matching an address retired within
+        /// `years` are reported.  See [`crate::ValidationRecords::check_retired`].
+        #[arg(long, help = "Path to the registry of retired addresses.")]
+        registry: Option<std::path::PathBuf>,
+        /// The `years` field holds the number of years after retirement during which an address
+        /// may not be reissued.  Defaults to [`crate::RETIRED_YEARS`].
+        #[arg(long, help = "Years before a retired address may be reissued.")]
+        years: Option<u32>,
+        /// The `zips` field holds an optional path to a zip code polygon layer, read from a
+        /// GeoJSON file or a polygon shapefile.  When set, the source addresses are read with
+        /// coordinates, and addresses with a zip code or postal community differing from the
+        /// polygon containing them are reported.  See [`crate::ValidationRecords::check_zip`].
+        #[arg(long, help = "Path to the zip code polygons (GeoJSON or shp).")]
+        zips: Option<std::path::PathBuf>,
+        /// The `zip_field` field holds the name of the attribute holding the zip code of each
+        /// polygon.  Defaults to [`crate::ZIP_FIELD`].
+        #[arg(
+            long,
+            help = "Attribute of the zip code polygons holding the zip code."
+        )]
+        zip_field: Option<String>,
+        /// The `community_field` field holds the name of the attribute holding the postal
+        /// community of each polygon.  Defaults to [`crate::COMMUNITY_FIELD`].
+        #[arg(
+            long,
+            help = "Attribute of the zip code polygons holding the postal community."
+        )]
+        community_field: Option<String>,
+    },
+}
+
+impl Cli {
+    /// The `load` method parses the command-line arguments, applying the configuration file if
+    /// one is specified.
+    pub fn load() -> Result<Self, AddressErrorKind> {
+        Self::load_from(std::env::args_os())
+    }
+
+    /// The `load_from` method parses the arguments in `args`, applying the configuration file if
+    /// one is specified.  Flags passed as arguments take precedence over configuration values.
+    pub fn load_from<I, T>(args: I) -> Result<Self, AddressErrorKind>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let error = |source| Arguments::new(source, line!(), file!().to_string());
+        let matches = Self::command().try_get_matches_from(args).map_err(error)?;
+        let mut cli = Self::from_arg_matches(&matches).map_err(error)?;
+        if let Some(path) = &cli.config {
+            let config = RunConfig::from_toml(path)?;
+            cli.configure(&config, &matches);
+        }
+        if let Some(name) = &cli.compress {
+            let Some(compression) = Compression::from_name(name) else {
+                return Err(Builder::new(
+                    format!("unrecognized compression '{name}'"),
+                    "Cli".to_string(),
+                    line!(),
+                    file!().to_string(),
+                )
+                .into());
+            };
+            cli.output = compression.path(&cli.output);
+        }
+        if (!cli.select.is_empty() || !cli.rename.is_empty()) && cli.format != "csv" {
+            return Err(Builder::new(
+                format!(
+                    "--select and --rename apply to csv output, not '{}'",
+                    cli.format
+                ),
+                "Cli".to_string(),
+                line!(),
+                file!().to_string(),
+            )
+            .into());
+        }
+        // GeoJSON holds WGS84 coordinates, computed from the projected coordinates of the source.
+        if cli.format == "geojson"
+            && matches!(cli.command, Command::Drift { .. })
+            && cli.source_epsg.is_none()
+        {
+            return Err(Builder::new(
+                "geojson output from 'drift' requires --source-epsg".to_string(),
+                "Cli".to_string(),
+                line!(),
+                file!().to_string(),
+            )
+            .into());
+        }
+        Ok(cli)
+    }
+
+    /// The `configure` method fills in values from `config` for flags not passed on the command
+    /// line, as recorded in `matches`, then replaces dataset names with their paths.
+    pub fn configure(&mut self, config: &RunConfig, matches: &ArgMatches) {
+        // Shared arguments are global, so the matches of the command record both the shared
+        // arguments and the arguments of the command.
+        let matches = matches.subcommand().map_or(matches, |(_, m)| m);
+        let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+        let parameters = config.parameters(&self.command.to_string());
+        if unset("source") && parameters.source.is_some() {
+            self.source = parameters.source.map(|v| v.into());
+        }
+        if unset("source_type") && parameters.source_type.is_some() {
+            self.source_type = parameters.source_type;
+        }
+        if unset("target") && parameters.target.is_some() {
+            self.target = parameters.target.map(|v| v.into());
+        }
+        if unset("target_type") && parameters.target_type.is_some() {
+            self.target_type = parameters.target_type;
+        }
+        if unset("filter") && parameters.filter.is_some() {
+            self.filter = parameters.filter;
+        }
+        if unset("output")
+            && let Some(output) = parameters.output
+        {
+            self.output = output;
+        }
+        if unset("format")
+            && let Some(format) = parameters.format
+        {
+            self.format = format;
+        }
+        if unset("fuzzy") && parameters.fuzzy.is_some() {
+            self.fuzzy = parameters.fuzzy;
+        }
+        if unset("radius") && parameters.radius.is_some() {
+            self.radius = parameters.radius;
+        }
+        if unset("rules") && parameters.rules.is_some() {
+            self.rules = parameters.rules;
+        }
+        if unset("aliases") && parameters.aliases.is_some() {
+            self.aliases = parameters.aliases;
+        }
+        if unset("grid") && parameters.grid.is_some() {
+            self.grid = parameters.grid;
+        }
+        if unset("grid_field") && parameters.grid_field.is_some() {
+            self.grid_field = parameters.grid_field;
+        }
+        if unset("on_error") && parameters.on_error.is_some() {
+            self.on_error = parameters.on_error;
+        }
+        if unset("compress") && parameters.compress.is_some() {
+            self.compress = parameters.compress;
+        }
+        if unset("select")
+            && let Some(select) = parameters.select
+        {
+            self.select = select;
+        }
+        if unset("rename")
+            && let Some(rename) = parameters.rename
+        {
+            self.rename = rename;
+        }
+        if unset("mapping") && parameters.mapping.is_some() {
+            self.mapping = parameters.mapping;
+        }
+        if unset("jobs") && parameters.jobs.is_some() {
+            self.jobs = parameters.jobs;
+        }
+        if unset("quiet")
+            && let Some(quiet) = parameters.quiet
+        {
+            self.quiet = quiet;
+        }
+        if unset("dsn") && parameters.dsn.is_some() {
+            self.dsn = parameters.dsn;
+        }
+        if unset("source_epsg") && parameters.source_epsg.is_some() {
+            self.source_epsg = parameters.source_epsg;
+        }
+        if unset("target_epsg") && parameters.target_epsg.is_some() {
+            self.target_epsg = parameters.target_epsg;
+        }
+        match &mut self.command {
+            Command::Activity { since, until } => {
+                if unset("since") && parameters.since.is_some() {
+                    *since = parameters.since;
+                }
+                if unset("until") && parameters.until.is_some() {
+                    *until = parameters.until;
+                }
+            }
+            Command::Assign { registry, years } => {
+                if unset("registry") && parameters.registry.is_some() {
+                    *registry = parameters.registry;
+                }
+                if unset("years") && parameters.years.is_some() {
+                    *years = parameters.years;
+                }
+            }
+            Command::AssignNearest { confident_distance }
+                if unset("confident_distance") && parameters.confident_distance.is_some() =>
+            {
+                *confident_distance = parameters.confident_distance;
+            }
+            Command::Validate {
+                registry,
+                years,
+                zips,
+                zip_field,
+                community_field,
+            } => {
+                if unset("registry") && parameters.registry.is_some() {
+                    *registry = parameters.registry;
+                }
+                if unset("years") && parameters.years.is_some() {
+                    *years = parameters.years;
+                }
+                if unset("zips") && parameters.zips.is_some() {
+                    *zips = parameters.zips;
+                }
+                if unset("zip_field") && parameters.zip_field.is_some() {
+                    *zip_field = parameters.zip_field;
+                }
+                if unset("community_field") && parameters.community_field.is_some() {
+                    *community_field = parameters.community_field;
+                }
+            }
+            Command::Bench { tolerance, stats } => {
+                if unset("tolerance") && parameters.tolerance.is_some() {
+                    *tolerance = parameters.tolerance;
+                }
+                if unset("stats") && parameters.stats.is_some() {
+                    *stats = parameters.stats;
+                }
+            }
+            Command::Business {
+                alternate,
+                alternate_type,
+                mailing,
+                sheet,
+                columns,
+            } => {
+                if unset("mailing") && parameters.mailing.is_some() {
+                    *mailing = parameters.mailing;
+                }
+                if unset("sheet") && parameters.sheet.is_some() {
+                    *sheet = parameters.sheet;
+                }
+                if unset("columns")
+                    && let Some(value) = parameters.columns
+                {
+                    *columns = value;
+                }
+                if unset("alternate") && parameters.alternate.is_some() {
+                    *alternate = parameters.alternate.map(|v| v.into());
+                }
+                if unset("alternate_type") && parameters.alternate_type.is_some() {
+                    *alternate_type = parameters.alternate_type;
+                }
+                Self::resolve(config, alternate, alternate_type);
+            }
+            Command::Compare {
+                business,
+                alternate,
+                alternate_type,
+                mailing,
+                sheet,
+                columns,
+                incremental,
+                resume,
+                accept,
+                review,
+                blocking,
+                phonetic,
+                include,
+                exclude,
+                normalize,
+                bidirectional,
+                watch,
+            } => {
+                if unset("watch")
+                    && let Some(value) = parameters.watch
+                {
+                    *watch = value;
+                }
+                if unset("mailing") && parameters.mailing.is_some() {
+                    *mailing = parameters.mailing;
+                }
+                if unset("sheet") && parameters.sheet.is_some() {
+                    *sheet = parameters.sheet;
+                }
+                if unset("columns")
+                    && let Some(value) = parameters.columns
+                {
+                    *columns = value;
+                }
+                if unset("business")
+                    && let Some(value) = parameters.business
+                {
+                    *business = value;
+                }
+                if unset("alternate")
+                    && let Some(value) = parameters.alternate
+                {
+                    *alternate = vec![value.into()];
+                }
+                if unset("alternate_type")
+                    && let Some(value) = parameters.alternate_type
+                {
+                    *alternate_type = vec![value];
+                }
+                Self::resolve_all(config, alternate, alternate_type);
+                if unset("incremental")
+                    && let Some(value) = parameters.incremental
+                {
+                    *incremental = value;
+                }
+                if unset("resume")
+                    && let Some(value) = parameters.resume
+                {
+                    *resume = value;
+                }
+                if unset("accept") && parameters.accept.is_some() {
+                    *accept = parameters.accept;
+                }
+                if unset("review") && parameters.review.is_some() {
+                    *review = parameters.review;
+                }
+                if unset("blocking") && parameters.blocking.is_some() {
+                    *blocking = parameters.blocking;
+                }
+                if unset("phonetic")
+                    && let Some(value) = parameters.phonetic
+                {
+                    *phonetic = value;
+                }
+                if unset("include")
+                    && let Some(value) = parameters.include
+                {
+                    *include = value;
+                }
+                if unset("exclude")
+                    && let Some(value) = parameters.exclude
+                {
+                    *exclude = value;
+                }
+                if unset("normalize")
+                    && let Some(value) = parameters.normalize
+                {
+                    *normalize = value;
+                }
+                if unset("bidirectional")
+                    && let Some(value) = parameters.bidirectional
+                {
+                    *bidirectional = value;
+                }
+            }
+            Command::Drift {
+                threshold,
+                min_threshold,
+                stats,
+                report,
+                clusters,
+                units,
+                stacks,
+                watch,
+            } => {
+                if unset("watch")
+                    && let Some(value) = parameters.watch
+                {
+                    *watch = value;
+                }
+                if unset("stacks")
+                    && let Some(value) = parameters.stacks
+                {
+                    *stacks = value;
+                }
+                if unset("threshold") && parameters.threshold.is_some() {
+                    *threshold = parameters.threshold;
+                }
+                if unset("min_threshold") && parameters.min_threshold.is_some() {
+                    *min_threshold = parameters.min_threshold;
+                }
+                if unset("stats") && parameters.stats.is_some() {
+                    *stats = parameters.stats;
+                }
+                if unset("report") && parameters.report.is_some() {
+                    *report = parameters.report;
+                }
+                if unset("clusters") && parameters.clusters.is_some() {
+                    *clusters = parameters.clusters;
+                }
+                if unset("units") && parameters.units.is_some() {
+                    *units = parameters.units;
+                }
+            }
+            Command::Duplicates { near, resolve } => {
+                if unset("near")
+                    && let Some(value) = parameters.near
+                {
+                    *near = value;
+                }
+                if unset("resolve") && parameters.resolve.is_some() {
+                    *resolve = parameters.resolve;
+                }
+            }
+            Command::Enrich { layers } => {
+                if unset("layers")
+                    && let Some(value) = parameters.layers
+                {
+                    *layers = value;
+                }
+            }
+            Command::Diff {
+                old,
+                new,
+                key,
+                ignore,
+            } => {
+                if unset("old") && parameters.old.is_some() {
+                    *old = parameters.old;
+                }
+                if unset("new") && parameters.new.is_some() {
+                    *new = parameters.new;
+                }
+                if unset("key") && parameters.key.is_some() {
+                    *key = parameters.key;
+                }
+                if unset("ignore")
+                    && let Some(value) = parameters.ignore
+                {
+                    *ignore = value;
+                }
+            }
+            Command::Labels { labels } => {
+                if unset("labels")
+                    && let Some(value) = parameters.labels
+                {
+                    *labels = value;
+                }
+            }
+            Command::History {
+                snapshots,
+                threshold,
+            } => {
+                if unset("snapshots")
+                    && let Some(value) = parameters.snapshots
+                {
+                    *snapshots = value;
+                }
+                if unset("threshold") && parameters.threshold.is_some() {
+                    *threshold = parameters.threshold;
+                }
+            }
+            Command::IdAudit { new } if unset("new") && parameters.new.is_some() => {
+                *new = parameters.new;
+            }
+            Command::Lexisnexis {
+                boundary,
+                previous,
+                template,
+            } => {
+                if unset("boundary") && parameters.boundary.is_some() {
+                    *boundary = parameters.boundary;
+                }
+                if unset("previous") && parameters.previous.is_some() {
+                    *previous = parameters.previous;
+                }
+                if unset("template") && parameters.template.is_some() {
+                    *template = parameters.template;
+                }
+            }
+            Command::Units {
+                range,
+                building,
+                unit_type,
+            } => {
+                if unset("range") && parameters.range.is_some() {
+                    *range = parameters.range;
+                }
+                if unset("building") && parameters.building.is_some() {
+                    *building = parameters.building;
+                }
+                if unset("unit_type") && parameters.unit_type.is_some() {
+                    *unit_type = parameters.unit_type;
+                }
+            }
+            Command::Esn {
+                esn,
+                esn_field,
+                buffer,
+            } => {
+                if unset("esn") && parameters.esn.is_some() {
+                    *esn = parameters.esn;
+                }
+                if unset("esn_field") && parameters.esn_field.is_some() {
+                    *esn_field = parameters.esn_field;
+                }
+                if unset("buffer") && parameters.buffer.is_some() {
+                    *buffer = parameters.buffer;
+                }
+            }
+            Command::Footprints {
+                footprints,
+                footprint_field,
+                snap,
+            } => {
+                if unset("footprints") && parameters.footprints.is_some() {
+                    *footprints = parameters.footprints;
+                }
+                if unset("footprint_field") && parameters.footprint_field.is_some() {
+                    *footprint_field = parameters.footprint_field;
+                }
+                if unset("snap")
+                    && let Some(value) = parameters.snap
+                {
+                    *snap = value;
+                }
+            }
+            Command::Geocode { centerlines }
+                if unset("centerlines") && parameters.centerlines.is_some() =>
+            {
+                *centerlines = parameters.centerlines;
+            }
+            Command::Nearest { neighbors }
+                if unset("neighbors") && parameters.neighbors.is_some() =>
+            {
+                *neighbors = parameters.neighbors;
+            }
+            Command::Overlap { boundary } if unset("boundary") && parameters.boundary.is_some() => {
+                *boundary = parameters.boundary;
+            }
+            Command::Parcels {
+                parcels,
+                taxlot_field,
+                situs_field,
+            } => {
+                if unset("parcels") && parameters.parcels.is_some() {
+                    *parcels = parameters.parcels;
+                }
+                if unset("taxlot_field") && parameters.taxlot_field.is_some() {
+                    *taxlot_field = parameters.taxlot_field;
+                }
+                if unset("situs_field") && parameters.situs_field.is_some() {
+                    *situs_field = parameters.situs_field;
+                }
+            }
+            Command::Vacancy {
+                parcels,
+                taxlot_field,
+                footprints,
+            } => {
+                if unset("parcels") && parameters.parcels.is_some() {
+                    *parcels = parameters.parcels;
+                }
+                if unset("taxlot_field") && parameters.taxlot_field.is_some() {
+                    *taxlot_field = parameters.taxlot_field;
+                }
+                if unset("footprints") && parameters.footprints.is_some() {
+                    *footprints = parameters.footprints;
+                }
+            }
+            Command::Msag { esn, esn_field } => {
+                if unset("esn") && parameters.esn.is_some() {
+                    *esn = parameters.esn;
+                }
+                if unset("esn_field") && parameters.esn_field.is_some() {
+                    *esn_field = parameters.esn_field;
+                }
+            }
+            Command::Serve { bind, grpc } => {
+                if unset("bind")
+                    && let Some(value) = parameters.bind
+                {
+                    *bind = value;
+                }
+                if unset("grpc") && parameters.grpc.is_some() {
+                    *grpc = parameters.grpc;
+                }
+            }
+            Command::Signs {
+                signs,
+                street_field,
+            } => {
+                if unset("signs") && parameters.signs.is_some() {
+                    *signs = parameters.signs;
+                }
+                if unset("street_field") && parameters.street_field.is_some() {
+                    *street_field = parameters.street_field;
+                }
+            }
+            Command::Push { token } if unset("token") && parameters.token.is_some() => {
+                *token = parameters.token;
+            }
+            Command::Standardize { profile }
+                if unset("profile") && parameters.profile.is_some() =>
+            {
+                *profile = parameters.profile;
+            }
+            _ => {}
+        }
+        Self::resolve(config, &mut self.source, &mut self.source_type);
+        Self::resolve(config, &mut self.target, &mut self.target_type);
+    }
+
+    /// The `inputs` method returns the paths of the files read by the command, for the provenance
+    /// of its outputs.  Paths that do not name a local file, such as feature service urls, are
+    /// included, and recorded without a checksum.  A source or target naming several csv files
+    /// lists each file.
+    pub fn inputs(&self) -> Vec<std::path::PathBuf> {
+        let mut inputs = [&self.source, &self.target]
+            .into_iter()
+            .flatten()
+            .flat_map(|path| match is_service(path) {
+                true => vec![path.clone()],
+                false => source_files(path).unwrap_or_else(|_| vec![path.clone()]),
+            })
+            .collect::<Vec<std::path::PathBuf>>();
+        inputs.extend(
+            [&self.mapping, &self.rules, &self.aliases, &self.grid]
+                .into_iter()
+                .flatten()
+                .cloned(),
+        );
+        match &self.command {
+            Command::Assign { registry, .. } => inputs.extend(registry.clone()),
+            Command::Business {
+                alternate, mailing, ..
+            } => {
+                inputs.extend(alternate.clone());
+                inputs.extend(mailing.clone());
+            }
+            Command::Compare {
+                alternate, mailing, ..
+            } => {
+                inputs.extend(alternate.iter().cloned());
+                inputs.extend(mailing.clone());
+            }
+            Command::Enrich { layers } => inputs.extend(
+                layers
+                    .iter()
+                    .filter_map(|layer| layer.split_once('=').map(|(_, path)| path.into())),
+            ),
+            Command::Esn { esn, .. } | Command::Msag { esn, .. } => inputs.extend(esn.clone()),
+            Command::Signs { signs, .. } => inputs.extend(signs.clone()),
+            Command::Diff { old, new, .. } => {
+                inputs.extend(old.clone());
+                inputs.extend(new.clone());
+            }
+            Command::Footprints { footprints, .. } => inputs.extend(footprints.clone()),
+            Command::Geocode { centerlines } => inputs.extend(centerlines.clone()),
+            Command::Parcels { parcels, .. } => inputs.extend(parcels.clone()),
+            Command::Vacancy {
+                parcels,
+                footprints,
+                ..
+            } => {
+                inputs.extend(parcels.clone());
+                inputs.extend(footprints.clone());
+            }
+            Command::Overlap { boundary } => inputs.extend(boundary.clone()),
+            Command::History { snapshots, .. } => inputs.extend(snapshots.iter().cloned()),
+            Command::IdAudit { new } => inputs.extend(new.clone()),
+            Command::Lexisnexis {
+                boundary,
+                previous,
+                template,
+                ..
+            } => {
+                inputs.extend(boundary.clone());
+                inputs.extend(previous.clone());
+                inputs.extend(template.clone());
+            }
+            Command::Validate { registry, zips, .. } => {
+                inputs.extend(registry.clone());
+                inputs.extend(zips.clone());
+            }
+            _ => {}
+        }
+        inputs.extend(self.config.clone());
+        inputs
+    }
+
+    /// The `outputs` method returns the paths of the files the command may write, beginning with
+    /// the output file, followed by side outputs such as drift statistics.
+    pub fn outputs(&self) -> Vec<std::path::PathBuf> {
+        let mut outputs = vec![self.output.clone()];
+        match &self.command {
+            Command::Bench { stats, .. } => outputs.extend(stats.clone()),
+            Command::Drift {
+                stats,
+                report,
+                clusters,
+                ..
+            } => {
+                outputs.extend(stats.clone());
+                outputs.extend(report.clone());
+                outputs.extend(clusters.clone());
+            }
+            _ => {}
+        }
+        outputs
+    }
+
+    // Resolves each dataset name in `paths` as in `resolve`, pairing each path with an address
+    // format in `address_types`.  A single address format applies to paths without a dataset
+    // format, and paths without any format are paired with an empty string.
+    fn resolve_all(
+        config: &RunConfig,
+        paths: &mut [std::path::PathBuf],
+        address_types: &mut Vec<String>,
+    ) {
+        let shared = (address_types.len() == 1).then(|| address_types[0].clone());
+        let mut resolved = Vec::with_capacity(paths.len());
+        for (i, path) in paths.iter_mut().enumerate() {
+            let mut resolved_path = Some(path.clone());
+            let mut address_type = address_types.get(i).filter(|_| shared.is_none()).cloned();
+            Self::resolve(config, &mut resolved_path, &mut address_type);
+            if let Some(resolved_path) = resolved_path {
+                *path = resolved_path;
+            }
+            resolved.push(address_type.or(shared.clone()).unwrap_or_default());
+        }
+        *address_types = resolved;
+    }
+
+    // Replaces a dataset name in `path` with the path of the dataset, and fills in the address
+    // format of the dataset if `address_type` is not set.
+    fn resolve(
+        config: &RunConfig,
+        path: &mut Option<std::path::PathBuf>,
+        address_type: &mut Option<String>,
+    ) {
+        if let Some(name) = path.as_ref().and_then(|p| p.to_str())
+            && let Some(dataset) = config.dataset(name)
+        {
+            *path = Some(dataset.path.clone());
+            if address_type.is_none() {
+                *address_type = dataset.address_type.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn parse_commands() {
+        let cli =
+            Cli::try_parse_from(["destination", "duplicates", "--near", "-s", "addresses.csv"])
+                .unwrap();
+        assert_eq!(
+            cli.command,
+            Command::Duplicates {
+                near: true,
+                resolve: None
+            }
+        );
+        assert_eq!(cli.command.to_string(), "duplicates");
+        assert_eq!(cli.source, Some("addresses.csv".into()));
+        let cli = Cli::try_parse_from(["destination", "orphan_streets"]).unwrap();
+        assert_eq!(cli.command, Command::OrphanStreets);
+        let cli = Cli::try_parse_from([
+            "destination",
+            "compare",
+            "-b",
+            "-a",
+            "alternate.csv",
+            "-y",
+            "grants_pass",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Command::Compare {
+                business: true,
+                alternate: vec!["alternate.csv".into()],
+                alternate_type: vec!["grants_pass".to_string()],
+                mailing: None,
+                sheet: None,
+                columns: vec![],
+                incremental: false,
+                resume: false,
+                accept: None,
+                review: None,
+                blocking: None,
+                phonetic: false,
+                include: vec![],
+                exclude: vec![],
+                normalize: false,
+                bidirectional: false,
+                watch: false
+            }
+        );
+        assert!(Cli::try_parse_from(["destination", "comapre"]).is_err());
+        assert!(Cli::try_parse_from(["destination", "save", "--near"]).is_err());
+        let cli = Cli::try_parse_from([
+            "destination",
+            "business",
+            "--sheet",
+            "Licenses",
+            "--column",
+            "BusinessName=Name",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Command::Business {
+                alternate: None,
+                alternate_type: None,
+                mailing: None,
+                sheet: Some("Licenses".to_string()),
+                columns: vec!["BusinessName=Name".to_string()],
+            }
+        );
+        assert!(Cli::try_parse_from(["destination", "save", "--sheet", "Licenses"]).is_err());
+        assert!(Cli::try_parse_from(["destination"]).is_err());
+        // Bad arguments are returned to the caller rather than exiting the process.
+        let error = Cli::load_from(["destination", "save", "--near"]).unwrap_err();
+        assert!(matches!(error, AddressErrorKind::Arguments(_)));
+        assert!(Cli::load_from(["destination", "--help"]).is_err());
+    }
 }
@@ -0,0 +1,241 @@
+//! The `cluster` module looks for systematic drift in the results of the `drift` command, grouping
+//! nearby addresses displaced by similar distances in similar directions.  A cluster of shifted
+//! points, such as a whole subdivision moved 30 feet northeast, points to a projection or
+//! adjustment problem, where scattered displacements point to individual edits.
+use crate::{
+    AddressDeltas, AddressErrorKind, IntoCsv, IntoJson, Io, SpatialIndex, crs::geolocated,
+    from_csv, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tracing::info;
+
+/// The `CLUSTER_DISTANCE` constant holds the default distance between neighboring points of a
+/// drift cluster, in the units of the projected coordinates.
+pub const CLUSTER_DISTANCE: f64 = 500.0;
+
+/// The `CLUSTER_DISPLACEMENT` constant holds the default difference allowed between the
+/// displacements of neighboring points of a drift cluster, in the units of the deltas.
+pub const CLUSTER_DISPLACEMENT: f64 = 5.0;
+
+/// The `CLUSTER_SIZE` constant holds the default number of neighbors, counting the point itself,
+/// needed to start or extend a drift cluster.
+pub const CLUSTER_SIZE: usize = 5;
+
+/// The `CLUSTER_EXAMPLES` constant holds the number of example addresses listed for each
+/// cluster.
+pub const CLUSTER_EXAMPLES: usize = 3;
+
+geolocated! {
+    /// The `DriftCluster` struct describes a group of nearby addresses displaced alike.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct DriftCluster {
+        /// The `cluster` field holds the number of the cluster, starting at one for the largest.
+        pub cluster: usize,
+        /// The `count` field holds the number of addresses in the cluster.
+        pub count: usize,
+        /// The `x` field holds the X coordinate of the centroid of the cluster.
+        pub x: f64,
+        /// The `y` field holds the Y coordinate of the centroid of the cluster.
+        pub y: f64,
+        /// The `delta` field holds the distance of the mean displacement of the cluster.
+        pub delta: f64,
+        /// The `bearing` field holds the direction of the mean displacement of the cluster, in
+        /// degrees clockwise from grid north.
+        pub bearing: f64,
+        /// The `examples` field holds the labels of up to [`CLUSTER_EXAMPLES`] addresses in the
+        /// cluster, separated by semicolons.
+        pub examples: String,
+    }
+}
+
+/// The `DriftClusters` struct holds a vector of type [`DriftCluster`], largest first.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct DriftClusters(Vec<DriftCluster>);
+
+impl DriftClusters {
+    /// The `detect` method groups the displacements in `deltas` by density-based clustering
+    /// (DBSCAN).  Two points are neighbors if they lie within `distance` of each other and their
+    /// displacement vectors differ by no more than `displacement`.  A point with at least `size`
+    /// neighbors, counting itself, starts or extends a cluster.  Deltas without a bearing have
+    /// no displacement vector, and are left out.
+    pub fn detect(deltas: &AddressDeltas, distance: f64, displacement: f64, size: usize) -> Self {
+        let vectors = deltas
+            .iter()
+            .map(|d| {
+                d.bearing.map(|bearing| {
+                    let radians = bearing.to_radians();
+                    [d.delta * radians.sin(), d.delta * radians.cos()]
+                })
+            })
+            .collect::<Vec<Option<[f64; 2]>>>();
+        let index = SpatialIndex::from_cartesian(deltas, distance);
+        let neighbors = |i: usize, vector: [f64; 2]| {
+            index
+                .within([deltas[i].longitude, deltas[i].latitude], distance)
+                .into_iter()
+                .filter(|j| {
+                    vectors[*j].is_some_and(|other| {
+                        (other[0] - vector[0]).hypot(other[1] - vector[1]) <= displacement
+                    })
+                })
+                .collect::<Vec<usize>>()
+        };
+        // The cluster of each visited point, numbered from one, with zero for noise.
+        let mut labels: Vec<Option<usize>> = vec![None; deltas.len()];
+        let mut clusters = 0;
+        for i in 0..deltas.len() {
+            let Some(vector) = vectors[i] else {
+                continue;
+            };
+            if labels[i].is_some() {
+                continue;
+            }
+            let found = neighbors(i, vector);
+            if found.len() < size {
+                labels[i] = Some(0);
+                continue;
+            }
+            clusters += 1;
+            labels[i] = Some(clusters);
+            let mut queue = VecDeque::from(found);
+            while let Some(j) = queue.pop_front() {
+                match labels[j] {
+                    // A noise point within reach of a core point borders the cluster.
+                    Some(0) => labels[j] = Some(clusters),
+                    Some(_) => continue,
+                    None => {
+                        labels[j] = Some(clusters);
+                        if let Some(vector) = vectors[j] {
+                            let found = neighbors(j, vector);
+                            if found.len() >= size {
+                                queue.extend(found);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let mut members = vec![Vec::new(); clusters];
+        for (i, label) in labels.iter().enumerate() {
+            if let Some(cluster) = label.filter(|c| *c > 0) {
+                members[cluster - 1].push(i);
+            }
+        }
+        let mut records = members
+            .iter()
+            .map(|members| {
+                let count = members.len() as f64;
+                let (mut x, mut y, mut dx, mut dy) = (0.0, 0.0, 0.0, 0.0);
+                for i in members {
+                    let vector = vectors[*i].unwrap_or_default();
+                    x += deltas[*i].longitude / count;
+                    y += deltas[*i].latitude / count;
+                    dx += vector[0] / count;
+                    dy += vector[1] / count;
+                }
+                DriftCluster {
+                    cluster: 0,
+                    count: members.len(),
+                    x,
+                    y,
+                    delta: dx.hypot(dy),
+                    bearing: dx.atan2(dy).to_degrees().rem_euclid(360.0),
+                    examples: members
+                        .iter()
+                        .take(CLUSTER_EXAMPLES)
+                        .map(|i| deltas[*i].label.clone())
+                        .collect::<Vec<String>>()
+                        .join("; "),
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<DriftCluster>>();
+        records.sort_by_key(|r| std::cmp::Reverse(r.count));
+        for (i, record) in records.iter_mut().enumerate() {
+            record.cluster = i + 1;
+        }
+        info!(
+            "{} drift clusters of {} addresses found.",
+            records.len(),
+            records.iter().map(|r| r.count).sum::<usize>()
+        );
+        Self(records)
+    }
+
+    /// The `clustered` method returns the number of addresses in the clusters.
+    pub fn clustered(&self) -> usize {
+        self.iter().map(|c| c.count).sum()
+    }
+}
+
+impl IntoCsv<DriftClusters> for DriftClusters {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for DriftClusters {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddressDelta;
+
+    #[test]
+    fn drift_clusters() -> anyhow::Result<()> {
+        let delta = |i: usize, x: f64, y: f64, delta: f64, bearing: f64| AddressDelta {
+            label: format!("{i} RIVERSIDE AVE"),
+            delta,
+            latitude: y,
+            longitude: x,
+            bearing: Some(bearing),
+            ..Default::default()
+        };
+        // A subdivision shifted 30 feet northeast, with a little noise.
+        let mut records = (0..12)
+            .map(|i| {
+                let (x, y) = ((i % 4) as f64 * 50.0, (i / 4) as f64 * 50.0);
+                delta(i, x, y, 30.0 + (i % 3) as f64, 45.0 + (i % 2) as f64)
+            })
+            .collect::<Vec<AddressDelta>>();
+        // Scattered edits across town, displaced in different directions.
+        records.extend((0..6).map(|i| {
+            let offset = 5000.0 + i as f64 * 2000.0;
+            delta(100 + i, offset, offset, 40.0, i as f64 * 60.0)
+        }));
+        // A point among the subdivision moved the other way is not part of the cluster.
+        records.push(delta(200, 75.0, 75.0, 30.0, 225.0));
+        let deltas = AddressDeltas::new(records);
+        let clusters = DriftClusters::detect(
+            &deltas,
+            CLUSTER_DISTANCE,
+            CLUSTER_DISPLACEMENT,
+            CLUSTER_SIZE,
+        );
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].cluster, 1);
+        assert_eq!(clusters[0].count, 12);
+        assert_eq!(clusters.clustered(), 12);
+        assert!((clusters[0].delta - 31.0).abs() < 0.5);
+        assert!((clusters[0].bearing - 45.5).abs() < 0.5);
+        assert!((clusters[0].x - 75.0).abs() < 1e-9);
+        assert!((clusters[0].y - 50.0).abs() < 1e-9);
+        assert_eq!(clusters[0].examples.split("; ").count(), CLUSTER_EXAMPLES);
+        Ok(())
+    }
+}
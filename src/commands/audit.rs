@@ -0,0 +1,578 @@
+use super::{
+    Context, geolocate_records, is_csv, mapped_addresses, projected_radius, write_records,
+};
+use destination::{
+    ACCESS_DISTANCE, AccessPoints, AccessRecords, AccessStatus, Address, AddressGaps, AddressIter,
+    Addresses, COMMUNITY_FIELD, Centerlines, CommonAddresses, Disposition, DispositionRecords,
+    DuplicateRecords, ESN_BUFFER, EsnConflictKind, EsnConflicts, FOOTPRINT_DISTANCE,
+    FootprintRecords, FootprintStatus, Footprints, FromShapefile, GrantsPassAddresses,
+    GrantsPassSpatialAddress, GrantsPassSpatialAddresses, IntoBin, IntoCsv, Io,
+    JacksonCountyAddresses, JacksonCountySpatialAddress, JosephineCountyAddresses2024,
+    JosephineCountySpatialAddress2024, JosephineCountySpatialAddresses,
+    JosephineCountySpatialAddresses2024, MSAG_ESN_FIELD, Occupancy, OrphanStreets,
+    PARCEL_SITUS_FIELD, PARCEL_TAXLOT_FIELD, ParcelIssueKind, ParcelIssues, Parcels, RETIRED_YEARS,
+    RangeViolations, Resolution, RetiredAddresses, RunSummary, SIGN_STREET_FIELD, SignGapKind,
+    SignGaps, SignInventory, SpatialAddress, SpatialAddresses, SpatialAddressesRaw, SubaddressType,
+    UnitFormatRecords, UnitRange, VacancyRecords, ValidationRecords, ZIP_FIELD, ZoneLayer,
+    stream_duplicates,
+};
+use std::path::Path;
+use tracing::{info, trace, warn};
+
+// Reports streets in the source that have no addresses in the target.
+pub(super) fn orphan_streets(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    info!("Reading source records.");
+    let source_addresses = CommonAddresses::from(&ctx.source(false, run_summary)?[..]);
+
+    info!("Source records read: {} entries.", source_addresses.len());
+
+    trace!("Reading exclusion addresses.");
+    let target_addresses = CommonAddresses::from(&ctx.target(false, run_summary)?[..]);
+    info!(
+        "Exclusion records read: {} entries.",
+        target_addresses.len()
+    );
+    let mut orphans = OrphanStreets::from_addresses(&source_addresses, &target_addresses);
+    info!("{:?} orphan streets reported.", orphans.len());
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut orphans, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Resolves duplicate addresses in the source with the strategy `resolve`.
+pub(super) fn resolve_duplicates(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    near: bool,
+    resolve: &str,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    let resolution = match Resolution::from_name(resolve) {
+        Some(resolution) => resolution,
+        None => {
+            warn!("Unrecognized resolution strategy: {resolve}.  Marking all for review.");
+            Resolution::Manual
+        }
+    };
+    info!("Reading source records.");
+    let mut records = DispositionRecords::default();
+    match cli.source_type.as_deref() {
+        Some("grants_pass") => {
+            let source = GrantsPassSpatialAddresses::from_source(source_path)?;
+            info!("Source records read: {} entries.", source.len());
+            records = DispositionRecords::resolve(&source, near, resolution);
+        }
+        Some("josephine_county") => {
+            let source = JosephineCountySpatialAddresses2024::from_source(source_path)?;
+            info!("Source records read: {} entries.", source.len());
+            records = DispositionRecords::resolve(&source, near, resolution);
+        }
+        Some(_) => run_summary.missing("Source type carries no edit metadata."),
+        None => run_summary.missing("No source data type provided."),
+    }
+    for disposition in [Disposition::Keep, Disposition::Drop, Disposition::Review] {
+        run_summary.count(&disposition.to_string(), records.count(disposition));
+    }
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Reports duplicate addresses in the source, or addresses nearly alike when `near` is set.
+pub(super) fn duplicates(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    near: bool,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    if !near
+        && is_csv(source_path)
+        && let Some(source_type) = &cli.source_type
+        && let Some(mut duplicates) = stream_duplicate_addresses(source_type, source_path)?
+    {
+        info!("Duplicate records: {:?}", duplicates.len());
+        info!("Output file: {:?}", cli.output);
+        write_records(&mut duplicates, &cli.output, &cli.format, run_summary)?;
+        return Ok(());
+    }
+    info!("Reading source records.");
+    let source_addresses = CommonAddresses::from(&ctx.source(false, run_summary)?[..]);
+
+    info!("Source records read: {} entries.", source_addresses.len());
+    info!("Screening addresses for duplicate records.");
+    if near {
+        let mut duplicates = DuplicateRecords::near(&source_addresses);
+        info!("Duplicate records: {:?}", duplicates.len());
+        info!("Output file: {:?}", cli.output);
+        write_records(&mut duplicates, &cli.output, &cli.format, run_summary)?;
+    } else {
+        let mut duplicates = CommonAddresses::from(&source_addresses.filter("duplicate")[..]);
+        info!("Duplicate records: {:?}", duplicates.len());
+        info!("Output file: {:?}", cli.output);
+        write_records(&mut duplicates, &cli.output, &cli.format, run_summary)?;
+    }
+    Ok(())
+}
+
+// Reports subaddress identifiers in the source that do not follow the unit policy of the rules.
+pub(super) fn unit_format(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context {
+        cli, standardizer, ..
+    } = ctx;
+    info!("Reading source records.");
+    let source_addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    let policy = standardizer.unit_policy().copied().unwrap_or_default();
+    let mut records = UnitFormatRecords::check(&source_addresses, &policy);
+    run_summary.count("nonconforming", records.len());
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Expands the unit range `range` into individual unit addresses.
+pub(super) fn units(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    range: Option<&str>,
+    building: Option<&str>,
+    unit_type: Option<&str>,
+) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    let Some(range) = range else {
+        run_summary.missing("No unit range provided.");
+        return Ok(());
+    };
+    let units = UnitRange::parse(range)?;
+    info!("Units in range: {} entries.", units.len());
+    let subaddress_type = match unit_type {
+        Some(value) => match SubaddressType::match_mixed(value) {
+            Some(subaddress_type) => Some(subaddress_type),
+            None => {
+                run_summary.missing("Invalid unit type.");
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    info!("Reading source records.");
+    let source_addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    let Some(label) = building else {
+        run_summary.missing("No building address provided.");
+        return Ok(());
+    };
+    let label = label.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let Some(building) = source_addresses
+        .iter()
+        .find(|a| a.label().eq_ignore_ascii_case(&label))
+    else {
+        run_summary.missing("Building address not found in source.");
+        return Ok(());
+    };
+    let mut records = SpatialAddressesRaw::from(&units.expand(building, subaddress_type));
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Validates the source addresses against the FGDC standard and the optional reference layers.
+pub(super) fn validate(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    registry: Option<&Path>,
+    years: Option<u32>,
+    zips: Option<&Path>,
+    zip_field: Option<&str>,
+    community_field: Option<&str>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    info!("Reading source records.");
+    let mut source_addresses = CommonAddresses::default();
+    // Addresses are read with coordinates to test against zip code polygons.
+    let mut spatial_addresses = SpatialAddresses::default();
+    // City addresses carry stored concatenations to check against their components.
+    let mut city_addresses = None;
+    if zips.is_some() {
+        spatial_addresses = ctx.source(false, run_summary)?;
+        source_addresses = CommonAddresses::from(&spatial_addresses[..]);
+    } else if let Some(source_type) = &cli.source_type {
+        match source_type.as_str() {
+            "grants_pass" => {
+                let addresses = GrantsPassAddresses::from_csv(source_path.clone())?;
+                source_addresses = CommonAddresses::from(&addresses[..]);
+                city_addresses = Some(addresses);
+            }
+            "josephine_county" => {
+                source_addresses = CommonAddresses::from(
+                    &JosephineCountyAddresses2024::from_csv(source_path.clone())?[..],
+                )
+            }
+            "jackson_county" => {
+                source_addresses = CommonAddresses::from(
+                    &JacksonCountyAddresses::from_csv(source_path.clone())?[..],
+                )
+            }
+            "mapped" => {
+                source_addresses =
+                    CommonAddresses::from(&mapped_addresses(&cli.mapping, source_path.clone())?[..])
+            }
+            "data" => {
+                source_addresses =
+                    CommonAddresses::from(&SpatialAddresses::load(source_path.clone())?[..])
+            }
+            "common" => {
+                source_addresses =
+                    CommonAddresses::from(SpatialAddressesRaw::from_source(source_path.clone())?)
+            }
+            _ => run_summary.missing("Unrecognized file format."),
+        }
+    } else {
+        run_summary.missing("No source data type provided.");
+    }
+    info!("Source records read: {} entries.", source_addresses.len());
+    let mut records = ValidationRecords::validate(&source_addresses);
+    if let Some(path) = registry {
+        let registry = RetiredAddresses::from_csv(path)?;
+        info!("Retired addresses read: {} entries.", registry.len());
+        let today = chrono::Local::now().date_naive();
+        let years = years.unwrap_or(RETIRED_YEARS);
+        records.check_retired(&source_addresses, &registry, today, years);
+    }
+    if let Some(path) = zips {
+        let zip_field = zip_field.unwrap_or(ZIP_FIELD);
+        let community_field = community_field.unwrap_or(COMMUNITY_FIELD);
+        let zip_layer = ZoneLayer::from_path("zip", path, Some(zip_field))?;
+        let community_layer = ZoneLayer::from_path("community", path, Some(community_field))?;
+        records.check_zip(&spatial_addresses, &zip_layer, Some(&community_layer));
+    }
+    if cli.source_type.as_deref() == Some("grants_pass") {
+        let city_addresses = match city_addresses {
+            Some(addresses) => addresses,
+            None => GrantsPassAddresses::from_csv(source_path.clone())?,
+        };
+        records.check_complete(&city_addresses);
+    }
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("{:?} violations reported.", records.len());
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Associates each source address with its nearest access point in the target.
+pub(super) fn access(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    info!("Reading source addresses.");
+    let source_addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    info!("Reading access points.");
+    let mut points = AccessPoints::default();
+    if let Some(target) = &cli.target {
+        points = AccessPoints::from_csv(target)?;
+    } else {
+        run_summary.missing("No access point data specified.");
+    }
+    info!("Access points read: {} entries.", points.len());
+    let radius = projected_radius(cli).unwrap_or(ACCESS_DISTANCE);
+    let mut records = AccessRecords::associate(&source_addresses, &points, radius);
+    for status in [
+        AccessStatus::Associated,
+        AccessStatus::OtherStreet,
+        AccessStatus::Missing,
+    ] {
+        run_summary.count(&status.to_string(), records.count(status));
+    }
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut records, cli)?;
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Reports source addresses outside the address ranges of the centerlines in the target.
+pub(super) fn ranges(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    info!("Reading source addresses.");
+    let source_addresses = ctx.source(true, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    info!("Reading centerlines.");
+    let mut centerlines = Centerlines::default();
+    if let Some(target) = &cli.target {
+        centerlines = Centerlines::from_source(target)?;
+    } else {
+        run_summary.missing("No centerline data specified.");
+    }
+    info!("Centerline records read: {} entries.", centerlines.len());
+    let mut violations = RangeViolations::check(&source_addresses, &centerlines);
+    if let Some(filter) = &cli.filter {
+        violations = violations.filter(filter);
+    }
+    info!("{:?} violations reported.", violations.len());
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut violations, cli)?;
+    write_records(&mut violations, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Reports gaps in the address numbering of the source.
+pub(super) fn gaps(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    info!("Reading source addresses.");
+    let source_addresses = CommonAddresses::from(&ctx.source(true, run_summary)?[..]);
+    info!("Source records read: {} entries.", source_addresses.len());
+    let mut gaps = AddressGaps::from_addresses(&source_addresses);
+    if let Some(filter) = &cli.filter {
+        gaps = gaps.filter(filter);
+    }
+    info!("{:?} gaps reported.", gaps.len());
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut gaps, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Reports source addresses whose ESN disagrees with the ESN polygons.
+pub(super) fn esn(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    esn: Option<&Path>,
+    esn_field: Option<&str>,
+    buffer: Option<f64>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    let Some(esn) = esn else {
+        run_summary.missing("No ESN polygons provided.");
+        return Ok(());
+    };
+    let field = esn_field.unwrap_or(MSAG_ESN_FIELD);
+    let layer = ZoneLayer::from_path("esn", esn, Some(field))?;
+    info!("Reading source records.");
+    // Only the county schema records an ESN for each address.
+    let addresses = match cli.source_type.as_deref() {
+        Some("josephine_county") => JosephineCountySpatialAddresses2024::from_source(source_path)?
+            .iter()
+            .map(|a| (SpatialAddress::from(a), a.esn.clone()))
+            .collect::<Vec<(SpatialAddress, Option<String>)>>(),
+        _ => ctx
+            .source(false, run_summary)?
+            .iter()
+            .map(|a| (a.clone(), None))
+            .collect(),
+    };
+    info!("Source records read: {} entries.", addresses.len());
+    let mut conflicts = EsnConflicts::detect(&addresses, &layer, buffer.unwrap_or(ESN_BUFFER));
+    for kind in [
+        EsnConflictKind::Mismatch,
+        EsnConflictKind::Outside,
+        EsnConflictKind::Boundary,
+    ] {
+        let count = conflicts.iter().filter(|c| c.kind == kind).count();
+        run_summary.count(&kind.to_string(), count);
+    }
+    if let Some(filter) = &cli.filter {
+        conflicts = conflicts.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut conflicts, cli)?;
+    write_records(&mut conflicts, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Checks each source address against the building footprints.
+pub(super) fn footprints(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    footprints: Option<&Path>,
+    footprint_field: Option<&str>,
+    snap: bool,
+) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    let Some(footprints) = footprints else {
+        run_summary.missing("No building footprints provided.");
+        return Ok(());
+    };
+    let footprints = Footprints::from_path(footprints, footprint_field)?;
+    info!("Reading source records.");
+    let addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", addresses.len());
+    let mut records = FootprintRecords::check(
+        &addresses,
+        &footprints,
+        projected_radius(cli).unwrap_or(FOOTPRINT_DISTANCE),
+        snap,
+    );
+    for status in [FootprintStatus::Outside, FootprintStatus::Distant] {
+        run_summary.count(&status.to_string(), records.count(status));
+    }
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut records, cli)?;
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Checks the source addresses against the situs addresses of the parcels.
+pub(super) fn parcels(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    parcels: Option<&Path>,
+    taxlot_field: Option<&str>,
+    situs_field: Option<&str>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    let Some(parcels) = parcels else {
+        run_summary.missing("No parcel polygons provided.");
+        return Ok(());
+    };
+    let parcels = Parcels::from_path(
+        parcels,
+        taxlot_field.unwrap_or(PARCEL_TAXLOT_FIELD),
+        situs_field.unwrap_or(PARCEL_SITUS_FIELD),
+    )?;
+    info!("Reading source records.");
+    // Only the county schema prior to April of 2024 records a tax lot for each address.
+    let addresses = match cli.source_type.as_deref() {
+        Some("josephine_county_legacy") => JosephineCountySpatialAddresses::from_csv(source_path)?
+            .iter()
+            .map(|a| (SpatialAddress::from(a), a.taxlot.clone()))
+            .collect::<Vec<(SpatialAddress, Option<String>)>>(),
+        _ => ctx
+            .source(false, run_summary)?
+            .iter()
+            .map(|a| (a.clone(), None))
+            .collect(),
+    };
+    info!("Source records read: {} entries.", addresses.len());
+    let mut issues = ParcelIssues::check(&addresses, &parcels);
+    for kind in [
+        ParcelIssueKind::Taxlot,
+        ParcelIssueKind::Situs,
+        ParcelIssueKind::Outside,
+        ParcelIssueKind::Unaddressed,
+    ] {
+        let count = issues.iter().filter(|i| i.kind == kind).count();
+        run_summary.count(&kind.to_string(), count);
+    }
+    if let Some(filter) = &cli.filter {
+        issues = issues.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut issues, cli)?;
+    write_records(&mut issues, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Reports parcels with buildings but no address in the source.
+pub(super) fn vacancy(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    parcels: Option<&Path>,
+    taxlot_field: Option<&str>,
+    footprints: Option<&Path>,
+) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    let Some(parcels) = parcels else {
+        run_summary.missing("No parcel polygons provided.");
+        return Ok(());
+    };
+    let Some(footprints) = footprints else {
+        run_summary.missing("No building footprints provided.");
+        return Ok(());
+    };
+    let parcels = Parcels::from_path(
+        parcels,
+        taxlot_field.unwrap_or(PARCEL_TAXLOT_FIELD),
+        PARCEL_SITUS_FIELD,
+    )?;
+    let footprints = Footprints::from_path(footprints, None)?;
+    info!("Reading source records.");
+    let addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", addresses.len());
+    let mut records = VacancyRecords::infer(&addresses, &parcels, &footprints);
+    for occupancy in [Occupancy::Developed, Occupancy::Vacant, Occupancy::Unknown] {
+        run_summary.count(&occupancy.to_string(), records.count(occupancy));
+    }
+    run_summary.count("stale", records.stale());
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut records, cli)?;
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Reports streets with addresses in the source but no street sign in the inventory.
+pub(super) fn signs(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    signs: Option<&Path>,
+    street_field: Option<&str>,
+) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    let Some(signs) = signs else {
+        run_summary.missing("No sign inventory provided.");
+        return Ok(());
+    };
+    let field = street_field.unwrap_or(SIGN_STREET_FIELD);
+    let inventory = SignInventory::from_path(signs, field)?;
+    info!("Reading source records.");
+    // Signs follow city naming conventions.
+    let source_addresses = ctx.source(true, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    let mut gaps = SignGaps::from_inventory(&source_addresses, &inventory);
+    for kind in [SignGapKind::Unsigned, SignGapKind::Unaddressed] {
+        let count = gaps.iter().filter(|g| g.kind == kind).count();
+        run_summary.count(&kind.to_string(), count);
+    }
+    if let Some(filter) = &cli.filter {
+        gaps = gaps.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut gaps, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Reads the addresses of type `source_type` from the csv file at `path` in two streaming passes,
+// returning the duplicate addresses.  Returns `None` for address types that cannot be streamed,
+// which the caller screens in memory instead.
+fn stream_duplicate_addresses<P: AsRef<std::path::Path>>(
+    source_type: &str,
+    path: P,
+) -> Result<Option<CommonAddresses>, Io> {
+    info!("Streaming source records.");
+    let path = path.as_ref();
+    let duplicates = match source_type {
+        "grants_pass" => {
+            stream_duplicates(|| AddressIter::<GrantsPassSpatialAddress>::from_csv(path))?
+        }
+        "josephine_county" => {
+            stream_duplicates(|| AddressIter::<JosephineCountySpatialAddress2024>::from_csv(path))?
+        }
+        "jackson_county" => {
+            stream_duplicates(|| AddressIter::<JacksonCountySpatialAddress>::from_csv(path))?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(duplicates))
+}
@@ -0,0 +1,692 @@
+use super::{Context, geolocate_records, projected_radius, status_name, write_records};
+use destination::{
+    ASSET_CONFIDENT_DISTANCE, ASSET_DISTANCE, AddressSuggestions, Addresses, AssetAssignments,
+    AssetConfidence, BENCH_TOLERANCE, BenchRecords, Blocking, Boundary, BusinessLicenses,
+    BusinessMatchRecords, CHECKPOINT_INTERVAL, Centerlines, ExitStatus, FromShapefile,
+    GEOCODE_THRESHOLD, GeoAddresses, GeocodeInputs, GeocodeRecords, Geocoder, IntoBin, IntoCsv,
+    IntoGeoJson, IntoParquet, IntoPostgis, MatchCache, MatchConfig, MatchRecords, MatchScoring,
+    NEAREST_DISTANCE, NEAREST_NEIGHBORS, NearestRecords, NearestStatus, OverlapRecords,
+    ParcelPoints, RETIRED_YEARS, REVERSE_DISTANCE, ReconcileRecords, RetiredAddresses,
+    ReversePoints, ReverseRecords, RunSummary, Stewardship, TargetMatchRecords, TruthRecords,
+    block_on, http_post, is_parquet, push_match_records, to_json,
+};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+// Benchmarks the geocoder against the truth set at the source.
+pub(super) fn bench(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    tolerance: Option<f64>,
+    stats: Option<&Path>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    info!("Reading truth set.");
+    let truth = TruthRecords::from_csv(source_path.clone())?;
+    info!("Truth records read: {} entries.", truth.len());
+    let target = GeoAddresses::from(&ctx.target(true, run_summary)?[..]);
+    info!("Reference records read: {} entries.", target.len());
+    let threshold = cli.fuzzy.unwrap_or(GEOCODE_THRESHOLD);
+    let tolerance = tolerance.unwrap_or(BENCH_TOLERANCE);
+    let mut records = BenchRecords::bench(&truth, &target, threshold, tolerance);
+    let summary = records.summary();
+    eprintln!("{summary}");
+    if let Some(total) = summary.total() {
+        run_summary.count("correct", total.correct);
+        run_summary.count("incorrect", total.incorrect);
+        run_summary.count("unmatched", total.count - total.correct - total.incorrect);
+    }
+    if let Some(stats) = stats {
+        to_json(&summary, stats)?;
+        info!("Benchmark summary written to {:?}", stats);
+    }
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// The options of the `compare` command.
+pub(super) struct CompareOptions<'a> {
+    pub(super) alternate: &'a [PathBuf],
+    pub(super) alternate_type: &'a [String],
+    pub(super) incremental: bool,
+    pub(super) resume: bool,
+    pub(super) accept: Option<f64>,
+    pub(super) review: Option<f64>,
+    pub(super) blocking: Option<&'a str>,
+    pub(super) phonetic: bool,
+    pub(super) include: &'a [String],
+    pub(super) exclude: &'a [String],
+    pub(super) normalize: bool,
+    pub(super) bidirectional: bool,
+}
+
+// Matches the source addresses against the target addresses.
+pub(super) fn compare(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    options: CompareOptions,
+) -> anyhow::Result<()> {
+    let CompareOptions {
+        alternate,
+        alternate_type,
+        incremental,
+        resume,
+        accept,
+        review,
+        blocking,
+        phonetic,
+        include,
+        exclude,
+        normalize,
+        bidirectional,
+    } = options;
+    let Context {
+        cli,
+        aliases,
+        postgis,
+        table,
+        ..
+    } = ctx;
+    let geojson = ctx.geojson;
+    let parquet = ctx.parquet;
+    info!("Reading source records.");
+    let mut source = GeoAddresses::from(&ctx.source(false, run_summary)?[..]);
+    info!("Reading target records.");
+    let target = GeoAddresses::from(&ctx.target(true, run_summary)?[..]);
+    info!("Comparing records.");
+
+    info!("Remove retired addresses from source.");
+    info!("Source records prior: {}", source.len());
+    source.filter_field("active", "");
+    // source = GeoAddresses::from(&source.filter("active")[..]);
+    info!("Source records post: {}", source.len());
+
+    let threshold = match cli.fuzzy {
+        Some(threshold) if (0.0..=1.0).contains(&threshold) => {
+            info!("Fuzzy matching street names at threshold {threshold}.");
+            threshold
+        }
+        Some(threshold) => {
+            warn!("Fuzzy threshold {threshold} must be between 0 and 1.  Matching exactly.");
+            1.0
+        }
+        None => 1.0,
+    };
+    let mut scoring = MatchScoring::new(threshold)
+        .with_review(review)
+        .with_phonetic(phonetic);
+    if let Some(accept) = accept {
+        scoring = scoring.with_accept(accept);
+    }
+    if let Some(review) = review {
+        info!(
+            "Reviewing candidates scoring from {review} to {}.",
+            scoring.accept
+        );
+    }
+    if phonetic {
+        info!("Matching street names that sound alike.");
+    }
+    let mut config = MatchConfig::default().with_normalize(normalize);
+    for field in include {
+        config = config.include(field)?;
+    }
+    for field in exclude {
+        config = config.exclude(field)?;
+    }
+    if config != MatchConfig::default() {
+        info!("Matching with {config:?}.");
+        if !config.zip && blocking.is_some_and(|b| b.starts_with("zip")) {
+            warn!("Blocking by zip code misses matches when the zip code is excluded.");
+        }
+    }
+    scoring = scoring.with_config(config);
+    if aliases.is_some() && blocking.is_some() {
+        warn!("Blocking misses matches between aliased street names.");
+    }
+    scoring = scoring.with_aliases(aliases.clone());
+    if !alternate.is_empty() {
+        let mut targets = vec![(target_name(cli.target.as_ref(), 0), target)];
+        for (i, (path, address_type)) in alternate_targets(alternate, alternate_type)
+            .into_iter()
+            .enumerate()
+        {
+            let Some(address_type) = address_type else {
+                run_summary.missing("Alternate type required for alternate target.");
+                continue;
+            };
+            let addresses =
+                GeoAddresses::from(&ctx.load(address_type, path, true, run_summary)?[..]);
+            info!(
+                "Alternate target records read: {} entries.",
+                addresses.len()
+            );
+            let mut name = target_name(Some(path), i + 1);
+            if targets.iter().any(|(other, _)| *other == name) {
+                name = format!("{name}_{}", i + 1);
+            }
+            targets.push((name, addresses));
+        }
+        if incremental || resume || blocking.is_some() {
+            warn!("Comparing multiple targets without the match cache or blocking.");
+        }
+        if postgis.is_some() || parquet || geojson {
+            warn!(
+                "{} output is not available for multiple targets.  Writing csv.",
+                cli.format
+            );
+        }
+        let targets = targets
+            .iter()
+            .map(|(name, addresses)| (name.clone(), &addresses[..]))
+            .collect::<Vec<_>>();
+        info!("Comparing records against {} targets.", targets.len());
+        let mut records = TargetMatchRecords::compare(&source, &targets, cli.radius, &scoring);
+        for record in records.iter() {
+            run_summary.count(&status_name(&record.match_status), 1);
+        }
+        if let Some(filter) = &cli.filter {
+            records = records.filter(filter);
+        }
+        info!("Output file: {:?}", cli.output);
+        write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+        return Ok(());
+    }
+    if bidirectional {
+        if incremental || resume || blocking.is_some() {
+            warn!("Reconciling without the match cache or blocking.");
+        }
+        if postgis.is_some() || parquet || geojson {
+            warn!(
+                "{} output is not available for reconciliation.  Writing csv.",
+                cli.format
+            );
+        }
+        info!("Reconciling source and target records.");
+        let mut records = ReconcileRecords::compare(&source, &target, cli.radius, &scoring);
+        for record in records.iter() {
+            run_summary.count(&record.reconcile_status.to_string(), 1);
+        }
+        if let Some(filter) = &cli.filter {
+            records = records.filter(filter);
+        }
+        info!("Output file: {:?}", cli.output);
+        write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+        return Ok(());
+    }
+    // A resumable comparison picks up from the cache of the interrupted run.
+    let incremental = incremental || resume;
+    let blocking = match blocking.map(Blocking::parse) {
+        Some(Ok(strategy)) if !incremental => Some(strategy),
+        Some(Ok(_)) => {
+            warn!("Blocking is not available for incremental comparisons.");
+            None
+        }
+        Some(Err(e)) => {
+            warn!("{e}.  Comparing without blocking.");
+            None
+        }
+        None => None,
+    };
+    let mut match_records = match cli.radius {
+        _ if incremental => {
+            let mut cache_path = cli.output.clone().into_os_string();
+            cache_path.push(".cache");
+            let mut cache = MatchCache::default();
+            if std::path::Path::new(&cache_path).exists() {
+                match MatchCache::load(&cache_path) {
+                    Ok(loaded) => {
+                        info!("Match cache read: {} entries.", loaded.len());
+                        cache = loaded;
+                    }
+                    Err(e) => warn!("Match cache not read, comparing all records: {e}"),
+                }
+            } else {
+                info!("No match cache found, comparing all records.");
+            }
+            let checkpoint = if resume {
+                info!("Saving the match cache every {CHECKPOINT_INTERVAL} addresses.");
+                CHECKPOINT_INTERVAL
+            } else {
+                usize::MAX
+            };
+            let match_records = MatchRecords::compare_resumable(
+                &source,
+                &target,
+                cli.radius,
+                &scoring,
+                &mut cache,
+                checkpoint,
+                |cache| match cache.save(&cache_path) {
+                    Ok(()) => info!("Checkpoint written: {} entries.", cache.len()),
+                    Err(e) => warn!("Checkpoint not written: {e}"),
+                },
+            );
+            cache.save(&cache_path)?;
+            info!("Match cache written to {:?}", cache_path);
+            match_records
+        }
+        _ if let Some(strategy) = &blocking => {
+            MatchRecords::compare_blocked(&source, &target, strategy, &scoring)
+        }
+        Some(radius) => {
+            info!("Comparing addresses within {radius} meters.");
+            MatchRecords::compare_scored(&source, &target, Some(radius), &scoring)
+        }
+        None => MatchRecords::compare_scored(&source, &target, None, &scoring),
+    };
+    info!("{:?} records categorized.", match_records.len());
+    for record in match_records.iter() {
+        run_summary.count(&status_name(&record.match_status), 1);
+    }
+    for (field, count) in match_records.divergence() {
+        info!("Divergent {field}: {count} records.");
+    }
+    info!("Output file: {:?}", cli.output);
+    if let Some(dsn) = &postgis {
+        run_summary.write(match_records.len());
+        match_records.to_postgis(dsn, table)?;
+    } else if parquet {
+        run_summary.write(match_records.len());
+        match_records.to_parquet(&cli.output)?;
+    } else if geojson {
+        run_summary.write(match_records.len());
+        match_records.to_geojson(&cli.output)?;
+    } else {
+        write_records(&mut match_records, &cli.output, &cli.format, run_summary)?;
+    }
+    Ok(())
+}
+
+// Pushes the match status of the match records at the source to the feature service at the target.
+pub(super) fn push(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    token: Option<&str>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    info!("Reading match records.");
+    let match_records = if is_parquet(source_path) {
+        MatchRecords::from_parquet(source_path.clone())?
+    } else {
+        MatchRecords::from_csv(source_path.clone())?
+    };
+    info!("Source records read: {} entries.", match_records.len());
+    if let Some(target) = &cli.target {
+        let edits = block_on(push_match_records(
+            &match_records,
+            &target.to_string_lossy(),
+            token,
+            http_post,
+        ))??;
+        info!(
+            "Features added: {}, updated: {}, failed: {}.",
+            edits.added, edits.updated, edits.failed
+        );
+        run_summary.write(edits.added + edits.updated);
+        if edits.failed > 0 {
+            run_summary.fail(
+                ExitStatus::PartialFailure,
+                &format!("{} features failed to upload.", edits.failed),
+            );
+        }
+    } else {
+        run_summary.missing("Target feature service url (-t or --target) must be set.");
+    }
+    Ok(())
+}
+
+// Geocodes the address strings at the source against the reference addresses in the target.
+pub(super) fn geocode(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    centerlines: Option<&Path>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli,
+        source_path,
+        aliases,
+        grid,
+        ..
+    } = ctx;
+    let geojson = ctx.geojson;
+    info!("Reading addresses to geocode.");
+    let inputs = GeocodeInputs::from_csv(source_path.clone())?;
+    info!("Source records read: {} entries.", inputs.len());
+    info!("Reading reference records.");
+    let target = GeoAddresses::from(&ctx.target(true, run_summary)?[..]);
+    info!("Reference records read: {} entries.", target.len());
+    let threshold = cli.fuzzy.unwrap_or(GEOCODE_THRESHOLD);
+    let grid = grid.clone().map(|grid| grid.with_epsg(cli.target_epsg));
+    let mut geocoder = Geocoder::new(&target, threshold)
+        .with_aliases(aliases.as_ref())
+        .with_grid(grid.as_ref());
+    let lines = match centerlines {
+        Some(path) => Some(Centerlines::from_source(path)?),
+        None => None,
+    };
+    if let Some(lines) = &lines {
+        info!("Centerline records read: {} entries.", lines.len());
+        match cli.target_epsg {
+            Some(epsg) => geocoder = geocoder.with_centerlines(lines, epsg),
+            None => {
+                warn!("Centerlines require the EPSG code of their coordinates (--target-epsg).")
+            }
+        }
+    }
+    let mut records = GeocodeRecords::geocode_with(&inputs, &geocoder);
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("{:?} records geocoded.", records.len());
+    info!("Output file: {:?}", cli.output);
+    if geojson {
+        run_summary.write(records.len());
+        records.to_geojson(&cli.output)?;
+    } else {
+        write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    }
+    Ok(())
+}
+
+// Assigns each asset at the source the nearest address in the target.
+pub(super) fn assign_nearest(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    confident_distance: Option<f64>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    info!("Reading asset points.");
+    let assets = ReversePoints::from_source(source_path)?;
+    info!("Source records read: {} entries.", assets.len());
+    info!("Reading reference records.");
+    let target = ctx.target(true, run_summary)?;
+    info!("Reference records read: {} entries.", target.len());
+    let mut records = AssetAssignments::assign(
+        &assets,
+        &target,
+        projected_radius(cli).unwrap_or(ASSET_DISTANCE),
+        confident_distance.unwrap_or(ASSET_CONFIDENT_DISTANCE),
+    );
+    for confidence in [
+        AssetConfidence::High,
+        AssetConfidence::Low,
+        AssetConfidence::Unassigned,
+    ] {
+        run_summary.count(&confidence.to_string(), records.count(confidence));
+    }
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut records, cli)?;
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Suggests addresses for the parcel points at the source from the addresses in the target.
+pub(super) fn assign(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    registry: Option<&Path>,
+    years: Option<u32>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    info!("Reading parcel points.");
+    let parcels = ParcelPoints::from_csv(source_path.clone())?;
+    info!("Source records read: {} entries.", parcels.len());
+    info!("Reading reference records.");
+    let target = ctx.target(true, run_summary)?;
+    info!("Reference records read: {} entries.", target.len());
+    let mut suggestions = AddressSuggestions::suggest(&parcels, &target);
+    let missing = suggestions.iter().filter(|s| s.number.is_none()).count();
+    if missing > 0 {
+        warn!("No number suggested for {} parcels.", missing);
+    }
+    info!("{:?} suggestions made.", suggestions.len() - missing);
+    if let Some(path) = registry {
+        let registry = RetiredAddresses::from_csv(path)?;
+        info!("Retired addresses read: {} entries.", registry.len());
+        let today = chrono::Local::now().date_naive();
+        let years = years.unwrap_or(RETIRED_YEARS);
+        let retired = suggestions.check_retired(&registry, today, years);
+        if retired > 0 {
+            warn!("{retired} suggested numbers match recently retired addresses.");
+        }
+    }
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut suggestions, cli)?;
+    write_records(&mut suggestions, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Compares each source address with its nearest neighbors in the target.
+pub(super) fn nearest(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    neighbors: Option<usize>,
+) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    info!("Reading source addresses.");
+    let source_addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    info!("Reading reference records.");
+    let target = ctx.target(true, run_summary)?;
+    info!("Reference records read: {} entries.", target.len());
+    let mut records = NearestRecords::nearest(
+        &source_addresses,
+        &target,
+        neighbors.unwrap_or(NEAREST_NEIGHBORS),
+        projected_radius(cli).unwrap_or(NEAREST_DISTANCE),
+    );
+    for status in [
+        NearestStatus::Agrees,
+        NearestStatus::Misplaced,
+        NearestStatus::Disagrees,
+        NearestStatus::Isolated,
+    ] {
+        run_summary.count(&status.to_string(), records.count(status));
+    }
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut records, cli)?;
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Compares city and county addresses where their jurisdictions overlap.
+pub(super) fn overlap(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    boundary: Option<&Path>,
+) -> anyhow::Result<()> {
+    let Context { cli, aliases, .. } = ctx;
+    let Some(boundary) = boundary else {
+        run_summary.missing("No boundary provided.");
+        return Ok(());
+    };
+    let boundary = Boundary::from_path(boundary)?;
+    info!("Reading city addresses.");
+    let source_addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    info!("Reading county addresses.");
+    let target = ctx.target(true, run_summary)?;
+    info!("Reference records read: {} entries.", target.len());
+    let scoring = MatchScoring::new(cli.fuzzy.unwrap_or(1.0)).with_aliases(aliases.clone());
+    let mut records =
+        OverlapRecords::overlap(&source_addresses, &target, &boundary, cli.radius, &scoring);
+    for stewardship in [
+        Stewardship::CityAuthoritative,
+        Stewardship::CountyAuthoritative,
+        Stewardship::Conflicting,
+    ] {
+        run_summary.count(&stewardship.to_string(), records.count(stewardship));
+    }
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Reverse geocodes the points at the source against the addresses in the target.
+pub(super) fn reverse(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    let geojson = ctx.geojson;
+    info!("Reading points to reverse geocode.");
+    let points = ReversePoints::from_csv(source_path.clone())?;
+    info!("Source records read: {} entries.", points.len());
+    info!("Reading reference records.");
+    let target = ctx.target(true, run_summary)?;
+    info!("Reference records read: {} entries.", target.len());
+    let distance = projected_radius(cli).unwrap_or(REVERSE_DISTANCE);
+    let mut records = ReverseRecords::reverse(&points, &target, distance);
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("{:?} points located.", records.len());
+    info!("Output file: {:?}", cli.output);
+    if geojson {
+        run_summary.write(records.len());
+        records.to_geojson(&cli.output)?;
+    } else {
+        write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    }
+    Ok(())
+}
+
+// Matches the business licenses at the source, a csv file or xlsx workbook, against the target
+// addresses, writing [`BusinessMatchRecords`] grouped by business and address to the output.  With
+// an `alternate` target, each license takes the first exact match across the target and the
+// alternate, in that order.  With `mailing` addresses, licenses whose situs address has no exact
+// match are matched by their mailing address.  Workbooks are read from `sheet`, taking the license
+// fields from `columns`.
+pub(super) fn compare_businesses(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    alternates: &[(&PathBuf, Option<&str>)],
+    mailing: Option<&Path>,
+    sheet: Option<&str>,
+    columns: &[String],
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    info!("Matching business addresses.");
+    info!("Reading source records.");
+    let source_addresses = BusinessLicenses::from_source(source_path, sheet, columns)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    let mut source_addresses = source_addresses.deduplicate();
+    source_addresses.detype_subaddresses()?;
+    info!(
+        "Records deduplicated: {} remaining.",
+        source_addresses.len()
+    );
+    info!("Reading comparison records.");
+    let mut target_addresses = GeoAddresses::default();
+    if let Some(target) = &cli.target {
+        match &cli.target_type {
+            Some(target_type) => {
+                target_addresses =
+                    GeoAddresses::from(&ctx.load(target_type, target, false, run_summary)?[..])
+            }
+            None => run_summary.missing("Target type required for business licenses."),
+        }
+        info!("Target records read: {} entries.", target_addresses.len());
+    }
+    if !alternates.is_empty() {
+        info!("Comparing multiple targets.");
+    }
+    let mut alt_targets = Vec::with_capacity(alternates.len());
+    for (alternate, alternate_type) in alternates {
+        match alternate_type {
+            Some(target_type) => {
+                let alt_target =
+                    GeoAddresses::from(&ctx.load(target_type, alternate, false, run_summary)?[..]);
+                info!(
+                    "Alternate target records read: {} entries.",
+                    alt_target.len()
+                );
+                alt_targets.push(alt_target);
+            }
+            None => run_summary.missing("Alternate type required for alternate target."),
+        }
+    }
+    let mut targets = vec![&target_addresses[..]];
+    targets.extend(alt_targets.iter().map(|t| &t[..]));
+    let match_records = match mailing {
+        Some(mailing) => {
+            info!("Reading mailing addresses.");
+            let mut mailing = BusinessLicenses::from_source(mailing, sheet, columns)?.deduplicate();
+            mailing.detype_subaddresses()?;
+            info!("Mailing addresses read: {} entries.", mailing.len());
+            info!("Comparing records.");
+            BusinessMatchRecords::compare_mailing(&source_addresses, &mailing, &targets)
+        }
+        None if !alternates.is_empty() => {
+            info!("Comparing records.");
+            BusinessMatchRecords::compare_chain(&source_addresses, &targets)
+        }
+        None => {
+            info!("Comparing records.");
+            BusinessMatchRecords::compare(&source_addresses, &target_addresses)
+        }
+    };
+    info!("{:?} records categorized.", match_records.len());
+    let mut match_records = match_records.group();
+    for record in match_records.iter() {
+        run_summary.count(&status_name(&record.match_status()), 1);
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut match_records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Returns the name of the target at `path` for the columns of a multi-target comparison, the
+// file stem of the path, or "target_" followed by the position of the target if the path has no
+// file stem.
+fn target_name(path: Option<&std::path::PathBuf>, position: usize) -> String {
+    path.and_then(|p| p.file_stem())
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| format!("target_{position}"))
+}
+
+// Pairs each alternate target path in `alternate` with its address format in `alternate_type`,
+// by position.  A single address format applies to every alternate target, and an empty format,
+// left by a dataset without one, is `None`.
+pub(super) fn alternate_targets<'a>(
+    alternate: &'a [std::path::PathBuf],
+    alternate_type: &'a [String],
+) -> Vec<(&'a std::path::PathBuf, Option<&'a str>)> {
+    alternate
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let address_type = match alternate_type {
+                [shared] => Some(shared),
+                types => types.get(i),
+            };
+            (
+                path,
+                address_type.map(String::as_str).filter(|t| !t.is_empty()),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,156 @@
+use super::{Context, geolocate_records, projected_radius, projected_units, write_records};
+use destination::{
+    CLUSTER_DISPLACEMENT, CLUSTER_DISTANCE, CLUSTER_SIZE, Cartesian, DRIFT_BINS, DRIFT_OFFENDERS,
+    DriftClusters, DriftReport, IntoCsv, IntoGeoJson, IntoJson, RunSummary, STACK_TOLERANCE,
+    SpatialAddress, Stacks, Units, to_json,
+};
+use std::path::Path;
+use tracing::{info, trace, warn};
+
+// The arguments of the 'drift' command.  See [`Command::Drift`].
+pub(super) struct DriftOptions<'a> {
+    pub(super) threshold: Option<f64>,
+    pub(super) min_threshold: Option<f64>,
+    pub(super) stats: Option<&'a Path>,
+    pub(super) report: Option<&'a Path>,
+    pub(super) clusters: Option<&'a Path>,
+    pub(super) units: Option<&'a str>,
+    pub(super) stacks: bool,
+}
+
+// Measures the distance between matching addresses in the source and the target.
+pub(super) fn drift(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    options: DriftOptions,
+) -> anyhow::Result<()> {
+    let DriftOptions {
+        threshold,
+        min_threshold,
+        stats,
+        report,
+        clusters,
+        units,
+        stacks,
+    } = options;
+    let cli = &ctx.cli;
+    let geojson = ctx.geojson;
+    info!("Calculating spatial drift between datasets.");
+    trace!("Reading source addresses.");
+    let source_addresses = ctx.source(false, run_summary)?;
+    trace!("Reading target addresses.");
+    let mut target_addresses = ctx.target(false, run_summary)?;
+
+    match (cli.source_epsg, cli.target_epsg) {
+        (Some(source_epsg), Some(target_epsg)) => {
+            info!("Reprojecting target from EPSG:{target_epsg} to EPSG:{source_epsg}.");
+            target_addresses = target_addresses.with_epsg(target_epsg);
+            target_addresses.reproject(source_epsg)?;
+        }
+        (None, None) => {}
+        _ => warn!(
+            "Both --source-epsg and --target-epsg are needed to reproject.  Using coordinates as read."
+        ),
+    }
+
+    // Measure every matching pair, so the summary covers addresses below the threshold.
+    let mut deltas = if stacks {
+        Stacks::group(&source_addresses, STACK_TOLERANCE).deltas(
+            &target_addresses,
+            f64::MIN,
+            projected_radius(cli),
+        )
+    } else {
+        match projected_radius(cli) {
+            Some(radius) => <SpatialAddress as Cartesian>::deltas_within(
+                &source_addresses,
+                &target_addresses,
+                f64::MIN,
+                radius,
+            ),
+            None => <SpatialAddress as Cartesian>::deltas(
+                &source_addresses,
+                &target_addresses,
+                f64::MIN,
+            ),
+        }
+    };
+    let projected = projected_units(cli);
+    let units = match units {
+        Some("feet") => Units::Feet,
+        Some("meters") => Units::Meters,
+        Some(units) => {
+            warn!("Unrecognized units: {units}.  Reporting in {projected}.");
+            projected
+        }
+        None => projected,
+    };
+    if units != projected {
+        info!("Converting distances from {projected} to {units}.");
+        deltas.scale(projected.meters() / units.meters());
+    }
+    let matched = deltas.len();
+    if let Some(min) = min_threshold {
+        deltas.retain(|d| d.delta > min);
+        run_summary.count("jitter", matched - deltas.len());
+    }
+    let threshold = threshold.unwrap_or(99.0);
+    let summary = deltas.summary(threshold, units);
+    eprintln!("{summary}");
+    if let Some(stats) = stats {
+        to_json(&summary, stats)?;
+        info!("Drift summary written to {:?}", stats);
+    }
+    if let Some(path) = report {
+        let report = DriftReport::new(&deltas, threshold, units, DRIFT_BINS);
+        eprint!("{}", report.histogram());
+        report.save(path)?;
+        info!("Drift report written to {:?}", path);
+    }
+    if let Some(path) = clusters {
+        let mut clusters = DriftClusters::detect(
+            &deltas,
+            CLUSTER_DISTANCE,
+            CLUSTER_DISPLACEMENT,
+            CLUSTER_SIZE,
+        );
+        run_summary.count("clustered", clusters.clustered());
+        for cluster in clusters.iter().take(DRIFT_OFFENDERS) {
+            run_summary.highlight(format!(
+                "Cluster {}: {} addresses moved {:.1} {units} at {:.0}°",
+                cluster.cluster, cluster.count, cluster.delta, cluster.bearing
+            ));
+        }
+        geolocate_records(&mut clusters, cli)?;
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            clusters.to_json(path)?;
+        } else {
+            clusters.to_csv(path)?;
+        }
+        info!("Drift clusters written to {:?}", path);
+    }
+    deltas.retain(|d| d.delta > threshold);
+    run_summary.count("matched", matched);
+    run_summary.count("over_threshold", summary.over_threshold);
+    for delta in deltas.offenders(DRIFT_OFFENDERS) {
+        let bearing = delta
+            .bearing
+            .map(|b| format!(" at {b:.0}°"))
+            .unwrap_or_default();
+        run_summary.highlight(format!(
+            "{}: {:.1} {units}{bearing}",
+            delta.label, delta.delta
+        ));
+    }
+    geolocate_records(&mut deltas, cli)?;
+    if geojson {
+        run_summary.write(deltas.len());
+        deltas.to_geojson(&cli.output)?;
+    } else {
+        write_records(&mut deltas, &cli.output, &cli.format, run_summary)?;
+    }
+    Ok(())
+}
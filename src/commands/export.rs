@@ -0,0 +1,579 @@
+use super::{Context, is_csv, mapped_addresses, write_records};
+use destination::{
+    Address, AddressErrorKind, AddressIter, AddressService, Boundary, BusinessMatchRecords,
+    Cartesian, Cli, CommonAddresses, DataHeader, EnrichedAddresses, ExitStatus, FromShapefile,
+    GEOCODE_THRESHOLD, GeoAddresses, Geographic, GrantsPassAddresses, GrantsPassSpatialAddress,
+    IntoBin, IntoCsv, IntoGeoJson, IntoJson, IntoParquet, IntoPostgis, IntoSqlite,
+    JacksonCountyAddresses, JacksonCountySpatialAddress, JosephineCountyAddresses,
+    JosephineCountyAddresses2024, JosephineCountySpatialAddress2024, LabelColumn, LabeledAddresses,
+    LexisNexis, LexisNexisChangeKind, LexisNexisTemplate, MSAG_ESN_FIELD, Manifest,
+    MatchPartialRecord, MatchRecord, MatchRecords, Msag, PROVENANCE_EXTENSION, Profile, Provenance,
+    RunSummary, SUGGESTION_LIMIT, SpatialAddress, SpatialAddresses, SpatialAddressesRaw,
+    StandardizedAddresses, Standardizer, StreetDictionary, ZoneLayer, to_stream,
+};
+use std::path::Path;
+use tracing::{info, trace, warn};
+
+// Filters the match records at the source, keeping the records that pass `--filter`.
+pub(super) fn filter(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    let geojson = ctx.geojson;
+    if let Some(filter) = &cli.filter {
+        info!("Filtering records.");
+        if let Some(source) = &cli.source_type {
+            match source.as_str() {
+                "business" => {
+                    let match_records = BusinessMatchRecords::from_csv(source_path.clone())?;
+                    info!("Source records read: {} entries.", match_records.len());
+                    let mut filtered = match_records.filter(filter);
+                    info!("Records remaining: {} entries.", filtered.len());
+                    write_records(&mut filtered, &cli.output, &cli.format, run_summary)?;
+                }
+                "partial" => {
+                    if !MatchPartialRecord::FILTERS.contains(&filter.as_str()) {
+                        info!("Invalid filter provided.");
+                    }
+                    let mut records = AddressIter::<MatchPartialRecord>::from_csv(source_path)?;
+                    let remaining = to_stream(
+                        records.by_ref().filter(|r| r.passes(filter)),
+                        &cli.output,
+                        &cli.format,
+                    )?;
+                    records.check()?;
+                    info!("Source records read: {} entries.", records.read());
+                    info!("Records remaining: {remaining} entries.");
+                    run_summary.write(remaining);
+                }
+                "full" if geojson => {
+                    let match_records = MatchRecords::from_csv(source_path.clone())?;
+                    info!("Source records read: {} entries.", match_records.len());
+                    let filtered = match_records.filter(filter);
+                    info!("Records remaining: {} entries.", filtered.len());
+                    run_summary.write(filtered.len());
+                    filtered.to_geojson(&cli.output)?;
+                }
+                "full" => {
+                    if !MatchRecord::FILTERS.contains(&filter.as_str()) {
+                        info!("Invalid filter provided.");
+                    }
+                    let mut records = AddressIter::<MatchRecord>::from_csv(source_path)?;
+                    let remaining = to_stream(
+                        records.by_ref().filter(|r| r.passes(filter)),
+                        &cli.output,
+                        &cli.format,
+                    )?;
+                    records.check()?;
+                    info!("Source records read: {} entries.", records.read());
+                    info!("Records remaining: {remaining} entries.");
+                    run_summary.write(remaining);
+                }
+                _ => warn!("Unrecognized source type: {source}"),
+            }
+        }
+    } else {
+        run_summary.missing("Filter parameter (-f or --filter) must be set.");
+    }
+    Ok(())
+}
+
+// Writes the LexisNexis address table for the source, less the exclusion addresses in the target.
+pub(super) fn lexisnexis(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    boundary: Option<&Path>,
+    previous: Option<&Path>,
+    template: Option<&Path>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    let template = match template {
+        Some(path) => Some(LexisNexisTemplate::from_toml(path)?),
+        None => None,
+    };
+    if template.is_some() && cli.format != "csv" {
+        warn!(
+            "Output templates apply to csv output.  Writing {}.",
+            cli.format
+        );
+    }
+    // Addresses are read with coordinates to test against a boundary.
+    let boundary = match boundary {
+        Some(path) => Some(Boundary::from_path(path)?),
+        None => None,
+    };
+    let spatial = boundary.is_some();
+    info!("Reading source records.");
+    let mut source_addresses = CommonAddresses::default();
+    let mut spatial_addresses = SpatialAddresses::default();
+    if spatial {
+        spatial_addresses = ctx.source(false, run_summary)?;
+    } else if let Some(source_type) = &cli.source_type {
+        // Without a boundary, addresses are read without coordinates.
+        match source_type.as_str() {
+            "grants_pass" => {
+                source_addresses =
+                    CommonAddresses::from(&GrantsPassAddresses::from_csv(source_path.clone())?[..])
+            }
+            "josephine_county" => {
+                source_addresses = CommonAddresses::from(
+                    &JosephineCountyAddresses2024::from_csv(source_path.clone())?[..],
+                )
+            }
+            "jackson_county" => {
+                source_addresses = CommonAddresses::from(
+                    &JacksonCountyAddresses::from_csv(source_path.clone())?[..],
+                )
+            }
+            "mapped" => {
+                source_addresses =
+                    CommonAddresses::from(&mapped_addresses(&cli.mapping, source_path.clone())?[..])
+            }
+            "data" => {
+                source_addresses =
+                    CommonAddresses::from(&SpatialAddresses::load(source_path.clone())?[..])
+            }
+            "common" => {
+                source_addresses =
+                    CommonAddresses::from(SpatialAddressesRaw::from_source(source_path.clone())?)
+            }
+            _ => run_summary.missing("Unrecognized file format."),
+        }
+    }
+
+    info!(
+        "Source records read: {} entries.",
+        source_addresses.len() + spatial_addresses.len()
+    );
+
+    trace!("Reading exclusion addresses.");
+    let mut target_addresses = CommonAddresses::default();
+    if let Some(target) = &cli.target {
+        if let Some(target_type) = &cli.target_type {
+            match target_type.as_str() {
+                "grants_pass" => {
+                    target_addresses =
+                        CommonAddresses::from(&GrantsPassAddresses::from_csv(target)?[..])
+                }
+                "josephine_county" => {
+                    target_addresses =
+                        CommonAddresses::from(&JosephineCountyAddresses::from_csv(target)?[..])
+                }
+                "jackson_county" => {
+                    target_addresses =
+                        CommonAddresses::from(&JacksonCountyAddresses::from_csv(target)?[..])
+                }
+                "mapped" => {
+                    target_addresses =
+                        CommonAddresses::from(&mapped_addresses(&cli.mapping, target)?[..])
+                }
+                "data" => {
+                    target_addresses = CommonAddresses::from(&SpatialAddresses::load(target)?[..])
+                }
+                "common" => {
+                    target_addresses =
+                        CommonAddresses::from(SpatialAddressesRaw::from_source(target)?)
+                }
+                _ => run_summary.missing("Invalid target data type."),
+            }
+        } else {
+            run_summary.missing("No target data type provided.");
+        }
+    } else if !spatial {
+        run_summary.missing("No target data specified.");
+    }
+    info!(
+        "Exclusion records read: {} entries.",
+        target_addresses.len()
+    );
+    let mut lx = match &boundary {
+        Some(boundary) => {
+            LexisNexis::from_boundary(&spatial_addresses, boundary, &target_addresses)?
+        }
+        None => LexisNexis::from_addresses(&source_addresses, &target_addresses)?,
+    };
+    if let Some(path) = previous {
+        let previous = LexisNexis::from_csv(path)?;
+        info!("Previous records read: {} entries.", previous.len());
+        let mut changes = lx.diff(&previous);
+        info!(
+            "Rows added: {}, removed: {}, changed: {}.",
+            changes.count(LexisNexisChangeKind::Add),
+            changes.count(LexisNexisChangeKind::Remove),
+            changes.count(LexisNexisChangeKind::Change)
+        );
+        write_lexisnexis(
+            &mut changes,
+            &cli.output,
+            &cli.format,
+            template.as_ref(),
+            run_summary,
+        )?;
+        // The full table is kept for comparison with the next submission.
+        let stem = cli.output.file_stem().unwrap_or_default().to_string_lossy();
+        let mut full = cli.output.with_file_name(format!("{stem}_full"));
+        if let Some(extension) = cli.output.extension() {
+            full.set_extension(extension);
+        }
+        write_lexisnexis(&mut lx, &full, &cli.format, template.as_ref(), run_summary)?;
+        info!("Full table written to {:?}", full);
+    } else {
+        write_lexisnexis(
+            &mut lx,
+            &cli.output,
+            &cli.format,
+            template.as_ref(),
+            run_summary,
+        )?;
+    }
+    Ok(())
+}
+
+// Serves the source addresses for matching and geocoding over HTTP, and optionally gRPC.
+pub(super) fn serve(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    bind: &str,
+    grpc: Option<&str>,
+) -> anyhow::Result<()> {
+    let Context { cli, grid, .. } = ctx;
+    info!("Reading reference records.");
+    let source = GeoAddresses::from(&ctx.source(true, run_summary)?[..]);
+    info!("Reference records read: {} entries.", source.len());
+    if !source.is_empty() {
+        let threshold = cli.fuzzy.unwrap_or(GEOCODE_THRESHOLD);
+        let mut service = AddressService::new(source.to_vec(), threshold);
+        if let Some(grid) = grid {
+            service = service.with_grid(grid.clone().with_epsg(cli.source_epsg));
+        }
+        match grpc {
+            #[cfg(feature = "grpc")]
+            Some(grpc) => service.serve_grpc(bind, grpc)?,
+            #[cfg(not(feature = "grpc"))]
+            Some(_) => {
+                warn!("Serving gRPC requires the grpc feature.  Serving HTTP only.");
+                service.serve(bind)?;
+            }
+            None => service.serve(bind)?,
+        }
+    }
+    Ok(())
+}
+
+// Reads the source addresses and writes them in the output format.
+pub(super) fn save(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context {
+        cli,
+        source_path,
+        standardizer,
+        postgis,
+        table,
+        ..
+    } = ctx;
+    let geojson = ctx.geojson;
+    let parquet = ctx.parquet;
+    let sqlite = ctx.sqlite;
+    // Streamed records keep the geographic coordinates of the source, so saves that compute
+    // them from the projected coordinates are read into memory.
+    if matches!(cli.format.as_str(), "json" | "ndjson")
+        && cli.source_epsg.is_none()
+        && is_csv(source_path)
+        && let Some(source_type) = &cli.source_type
+        && let Some(count) = stream_save(source_type, source_path, cli, standardizer)?
+    {
+        info!("Addresses written to {:?}: {count} entries.", &cli.output);
+        run_summary.write(count);
+        return Ok(());
+    }
+    info!("Loading and saving addresses...");
+    trace!("Reading source addresses.");
+    let mut source_addresses = ctx.source(true, run_summary)?;
+    if let Some(epsg) = cli.source_epsg {
+        info!("Computing geographic coordinates from EPSG:{epsg}.");
+        source_addresses = source_addresses.with_epsg(epsg);
+        source_addresses.geolocate()?;
+    }
+    run_summary.write(source_addresses.len());
+    if source_addresses.is_empty() {
+        warn!("All records dropped.  Aborting save.");
+    } else if let Some(dsn) = &postgis {
+        source_addresses.to_postgis(dsn, table)?;
+        info!("Addresses written to table {table}");
+    } else if sqlite && let Some(source_type) = &cli.source_type {
+        // One table per address type, named after the source type.
+        source_addresses.to_sqlite(&cli.output, source_type)?;
+        info!(
+            "Addresses written to table {source_type} in {:?}",
+            &cli.output
+        );
+    } else if parquet {
+        source_addresses.to_parquet(&cli.output)?;
+        info!("Addresses written to {:?}", &cli.output);
+    } else if cli.format == "json" {
+        source_addresses.to_json(&cli.output)?;
+        info!("Addresses written to {:?}", &cli.output);
+    } else if cli.format == "ndjson" {
+        source_addresses.to_ndjson(&cli.output)?;
+        info!("Addresses written to {:?}", &cli.output);
+    } else if geojson {
+        source_addresses.to_geojson(&cli.output)?;
+        info!("Addresses written to {:?}", &cli.output);
+    } else {
+        source_addresses.save(&cli.output)?;
+        info!("Addresses saved to {:?}", &cli.output);
+    }
+    Ok(())
+}
+
+// Tags the source addresses with the attributes of the polygon layers that contain them.
+pub(super) fn enrich(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    layers: &[String],
+) -> anyhow::Result<()> {
+    let Context { cli, grid, .. } = ctx;
+    if layers.is_empty() && grid.is_none() {
+        run_summary.missing("No polygon layers provided.");
+        return Ok(());
+    }
+    let mut layers = layers
+        .iter()
+        .map(|spec| ZoneLayer::parse(spec))
+        .collect::<Result<Vec<ZoneLayer>, AddressErrorKind>>()?;
+    if let Some(grid) = &grid {
+        layers.push(grid.layer.clone());
+    }
+    info!("Reading source records.");
+    let source_addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    let mut records = EnrichedAddresses::new(&source_addresses, &layers);
+    for layer in &layers {
+        run_summary.count(
+            &format!("outside {}", layer.name),
+            records.outside(&layer.name),
+        );
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Writes the source addresses with the label columns described by `labels`.
+pub(super) fn labels(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    labels: &[String],
+) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    if labels.is_empty() {
+        run_summary.missing("No label templates provided.");
+        return Ok(());
+    }
+    let columns = labels
+        .iter()
+        .map(|spec| LabelColumn::parse(spec))
+        .collect::<Result<Vec<LabelColumn>, AddressErrorKind>>()?;
+    info!("Reading source records.");
+    let source_addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    let mut records = LabeledAddresses::new(&source_addresses, &columns);
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Writes the Master Street Address Guide for the source addresses.
+pub(super) fn msag(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    esn: Option<&Path>,
+    esn_field: Option<&str>,
+) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    let Some(esn) = esn else {
+        run_summary.missing("No ESN polygons provided.");
+        return Ok(());
+    };
+    let field = esn_field.unwrap_or(MSAG_ESN_FIELD);
+    let layer = ZoneLayer::from_path("esn", esn, Some(field))?;
+    info!("Reading source records.");
+    let source_addresses = ctx.source(false, run_summary)?;
+    info!("Source records read: {} entries.", source_addresses.len());
+    let mut rows = Msag::from_addresses(&source_addresses, &layer);
+    run_summary.count(
+        "outside ESN",
+        rows.iter()
+            .filter(|r| r.esn.is_empty())
+            .map(|r| r.count)
+            .sum(),
+    );
+    if let Some(filter) = &cli.filter {
+        rows = rows.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut rows, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Prints the provenance sidecar of the source.
+pub(super) fn provenance(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context { source_path, .. } = ctx;
+    let sidecar = if source_path
+        .to_string_lossy()
+        .ends_with(PROVENANCE_EXTENSION)
+    {
+        source_path.clone()
+    } else {
+        Provenance::sidecar(source_path)
+    };
+    if !sidecar.is_file() {
+        run_summary.missing(&format!("No provenance found at {sidecar:?}."));
+        return Ok(());
+    }
+    let provenance = Provenance::read(&sidecar)?;
+    println!("{provenance}");
+    for (digest, check) in provenance.verify() {
+        let reason = match check {
+            "changed" => "has changed since the output was written",
+            _ => "is missing",
+        };
+        run_summary.fail(ExitStatus::Failure, &format!("{:?} {reason}.", digest.path));
+    }
+    Ok(())
+}
+
+// Writes the source addresses with labels standardized to `profile`.
+pub(super) fn standardize(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    profile: Option<&str>,
+) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    info!("Reading source addresses.");
+    let source_addresses = CommonAddresses::from(&ctx.source(true, run_summary)?[..]);
+    info!("Source records read: {} entries.", source_addresses.len());
+    let profile = match profile {
+        Some("fgdc") | None => Profile::Fgdc,
+        Some("usps") => Profile::Usps,
+        Some(profile) => {
+            warn!("Unrecognized profile: {profile}.  Using {}.", Profile::Fgdc);
+            Profile::Fgdc
+        }
+    };
+    info!("Standardizing labels with the {profile} profile.");
+    let mut records = StandardizedAddresses::from_addresses(&source_addresses, profile);
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Writes the street dictionary of the source, or suggestions for the street named by `--filter`.
+pub(super) fn streets(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context { cli, .. } = ctx;
+    info!("Reading source addresses.");
+    let source_addresses = CommonAddresses::from(&ctx.source(true, run_summary)?[..]);
+    info!("Source records read: {} entries.", source_addresses.len());
+    let mut dictionary = StreetDictionary::from_addresses(&source_addresses);
+    info!("Output file: {:?}", cli.output);
+    if let Some(street) = &cli.filter {
+        let mut suggestions = dictionary.suggest(street, SUGGESTION_LIMIT);
+        info!("{:?} suggestions for {}.", suggestions.len(), street);
+        write_records(&mut suggestions, &cli.output, &cli.format, run_summary)?;
+    } else {
+        write_records(&mut dictionary, &cli.output, &cli.format, run_summary)?;
+    }
+    Ok(())
+}
+
+// Runs the steps of the manifest at the source.
+pub(super) fn run_manifest(ctx: &Context, _run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context { source_path, .. } = ctx;
+    info!("Running manifest steps.");
+    Manifest::from_toml(source_path)?.run()?;
+    info!("Manifest complete.");
+    Ok(())
+}
+
+// Writes LexisNexis `records` to `path` with the columns of `template` when writing csv, and as
+// `write_records` does otherwise.
+fn write_lexisnexis<T, U, P>(
+    records: &mut T,
+    path: P,
+    format: &str,
+    template: Option<&LexisNexisTemplate>,
+    run_summary: &mut RunSummary,
+) -> Result<(), AddressErrorKind>
+where
+    T: IntoCsv<T> + IntoJson + std::ops::Deref<Target = Vec<U>>,
+    U: serde::Serialize + Default,
+    P: AsRef<std::path::Path>,
+{
+    match template {
+        Some(template) if format == "csv" => {
+            run_summary.write(records.len());
+            template.to_csv(records, path)
+        }
+        _ => write_records(records, path, format, run_summary),
+    }
+}
+
+// Streams the addresses of type `source_type` from the csv file at `path` to the output file as
+// JSON, one record at a time, returning the number of addresses written.  Returns `None` for
+// address types that cannot be streamed, which the caller saves in memory instead.
+fn stream_save<P: AsRef<std::path::Path>>(
+    source_type: &str,
+    path: P,
+    cli: &Cli,
+    standardizer: &Standardizer,
+) -> Result<Option<usize>, AddressErrorKind> {
+    info!("Streaming addresses to {:?}", &cli.output);
+    let count = match source_type {
+        "grants_pass" => stream_spatial::<GrantsPassSpatialAddress, _>(path, cli, |_| {})?,
+        "josephine_county" => {
+            stream_spatial::<JosephineCountySpatialAddress2024, _>(path, cli, |address| {
+                standardizer.apply(address)
+            })?
+        }
+        "jackson_county" => stream_spatial::<JacksonCountySpatialAddress, _>(path, cli, |_| {})?,
+        _ => return Ok(None),
+    };
+    Ok(Some(count))
+}
+
+// Streams addresses of type `T` from the csv file at `path` to the output file, converting each
+// to a spatial address and passing it through `edit` before writing.
+fn stream_spatial<T, P>(
+    path: P,
+    cli: &Cli,
+    edit: impl Fn(&mut SpatialAddress),
+) -> Result<usize, AddressErrorKind>
+where
+    T: serde::de::DeserializeOwned + Address + Cartesian + Geographic + Clone,
+    P: AsRef<std::path::Path>,
+{
+    let mut records = AddressIter::<T>::from_csv(path)?;
+    let count = to_stream(
+        records.by_ref().map(|address| {
+            let mut address = SpatialAddress::from(&address);
+            edit(&mut address);
+            address
+        }),
+        &cli.output,
+        &cli.format,
+    )?;
+    records.check()?;
+    Ok(count)
+}
+
+// Prints the header of the data file at the source.
+pub(super) fn inspect(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    match DataHeader::read(&ctx.source_path)? {
+        Some(header) => println!("{header}"),
+        None => run_summary.missing(
+            "No data header found.  The file predates versioned data files, or is not a data file.",
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,248 @@
+use super::{Context, geolocate_records, read_global_ids, status_name, write_records};
+use destination::{
+    ChangeKind, DIFF_KEY, EditorActivities, FromShapefile, GrantsPassSpatialAddresses,
+    HISTORY_THRESHOLD, IdChangeKind, IdChanges, IntoCsv, LayerChanges, LayerVintage,
+    LifecycleRecords, MatchScoring, MatchStatus, NoteAction, NoteQueue, RelationshipRecords,
+    RetiredAddresses, RunSummary, Vintage,
+};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+// Reports the changes between the vintages `old` and `new` of a layer.
+pub(super) fn diff(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    old: Option<&Path>,
+    new: Option<&Path>,
+    key: Option<&str>,
+    ignore: &[String],
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    let Some(new) = new else {
+        run_summary.missing("No later vintage provided.");
+        return Ok(());
+    };
+    let key = key.unwrap_or(DIFF_KEY);
+    let old = LayerVintage::from_path(old.unwrap_or(source_path), key)?;
+    let new = LayerVintage::from_path(new, key)?;
+    let mut changes = LayerChanges::diff(&old, &new, ignore);
+    for change in [ChangeKind::Added, ChangeKind::Deleted, ChangeKind::Modified] {
+        run_summary.count(&change.to_string(), changes.count(change));
+    }
+    if let Some(filter) = &cli.filter {
+        changes = changes.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut changes, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Traces the lifecycle of addresses across the snapshots of the source.
+pub(super) fn history(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    snapshots: &[PathBuf],
+    threshold: Option<f64>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    info!("Reading address snapshots.");
+    let mut vintages = Vec::new();
+    match cli.source_type.as_deref() {
+        Some("grants_pass") => {
+            for path in std::iter::once(source_path).chain(snapshots) {
+                let addresses = GrantsPassSpatialAddresses::from_source(path)?;
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                info!("Snapshot {name} read: {} entries.", addresses.len());
+                vintages.push(Vintage::from_grants_pass(&name, &addresses));
+            }
+        }
+        Some(_) => run_summary
+            .missing("Lifecycle tracking requires addresses with a Global ID (grants_pass)."),
+        None => run_summary.missing("No source data type provided."),
+    }
+    if snapshots.is_empty() {
+        run_summary.missing("No later snapshots specified.");
+    }
+    let threshold = threshold.unwrap_or(HISTORY_THRESHOLD);
+    let mut records = LifecycleRecords::track(&vintages, threshold);
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    for record in records.iter() {
+        run_summary.count(&record.event.to_string(), 1);
+    }
+    info!("{:?} lifecycle events reported.", records.len());
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut records, cli)?;
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Reports changes to the Global IDs of addresses between the source and the snapshot `new`.
+pub(super) fn id_audit(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    new: Option<&Path>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    let Some(new) = new else {
+        run_summary.missing("No later snapshot specified.");
+        return Ok(());
+    };
+    if cli.source_type.as_deref() != Some("grants_pass") {
+        run_summary.missing("Identifier audits require addresses with a Global ID (grants_pass).");
+        return Ok(());
+    }
+    info!("Reading address snapshots.");
+    let mut vintages = Vec::new();
+    for path in [source_path, new] {
+        let addresses = GrantsPassSpatialAddresses::from_source(path)?;
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        info!("Snapshot {name} read: {} entries.", addresses.len());
+        vintages.push(Vintage::from_grants_pass(&name, &addresses));
+    }
+    let mut records = IdChanges::audit(&vintages[0], &vintages[1]);
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    for kind in [
+        IdChangeKind::GlobalIdChanged,
+        IdChangeKind::ObjectIdChanged,
+        IdChangeKind::ObjectIdReused,
+    ] {
+        run_summary.count(&kind.to_string(), records.count(kind));
+    }
+    info!("Output file: {:?}", cli.output);
+    geolocate_records(&mut records, cli)?;
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Relates the source addresses to the target addresses by Global ID.
+pub(super) fn relate(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context {
+        cli,
+        source_path,
+        standardizer,
+        aliases,
+        ..
+    } = ctx;
+    info!("Reading source records.");
+    let Some(source_type) = &cli.source_type else {
+        run_summary.missing("No source data type provided.");
+        return Ok(());
+    };
+    let source = read_global_ids(source_type, source_path, standardizer, run_summary)?;
+    info!("Source records read: {} entries.", source.len());
+    info!("Reading target records.");
+    let (Some(target_type), Some(target_path)) = (&cli.target_type, &cli.target) else {
+        run_summary.missing("Target addresses (-t and --target-type) must be set.");
+        return Ok(());
+    };
+    let target = read_global_ids(target_type, target_path, standardizer, run_summary)?;
+    info!("Target records read: {} entries.", target.len());
+    let scoring = MatchScoring::new(cli.fuzzy.unwrap_or(1.0)).with_aliases(aliases.clone());
+    let mut records = RelationshipRecords::relate(&source, &target, &scoring);
+    for status in [MatchStatus::Matching, MatchStatus::Divergent] {
+        let count = records.iter().filter(|r| r.match_status == status).count();
+        run_summary.count(&status_name(&status), count);
+    }
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Summarizes the editing of the source by user and week.
+pub(super) fn activity(
+    ctx: &Context,
+    run_summary: &mut RunSummary,
+    since: Option<chrono::NaiveDate>,
+    until: Option<chrono::NaiveDate>,
+) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    if cli.source_type.as_deref() != Some("grants_pass") {
+        run_summary.missing("Editor activity is read from 'grants_pass' addresses.");
+        return Ok(());
+    }
+    info!("Reading source records.");
+    let addresses = GrantsPassSpatialAddresses::from_source(source_path)?;
+    info!("Source records read: {} entries.", addresses.len());
+    let mut records = EditorActivities::from_grants_pass(&addresses, since, until);
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    run_summary.count("created", records.iter().map(|r| r.created).sum::<usize>());
+    run_summary.count("edited", records.iter().map(|r| r.edited).sum::<usize>());
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Writes the queue of editor notes in the source.
+pub(super) fn notes(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    if cli.source_type.as_deref() != Some("grants_pass") {
+        run_summary.missing("Notes are read from 'grants_pass' addresses.");
+        return Ok(());
+    }
+    info!("Reading source records.");
+    let addresses = GrantsPassSpatialAddresses::from_source(source_path)?;
+    info!("Source records read: {} entries.", addresses.len());
+    let mut records = NoteQueue::from_grants_pass(&addresses);
+    if let Some(filter) = &cli.filter {
+        records = records.filter(filter);
+    }
+    for action in [
+        NoteAction::Retire,
+        NoteAction::Renumber,
+        NoteAction::Verify,
+        NoteAction::Other,
+    ] {
+        run_summary.count(&action.to_string(), records.count(action));
+    }
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut records, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
+
+// Adds the retired addresses in the source to the registry at the output path.
+pub(super) fn retired(ctx: &Context, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    let Context {
+        cli, source_path, ..
+    } = ctx;
+    info!("Reading source records.");
+    let mut retired = RetiredAddresses::default();
+    match cli.source_type.as_deref() {
+        Some("grants_pass") => {
+            let addresses = GrantsPassSpatialAddresses::from_source(source_path)?;
+            info!("Source records read: {} entries.", addresses.len());
+            retired = RetiredAddresses::from_grants_pass(&addresses);
+        }
+        Some(_) => run_summary.missing("Retirement dates require 'grants_pass' addresses."),
+        None => run_summary.missing("No source data type provided."),
+    }
+    // The output file holds the registry, so records from earlier runs are kept.
+    let mut registry = RetiredAddresses::default();
+    if cli.output.is_file() {
+        registry = RetiredAddresses::from_csv(&cli.output)?;
+        info!("Registry records read: {} entries.", registry.len());
+    }
+    registry.merge(retired);
+    info!("Registry records: {} entries.", registry.len());
+    info!("Output file: {:?}", cli.output);
+    write_records(&mut registry, &cli.output, &cli.format, run_summary)?;
+    Ok(())
+}
@@ -0,0 +1,634 @@
+//! The `commands` module runs the commands of the command line interface.  The [`run`] function
+//! reads the options shared by every command into a [`Context`], and hands it to the function for
+//! the command, grouped by purpose into submodules.
+mod audit;
+mod compare;
+mod drift;
+mod export;
+mod history;
+
+use compare::{CompareOptions, alternate_targets};
+use destination::{
+    AddressError, AddressErrorKind, Cli, Command, Config, FromShapefile, GRID_FIELD, Geolocate,
+    GrantsPassSpatialAddresses, IntoBin, IntoCsv, IntoJson, IntoParquet, IntoPostgis,
+    JacksonCountySpatialAddresses, JosephineCountySpatialAddresses2024, MapGrid, MappedAddresses,
+    MatchStatus, OnError, OutputColumns, RunSummary, SpatialAddress, SpatialAddresses,
+    SpatialAddressesRaw, Standardizer, StreetAliases, Units, geolocate, is_geodatabase, is_service,
+    is_shapefile, on_error, set_on_error, set_output_columns, set_quiet, take_parse_errors,
+};
+use drift::DriftOptions;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+// The options shared by every command, read once before the command runs.
+pub(crate) struct Context {
+    // The command line arguments.
+    cli: Cli,
+    // The path to the source data.
+    source_path: PathBuf,
+    // Rules for converting county naming conventions to city naming conventions.
+    standardizer: Standardizer,
+    // Street names known to refer to the same street.
+    aliases: Option<StreetAliases>,
+    // Map-book grid cells for dispatch references.
+    grid: Option<MapGrid>,
+    // True if the output is GeoJSON.
+    geojson: bool,
+    // The connection string for PostGIS output.
+    postgis: Option<String>,
+    // The table written by PostGIS output, named by the output path.
+    table: String,
+    // True if the output is Parquet.
+    parquet: bool,
+    // True if the output is a SQLite database.
+    sqlite: bool,
+}
+
+impl Context {
+    // Reads the source addresses with coordinates, using the source type of the command line.
+    // County addresses are standardized to city naming conventions when `standardize` is set.
+    fn source(
+        &self,
+        standardize: bool,
+        run_summary: &mut RunSummary,
+    ) -> anyhow::Result<SpatialAddresses> {
+        match &self.cli.source_type {
+            Some(source_type) => {
+                self.load(source_type, &self.source_path, standardize, run_summary)
+            }
+            None => {
+                run_summary.missing("No source data type provided.");
+                Ok(SpatialAddresses::default())
+            }
+        }
+    }
+
+    // Reads the target addresses with coordinates, using the target type of the command line.
+    // County addresses are standardized to city naming conventions when `standardize` is set.
+    fn target(
+        &self,
+        standardize: bool,
+        run_summary: &mut RunSummary,
+    ) -> anyhow::Result<SpatialAddresses> {
+        match (&self.cli.target, &self.cli.target_type) {
+            (Some(path), Some(target_type)) => {
+                self.load(target_type, path, standardize, run_summary)
+            }
+            (Some(_), None) => {
+                run_summary.missing("No target data type provided.");
+                Ok(SpatialAddresses::default())
+            }
+            (None, _) => {
+                run_summary.missing("No target data specified.");
+                Ok(SpatialAddresses::default())
+            }
+        }
+    }
+
+    // Reads the addresses of type `address_type` at `path` with coordinates.  County addresses
+    // are standardized to city naming conventions when `standardize` is set.  Unrecognized types
+    // are reported as missing, returning no addresses.
+    fn load<P: AsRef<Path>>(
+        &self,
+        address_type: &str,
+        path: P,
+        standardize: bool,
+        run_summary: &mut RunSummary,
+    ) -> anyhow::Result<SpatialAddresses> {
+        let path = path.as_ref();
+        let mut addresses = match address_type {
+            "grants_pass" => {
+                SpatialAddresses::from(&GrantsPassSpatialAddresses::from_source(path)?[..])
+            }
+            "josephine_county" => {
+                SpatialAddresses::from(&JosephineCountySpatialAddresses2024::from_source(path)?[..])
+            }
+            "jackson_county" => {
+                SpatialAddresses::from(&JacksonCountySpatialAddresses::from_source(path)?[..])
+            }
+            "mapped" => SpatialAddresses::from(&mapped_addresses(&self.cli.mapping, path)?[..]),
+            "common" => SpatialAddresses::from(SpatialAddressesRaw::from_source(path)?),
+            "data" => SpatialAddresses::load(path)?,
+            "parquet" => SpatialAddresses::from_parquet(path)?,
+            "postgis" => match &self.cli.dsn {
+                Some(dsn) => SpatialAddresses::from_postgis(dsn, &path.to_string_lossy())?,
+                None => {
+                    run_summary.missing("PostGIS addresses require a connection string (--dsn).");
+                    SpatialAddresses::default()
+                }
+            },
+            _ => {
+                run_summary.missing(&format!("Unrecognized address type: {address_type}."));
+                SpatialAddresses::default()
+            }
+        };
+        if standardize && address_type == "josephine_county" {
+            self.standardizer.standardize(&mut addresses);
+        }
+        Ok(addresses)
+    }
+}
+
+// Runs the command in `cli`, recording missing inputs and the number of records written in
+// `run_summary`.
+pub(crate) fn run(cli: Cli, run_summary: &mut RunSummary) -> anyhow::Result<()> {
+    // The 'diff' command names its inputs with '--old' and '--new'.
+    let old = match &cli.command {
+        Command::Diff { old, .. } => old.clone(),
+        _ => None,
+    };
+    let Some(source_path) = cli.source.clone().or(old) else {
+        run_summary.missing("No source data specified.");
+        return Ok(());
+    };
+
+    set_quiet(cli.quiet);
+    set_output_columns(&cli.output, OutputColumns::new(&cli.select, &cli.rename)?);
+    if let Some(mode) = &cli.on_error {
+        match OnError::from_name(mode) {
+            Some(mode) => set_on_error(mode),
+            None => warn!("Unrecognized parse error mode: {mode}.  Skipping bad rows."),
+        }
+    }
+    // Rows collected under `--on-error collect` are written beside the output file.
+    let parse_errors = cli.output.with_file_name("parse_errors.csv");
+    if let Some(jobs) = cli.jobs {
+        match rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+        {
+            Ok(()) => info!("Comparisons limited to {jobs} threads."),
+            Err(e) => warn!("Could not limit thread count: {e}"),
+        }
+    }
+
+    // Rules for converting county naming conventions to city naming conventions.
+    let standardizer = match &cli.rules {
+        Some(path) => Standardizer::from_toml(path)?,
+        None => Standardizer::josephine_county().clone(),
+    };
+    // Street names known to refer to the same street.
+    let aliases = match &cli.aliases {
+        Some(path) => {
+            let aliases = StreetAliases::from_csv(path)?;
+            info!("Street aliases read: {} entries.", aliases.len());
+            Some(aliases)
+        }
+        None => None,
+    };
+    // Map-book grid cells for dispatch references.
+    let grid = match &cli.grid {
+        Some(path) => {
+            let field = cli.grid_field.as_deref().unwrap_or(GRID_FIELD);
+            let grid = MapGrid::from_path(path, field)?;
+            info!("Map grid read: {} cells.", grid.len());
+            Some(grid)
+        }
+        None => None,
+    };
+
+    let geojson = match cli.format.as_str() {
+        "csv" | "json" | "ndjson" | "parquet" | "postgis" | "sqlite" => false,
+        "geojson" => true,
+        _ => {
+            warn!("Unrecognized output format: {}.  Writing csv.", cli.format);
+            false
+        }
+    };
+    if geojson
+        && !matches!(
+            cli.command,
+            Command::Filter
+                | Command::Drift { .. }
+                | Command::Save
+                | Command::Compare {
+                    business: false,
+                    ..
+                }
+                | Command::Geocode { .. }
+                | Command::Reverse
+        )
+    {
+        warn!(
+            "GeoJSON output is not available for {}.  Writing csv.",
+            cli.command
+        );
+    }
+
+    // PostGIS output writes to the table named by the output path.
+    let postgis = match cli.format.as_str() {
+        "postgis"
+            if !matches!(
+                cli.command,
+                Command::Save
+                    | Command::Compare {
+                        business: false,
+                        ..
+                    }
+            ) =>
+        {
+            warn!(
+                "PostGIS output is not available for {}.  Writing csv.",
+                cli.command
+            );
+            None
+        }
+        "postgis" if cli.dsn.is_none() => {
+            warn!("PostGIS output requires a connection string (--dsn).  Writing csv.");
+            None
+        }
+        "postgis" => cli.dsn.clone(),
+        _ => None,
+    };
+    let table = cli.output.to_string_lossy().to_string();
+    let parquet = cli.format == "parquet";
+    if parquet
+        && !matches!(
+            cli.command,
+            Command::Save
+                | Command::Compare {
+                    business: false,
+                    ..
+                }
+        )
+    {
+        warn!(
+            "Parquet output is not available for {}.  Writing csv.",
+            cli.command
+        );
+    }
+    // SQLite output writes to the database file at the output path.
+    let sqlite = cli.format == "sqlite";
+    if sqlite && cli.command != Command::Save {
+        warn!(
+            "SQLite output is not available for {}.  Writing csv.",
+            cli.command
+        );
+    }
+
+    let ctx = Context {
+        cli,
+        source_path,
+        standardizer,
+        aliases,
+        grid,
+        geojson,
+        postgis,
+        table,
+        parquet,
+        sqlite,
+    };
+    match &ctx.cli.command {
+        Command::Filter => export::filter(&ctx, run_summary)?,
+        Command::Drift {
+            threshold,
+            min_threshold,
+            stats,
+            report,
+            clusters,
+            units,
+            stacks,
+            ..
+        } => drift::drift(
+            &ctx,
+            run_summary,
+            DriftOptions {
+                threshold: *threshold,
+                min_threshold: *min_threshold,
+                stats: stats.as_deref(),
+                report: report.as_deref(),
+                clusters: clusters.as_deref(),
+                units: units.as_deref(),
+                stacks: *stacks,
+            },
+        )?,
+        Command::Lexisnexis {
+            boundary,
+            previous,
+            template,
+        } => export::lexisnexis(
+            &ctx,
+            run_summary,
+            boundary.as_deref(),
+            previous.as_deref(),
+            template.as_deref(),
+        )?,
+        Command::Serve { bind, grpc } => export::serve(&ctx, run_summary, bind, grpc.as_deref())?,
+        Command::Save => export::save(&ctx, run_summary)?,
+        Command::OrphanStreets => audit::orphan_streets(&ctx, run_summary)?,
+        Command::Duplicates {
+            near,
+            resolve: Some(resolve),
+        } => audit::resolve_duplicates(&ctx, run_summary, *near, resolve)?,
+        Command::Duplicates { near, .. } => audit::duplicates(&ctx, run_summary, *near)?,
+        Command::Bench { tolerance, stats } => {
+            compare::bench(&ctx, run_summary, *tolerance, stats.as_deref())?
+        }
+        Command::Business {
+            alternate,
+            alternate_type,
+            mailing,
+            sheet,
+            columns,
+        } => compare::compare_businesses(
+            &ctx,
+            run_summary,
+            &alternate_targets(alternate.as_slice(), alternate_type.as_slice()),
+            mailing.as_deref(),
+            sheet.as_deref(),
+            columns,
+        )?,
+        Command::Compare {
+            business: true,
+            alternate,
+            alternate_type,
+            mailing,
+            sheet,
+            columns,
+            ..
+        } => compare::compare_businesses(
+            &ctx,
+            run_summary,
+            &alternate_targets(alternate, alternate_type),
+            mailing.as_deref(),
+            sheet.as_deref(),
+            columns,
+        )?,
+        Command::Compare {
+            alternate,
+            alternate_type,
+            incremental,
+            resume,
+            accept,
+            review,
+            blocking,
+            phonetic,
+            include,
+            exclude,
+            normalize,
+            bidirectional,
+            ..
+        } => compare::compare(
+            &ctx,
+            run_summary,
+            CompareOptions {
+                alternate,
+                alternate_type,
+                incremental: *incremental,
+                resume: *resume,
+                accept: *accept,
+                review: *review,
+                blocking: blocking.as_deref(),
+                phonetic: *phonetic,
+                include,
+                exclude,
+                normalize: *normalize,
+                bidirectional: *bidirectional,
+            },
+        )?,
+        Command::Push { token } => compare::push(&ctx, run_summary, token.as_deref())?,
+        Command::Geocode { centerlines } => {
+            compare::geocode(&ctx, run_summary, centerlines.as_deref())?
+        }
+        Command::UnitFormat => audit::unit_format(&ctx, run_summary)?,
+        Command::Units {
+            range,
+            building,
+            unit_type,
+        } => audit::units(
+            &ctx,
+            run_summary,
+            range.as_deref(),
+            building.as_deref(),
+            unit_type.as_deref(),
+        )?,
+        Command::Validate {
+            registry,
+            years,
+            zips,
+            zip_field,
+            community_field,
+        } => audit::validate(
+            &ctx,
+            run_summary,
+            registry.as_deref(),
+            *years,
+            zips.as_deref(),
+            zip_field.as_deref(),
+            community_field.as_deref(),
+        )?,
+        Command::Access => audit::access(&ctx, run_summary)?,
+        Command::Ranges => audit::ranges(&ctx, run_summary)?,
+        Command::Gaps => audit::gaps(&ctx, run_summary)?,
+        Command::Enrich { layers } => export::enrich(&ctx, run_summary, layers)?,
+        Command::Diff {
+            old,
+            new,
+            key,
+            ignore,
+        } => history::diff(
+            &ctx,
+            run_summary,
+            old.as_deref(),
+            new.as_deref(),
+            key.as_deref(),
+            ignore,
+        )?,
+        Command::Labels { labels } => export::labels(&ctx, run_summary, labels)?,
+        Command::Esn {
+            esn,
+            esn_field,
+            buffer,
+        } => audit::esn(
+            &ctx,
+            run_summary,
+            esn.as_deref(),
+            esn_field.as_deref(),
+            *buffer,
+        )?,
+        Command::Footprints {
+            footprints,
+            footprint_field,
+            snap,
+        } => audit::footprints(
+            &ctx,
+            run_summary,
+            footprints.as_deref(),
+            footprint_field.as_deref(),
+            *snap,
+        )?,
+        Command::Parcels {
+            parcels,
+            taxlot_field,
+            situs_field,
+        } => audit::parcels(
+            &ctx,
+            run_summary,
+            parcels.as_deref(),
+            taxlot_field.as_deref(),
+            situs_field.as_deref(),
+        )?,
+        Command::Vacancy {
+            parcels,
+            taxlot_field,
+            footprints,
+        } => audit::vacancy(
+            &ctx,
+            run_summary,
+            parcels.as_deref(),
+            taxlot_field.as_deref(),
+            footprints.as_deref(),
+        )?,
+        Command::Msag { esn, esn_field } => {
+            export::msag(&ctx, run_summary, esn.as_deref(), esn_field.as_deref())?
+        }
+        Command::Inspect => export::inspect(&ctx, run_summary)?,
+        Command::Provenance => export::provenance(&ctx, run_summary)?,
+        Command::History {
+            snapshots,
+            threshold,
+        } => history::history(&ctx, run_summary, snapshots, *threshold)?,
+        Command::IdAudit { new } => history::id_audit(&ctx, run_summary, new.as_deref())?,
+        Command::AssignNearest { confident_distance } => {
+            compare::assign_nearest(&ctx, run_summary, *confident_distance)?
+        }
+        Command::Assign { registry, years } => {
+            compare::assign(&ctx, run_summary, registry.as_deref(), *years)?
+        }
+        Command::Signs {
+            signs,
+            street_field,
+        } => audit::signs(&ctx, run_summary, signs.as_deref(), street_field.as_deref())?,
+        Command::Standardize { profile } => {
+            export::standardize(&ctx, run_summary, profile.as_deref())?
+        }
+        Command::Streets => export::streets(&ctx, run_summary)?,
+        Command::Run => export::run_manifest(&ctx, run_summary)?,
+        Command::Relate => history::relate(&ctx, run_summary)?,
+        Command::Activity { since, until } => history::activity(&ctx, run_summary, *since, *until)?,
+        Command::Notes => history::notes(&ctx, run_summary)?,
+        Command::Retired => history::retired(&ctx, run_summary)?,
+        Command::Nearest { neighbors } => compare::nearest(&ctx, run_summary, *neighbors)?,
+        Command::Overlap { boundary } => compare::overlap(&ctx, run_summary, boundary.as_deref())?,
+        Command::Reverse => compare::reverse(&ctx, run_summary)?,
+    }
+
+    if on_error() == OnError::Collect {
+        let mut errors = take_parse_errors();
+        info!("Parse errors: {} rows.", errors.len());
+        if !errors.is_empty() {
+            errors.to_csv(&parse_errors)?;
+            info!("Parse errors written to {:?}", parse_errors);
+        }
+    }
+
+    Ok(())
+}
+
+// Writes `records` to `path` as a JSON array if `format` is "json", as newline delimited JSON if
+// `format` is "ndjson", and as csv otherwise.
+fn write_records<T, U, P>(
+    records: &mut T,
+    path: P,
+    format: &str,
+    run_summary: &mut RunSummary,
+) -> Result<(), AddressErrorKind>
+where
+    T: IntoCsv<T> + IntoJson + std::ops::Deref<Target = Vec<U>>,
+    P: AsRef<std::path::Path>,
+{
+    run_summary.write(records.len());
+    match format {
+        "json" => records.to_json(path),
+        "ndjson" => records.to_ndjson(path),
+        _ => records.to_csv(path),
+    }
+}
+
+// Fills in the WGS84 `lat` and `lon` columns of `records` from their projected coordinates, when
+// the coordinate reference system of the source is known.
+fn geolocate_records<T: Geolocate>(records: &mut [T], cli: &Cli) -> Result<(), AddressError> {
+    if let Some(epsg) = cli.source_epsg {
+        geolocate(records, epsg)?;
+    }
+    Ok(())
+}
+
+// Returns the units of the projected coordinates of the source, taken from `--source-epsg` and
+// otherwise assumed to be meters.
+fn projected_units(cli: &Cli) -> Units {
+    cli.source_epsg
+        .and_then(Units::from_epsg)
+        .unwrap_or_default()
+}
+
+// Converts the `--radius` flag from meters to the units of the projected coordinates of the
+// source, for commands that measure distances between projected coordinates.
+fn projected_radius(cli: &Cli) -> Option<f64> {
+    cli.radius
+        .map(|radius| radius / projected_units(cli).meters())
+}
+
+// Reads the addresses of type `address_type` at `path`, paired with their Global IDs.  Only the
+// city and county schemas record a Global ID, so other types are reported as missing.
+fn read_global_ids<P: AsRef<std::path::Path>>(
+    address_type: &str,
+    path: P,
+    standardizer: &Standardizer,
+    run_summary: &mut RunSummary,
+) -> anyhow::Result<Vec<(String, SpatialAddress)>> {
+    let (ids, addresses) = match address_type {
+        "grants_pass" => GrantsPassSpatialAddresses::from_source(path)?
+            .iter()
+            .map(|a| (a.global_id.clone(), SpatialAddress::from(a)))
+            .unzip::<_, _, Vec<String>, Vec<SpatialAddress>>(),
+        "josephine_county" => {
+            let (ids, mut addresses) = JosephineCountySpatialAddresses2024::from_source(path)?
+                .iter()
+                .map(|a| {
+                    (
+                        a.global_id.clone().unwrap_or_default(),
+                        SpatialAddress::from(a),
+                    )
+                })
+                .unzip::<_, _, Vec<String>, Vec<SpatialAddress>>();
+            standardizer.standardize(&mut addresses);
+            (ids, addresses)
+        }
+        _ => {
+            run_summary
+                .missing("Global IDs require 'grants_pass' or 'josephine_county' addresses.");
+            Default::default()
+        }
+    };
+    Ok(ids.into_iter().zip(addresses).collect())
+}
+
+// Reads addresses from the csv file at `path`, using the column map named by the `mapping`
+// argument.
+fn mapped_addresses<P: AsRef<std::path::Path>>(
+    mapping: &Option<std::path::PathBuf>,
+    path: P,
+) -> Result<MappedAddresses, AddressError> {
+    match mapping {
+        Some(mapping) => MappedAddresses::from_csv_with_map(path, mapping),
+        None => Err(AddressErrorKind::from(Config::new(
+            path.as_ref().into(),
+            "no column mapping provided".into(),
+            line!(),
+            file!().into(),
+        ))
+        .into()),
+    }
+}
+
+// Returns true if `path` names a csv file rather than a shapefile, a File Geodatabase or an ArcGIS
+// Feature Service, so records can be streamed from it.
+fn is_csv<P: AsRef<std::path::Path>>(path: P) -> bool {
+    !is_service(&path) && !is_shapefile(&path) && !is_geodatabase(&path)
+}
+
+// Returns the name of `status` in lower case, for counting records in the run summary.
+fn status_name(status: &MatchStatus) -> String {
+    format!("{status:?}").to_lowercase()
+}
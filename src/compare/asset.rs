@@ -0,0 +1,251 @@
+//! The `asset` module attaches the nearest valid address to each point of an asset layer, such as
+//! fire hydrants or utility valves, as the location description of the asset.  It replaces the
+//! near table workflow of a desktop GIS, and flags assignments that need a second look.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, Cartesian, IntoCsv, IntoJson, Io, ReversePoint,
+    STACK_TOLERANCE, SpatialIndex, Stacks, compare::reverse::full_label, crs::geolocated, from_csv,
+    progress_bar, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use tracing::info;
+
+/// The `ASSET_DISTANCE` constant holds the default search distance for the nearest address to an
+/// asset, in the units of the projected coordinates.
+pub const ASSET_DISTANCE: f64 = 150.0;
+
+/// The `ASSET_CONFIDENT_DISTANCE` constant holds the default distance within which the nearest
+/// address to an asset is assigned with high confidence, in the units of the projected
+/// coordinates.
+pub const ASSET_CONFIDENT_DISTANCE: f64 = 50.0;
+
+/// The `ASSET_AMBIGUITY` constant holds the ratio to the distance of the nearest address within
+/// which an address on another street makes the assignment ambiguous, as for an asset at a street
+/// corner.
+pub const ASSET_AMBIGUITY: f64 = 1.25;
+
+/// The `AssetConfidence` enum flags how far an asset address assignment can be trusted.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+    strum::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetConfidence {
+    /// The nearest address lies within the confident distance, and no address on another street
+    /// is nearly as close.
+    #[display("high")]
+    High,
+    /// The nearest address lies beyond the confident distance, or an address on another street
+    /// is nearly as close.
+    #[display("low")]
+    Low,
+    /// No valid address lies within the search distance.
+    #[default]
+    #[display("unassigned")]
+    Unassigned,
+}
+
+geolocated! {
+    /// The `AssetAssignment` struct pairs an asset with the nearest valid address.  Assets without
+    /// an address within the search distance have empty address fields.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct AssetAssignment {
+        /// The `id` field holds the identifier of the asset, if any.
+        pub id: Option<String>,
+        /// The `label` field holds the full label of the nearest valid address, including the
+        /// postal community, state and zip code.  Addresses stacked at one location are labeled by
+        /// their structure.
+        pub label: Option<String>,
+        /// The `distance` field holds the distance from the asset to the nearest valid address, in
+        /// the units of the projected coordinates.
+        pub distance: Option<f64>,
+        /// The `confidence` field flags how far the assignment can be trusted.
+        pub confidence: AssetConfidence,
+        /// The `alternate` field holds the label of the nearest address on another street, when it
+        /// makes the assignment ambiguous.
+        pub alternate: Option<String>,
+        /// The `x` field holds the X coordinate of the asset.
+        pub x: f64,
+        /// The `y` field holds the Y coordinate of the asset.
+        pub y: f64,
+    }
+}
+
+/// The `AssetAssignments` struct holds a vector of type [`AssetAssignment`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct AssetAssignments(Vec<AssetAssignment>);
+
+impl AssetAssignments {
+    /// The `assign` method attaches the nearest valid address in `addresses` to each asset in
+    /// `assets`, within a search distance of `distance` in the units of the projected
+    /// coordinates.  Retired, pending and temporary addresses are not valid locations.  The
+    /// assignment has high confidence if the address lies within `confident` and no address on
+    /// another street lies within [`ASSET_AMBIGUITY`] times its distance.  Uses a
+    /// [`SpatialIndex`] to select candidates.
+    pub fn assign<T: Address + Cartesian + Clone + Send + Sync>(
+        assets: &[ReversePoint],
+        addresses: &[T],
+        distance: f64,
+        confident: f64,
+    ) -> Self {
+        let valid = addresses
+            .iter()
+            .filter(|a| {
+                !matches!(
+                    a.status(),
+                    AddressStatus::Retired | AddressStatus::Pending | AddressStatus::Temporary
+                )
+            })
+            .cloned()
+            .collect::<Vec<T>>();
+        info!("Indexing {} valid addresses.", valid.len());
+        let index = SpatialIndex::from_cartesian(&valid, distance);
+        let stacks = Stacks::group(&valid, STACK_TOLERANCE);
+        let stack_of = stacks.stack_of();
+        let label = |i: usize| {
+            let stack = &stacks[stack_of[i]];
+            match stack.is_stacked() {
+                true => full_label(&stack.label, &valid[i]),
+                false => full_label(&valid[i].label(), &valid[i]),
+            }
+        };
+        let records = assets
+            .par_iter()
+            .progress_with(progress_bar(assets.len(), "Assigning nearest addresses."))
+            .map(|asset| {
+                let mut record = AssetAssignment {
+                    id: asset.id.clone(),
+                    x: asset.x,
+                    y: asset.y,
+                    ..Default::default()
+                };
+                let mut found = index
+                    .within([asset.x, asset.y], distance)
+                    .into_iter()
+                    .map(|i| (i, asset.distance(&valid[i])))
+                    .collect::<Vec<(usize, f64)>>();
+                found.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let Some((nearest, near)) = found.first().copied() else {
+                    return record;
+                };
+                let street = valid[nearest].complete_street_name(true);
+                let alternate = found
+                    .iter()
+                    .take_while(|(_, d)| *d <= near * ASSET_AMBIGUITY)
+                    .find(|(i, _)| valid[*i].complete_street_name(true) != street);
+                record.label = Some(label(nearest));
+                record.distance = Some(near);
+                record.alternate = alternate.map(|(i, _)| label(*i));
+                record.confidence = match near <= confident && alternate.is_none() {
+                    true => AssetConfidence::High,
+                    false => AssetConfidence::Low,
+                };
+                record
+            })
+            .collect::<Vec<AssetAssignment>>();
+        Self(records)
+    }
+
+    /// The `count` method returns the number of assignments with the confidence `confidence`.
+    pub fn count(&self, confidence: AssetConfidence) -> usize {
+        self.iter().filter(|r| r.confidence == confidence).count()
+    }
+
+    /// The `filter` method returns the subset of records with the confidence named by `filter`,
+    /// either "high", "low" or "unassigned".
+    pub fn filter(mut self, filter: &str) -> Self {
+        if AssetConfidence::iter().any(|confidence| confidence.to_string() == filter) {
+            self.retain(|r| r.confidence.to_string() == filter);
+        } else {
+            info!("Invalid filter provided.");
+        }
+        self
+    }
+}
+
+impl IntoCsv<AssetAssignments> for AssetAssignments {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for AssetAssignments {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommonAddress, SpatialAddress, StreetNamePostType};
+
+    #[test]
+    fn assign_nearest_assets() -> anyhow::Result<()> {
+        let address =
+            |number: i64, street_name: &str, x: f64, status: AddressStatus| SpatialAddress {
+                address: CommonAddress {
+                    number,
+                    street_name: street_name.to_string(),
+                    street_type: Some(StreetNamePostType::STREET),
+                    postal_community: "GRANTS PASS".to_string(),
+                    zip: 97526,
+                    status,
+                    ..Default::default()
+                },
+                x,
+                y: 0.0,
+                ..Default::default()
+            };
+        let addresses = vec![
+            address(100, "A", 0.0, AddressStatus::Current),
+            address(110, "A", 20.0, AddressStatus::Retired),
+            address(200, "B", 100.0, AddressStatus::Current),
+            address(210, "C", 110.0, AddressStatus::Current),
+        ];
+        let asset = |id: &str, x: f64| ReversePoint {
+            id: Some(id.to_string()),
+            x,
+            y: 10.0,
+        };
+        let assets = vec![asset("H-1", 18.0), asset("H-2", 105.0), asset("H-3", 500.0)];
+        let records = AssetAssignments::assign(&assets, &addresses, 150.0, 50.0);
+        // The retired address is nearer, but only valid addresses are assigned.
+        assert_eq!(
+            records[0].label.as_deref(),
+            Some("100 A ST, GRANTS PASS, OR 97526")
+        );
+        assert_eq!(records[0].confidence, AssetConfidence::High);
+        // Equidistant addresses on two streets make the assignment ambiguous.
+        assert_eq!(records[1].confidence, AssetConfidence::Low);
+        assert!(records[1].alternate.is_some());
+        assert_eq!(records[2].confidence, AssetConfidence::Unassigned);
+        assert_eq!(records[2].label, None);
+        assert_eq!(records.count(AssetConfidence::Low), 1);
+        assert_eq!(records.filter("high").len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+//! The `bench` module scores the geocoder against a truth set of addresses with known
+//! coordinates, reporting precision, recall and positional error by match type, so that changes
+//! to the fuzzy matching threshold can be measured rather than guessed.
+use crate::{
+    Address, AddressErrorKind, GeocodeInput, GeocodeRecords, Geographic, IntoCsv, IntoJson, Io,
+    MatchType, crs::METERS_PER_DEGREE, from_csv, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use tracing::info;
+
+/// The `BENCH_TOLERANCE` constant holds the default distance in meters between a geocoded
+/// location and its true location within which the result counts as correct.
+pub const BENCH_TOLERANCE: f64 = 25.0;
+
+/// The `TruthRecord` struct represents a row of the truth set, an address paired with its known
+/// location.  Other columns in the input file are ignored.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct TruthRecord {
+    /// The `address` field holds the one-line address to geocode.
+    pub address: String,
+    /// The `zip` field holds an optional zip code for the address.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub zip: Option<i64>,
+    /// The `latitude` field holds the known latitude of the address.
+    pub latitude: f64,
+    /// The `longitude` field holds the known longitude of the address.
+    pub longitude: f64,
+}
+
+impl Geographic for TruthRecord {
+    fn latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    fn longitude(&self) -> f64 {
+        self.longitude
+    }
+}
+
+/// The `TruthRecords` struct holds a vector of type [`TruthRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct TruthRecords(Vec<TruthRecord>);
+
+impl IntoCsv<TruthRecords> for TruthRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+/// The `BenchRecord` struct reports the result of geocoding a single address of the truth set.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct BenchRecord {
+    /// The `input` field holds the address as provided.
+    pub input: String,
+    /// The `match_type` field indicates how the address was located.
+    pub match_type: MatchType,
+    /// The `confidence` field holds the confidence score of the match.
+    pub confidence: f64,
+    /// The `label` field holds the address label of the matching reference address.
+    pub label: Option<String>,
+    /// The `error` field holds the distance in meters between the geocoded and true locations,
+    /// or `None` if the address was not located.
+    pub error: Option<f64>,
+    /// The `correct` field is true if the geocoded location lies within the tolerance of the true
+    /// location.
+    pub correct: bool,
+}
+
+/// The `BenchRecords` struct holds a vector of type [`BenchRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct BenchRecords(Vec<BenchRecord>);
+
+impl BenchRecords {
+    /// The `bench` method geocodes each address of the truth set `truth` against the reference
+    /// `addresses`, using similarity threshold `threshold` for street names.  A located address
+    /// is correct if it lies within `tolerance` meters of its true location.
+    pub fn bench<T: Address + Geographic + Send + Sync>(
+        truth: &[TruthRecord],
+        addresses: &[T],
+        threshold: f64,
+        tolerance: f64,
+    ) -> Self {
+        let inputs = truth
+            .iter()
+            .map(|t| GeocodeInput {
+                address: t.address.clone(),
+                zip: t.zip,
+            })
+            .collect::<Vec<GeocodeInput>>();
+        let geocoded = GeocodeRecords::geocode(&inputs, addresses, threshold, None);
+        let records = geocoded
+            .iter()
+            .zip(truth)
+            .map(|(result, known)| {
+                let error = (result.match_type != MatchType::Unmatched)
+                    .then(|| meters_between(result, known));
+                BenchRecord {
+                    input: result.input.clone(),
+                    match_type: result.match_type,
+                    confidence: result.confidence,
+                    label: result.label.clone(),
+                    error,
+                    correct: error.is_some_and(|e| e <= tolerance),
+                }
+            })
+            .collect::<Vec<BenchRecord>>();
+        Self(records)
+    }
+
+    /// The `summary` method tallies the records by match type, with a final row named "all"
+    /// covering every record.
+    pub fn summary(&self) -> BenchSummary {
+        let mut rows = MatchType::iter()
+            .map(|match_type| {
+                let records = self
+                    .iter()
+                    .filter(|r| r.match_type == match_type)
+                    .collect::<Vec<&BenchRecord>>();
+                BenchRow::new(&match_type.to_string(), &records, self.len())
+            })
+            .collect::<Vec<BenchRow>>();
+        let records = self.iter().collect::<Vec<&BenchRecord>>();
+        rows.push(BenchRow::new("all", &records, self.len()));
+        BenchSummary(rows)
+    }
+
+    /// The `filter` method returns the subset of records that match the filter.  Current values
+    /// for the `filter` field include "correct" and "incorrect", which filter located records by
+    /// whether they fall within the tolerance, and the names of match types, such as "fuzzy".
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "correct" => self.retain(|r| r.correct),
+            "incorrect" => self.retain(|r| r.error.is_some() && !r.correct),
+            _ if MatchType::iter().any(|t| t.to_string() == filter) => {
+                self.retain(|r| r.match_type.to_string() == filter)
+            }
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+impl IntoCsv<BenchRecords> for BenchRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for BenchRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+/// The `BenchRow` struct tallies the results of the benchmark for a single match type.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct BenchRow {
+    /// The `match_type` field holds the name of the match type, or "all" for every record.
+    pub match_type: String,
+    /// The `count` field holds the number of records of the match type.
+    pub count: usize,
+    /// The `correct` field holds the number of records located within the tolerance.
+    pub correct: usize,
+    /// The `incorrect` field holds the number of records located outside of the tolerance.
+    pub incorrect: usize,
+    /// The `precision` field holds the share of located records that are correct, or `None` if
+    /// no records were located.
+    pub precision: Option<f64>,
+    /// The `recall` field holds the share of the truth set correctly located by records of the
+    /// match type.
+    pub recall: Option<f64>,
+    /// The `mean_error` field holds the mean distance in meters between the geocoded and true
+    /// locations of the located records.
+    pub mean_error: Option<f64>,
+}
+
+impl BenchRow {
+    // Tallies `records` under the name `match_type`, out of `total` records in the truth set.
+    fn new(match_type: &str, records: &[&BenchRecord], total: usize) -> Self {
+        let errors = records.iter().filter_map(|r| r.error).collect::<Vec<f64>>();
+        let correct = records.iter().filter(|r| r.correct).count();
+        let ratio = |n: usize, d: usize| (d > 0).then(|| n as f64 / d as f64);
+        Self {
+            match_type: match_type.to_string(),
+            count: records.len(),
+            correct,
+            incorrect: errors.len() - correct,
+            precision: ratio(correct, errors.len()),
+            recall: ratio(correct, total),
+            mean_error: (!errors.is_empty())
+                .then(|| errors.iter().sum::<f64>() / errors.len() as f64),
+        }
+    }
+}
+
+/// The `BenchSummary` struct holds a vector of type [`BenchRow`], one for each match type and a
+/// final row for every record.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct BenchSummary(Vec<BenchRow>);
+
+impl BenchSummary {
+    /// The `total` method returns the row covering every record, if any.
+    pub fn total(&self) -> Option<&BenchRow> {
+        self.iter().find(|r| r.match_type == "all")
+    }
+}
+
+impl std::fmt::Display for BenchSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let share = |value: Option<f64>| value.map_or("-".to_string(), |v| format!("{v:.3}"));
+        let meters = |value: Option<f64>| value.map_or("-".to_string(), |v| format!("{v:.1}"));
+        write!(
+            f,
+            "Geocoder benchmark:\n  {:<10}{:>8}{:>9}{:>11}{:>11}{:>8}{:>12}",
+            "match", "count", "correct", "incorrect", "precision", "recall", "mean error"
+        )?;
+        for row in self.iter() {
+            write!(
+                f,
+                "\n  {:<10}{:>8}{:>9}{:>11}{:>11}{:>8}{:>12}",
+                row.match_type,
+                row.count,
+                row.correct,
+                row.incorrect,
+                share(row.precision),
+                share(row.recall),
+                meters(row.mean_error)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoCsv<BenchSummary> for BenchSummary {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for BenchSummary {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+// Returns the approximate distance in meters between two geographic points, treating the
+// surface as flat across the short distances between a geocoded and true location.
+pub(super) fn meters_between<T: Geographic, U: Geographic>(a: &T, b: &U) -> f64 {
+    let latitude = (a.latitude() + b.latitude()) / 2.0;
+    let dx = (a.longitude() - b.longitude()) * latitude.to_radians().cos();
+    let dy = a.latitude() - b.latitude();
+    (dx * dx + dy * dy).sqrt() * METERS_PER_DEGREE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GEOCODE_THRESHOLD, Geocoder, IntoBin, SpatialAddresses};
+    use tracing::info;
+
+    #[test]
+    fn geocode_bench() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let located = Geocoder::new(&city_addresses, GEOCODE_THRESHOLD)
+            .geocode("322 SE K Street, Grants Pass, OR 97526");
+        let truth = |address: &str, offset: f64| TruthRecord {
+            address: address.to_string(),
+            zip: Some(97526),
+            latitude: located.latitude() + offset,
+            longitude: located.longitude(),
+        };
+        let truth = vec![
+            truth("322 SE K Street, Grants Pass", 0.0),
+            truth("322 SE K Street, Grants Pass, OR 97527", 0.0001),
+            truth("322 SE K Street, Grants Pass", 0.01),
+            truth("322 Nowhere Boulevard, Grants Pass", 0.0),
+        ];
+        let records =
+            BenchRecords::bench(&truth, &city_addresses, GEOCODE_THRESHOLD, BENCH_TOLERANCE);
+        assert_eq!(records.len(), 4);
+        assert!(records[0].correct);
+        assert_eq!(records[1].match_type, MatchType::Fuzzy);
+        assert!(records[1].correct);
+        assert!(records[1].error.is_some_and(|e| e > 10.0 && e < 12.0));
+        assert!(!records[2].correct);
+        assert_eq!(records[3].match_type, MatchType::Unmatched);
+        assert_eq!(records.clone().filter("incorrect").len(), 1);
+        let summary = records.summary();
+        let total = summary.total().unwrap();
+        assert_eq!((total.count, total.correct, total.incorrect), (4, 2, 1));
+        assert_eq!(total.precision, Some(2.0 / 3.0));
+        assert_eq!(total.recall, Some(0.5));
+        let unmatched = summary
+            .iter()
+            .find(|r| r.match_type == "unmatched")
+            .unwrap();
+        assert_eq!(unmatched.count, 1);
+        assert_eq!(unmatched.mean_error, None);
+        info!("{summary}");
+        Ok(())
+    }
+}
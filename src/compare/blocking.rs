@@ -0,0 +1,128 @@
+//! The `blocking` module groups addresses into blocks sharing a key, such as the zip code and the
+//! sound of the street name, so that each subject address is only compared against the target
+//! addresses in its block.  Blocking trades a small risk of missing a match whose key differs for
+//! a large cut in the number of comparisons, which otherwise grows with the product of the sizes
+//! of the source and target.
+use crate::{Address, Builder, metaphone, normalize_text, soundex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The `BLOCK_BUCKET` constant holds the default width of the address number buckets used by
+/// [`Blocking::StreetBucket`].
+pub const BLOCK_BUCKET: i64 = 100;
+
+/// The `BlockingStrategy` trait assigns addresses to blocks.  Addresses are only compared against
+/// addresses with the same key.  Implement the trait to compare addresses under a custom blocking
+/// scheme with [`crate::MatchRecords::compare_blocked`].
+pub trait BlockingStrategy: Sync {
+    /// The `key` method returns the key of the block holding `address`.
+    fn key<T: Address>(&self, address: &T) -> String;
+
+    /// The `blocks` method returns the indices of the addresses in `addresses`, grouped by key.
+    fn blocks<T: Address>(&self, addresses: &[T]) -> HashMap<String, Vec<usize>> {
+        let mut blocks: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, address) in addresses.iter().enumerate() {
+            blocks.entry(self.key(address)).or_default().push(i);
+        }
+        blocks
+    }
+}
+
+/// The `Blocking` enum holds the blocking strategies available from the command line.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Blocking {
+    /// The `ZipSoundex` variant blocks addresses by zip code and the Soundex code of the street
+    /// name, so street names spelled differently but pronounced alike share a block.
+    ZipSoundex,
+    /// The `ZipMetaphone` variant blocks addresses by zip code and the Metaphone code of the
+    /// street name, which keeps names such as "THOMPSON" and "THOMSEN" together where the Soundex
+    /// codes differ.
+    ZipMetaphone,
+    /// The `StreetBucket` variant blocks addresses by street name and address number, divided by
+    /// the width of the bucket.  Addresses in different zip codes share a block.
+    StreetBucket(i64),
+}
+
+impl Blocking {
+    /// The `parse` method reads a blocking strategy from its name, either "zip_soundex",
+    /// "zip_metaphone" or "street_bucket".  The street bucket takes an optional width after a colon, as in
+    /// "street_bucket:50", defaulting to [`BLOCK_BUCKET`].
+    pub fn parse(spec: &str) -> Result<Self, Builder> {
+        let invalid = |issue: &str| {
+            Builder::new(
+                format!("{issue} in '{spec}'"),
+                "Blocking".to_string(),
+                line!(),
+                file!().to_string(),
+            )
+        };
+        let (name, width) = match spec.split_once(':') {
+            Some((name, width)) => (name.trim(), Some(width.trim())),
+            None => (spec.trim(), None),
+        };
+        match (name, width) {
+            ("zip_soundex", None) => Ok(Self::ZipSoundex),
+            ("zip_metaphone", None) => Ok(Self::ZipMetaphone),
+            ("street_bucket", None) => Ok(Self::StreetBucket(BLOCK_BUCKET)),
+            ("street_bucket", Some(width)) => match width.parse::<i64>() {
+                Ok(width) if width > 0 => Ok(Self::StreetBucket(width)),
+                _ => Err(invalid("bucket width must be a positive integer")),
+            },
+            ("zip_soundex" | "zip_metaphone", Some(_)) => {
+                Err(invalid(&format!("{name} takes no parameter")))
+            }
+            _ => Err(invalid(
+                "expected 'zip_soundex', 'zip_metaphone' or 'street_bucket'",
+            )),
+        }
+    }
+}
+
+impl BlockingStrategy for Blocking {
+    fn key<T: Address>(&self, address: &T) -> String {
+        match self {
+            Self::ZipSoundex => format!("{}|{}", address.zip(), soundex(address.street_name())),
+            Self::ZipMetaphone => {
+                format!("{}|{}", address.zip(), metaphone(address.street_name()))
+            }
+            Self::StreetBucket(width) => format!(
+                "{}|{}",
+                normalize_text(address.street_name()),
+                address.number().div_euclid(*width)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoBin, MatchRecords, MatchScoring, MatchStatus, SpatialAddresses};
+
+    #[test]
+    fn compare_blocking() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let county_addresses = SpatialAddresses::load("data/county_addresses.data")?;
+        let source = &city_addresses[0..1000];
+        let exhaustive = MatchRecords::compare(source, &county_addresses);
+        let labels = |records: &MatchRecords| {
+            records
+                .iter()
+                .map(|r| (r.address_label.clone(), r.match_status.clone()))
+                .collect::<Vec<(String, MatchStatus)>>()
+        };
+        let scoring = MatchScoring::default();
+        for spec in ["zip_soundex", "street_bucket", "street_bucket:10"] {
+            let strategy = Blocking::parse(spec)?;
+            let blocked =
+                MatchRecords::compare_blocked(source, &county_addresses, &strategy, &scoring);
+            assert_eq!(labels(&blocked), labels(&exhaustive));
+        }
+        assert_eq!(soundex("SMITH"), soundex("SMYTHE"));
+        assert_eq!(soundex("RIVERSIDE"), soundex("RIVER SIDE"));
+        assert_eq!(soundex("Lee"), "L000");
+        assert!(Blocking::parse("street_bucket:0").is_err());
+        assert!(Blocking::parse("zip").is_err());
+        Ok(())
+    }
+}
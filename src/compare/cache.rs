@@ -0,0 +1,230 @@
+//! The `cache` module persists the results of previous comparisons, so that a repeat comparison
+//! only recomputes matches for addresses that changed since the last run.
+use crate::{
+    Address, AddressError, AddressErrorKind, Bincode, IntoBin, MatchRecord, MatchScoring, from_bin,
+    to_bin,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The `CHECKPOINT_INTERVAL` constant holds the number of subject addresses compared between
+/// saves of the match cache in a resumable comparison.  See
+/// [`crate::MatchRecords::compare_resumable`].
+pub const CHECKPOINT_INTERVAL: usize = 5_000;
+
+/// The `MatchCache` struct holds the match records from a previous comparison.
+///
+/// The common address types carry no object ID, so records are keyed by a fingerprint of the
+/// subject address fields rather than by ID.  Addresses only match addresses with the same
+/// address number, so each entry also records a fingerprint of the target addresses sharing the
+/// number of the subject.  An entry is reused when neither fingerprint has changed.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchCache {
+    scoring: MatchScoring,
+    radius: Option<f64>,
+    entries: HashMap<u64, CacheEntry>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheEntry {
+    block: u64,
+    records: Vec<MatchRecord>,
+}
+
+impl MatchCache {
+    /// The `len` method returns the number of subject addresses held in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The `is_empty` method returns `true` if the cache holds no subject addresses.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Clears the cache if the entries were computed with different comparison parameters.
+    pub(crate) fn configure(&mut self, scoring: &MatchScoring, radius: Option<f64>) {
+        if self.scoring != *scoring || self.radius != radius {
+            self.entries.clear();
+            self.scoring = scoring.clone();
+            self.radius = radius;
+        }
+    }
+
+    // Returns the cached records for the subject address with fingerprint `key`, if the target
+    // block has not changed.
+    pub(crate) fn get(&self, key: u64, block: u64) -> Option<&Vec<MatchRecord>> {
+        self.entries
+            .get(&key)
+            .filter(|entry| entry.block == block)
+            .map(|entry| &entry.records)
+    }
+
+    // Adds the records of the subject address with fingerprint `key`, compared against the target
+    // block with fingerprint `block`, replacing any previous entry.
+    pub(crate) fn insert(&mut self, key: u64, block: u64, records: Vec<MatchRecord>) {
+        self.entries.insert(key, CacheEntry { block, records });
+    }
+
+    // Replaces the contents of the cache with `entries`, dropping addresses no longer present.
+    pub(crate) fn replace(
+        &mut self,
+        entries: impl IntoIterator<Item = (u64, u64, Vec<MatchRecord>)>,
+    ) {
+        self.entries = entries
+            .into_iter()
+            .map(|(key, block, records)| (key, CacheEntry { block, records }))
+            .collect();
+    }
+
+    // Fingerprints the target addresses sharing each address number.  The fingerprint does not
+    // depend on the order of the addresses.
+    pub(crate) fn blocks<U: Address + Serialize>(addresses: &[U]) -> HashMap<i64, u64> {
+        let mut members: HashMap<i64, Vec<u64>> = HashMap::new();
+        for address in addresses {
+            members
+                .entry(address.number())
+                .or_default()
+                .push(fingerprint(address));
+        }
+        members
+            .into_iter()
+            .map(|(number, mut keys)| {
+                keys.sort_unstable();
+                let bytes = keys
+                    .iter()
+                    .flat_map(|k| k.to_le_bytes())
+                    .collect::<Vec<u8>>();
+                (number, fnv(&bytes))
+            })
+            .collect()
+    }
+}
+
+impl IntoBin<MatchCache> for MatchCache {
+    fn load<P: AsRef<Path>>(path: P) -> Result<Self, AddressError> {
+        match from_bin(path) {
+            Ok(records) => bincode::deserialize::<Self>(&records)
+                .map_err(|source| Bincode::new(source, line!(), file!().into()).into()),
+            Err(source) => Err(AddressErrorKind::from(source).into()),
+        }
+    }
+
+    fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
+        to_bin(self, self.len(), path)
+    }
+}
+
+// Fingerprints the serialized fields of `value`.  Uses FNV-1a rather than the standard hasher,
+// because the cache must produce the same fingerprints across program versions.
+pub(crate) fn fingerprint<T: Serialize>(value: &T) -> u64 {
+    // Serializing plain address data into memory cannot fail.
+    let bytes = bincode::serialize(value).unwrap_or_default();
+    fnv(&bytes)
+}
+
+fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MatchRecords, MatchStatus, SpatialAddresses};
+
+    #[test]
+    fn incremental_compare() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let mut county_addresses = SpatialAddresses::load("data/county_addresses.data")?;
+        let source = &city_addresses[0..1000];
+        let labels = |records: &MatchRecords| {
+            records
+                .iter()
+                .map(|r| (r.address_label.clone(), r.match_status.clone()))
+                .collect::<Vec<(String, MatchStatus)>>()
+        };
+
+        let scoring = MatchScoring::default();
+        let mut cache = MatchCache::default();
+        let first = MatchRecords::compare_incremental(
+            source,
+            &county_addresses,
+            None,
+            &scoring,
+            &mut cache,
+        );
+        assert_eq!(
+            labels(&first),
+            labels(&MatchRecords::compare(source, &county_addresses))
+        );
+        let path = std::env::temp_dir().join("destination_match.cache");
+        cache.save(&path)?;
+        let mut cache = MatchCache::load(&path)?;
+        std::fs::remove_file(&path)?;
+        assert!(!cache.is_empty());
+
+        // Renaming the street of a target address invalidates the subjects sharing its number.
+        let number = source[0].number();
+        if let Some(address) = county_addresses.iter_mut().find(|a| a.number() == number) {
+            *address.street_name_mut() = "RENAMED".to_string();
+        }
+        let second = MatchRecords::compare_incremental(
+            source,
+            &county_addresses,
+            None,
+            &scoring,
+            &mut cache,
+        );
+        assert_eq!(
+            labels(&second),
+            labels(&MatchRecords::compare(source, &county_addresses))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resumable_compare() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let county_addresses = SpatialAddresses::load("data/county_addresses.data")?;
+        let source = &city_addresses[0..300];
+        let labels = |records: &MatchRecords| {
+            records
+                .iter()
+                .map(|r| (r.address_label.clone(), r.match_status.clone()))
+                .collect::<Vec<(String, MatchStatus)>>()
+        };
+        let scoring = MatchScoring::default();
+
+        // Keep the cache saved at the first checkpoint, as if the run stopped there.
+        let mut checkpoints = Vec::new();
+        let mut cache = MatchCache::default();
+        let complete = MatchRecords::compare_resumable(
+            source,
+            &county_addresses,
+            None,
+            &scoring,
+            &mut cache,
+            100,
+            |cache| checkpoints.push(cache.clone()),
+        );
+        assert_eq!(checkpoints.len(), 2);
+        assert_eq!(checkpoints[0].len(), 100);
+
+        // Resuming from the checkpoint produces the same records as the complete run.
+        let mut cache = checkpoints.remove(0);
+        let resumed = MatchRecords::compare_resumable(
+            source,
+            &county_addresses,
+            None,
+            &scoring,
+            &mut cache,
+            CHECKPOINT_INTERVAL,
+            |_| {},
+        );
+        assert_eq!(labels(&resumed), labels(&complete));
+        Ok(())
+    }
+}
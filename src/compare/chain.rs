@@ -0,0 +1,327 @@
+//! The `chain` module compares source addresses against several target datasets in one pass,
+//! reporting which targets hold a match for each source address, so the city addresses can be
+//! reconciled against the current and legacy county extracts in a single run.
+use crate::{
+    Address, AddressErrorKind, Csv, Geographic, IntoCsv, IntoJson, Io, MatchRecords, MatchScoring,
+    MatchStatus, SpatialIndex, compare::Scorer, open_csv, progress_bar, to_json, to_ndjson,
+    write_atomic,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+/// The `TargetStatus` struct holds the status of the best match for a source address in a single
+/// target dataset.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TargetStatus {
+    /// The `target` field holds the name of the target dataset.
+    pub target: String,
+    /// The `match_status` field holds the status of the best match in the target.
+    pub match_status: MatchStatus,
+}
+
+/// The `TargetMatchRecord` struct reports the match status of a source address in each of the
+/// target datasets of a multi-target comparison.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct TargetMatchRecord {
+    /// The `address_label` field is the text representation of the source address.
+    pub address_label: String,
+    /// The `match_status` field holds the best status of the address across the targets.
+    pub match_status: MatchStatus,
+    /// The `matched` field holds the names of the targets holding a matching or divergent
+    /// address, in the order of the targets.
+    pub matched: Vec<String>,
+    /// The `targets` field holds the status of the address in each target, in the order of the
+    /// targets.
+    pub targets: Vec<TargetStatus>,
+    /// The `longitude` field represents the 'x' value of the address point.
+    pub longitude: f64,
+    /// The `latitude` field represents the 'y' value of the address point.
+    pub latitude: f64,
+}
+
+impl TargetMatchRecord {
+    /// The `status` method returns the status of the address in the target named `target`, if
+    /// the target was compared.
+    pub fn status(&self, target: &str) -> Option<&MatchStatus> {
+        self.targets
+            .iter()
+            .find(|t| t.target == target)
+            .map(|t| &t.match_status)
+    }
+}
+
+/// The `TargetMatchRecords` struct holds a vector of type [`TargetMatchRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct TargetMatchRecords(Vec<TargetMatchRecord>);
+
+impl TargetMatchRecords {
+    /// The `compare` method compares each address in `source` against every target in
+    /// `targets`, each paired with the name reported for it, scoring candidates with `scoring`.
+    /// When `radius` is set, only candidates within `radius` meters are compared, as in
+    /// [`MatchRecords::compare_scored`].  Unlike a chained comparison, every target is searched
+    /// for every address, so an address present in several targets lists each of them.
+    pub fn compare<T: Address + Geographic + Sync, U: Address + Geographic + Sync>(
+        source: &[T],
+        targets: &[(String, &[U])],
+        radius: Option<f64>,
+        scoring: &MatchScoring,
+    ) -> Self {
+        let scorer = Scorer::new(scoring);
+        let indices = targets
+            .iter()
+            .map(|(_, addresses)| {
+                radius.map(|radius| SpatialIndex::from_geographic(addresses, radius))
+            })
+            .collect::<Vec<Option<SpatialIndex>>>();
+        let records = source
+            .par_iter()
+            .map(|address| {
+                let targets = targets
+                    .iter()
+                    .zip(&indices)
+                    .map(|((target, others), index)| {
+                        let records = match (index, radius) {
+                            (Some(index), Some(radius)) => {
+                                let candidates = index
+                                    .within([address.longitude(), address.latitude()], radius)
+                                    .into_iter()
+                                    .map(|i| &others[i]);
+                                MatchRecords::from_candidates(address, candidates, &scorer)
+                            }
+                            _ => MatchRecords::from_candidates(address, others.iter(), &scorer),
+                        };
+                        // Statuses are ordered from best to worst.
+                        let match_status = records
+                            .iter()
+                            .map(|r| r.match_status.clone())
+                            .min()
+                            .unwrap_or_default();
+                        TargetStatus {
+                            target: target.clone(),
+                            match_status,
+                        }
+                    })
+                    .collect::<Vec<TargetStatus>>();
+                let matched = targets
+                    .iter()
+                    .filter(|t| is_match(&t.match_status))
+                    .map(|t| t.target.clone())
+                    .collect();
+                TargetMatchRecord {
+                    address_label: address.label(),
+                    match_status: targets
+                        .iter()
+                        .map(|t| t.match_status.clone())
+                        .min()
+                        .unwrap_or_default(),
+                    matched,
+                    targets,
+                    longitude: address.longitude(),
+                    latitude: address.latitude(),
+                }
+            })
+            .progress_with(progress_bar(source.len(), "Comparing addresses."))
+            .collect::<Vec<TargetMatchRecord>>();
+        for (target, _) in targets {
+            let matched = records
+                .iter()
+                .filter(|r| r.matched.contains(target))
+                .count();
+            info!(
+                "{matched} of {} addresses matched in {target}.",
+                records.len()
+            );
+        }
+        Self(records)
+    }
+
+    /// The `filter` method returns the subset of records passing `filter`.  Accepts "matching",
+    /// "divergent", "review" and "missing", comparing the best status of each address, and
+    /// "partial", keeping addresses matched in some targets but not others.
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "matching" => self.retain(|r| r.match_status == MatchStatus::Matching),
+            "divergent" => self.retain(|r| r.match_status == MatchStatus::Divergent),
+            "review" => self.retain(|r| r.match_status == MatchStatus::Review),
+            "missing" => self.retain(|r| r.match_status == MatchStatus::Missing),
+            "partial" => {
+                self.retain(|r| !r.matched.is_empty() && r.matched.len() < r.targets.len())
+            }
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+
+    // Returns the names of the targets held by the records, in the order of the columns.
+    fn targets(&self) -> Vec<String> {
+        self.first()
+            .map(|r| r.targets.iter().map(|t| t.target.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+// Returns true if `status` reports an address present in the target, if with differences.
+fn is_match(status: &MatchStatus) -> bool {
+    matches!(status, MatchStatus::Matching | MatchStatus::Divergent)
+}
+
+// The fixed columns of the csv output, before and after the column of each target.
+const LEADING_COLUMNS: [&str; 3] = ["address_label", "match_status", "matched"];
+const TRAILING_COLUMNS: [&str; 2] = ["longitude", "latitude"];
+
+impl IntoCsv<TargetMatchRecords> for TargetMatchRecords {
+    /// Reads the columns between `matched` and `longitude` as the status in each target.  The
+    /// targets in the `matched` column are separated by semicolons.
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let path = path.as_ref();
+        let io = |source: std::io::Error| Io::new(path.into(), source, line!(), file!().into());
+        let mut rdr = csv::Reader::from_reader(open_csv(path)?);
+        let headers = rdr.headers().map_err(|source| io(source.into()))?.clone();
+        let targets = headers
+            .iter()
+            .skip(LEADING_COLUMNS.len())
+            .take(
+                headers
+                    .len()
+                    .saturating_sub(LEADING_COLUMNS.len() + TRAILING_COLUMNS.len()),
+            )
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+        let status = |value: &str| {
+            csv::StringRecord::from(vec![value])
+                .deserialize::<MatchStatus>(None)
+                .map_err(|source| io(source.into()))
+        };
+        let number = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|e| io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+        };
+        let mut records = Vec::new();
+        for row in rdr.records() {
+            let row = row.map_err(|source| io(source.into()))?;
+            let cell = |i: usize| row.get(i).unwrap_or_default();
+            let statuses = targets
+                .iter()
+                .enumerate()
+                .map(|(i, target)| {
+                    Ok(TargetStatus {
+                        target: target.clone(),
+                        match_status: status(cell(LEADING_COLUMNS.len() + i))?,
+                    })
+                })
+                .collect::<Result<Vec<TargetStatus>, Io>>()?;
+            let trailing = LEADING_COLUMNS.len() + targets.len();
+            records.push(TargetMatchRecord {
+                address_label: cell(0).to_string(),
+                match_status: status(cell(1))?,
+                matched: cell(2)
+                    .split(';')
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                targets: statuses,
+                longitude: number(cell(trailing))?,
+                latitude: number(cell(trailing + 1))?,
+            });
+        }
+        Ok(Self(records))
+    }
+
+    /// Writes the status of each address in each target to a column named for the target.
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        let targets = self.targets();
+        write_atomic(path, |path| -> Result<(), AddressErrorKind> {
+            let csv_error = |source| Csv::new(path.into(), source, line!(), file!().into());
+            let mut wtr = csv::Writer::from_path(path).map_err(csv_error)?;
+            let mut headers = LEADING_COLUMNS.to_vec();
+            headers.extend(targets.iter().map(String::as_str));
+            headers.extend(TRAILING_COLUMNS);
+            wtr.write_record(&headers).map_err(csv_error)?;
+            for record in self.iter() {
+                let mut row = vec![
+                    record.address_label.clone(),
+                    format!("{:?}", record.match_status),
+                    record.matched.join(";"),
+                ];
+                row.extend(
+                    record
+                        .targets
+                        .iter()
+                        .map(|t| format!("{:?}", t.match_status)),
+                );
+                row.push(record.longitude.to_string());
+                row.push(record.latitude.to_string());
+                wtr.write_record(&row).map_err(csv_error)?;
+            }
+            wtr.flush()
+                .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+            Ok(())
+        })
+    }
+}
+
+impl IntoJson for TargetMatchRecords {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoBin, SpatialAddresses};
+
+    #[test]
+    fn multi_target_compare() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let county_addresses = SpatialAddresses::load("data/county_addresses.data")?;
+        let source = &city_addresses[0..200];
+        let legacy = &city_addresses[0..100];
+        let targets = vec![
+            ("county".to_string(), &county_addresses[..]),
+            ("legacy".to_string(), legacy),
+        ];
+        let records = TargetMatchRecords::compare(source, &targets, None, &MatchScoring::default());
+        assert_eq!(records.len(), source.len());
+        for (record, address) in records.iter().zip(source) {
+            assert_eq!(record.address_label, address.label());
+            assert_eq!(record.targets.len(), 2);
+            // The best status across targets is the best status in any target.
+            let best = record.targets.iter().map(|t| &t.match_status).min();
+            assert_eq!(Some(&record.match_status), best);
+        }
+        // The legacy target holds the first half of the source, so those addresses all match it.
+        assert!(
+            records[0..100]
+                .iter()
+                .all(|r| r.status("legacy") == Some(&MatchStatus::Matching))
+        );
+        assert!(
+            records[100..]
+                .iter()
+                .all(|r| !r.matched.contains(&"legacy".to_string()))
+        );
+
+        let partial = records.clone().filter("partial");
+        assert!(!partial.is_empty());
+        assert!(partial.iter().all(|r| r.matched.len() == 1));
+
+        let path = std::env::temp_dir().join("destination_multi_target.csv");
+        let mut records = records;
+        records.to_csv(&path)?;
+        let header = std::fs::read_to_string(&path)?;
+        assert!(header.starts_with("address_label,match_status,matched,county,legacy,"));
+        assert_eq!(TargetMatchRecords::from_csv(&path)?, records);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 //!  The `compare_fire` module implements address matching and comparison for Fire Inspections.
 use crate::{
-    Address, AddressErrorKind, FireInspection, FireInspections, Geographic, IntoCsv, Io,
-    MatchPartialRecord, MatchPartialRecords, MatchStatus, from_csv, to_csv,
+    Address, AddressErrorKind, FireInspection, FireInspections, Geographic, IntoCsv, IntoJson, Io,
+    MatchPartialRecord, MatchPartialRecords, MatchStatus, from_csv, progress_bar, to_csv, to_json,
+    to_ndjson,
 };
 use derive_more::{Deref, DerefMut};
 use indicatif::ParallelProgressIterator;
@@ -57,14 +58,10 @@ impl FireInspectionMatches {
         inspections: &FireInspections,
         addresses: &[T],
     ) -> Self {
-        let style = indicatif::ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {'Comparing addresses.'}",
-        )
-        .unwrap();
         let records = inspections
             .par_iter()
             .map(|r| FireInspectionMatch::compare(r, addresses))
-            .progress_with_style(style)
+            .progress_with(progress_bar(inspections.len(), "Comparing addresses."))
             .collect::<Vec<FireInspectionMatch>>();
         FireInspectionMatches(records)
     }
@@ -142,6 +139,16 @@ impl IntoCsv<FireInspectionMatchRecords> for FireInspectionMatchRecords {
     }
 }
 
+impl IntoJson for FireInspectionMatchRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
 impl From<&FireInspectionMatch> for FireInspectionMatchRecords {
     fn from(inspection: &FireInspectionMatch) -> Self {
         let mut records = Vec::new();
@@ -1,53 +1,175 @@
 //! The `eponym` module is the eponymous module for `compare`.  Contains types and methods for
 //! comparing addresses.
 use crate::{
-    Address, AddressErrorKind, AddressStatus, Geographic, IntoCsv, Io, PartialAddress,
-    PartialAddresses, SubaddressType, from_csv, to_csv,
+    Address, AddressError, AddressErrorKind, AddressStatus, BlockingStrategy, Geographic, IntoCsv,
+    IntoGeoJson, IntoJson, IntoParquet, Io, MatchCache, MatchScoring, PartialAddress,
+    PartialAddresses, SpatialIndex, SubaddressType, compare::Scorer, compare::cache::fingerprint,
+    from_csv, from_parquet, progress_bar, to_csv, to_geojson, to_json, to_ndjson, to_parquet,
 };
+#[cfg(feature = "io")]
+use crate::{IntoPostgis, from_postgis, to_postgis};
 use derive_more::{Deref, DerefMut};
 use indicatif::ParallelProgressIterator;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
+/// The `FieldDiff` struct holds the values of a field that differs between a subject address and
+/// its match, so divergent fields can be tallied and corrected in bulk.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct FieldDiff {
+    /// The `source` field holds the value of the field in the subject address, if present.
+    pub source: Option<String>,
+    /// The `target` field holds the value of the field in the matching address, if present.
+    pub target: Option<String>,
+}
+
+impl FieldDiff {
+    /// The `new` method creates a `FieldDiff` from the values of the field in the subject address
+    /// and its match.
+    pub fn new<T: std::fmt::Display>(source: Option<T>, target: Option<T>) -> Self {
+        Self {
+            source: source.map(|value| value.to_string()),
+            target: target.map(|value| value.to_string()),
+        }
+    }
+}
+
 /// The `Mismatch` enum tracks the fields of an address that can diverge while still potentially
-/// referring to the same location.
+/// referring to the same location.  Each variant holds the values of the field in the subject
+/// address and its match.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum Mismatch {
     /// Represents a mismatch in the subaddress type.
-    SubaddressType(String),
+    SubaddressType(FieldDiff),
     /// Represents a mismatch in the floor number.
-    Floor(String),
+    Floor(FieldDiff),
     /// Represents a mismatch in the building identifier.
-    Building(String),
+    Building(FieldDiff),
     /// Represents a mismatch in the address status.
-    Status(String),
+    Status(FieldDiff),
+    /// Represents a street name that is similar but not equal, from fuzzy matching.
+    StreetName(FieldDiff),
+    /// Represents a mismatch in the zip code, when excluded from the match.  See [`MatchConfig`].
+    Zip(FieldDiff),
+    /// Represents a mismatch in the postal community, when excluded from the match.
+    PostalCommunity(FieldDiff),
+    /// Represents a mismatch in the subaddress identifier, when excluded from the match.
+    SubaddressId(FieldDiff),
 }
 
 impl Mismatch {
-    /// The `subaddress_type` method captures information about the mismatch between subaddress
-    /// type fields as a message contained in the enum variant.
+    /// The `FIELDS` constant lists the names of the fields that can mismatch, in the order of the
+    /// columns of a [`MatchRecord`].
+    pub const FIELDS: [&str; 8] = [
+        "subaddress_type",
+        "floor",
+        "building",
+        "status",
+        "street_name",
+        "zip",
+        "postal_community",
+        "subaddress_id",
+    ];
+
+    /// The `subaddress_type` method captures the values of the mismatched subaddress type fields.
     pub fn subaddress_type(from: Option<SubaddressType>, to: Option<SubaddressType>) -> Self {
-        let message = format!("{:?} not equal to {:?}", from, to);
-        Self::SubaddressType(message)
+        Self::SubaddressType(FieldDiff::new(from, to))
     }
 
-    /// The `floor` method captures information about the mismatch between the `floor` fields as a message contained in the enum variant.
+    /// The `floor` method captures the values of the mismatched `floor` fields.
     pub fn floor(from: Option<i64>, to: Option<i64>) -> Self {
-        let message = format!("{:?} not equal to {:?}", from, to);
-        Self::Floor(message)
+        Self::Floor(FieldDiff::new(from, to))
     }
 
-    /// The `building` method captures information about the mismatch between the `building` fields as a message contained in the enum variant.
+    /// The `building` method captures the values of the mismatched `building` fields.
     pub fn building(from: Option<String>, to: Option<String>) -> Self {
-        let message = format!("{:?} not equal to {:?}", from, to);
-        Self::Building(message)
+        Self::Building(FieldDiff::new(from, to))
     }
 
-    /// The `status` method captures information about the mismatch between the `status` fields as a message contained in the enum variant.
+    /// The `status` method captures the values of the mismatched `status` fields.
     pub fn status(from: AddressStatus, to: AddressStatus) -> Self {
-        let message = format!("{} not equal to {}", from, to);
-        Self::Status(message)
+        Self::Status(FieldDiff::new(Some(from), Some(to)))
+    }
+
+    /// The `street_name` method captures the values of similar `street_name` fields under fuzzy
+    /// matching.
+    pub fn street_name(from: String, to: String) -> Self {
+        Self::StreetName(FieldDiff::new(Some(from), Some(to)))
+    }
+
+    /// The `zip` method captures the values of the mismatched `zip` fields.
+    pub fn zip(from: i64, to: i64) -> Self {
+        Self::Zip(FieldDiff::new(Some(from), Some(to)))
+    }
+
+    /// The `postal_community` method captures the values of the mismatched `postal_community`
+    /// fields.
+    pub fn postal_community(from: String, to: String) -> Self {
+        Self::PostalCommunity(FieldDiff::new(Some(from), Some(to)))
+    }
+
+    /// The `subaddress_id` method captures the values of the mismatched `subaddress_id` fields.
+    pub fn subaddress_id(from: Option<String>, to: Option<String>) -> Self {
+        Self::SubaddressId(FieldDiff::new(from, to))
+    }
+
+    /// The `field` method returns the name of the mismatched field, as named in the columns of a
+    /// [`MatchRecord`].
+    pub fn field(&self) -> &'static str {
+        match self {
+            Self::SubaddressType(_) => "subaddress_type",
+            Self::Floor(_) => "floor",
+            Self::Building(_) => "building",
+            Self::Status(_) => "status",
+            Self::StreetName(_) => "street_name",
+            Self::Zip(_) => "zip",
+            Self::PostalCommunity(_) => "postal_community",
+            Self::SubaddressId(_) => "subaddress_id",
+        }
+    }
+
+    /// The `diff` method returns the values of the mismatched field.
+    pub fn diff(&self) -> &FieldDiff {
+        match self {
+            Self::SubaddressType(diff)
+            | Self::Floor(diff)
+            | Self::Building(diff)
+            | Self::Status(diff)
+            | Self::StreetName(diff)
+            | Self::Zip(diff)
+            | Self::PostalCommunity(diff)
+            | Self::SubaddressId(diff) => diff,
+        }
+    }
+
+    /// The `message` method describes the mismatch for display, e.g. "Some(Suite) not equal to
+    /// None", or "RIVERSIDE similar to RIVER SIDE" for street names.
+    pub fn message(&self) -> String {
+        let FieldDiff { source, target } = self.diff();
+        // Optional fields print as the `Debug` form of the original values.
+        let optional = |value: &Option<String>| match value {
+            Some(value) => format!("Some({value})"),
+            None => "None".to_string(),
+        };
+        match self {
+            Self::SubaddressType(_) | Self::Floor(_) => {
+                format!("{} not equal to {}", optional(source), optional(target))
+            }
+            Self::Building(_) | Self::SubaddressId(_) => {
+                format!("{:?} not equal to {:?}", source, target)
+            }
+            Self::Status(_) | Self::Zip(_) | Self::PostalCommunity(_) => format!(
+                "{} not equal to {}",
+                source.as_deref().unwrap_or_default(),
+                target.as_deref().unwrap_or_default()
+            ),
+            Self::StreetName(_) => format!(
+                "{} similar to {}",
+                source.as_deref().unwrap_or_default(),
+                target.as_deref().unwrap_or_default()
+            ),
+        }
     }
 }
 
@@ -77,7 +199,7 @@ impl Mismatches {
 
 /// The `AddressMatch` is an intermediary data structure used internally to aggregate match information from
 /// comparing types that implement [`crate::Addresses`], for the purpose of producing [`MatchRecords`].
-#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct AddressMatch {
     /// The `coincident` field indicates the compared addresses refer to the same location, or are
     /// coincidental.
@@ -85,6 +207,12 @@ pub struct AddressMatch {
     /// The `mismatches` field holds [`Mismatch`] information for each field that differs between
     /// the compared addresses.  If no coincident address is present, this field is `None`.
     pub mismatches: Option<Mismatches>,
+    /// The `similarity` field holds the similarity score of the street names when the addresses
+    /// match under fuzzy matching, but the street names are not equal.
+    pub similarity: Option<f64>,
+    /// The `score` field holds the composite score of the candidate address under the scoring
+    /// model of [`MatchScoring`], if the addresses were scored.
+    pub score: Option<f64>,
 }
 
 impl AddressMatch {
@@ -98,13 +226,67 @@ impl AddressMatch {
         AddressMatch {
             coincident,
             mismatches,
+            similarity: None,
+            score: None,
         }
     }
+
+    /// The `with_similarity` method sets the value of the `similarity` field.
+    pub fn with_similarity(mut self, similarity: Option<f64>) -> Self {
+        self.similarity = similarity;
+        self
+    }
+
+    /// The `with_score` method sets the value of the `score` field.
+    pub fn with_score(mut self, score: Option<f64>) -> Self {
+        self.score = score;
+        self
+    }
+}
+
+// The `similarity` and `score` fields compare with `f64::total_cmp`, which orders every value and
+// treats values as equal only if their bits are equal, so the ordering agrees with the hash.
+impl Ord for AddressMatch {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let scores = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        };
+        self.coincident
+            .cmp(&other.coincident)
+            .then_with(|| self.mismatches.cmp(&other.mismatches))
+            .then_with(|| scores(self.similarity, other.similarity))
+            .then_with(|| scores(self.score, other.score))
+    }
+}
+
+impl PartialOrd for AddressMatch {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for AddressMatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for AddressMatch {}
+
+impl std::hash::Hash for AddressMatch {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.coincident.hash(state);
+        self.mismatches.hash(state);
+        self.similarity.map(f64::to_bits).hash(state);
+        self.score.map(f64::to_bits).hash(state);
+    }
 }
 
 /// The `MatchStatus` enum delineates whether a given address has a match (the `Matching` variant),
-/// has a match but differs in some descriptive fields (the `Divergent` variant), or does not have
-/// a match in the comparison set (the `Missing` variant).
+/// has a match but differs in some descriptive fields (the `Divergent` variant), has a candidate
+/// match scoring in the review band (the `Review` variant), or does not have a match in the
+/// comparison set (the `Missing` variant).
 ///
 /// We have derived Default using the Missing variant, mostly so structs that take a `MatchStatus`
 /// as a field can also derive default.  Properly speaking, there is no meaningful default for this
@@ -117,6 +299,10 @@ pub enum MatchStatus {
     /// the address contains fields with different values than in the comparison (e.g. the
     /// address has status 'Retired' compared to 'Current').
     Divergent,
+    /// The `Review` variant indicates the best candidate for the address in the comparison set
+    /// scores between the review and accept thresholds of the [`MatchScoring`], such as an
+    /// address with the same number and street but a different number suffix.
+    Review,
     #[default]
     /// The `Missing` variant indicates the address does not have a match in the comparison set.
     Missing,
@@ -141,6 +327,93 @@ pub struct MatchRecord {
     /// The `status` field indicates the subject address and its match, if present, have different
     /// values for the address status. E.g. "Current" does not match "Other".
     pub status: Option<String>,
+    /// The `street_name` field indicates the subject address matched under fuzzy matching, and the
+    /// street name differs from the match.  E.g. "RIVERSIDE" is similar to "RIVER SIDE".
+    pub street_name: Option<String>,
+    /// The `subaddress_type_source` field holds the subaddress type of the subject address when
+    /// it differs from the match.
+    #[serde(default)]
+    pub subaddress_type_source: Option<String>,
+    /// The `subaddress_type_target` field holds the subaddress type of the match when it differs
+    /// from the subject address.
+    #[serde(default)]
+    pub subaddress_type_target: Option<String>,
+    /// The `floor_source` field holds the floor of the subject address when it differs from the
+    /// match.
+    #[serde(default)]
+    pub floor_source: Option<String>,
+    /// The `floor_target` field holds the floor of the match when it differs from the subject
+    /// address.
+    #[serde(default)]
+    pub floor_target: Option<String>,
+    /// The `building_source` field holds the building of the subject address when it differs
+    /// from the match.
+    #[serde(default)]
+    pub building_source: Option<String>,
+    /// The `building_target` field holds the building of the match when it differs from the
+    /// subject address.
+    #[serde(default)]
+    pub building_target: Option<String>,
+    /// The `status_source` field holds the status of the subject address when it differs from
+    /// the match.
+    #[serde(default)]
+    pub status_source: Option<String>,
+    /// The `status_target` field holds the status of the match when it differs from the subject
+    /// address.
+    #[serde(default)]
+    pub status_target: Option<String>,
+    /// The `street_name_source` field holds the street name of the subject address under a fuzzy
+    /// match.
+    #[serde(default)]
+    pub street_name_source: Option<String>,
+    /// The `street_name_target` field holds the street name of the match under a fuzzy match.
+    #[serde(default)]
+    pub street_name_target: Option<String>,
+    /// The `zip` field indicates the subject address and its match have different zip codes,
+    /// when the zip code is excluded from the match.  See [`MatchConfig`].
+    #[serde(default)]
+    pub zip: Option<String>,
+    /// The `zip_source` field holds the zip code of the subject address when it differs from the
+    /// match.
+    #[serde(default)]
+    pub zip_source: Option<String>,
+    /// The `zip_target` field holds the zip code of the match when it differs from the subject
+    /// address.
+    #[serde(default)]
+    pub zip_target: Option<String>,
+    /// The `postal_community` field indicates the subject address and its match have different
+    /// postal communities, when the postal community is excluded from the match.
+    #[serde(default)]
+    pub postal_community: Option<String>,
+    /// The `postal_community_source` field holds the postal community of the subject address
+    /// when it differs from the match.
+    #[serde(default)]
+    pub postal_community_source: Option<String>,
+    /// The `postal_community_target` field holds the postal community of the match when it
+    /// differs from the subject address.
+    #[serde(default)]
+    pub postal_community_target: Option<String>,
+    /// The `subaddress_id` field indicates the subject address and its match have different
+    /// subaddress identifiers, when the subaddress is excluded from the match.
+    #[serde(default)]
+    pub subaddress_id: Option<String>,
+    /// The `subaddress_id_source` field holds the subaddress identifier of the subject address
+    /// when it differs from the match.
+    #[serde(default)]
+    pub subaddress_id_source: Option<String>,
+    /// The `subaddress_id_target` field holds the subaddress identifier of the match when it
+    /// differs from the subject address.
+    #[serde(default)]
+    pub subaddress_id_target: Option<String>,
+    /// The `similarity` field holds the similarity score between the street names of a fuzzy match.
+    pub similarity: Option<f64>,
+    /// The `score` field holds the composite score of the match under the [`MatchScoring`], or
+    /// `None` for missing addresses.
+    #[serde(default)]
+    pub score: Option<f64>,
+    /// The `candidate` field holds the label of the best candidate for an address needing review.
+    #[serde(default)]
+    pub candidate: Option<String>,
     /// The `longitude` field represents the 'x' value of the address point.  Depending on the
     /// input from the caller, the value may be in decimal degrees, meters or feet.
     pub longitude: f64,
@@ -151,6 +424,137 @@ pub struct MatchRecord {
     pub id: uuid::Uuid,
 }
 
+impl MatchRecord {
+    /// The `FILTERS` constant lists the filter values accepted by [`Self::passes`] and
+    /// [`MatchRecords::filter`].
+    pub const FILTERS: [&str; 12] = [
+        "matching",
+        "missing",
+        "divergent",
+        "review",
+        "subaddress",
+        "floor",
+        "building",
+        "status",
+        "street_name",
+        "zip",
+        "postal_community",
+        "subaddress_id",
+    ];
+
+    /// The `passes` method returns true if the record belongs to the subset selected by `filter`.
+    /// See [`MatchRecords::filter`] for the accepted values.  Records pass any unrecognized
+    /// filter.
+    pub fn passes(&self, filter: &str) -> bool {
+        let divergent = self.match_status == MatchStatus::Divergent;
+        match filter {
+            "matching" => self.match_status == MatchStatus::Matching,
+            "missing" => self.match_status == MatchStatus::Missing,
+            "divergent" => divergent,
+            "review" => self.match_status == MatchStatus::Review,
+            "subaddress" => divergent && self.subaddress_type.is_some(),
+            "floor" => divergent && self.floor.is_some(),
+            "building" => divergent && self.building.is_some(),
+            "status" => divergent && self.status.is_some(),
+            "street_name" => divergent && self.street_name.is_some(),
+            "zip" => divergent && self.zip.is_some(),
+            "postal_community" => divergent && self.postal_community.is_some(),
+            "subaddress_id" => divergent && self.subaddress_id.is_some(),
+            _ => true,
+        }
+    }
+
+    /// The `diffs` method returns the fields that differ between the subject address and its
+    /// match, paired with the values of the field in each, in the order of the columns.
+    pub fn diffs(&self) -> Vec<(&'static str, FieldDiff)> {
+        [
+            (
+                "subaddress_type",
+                &self.subaddress_type_source,
+                &self.subaddress_type_target,
+            ),
+            ("floor", &self.floor_source, &self.floor_target),
+            ("building", &self.building_source, &self.building_target),
+            ("status", &self.status_source, &self.status_target),
+            (
+                "street_name",
+                &self.street_name_source,
+                &self.street_name_target,
+            ),
+            ("zip", &self.zip_source, &self.zip_target),
+            (
+                "postal_community",
+                &self.postal_community_source,
+                &self.postal_community_target,
+            ),
+            (
+                "subaddress_id",
+                &self.subaddress_id_source,
+                &self.subaddress_id_target,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, source, target)| source.is_some() || target.is_some())
+        .map(|(field, source, target)| {
+            (
+                field,
+                FieldDiff {
+                    source: source.clone(),
+                    target: target.clone(),
+                },
+            )
+        })
+        .collect()
+    }
+
+    // Records the message and values of `mismatch` in the columns of the mismatched field.
+    fn record_mismatch(&mut self, mismatch: &Mismatch) {
+        let message = Some(mismatch.message());
+        let FieldDiff { source, target } = mismatch.diff().clone();
+        let (column, source_column, target_column) = match mismatch {
+            Mismatch::SubaddressType(_) => (
+                &mut self.subaddress_type,
+                &mut self.subaddress_type_source,
+                &mut self.subaddress_type_target,
+            ),
+            Mismatch::Floor(_) => (
+                &mut self.floor,
+                &mut self.floor_source,
+                &mut self.floor_target,
+            ),
+            Mismatch::Building(_) => (
+                &mut self.building,
+                &mut self.building_source,
+                &mut self.building_target,
+            ),
+            Mismatch::Status(_) => (
+                &mut self.status,
+                &mut self.status_source,
+                &mut self.status_target,
+            ),
+            Mismatch::StreetName(_) => (
+                &mut self.street_name,
+                &mut self.street_name_source,
+                &mut self.street_name_target,
+            ),
+            Mismatch::Zip(_) => (&mut self.zip, &mut self.zip_source, &mut self.zip_target),
+            Mismatch::PostalCommunity(_) => (
+                &mut self.postal_community,
+                &mut self.postal_community_source,
+                &mut self.postal_community_target,
+            ),
+            Mismatch::SubaddressId(_) => (
+                &mut self.subaddress_id,
+                &mut self.subaddress_id_source,
+                &mut self.subaddress_id_target,
+            ),
+        };
+        *column = message;
+        *source_column = source;
+        *target_column = target;
+    }
+}
+
 impl Geographic for MatchRecord {
     fn latitude(&self) -> f64 {
         self.latitude
@@ -161,86 +565,133 @@ impl Geographic for MatchRecord {
     }
 }
 
+// Returns true if the coincident match `a` is a better match than `b`.  Matches with equal street
+// names beat fuzzy matches, then higher scores win.  Ties keep the earlier candidate.
+fn outranks(a: &AddressMatch, b: &AddressMatch) -> bool {
+    match (a.similarity.is_none(), b.similarity.is_none()) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => a.score.unwrap_or(0.0) > b.score.unwrap_or(0.0),
+    }
+}
+
+/// The minimum number of subject addresses assigned to a single parallel task in
+/// [`MatchRecords::compare`].
+const COMPARE_CHUNK: usize = 64;
+
 /// The `MatchRecords` struct holds a vector of type [`MatchRecord`].
 #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
 pub struct MatchRecords(Vec<MatchRecord>);
 
 impl MatchRecords {
     /// The constructor for `MatchRecords` compares a single subject address against a set of
-    /// addresses, and returns the `MatchRecords` for the subject address.  A subject address can
-    /// match against multiple candidates (e.g. a parent address will match against all
-    /// subaddresses associated with the parent), so the result type must potentially accommodate
-    /// multiple records.
+    /// addresses, and returns the `MatchRecords` for the subject address.  When several
+    /// candidates match, the record describes the best of them: a candidate with the same street
+    /// name beats a fuzzy match, and otherwise the candidate with the highest score wins.
     pub fn new<T: Address + Geographic, U: Address + Geographic>(
         self_address: &T,
         other_addresses: &[U],
+    ) -> Self {
+        Self::new_fuzzy(self_address, other_addresses, 1.0)
+    }
+
+    /// The `new_fuzzy` method works like [`MatchRecords::new`], but street names match if their
+    /// similarity meets or exceeds `threshold`, using [`Address::coincident_fuzzy`].
+    pub fn new_fuzzy<T: Address + Geographic, U: Address + Geographic>(
+        self_address: &T,
+        other_addresses: &[U],
+        threshold: f64,
+    ) -> Self {
+        Self::new_scored(self_address, other_addresses, &MatchScoring::new(threshold))
+    }
+
+    /// The `new_scored` method works like [`MatchRecords::new`], but scores each candidate with
+    /// `scoring`.  Candidates meeting the accept threshold are reported as matching or divergent.
+    /// If no candidate is accepted, the best candidate in the review band is reported for review,
+    /// and otherwise the address is missing.
+    pub fn new_scored<T: Address + Geographic, U: Address + Geographic>(
+        self_address: &T,
+        other_addresses: &[U],
+        scoring: &MatchScoring,
+    ) -> Self {
+        Self::from_candidates(self_address, other_addresses, &Scorer::new(scoring))
+    }
+
+    // Compares the subject address against each address in `candidates`, keeping the best match.
+    // Shared by the exhaustive constructors and the spatially indexed comparison.
+    pub(crate) fn from_candidates<'a, T: Address + Geographic, U: Address + Geographic + 'a>(
+        self_address: &T,
+        candidates: impl IntoIterator<Item = &'a U>,
+        scorer: &Scorer,
     ) -> Self {
         let address_label = self_address.label();
         let latitude = self_address.latitude();
         let longitude = self_address.longitude();
         let id = uuid::Uuid::new_v4();
 
-        let mut match_record = Vec::new();
+        let mut best: Option<AddressMatch> = None;
+        let mut review: Option<(f64, String)> = None;
 
-        for address in other_addresses {
-            let address_match = self_address.coincident(address);
+        for address in candidates {
+            let address_match = scorer.compare(self_address, address);
+            let score = address_match.score;
             if address_match.coincident {
-                let mut subaddress_type = None;
-                let mut floor = None;
-                let mut building = None;
-                let mut status = None;
-                match address_match.mismatches {
-                    None => match_record.push(MatchRecord {
-                        match_status: MatchStatus::Matching,
-                        address_label: address_label.clone(),
-                        subaddress_type,
-                        floor,
-                        building,
-                        status,
-                        longitude,
-                        latitude,
-                        id,
-                    }),
-                    Some(mismatches) => {
-                        for mismatch in mismatches.iter() {
-                            match mismatch {
-                                Mismatch::SubaddressType(message) => {
-                                    subaddress_type = Some(message.to_owned())
-                                }
-                                Mismatch::Floor(message) => floor = Some(message.to_owned()),
-                                Mismatch::Building(message) => building = Some(message.to_owned()),
-                                Mismatch::Status(message) => status = Some(message.to_owned()),
-                            }
-                        }
-                        match_record.push(MatchRecord {
-                            match_status: MatchStatus::Divergent,
-                            address_label: address_label.clone(),
-                            subaddress_type,
-                            floor,
-                            building,
-                            status,
-                            longitude,
-                            latitude,
-                            id,
-                        })
-                    }
+                if best
+                    .as_ref()
+                    .is_none_or(|best| outranks(&address_match, best))
+                {
+                    best = Some(address_match);
                 }
+            } else if let Some(score) = score
+                && scorer.is_review(score)
+                && review.as_ref().is_none_or(|(best, _)| score > *best)
+            {
+                review = Some((score, address.label()));
             }
         }
-        if match_record.is_empty() {
-            match_record.push(MatchRecord {
-                match_status: MatchStatus::Missing,
-                address_label,
-                subaddress_type: None,
-                floor: None,
-                building: None,
-                status: None,
-                longitude,
-                latitude,
-                id,
-            })
-        }
-        MatchRecords(match_record)
+        let record = match best {
+            Some(address_match) => {
+                let score = address_match.score;
+                let mut record = MatchRecord {
+                    match_status: MatchStatus::Matching,
+                    address_label,
+                    similarity: address_match.similarity,
+                    score,
+                    longitude,
+                    latitude,
+                    id,
+                    ..Default::default()
+                };
+                // A candidate accepted below a perfect score differs in an identifying field.
+                if score.is_some_and(|s| s < 1.0) {
+                    record.match_status = MatchStatus::Divergent;
+                }
+                if let Some(mismatches) = address_match.mismatches {
+                    record.match_status = MatchStatus::Divergent;
+                    for mismatch in mismatches.iter() {
+                        record.record_mismatch(mismatch);
+                    }
+                }
+                record
+            }
+            None => {
+                let (match_status, score, candidate) = match review {
+                    Some((score, label)) => (MatchStatus::Review, Some(score), Some(label)),
+                    None => (MatchStatus::Missing, None, None),
+                };
+                MatchRecord {
+                    match_status,
+                    address_label,
+                    score,
+                    candidate,
+                    longitude,
+                    latitude,
+                    id,
+                    ..Default::default()
+                }
+            }
+        };
+        MatchRecords(vec![record])
     }
 
     /// For each address in `self_addresses`, the `compare` method calculates the match record for
@@ -250,46 +701,296 @@ impl MatchRecords {
         self_addresses: &[T],
         other_addresses: &[U],
     ) -> Self {
-        let style = indicatif::ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {'Comparing addresses.'}",
+        Self::compare_fuzzy(self_addresses, other_addresses, 1.0)
+    }
+
+    /// The `compare_fuzzy` method works like [`MatchRecords::compare`], but street names match if
+    /// their similarity meets or exceeds `threshold`.  Fuzzy matches are reported as divergent,
+    /// with the similarity score recorded in the `similarity` field.
+    pub fn compare_fuzzy<
+        T: Address + Geographic + Send + Sync,
+        U: Address + Geographic + Send + Sync,
+    >(
+        self_addresses: &[T],
+        other_addresses: &[U],
+        threshold: f64,
+    ) -> Self {
+        Self::compare_scored(
+            self_addresses,
+            other_addresses,
+            None,
+            &MatchScoring::new(threshold),
         )
-        .unwrap();
-        let record = self_addresses
+    }
+
+    /// The `compare_within` method works like [`MatchRecords::compare_fuzzy`], but only compares
+    /// each subject address against addresses in `other_addresses` within `radius` meters, using a
+    /// [`SpatialIndex`] to select candidates.  This avoids comparing every pair of addresses, at
+    /// the cost of reporting addresses as missing when their match lies outside the radius.
+    pub fn compare_within<
+        T: Address + Geographic + Send + Sync,
+        U: Address + Geographic + Send + Sync,
+    >(
+        self_addresses: &[T],
+        other_addresses: &[U],
+        radius: f64,
+        threshold: f64,
+    ) -> Self {
+        Self::compare_scored(
+            self_addresses,
+            other_addresses,
+            Some(radius),
+            &MatchScoring::new(threshold),
+        )
+    }
+
+    /// The `compare_scored` method works like [`MatchRecords::compare_fuzzy`], but scores each
+    /// candidate with `scoring`, as in [`MatchRecords::new_scored`].  When `radius` is set, only
+    /// candidates within `radius` meters are compared, as in [`MatchRecords::compare_within`].
+    pub fn compare_scored<
+        T: Address + Geographic + Send + Sync,
+        U: Address + Geographic + Send + Sync,
+    >(
+        self_addresses: &[T],
+        other_addresses: &[U],
+        radius: Option<f64>,
+        scoring: &MatchScoring,
+    ) -> Self {
+        let scorer = Scorer::new(scoring);
+        let index = radius.map(|radius| {
+            info!("Indexing comparison addresses.");
+            SpatialIndex::from_geographic(other_addresses, radius)
+        });
+        // Each task takes a chunk of at least `COMPARE_CHUNK` subject addresses, so the work
+        // queue does not thrash on large sets.  The thread count follows the global rayon pool.
+        let records = self_addresses
+            .par_iter()
+            .with_min_len(COMPARE_CHUNK)
+            .map(|address| match (&index, radius) {
+                (Some(index), Some(radius)) => {
+                    let candidates = index
+                        .within([address.longitude(), address.latitude()], radius)
+                        .into_iter()
+                        .map(|i| &other_addresses[i]);
+                    MatchRecords::from_candidates(address, candidates, &scorer)
+                }
+                _ => MatchRecords::from_candidates(address, other_addresses, &scorer),
+            })
+            .progress_with(progress_bar(self_addresses.len(), "Comparing addresses."))
+            .flat_map_iter(|record| record.0)
+            .collect::<Vec<MatchRecord>>();
+        MatchRecords(records)
+    }
+
+    /// The `compare_blocked` method works like [`MatchRecords::compare_scored`], but only
+    /// compares each subject address against the addresses in `other_addresses` sharing its block
+    /// under `strategy`.  Addresses whose match falls in another block are reported as missing.
+    pub fn compare_blocked<
+        T: Address + Geographic + Send + Sync,
+        U: Address + Geographic + Send + Sync,
+        B: BlockingStrategy,
+    >(
+        self_addresses: &[T],
+        other_addresses: &[U],
+        strategy: &B,
+        scoring: &MatchScoring,
+    ) -> Self {
+        let scorer = Scorer::new(scoring);
+        info!("Blocking comparison addresses.");
+        let blocks = strategy.blocks(other_addresses);
+        let empty = Vec::new();
+        let records = self_addresses
             .par_iter()
-            .map(|address| MatchRecords::new(address, other_addresses))
-            .progress_with_style(style)
-            .collect::<Vec<MatchRecords>>();
+            .with_min_len(COMPARE_CHUNK)
+            .map(|address| {
+                let block = blocks.get(&strategy.key(address)).unwrap_or(&empty);
+                (
+                    block.len(),
+                    MatchRecords::from_candidates(
+                        address,
+                        block.iter().map(|i| &other_addresses[*i]),
+                        &scorer,
+                    ),
+                )
+            })
+            .progress_with(progress_bar(self_addresses.len(), "Comparing addresses."))
+            .collect::<Vec<(usize, MatchRecords)>>();
+        let pairs = records.iter().map(|(pairs, _)| pairs).sum::<usize>();
+        info!(
+            "Compared {} pairs in {} blocks, of {} pairs without blocking.",
+            pairs,
+            blocks.len(),
+            self_addresses.len() * other_addresses.len()
+        );
+        MatchRecords(
+            records
+                .into_iter()
+                .flat_map(|(_, records)| records.0)
+                .collect(),
+        )
+    }
+
+    /// The `compare_incremental` method works like [`MatchRecords::compare_fuzzy`], or like
+    /// [`MatchRecords::compare_within`] when `radius` is set, but reuses the records in `cache`
+    /// for subject addresses that are unchanged since the last run, and whose potential matches
+    /// in `other_addresses` are also unchanged.  Only the remaining addresses are compared, scored
+    /// with `scoring`.  The cache is updated with the results of this run.  Changes to a target
+    /// address with a different address number do not invalidate the cache, so a review band wide
+    /// enough to admit candidates with a different number may leave stale records.
+    pub fn compare_incremental<
+        T: Address + Geographic + Serialize + Send + Sync,
+        U: Address + Geographic + Serialize + Send + Sync,
+    >(
+        self_addresses: &[T],
+        other_addresses: &[U],
+        radius: Option<f64>,
+        scoring: &MatchScoring,
+        cache: &mut MatchCache,
+    ) -> Self {
+        Self::compare_resumable(
+            self_addresses,
+            other_addresses,
+            radius,
+            scoring,
+            cache,
+            usize::MAX,
+            |_| {},
+        )
+    }
+
+    /// The `compare_resumable` method works like [`MatchRecords::compare_incremental`], but
+    /// compares the remaining addresses in batches of `checkpoint` addresses, adding the records
+    /// of each batch to `cache` and passing the cache to `save` before starting the next batch.
+    /// If the run is interrupted, the last cache saved holds every batch compared so far, so the
+    /// next run resumes from the last checkpoint instead of starting over.
+    pub fn compare_resumable<
+        T: Address + Geographic + Serialize + Send + Sync,
+        U: Address + Geographic + Serialize + Send + Sync,
+        F: FnMut(&MatchCache),
+    >(
+        self_addresses: &[T],
+        other_addresses: &[U],
+        radius: Option<f64>,
+        scoring: &MatchScoring,
+        cache: &mut MatchCache,
+        checkpoint: usize,
+        mut save: F,
+    ) -> Self {
+        cache.configure(scoring, radius);
+        let scorer = Scorer::new(scoring);
+        let blocks = MatchCache::blocks(other_addresses);
+        let keys = self_addresses
+            .par_iter()
+            .map(|address| {
+                let block = blocks.get(&address.number()).copied().unwrap_or_default();
+                (fingerprint(address), block)
+            })
+            .collect::<Vec<(u64, u64)>>();
+        let stale = keys
+            .iter()
+            .enumerate()
+            .filter(|(_, (key, block))| cache.get(*key, *block).is_none())
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        info!(
+            "Reusing {} of {} cached records.",
+            self_addresses.len() - stale.len(),
+            self_addresses.len()
+        );
+
+        let index = match radius {
+            Some(radius) if !stale.is_empty() => {
+                info!("Indexing comparison addresses.");
+                Some(SpatialIndex::from_geographic(other_addresses, radius))
+            }
+            _ => None,
+        };
+        let bar = progress_bar(stale.len(), "Comparing addresses.");
+        let mut fresh = std::collections::HashMap::with_capacity(stale.len());
+        for batch in stale.chunks(checkpoint.max(1)) {
+            let results = batch
+                .par_iter()
+                .with_min_len(COMPARE_CHUNK)
+                .map(|i| {
+                    let address = &self_addresses[*i];
+                    let records = match (&index, radius) {
+                        (Some(index), Some(radius)) => {
+                            let candidates = index
+                                .within([address.longitude(), address.latitude()], radius)
+                                .into_iter()
+                                .map(|i| &other_addresses[i]);
+                            MatchRecords::from_candidates(address, candidates, &scorer)
+                        }
+                        _ => MatchRecords::from_candidates(address, other_addresses, &scorer),
+                    };
+                    (*i, records.0)
+                })
+                .progress_with(bar.clone())
+                .collect::<Vec<(usize, Vec<MatchRecord>)>>();
+            for (i, records) in results {
+                let (key, block) = keys[i];
+                cache.insert(key, block, records.clone());
+                fresh.insert(i, records);
+            }
+            if fresh.len() < stale.len() {
+                save(cache);
+            }
+        }
+
+        let mut entries = Vec::with_capacity(keys.len());
         let mut records = Vec::new();
-        for mut item in record {
-            records.append(&mut item);
+        for (i, (key, block)) in keys.into_iter().enumerate() {
+            let result = match fresh.get(&i) {
+                Some(result) => result.clone(),
+                None => {
+                    // Cached records receive a new id, so duplicate subject addresses stay
+                    // distinct.
+                    let id = uuid::Uuid::new_v4();
+                    let mut result = cache.get(key, block).cloned().unwrap_or_default();
+                    result.iter_mut().for_each(|r| r.id = id);
+                    result
+                }
+            };
+            records.extend(result.iter().cloned());
+            entries.push((key, block, result));
         }
+        cache.replace(entries);
         MatchRecords(records)
     }
 
+    /// The `divergence` method counts the divergent records differing in each field, in the order
+    /// of the columns, omitting fields without differences.
+    pub fn divergence(&self) -> Vec<(&'static str, usize)> {
+        let diffs = self
+            .iter()
+            .filter(|r| r.match_status == MatchStatus::Divergent)
+            .map(|r| r.diffs())
+            .collect::<Vec<_>>();
+        Mismatch::FIELDS
+            .into_iter()
+            .map(|field| {
+                let count = diffs
+                    .iter()
+                    .filter(|d| d.iter().any(|(name, _)| *name == field))
+                    .count();
+                (field, count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
     /// The `filter` method returns the subset of `MatchRecords` that meet the filter requirement.
     /// The `filter` parameter takes a string reference that can take the values "matching",
-    /// "missing", "divergent", "subaddress", "floor", "building" and "status".  When filtering by
-    /// match status, the return records contain those records where the match status equals the
-    /// filter value.  For the mismatch fields, the return records contain values where a mismatch
+    /// "missing", "divergent", "subaddress", "floor", "building", "status", "street_name", "zip",
+    /// "postal_community" and "subaddress_id".
+    /// When filtering by match status, the return records contain those records where the match
+    /// status equals the filter value.  For the mismatch fields, the return records contain values where a mismatch
     /// is present in the provided field.
     pub fn filter(mut self, filter: &str) -> Self {
-        match filter {
-            "matching" => self.retain(|r| r.match_status == MatchStatus::Matching),
-            "missing" => self.retain(|r| r.match_status == MatchStatus::Missing),
-            "divergent" => self.retain(|r| r.match_status == MatchStatus::Divergent),
-            "subaddress" => self.retain(|r| {
-                r.match_status == MatchStatus::Divergent && r.subaddress_type.is_some()
-            }),
-            "floor" => {
-                self.retain(|r| r.match_status == MatchStatus::Divergent && r.floor.is_some())
-            }
-            "building" => {
-                self.retain(|r| r.match_status == MatchStatus::Divergent && r.building.is_some())
-            }
-            "status" => {
-                self.retain(|r| r.match_status == MatchStatus::Divergent && r.status.is_some())
-            }
-            _ => info!("Invalid filter provided."),
+        if MatchRecord::FILTERS.contains(&filter) {
+            self.retain(|r| r.passes(filter));
+        } else {
+            info!("Invalid filter provided.");
         }
         self
     }
@@ -306,6 +1007,45 @@ impl IntoCsv<MatchRecords> for MatchRecords {
     }
 }
 
+impl IntoJson for MatchRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(feature = "io")]
+impl IntoPostgis<MatchRecords> for MatchRecords {
+    fn from_postgis(dsn: &str, table: &str) -> Result<Self, AddressError> {
+        let records = from_postgis(dsn, table)?;
+        Ok(Self(records))
+    }
+
+    fn to_postgis(&self, dsn: &str, table: &str) -> Result<(), AddressError> {
+        Ok(to_postgis(&self.0, dsn, table)?)
+    }
+}
+
+impl IntoParquet<MatchRecords> for MatchRecords {
+    fn from_parquet<P: AsRef<std::path::Path>>(path: P) -> Result<Self, AddressError> {
+        let records = from_parquet(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_parquet<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressError> {
+        Ok(to_parquet(&self.0, path)?)
+    }
+}
+
+impl IntoGeoJson for MatchRecords {
+    fn to_geojson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_geojson(&self.0, path)
+    }
+}
+
 /// The `MatchPartialRecord` struct contains match data for a [`PartialAddress`].
 #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct MatchPartialRecord {
@@ -322,6 +1062,21 @@ pub struct MatchPartialRecord {
 }
 
 impl MatchPartialRecord {
+    /// The `FILTERS` constant lists the filter values accepted by [`Self::passes`] and
+    /// [`MatchPartialRecords::filter`].
+    pub const FILTERS: [&str; 3] = ["matching", "missing", "divergent"];
+
+    /// The `passes` method returns true if the match status of the record equals `filter`.
+    /// Records pass any unrecognized filter.
+    pub fn passes(&self, filter: &str) -> bool {
+        match filter {
+            "matching" => self.match_status == MatchStatus::Matching,
+            "missing" => self.match_status == MatchStatus::Missing,
+            "divergent" => self.match_status == MatchStatus::Divergent,
+            _ => true,
+        }
+    }
+
     /// The `coincident` method attempts to match fields present in the partial address against the
     /// comparison address, returning a `MatchPartialRecord` if successful.  Returns `None` if
     /// the match status is "missing".
@@ -331,10 +1086,10 @@ impl MatchPartialRecord {
     ) -> Option<MatchPartialRecord> {
         let mut match_status = MatchStatus::Missing;
 
-        if let Some(value) = partial.address_number {
-            if value == address.number() {
-                match_status = MatchStatus::Matching;
-            }
+        if let Some(value) = partial.address_number
+            && value == address.number()
+        {
+            match_status = MatchStatus::Matching;
         }
 
         if &partial.street_name_pre_directional != address.directional()
@@ -343,18 +1098,19 @@ impl MatchPartialRecord {
             match_status = MatchStatus::Missing;
         }
 
-        if let Some(value) = &partial.street_name {
-            if value != address.street_name() && match_status == MatchStatus::Matching {
-                match_status = MatchStatus::Missing;
-            }
+        if let Some(value) = &partial.street_name
+            && value != address.street_name()
+            && match_status == MatchStatus::Matching
+        {
+            match_status = MatchStatus::Missing;
         }
 
-        if let Some(value) = partial.street_name_post_type() {
-            if let &Some(street_type) = address.street_type() {
-                if value != street_type && match_status == MatchStatus::Matching {
-                    match_status = MatchStatus::Missing;
-                }
-            }
+        if let Some(value) = partial.street_name_post_type()
+            && let &Some(street_type) = address.street_type()
+            && value != street_type
+            && match_status == MatchStatus::Matching
+        {
+            match_status = MatchStatus::Missing;
         }
 
         if &partial.subaddress_identifier() != address.subaddress_id()
@@ -462,14 +1218,10 @@ impl MatchPartialRecords {
         self_addresses: &PartialAddresses,
         other_addresses: &[T],
     ) -> Self {
-        let style = indicatif::ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {'Comparing addresses.'}",
-        )
-        .unwrap();
         let record = self_addresses
             .par_iter()
             .map(|address| MatchPartialRecord::compare(address, other_addresses))
-            .progress_with_style(style)
+            .progress_with(progress_bar(self_addresses.len(), "Comparing addresses."))
             .collect::<Vec<MatchPartialRecords>>();
         let mut records = Vec::new();
         for mut item in record {
@@ -483,11 +1235,10 @@ impl MatchPartialRecords {
     /// "missing", or "divergent".  The return records contain those records where the match status equals the
     /// filter value.
     pub fn filter(mut self, filter: &str) -> Self {
-        match filter {
-            "missing" => self.retain(|r| r.match_status == MatchStatus::Missing),
-            "divergent" => self.retain(|r| r.match_status == MatchStatus::Divergent),
-            "matching" => self.retain(|r| r.match_status == MatchStatus::Matching),
-            _ => info!("Invalid filter provided."),
+        if MatchPartialRecord::FILTERS.contains(&filter) {
+            self.retain(|r| r.passes(filter));
+        } else {
+            info!("Invalid filter provided.");
         }
         self
     }
@@ -503,3 +1254,134 @@ impl IntoCsv<MatchPartialRecords> for MatchPartialRecords {
         to_csv(&mut self.0, path.as_ref().into())
     }
 }
+
+impl IntoJson for MatchPartialRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommonAddress, IntoBin, SpatialAddress, SpatialAddresses, StreetNamePostType};
+    use tracing::info;
+
+    fn address(street_name: &str) -> SpatialAddress {
+        SpatialAddress {
+            address: CommonAddress {
+                number: 1450,
+                street_name: street_name.to_string(),
+                street_type: Some(StreetNamePostType::AVENUE),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn exact_candidate_beats_fuzzy() {
+        let subject = address("RIVERSIDE");
+        let candidates = vec![address("RIVER SIDE"), address("RIVERSIDE")];
+        let scoring = MatchScoring::new(0.9);
+        let fuzzy = scoring.compare(&subject, &candidates[0]);
+        let exact = scoring.compare(&subject, &candidates[1]);
+        assert!(fuzzy.coincident && exact.coincident);
+        assert!(outranks(&exact, &fuzzy));
+        assert!(!outranks(&fuzzy, &exact));
+        assert_ne!(fuzzy, exact);
+        let matches = std::collections::HashSet::from([fuzzy, exact.clone(), exact]);
+        assert_eq!(matches.len(), 2);
+
+        // The exact candidate wins in either order, and the fuzzy candidate is not reported.
+        for candidates in [candidates.clone(), candidates.into_iter().rev().collect()] {
+            let records = MatchRecords::new_fuzzy(&subject, &candidates, 0.9);
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].match_status, MatchStatus::Matching);
+            assert_eq!(records[0].similarity, None);
+            assert_eq!(records[0].street_name, None);
+        }
+    }
+
+    #[test]
+    fn compare_within_radius() -> anyhow::Result<()> {
+        let city_path = "data/addresses.data";
+        let county_path = "data/county_addresses.data";
+        let city_addresses = SpatialAddresses::load(city_path)?;
+        let county_addresses = SpatialAddresses::load(county_path)?;
+        let exhaustive = MatchRecords::compare(&city_addresses[0..1000], &county_addresses);
+        let indexed =
+            MatchRecords::compare_within(&city_addresses[0..1000], &county_addresses, 500.0, 1.0);
+        let missing = |records: &MatchRecords| {
+            records
+                .iter()
+                .filter(|r| r.match_status == MatchStatus::Missing)
+                .count()
+        };
+        assert_eq!(missing(&exhaustive), missing(&indexed));
+        assert_eq!(exhaustive.len(), indexed.len());
+        info!("Indexed comparison agrees with exhaustive comparison.");
+        Ok(())
+    }
+
+    #[test]
+    fn field_level_diffs() -> anyhow::Result<()> {
+        let address = |floor: Option<i64>, status: AddressStatus| SpatialAddress {
+            address: CommonAddress {
+                number: 100,
+                street_name: "RIVERSIDE".to_string(),
+                street_type: Some(StreetNamePostType::AVENUE),
+                floor,
+                status,
+                zip: 97527,
+                postal_community: "GRANTS PASS".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let city = address(Some(2), AddressStatus::Current);
+        let county = vec![address(None, AddressStatus::Retired)];
+        let records = MatchRecords::new_scored(&city, &county, &MatchScoring::default());
+        let record = &records[0];
+        assert_eq!(record.match_status, MatchStatus::Divergent);
+        // The message columns are unchanged, with the values of each field in dedicated columns.
+        assert_eq!(record.floor, Some("Some(2) not equal to None".to_string()));
+        assert_eq!(record.floor_source, Some("2".to_string()));
+        assert_eq!(record.floor_target, None);
+        assert_eq!(record.status_source, Some("Current".to_string()));
+        assert_eq!(record.status_target, Some("Retired".to_string()));
+        assert_eq!(
+            record.diffs(),
+            vec![
+                (
+                    "floor",
+                    FieldDiff {
+                        source: Some("2".to_string()),
+                        target: None
+                    }
+                ),
+                (
+                    "status",
+                    FieldDiff {
+                        source: Some("Current".to_string()),
+                        target: Some("Retired".to_string())
+                    }
+                ),
+            ]
+        );
+        assert_eq!(records.divergence(), vec![("floor", 1), ("status", 1)]);
+
+        let path = std::env::temp_dir().join("destination_field_diffs.csv");
+        let mut records = records;
+        records.to_csv(&path)?;
+        let header = std::fs::read_to_string(&path)?;
+        assert!(header.contains("floor_source,floor_target"));
+        assert_eq!(MatchRecords::from_csv(&path)?, records);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
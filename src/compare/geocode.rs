@@ -0,0 +1,890 @@
+//! The `geocode` module matches free-form or partially structured address strings against a set
+//! of reference addresses, returning the coordinates of the best candidate along with a
+//! confidence score and the type of match.  Inputs naming the intersection of two streets, as in
+//! "6th St & M St", are located where the streets meet.
+use crate::compare::bench::meters_between;
+use crate::{
+    Address, AddressErrorKind, Centerline, Centerlines, Geographic, IntoCsv, IntoGeoJson, IntoJson,
+    Io, MapGrid, Parse, PartialAddress, StreetAliases, from_csv, progress_bar,
+    street_name_similarity, to_csv, to_geographic, to_geojson, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+
+/// The default similarity threshold for fuzzy street name matching when geocoding.
+pub const GEOCODE_THRESHOLD: f64 = 0.9;
+
+/// The greatest distance in meters between a pair of addresses on the two streets of an
+/// intersection for the pair to locate the intersection.
+pub const INTERSECTION_DISTANCE: f64 = 150.0;
+
+// The vertices of each part of a centerline.
+type Line = Vec<Vec<[f64; 2]>>;
+
+// The positions of the reference addresses on each street, by upper case street name.
+pub(crate) type StreetIndex = HashMap<String, Vec<usize>>;
+
+/// The `MatchType` enum describes how a geocoded address was located.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    PartialOrd,
+    Eq,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+    strum::EnumIter,
+)]
+pub enum MatchType {
+    /// The `Exact` variant indicates every field present in the input matches the reference
+    /// address.
+    #[display("exact")]
+    Exact,
+    /// The `Fuzzy` variant indicates the address number matches, but the street name or other
+    /// descriptive fields differ from the reference address.
+    #[display("fuzzy")]
+    Fuzzy,
+    /// The `Interpolated` variant indicates no address with a matching number exists, so the
+    /// result lies between the nearest lower and higher address numbers on the same side of the
+    /// street, in proportion to the address number.
+    #[display("interpolated")]
+    Interpolated,
+    /// The `Street` variant indicates no address with a matching number exists, and no addresses
+    /// on either side of the number to interpolate between, so the result falls back to the
+    /// address on the same street with the nearest address number.
+    #[display("street")]
+    Street,
+    /// The `Intersection` variant indicates the input names the intersection of two streets.
+    /// The result lies where the street centerlines cross, or without centerlines, midway
+    /// between the nearest pair of addresses on the two streets, at reduced confidence.
+    #[display("intersection")]
+    Intersection,
+    /// The `Unmatched` variant indicates the input could not be located.
+    #[default]
+    #[display("unmatched")]
+    Unmatched,
+}
+
+/// The `GeocodeInput` struct represents a row of input to the geocoder.  The `address` field
+/// holds a one-line address.  If the address does not include a zip code, the optional `zip`
+/// field supplies it.  Other columns in the input file are ignored.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct GeocodeInput {
+    /// The `address` field holds the free-form address to geocode.
+    pub address: String,
+    /// The `zip` field holds an optional zip code for the address.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub zip: Option<i64>,
+}
+
+/// The `GeocodeInputs` struct holds a vector of type [`GeocodeInput`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct GeocodeInputs(Vec<GeocodeInput>);
+
+impl IntoCsv<GeocodeInputs> for GeocodeInputs {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+/// The `GeocodeRecord` struct reports the result of geocoding a single input address.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct GeocodeRecord {
+    /// The `input` field holds the address as provided.
+    pub input: String,
+    /// The `match_type` field indicates how the address was located.
+    pub match_type: MatchType,
+    /// The `confidence` field holds a score between 0 and 1 for the match, where 1 is an exact
+    /// match.
+    pub confidence: f64,
+    /// The `accuracy` field holds an estimate in meters of the distance between an interpolated
+    /// location and the true location, half the distance between the addresses on either side.
+    /// Absent for locations not interpolated.
+    #[serde(default)]
+    pub accuracy: Option<f64>,
+    /// The `label` field holds the address label of the matching reference address.
+    pub label: Option<String>,
+    /// The `latitude` field holds the latitude of the matching reference address.
+    pub latitude: Option<f64>,
+    /// The `longitude` field holds the longitude of the matching reference address.
+    pub longitude: Option<f64>,
+    /// The `grid` field holds the map grid reference of the located point, if the geocoder has a
+    /// [`MapGrid`].
+    #[serde(default)]
+    pub grid: Option<String>,
+}
+
+impl GeocodeRecord {
+    /// The `unmatched` method creates a record for an input that could not be located.
+    pub fn unmatched(input: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn located<T: Address + Geographic>(
+        input: &str,
+        match_type: MatchType,
+        confidence: f64,
+        address: &T,
+    ) -> Self {
+        Self {
+            input: input.to_string(),
+            match_type,
+            confidence,
+            accuracy: None,
+            label: Some(address.label()),
+            latitude: Some(address.latitude()),
+            longitude: Some(address.longitude()),
+            grid: None,
+        }
+    }
+
+    fn intersection(
+        input: &str,
+        confidence: f64,
+        label: String,
+        longitude: f64,
+        latitude: f64,
+    ) -> Self {
+        Self {
+            input: input.to_string(),
+            match_type: MatchType::Intersection,
+            confidence,
+            accuracy: None,
+            label: Some(label),
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            grid: None,
+        }
+    }
+}
+
+impl Geographic for GeocodeRecord {
+    fn latitude(&self) -> f64 {
+        self.latitude.unwrap_or_default()
+    }
+
+    fn longitude(&self) -> f64 {
+        self.longitude.unwrap_or_default()
+    }
+}
+
+/// The `GeocodeRecords` struct holds a vector of type [`GeocodeRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct GeocodeRecords(Vec<GeocodeRecord>);
+
+impl GeocodeRecords {
+    /// The `geocode` method geocodes each input in `inputs` against the reference `addresses`,
+    /// using a [`Geocoder`] with similarity threshold `threshold` for street names, and searching
+    /// the streets named in `aliases` for inputs on an aliased street.
+    pub fn geocode<T: Address + Geographic + Send + Sync>(
+        inputs: &[GeocodeInput],
+        addresses: &[T],
+        threshold: f64,
+        aliases: Option<&StreetAliases>,
+    ) -> Self {
+        info!("Indexing reference addresses.");
+        let geocoder = Geocoder::new(addresses, threshold).with_aliases(aliases);
+        Self::geocode_with(inputs, &geocoder)
+    }
+
+    /// The `geocode_with` method geocodes each input in `inputs` with `geocoder`.
+    pub fn geocode_with<T: Address + Geographic + Send + Sync>(
+        inputs: &[GeocodeInput],
+        geocoder: &Geocoder<T>,
+    ) -> Self {
+        let records = inputs
+            .par_iter()
+            .map(|input| geocoder.geocode_input(input))
+            .progress_with(progress_bar(inputs.len(), "Geocoding addresses."))
+            .collect::<Vec<GeocodeRecord>>();
+        Self(records)
+    }
+
+    /// The `filter` method returns the subset of records that match the filter.  Current values
+    /// for the `filter` field include "exact", "fuzzy", "interpolated", "street", "intersection"
+    /// and "unmatched", which filter by match type.
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "exact" => self.retain(|r| r.match_type == MatchType::Exact),
+            "fuzzy" => self.retain(|r| r.match_type == MatchType::Fuzzy),
+            "interpolated" => self.retain(|r| r.match_type == MatchType::Interpolated),
+            "street" => self.retain(|r| r.match_type == MatchType::Street),
+            "intersection" => self.retain(|r| r.match_type == MatchType::Intersection),
+            "unmatched" => self.retain(|r| r.match_type == MatchType::Unmatched),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+impl IntoCsv<GeocodeRecords> for GeocodeRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for GeocodeRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+// Unmatched records have no location, so they are left out of the point layer.
+impl IntoGeoJson for GeocodeRecords {
+    fn to_geojson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        let located = self
+            .iter()
+            .filter(|r| r.match_type != MatchType::Unmatched)
+            .cloned()
+            .collect::<Vec<GeocodeRecord>>();
+        to_geojson(&located, path)
+    }
+}
+
+/// The `Geocoder` struct indexes a set of reference addresses by street name, and scores the
+/// candidates on a matching street against an input address.
+///
+/// Candidates must share the address number and have a street name with similarity at or above
+/// the `threshold`.  The confidence starts at the street name similarity and is discounted for
+/// each field present in the input that disagrees with the candidate.  If no candidate shares the
+/// address number, the geocoder interpolates between the nearest lower and higher numbers of the
+/// same parity on the best matching street, at three quarters confidence.  Failing that, it falls
+/// back to the address on the best matching street with the nearest address number, at half
+/// confidence.
+///
+/// With a table of [`StreetAliases`], the geocoder also searches the aliases of the input street,
+/// so an input on "HWY 99" finds the reference addresses on "ROGUE RIVER HWY".  With road
+/// [`Centerlines`], intersections are located where the centerlines cross.  With a [`MapGrid`],
+/// each located point reports the grid reference of its cell.
+#[derive(Debug, Clone)]
+pub struct Geocoder<'a, T> {
+    addresses: &'a [T],
+    streets: Arc<StreetIndex>,
+    threshold: f64,
+    aliases: Option<&'a StreetAliases>,
+    // The centerlines with the EPSG code of their projected coordinates.
+    centerlines: Option<(&'a Centerlines, u32)>,
+    grid: Option<&'a MapGrid>,
+}
+
+impl<'a, T: Address + Geographic> Geocoder<'a, T> {
+    /// The `new` method creates a `Geocoder` over the reference `addresses`.
+    pub fn new(addresses: &'a [T], threshold: f64) -> Self {
+        Self::from_index(addresses, Arc::new(Self::index(addresses)), threshold)
+    }
+
+    // Returns the street index of `addresses`.
+    pub(crate) fn index(addresses: &[T]) -> StreetIndex {
+        let mut streets = StreetIndex::new();
+        for (i, address) in addresses.iter().enumerate() {
+            streets
+                .entry(address.street_name().to_uppercase())
+                .or_default()
+                .push(i);
+        }
+        streets
+    }
+
+    // Creates a geocoder over `addresses` from their street index, built once by `index` and
+    // shared between geocoders over the same addresses.
+    pub(crate) fn from_index(
+        addresses: &'a [T],
+        streets: Arc<StreetIndex>,
+        threshold: f64,
+    ) -> Self {
+        Self {
+            addresses,
+            streets,
+            threshold,
+            aliases: None,
+            centerlines: None,
+            grid: None,
+        }
+    }
+
+    /// The `with_aliases` method sets the table of street aliases searched by the geocoder.
+    pub fn with_aliases(mut self, aliases: Option<&'a StreetAliases>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// The `with_centerlines` method sets the road centerlines used to locate intersections, in
+    /// the projected coordinate system `epsg`.  See [`crate::EPSG_CODES`] for supported systems.
+    pub fn with_centerlines(mut self, centerlines: &'a Centerlines, epsg: u32) -> Self {
+        self.centerlines = Some((centerlines, epsg));
+        self
+    }
+
+    /// The `with_grid` method sets the map grid used to report the grid reference of each
+    /// located point.
+    pub fn with_grid(mut self, grid: Option<&'a MapGrid>) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    /// The `geocode` method parses the one-line address `input` and returns the best match from
+    /// the reference addresses.
+    pub fn geocode(&self, input: &str) -> GeocodeRecord {
+        if let Some((first, second)) = parse_intersection(input) {
+            return self.geocode_intersection(input, &first, &second);
+        }
+        match Parse::address(input) {
+            Ok((_, partial)) => self.geocode_partial(input, &partial),
+            Err(_) => GeocodeRecord::unmatched(input),
+        }
+    }
+
+    /// The `geocode_input` method geocodes a [`GeocodeInput`], filling in the zip code from the
+    /// input if the address does not include one.
+    pub fn geocode_input(&self, input: &GeocodeInput) -> GeocodeRecord {
+        if let Some((first, second)) = parse_intersection(&input.address) {
+            return self.geocode_intersection(&input.address, &first, &second);
+        }
+        match Parse::address(&input.address) {
+            Ok((_, mut partial)) => {
+                if partial.zip_code.is_none() {
+                    partial.zip_code = input.zip;
+                }
+                self.geocode_partial(&input.address, &partial)
+            }
+            Err(_) => GeocodeRecord::unmatched(&input.address),
+        }
+    }
+
+    /// The `geocode_partial` method returns the best match for a [`PartialAddress`] from the
+    /// reference addresses.  The `input` is the original text, reported in the result.
+    pub fn geocode_partial(&self, input: &str, partial: &PartialAddress) -> GeocodeRecord {
+        self.with_cell(self.locate(input, partial))
+    }
+
+    // Returns the record with the grid reference of the located point, if the geocoder has a
+    // map grid.
+    fn with_cell(&self, mut record: GeocodeRecord) -> GeocodeRecord {
+        if let (Some(grid), Some(longitude), Some(latitude)) =
+            (self.grid, record.longitude, record.latitude)
+        {
+            record.grid = grid.cell_at(longitude, latitude).map(str::to_string);
+        }
+        record
+    }
+
+    // Returns the best match for `partial` from the reference addresses.
+    fn locate(&self, input: &str, partial: &PartialAddress) -> GeocodeRecord {
+        let Some(street_name) = &partial.street_name else {
+            return GeocodeRecord::unmatched(input);
+        };
+        let streets = self.streets(street_name, partial);
+        if streets.is_empty() {
+            return GeocodeRecord::unmatched(input);
+        }
+
+        // Score candidates sharing the address number.
+        if let Some(number) = partial.address_number {
+            let mut best: Option<(f64, &T)> = None;
+            for (similarity, indices, aliased) in &streets {
+                for i in indices.iter() {
+                    let address = &self.addresses[*i];
+                    if address.number() != number || !self.on_street(partial, address, *aliased) {
+                        continue;
+                    }
+                    let score = similarity * Self::agreement(partial, address, *aliased);
+                    if best.is_none_or(|(top, _)| score > top) {
+                        best = Some((score, address));
+                    }
+                }
+            }
+            if let Some((score, address)) = best {
+                let match_type = if score >= 1.0 {
+                    MatchType::Exact
+                } else {
+                    MatchType::Fuzzy
+                };
+                return GeocodeRecord::located(input, match_type, score, address);
+            }
+        }
+
+        // Interpolate between the nearest address numbers on the same side of the best matching
+        // street.
+        if let Some(number) = partial.address_number
+            && let Some(record) = self.interpolate(input, partial, &streets[0], number)
+        {
+            return record;
+        }
+
+        // Fall back to the nearest address number on the best matching street.
+        let (similarity, indices, aliased) = &streets[0];
+        let target = partial.address_number.unwrap_or_default();
+        let nearest = indices
+            .iter()
+            .map(|i| &self.addresses[*i])
+            .filter(|a| {
+                (partial.street_name_pre_directional.is_none()
+                    || &partial.street_name_pre_directional == a.directional())
+                    && self.on_street(partial, a, *aliased)
+            })
+            .min_by_key(|a| (a.number() - target).abs());
+        match nearest {
+            Some(address) => {
+                GeocodeRecord::located(input, MatchType::Street, similarity * 0.5, address)
+            }
+            None => GeocodeRecord::unmatched(input),
+        }
+    }
+
+    // Returns the record for the location of `number` interpolated between the nearest lower
+    // and higher address numbers of the same parity on `street`, or `None` if either is missing.
+    fn interpolate(
+        &self,
+        input: &str,
+        partial: &PartialAddress,
+        street: &(f64, &Vec<usize>, bool),
+        number: i64,
+    ) -> Option<GeocodeRecord> {
+        let (similarity, indices, aliased) = street;
+        let mut lower: Option<&T> = None;
+        let mut higher: Option<&T> = None;
+        for address in indices.iter().map(|i| &self.addresses[*i]) {
+            if (address.number() - number) % 2 != 0
+                || (partial.street_name_pre_directional.is_some()
+                    && &partial.street_name_pre_directional != address.directional())
+                || !self.on_street(partial, address, *aliased)
+            {
+                continue;
+            }
+            if address.number() < number && lower.is_none_or(|a| address.number() > a.number()) {
+                lower = Some(address);
+            } else if address.number() > number
+                && higher.is_none_or(|a| address.number() < a.number())
+            {
+                higher = Some(address);
+            }
+        }
+        let (lower, higher) = (lower?, higher?);
+        let fraction = (number - lower.number()) as f64 / (higher.number() - lower.number()) as f64;
+        Some(GeocodeRecord {
+            input: input.to_string(),
+            match_type: MatchType::Interpolated,
+            confidence: similarity * 0.75,
+            accuracy: Some(meters_between(lower, higher) / 2.0),
+            label: Some(format!("{number} {}", lower.complete_street_name(true))),
+            latitude: Some(lower.latitude() + fraction * (higher.latitude() - lower.latitude())),
+            longitude: Some(
+                lower.longitude() + fraction * (higher.longitude() - lower.longitude()),
+            ),
+            grid: None,
+        })
+    }
+
+    /// The `geocode_intersection` method returns the point where the streets of `first` and
+    /// `second` meet.  With centerlines, the point is where the centerlines of the two streets
+    /// cross.  Otherwise, or if the centerlines do not cross, the point lies midway between the
+    /// nearest pair of reference addresses on the two streets, if the pair lies within
+    /// [`INTERSECTION_DISTANCE`].  The `input` is the original text, reported in the result.
+    pub fn geocode_intersection(
+        &self,
+        input: &str,
+        first: &PartialAddress,
+        second: &PartialAddress,
+    ) -> GeocodeRecord {
+        let record = self
+            .crossing(input, first, second)
+            .or_else(|| self.nearest_pair(input, first, second))
+            .unwrap_or_else(|| GeocodeRecord::unmatched(input));
+        self.with_cell(record)
+    }
+
+    // Returns the record for the point where the centerlines of `first` and `second` cross, if
+    // centerlines are set.  Of several crossings, the one on the most similar street names wins.
+    fn crossing(
+        &self,
+        input: &str,
+        first: &PartialAddress,
+        second: &PartialAddress,
+    ) -> Option<GeocodeRecord> {
+        let (centerlines, epsg) = self.centerlines?;
+        let (first, second) = (
+            self.segments(centerlines, first),
+            self.segments(centerlines, second),
+        );
+        let mut best: Option<(f64, [f64; 2], String)> = None;
+        for (similarity, a, a_lines) in &first {
+            for (other, b, b_lines) in &second {
+                let score = similarity * other;
+                if a.street() == b.street() || best.as_ref().is_some_and(|(top, ..)| score <= *top)
+                {
+                    continue;
+                }
+                if let Some(point) = crossing_point(a_lines, b_lines) {
+                    best = Some((score, point, format!("{} & {}", a.street(), b.street())));
+                }
+            }
+        }
+        let (score, [x, y], label) = best?;
+        let (longitude, latitude) = to_geographic(epsg, x, y).ok()?;
+        Some(GeocodeRecord::intersection(
+            input, score, label, longitude, latitude,
+        ))
+    }
+
+    // Returns the centerline segments on the street of `partial`, with the similarity of the
+    // street name and the vertices of the segment.
+    fn segments<'c>(
+        &self,
+        centerlines: &'c Centerlines,
+        partial: &PartialAddress,
+    ) -> Vec<(f64, &'c Centerline, Line)> {
+        let Some(name) = &partial.street_name else {
+            return Vec::new();
+        };
+        let name = name.to_uppercase();
+        let aliases = self
+            .aliases
+            .map(|aliases| aliases.street_names(partial))
+            .unwrap_or_default();
+        centerlines
+            .iter()
+            .filter_map(|segment| {
+                let street = segment.street_name.to_uppercase();
+                let aliased = aliases.contains(&street.as_str());
+                let similarity = if aliased {
+                    1.0
+                } else {
+                    street_name_similarity(&name, &street, self.threshold)?
+                };
+                let directional = partial.street_name_pre_directional.is_none()
+                    || partial.street_name_pre_directional == segment.street_name_pre_directional;
+                let post_type = aliased
+                    || partial.street_name_post_type.is_none()
+                    || segment.street_name_post_type.is_none()
+                    || partial.street_name_post_type == segment.street_name_post_type;
+                (directional && post_type).then(|| (similarity, segment, segment.vertices()))
+            })
+            .collect()
+    }
+
+    // Returns the record for the midpoint of the nearest pair of reference addresses on the
+    // streets of `first` and `second`, if the pair lies within [`INTERSECTION_DISTANCE`].  The
+    // confidence is discounted, since the midpoint only approximates the intersection.
+    fn nearest_pair(
+        &self,
+        input: &str,
+        first: &PartialAddress,
+        second: &PartialAddress,
+    ) -> Option<GeocodeRecord> {
+        let (first, second) = (self.street_addresses(first), self.street_addresses(second));
+        let mut best: Option<(f64, f64, &T, &T)> = None;
+        for (similarity, a) in &first {
+            for (other, b) in &second {
+                if a.street_name() == b.street_name() && a.street_type() == b.street_type() {
+                    continue;
+                }
+                let distance = meters_between(*a, *b);
+                if distance <= INTERSECTION_DISTANCE
+                    && best.is_none_or(|(nearest, ..)| distance < nearest)
+                {
+                    best = Some((distance, similarity * other, a, b));
+                }
+            }
+        }
+        let (_, similarity, a, b) = best?;
+        Some(GeocodeRecord::intersection(
+            input,
+            similarity * 0.75,
+            format!(
+                "{} & {}",
+                a.complete_street_name(true),
+                b.complete_street_name(true)
+            ),
+            (a.longitude() + b.longitude()) / 2.0,
+            (a.latitude() + b.latitude()) / 2.0,
+        ))
+    }
+
+    // Returns the reference addresses on the street of `partial`, with the similarity of the
+    // street name.
+    fn street_addresses(&self, partial: &PartialAddress) -> Vec<(f64, &T)> {
+        let Some(name) = &partial.street_name else {
+            return Vec::new();
+        };
+        let mut addresses = Vec::new();
+        for (similarity, indices, aliased) in self.streets(name, partial) {
+            for i in indices {
+                let address = &self.addresses[*i];
+                let directional = partial.street_name_pre_directional.is_none()
+                    || &partial.street_name_pre_directional == address.directional();
+                let post_type = aliased
+                    || partial.street_name_post_type.is_none()
+                    || address.street_type().is_none()
+                    || &partial.street_name_post_type == address.street_type();
+                if directional && post_type && self.on_street(partial, address, aliased) {
+                    addresses.push((similarity, address));
+                }
+            }
+        }
+        addresses
+    }
+
+    // Returns the streets matching `name` with their similarity, best match first, followed by
+    // the aliases of the street in `partial`.  Aliased streets are flagged, because they share
+    // only the street name with the alias, and the other addresses on the street do not match.
+    fn streets(&self, name: &str, partial: &PartialAddress) -> Vec<(f64, &Vec<usize>, bool)> {
+        let name = name.to_uppercase();
+        let mut streets = match self.streets.get(&name) {
+            Some(indices) => vec![(1.0, indices, false)],
+            None => {
+                let mut streets = self
+                    .streets
+                    .iter()
+                    .filter_map(|(street, indices)| {
+                        street_name_similarity(&name, street, self.threshold)
+                            .map(|score| (score, indices, false))
+                    })
+                    .collect::<Vec<(f64, &Vec<usize>, bool)>>();
+                streets.sort_by(|a, b| b.0.total_cmp(&a.0));
+                streets
+            }
+        };
+        if let Some(aliases) = self.aliases {
+            for alias in aliases.street_names(partial) {
+                if alias != name
+                    && let Some(indices) = self.streets.get(alias)
+                {
+                    streets.push((1.0, indices, true));
+                }
+            }
+        }
+        streets
+    }
+
+    // Returns true if `address` is on the street of `partial`, for candidates found through an
+    // alias.  Candidates found by street name are always on the street.
+    fn on_street(&self, partial: &PartialAddress, address: &T, aliased: bool) -> bool {
+        !aliased
+            || self
+                .aliases
+                .is_some_and(|aliases| aliases.names_street(partial, address))
+    }
+
+    // Returns a discount factor between 0 and 1 for the fields of `partial` that disagree with
+    // `address`.  Fields absent from the input do not count against the candidate, except that a
+    // candidate with a unit is a slightly worse match for an input without one.  The street type
+    // of a candidate found through an alias is not compared.
+    fn agreement(partial: &PartialAddress, address: &T, aliased: bool) -> f64 {
+        let mut factor = 1.0;
+        if partial.address_number_suffix != *address.number_suffix() {
+            factor *= 0.9;
+        }
+        if partial.street_name_pre_directional.is_some()
+            && &partial.street_name_pre_directional != address.directional()
+        {
+            factor *= 0.5;
+        } else if partial.street_name_pre_directional.is_none() && address.directional().is_some() {
+            factor *= 0.9;
+        }
+        if let Some(post_type) = partial.street_name_post_type
+            && !aliased
+            && address.street_type().is_some_and(|t| t != post_type)
+        {
+            factor *= 0.8;
+        }
+        match (&partial.subaddress_identifier, address.subaddress_id()) {
+            (Some(id), Some(other)) if !id.eq_ignore_ascii_case(other) => factor *= 0.7,
+            (Some(_), None) => factor *= 0.8,
+            (None, Some(_)) => factor *= 0.95,
+            _ => {}
+        }
+        if let Some(zip) = partial.zip_code
+            && zip != address.zip()
+        {
+            factor *= 0.9;
+        }
+        if let Some(community) = &partial.postal_community
+            && !community
+                .label()
+                .eq_ignore_ascii_case(address.postal_community())
+        {
+            factor *= 0.9;
+        }
+        factor
+    }
+}
+
+/// The `parse_intersection` function splits an input naming the intersection of two streets, as
+/// in "6th St & M St" or "NE 6TH ST AND M ST, GRANTS PASS", into the two streets.  The streets
+/// may be joined by "&", "@", "/" or "and".  Returns `None` if the input begins with an address
+/// number, or if either side lacks a street name.
+pub fn parse_intersection(input: &str) -> Option<(PartialAddress, PartialAddress)> {
+    let text = input.to_uppercase();
+    if text
+        .split_whitespace()
+        .next()?
+        .chars()
+        .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let (first, second) = ["&", "@", "/", " AND "]
+        .iter()
+        .find_map(|separator| text.split_once(separator))?;
+    let street = |side: &str| match Parse::address(&format!("1 {}", side.trim())) {
+        Ok((_, partial)) if partial.street_name.is_some() => Some(partial),
+        _ => None,
+    };
+    Some((street(first)?, street(second)?))
+}
+
+// Returns the first point where a segment of the line `a` meets a segment of the line `b`.
+fn crossing_point(a: &[Vec<[f64; 2]>], b: &[Vec<[f64; 2]>]) -> Option<[f64; 2]> {
+    let segments = |line: &[Vec<[f64; 2]>]| {
+        line.iter()
+            .flat_map(|part| part.windows(2).map(|pair| (pair[0], pair[1])))
+            .collect::<Vec<([f64; 2], [f64; 2])>>()
+    };
+    let (a, b) = (segments(a), segments(b));
+    a.iter()
+        .find_map(|(p, q)| b.iter().find_map(|(r, s)| segment_crossing(*p, *q, *r, *s)))
+}
+
+// Returns the point where the segment from `p` to `q` meets the segment from `r` to `s`, if any.
+// Parallel segments do not meet.
+fn segment_crossing(p: [f64; 2], q: [f64; 2], r: [f64; 2], s: [f64; 2]) -> Option<[f64; 2]> {
+    let d1 = [q[0] - p[0], q[1] - p[1]];
+    let d2 = [s[0] - r[0], s[1] - r[1]];
+    let denominator = d1[0] * d2[1] - d1[1] * d2[0];
+    if denominator.abs() < f64::EPSILON {
+        return None;
+    }
+    let offset = [r[0] - p[0], r[1] - p[1]];
+    let t = (offset[0] * d2[1] - offset[1] * d2[0]) / denominator;
+    let u = (offset[0] * d1[1] - offset[1] * d1[0]) / denominator;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u))
+        .then(|| [p[0] + t * d1[0], p[1] + t * d1[1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        IntoBin, SpatialAddresses, StreetNamePostType, StreetNamePreDirectional, from_geographic,
+    };
+    use tracing::info;
+
+    #[test]
+    fn geocode_sample() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let inputs = GeocodeInputs::from_csv("data/address_sample.csv")?;
+        let records = GeocodeRecords::geocode(&inputs, &city_addresses, GEOCODE_THRESHOLD, None);
+        assert_eq!(records.len(), inputs.len());
+        for kind in ["exact", "fuzzy", "interpolated", "street", "unmatched"] {
+            info!("{kind}: {}", records.clone().filter(kind).len());
+        }
+        let geocoder = Geocoder::new(&city_addresses, GEOCODE_THRESHOLD);
+        let exact = geocoder.geocode("322 SE K Street, Grants Pass, OR 97526");
+        assert_eq!(exact.match_type, MatchType::Exact);
+        assert_eq!(exact.label, Some("322 SE K ST".to_string()));
+        let fuzzy = geocoder.geocode("322 SE K Street, Grants Pass, OR 97527");
+        assert_eq!(fuzzy.match_type, MatchType::Fuzzy);
+        assert!(fuzzy.confidence < 1.0);
+        // 325 lies between 323 and 401 on the odd side of the street.
+        let interpolated = geocoder.geocode("325 SE K Street, Grants Pass, OR 97526");
+        assert_eq!(interpolated.match_type, MatchType::Interpolated);
+        assert_eq!(interpolated.label, Some("325 SE K ST".to_string()));
+        assert!(interpolated.accuracy.is_some_and(|a| a > 0.0));
+        let between = |a: &str, b: &str| {
+            let (a, b) = (geocoder.geocode(a), geocoder.geocode(b));
+            let latitude = interpolated.latitude();
+            latitude >= a.latitude().min(b.latitude()) && latitude <= a.latitude().max(b.latitude())
+        };
+        assert!(between("323 SE K Street", "401 SE K Street"));
+        let street = geocoder.geocode("9999 SE K Street, Grants Pass, OR 97526");
+        assert_eq!(street.match_type, MatchType::Street);
+        assert!(street.accuracy.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn intersection_geocoding() -> anyhow::Result<()> {
+        let (first, second) = parse_intersection("NE 6th St & NE E St, Grants Pass, OR 97526")
+            .expect("an intersection");
+        assert_eq!(first.street_name.as_deref(), Some("6TH"));
+        assert_eq!(second.street_name.as_deref(), Some("E"));
+        assert!(parse_intersection("322 SE K Street, Grants Pass, OR 97526").is_none());
+
+        // Without centerlines, the intersection lies between the nearest addresses on the streets.
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let geocoder = Geocoder::new(&city_addresses, GEOCODE_THRESHOLD);
+        let record = geocoder.geocode("NE 6th St and NE E St");
+        assert_eq!(record.match_type, MatchType::Intersection);
+        assert_eq!(record.label.as_deref(), Some("NE 6TH ST & NE E ST"));
+        assert!(record.confidence < 1.0);
+        // The point lies within the intersection distance of an address on NE E ST.
+        let nearest = city_addresses
+            .iter()
+            .filter(|a| {
+                a.street_name() == "E" && a.directional_abbreviated().as_deref() == Some("NE")
+            })
+            .map(|a| meters_between(a, &record))
+            .fold(f64::MAX, f64::min);
+        assert!(nearest <= INTERSECTION_DISTANCE);
+        assert_eq!(
+            geocoder.geocode("6th St & 6th St").match_type,
+            MatchType::Unmatched
+        );
+
+        // With centerlines, the intersection lies where the centerlines cross.
+        let (x, y) = from_geographic(2270, -123.3253, 42.4407)?;
+        let line = |name: &str, wkt: String| Centerline {
+            street_name_pre_directional: Some(StreetNamePreDirectional::NORTHEAST),
+            street_name: name.to_string(),
+            street_name_post_type: Some(StreetNamePostType::STREET),
+            wkt,
+            ..Default::default()
+        };
+        let mut lines = vec![
+            line(
+                "6TH",
+                format!("LINESTRING ({} {}, {} {})", x, y - 300.0, x, y + 300.0),
+            ),
+            line(
+                "E",
+                format!("LINESTRING ({} {}, {} {})", x - 300.0, y, x + 300.0, y),
+            ),
+        ];
+        let mut centerlines = Centerlines::default();
+        centerlines.append(&mut lines);
+        let geocoder = geocoder.with_centerlines(&centerlines, 2270);
+        let record = geocoder.geocode("NE 6th St & NE E St");
+        assert_eq!(record.match_type, MatchType::Intersection);
+        assert_eq!(record.confidence, 1.0);
+        assert!((record.longitude() - -123.3253).abs() < 1e-6);
+        assert!((record.latitude() - 42.4407).abs() < 1e-6);
+        Ok(())
+    }
+}
@@ -1,6 +1,37 @@
 //! The `compare` module contains data types and methods for comparing addresses.
+mod asset;
+mod bench;
+mod blocking;
+mod cache;
+mod chain;
 mod compare_fire;
 mod eponym;
+mod geocode;
+mod nearest;
+mod overlap;
+mod reconcile;
+mod relate;
+mod reverse;
+mod score;
 
+pub use asset::{
+    ASSET_AMBIGUITY, ASSET_CONFIDENT_DISTANCE, ASSET_DISTANCE, AssetAssignment, AssetAssignments,
+    AssetConfidence,
+};
+pub use bench::*;
+pub use blocking::*;
+pub use cache::{CHECKPOINT_INTERVAL, MatchCache};
+pub use chain::{TargetMatchRecord, TargetMatchRecords, TargetStatus};
 pub use compare_fire::*;
 pub use eponym::*;
+pub(crate) use geocode::StreetIndex;
+pub use geocode::*;
+pub use nearest::{
+    NEAREST_DISTANCE, NEAREST_NEIGHBORS, NearestRecord, NearestRecords, NearestStatus,
+};
+pub use overlap::{OverlapRecord, OverlapRecords, Stewardship};
+pub use reconcile::{ReconcileRecord, ReconcileRecords, ReconcileStatus};
+pub use relate::*;
+pub use reverse::*;
+pub(crate) use score::Scorer;
+pub use score::{FieldWeight, MatchConfig, MatchScoring, MatchWeights};
@@ -0,0 +1,248 @@
+//! The `nearest` module lists the nearest target addresses to each source address, for auditing
+//! matches by location.  A source point whose nearest neighbor carries a different civic address,
+//! while a farther one agrees, has likely been snapped to the wrong structure.
+use crate::{
+    Address, AddressErrorKind, Cartesian, IntoCsv, IntoJson, Io, SpatialIndex, crs::geolocated,
+    from_csv, progress_bar, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// The `NEAREST_NEIGHBORS` constant holds the default number of target addresses listed for each
+/// source address.
+pub const NEAREST_NEIGHBORS: usize = 3;
+
+/// The `NEAREST_DISTANCE` constant holds the default search distance for neighboring addresses,
+/// in the units of the projected coordinates.
+pub const NEAREST_DISTANCE: f64 = 500.0;
+
+/// The `NearestStatus` enum summarizes the neighbors of a source address.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NearestStatus {
+    /// The nearest target address has the same civic address.
+    #[display("agrees")]
+    Agrees,
+    /// The nearest target address differs, but a farther neighbor has the same civic address.
+    #[display("misplaced")]
+    Misplaced,
+    /// None of the neighbors has the same civic address.
+    #[display("disagrees")]
+    Disagrees,
+    /// No target address lies within the search distance.
+    #[default]
+    #[display("isolated")]
+    Isolated,
+}
+
+geolocated! {
+    /// The `NearestRecord` struct pairs a source address with one of its nearest target addresses.
+    /// Source addresses without neighbors appear once, with empty neighbor fields.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct NearestRecord {
+        /// The `label` field holds the label of the source address.
+        pub label: String,
+        /// The `status` field summarizes the neighbors of the source address.
+        pub status: NearestStatus,
+        /// The `rank` field holds the position of the neighbor by distance, starting at one for the
+        /// nearest.
+        pub rank: Option<usize>,
+        /// The `neighbor` field holds the label of the target address.
+        pub neighbor: Option<String>,
+        /// The `distance` field holds the distance to the target address, in the units of the
+        /// projected coordinates.
+        pub distance: Option<f64>,
+        /// The `agrees` field is true if the civic addresses of the source and target agree,
+        /// comparing the address number and complete street name, without subaddresses.
+        pub agrees: Option<bool>,
+        /// The `x` field holds the X coordinate of the source address.
+        pub x: f64,
+        /// The `y` field holds the Y coordinate of the source address.
+        pub y: f64,
+    }
+}
+
+/// The `NearestRecords` struct holds a vector of type [`NearestRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct NearestRecords(Vec<NearestRecord>);
+
+impl NearestRecords {
+    /// The `nearest` method lists up to `neighbors` target addresses in `targets` nearest each
+    /// address in `addresses`, within `distance` in the units of the projected coordinates.  Uses
+    /// a [`SpatialIndex`] to select candidates.
+    pub fn nearest<T: Address + Cartesian + Sync, U: Address + Cartesian + Sync>(
+        addresses: &[T],
+        targets: &[U],
+        neighbors: usize,
+        distance: f64,
+    ) -> Self {
+        info!("Indexing target addresses.");
+        let index = SpatialIndex::from_cartesian(targets, distance);
+        let civics = targets.iter().map(civic).collect::<Vec<String>>();
+        let records = addresses
+            .par_iter()
+            .progress_with(progress_bar(addresses.len(), "Listing nearest addresses."))
+            .flat_map_iter(|address| {
+                let mut found = index
+                    .within([address.x(), address.y()], distance)
+                    .into_iter()
+                    .map(|i| (i, address.distance(&targets[i])))
+                    .collect::<Vec<(usize, f64)>>();
+                found.sort_by(|a, b| a.1.total_cmp(&b.1));
+                found.truncate(neighbors);
+                let source = civic(address);
+                let agrees = found
+                    .iter()
+                    .map(|(i, _)| civics[*i] == source)
+                    .collect::<Vec<bool>>();
+                let status = match agrees.first() {
+                    None => NearestStatus::Isolated,
+                    Some(true) => NearestStatus::Agrees,
+                    Some(false) if agrees.contains(&true) => NearestStatus::Misplaced,
+                    Some(false) => NearestStatus::Disagrees,
+                };
+                let record = NearestRecord {
+                    label: address.label(),
+                    status,
+                    x: address.x(),
+                    y: address.y(),
+                    ..Default::default()
+                };
+                if found.is_empty() {
+                    return vec![record];
+                }
+                found
+                    .iter()
+                    .zip(agrees)
+                    .enumerate()
+                    .map(|(rank, ((i, distance), agrees))| NearestRecord {
+                        rank: Some(rank + 1),
+                        neighbor: Some(targets[*i].label()),
+                        distance: Some(*distance),
+                        agrees: Some(agrees),
+                        ..record.clone()
+                    })
+                    .collect::<Vec<NearestRecord>>()
+            })
+            .collect::<Vec<NearestRecord>>();
+        Self(records)
+    }
+
+    /// The `count` method returns the number of source addresses with the status `status`.
+    pub fn count(&self, status: NearestStatus) -> usize {
+        self.iter()
+            .filter(|r| r.status == status && r.rank.is_none_or(|rank| rank == 1))
+            .count()
+    }
+
+    /// The `filter` method returns the subset of records with the status named by `filter`,
+    /// either "agrees", "misplaced", "disagrees" or "isolated".
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "agrees" => self.retain(|r| r.status == NearestStatus::Agrees),
+            "misplaced" => self.retain(|r| r.status == NearestStatus::Misplaced),
+            "disagrees" => self.retain(|r| r.status == NearestStatus::Disagrees),
+            "isolated" => self.retain(|r| r.status == NearestStatus::Isolated),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+// Returns the address number and complete street name of `address`, without the subaddress.
+fn civic<T: Address>(address: &T) -> String {
+    format!(
+        "{} {}",
+        address.complete_address_number(),
+        address.complete_street_name(true)
+    )
+}
+
+impl IntoCsv<NearestRecords> for NearestRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for NearestRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CommonAddress, SpatialAddress,
+        address::{riverside, riverside_point},
+    };
+
+    #[test]
+    fn nearest_neighbors() -> anyhow::Result<()> {
+        let point = |number: i64, unit: Option<&str>, x: f64, y: f64| SpatialAddress {
+            address: CommonAddress {
+                subaddress_id: unit.map(str::to_string),
+                ..riverside(number)
+            },
+            ..riverside_point(number, x, y)
+        };
+        let source = [
+            point(100, None, 0.0, 0.0),
+            point(102, None, 5.0, 1.0),
+            point(400, None, 5000.0, 5000.0),
+        ];
+        let target = [
+            point(102, None, 5.0, 0.0),
+            point(100, Some("A"), 10.0, 0.0),
+            point(300, None, 1000.0, 0.0),
+        ];
+        let records =
+            NearestRecords::nearest(&source, &target, NEAREST_NEIGHBORS, NEAREST_DISTANCE);
+        // Two neighbors for each of the first two sources, and an empty row for the third.
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[0].status, NearestStatus::Misplaced);
+        assert_eq!(records[0].rank, Some(1));
+        assert_eq!(records[0].neighbor.as_deref(), Some("102 RIVERSIDE AVE"));
+        assert_eq!(records[0].agrees, Some(false));
+        assert_eq!(records[1].neighbor.as_deref(), Some("100 RIVERSIDE AVE #A"));
+        assert_eq!(records[1].agrees, Some(true));
+        assert_eq!(records[2].status, NearestStatus::Agrees);
+        assert_eq!(records[2].distance, Some(1.0));
+        assert_eq!(records[4].status, NearestStatus::Isolated);
+        assert!(records[4].neighbor.is_none());
+        assert_eq!(records.count(NearestStatus::Misplaced), 1);
+        assert_eq!(records.count(NearestStatus::Isolated), 1);
+
+        let nearest = NearestRecords::nearest(&source, &target, 1, NEAREST_DISTANCE);
+        assert_eq!(nearest.len(), 3);
+        assert_eq!(nearest[0].status, NearestStatus::Disagrees);
+        assert_eq!(records.filter("misplaced").len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+//! The `overlap` module lists the addresses recorded by both the city and the county, and assigns
+//! each a steward for the annual reconciliation under the data-sharing agreement.  The city
+//! maintains the addresses inside the city limits or urban growth boundary, and the county those
+//! outside of it.
+use super::reconcile::{best_matches, differences};
+use crate::{
+    Address, AddressErrorKind, Boundary, Cartesian, Geographic, IntoCsv, IntoJson, Io,
+    MatchScoring, compare::Scorer, from_csv, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// The `Stewardship` enum names the dataset holding the authoritative copy of an address found in
+/// both datasets.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Stewardship {
+    /// The address lies inside the boundary, and the datasets agree.  The city record governs.
+    #[display("city_authoritative")]
+    CityAuthoritative,
+    /// The address lies outside the boundary, and the datasets agree.  The county record governs.
+    #[display("county_authoritative")]
+    CountyAuthoritative,
+    /// The datasets differ in some fields, or place the address on opposite sides of the
+    /// boundary, and the stewards must agree on a correction.
+    #[default]
+    #[display("conflicting")]
+    Conflicting,
+}
+
+/// The `OverlapRecord` struct pairs a city address with the matching county address.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct OverlapRecord {
+    /// The `stewardship` field holds the dataset holding the authoritative copy of the address.
+    pub stewardship: Stewardship,
+    /// The `city_label` field holds the label of the city address.
+    pub city_label: String,
+    /// The `county_label` field holds the label of the county address.
+    pub county_label: String,
+    /// The `inside` field is true if the city address lies inside the boundary.
+    pub inside: bool,
+    /// The `differences` field describes the fields that differ between the datasets, separated
+    /// by semicolons.  Addresses placed on opposite sides of the boundary note "boundary".
+    pub differences: Option<String>,
+    /// The `score` field holds the composite score of the match under the [`MatchScoring`].
+    pub score: Option<f64>,
+    /// The `longitude` field holds the longitude of the city address.
+    pub longitude: f64,
+    /// The `latitude` field holds the latitude of the city address.
+    pub latitude: f64,
+}
+
+/// The `OverlapRecords` struct holds a vector of type [`OverlapRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct OverlapRecords(Vec<OverlapRecord>);
+
+impl OverlapRecords {
+    /// The `overlap` method matches each address in `city` against `county`, scoring candidates
+    /// with `scoring`, and reports the addresses found in both.  When `radius` is set, only
+    /// candidates within `radius` meters are compared.  Matches inside `boundary` are
+    /// [`Stewardship::CityAuthoritative`] and matches outside it are
+    /// [`Stewardship::CountyAuthoritative`], unless the datasets differ or place the address on
+    /// opposite sides of the boundary, which makes them [`Stewardship::Conflicting`].
+    pub fn overlap<
+        T: Address + Cartesian + Geographic + Sync,
+        U: Address + Cartesian + Geographic + Sync,
+    >(
+        city: &[T],
+        county: &[U],
+        boundary: &Boundary,
+        radius: Option<f64>,
+        scoring: &MatchScoring,
+    ) -> Self {
+        let scorer = Scorer::new(scoring);
+        info!("Comparing city addresses to the county.");
+        let matches = best_matches(city, county, radius, &scorer);
+        let records = city
+            .iter()
+            .zip(matches)
+            .filter_map(|(address, found)| {
+                let (j, address_match) = found?;
+                let other = &county[j];
+                let inside = boundary.contains(address);
+                let straddles = inside != boundary.contains(other);
+                let mut differences = differences(&address_match);
+                if straddles {
+                    differences = Some(match differences {
+                        Some(d) => format!("{d}; boundary"),
+                        None => "boundary".to_string(),
+                    });
+                }
+                let divergent =
+                    differences.is_some() || address_match.score.is_some_and(|score| score < 1.0);
+                let stewardship = match (divergent, inside) {
+                    (true, _) => Stewardship::Conflicting,
+                    (false, true) => Stewardship::CityAuthoritative,
+                    (false, false) => Stewardship::CountyAuthoritative,
+                };
+                Some(OverlapRecord {
+                    stewardship,
+                    city_label: address.label(),
+                    county_label: other.label(),
+                    inside,
+                    differences,
+                    score: address_match.score,
+                    longitude: address.longitude(),
+                    latitude: address.latitude(),
+                })
+            })
+            .collect::<Vec<OverlapRecord>>();
+        info!(
+            "{} of {} city addresses found in the county dataset.",
+            records.len(),
+            city.len()
+        );
+        let records = Self(records);
+        for stewardship in [
+            Stewardship::CityAuthoritative,
+            Stewardship::CountyAuthoritative,
+            Stewardship::Conflicting,
+        ] {
+            info!("{stewardship}: {} records.", records.count(stewardship));
+        }
+        records
+    }
+
+    /// The `count` method returns the number of records with the stewardship `stewardship`.
+    pub fn count(&self, stewardship: Stewardship) -> usize {
+        self.iter().filter(|r| r.stewardship == stewardship).count()
+    }
+
+    /// The `filter` method returns the subset of records with the stewardship named by
+    /// `filter`, either "city_authoritative", "county_authoritative" or "conflicting".  The
+    /// filter "inside" keeps the addresses inside the boundary.
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "city_authoritative" => {
+                self.retain(|r| r.stewardship == Stewardship::CityAuthoritative)
+            }
+            "county_authoritative" => {
+                self.retain(|r| r.stewardship == Stewardship::CountyAuthoritative)
+            }
+            "conflicting" => self.retain(|r| r.stewardship == Stewardship::Conflicting),
+            "inside" => self.retain(|r| r.inside),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+impl IntoCsv<OverlapRecords> for OverlapRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for OverlapRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CommonAddress, SpatialAddress,
+        address::{riverside, riverside_point},
+    };
+
+    #[test]
+    fn overlap_stewardship() -> anyhow::Result<()> {
+        let point = |number: i64, floor: Option<i64>, x: f64, y: f64| SpatialAddress {
+            address: CommonAddress {
+                floor,
+                ..riverside(number)
+            },
+            ..riverside_point(number, x, y)
+        };
+        let boundary = Boundary {
+            rings: vec![vec![
+                [0.0, 0.0],
+                [100.0, 0.0],
+                [100.0, 100.0],
+                [0.0, 100.0],
+                [0.0, 0.0],
+            ]],
+            geographic: false,
+        };
+        let city = [
+            point(100, None, 10.0, 10.0),
+            point(200, None, 500.0, 500.0),
+            point(300, None, 50.0, 50.0),
+            point(400, None, 90.0, 50.0),
+            point(500, None, 20.0, 20.0),
+        ];
+        let county = [
+            point(100, None, 11.0, 10.0),
+            point(200, None, 501.0, 500.0),
+            point(300, Some(2), 50.0, 50.0),
+            point(400, None, 110.0, 50.0),
+        ];
+        let scoring = MatchScoring::new(1.0);
+        let records = OverlapRecords::overlap(&city, &county, &boundary, None, &scoring);
+        // The address found only in the city is not reported.
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].stewardship, Stewardship::CityAuthoritative);
+        assert!(records[0].inside);
+        assert_eq!(records[1].stewardship, Stewardship::CountyAuthoritative);
+        assert_eq!(records[2].stewardship, Stewardship::Conflicting);
+        assert!(records[2].differences.is_some());
+        assert_eq!(records[3].stewardship, Stewardship::Conflicting);
+        assert_eq!(records[3].differences.as_deref(), Some("boundary"));
+        assert_eq!(records.count(Stewardship::Conflicting), 2);
+        assert_eq!(records.filter("inside").len(), 3);
+        Ok(())
+    }
+}
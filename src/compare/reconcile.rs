@@ -0,0 +1,306 @@
+//! The `reconcile` module compares two address datasets in both directions, merging the results
+//! into a single report that lists each address found in both, in only one of the datasets, or in
+//! both with differences, as reviewed at the quarterly sync between the city and the county.
+use crate::{
+    Address, AddressErrorKind, AddressMatch, Geographic, IntoCsv, IntoJson, Io, MatchScoring,
+    Mismatch, SpatialIndex, compare::Scorer, from_csv, progress_bar, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::info;
+
+/// The `ReconcileStatus` enum places an address in the categories of the reconciliation report.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconcileStatus {
+    /// The address matches an address in the other dataset exactly.
+    #[display("both")]
+    Both,
+    /// The address matches an address in the other dataset, but differs in some fields.
+    #[display("divergent")]
+    Divergent,
+    /// The address is in the source, with no match in the target.
+    #[default]
+    #[display("only_source")]
+    OnlySource,
+    /// The address is in the target, with no match in the source.
+    #[display("only_target")]
+    OnlyTarget,
+}
+
+/// The `ReconcileRecord` struct represents a row of the reconciliation report, pairing a source
+/// address with its match in the target, or holding an address found in only one dataset.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ReconcileRecord {
+    /// The `reconcile_status` field holds the category of the row.
+    pub reconcile_status: ReconcileStatus,
+    /// The `source_label` field holds the label of the source address, if any.
+    pub source_label: Option<String>,
+    /// The `target_label` field holds the label of the target address, if any.
+    pub target_label: Option<String>,
+    /// The `differences` field describes the fields that differ between divergent addresses,
+    /// separated by semicolons.
+    pub differences: Option<String>,
+    /// The `score` field holds the composite score of the match under the [`MatchScoring`].
+    pub score: Option<f64>,
+    /// The `longitude` field represents the 'x' value of the source address point, or of the
+    /// target address point for addresses only in the target.
+    pub longitude: f64,
+    /// The `latitude` field represents the 'y' value of the source address point, or of the
+    /// target address point for addresses only in the target.
+    pub latitude: f64,
+}
+
+/// The `ReconcileRecords` struct holds a vector of type [`ReconcileRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct ReconcileRecords(Vec<ReconcileRecord>);
+
+impl ReconcileRecords {
+    /// The `compare` method matches each address in `source` against `target`, and each address
+    /// in `target` against `source`, scoring candidates with `scoring`.  When `radius` is set,
+    /// only candidates within `radius` meters are compared.  Source addresses appear once, with
+    /// their best match in the target.  Target addresses appear only when they have no match in
+    /// the source, or when their match in the source was not already reported, as can happen
+    /// when several target addresses match the same source address.
+    pub fn compare<T: Address + Geographic + Sync, U: Address + Geographic + Sync>(
+        source: &[T],
+        target: &[U],
+        radius: Option<f64>,
+        scoring: &MatchScoring,
+    ) -> Self {
+        let scorer = Scorer::new(scoring);
+        info!("Comparing source addresses to the target.");
+        let forward = best_matches(source, target, radius, &scorer);
+        info!("Comparing target addresses to the source.");
+        let backward = best_matches(target, source, radius, &scorer);
+
+        let mut records = Vec::with_capacity(source.len());
+        let mut reported = HashSet::new();
+        for (i, address) in source.iter().enumerate() {
+            let record = match &forward[i] {
+                Some((j, address_match)) => {
+                    reported.insert((i, *j));
+                    Self::pair(address, &target[*j], address_match)
+                }
+                None => ReconcileRecord {
+                    reconcile_status: ReconcileStatus::OnlySource,
+                    source_label: Some(address.label()),
+                    longitude: address.longitude(),
+                    latitude: address.latitude(),
+                    ..Default::default()
+                },
+            };
+            records.push(record);
+        }
+        for (j, address) in target.iter().enumerate() {
+            match &backward[j] {
+                Some((i, _)) if reported.contains(&(*i, j)) => {}
+                // A match seen only from the target side is reported from the source side, so
+                // the source and target columns stay in place.
+                Some((i, address_match)) => {
+                    reported.insert((*i, j));
+                    records.push(Self::pair(&source[*i], address, address_match));
+                }
+                None => records.push(ReconcileRecord {
+                    reconcile_status: ReconcileStatus::OnlyTarget,
+                    target_label: Some(address.label()),
+                    longitude: address.longitude(),
+                    latitude: address.latitude(),
+                    ..Default::default()
+                }),
+            }
+        }
+        let records = Self(records);
+        for status in [
+            ReconcileStatus::Both,
+            ReconcileStatus::Divergent,
+            ReconcileStatus::OnlySource,
+            ReconcileStatus::OnlyTarget,
+        ] {
+            info!("{status}: {} records.", records.count(status));
+        }
+        records
+    }
+
+    // Returns the record pairing `source` with its match `target`.
+    fn pair<T: Address + Geographic, U: Address>(
+        source: &T,
+        target: &U,
+        address_match: &AddressMatch,
+    ) -> ReconcileRecord {
+        let differences = differences(address_match);
+        let divergent =
+            differences.is_some() || address_match.score.is_some_and(|score| score < 1.0);
+        ReconcileRecord {
+            reconcile_status: if divergent {
+                ReconcileStatus::Divergent
+            } else {
+                ReconcileStatus::Both
+            },
+            source_label: Some(source.label()),
+            target_label: Some(target.label()),
+            differences,
+            score: address_match.score,
+            longitude: source.longitude(),
+            latitude: source.latitude(),
+        }
+    }
+
+    /// The `count` method returns the number of records with the status `status`.
+    pub fn count(&self, status: ReconcileStatus) -> usize {
+        self.iter().filter(|r| r.reconcile_status == status).count()
+    }
+
+    /// The `filter` method returns the subset of records with the status named by `filter`,
+    /// either "both", "divergent", "only_source" or "only_target".  The filter "unmatched" keeps
+    /// the addresses found in only one of the datasets.
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "both" => self.retain(|r| r.reconcile_status == ReconcileStatus::Both),
+            "divergent" => self.retain(|r| r.reconcile_status == ReconcileStatus::Divergent),
+            "only_source" => self.retain(|r| r.reconcile_status == ReconcileStatus::OnlySource),
+            "only_target" => self.retain(|r| r.reconcile_status == ReconcileStatus::OnlyTarget),
+            "unmatched" => self.retain(|r| {
+                matches!(
+                    r.reconcile_status,
+                    ReconcileStatus::OnlySource | ReconcileStatus::OnlyTarget
+                )
+            }),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+// Describes the fields that differ in `address_match`, separated by semicolons, or `None` if the
+// addresses agree.
+pub(crate) fn differences(address_match: &AddressMatch) -> Option<String> {
+    address_match.mismatches.as_ref().map(|mismatches| {
+        mismatches
+            .iter()
+            .map(Mismatch::message)
+            .collect::<Vec<String>>()
+            .join("; ")
+    })
+}
+
+// Returns the index and match of the best candidate in `others` for each address in
+// `addresses`, preferring exact matches to divergent ones, or `None` if no candidate matches.
+pub(crate) fn best_matches<T: Address + Geographic + Sync, U: Address + Geographic + Sync>(
+    addresses: &[T],
+    others: &[U],
+    radius: Option<f64>,
+    scorer: &Scorer,
+) -> Vec<Option<(usize, AddressMatch)>> {
+    let index = radius.map(|radius| SpatialIndex::from_geographic(others, radius));
+    addresses
+        .par_iter()
+        .map(|address| {
+            let candidates = match (&index, radius) {
+                (Some(index), Some(radius)) => {
+                    index.within([address.longitude(), address.latitude()], radius)
+                }
+                _ => (0..others.len()).collect(),
+            };
+            let mut best: Option<(usize, AddressMatch)> = None;
+            for j in candidates {
+                let address_match = scorer.compare(address, &others[j]);
+                if !address_match.coincident {
+                    continue;
+                }
+                let exact = address_match.mismatches.is_none()
+                    && address_match.score.is_none_or(|s| s >= 1.0);
+                if exact {
+                    return Some((j, address_match));
+                }
+                if best.is_none() {
+                    best = Some((j, address_match));
+                }
+            }
+            best
+        })
+        .progress_with(progress_bar(addresses.len(), "Comparing addresses."))
+        .collect()
+}
+
+impl IntoCsv<ReconcileRecords> for ReconcileRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for ReconcileRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoBin, SpatialAddresses};
+
+    #[test]
+    fn bidirectional_compare() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        // The source and target overlap in the middle hundred addresses.
+        let source = &city_addresses[0..200];
+        let target = &city_addresses[100..300];
+        let records = ReconcileRecords::compare(source, target, None, &MatchScoring::default());
+        // Every source address is reported once, in order, before the addresses only in the target.
+        for (record, address) in records.iter().zip(source) {
+            assert_eq!(record.source_label, Some(address.label()));
+        }
+        assert!(
+            records[100..200]
+                .iter()
+                .all(|r| r.reconcile_status == ReconcileStatus::Both)
+        );
+        assert!(
+            records[200..]
+                .iter()
+                .all(|r| r.reconcile_status == ReconcileStatus::OnlyTarget
+                    && r.source_label.is_none())
+        );
+        assert!(records.count(ReconcileStatus::OnlySource) > 0);
+        assert!(records.count(ReconcileStatus::OnlyTarget) > 0);
+
+        let unmatched = records.clone().filter("unmatched");
+        assert_eq!(
+            unmatched.len(),
+            records.count(ReconcileStatus::OnlySource) + records.count(ReconcileStatus::OnlyTarget)
+        );
+
+        let path = std::env::temp_dir().join("destination_reconcile.csv");
+        let mut records = records;
+        records.to_csv(&path)?;
+        assert_eq!(ReconcileRecords::from_csv(&path)?, records);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
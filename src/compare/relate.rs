@@ -0,0 +1,203 @@
+//! The `relate` module links matching source and target addresses by their feature IDs, producing
+//! a link table that ArcGIS Pro can load as a relationship class, so editors can navigate from a
+//! city address point to its matching county point.
+use crate::{
+    Address, AddressErrorKind, Geographic, IntoCsv, IntoJson, Io, MatchScoring, MatchStatus,
+    compare::Scorer, from_csv, progress_bar, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// The `RelationshipRecord` struct represents a row of the link table, pairing the Global ID of
+/// a source address with the Global ID of a matching target address.  The ID columns serve as
+/// the origin and destination foreign keys of an attributed relationship class, and the remaining
+/// columns become attributes of the relationship.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct RelationshipRecord {
+    /// The `source_id` field holds the Global ID of the source address.
+    #[serde(rename(serialize = "SourceGlobalID", deserialize = "SourceGlobalID"))]
+    pub source_id: String,
+    /// The `target_id` field holds the Global ID of the matching target address.
+    #[serde(rename(serialize = "TargetGlobalID", deserialize = "TargetGlobalID"))]
+    pub target_id: String,
+    /// The `match_status` field indicates whether the addresses match exactly, or diverge in some
+    /// fields.
+    pub match_status: MatchStatus,
+    /// The `source_label` field holds the address label of the source address.
+    pub source_label: String,
+    /// The `target_label` field holds the address label of the target address.
+    pub target_label: String,
+    /// The `similarity` field holds the similarity score between the street names of a fuzzy
+    /// match.
+    pub similarity: Option<f64>,
+    /// The `score` field holds the composite score of the match under the [`MatchScoring`].
+    pub score: Option<f64>,
+}
+
+/// The `RelationshipRecords` struct holds a vector of type [`RelationshipRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct RelationshipRecords(Vec<RelationshipRecord>);
+
+impl RelationshipRecords {
+    /// The `relate` method compares each source address in `source` against the target
+    /// addresses in `target`, each paired with its Global ID, and returns a link for every pair
+    /// of addresses accepted under `scoring`.  A source address matching several targets, such as
+    /// a parent address and its subaddresses, receives a link to each.  Addresses without a
+    /// Global ID cannot be linked, and are skipped with a warning.
+    pub fn relate<T: Address + Geographic + Sync, U: Address + Geographic + Sync>(
+        source: &[(String, T)],
+        target: &[(String, U)],
+        scoring: &MatchScoring,
+    ) -> Self {
+        let unidentified = |addresses: usize, name: &str| {
+            if addresses > 0 {
+                warn!("{addresses} {name} addresses without a Global ID skipped.");
+            }
+        };
+        unidentified(
+            source.iter().filter(|(id, _)| id.trim().is_empty()).count(),
+            "source",
+        );
+        let target = target
+            .iter()
+            .filter(|(id, _)| !id.trim().is_empty())
+            .collect::<Vec<&(String, U)>>();
+        let scorer = Scorer::new(scoring);
+        let records = source
+            .par_iter()
+            .progress_with(progress_bar(source.len(), "Relating addresses."))
+            .filter(|(id, _)| !id.trim().is_empty())
+            .flat_map_iter(|(source_id, address)| {
+                let source_label = address.label();
+                target.iter().filter_map(move |(target_id, other)| {
+                    let address_match = scorer.compare(address, other);
+                    if !address_match.coincident {
+                        return None;
+                    }
+                    let match_status = if address_match.mismatches.is_some()
+                        || address_match.score.is_some_and(|s| s < 1.0)
+                    {
+                        MatchStatus::Divergent
+                    } else {
+                        MatchStatus::Matching
+                    };
+                    Some(RelationshipRecord {
+                        source_id: source_id.clone(),
+                        target_id: target_id.clone(),
+                        match_status,
+                        source_label: source_label.clone(),
+                        target_label: other.label(),
+                        similarity: address_match.similarity,
+                        score: address_match.score,
+                    })
+                })
+            })
+            .collect::<Vec<RelationshipRecord>>();
+        info!("{} relationships found.", records.len());
+        Self(records)
+    }
+
+    /// The `filter` method returns the subset of records with the match status named by
+    /// `filter`, either "matching" or "divergent".
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "matching" => self.retain(|r| r.match_status == MatchStatus::Matching),
+            "divergent" => self.retain(|r| r.match_status == MatchStatus::Divergent),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+impl IntoCsv<RelationshipRecords> for RelationshipRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for RelationshipRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GrantsPassSpatialAddresses, JosephineCountySpatialAddresses2024, MatchRecords,
+        SpatialAddress,
+    };
+
+    #[test]
+    fn relate_global_ids() -> anyhow::Result<()> {
+        let city = GrantsPassSpatialAddresses::from_csv("data/city_addresses_20241007.csv")?;
+        let county =
+            JosephineCountySpatialAddresses2024::from_csv("data/county_addresses_20241007.csv")?;
+        let source = city[0..500]
+            .iter()
+            .map(|a| (a.global_id.clone(), SpatialAddress::from(a)))
+            .collect::<Vec<(String, SpatialAddress)>>();
+        let target = county
+            .iter()
+            .map(|a| {
+                (
+                    a.global_id.clone().unwrap_or_default(),
+                    SpatialAddress::from(a),
+                )
+            })
+            .collect::<Vec<(String, SpatialAddress)>>();
+        assert!(target.iter().all(|(id, _)| !id.is_empty()));
+        let scoring = MatchScoring::default();
+        let links = RelationshipRecords::relate(&source, &target, &scoring);
+
+        // Every accepted match record becomes a link.
+        let addresses = source.iter().map(|(_, a)| a.clone()).collect::<Vec<_>>();
+        let targets = target.iter().map(|(_, a)| a.clone()).collect::<Vec<_>>();
+        let matches = MatchRecords::compare(&addresses, &targets);
+        let accepted = matches
+            .iter()
+            .filter(|r| r.match_status != MatchStatus::Missing)
+            .count();
+        assert!(accepted > 0);
+        assert_eq!(links.len(), accepted);
+        for link in links.iter() {
+            let (_, address) = source.iter().find(|(id, _)| *id == link.source_id).unwrap();
+            let (_, other) = target.iter().find(|(id, _)| *id == link.target_id).unwrap();
+            assert_eq!(address.label(), link.source_label);
+            assert_eq!(other.label(), link.target_label);
+        }
+        let divergent = links.clone().filter("divergent");
+        assert!(
+            divergent
+                .iter()
+                .all(|r| r.match_status == MatchStatus::Divergent)
+        );
+
+        // The link table loads with the ID columns named for ArcGIS.
+        let path = std::env::temp_dir().join("destination_relationships.csv");
+        let mut links = links;
+        links.to_csv(&path)?;
+        let header = std::fs::read_to_string(&path)?;
+        assert!(header.starts_with("SourceGlobalID,TargetGlobalID,"));
+        assert_eq!(RelationshipRecords::from_csv(&path)?, links);
+        std::fs::remove_file(&path)?;
+
+        // Addresses without a Global ID are not linked.
+        let anonymous = vec![(String::new(), source[0].1.clone())];
+        assert!(RelationshipRecords::relate(&anonymous, &target, &scoring).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+//! The `reverse` module locates the nearest reference address to a set of input points, such as
+//! incident locations from dispatch.
+use crate::{
+    Address, AddressErrorKind, Cartesian, FromArcGis, FromShapefile, Geographic, IntoCsv,
+    IntoGeoJson, IntoJson, Io, STACK_TOLERANCE, SpatialIndex, Stack, Stacks, from_arcgis_service,
+    from_csv, from_shapefile, progress_bar, to_csv, to_geojson, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// The default search distance for reverse geocoding, in the units of the projected coordinates.
+pub const REVERSE_DISTANCE: f64 = 100.0;
+
+/// The `ReversePoint` struct represents a row of input to the reverse geocoder.  The `x` and `y`
+/// fields hold projected coordinates in the same system as the reference addresses.  The optional
+/// `id` field carries an identifier for the point, such as an incident number, into the output.
+/// Other columns in the input file are ignored.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ReversePoint {
+    /// The `id` field holds an optional identifier for the point.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// The `x` field represents the cartesian X portion of the projected coordinates.
+    pub x: f64,
+    /// The `y` field represents the cartesian Y portion of the projected coordinates.
+    pub y: f64,
+}
+
+impl Cartesian for ReversePoint {
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+/// The `ReversePoints` struct holds a vector of type [`ReversePoint`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct ReversePoints(Vec<ReversePoint>);
+
+impl IntoCsv<ReversePoints> for ReversePoints {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+// Point geometry populates the `x` and `y` fields, so any point layer can be read.
+impl FromShapefile for ReversePoints {
+    fn from_shapefile<P: AsRef<std::path::Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        let records = from_shapefile(path)?;
+        Ok(Self(records))
+    }
+}
+
+impl FromArcGis for ReversePoints {
+    async fn from_arcgis_service<F, Fut>(url: &str, post: F) -> Result<Self, AddressErrorKind>
+    where
+        F: FnMut(String, String) -> Fut,
+        Fut: Future<Output = Result<String, AddressErrorKind>>,
+    {
+        let records = from_arcgis_service(url, post).await?;
+        Ok(Self(records))
+    }
+}
+
+/// The `ReverseRecord` struct reports the nearest reference address to an input point.  If no
+/// address lies within the search distance, the address fields are empty.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ReverseRecord {
+    /// The `id` field holds the identifier of the input point, if any.
+    pub id: Option<String>,
+    /// The `x` field holds the X coordinate of the input point.
+    pub x: f64,
+    /// The `y` field holds the Y coordinate of the input point.
+    pub y: f64,
+    /// The `label` field holds the full label of the nearest address, including the postal
+    /// community, state and zip code.
+    pub label: Option<String>,
+    /// The `distance` field holds the distance from the input point to the nearest address, in
+    /// the units of the projected coordinates.
+    pub distance: Option<f64>,
+    /// The `latitude` field holds the latitude of the nearest address.
+    pub latitude: Option<f64>,
+    /// The `longitude` field holds the longitude of the nearest address.
+    pub longitude: Option<f64>,
+    /// The `units` field holds the number of addresses stacked at the location of the nearest
+    /// address, if more than one.  The label then names the structure rather than a unit.
+    #[serde(default)]
+    pub units: Option<usize>,
+}
+
+impl ReverseRecord {
+    /// The `unmatched` method creates a record for a point with no address within the search
+    /// distance.
+    pub fn unmatched(point: &ReversePoint) -> Self {
+        Self {
+            id: point.id.clone(),
+            x: point.x,
+            y: point.y,
+            ..Default::default()
+        }
+    }
+
+    /// The `matched` method creates a record pairing `point` with `address`.
+    pub fn matched<T: Address + Geographic + Cartesian>(point: &ReversePoint, address: &T) -> Self {
+        Self {
+            id: point.id.clone(),
+            x: point.x,
+            y: point.y,
+            label: Some(full_label(&address.label(), address)),
+            distance: Some(point.distance(address)),
+            latitude: Some(address.latitude()),
+            longitude: Some(address.longitude()),
+            units: None,
+        }
+    }
+
+    /// The `stacked` method creates a record pairing `point` with the structure of `stack`, the
+    /// stack holding `address`.  A stack of one address pairs the point with the address.
+    pub fn stacked<T: Address + Geographic + Cartesian>(
+        point: &ReversePoint,
+        address: &T,
+        stack: &Stack,
+    ) -> Self {
+        let mut record = Self::matched(point, address);
+        if stack.is_stacked() {
+            record.label = Some(full_label(&stack.label, address));
+            record.units = Some(stack.members.len());
+        }
+        record
+    }
+}
+
+// Appends the postal community, state and zip code of `address` to `label`.
+pub(crate) fn full_label<T: Address>(label: &str, address: &T) -> String {
+    format!(
+        "{}, {}, {} {}",
+        label,
+        address.postal_community(),
+        address.state().abbreviate(),
+        address.zip()
+    )
+}
+
+impl Geographic for ReverseRecord {
+    fn latitude(&self) -> f64 {
+        self.latitude.unwrap_or_default()
+    }
+
+    fn longitude(&self) -> f64 {
+        self.longitude.unwrap_or_default()
+    }
+}
+
+/// The `ReverseRecords` struct holds a vector of type [`ReverseRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct ReverseRecords(Vec<ReverseRecord>);
+
+impl ReverseRecords {
+    /// The `reverse` method finds the nearest address in `addresses` to each point in `points`,
+    /// within a search distance of `distance` in the units of the projected coordinates.  Uses a
+    /// [`SpatialIndex`] to select candidates.  Addresses stacked at one location are reported as
+    /// a single structure, so the nearest of several units is not picked at random.
+    pub fn reverse<T: Address + Geographic + Cartesian + Send + Sync>(
+        points: &[ReversePoint],
+        addresses: &[T],
+        distance: f64,
+    ) -> Self {
+        info!("Indexing reference addresses.");
+        let index = SpatialIndex::from_cartesian(addresses, distance);
+        let stacks = Stacks::group(addresses, STACK_TOLERANCE);
+        let stack_of = stacks.stack_of();
+        let records = points
+            .par_iter()
+            .progress_with(progress_bar(points.len(), "Locating nearest addresses."))
+            .map(|point| {
+                index
+                    .within([point.x, point.y], distance)
+                    .into_iter()
+                    .min_by(|a, b| {
+                        point
+                            .distance(&addresses[*a])
+                            .total_cmp(&point.distance(&addresses[*b]))
+                    })
+                    .map_or_else(
+                        || ReverseRecord::unmatched(point),
+                        |i| ReverseRecord::stacked(point, &addresses[i], &stacks[stack_of[i]]),
+                    )
+            })
+            .collect::<Vec<ReverseRecord>>();
+        Self(records)
+    }
+
+    /// The `filter` method returns the subset of records that match the filter.  Current values
+    /// for the `filter` field include "matching", for points with an address in range, and
+    /// "missing", for points without one.
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "matching" => self.retain(|r| r.label.is_some()),
+            "missing" => self.retain(|r| r.label.is_none()),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+impl IntoCsv<ReverseRecords> for ReverseRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for ReverseRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+// Points without a matching address have no geographic location, so they are left out of the
+// point layer.
+impl IntoGeoJson for ReverseRecords {
+    fn to_geojson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        let located = self
+            .iter()
+            .filter(|r| r.label.is_some())
+            .cloned()
+            .collect::<Vec<ReverseRecord>>();
+        to_geojson(&located, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoBin, SpatialAddresses};
+    use tracing::info;
+
+    #[test]
+    fn reverse_nearest_address() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let mut points = city_addresses[0..100]
+            .iter()
+            .enumerate()
+            .map(|(i, a)| ReversePoint {
+                id: Some(i.to_string()),
+                x: a.x + 1.0,
+                y: a.y,
+            })
+            .collect::<Vec<ReversePoint>>();
+        points.push(ReversePoint {
+            id: None,
+            x: 0.0,
+            y: 0.0,
+        });
+        let records = ReverseRecords::reverse(&points, &city_addresses, REVERSE_DISTANCE);
+        assert_eq!(records.len(), points.len());
+        for record in records.iter().take(100) {
+            assert!(record.label.is_some());
+            assert!(record.distance.is_some_and(|d| d <= 1.0 + f64::EPSILON));
+        }
+        assert_eq!(records.clone().filter("missing").len(), 1);
+        info!(
+            "Reverse geocoding located {} points.",
+            records.clone().filter("matching").len()
+        );
+        Ok(())
+    }
+}
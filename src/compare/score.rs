@@ -0,0 +1,586 @@
+//! The `score` module weighs the agreement between the fields of two addresses, in the manner of
+//! the Fellegi-Sunter model of record linkage.  Each field that agrees adds to the score of a
+//! candidate match, and each field that disagrees subtracts from it, in proportion to how well the
+//! field tells matching addresses apart from unrelated ones.  Candidates scoring at or above the
+//! accept threshold are matches, candidates between the review and accept thresholds need review,
+//! and candidates below the review threshold are rejected.
+use crate::address::descriptive_mismatches;
+use crate::{
+    Address, AddressMatch, Builder, Mismatch, StreetAliases, metaphone, normalize_text,
+    street_name_similarity,
+};
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+
+/// The `FieldWeight` struct holds the Fellegi-Sunter probabilities for a single address field.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct FieldWeight {
+    /// The `m` field holds the probability the field agrees when two addresses match.
+    pub m: f64,
+    /// The `u` field holds the probability the field agrees when two addresses do not match.
+    pub u: f64,
+}
+
+impl FieldWeight {
+    /// The `new` method creates a `FieldWeight` from the probabilities `m` and `u`.
+    pub const fn new(m: f64, u: f64) -> Self {
+        Self { m, u }
+    }
+
+    /// The `agreement` method returns the weight added to the score when the field agrees, the
+    /// base 2 logarithm of `m / u`.
+    pub fn agreement(&self) -> f64 {
+        (self.m / self.u).log2()
+    }
+
+    /// The `disagreement` method returns the weight added to the score when the field disagrees,
+    /// the base 2 logarithm of `(1 - m) / (1 - u)`, a negative number for useful fields.
+    pub fn disagreement(&self) -> f64 {
+        ((1.0 - self.m) / (1.0 - self.u)).log2()
+    }
+
+    // Returns the difference between the agreement and disagreement weights.
+    fn span(&self) -> f64 {
+        self.agreement() - self.disagreement()
+    }
+}
+
+/// The `MatchWeights` struct holds a [`FieldWeight`] for each field that identifies an address.
+/// The descriptive fields, such as the floor or address status, do not count toward the score,
+/// and are reported as mismatches instead.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct MatchWeights {
+    /// The `number` field holds the weight of the address number.
+    pub number: FieldWeight,
+    /// The `number_suffix` field holds the weight of the address number suffix.
+    pub number_suffix: FieldWeight,
+    /// The `directional` field holds the weight of the street name pre directional.
+    pub directional: FieldWeight,
+    /// The `street_name` field holds the weight of the street name, along with its premodifier,
+    /// pretype and separator.
+    pub street_name: FieldWeight,
+    /// The `street_type` field holds the weight of the street name post type.
+    pub street_type: FieldWeight,
+    /// The `subaddress_id` field holds the weight of the subaddress identifier.
+    pub subaddress_id: FieldWeight,
+    /// The `zip` field holds the weight of the zip code.
+    pub zip: FieldWeight,
+    /// The `postal_community` field holds the weight of the postal community.
+    pub postal_community: FieldWeight,
+    /// The `state` field holds the weight of the state.
+    pub state: FieldWeight,
+}
+
+impl Default for MatchWeights {
+    fn default() -> Self {
+        Self {
+            number: FieldWeight::new(0.98, 0.001),
+            number_suffix: FieldWeight::new(0.95, 0.05),
+            directional: FieldWeight::new(0.95, 0.25),
+            street_name: FieldWeight::new(0.95, 0.01),
+            street_type: FieldWeight::new(0.9, 0.3),
+            subaddress_id: FieldWeight::new(0.95, 0.1),
+            zip: FieldWeight::new(0.9, 0.2),
+            postal_community: FieldWeight::new(0.9, 0.2),
+            state: FieldWeight::new(0.99, 0.9),
+        }
+    }
+}
+
+/// The `MatchConfig` struct selects the fields that must agree for two addresses to match.  The
+/// address number and street name always define a match.  By default, the zip code, postal
+/// community and subaddress identifier must agree, while the address status is descriptive.
+/// Excluded fields do not count toward the score, and differences in them are reported as a
+/// [`Mismatch`] on divergent matches, so addresses can be matched on the civic address alone.
+/// An included address status must agree for addresses to match.
+///
+/// With `normalize` set, text fields agree if they differ only in case or spacing, as in county
+/// exports with trailing spaces in street names.  The stored values are unchanged.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct MatchConfig {
+    /// The `zip` field indicates the zip code must agree.
+    pub zip: bool,
+    /// The `postal_community` field indicates the postal community must agree.
+    pub postal_community: bool,
+    /// The `subaddress` field indicates the subaddress identifier must agree.
+    pub subaddress: bool,
+    /// The `status` field indicates the address status must agree.
+    pub status: bool,
+    /// The `normalize` field indicates text fields are compared after [`normalize_text`], so
+    /// that values differing only in case or whitespace agree.  Applies to the number suffix,
+    /// street name, subaddress identifier, building and postal community.
+    #[serde(default)]
+    pub normalize: bool,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            zip: true,
+            postal_community: true,
+            subaddress: true,
+            status: false,
+            normalize: false,
+        }
+    }
+}
+
+impl MatchConfig {
+    /// The `FIELDS` constant lists the names of the fields accepted by [`Self::include`] and
+    /// [`Self::exclude`].
+    pub const FIELDS: [&str; 4] = ["zip", "postal_community", "subaddress", "status"];
+
+    /// The `include` method requires the field named `field` to agree for addresses to match.
+    /// Errors if the field is not one of [`Self::FIELDS`].
+    pub fn include(self, field: &str) -> Result<Self, Builder> {
+        self.set(field, true)
+    }
+
+    /// The `exclude` method reports differences in the field named `field` as mismatches, rather
+    /// than preventing a match.  Errors if the field is not one of [`Self::FIELDS`].
+    pub fn exclude(self, field: &str) -> Result<Self, Builder> {
+        self.set(field, false)
+    }
+
+    /// The `with_normalize` method sets the value of the `normalize` field.
+    pub fn with_normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// The `text_agrees` method returns true if the text fields `a` and `b` agree, comparing the
+    /// values after [`normalize_text`] if `normalize` is set.
+    pub fn text_agrees(&self, a: &str, b: &str) -> bool {
+        a == b || (self.normalize && normalize_text(a) == normalize_text(b))
+    }
+
+    /// The `optional_agrees` method works like [`Self::text_agrees`] for optional fields.  When
+    /// normalizing, a blank value agrees with a missing one.
+    pub fn optional_agrees(&self, a: &Option<String>, b: &Option<String>) -> bool {
+        let normalized = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(normalize_text)
+                .filter(|value| !value.is_empty())
+        };
+        a == b || (self.normalize && normalized(a) == normalized(b))
+    }
+
+    /// The `street_name_similarity` method works like [`street_name_similarity`], comparing the
+    /// street names after [`normalize_text`] if `normalize` is set.
+    pub fn street_name_similarity(&self, a: &str, b: &str, threshold: f64) -> Option<f64> {
+        if self.normalize && a != b {
+            street_name_similarity(&normalize_text(a), &normalize_text(b), threshold)
+        } else {
+            street_name_similarity(a, b, threshold)
+        }
+    }
+
+    fn set(mut self, field: &str, value: bool) -> Result<Self, Builder> {
+        match field.trim() {
+            "zip" => self.zip = value,
+            "postal_community" => self.postal_community = value,
+            "subaddress" => self.subaddress = value,
+            "status" => self.status = value,
+            _ => {
+                return Err(Builder::new(
+                    format!(
+                        "unknown match field '{field}', expected one of {}",
+                        Self::FIELDS.join(", ")
+                    ),
+                    "MatchConfig".to_string(),
+                    line!(),
+                    file!().to_string(),
+                ));
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// The `MatchScoring` struct holds the parameters of the scoring model used to compare
+/// addresses.  The composite score of a candidate is scaled between 0, when every field
+/// disagrees, and 1, when every field agrees.
+///
+/// The default scoring accepts only candidates where every field agrees, and has no review band,
+/// which reproduces [`Address::coincident_fuzzy`].  With the default weights, a review threshold
+/// of 0.8 flags candidates disagreeing in a single field other than the address number, such as
+/// the number suffix or subaddress identifier.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct MatchScoring {
+    /// The `threshold` field holds the similarity threshold between 0 and 1 at which street names
+    /// agree.
+    pub threshold: f64,
+    /// The `accept` field holds the score at or above which a candidate matches.
+    pub accept: f64,
+    /// The `review` field holds the score at or above which a candidate below the `accept`
+    /// threshold needs review.  Candidates below the threshold are rejected.  If `None`,
+    /// candidates below the `accept` threshold are rejected.
+    pub review: Option<f64>,
+    /// The `weights` field holds the weights of the address fields.
+    pub weights: MatchWeights,
+    /// The `phonetic` field indicates street names below the similarity threshold still agree if
+    /// they share a Metaphone code, such as "THOMPSON" and "THOMSEN".  See [`crate::metaphone`].
+    #[serde(default)]
+    pub phonetic: bool,
+    /// The `config` field selects the fields that must agree for addresses to match.
+    #[serde(default)]
+    pub config: MatchConfig,
+    /// The `aliases` field holds a table of street names known to refer to the same street.
+    /// Streets named in the same group of aliases agree in both the street name and type.
+    #[serde(default)]
+    pub aliases: Option<StreetAliases>,
+}
+
+impl Default for MatchScoring {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl MatchScoring {
+    /// The `new` method creates a `MatchScoring` with the default weights, matching street names
+    /// with a similarity at or above `threshold`, and accepting only candidates where every field
+    /// agrees.
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            accept: 1.0,
+            review: None,
+            weights: MatchWeights::default(),
+            phonetic: false,
+            config: MatchConfig::default(),
+            aliases: None,
+        }
+    }
+
+    /// The `with_accept` method sets the value of the `accept` field.
+    pub fn with_accept(mut self, accept: f64) -> Self {
+        self.accept = accept;
+        self
+    }
+
+    /// The `with_review` method sets the value of the `review` field.
+    pub fn with_review(mut self, review: Option<f64>) -> Self {
+        self.review = review;
+        self
+    }
+
+    /// The `with_phonetic` method sets the value of the `phonetic` field.
+    pub fn with_phonetic(mut self, phonetic: bool) -> Self {
+        self.phonetic = phonetic;
+        self
+    }
+
+    /// The `with_config` method sets the value of the `config` field.
+    pub fn with_config(mut self, config: MatchConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The `with_aliases` method sets the value of the `aliases` field.
+    pub fn with_aliases(mut self, aliases: Option<StreetAliases>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// The `is_review` method returns true if `score` falls between the review and accept
+    /// thresholds.
+    pub fn is_review(&self, score: f64) -> bool {
+        self.review.is_some_and(|review| score >= review) && score < self.accept
+    }
+
+    /// The `compare` method scores the candidate address `other` against `address`.  The
+    /// candidate is coincident if the score meets the `accept` threshold.  The street name
+    /// similarity and descriptive mismatches are reported as in [`Address::coincident_fuzzy`].
+    pub fn compare<T: Address, U: Address>(&self, address: &T, other: &U) -> AddressMatch {
+        Scorer::new(self).compare(address, other)
+    }
+}
+
+/// The `Scorer` struct holds a [`MatchScoring`] with the spans of its field weights computed
+/// ahead of time, for comparing many pairs of addresses.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Scorer<'a> {
+    scoring: &'a MatchScoring,
+    // The difference between the agreement and disagreement weights of each field, in the order
+    // the fields are compared.
+    spans: [f64; 9],
+    // The sum of the spans, the range of possible composite scores.
+    range: f64,
+    // The lowest score of interest, below which a candidate is rejected.
+    floor: f64,
+}
+
+impl<'a> Scorer<'a> {
+    pub(crate) fn new(scoring: &'a MatchScoring) -> Self {
+        let weights = &scoring.weights;
+        let config = &scoring.config;
+        let mut spans = [
+            weights.number,
+            weights.number_suffix,
+            weights.directional,
+            weights.street_type,
+            weights.subaddress_id,
+            weights.zip,
+            weights.postal_community,
+            weights.state,
+            weights.street_name,
+        ]
+        .map(|w| w.span());
+        // Excluded fields neither add to nor subtract from the score.
+        for (i, included) in [
+            (4, config.subaddress),
+            (5, config.zip),
+            (6, config.postal_community),
+        ] {
+            if !included {
+                spans[i] = 0.0;
+            }
+        }
+        Self {
+            scoring,
+            spans,
+            range: spans.iter().sum(),
+            floor: scoring.review.unwrap_or(scoring.accept).min(scoring.accept),
+        }
+    }
+
+    pub(crate) fn is_review(&self, score: f64) -> bool {
+        self.scoring.is_review(score)
+    }
+
+    pub(crate) fn compare<T: Address, U: Address>(&self, address: &T, other: &U) -> AddressMatch {
+        // The shortfall is the distance below a perfect score, so that candidates where every
+        // field agrees score exactly 1.  Fields are compared in order of cost, and the comparison
+        // stops once the candidate falls below the floor, so most pairs of unrelated addresses
+        // only compare the address number.
+        let mut shortfall = 0.0;
+        let config = &self.scoring.config;
+        // Streets named in the same group of aliases agree in both name and type.
+        let aliased = OnceCell::new();
+        let aliased = || {
+            *aliased.get_or_init(|| {
+                self.scoring
+                    .aliases
+                    .as_ref()
+                    .is_some_and(|aliases| aliases.equivalent(address, other))
+            })
+        };
+        let agrees: [&dyn Fn() -> bool; 8] = [
+            &|| address.number() == other.number(),
+            &|| config.optional_agrees(address.number_suffix(), other.number_suffix()),
+            &|| address.directional() == other.directional(),
+            &|| address.street_type() == other.street_type() || aliased(),
+            &|| config.optional_agrees(address.subaddress_id(), other.subaddress_id()),
+            &|| address.zip() == other.zip(),
+            &|| config.text_agrees(address.postal_community(), other.postal_community()),
+            &|| address.state() == other.state(),
+        ];
+        for (agree, span) in agrees.iter().zip(self.spans) {
+            if span != 0.0 && !agree() {
+                shortfall += span;
+                if 1.0 - shortfall / self.range < self.floor {
+                    return AddressMatch::new(false, Vec::new());
+                }
+            }
+        }
+        let mut similarity = None;
+        let street_agrees = aliased()
+            || address.street_name_pre_modifier() == other.street_name_pre_modifier()
+                && address.street_name_pre_type() == other.street_name_pre_type()
+                && address.street_name_separator() == other.street_name_separator()
+                && match config.street_name_similarity(
+                    address.street_name(),
+                    other.street_name(),
+                    self.scoring.threshold,
+                ) {
+                    Some(score) => {
+                        similarity = (score < 1.0).then_some(score);
+                        true
+                    }
+                    None if self.scoring.phonetic && sounds_alike(address, other) => {
+                        similarity = Some(strsim::jaro_winkler(
+                            &address.street_name().to_uppercase(),
+                            &other.street_name().to_uppercase(),
+                        ));
+                        true
+                    }
+                    None => false,
+                };
+        if !street_agrees {
+            shortfall += self.spans[8];
+        }
+        let score = 1.0 - shortfall / self.range;
+
+        let coincident = score >= self.scoring.accept
+            && (!self.scoring.config.status || address.status() == other.status());
+        let mut mismatches = Vec::new();
+        if coincident {
+            if similarity.is_some() {
+                mismatches.push(Mismatch::street_name(
+                    address.street_name().clone(),
+                    other.street_name().clone(),
+                ));
+            }
+            mismatches.extend(descriptive_mismatches(address, other, &self.scoring.config));
+        }
+        AddressMatch::new(coincident, mismatches)
+            .with_similarity(similarity)
+            .with_score(Some(score))
+    }
+}
+
+// Returns true if the street names of `address` and `other` share a Metaphone code.
+fn sounds_alike<T: Address, U: Address>(address: &T, other: &U) -> bool {
+    let code = metaphone(address.street_name());
+    !code.is_empty() && code == metaphone(other.street_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AddressStatus, Blocking, BlockingStrategy, CommonAddress, MatchRecords, MatchStatus,
+        SpatialAddress, StreetNamePostType, address::riverside,
+    };
+
+    #[test]
+    fn match_scoring() -> anyhow::Result<()> {
+        let address = |number: i64, suffix: Option<&str>| SpatialAddress {
+            address: CommonAddress {
+                number_suffix: suffix.map(str::to_string),
+                zip: 97526,
+                postal_community: "GRANTS PASS".to_string(),
+                ..riverside(number)
+            },
+            ..Default::default()
+        };
+        let subject = address(100, None);
+        let scoring = MatchScoring::new(1.0);
+        let exact = scoring.compare(&subject, &address(100, None));
+        assert!(exact.coincident);
+        assert_eq!(exact.score, Some(1.0));
+        let suffix = scoring.compare(&subject, &address(100, Some("1/2")));
+        assert!(!suffix.coincident);
+
+        // Without a review band, a differing suffix is missing, as under `coincident`.
+        let targets = vec![address(100, Some("1/2")), address(102, None)];
+        let records = MatchRecords::new_scored(&subject, &targets, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Missing);
+        assert_eq!(records[0].score, None);
+
+        // With a review band, the differing suffix surfaces for review, but a different number does not.
+        let scoring = scoring.with_review(Some(0.8));
+        let records = MatchRecords::new_scored(&subject, &targets, &scoring);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].match_status, MatchStatus::Review);
+        assert_eq!(
+            records[0].candidate,
+            Some("100 1/2 RIVERSIDE AVE".to_string())
+        );
+        assert!(records[0].score.is_some_and(|s| (0.8..1.0).contains(&s)));
+        assert_eq!(records.clone().filter("review").len(), 1);
+        let records = MatchRecords::new_scored(&address(102, Some("A")), &targets[..1], &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Missing);
+
+        // Lowering the accept threshold reports the candidate as divergent.
+        let scoring = scoring.with_accept(0.85);
+        let records = MatchRecords::new_scored(&subject, &targets, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Divergent);
+        Ok(())
+    }
+
+    #[test]
+    fn match_config() -> anyhow::Result<()> {
+        let address =
+            |zip: i64, subaddress_id: Option<&str>, status: AddressStatus| SpatialAddress {
+                address: CommonAddress {
+                    subaddress_id: subaddress_id.map(str::to_string),
+                    status,
+                    zip,
+                    postal_community: "GRANTS PASS".to_string(),
+                    ..riverside(100)
+                },
+                ..Default::default()
+            };
+        let city = address(97527, None, AddressStatus::Current);
+        let county = vec![address(97526, None, AddressStatus::Current)];
+
+        // By default the zip code must agree.
+        let records = MatchRecords::new_scored(&city, &county, &MatchScoring::default());
+        assert_eq!(records[0].match_status, MatchStatus::Missing);
+        assert!(!city.coincident(&county[0]).coincident);
+
+        // Excluding the zip code matches on the civic address, noting the difference.
+        let config = MatchConfig::default().exclude("zip")?;
+        let scoring = MatchScoring::default().with_config(config);
+        let records = MatchRecords::new_scored(&city, &county, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Divergent);
+        assert_eq!(records[0].zip_source, Some("97527".to_string()));
+        assert_eq!(records[0].zip_target, Some("97526".to_string()));
+        assert_eq!(records.clone().filter("zip").len(), 1);
+        assert_eq!(records.divergence(), vec![("zip", 1)]);
+        assert!(city.coincident_with(&county[0], 1.0, &config).coincident);
+
+        // Excluding the subaddress matches units against the building address.
+        let unit = vec![address(97527, Some("A"), AddressStatus::Current)];
+        let records = MatchRecords::new_scored(&city, &unit, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Missing);
+        let scoring = scoring.with_config(config.exclude("subaddress")?);
+        let records = MatchRecords::new_scored(&city, &unit, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Divergent);
+        assert_eq!(records[0].subaddress_id_target, Some("A".to_string()));
+
+        // Including the status rejects addresses with a different status.
+        let retired = vec![address(97527, None, AddressStatus::Retired)];
+        let records = MatchRecords::new_scored(&city, &retired, &MatchScoring::default());
+        assert_eq!(records[0].match_status, MatchStatus::Divergent);
+        let config = MatchConfig::default().include("status")?;
+        let scoring = MatchScoring::default().with_config(config);
+        let records = MatchRecords::new_scored(&city, &retired, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Missing);
+        assert!(!city.coincident_with(&retired[0], 1.0, &config).coincident);
+
+        assert!(MatchConfig::default().exclude("street_name").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn normalized_comparison() -> anyhow::Result<()> {
+        assert_eq!(normalize_text("  Rogue  River hwy "), "ROGUE RIVER HWY");
+        let address = |street_name: &str, subaddress_id: Option<&str>, postal_community: &str| {
+            SpatialAddress {
+                address: CommonAddress {
+                    number: 100,
+                    street_name: street_name.to_string(),
+                    street_type: Some(StreetNamePostType::AVENUE),
+                    subaddress_id: subaddress_id.map(str::to_string),
+                    zip: 97527,
+                    postal_community: postal_community.to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        };
+        let city = address("RIVERSIDE", Some("A"), "GRANTS PASS");
+        let county = vec![address("Riverside ", Some("a"), "Grants  Pass")];
+        let records = MatchRecords::new_scored(&city, &county, &MatchScoring::default());
+        assert_eq!(records[0].match_status, MatchStatus::Missing);
+
+        // Normalized fields agree without reporting a mismatch, and the stored values are unchanged.
+        let config = MatchConfig::default().with_normalize(true);
+        let scoring = MatchScoring::default().with_config(config);
+        let records = MatchRecords::new_scored(&city, &county, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Matching);
+        assert!(city.coincident_with(&county[0], 1.0, &config).coincident);
+        assert_eq!(county[0].street_name(), "Riverside ");
+
+        // A blank subaddress agrees with a missing one.
+        assert!(config.optional_agrees(&Some(" ".to_string()), &None));
+        assert!(!config.optional_agrees(&Some("B".to_string()), &None));
+        let strategy = Blocking::parse("street_bucket")?;
+        assert_eq!(strategy.key(&city), strategy.key(&county[0]));
+        Ok(())
+    }
+}
@@ -0,0 +1,438 @@
+//! The `config` module reads run configurations from TOML files, so that datasets and parameters
+//! used on every run of a workflow can be recorded once rather than passed as flags each time.
+//!
+//! A configuration defines named datasets, default parameters, and parameters for specific
+//! commands:
+//!
+//! ```toml
+//! [datasets.city]
+//! path = "data/city_addresses_20241007.csv"
+//! type = "grants_pass"
+//!
+//! [datasets.county]
+//! path = "data/county_addresses_20241007.csv"
+//! type = "josephine_county"
+//!
+//! [defaults]
+//! source = "city"
+//! target = "county"
+//!
+//! [commands.compare]
+//! output = "output/compare.csv"
+//! fuzzy = 0.9
+//! ```
+use crate::{AddressErrorKind, Config, Io, NotifyConfig};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// The `Dataset` struct names an address file and its format.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dataset {
+    /// The `path` field holds the path to the address file.
+    pub path: PathBuf,
+    /// The `address_type` field holds the format of the address file, as passed to the
+    /// `--source-type` or `--target-type` flags.  Read from the `type` key.
+    #[serde(rename = "type", default)]
+    pub address_type: Option<String>,
+}
+
+/// The `RunParameters` struct holds parameters for a run of the program.  Each field corresponds
+/// to a command-line flag of the same name.  The `source`, `target` and `alternate` fields accept
+/// either a path or the name of a [`Dataset`].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunParameters {
+    /// The `source` field holds the source dataset name or path.
+    pub source: Option<String>,
+    /// The `source_type` field holds the address format of the source.
+    pub source_type: Option<String>,
+    /// The `target` field holds the target dataset name or path.
+    pub target: Option<String>,
+    /// The `target_type` field holds the address format of the target.
+    pub target_type: Option<String>,
+    /// The `alternate` field holds the alternate target dataset name or path.
+    pub alternate: Option<String>,
+    /// The `alternate_type` field holds the address format of the alternate target.
+    pub alternate_type: Option<String>,
+    /// The `filter` field holds the filter value.
+    pub filter: Option<String>,
+    /// The `output` field holds the path for output records.
+    pub output: Option<PathBuf>,
+    /// The `format` field holds the output format.
+    pub format: Option<String>,
+    /// The `fuzzy` field holds the street name similarity threshold.
+    pub fuzzy: Option<f64>,
+    /// The `radius` field holds the search radius for candidate addresses, in meters.
+    pub radius: Option<f64>,
+    /// The `units` field holds the units for drift distances.
+    pub units: Option<String>,
+    /// The `rules` field holds the path to a TOML file of standardization rules.
+    pub rules: Option<PathBuf>,
+    /// The `aliases` field holds the path to a csv table of street name aliases.
+    pub aliases: Option<PathBuf>,
+    /// The `grid` field holds the path to a map grid polygon layer.
+    pub grid: Option<PathBuf>,
+    /// The `grid_field` field holds the attribute of the map grid polygons holding the grid
+    /// reference.
+    pub grid_field: Option<String>,
+    /// The `mapping` field holds the path to a TOML column map for 'mapped' addresses.
+    pub mapping: Option<PathBuf>,
+    /// The `sheet` field holds the sheet of business licenses in an Excel workbook.
+    pub sheet: Option<String>,
+    /// The `columns` field holds the workbook columns of business license fields, as
+    /// `FIELD=header`.
+    pub columns: Option<Vec<String>>,
+    /// The `profile` field holds the spelling profile for standardized labels.
+    pub profile: Option<String>,
+    /// The `boundary` field holds the path to a service area polygon for 'lexisnexis', or to the
+    /// city limits for 'overlap'.
+    pub boundary: Option<PathBuf>,
+    /// The `previous` field holds the path to the previous 'lexisnexis' submission.
+    pub previous: Option<PathBuf>,
+    /// The `template` field holds the path to a TOML output template for 'lexisnexis'.
+    pub template: Option<PathBuf>,
+    /// The `threshold` field holds the minimum drift distance to report, or the minimum distance
+    /// moved for 'history'.
+    pub threshold: Option<f64>,
+    /// The `min_threshold` field holds the drift distance at or below which 'drift' ignores a
+    /// displacement as jitter.
+    pub min_threshold: Option<f64>,
+    /// The `registry` field holds the path to the registry of retired addresses.
+    pub registry: Option<PathBuf>,
+    /// The `years` field holds the years before a retired address may be reissued.
+    pub years: Option<u32>,
+    /// The `layers` field holds the polygon layers for 'enrich', as `name:field=path`.
+    pub layers: Option<Vec<String>>,
+    /// The `labels` field holds the label columns for 'labels', as `name=template`.
+    pub labels: Option<Vec<String>>,
+    /// The `esn` field holds the path to the ESN polygon layer for 'msag'.
+    pub esn: Option<PathBuf>,
+    /// The `esn_field` field holds the attribute of the ESN polygons holding the ESN.
+    pub esn_field: Option<String>,
+    /// The `buffer` field holds the distance from another ESN needing verification for 'esn'.
+    pub buffer: Option<f64>,
+    /// The `old` field holds the path to the earlier vintage for 'diff'.
+    pub old: Option<PathBuf>,
+    /// The `new` field holds the path to the later vintage for 'diff', or the later snapshot for
+    /// 'id_audit'.
+    pub new: Option<PathBuf>,
+    /// The `key` field holds the column pairing the records of the vintages for 'diff'.
+    pub key: Option<String>,
+    /// The `ignore` field holds the columns left out of the comparison for 'diff'.
+    pub ignore: Option<Vec<String>>,
+    /// The `signs` field holds the path to the street name sign inventory for 'signs'.
+    pub signs: Option<PathBuf>,
+    /// The `street_field` field holds the column of the sign inventory holding the street name.
+    pub street_field: Option<String>,
+    /// The `centerlines` field holds the path to the road centerlines for 'geocode'.
+    pub centerlines: Option<PathBuf>,
+    /// The `neighbors` field holds the number of target addresses listed by 'nearest'.
+    pub neighbors: Option<usize>,
+    /// The `confident_distance` field holds the distance within which 'assign_nearest' assigns
+    /// the nearest address with high confidence.
+    pub confident_distance: Option<f64>,
+    /// The `parcels` field holds the path to the parcel polygon layer for 'parcels' or
+    /// 'vacancy'.
+    pub parcels: Option<PathBuf>,
+    /// The `taxlot_field` field holds the attribute of the parcels holding the map tax lot number.
+    pub taxlot_field: Option<String>,
+    /// The `situs_field` field holds the attribute of the parcels holding the situs address.
+    pub situs_field: Option<String>,
+    /// The `footprints` field holds the path to the building footprint layer for 'footprints' or
+    /// 'vacancy'.
+    pub footprints: Option<PathBuf>,
+    /// The `footprint_field` field holds the attribute of the footprints holding the footprint
+    /// identifier.
+    pub footprint_field: Option<String>,
+    /// The `snap` field holds the flag to write coordinates snapped to the nearest footprint.
+    pub snap: Option<bool>,
+    /// The `zips` field holds the path to the zip code polygon layer for 'validate'.
+    pub zips: Option<PathBuf>,
+    /// The `zip_field` field holds the attribute of the zip code polygons holding the zip code.
+    pub zip_field: Option<String>,
+    /// The `community_field` field holds the attribute of the zip code polygons holding the
+    /// postal community.
+    pub community_field: Option<String>,
+    /// The `accept` field holds the score at or above which a candidate matches for 'compare'.
+    pub accept: Option<f64>,
+    /// The `review` field holds the score at or above which a candidate needs review for
+    /// 'compare'.
+    pub review: Option<f64>,
+    /// The `blocking` field names the blocking strategy for 'compare'.
+    pub blocking: Option<String>,
+    /// The `phonetic` field holds the flag to match street names that sound alike in 'compare'.
+    pub phonetic: Option<bool>,
+    /// The `tolerance` field holds the distance in meters counted as correct for 'bench'.
+    pub tolerance: Option<f64>,
+    /// The `snapshots` field holds the paths to the later snapshots for 'history', oldest first.
+    pub snapshots: Option<Vec<PathBuf>>,
+    /// The `since` field holds the first date counted by 'activity'.
+    pub since: Option<chrono::NaiveDate>,
+    /// The `until` field holds the last date counted by 'activity'.
+    pub until: Option<chrono::NaiveDate>,
+    /// The `range` field holds the unit range specification for 'units'.
+    pub range: Option<String>,
+    /// The `building` field holds the label of the building address for 'units'.
+    pub building: Option<String>,
+    /// The `unit_type` field holds the subaddress type of the units for 'units'.
+    pub unit_type: Option<String>,
+    /// The `stats` field holds the path for summary statistics from 'drift' or 'bench'.
+    pub stats: Option<PathBuf>,
+    /// The `report` field holds the path for a drift report.
+    pub report: Option<PathBuf>,
+    /// The `clusters` field holds the path for the clusters of systematic drift.
+    pub clusters: Option<PathBuf>,
+    /// The `jobs` field holds the number of threads for parallel comparison.
+    pub jobs: Option<usize>,
+    /// The `near` field holds the flag to include near duplicates.
+    pub near: Option<bool>,
+    /// The `resolve` field holds the strategy for resolving duplicates.
+    pub resolve: Option<String>,
+    /// The `quiet` field holds the flag to hide progress bars.
+    pub quiet: Option<bool>,
+    /// The `on_error` field holds the response to rows that fail to parse.
+    pub on_error: Option<String>,
+    /// The `compress` field holds the compression of csv output.
+    pub compress: Option<String>,
+    /// The `select` field holds the columns kept in csv output.
+    pub select: Option<Vec<String>>,
+    /// The `rename` field holds the columns of csv output renamed, as `column=HEADER`.
+    pub rename: Option<Vec<String>>,
+    /// The `business` field holds the flag for business licenses.
+    pub business: Option<bool>,
+    /// The `mailing` field holds the path to the mailing addresses of business licenses.
+    pub mailing: Option<PathBuf>,
+    /// The `bind` field holds the socket address for the 'serve' command.
+    pub bind: Option<String>,
+    /// The `grpc` field holds the socket address for gRPC requests to the 'serve' command.
+    pub grpc: Option<String>,
+    /// The `stacks` field holds the flag to measure drift per structure for stacked units.
+    pub stacks: Option<bool>,
+    /// The `watch` field holds the flag to rerun 'compare' and 'drift' when the inputs change.
+    pub watch: Option<bool>,
+    /// The `incremental` field holds the flag to reuse cached match records.
+    pub incremental: Option<bool>,
+    /// The `resume` field holds the flag to checkpoint comparisons, resuming interrupted runs.
+    pub resume: Option<bool>,
+    /// The `include` field holds the fields that must agree to match in 'compare'.
+    pub include: Option<Vec<String>>,
+    /// The `exclude` field holds the fields reported as mismatches in 'compare'.
+    pub exclude: Option<Vec<String>>,
+    /// The `normalize` field holds the flag to ignore case and spacing in 'compare'.
+    pub normalize: Option<bool>,
+    /// The `bidirectional` field holds the flag to reconcile 'compare' in both directions.
+    pub bidirectional: Option<bool>,
+    /// The `token` field holds the access token for ArcGIS Feature Services.
+    pub token: Option<String>,
+    /// The `dsn` field holds the connection string for a PostGIS database.
+    pub dsn: Option<String>,
+    /// The `source_epsg` field holds the EPSG code of the source coordinates.
+    pub source_epsg: Option<u32>,
+    /// The `target_epsg` field holds the EPSG code of the target coordinates.
+    pub target_epsg: Option<u32>,
+}
+
+impl RunParameters {
+    /// The `or` method returns the parameters of `self`, filling any parameters not set from
+    /// `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            source: self.source.or(other.source),
+            source_type: self.source_type.or(other.source_type),
+            target: self.target.or(other.target),
+            target_type: self.target_type.or(other.target_type),
+            alternate: self.alternate.or(other.alternate),
+            alternate_type: self.alternate_type.or(other.alternate_type),
+            filter: self.filter.or(other.filter),
+            output: self.output.or(other.output),
+            format: self.format.or(other.format),
+            fuzzy: self.fuzzy.or(other.fuzzy),
+            radius: self.radius.or(other.radius),
+            units: self.units.or(other.units),
+            rules: self.rules.or(other.rules),
+            aliases: self.aliases.or(other.aliases),
+            grid: self.grid.or(other.grid),
+            grid_field: self.grid_field.or(other.grid_field),
+            mapping: self.mapping.or(other.mapping),
+            sheet: self.sheet.or(other.sheet),
+            columns: self.columns.or(other.columns),
+            profile: self.profile.or(other.profile),
+            boundary: self.boundary.or(other.boundary),
+            previous: self.previous.or(other.previous),
+            template: self.template.or(other.template),
+            threshold: self.threshold.or(other.threshold),
+            min_threshold: self.min_threshold.or(other.min_threshold),
+            registry: self.registry.or(other.registry),
+            years: self.years.or(other.years),
+            layers: self.layers.or(other.layers),
+            labels: self.labels.or(other.labels),
+            esn: self.esn.or(other.esn),
+            esn_field: self.esn_field.or(other.esn_field),
+            old: self.old.or(other.old),
+            new: self.new.or(other.new),
+            key: self.key.or(other.key),
+            ignore: self.ignore.or(other.ignore),
+            signs: self.signs.or(other.signs),
+            street_field: self.street_field.or(other.street_field),
+            centerlines: self.centerlines.or(other.centerlines),
+            buffer: self.buffer.or(other.buffer),
+            neighbors: self.neighbors.or(other.neighbors),
+            confident_distance: self.confident_distance.or(other.confident_distance),
+            parcels: self.parcels.or(other.parcels),
+            taxlot_field: self.taxlot_field.or(other.taxlot_field),
+            situs_field: self.situs_field.or(other.situs_field),
+            footprints: self.footprints.or(other.footprints),
+            footprint_field: self.footprint_field.or(other.footprint_field),
+            snap: self.snap.or(other.snap),
+            zips: self.zips.or(other.zips),
+            zip_field: self.zip_field.or(other.zip_field),
+            community_field: self.community_field.or(other.community_field),
+            accept: self.accept.or(other.accept),
+            review: self.review.or(other.review),
+            blocking: self.blocking.or(other.blocking),
+            phonetic: self.phonetic.or(other.phonetic),
+            tolerance: self.tolerance.or(other.tolerance),
+            snapshots: self.snapshots.or(other.snapshots),
+            since: self.since.or(other.since),
+            until: self.until.or(other.until),
+            range: self.range.or(other.range),
+            building: self.building.or(other.building),
+            unit_type: self.unit_type.or(other.unit_type),
+            stats: self.stats.or(other.stats),
+            report: self.report.or(other.report),
+            clusters: self.clusters.or(other.clusters),
+            jobs: self.jobs.or(other.jobs),
+            near: self.near.or(other.near),
+            resolve: self.resolve.or(other.resolve),
+            quiet: self.quiet.or(other.quiet),
+            on_error: self.on_error.or(other.on_error),
+            compress: self.compress.or(other.compress),
+            select: self.select.or(other.select),
+            rename: self.rename.or(other.rename),
+            business: self.business.or(other.business),
+            mailing: self.mailing.or(other.mailing),
+            bind: self.bind.or(other.bind),
+            grpc: self.grpc.or(other.grpc),
+            stacks: self.stacks.or(other.stacks),
+            watch: self.watch.or(other.watch),
+            incremental: self.incremental.or(other.incremental),
+            resume: self.resume.or(other.resume),
+            include: self.include.or(other.include),
+            exclude: self.exclude.or(other.exclude),
+            normalize: self.normalize.or(other.normalize),
+            bidirectional: self.bidirectional.or(other.bidirectional),
+            token: self.token.or(other.token),
+            dsn: self.dsn.or(other.dsn),
+            source_epsg: self.source_epsg.or(other.source_epsg),
+            target_epsg: self.target_epsg.or(other.target_epsg),
+        }
+    }
+}
+
+/// The `RunConfig` struct holds the contents of a configuration file.  The `datasets` field maps
+/// dataset names to files, the `defaults` field holds parameters for every command, and the
+/// `commands` field holds parameters for individual commands, which take precedence over the
+/// defaults.  The `notify` field holds the destinations for notifications of finished runs.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunConfig {
+    /// The `datasets` field maps dataset names to address files.
+    pub datasets: BTreeMap<String, Dataset>,
+    /// The `defaults` field holds parameters for all commands.
+    pub defaults: RunParameters,
+    /// The `commands` field maps command names to parameters for the command.
+    pub commands: BTreeMap<String, RunParameters>,
+    /// The `notify` field holds the webhook and mail server notified when a run finishes.
+    pub notify: NotifyConfig,
+}
+
+impl RunConfig {
+    /// The `from_toml` method reads a configuration from the TOML file at `path`.
+    pub fn from_toml<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        info!("Reading configuration from {:?}", path.as_ref());
+        from_toml(path)
+    }
+
+    /// The `parameters` method returns the parameters for `command`, falling back to the
+    /// defaults for parameters the command does not set.
+    pub fn parameters(&self, command: &str) -> RunParameters {
+        match self.commands.get(command) {
+            Some(parameters) => parameters.clone().or(self.defaults.clone()),
+            None => self.defaults.clone(),
+        }
+    }
+
+    /// The `dataset` method returns the dataset with the name `name`, if defined.
+    pub fn dataset(&self, name: &str) -> Option<&Dataset> {
+        self.datasets.get(name)
+    }
+}
+
+/// Generic function to deserialize a data type from a TOML file.  Called by methods to avoid code
+/// duplication.
+pub(crate) fn from_toml<T: DeserializeOwned, P: AsRef<Path>>(
+    path: P,
+) -> Result<T, AddressErrorKind> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+    let record = toml::from_str(&text)
+        .map_err(|source| Config::new(path.into(), source.to_string(), line!(), file!().into()))?;
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cli, Command};
+
+    #[test]
+    fn load_run_config() -> anyhow::Result<()> {
+        let config = RunConfig::from_toml("data/config_sample.toml")?;
+        assert_eq!(config.datasets.len(), 2);
+        assert_eq!(config.parameters("drift").output, Some("output.csv".into()));
+        let cli = Cli::load_from([
+            "destination",
+            "--config",
+            "data/config_sample.toml",
+            "compare",
+            "--fuzzy",
+            "0.8",
+            "-z",
+            "common",
+            "-i",
+        ])?;
+        assert_eq!(
+            cli.command,
+            Command::Compare {
+                business: false,
+                alternate: vec![],
+                alternate_type: vec![],
+                mailing: None,
+                sheet: None,
+                columns: vec![],
+                incremental: true,
+                resume: false,
+                accept: None,
+                review: None,
+                blocking: None,
+                phonetic: false,
+                include: vec![],
+                exclude: vec![],
+                normalize: false,
+                bidirectional: false,
+                watch: false
+            }
+        );
+        assert_eq!(cli.source, Some("data/city_addresses_20241007.csv".into()));
+        assert_eq!(cli.source_type, Some("grants_pass".to_string()));
+        assert_eq!(cli.target_type, Some("common".to_string()));
+        assert_eq!(cli.output, std::path::PathBuf::from("compare.csv"));
+        assert_eq!(cli.fuzzy, Some(0.8));
+        assert_eq!(cli.radius, Some(500.0));
+        Ok(())
+    }
+}
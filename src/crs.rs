@@ -0,0 +1,535 @@
+//! The `crs` module transforms coordinates between the coordinate reference systems used by
+//! address sources, identified by EPSG code.  Transformations go through geographic coordinates,
+//! using built-in Lambert conformal conic, transverse Mercator and web Mercator projections.
+//!
+//! NAD83 and WGS84 are treated as the same datum.  The two differ by a meter or two in southern
+//! Oregon, well below the displacement thresholds used to compare address points.
+use crate::Projection;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+/// The `WGS84` constant holds the EPSG code for geographic coordinates in WGS84, with longitude
+/// as X and latitude as Y.
+pub const WGS84: u32 = 4326;
+
+/// The `EPSG_CODES` constant lists the EPSG codes supported by [`transform`].
+///
+/// * 2270 - NAD83 / Oregon South, in international feet.
+/// * 2992 - NAD83 / Oregon GIC Lambert, in international feet.
+/// * 3857 - WGS84 / Pseudo-Mercator, as used by web maps.
+/// * 4269 - NAD83 geographic coordinates.
+/// * 4326 - WGS84 geographic coordinates.
+/// * 26910 - NAD83 / UTM zone 10N.
+/// * 32610 - WGS84 / UTM zone 10N.
+pub const EPSG_CODES: [u32; 7] = [2270, 2992, 3857, 4269, 4326, 26910, 32610];
+
+/// The `Units` enum lists the units of length for projected coordinates and distances.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, derive_more::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// International feet.
+    #[display("feet")]
+    Feet,
+    /// Meters.
+    #[default]
+    #[display("meters")]
+    Meters,
+}
+
+impl Units {
+    /// The `meters` method returns the length of the unit in meters.
+    pub fn meters(&self) -> f64 {
+        match self {
+            Self::Feet => FOOT,
+            Self::Meters => 1.0,
+        }
+    }
+
+    /// The `from_epsg` method returns the units of the projected coordinates in the system
+    /// `epsg`, or `None` for geographic or unsupported systems.  Web Mercator coordinates are
+    /// nominal meters, which overstate ground distances by about a third at the latitude of
+    /// southern Oregon.
+    pub fn from_epsg(epsg: u32) -> Option<Self> {
+        match epsg {
+            2270 | 2992 => Some(Self::Feet),
+            3857 | 26910 | 32610 => Some(Self::Meters),
+            _ => None,
+        }
+    }
+}
+
+// Meters per international foot.
+const FOOT: f64 = 0.3048;
+
+// The GRS80 ellipsoid, shared by NAD83 and (to within a tenth of a millimeter) WGS84.
+const GRS80: Ellipsoid = Ellipsoid {
+    a: 6378137.0,
+    f: 1.0 / 298.257222101,
+};
+
+// Radius of the sphere used by the web Mercator projection.
+const WEB_MERCATOR_RADIUS: f64 = 6378137.0;
+
+// Meters per degree of latitude, and of longitude at the equator, on a sphere of mean radius.
+// Shared by every conversion of a radius or distance between degrees and meters, so a radius
+// means the same distance in each.
+pub(crate) const METERS_PER_DEGREE: f64 = 111_195.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Ellipsoid {
+    // Semi-major axis in meters.
+    a: f64,
+    // Flattening.
+    f: f64,
+}
+
+impl Ellipsoid {
+    // Eccentricity squared.
+    fn e2(&self) -> f64 {
+        self.f * (2.0 - self.f)
+    }
+}
+
+// Parameters of a Lambert conformal conic projection with two standard parallels.  Angles are in
+// degrees, false easting and northing are in meters, and `unit` is the length of the output unit
+// in meters.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Lambert {
+    ellipsoid: Ellipsoid,
+    lat_1: f64,
+    lat_2: f64,
+    lat_0: f64,
+    lon_0: f64,
+    x_0: f64,
+    y_0: f64,
+    unit: f64,
+}
+
+impl Lambert {
+    // Returns the cone constant `n`, the scale term `a * F` and the radius `rho_0` at the origin,
+    // following Snyder, Map Projections: A Working Manual, equations 15-8 to 15-10.
+    fn constants(&self) -> (f64, f64, f64) {
+        let e = self.ellipsoid.e2().sqrt();
+        let (phi_1, phi_2) = (self.lat_1.to_radians(), self.lat_2.to_radians());
+        let m_1 = m(phi_1, e);
+        let m_2 = m(phi_2, e);
+        let t_1 = t(phi_1, e);
+        let t_2 = t(phi_2, e);
+        let n = if (phi_1 - phi_2).abs() < 1e-12 {
+            phi_1.sin()
+        } else {
+            (m_1.ln() - m_2.ln()) / (t_1.ln() - t_2.ln())
+        };
+        let af = self.ellipsoid.a * m_1 / (n * t_1.powf(n));
+        let rho_0 = af * t(self.lat_0.to_radians(), e).powf(n);
+        (n, af, rho_0)
+    }
+
+    fn forward(&self, longitude: f64, latitude: f64) -> (f64, f64) {
+        let e = self.ellipsoid.e2().sqrt();
+        let (n, af, rho_0) = self.constants();
+        let rho = af * t(latitude.to_radians(), e).powf(n);
+        let theta = n * (longitude - self.lon_0).to_radians();
+        let x = self.x_0 + rho * theta.sin();
+        let y = self.y_0 + rho_0 - rho * theta.cos();
+        (x / self.unit, y / self.unit)
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let e = self.ellipsoid.e2().sqrt();
+        let (n, af, rho_0) = self.constants();
+        let dx = x * self.unit - self.x_0;
+        let dy = rho_0 - (y * self.unit - self.y_0);
+        let rho = n.signum() * dx.hypot(dy);
+        let theta = (n.signum() * dx).atan2(n.signum() * dy);
+        let t = (rho / af).powf(1.0 / n);
+        let longitude = (theta / n).to_degrees() + self.lon_0;
+        (longitude, isometric_latitude(t, e).to_degrees())
+    }
+}
+
+// Parameters of a transverse Mercator projection.  Angles are in degrees, false easting and
+// northing are in meters, and `unit` is the length of the output unit in meters.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Transverse {
+    ellipsoid: Ellipsoid,
+    lat_0: f64,
+    lon_0: f64,
+    k_0: f64,
+    x_0: f64,
+    y_0: f64,
+    unit: f64,
+}
+
+impl Transverse {
+    // Distance along the meridian from the equator to latitude `phi`, following Snyder equation
+    // 3-21.
+    fn meridian(&self, phi: f64) -> f64 {
+        let e2 = self.ellipsoid.e2();
+        let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+        self.ellipsoid.a
+            * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * phi
+                - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * phi).sin()
+                + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+                - (35.0 * e6 / 3072.0) * (6.0 * phi).sin())
+    }
+
+    // Snyder equations 8-9 to 8-10.
+    fn forward(&self, longitude: f64, latitude: f64) -> (f64, f64) {
+        let e2 = self.ellipsoid.e2();
+        let ep2 = e2 / (1.0 - e2);
+        let phi = latitude.to_radians();
+        let n = self.ellipsoid.a / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+        let t = phi.tan().powi(2);
+        let c = ep2 * phi.cos().powi(2);
+        let a = (longitude - self.lon_0).to_radians() * phi.cos();
+        let m = self.meridian(phi);
+        let m_0 = self.meridian(self.lat_0.to_radians());
+        let x = self.k_0
+            * n
+            * (a + (1.0 - t + c) * a.powi(3) / 6.0
+                + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0);
+        let y = self.k_0
+            * (m - m_0
+                + n * phi.tan()
+                    * (a * a / 2.0
+                        + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+                        + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+        ((x + self.x_0) / self.unit, (y + self.y_0) / self.unit)
+    }
+
+    // Snyder equations 8-12 to 8-18.
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let e2 = self.ellipsoid.e2();
+        let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+        let ep2 = e2 / (1.0 - e2);
+        let x = x * self.unit - self.x_0;
+        let y = y * self.unit - self.y_0;
+        let m = self.meridian(self.lat_0.to_radians()) + y / self.k_0;
+        let mu = m / (self.ellipsoid.a * (1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0));
+        let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+        let phi_1 = mu
+            + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+            + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+            + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+            + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+        let c_1 = ep2 * phi_1.cos().powi(2);
+        let t_1 = phi_1.tan().powi(2);
+        let n_1 = self.ellipsoid.a / (1.0 - e2 * phi_1.sin().powi(2)).sqrt();
+        let r_1 = self.ellipsoid.a * (1.0 - e2) / (1.0 - e2 * phi_1.sin().powi(2)).powf(1.5);
+        let d = x / (n_1 * self.k_0);
+        let phi = phi_1
+            - (n_1 * phi_1.tan() / r_1)
+                * (d * d / 2.0
+                    - (5.0 + 3.0 * t_1 + 10.0 * c_1 - 4.0 * c_1 * c_1 - 9.0 * ep2) * d.powi(4)
+                        / 24.0
+                    + (61.0 + 90.0 * t_1 + 298.0 * c_1 + 45.0 * t_1 * t_1
+                        - 252.0 * ep2
+                        - 3.0 * c_1 * c_1)
+                        * d.powi(6)
+                        / 720.0);
+        let lambda = (d - (1.0 + 2.0 * t_1 + c_1) * d.powi(3) / 6.0
+            + (5.0 - 2.0 * c_1 + 28.0 * t_1 - 3.0 * c_1 * c_1 + 8.0 * ep2 + 24.0 * t_1 * t_1)
+                * d.powi(5)
+                / 120.0)
+            / phi_1.cos();
+        (self.lon_0 + lambda.to_degrees(), phi.to_degrees())
+    }
+}
+
+// The projection behind an EPSG code.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Method {
+    Geographic,
+    WebMercator,
+    Lambert(Lambert),
+    Transverse(Transverse),
+}
+
+impl Method {
+    fn from_epsg(epsg: u32) -> Result<Self, Projection> {
+        let utm_10 = Transverse {
+            ellipsoid: GRS80,
+            lat_0: 0.0,
+            lon_0: -123.0,
+            k_0: 0.9996,
+            x_0: 500000.0,
+            y_0: 0.0,
+            unit: 1.0,
+        };
+        match epsg {
+            2270 => Ok(Self::Lambert(Lambert {
+                ellipsoid: GRS80,
+                lat_1: 44.0,
+                lat_2: 42.0 + 1.0 / 3.0,
+                lat_0: 41.0 + 2.0 / 3.0,
+                lon_0: -120.5,
+                x_0: 1500000.0,
+                y_0: 0.0,
+                unit: FOOT,
+            })),
+            2992 => Ok(Self::Lambert(Lambert {
+                ellipsoid: GRS80,
+                lat_1: 43.0,
+                lat_2: 45.5,
+                lat_0: 41.75,
+                lon_0: -120.5,
+                x_0: 400000.0,
+                y_0: 0.0,
+                unit: FOOT,
+            })),
+            3857 => Ok(Self::WebMercator),
+            4269 | 4326 => Ok(Self::Geographic),
+            26910 | 32610 => Ok(Self::Transverse(utm_10)),
+            _ => Err(Projection::new(
+                epsg,
+                "unsupported coordinate reference system".into(),
+                line!(),
+                file!().into(),
+            )),
+        }
+    }
+
+    fn inverse(self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            Self::Geographic => (x, y),
+            Self::WebMercator => (
+                (x / WEB_MERCATOR_RADIUS).to_degrees(),
+                (2.0 * (y / WEB_MERCATOR_RADIUS).exp().atan() - FRAC_PI_2).to_degrees(),
+            ),
+            Self::Lambert(lambert) => lambert.inverse(x, y),
+            Self::Transverse(transverse) => transverse.inverse(x, y),
+        }
+    }
+
+    fn forward(self, longitude: f64, latitude: f64) -> (f64, f64) {
+        match self {
+            Self::Geographic => (longitude, latitude),
+            Self::WebMercator => (
+                WEB_MERCATOR_RADIUS * longitude.to_radians(),
+                WEB_MERCATOR_RADIUS * (FRAC_PI_4 + latitude.to_radians() / 2.0).tan().ln(),
+            ),
+            Self::Lambert(lambert) => lambert.forward(longitude, latitude),
+            Self::Transverse(transverse) => transverse.forward(longitude, latitude),
+        }
+    }
+}
+
+/// The `to_geographic` function converts the coordinates `x` and `y` in the system `epsg` to
+/// geographic coordinates, returned as (longitude, latitude) in degrees.  Errors if `epsg` is not
+/// listed in [`EPSG_CODES`].
+pub fn to_geographic(epsg: u32, x: f64, y: f64) -> Result<(f64, f64), Projection> {
+    Ok(Method::from_epsg(epsg)?.inverse(x, y))
+}
+
+/// The `from_geographic` function converts the geographic coordinates `longitude` and `latitude`,
+/// in degrees, to coordinates in the system `epsg`, returned as (x, y).  Errors if `epsg` is not
+/// listed in [`EPSG_CODES`].
+pub fn from_geographic(epsg: u32, longitude: f64, latitude: f64) -> Result<(f64, f64), Projection> {
+    Ok(Method::from_epsg(epsg)?.forward(longitude, latitude))
+}
+
+/// The `Geolocate` trait fills in the geographic coordinates of a record from its projected
+/// coordinates, for consumers that expect latitude and longitude whatever the native system of
+/// the data.
+pub trait Geolocate {
+    /// The `projected` method returns the projected coordinates of the record as (x, y), or
+    /// `None` if the record has no location.
+    fn projected(&self) -> Option<(f64, f64)>;
+
+    /// The `set_geographic` method records the geographic coordinates `longitude` and
+    /// `latitude`, in degrees.
+    fn set_geographic(&mut self, longitude: f64, latitude: f64);
+
+    /// The `geolocate` method converts the projected coordinates of the record from the system
+    /// `epsg` to geographic coordinates in WGS84, and records them.  Records without a location
+    /// are unchanged.  Errors if `epsg` is not listed in [`EPSG_CODES`].
+    fn geolocate(&mut self, epsg: u32) -> Result<(), Projection> {
+        if let Some((x, y)) = self.projected() {
+            let (longitude, latitude) = to_geographic(epsg, x, y)?;
+            self.set_geographic(longitude, latitude);
+        }
+        Ok(())
+    }
+}
+
+// Declares a record struct located by the projected coordinates in its `x` and `y` fields,
+// appending the `lat` and `lon` fields that hold its geographic coordinates and implementing
+// `Geolocate` over them, so each located record documents and fills them in the same way.
+macro_rules! geolocated {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                pub $field:ident: $ty:ty,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        pub struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+            /// The `lat` field holds the WGS84 latitude of the record, computed from the projected
+            /// coordinates when their coordinate reference system is known.
+            #[serde(default)]
+            pub lat: Option<f64>,
+            /// The `lon` field holds the WGS84 longitude of the record.
+            #[serde(default)]
+            pub lon: Option<f64>,
+        }
+
+        impl $crate::Geolocate for $name {
+            fn projected(&self) -> Option<(f64, f64)> {
+                Some((self.x, self.y))
+            }
+
+            fn set_geographic(&mut self, longitude: f64, latitude: f64) {
+                self.lat = Some(latitude);
+                self.lon = Some(longitude);
+            }
+        }
+    };
+}
+pub(crate) use geolocated;
+
+/// The `geolocate` function fills in the geographic coordinates of each record in `records`
+/// from its projected coordinates in the system `epsg`, using [`Geolocate::geolocate`].
+pub fn geolocate<T: Geolocate>(records: &mut [T], epsg: u32) -> Result<(), Projection> {
+    Method::from_epsg(epsg)?;
+    for record in records.iter_mut() {
+        record.geolocate(epsg)?;
+    }
+    Ok(())
+}
+
+/// The `transform` function converts the coordinates `x` and `y` from the system `from` to the
+/// system `to`, returned as (x, y).  Errors if either code is not listed in [`EPSG_CODES`].
+pub fn transform(from: u32, to: u32, x: f64, y: f64) -> Result<(f64, f64), Projection> {
+    let (source, target) = (Method::from_epsg(from)?, Method::from_epsg(to)?);
+    if source == target {
+        return Ok((x, y));
+    }
+    let (longitude, latitude) = source.inverse(x, y);
+    Ok(target.forward(longitude, latitude))
+}
+
+// Snyder equation 14-15.
+fn m(phi: f64, e: f64) -> f64 {
+    phi.cos() / (1.0 - (e * phi.sin()).powi(2)).sqrt()
+}
+
+// Snyder equation 15-9.
+fn t(phi: f64, e: f64) -> f64 {
+    let es = e * phi.sin();
+    (FRAC_PI_4 - phi / 2.0).tan() / ((1.0 - es) / (1.0 + es)).powf(e / 2.0)
+}
+
+// Recovers the latitude from the value of `t`, iterating Snyder equation 7-9.
+fn isometric_latitude(t: f64, e: f64) -> f64 {
+    let mut phi = FRAC_PI_2 - 2.0 * t.atan();
+    for _ in 0..15 {
+        let es = e * phi.sin();
+        let next = FRAC_PI_2 - 2.0 * (t * ((1.0 - es) / (1.0 + es)).powf(e / 2.0)).atan();
+        if (next - phi).abs() < 1e-12 {
+            return next;
+        }
+        phi = next;
+    }
+    phi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccessRecord, Cartesian, IntoBin, ParcelIssue, ParcelIssueKind, SpatialAddresses};
+
+    #[test]
+    fn reproject_addresses() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let addresses = SpatialAddresses::from(&city_addresses[0..100]);
+        assert_eq!(addresses.epsg(), None);
+        assert!(addresses.clone().reproject(WGS84).is_err());
+
+        // Projected coordinates of the sample are web Mercator.
+        let mut geographic = addresses.clone().with_epsg(3857);
+        geographic.reproject(WGS84)?;
+        for address in geographic.iter() {
+            assert!((address.x - address.longitude).abs() < 1e-7);
+            assert!((address.y - address.latitude).abs() < 1e-7);
+        }
+
+        for epsg in [2270, 2992, 26910] {
+            let mut projected = addresses.clone().with_epsg(3857);
+            projected.reproject(epsg)?;
+            assert_eq!(projected.epsg(), Some(epsg));
+            projected.reproject(3857)?;
+            for (a, b) in projected.iter().zip(addresses.iter()) {
+                assert!(a.distance(b) < 1e-3);
+            }
+        }
+
+        // The origin of Oregon South lies at the false easting.
+        let (x, y) = from_geographic(2270, -120.5, 41.0 + 2.0 / 3.0)?;
+        assert!((x - 4921259.843).abs() < 1e-3);
+        assert!(y.abs() < 1e-3);
+        let (longitude, latitude) = to_geographic(2270, x, y)?;
+        assert!((longitude + 120.5).abs() < 1e-9);
+        assert!((latitude - (41.0 + 2.0 / 3.0)).abs() < 1e-9);
+        assert!(transform(2270, 9999, x, y).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn geographic_columns() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let addresses = SpatialAddresses::from(&city_addresses[0..100]);
+        assert!(addresses.clone().geolocate().is_err());
+
+        // Projected coordinates of the sample are web Mercator, so the computed coordinates should
+        // match those recorded by the source.
+        let mut located = addresses.clone().with_epsg(3857);
+        for address in located.iter_mut() {
+            address.latitude = 0.0;
+            address.longitude = 0.0;
+        }
+        located.geolocate()?;
+        for (a, b) in located.iter().zip(addresses.iter()) {
+            assert!((a.latitude - b.latitude).abs() < 1e-7);
+            assert!((a.longitude - b.longitude).abs() < 1e-7);
+        }
+
+        // The origin of Oregon South lies at the false easting.
+        let mut records = vec![AccessRecord {
+            x: 4921259.843,
+            y: 0.0,
+            ..Default::default()
+        }];
+        geolocate(&mut records, 2270)?;
+        assert!((records[0].lon.unwrap_or_default() + 120.5).abs() < 1e-6);
+        assert!((records[0].lat.unwrap_or_default() - (41.0 + 2.0 / 3.0)).abs() < 1e-6);
+        assert!(geolocate(&mut records, 9999).is_err());
+
+        // Unaddressed parcels have no location to convert.
+        let mut issues = vec![
+            ParcelIssue {
+                kind: ParcelIssueKind::Unaddressed,
+                ..Default::default()
+            },
+            ParcelIssue {
+                x: Some(0.0),
+                y: Some(0.0),
+                ..Default::default()
+            },
+        ];
+        geolocate(&mut issues, WGS84)?;
+        assert_eq!(issues[0].lat, None);
+        assert_eq!(issues[1].lat, Some(0.0));
+        assert_eq!(issues[1].lon, Some(0.0));
+        Ok(())
+    }
+}
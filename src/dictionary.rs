@@ -0,0 +1,264 @@
+//! The `dictionary` module compiles the street names in an authoritative address set into a
+//! dictionary, for lookups, frequency counts and spelling suggestions.  Tools outside this crate,
+//! such as permit intake, can check a street name against the dictionary before accepting it.
+use crate::{
+    Address, AddressErrorKind, IntoCsv, IntoJson, Io, from_csv, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::info;
+
+/// The `SUGGESTION_DISTANCE` constant holds the largest edit distance between a street name and
+/// a suggested spelling.
+pub const SUGGESTION_DISTANCE: usize = 2;
+
+/// The `SUGGESTION_LIMIT` constant holds the number of suggestions listed by the 'streets'
+/// command.
+pub const SUGGESTION_LIMIT: usize = 5;
+
+/// The `StreetEntry` struct represents a street in a [`StreetDictionary`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct StreetEntry {
+    /// The `street` field holds the complete street name, with post types and directionals
+    /// spelled out.
+    pub street: String,
+    /// The `abbreviated` field holds the complete street name with abbreviated post types and
+    /// directionals.
+    pub abbreviated: String,
+    /// The `name` field holds the street name without directionals or post type.
+    pub name: String,
+    /// The `count` field holds the number of addresses on the street.
+    pub count: usize,
+}
+
+impl StreetEntry {
+    // Edit distance between `street` and the closest of the spellings of the entry.
+    fn distance(&self, street: &str) -> usize {
+        [&self.street, &self.abbreviated, &self.name]
+            .iter()
+            .map(|spelling| strsim::levenshtein(street, spelling))
+            .min()
+            .unwrap_or(usize::MAX)
+    }
+}
+
+/// The `StreetDictionary` struct holds the streets named in a set of addresses, sorted by
+/// complete street name.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct StreetDictionary(Vec<StreetEntry>);
+
+impl StreetDictionary {
+    /// The `from_addresses` method compiles a dictionary of the complete street names in
+    /// `addresses`, counting the addresses on each street.
+    pub fn from_addresses<T: Address>(addresses: &[T]) -> Self {
+        let mut streets: BTreeMap<String, StreetEntry> = BTreeMap::new();
+        for address in addresses {
+            let street = address.complete_street_name(false).to_uppercase();
+            let entry = streets
+                .entry(street.clone())
+                .or_insert_with(|| StreetEntry {
+                    street,
+                    abbreviated: address.complete_street_name(true).to_uppercase(),
+                    name: address.street_name().to_uppercase(),
+                    count: 0,
+                });
+            entry.count += 1;
+        }
+        info!("{} streets in dictionary.", streets.len());
+        Self(streets.into_values().collect())
+    }
+
+    /// The `get` method returns the entry for `street`, matching either the full or abbreviated
+    /// complete street name, ignoring case and extra whitespace.
+    pub fn get(&self, street: &str) -> Option<&StreetEntry> {
+        let street = normalize(street);
+        self.iter()
+            .find(|entry| entry.street == street || entry.abbreviated == street)
+    }
+
+    /// The `contains` method returns `true` if `street` is in the dictionary.  See
+    /// [`StreetDictionary::get`].
+    pub fn contains(&self, street: &str) -> bool {
+        self.get(street).is_some()
+    }
+
+    /// The `count` method returns the number of addresses on `street`, or zero if the street is
+    /// not in the dictionary.
+    pub fn count(&self, street: &str) -> usize {
+        self.get(street).map_or(0, |entry| entry.count)
+    }
+
+    /// The `suggest` method returns up to `limit` streets in the dictionary within
+    /// [`SUGGESTION_DISTANCE`] edits of `street`, closest first, breaking ties in favor of streets
+    /// with more addresses.  The distance is measured against the full and abbreviated complete
+    /// street names, and the bare street name, so "RIVERSID" suggests "RIVERSIDE AVENUE".  A
+    /// street in the dictionary suggests itself at a distance of zero.
+    pub fn suggest(&self, street: &str, limit: usize) -> StreetSuggestions {
+        let street = normalize(street);
+        let mut records = self
+            .iter()
+            .map(|entry| (entry, entry.distance(&street)))
+            .filter(|(_, distance)| *distance <= SUGGESTION_DISTANCE)
+            .map(|(entry, distance)| StreetSuggestion {
+                input: street.clone(),
+                street: entry.street.clone(),
+                distance,
+                count: entry.count,
+            })
+            .collect::<Vec<StreetSuggestion>>();
+        records.sort_by(|a, b| {
+            a.distance
+                .cmp(&b.distance)
+                .then(b.count.cmp(&a.count))
+                .then(a.street.cmp(&b.street))
+        });
+        records.truncate(limit);
+        StreetSuggestions(records)
+    }
+}
+
+impl IntoCsv<StreetDictionary> for StreetDictionary {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for StreetDictionary {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+/// The `StreetSuggestion` struct holds a street from a [`StreetDictionary`] suggested as the
+/// intended spelling of an input street name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct StreetSuggestion {
+    /// The `input` field holds the street name as entered, normalized to uppercase.
+    pub input: String,
+    /// The `street` field holds the complete street name of the suggestion.
+    pub street: String,
+    /// The `distance` field holds the edit distance between the input and the suggestion.
+    pub distance: usize,
+    /// The `count` field holds the number of addresses on the suggested street.
+    pub count: usize,
+}
+
+/// The `StreetSuggestions` struct holds a vector of type [`StreetSuggestion`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct StreetSuggestions(Vec<StreetSuggestion>);
+
+impl IntoCsv<StreetSuggestions> for StreetSuggestions {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for StreetSuggestions {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+// Uppercases `street` and collapses runs of whitespace.
+pub(crate) fn normalize(street: &str) -> String {
+    street
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommonAddress, IntoBin, SpatialAddresses, StreetNamePostType};
+
+    #[test]
+    fn street_dictionary() -> anyhow::Result<()> {
+        let address = |number: i64, street_name: &str| CommonAddress {
+            number,
+            street_name: street_name.to_string(),
+            street_type: Some(StreetNamePostType::AVENUE),
+            ..Default::default()
+        };
+        let addresses = [100, 102, 104]
+            .iter()
+            .map(|n| address(*n, "RIVERSIDE"))
+            .chain([address(200, "RIVERSHORE"), address(300, "ELM")])
+            .collect::<Vec<CommonAddress>>();
+        let dictionary = StreetDictionary::from_addresses(&addresses);
+        assert_eq!(dictionary.len(), 3);
+        assert!(dictionary.contains("Riverside Ave"));
+        assert!(dictionary.contains("RIVERSIDE  AVENUE"));
+        assert!(!dictionary.contains("RIVERSIDE"));
+        assert_eq!(dictionary.count("riverside ave"), 3);
+        assert_eq!(dictionary.count("OAK AVE"), 0);
+
+        let suggestions = dictionary.suggest("RIVERSID AVE", SUGGESTION_LIMIT);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].street, "RIVERSIDE AVENUE");
+        assert_eq!(suggestions[0].distance, 1);
+        let suggestions = dictionary.suggest("rivers", SUGGESTION_LIMIT);
+        assert!(suggestions.is_empty());
+        let suggestions = dictionary.suggest("RIVERSHIRE", SUGGESTION_LIMIT);
+        assert_eq!(suggestions[0].street, "RIVERSHORE AVENUE");
+        assert_eq!(
+            dictionary.suggest("ELM AVE", SUGGESTION_LIMIT)[0].distance,
+            0
+        );
+
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let dictionary = StreetDictionary::from_addresses(&city_addresses);
+        assert_eq!(
+            dictionary.iter().map(|e| e.count).sum::<usize>(),
+            city_addresses.len()
+        );
+        Ok(())
+    }
+}
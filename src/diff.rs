@@ -0,0 +1,338 @@
+//! The `diff` module detects the changes between two vintages of the same layer, such as weekly
+//! exports of the city address points, for auditing our own edits.  Unlike the `compare` command,
+//! which matches addresses across jurisdictions by their components, records are paired by a key
+//! column and every field is compared as text, so any csv layer can be audited.
+use crate::{
+    AddressErrorKind, Builder, Csv, IntoCsv, IntoJson, Io, from_csv, open_csv, to_csv, to_json,
+    to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use strum::IntoEnumIterator;
+use tracing::{info, warn};
+
+/// The `DIFF_KEY` constant holds the default name of the key column pairing the records of two
+/// vintages, the ESRI Global ID.
+pub const DIFF_KEY: &str = "global_id";
+
+// Normalizes a column name for matching, so "global_id" names the "GlobalID" column.
+fn column_name(name: &str) -> String {
+    name.trim_start_matches('\u{feff}')
+        .chars()
+        .filter(|c| *c != '_' && !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// The `LayerVintage` struct holds the records of a snapshot of a layer, keyed by the value of
+/// the key column.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerVintage {
+    /// The `headers` field holds the column names of the layer.
+    pub headers: Vec<String>,
+    /// The `records` field maps the key of each record to its values, in the order of the
+    /// headers.
+    pub records: BTreeMap<String, Vec<String>>,
+}
+
+impl LayerVintage {
+    /// The `from_path` method reads the csv file at `path`, keying each record by its value in the
+    /// column `key`.  Column names match ignoring case and underscores, so "global_id" names the
+    /// "GlobalID" column.  Records without a key are skipped, and only the first record with a
+    /// repeated key is kept.  Errors if the file has no column named `key`.
+    pub fn from_path<P: AsRef<Path>>(path: P, key: &str) -> Result<Self, AddressErrorKind> {
+        let path = path.as_ref();
+        let csv_error = |source| Csv::new(path.into(), source, line!(), file!().into());
+        info!("Reading vintage from {:?}", path);
+        let mut rdr = csv::Reader::from_reader(open_csv(path)?);
+        let headers = rdr
+            .headers()
+            .map_err(csv_error)?
+            .iter()
+            .map(|header| header.trim_start_matches('\u{feff}').to_string())
+            .collect::<Vec<String>>();
+        let Some(column) = headers
+            .iter()
+            .position(|header| column_name(header) == column_name(key))
+        else {
+            return Err(Builder::new(
+                format!("{} has no key column named '{key}'", path.display()),
+                "LayerVintage".to_string(),
+                line!(),
+                file!().to_string(),
+            )
+            .into());
+        };
+        let mut records = BTreeMap::new();
+        let mut repeated = 0;
+        for record in rdr.records() {
+            let record = record.map_err(csv_error)?;
+            let key = record.get(column).unwrap_or_default().trim().to_string();
+            if key.is_empty() {
+                continue;
+            }
+            if records.contains_key(&key) {
+                repeated += 1;
+                continue;
+            }
+            records.insert(key, record.iter().map(str::to_string).collect());
+        }
+        if repeated > 0 {
+            warn!(
+                "{repeated} records with a repeated key skipped in {:?}",
+                path
+            );
+        }
+        info!("Vintage records read: {}", records.len());
+        Ok(Self { headers, records })
+    }
+
+    /// The `len` method returns the number of records in the vintage.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// The `is_empty` method returns `true` if the vintage holds no records.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+/// The `ChangeKind` enum describes how a record changed between two vintages.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+    strum::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// The record appears only in the new vintage.
+    #[default]
+    #[display("added")]
+    Added,
+    /// The record appears only in the old vintage.
+    #[display("deleted")]
+    Deleted,
+    /// The record appears in both vintages with a different value in a field.
+    #[display("modified")]
+    Modified,
+}
+
+/// The `LayerChange` struct reports a record added to or deleted from a layer, or a field of a
+/// record modified, between two vintages.  A modified record is reported once for each field
+/// that changed.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct LayerChange {
+    /// The `key` field holds the value of the key column of the record.
+    pub key: String,
+    /// The `change` field holds how the record changed.
+    pub change: ChangeKind,
+    /// The `field` field holds the name of the modified field, and is empty for added and
+    /// deleted records.
+    pub field: Option<String>,
+    /// The `old` field holds the value of the modified field in the old vintage.
+    pub old: Option<String>,
+    /// The `new` field holds the value of the modified field in the new vintage.
+    pub new: Option<String>,
+}
+
+// Returns true if the values agree as text, or as numbers, so "12.0" and "12" agree.
+fn same(old: &str, new: &str) -> bool {
+    let (old, new) = (old.trim(), new.trim());
+    old == new
+        || matches!(
+            (old.parse::<f64>(), new.parse::<f64>()),
+            (Ok(old), Ok(new)) if old == new
+        )
+}
+
+/// The `LayerChanges` struct holds a vector of type [`LayerChange`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct LayerChanges(Vec<LayerChange>);
+
+impl LayerChanges {
+    /// The `diff` method reports the records added to, deleted from and modified in `new`
+    /// compared to `old`, sorted by key.  Only the columns present in both vintages are
+    /// compared, skipping the columns named in `ignore`, such as edit timestamps.  Values that
+    /// agree as numbers, such as "12.0" and "12", are unchanged.
+    pub fn diff(old: &LayerVintage, new: &LayerVintage, ignore: &[String]) -> Self {
+        let ignore = ignore
+            .iter()
+            .map(|name| column_name(name))
+            .collect::<HashSet<String>>();
+        let new_columns = new
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| (column_name(header), i))
+            .collect::<BTreeMap<String, usize>>();
+        // Pairs the index of each compared field in the old vintage with its index in the new.
+        let mut fields = Vec::new();
+        for (i, header) in old.headers.iter().enumerate() {
+            let name = column_name(header);
+            match new_columns.get(&name) {
+                Some(j) if !ignore.contains(&name) => fields.push((header, i, *j)),
+                Some(_) => {}
+                None => info!("Column {header} dropped from the new vintage."),
+            }
+        }
+        let old_columns = old
+            .headers
+            .iter()
+            .map(|header| column_name(header))
+            .collect::<HashSet<String>>();
+        for header in &new.headers {
+            if !old_columns.contains(&column_name(header)) {
+                info!("Column {header} added in the new vintage.");
+            }
+        }
+
+        let mut records = Vec::new();
+        for (key, values) in &old.records {
+            let Some(other) = new.records.get(key) else {
+                records.push(LayerChange {
+                    key: key.clone(),
+                    change: ChangeKind::Deleted,
+                    ..Default::default()
+                });
+                continue;
+            };
+            for (header, i, j) in &fields {
+                let before = values.get(*i).map(String::as_str).unwrap_or_default();
+                let after = other.get(*j).map(String::as_str).unwrap_or_default();
+                if !same(before, after) {
+                    records.push(LayerChange {
+                        key: key.clone(),
+                        change: ChangeKind::Modified,
+                        field: Some(header.to_string()),
+                        old: Some(before.to_string()),
+                        new: Some(after.to_string()),
+                    });
+                }
+            }
+        }
+        for key in new.records.keys() {
+            if !old.records.contains_key(key) {
+                records.push(LayerChange {
+                    key: key.clone(),
+                    change: ChangeKind::Added,
+                    ..Default::default()
+                });
+            }
+        }
+        records.sort_by(|a, b| a.key.cmp(&b.key));
+        Self(records)
+    }
+
+    /// The `count` method returns the number of records with the change `change`, counting a
+    /// modified record once however many fields changed.
+    pub fn count(&self, change: ChangeKind) -> usize {
+        self.iter()
+            .filter(|r| r.change == change)
+            .map(|r| &r.key)
+            .collect::<HashSet<&String>>()
+            .len()
+    }
+
+    /// The `filter` method retains the records with the change named by `filter`, either
+    /// "added", "deleted" or "modified".
+    pub fn filter(mut self, filter: &str) -> Self {
+        if ChangeKind::iter().any(|change| change.to_string() == filter) {
+            self.retain(|r| r.change.to_string() == filter);
+        } else {
+            info!("Invalid filter provided.");
+        }
+        self
+    }
+}
+
+impl IntoCsv<LayerChanges> for LayerChanges {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for LayerChanges {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_vintages() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir();
+        let old_path = dir.join("destination_vintage_old.csv");
+        let new_path = dir.join("destination_vintage_new.csv");
+        std::fs::write(
+            &old_path,
+            "\u{feff}GlobalID,Add_Number,St_Name,x,last_edited_date\n\
+             {A},100,MAIN,10.0,2024-10-01\n\
+             {B},200,ELM,20.0,2024-10-01\n\
+             {C},300,OAK,30.0,2024-10-01\n",
+        )?;
+        std::fs::write(
+            &new_path,
+            "GlobalID,Add_Number,St_Name,x,last_edited_date,notes\n\
+             {A},100,MAIN,10,2024-10-08,\n\
+             {B},210,ELMS,20.0,2024-10-08,renumbered\n\
+             {D},400,PINE,40.0,2024-10-08,\n",
+        )?;
+        assert!(LayerVintage::from_path(&old_path, "object_id").is_err());
+        let old = LayerVintage::from_path(&old_path, DIFF_KEY)?;
+        let new = LayerVintage::from_path(&new_path, DIFF_KEY)?;
+        std::fs::remove_file(&old_path)?;
+        std::fs::remove_file(&new_path)?;
+        assert_eq!(old.len(), 3);
+        let changes = LayerChanges::diff(&old, &new, &["last_edited_date".to_string()]);
+        // Only the renumbered record differs in a compared field, with one row for each field.
+        assert_eq!(changes.len(), 4);
+        assert_eq!(changes[0].key, "{B}");
+        assert_eq!(changes[0].field.as_deref(), Some("Add_Number"));
+        assert_eq!(changes[0].old.as_deref(), Some("200"));
+        assert_eq!(changes[0].new.as_deref(), Some("210"));
+        assert_eq!(changes[1].field.as_deref(), Some("St_Name"));
+        assert_eq!(changes[2].change, ChangeKind::Deleted);
+        assert_eq!(changes[3].change, ChangeKind::Added);
+        assert_eq!(changes.count(ChangeKind::Modified), 1);
+        assert_eq!(changes.filter("added").len(), 1);
+        Ok(())
+    }
+}
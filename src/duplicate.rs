@@ -0,0 +1,632 @@
+//! The `duplicate` module groups addresses that likely describe the same location, despite
+//! differences in formatting or a minor typo in the street name.
+use crate::retired::parse_timestamp;
+use crate::{
+    Address, AddressErrorKind, AddressStatus, GrantsPassSpatialAddress, IntoCsv, IntoJson, Io,
+    JosephineCountySpatialAddress2024, Parse, from_csv, to_csv, to_json, to_ndjson,
+};
+use chrono::NaiveDateTime;
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use tracing::info;
+
+/// Street names shorter than this length must match exactly, since a one-character difference
+/// between short names such as "K" and "J" usually distinguishes two real streets.
+const TYPO_MIN_LENGTH: usize = 4;
+
+/// The `DuplicateRecord` struct represents a member of a group of near-duplicate addresses.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct DuplicateRecord {
+    /// The `group` field holds an ID shared by the members of the group.
+    pub group: usize,
+    /// The `label` field holds the address label as recorded.
+    pub label: String,
+    /// The `normalized` field holds the normalized form of the address used for grouping.
+    pub normalized: String,
+    /// The `postal_community` field holds the postal community of the address.
+    pub postal_community: String,
+    /// The `zip` field holds the zip code of the address.
+    pub zip: i64,
+    /// The `status` field holds the status of the address.
+    pub status: AddressStatus,
+}
+
+/// The `DuplicateRecords` struct holds a vector of type [`DuplicateRecord`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct DuplicateRecords(Vec<DuplicateRecord>);
+
+// Normalized components of an address.  Near duplicates share every component except possibly
+// the street name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Normal {
+    number: String,
+    directional: String,
+    street_name: String,
+    post_type: String,
+    unit: String,
+}
+
+impl Normal {
+    fn new<T: Address>(address: &T) -> Self {
+        let clean = |value: &str| {
+            value
+                .to_uppercase()
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '/')
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<&str>>()
+                .join(" ")
+        };
+        let mut number = address.number().to_string();
+        if let Some(suffix) = address.number_suffix() {
+            number.push(' ');
+            number.push_str(&clean(suffix));
+        }
+        let mut directional = address.directional_abbreviated().unwrap_or_default();
+        let mut street_name = clean(address.street_name());
+        // A directional recorded in the street name field belongs in the directional.
+        if directional.is_empty()
+            && let Some((first, rest)) = street_name.split_once(' ')
+            && let Ok(("", Some(parsed))) = Parse::pre_directional(first)
+        {
+            directional = parsed.abbreviate();
+            street_name = rest.to_string();
+        }
+        let post_type = address
+            .street_type()
+            .map(|t| t.abbreviate())
+            .unwrap_or_default();
+        // Unit types vary between "APT", "UNIT" and "#", so only the identifier is compared.
+        let unit = address
+            .subaddress_id()
+            .as_ref()
+            .map(|id| {
+                let id = clean(id).replace(' ', "");
+                let trimmed = id.trim_start_matches('0');
+                if trimmed.is_empty() {
+                    id
+                } else {
+                    trimmed.to_string()
+                }
+            })
+            .unwrap_or_default();
+        Self {
+            number,
+            directional,
+            street_name,
+            post_type,
+            unit,
+        }
+    }
+
+    fn label(&self) -> String {
+        [
+            self.number.as_str(),
+            &self.directional,
+            &self.street_name,
+            &self.post_type,
+            &self.unit,
+        ]
+        .iter()
+        .filter(|s| !s.is_empty())
+        .copied()
+        .collect::<Vec<&str>>()
+        .join(" ")
+    }
+
+    // Components other than the street name.
+    fn block(&self) -> (String, String, String, String) {
+        (
+            self.number.clone(),
+            self.directional.clone(),
+            self.post_type.clone(),
+            self.unit.clone(),
+        )
+    }
+}
+
+impl DuplicateRecords {
+    /// The `near` method groups the addresses in `addresses` that differ only by whitespace,
+    /// punctuation, directional abbreviation, unit formatting, or a one-character typo in a
+    /// street name of at least four characters.  Only groups with more than one member are
+    /// returned.  Members of a group share the same `group` ID.
+    pub fn near<T: Address>(addresses: &[T]) -> Self {
+        let normals = addresses.iter().map(Normal::new).collect::<Vec<Normal>>();
+        let mut records = Vec::new();
+        for (group, members) in near_groups(&normals).iter().enumerate() {
+            for i in members {
+                let address = &addresses[*i];
+                records.push(DuplicateRecord {
+                    group,
+                    label: address.label(),
+                    normalized: normals[*i].label(),
+                    postal_community: address.postal_community().clone(),
+                    zip: address.zip(),
+                    status: *address.status(),
+                });
+            }
+        }
+        info!("Near duplicate records: {}", records.len());
+        Self(records)
+    }
+}
+
+// Groups the addresses with normalized forms `normals` that are near duplicates, returning the
+// indices of the members of each group with more than one member.
+fn near_groups(normals: &[Normal]) -> Vec<Vec<usize>> {
+    // Addresses with the same normalized form are duplicates.
+    let mut keys: BTreeMap<&Normal, Vec<usize>> = BTreeMap::new();
+    for (i, normal) in normals.iter().enumerate() {
+        keys.entry(normal).or_default().push(i);
+    }
+    let groups = keys.values().cloned().collect::<Vec<Vec<usize>>>();
+    let mut parents = (0..groups.len()).collect::<Vec<usize>>();
+
+    // Merge groups on the same block whose street names are one edit apart.
+    let mut blocks: HashMap<(String, String, String, String), Vec<usize>> = HashMap::new();
+    for (g, members) in groups.iter().enumerate() {
+        blocks
+            .entry(normals[members[0]].block())
+            .or_default()
+            .push(g);
+    }
+    for candidates in blocks.values().filter(|c| c.len() > 1) {
+        for (i, a) in candidates.iter().enumerate() {
+            for b in &candidates[i + 1..] {
+                let x = &normals[groups[*a][0]].street_name;
+                let y = &normals[groups[*b][0]].street_name;
+                if x.len() >= TYPO_MIN_LENGTH
+                    && y.len() >= TYPO_MIN_LENGTH
+                    && strsim::levenshtein(x, y) <= 1
+                {
+                    let (ra, rb) = (root(&mut parents, *a), root(&mut parents, *b));
+                    parents[ra.max(rb)] = ra.min(rb);
+                }
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (g, members) in groups.iter().enumerate() {
+        let r = root(&mut parents, g);
+        clusters.entry(r).or_default().extend(members);
+    }
+    clusters.into_values().filter(|m| m.len() > 1).collect()
+}
+
+impl IntoCsv<DuplicateRecords> for DuplicateRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for DuplicateRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+/// The `Edited` trait exposes the feature metadata used to choose between duplicate records.
+pub trait Edited {
+    /// The `object_id` method returns the ESRI Object ID of the feature, if known.
+    fn object_id(&self) -> Option<i64>;
+    /// The `global_id` method returns the ESRI Global ID of the feature, if known.
+    fn global_id(&self) -> Option<String>;
+    /// The `last_edited` method returns the date-time stamp of the last edit to the feature, as
+    /// recorded.
+    fn last_edited(&self) -> Option<String>;
+}
+
+impl Edited for GrantsPassSpatialAddress {
+    fn object_id(&self) -> Option<i64> {
+        self.object_id
+    }
+
+    fn global_id(&self) -> Option<String> {
+        Some(self.global_id.clone())
+    }
+
+    fn last_edited(&self) -> Option<String> {
+        Some(self.last_edited_date.clone())
+    }
+}
+
+impl Edited for JosephineCountySpatialAddress2024 {
+    fn object_id(&self) -> Option<i64> {
+        self.object_id
+    }
+
+    fn global_id(&self) -> Option<String> {
+        self.global_id.clone()
+    }
+
+    fn last_edited(&self) -> Option<String> {
+        self.date_updated.clone()
+    }
+}
+
+/// The `Resolution` enum selects how the `duplicates` command chooses the record to keep from
+/// each group of duplicates.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize, derive_more::Display,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Resolution {
+    /// Keeps the most recently edited record.
+    #[display("keep-newest")]
+    KeepNewest,
+    /// Keeps the record with the lowest Object ID, usually the first entered.
+    #[display("keep-lowest-oid")]
+    KeepLowestOid,
+    /// Marks every record for review.
+    #[default]
+    #[display("manual")]
+    Manual,
+}
+
+impl Resolution {
+    /// The `from_name` method returns the strategy named `name`, one of "keep-newest",
+    /// "keep-lowest-oid" or "manual".
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace('_', "-").as_str() {
+            "keep-newest" => Some(Self::KeepNewest),
+            "keep-lowest-oid" => Some(Self::KeepLowestOid),
+            "manual" => Some(Self::Manual),
+            _ => None,
+        }
+    }
+}
+
+/// The `Disposition` enum records the action proposed for a member of a group of duplicates.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Deserialize,
+    Serialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Disposition {
+    /// The record is kept.
+    #[display("keep")]
+    Keep,
+    /// The record duplicates a kept record, and can be deleted.
+    #[display("drop")]
+    Drop,
+    /// The record needs review before deciding.
+    #[default]
+    #[display("review")]
+    Review,
+}
+
+/// The `DispositionRecord` struct proposes an action for a member of a group of duplicates.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct DispositionRecord {
+    /// The `group` field holds an ID shared by the members of the group.
+    pub group: usize,
+    /// The `disposition` field holds the action proposed for the record.
+    pub disposition: Disposition,
+    /// The `label` field holds the address label as recorded.
+    pub label: String,
+    /// The `object_id` field holds the ESRI Object ID of the feature, if known.
+    pub object_id: Option<i64>,
+    /// The `global_id` field holds the ESRI Global ID of the feature, if known.
+    pub global_id: Option<String>,
+    /// The `last_edited` field holds the date-time stamp of the last edit to the feature, as
+    /// recorded.
+    pub last_edited: Option<String>,
+    /// The `status` field holds the status of the address.
+    pub status: AddressStatus,
+    /// The `note` field explains why a group needs review.
+    pub note: Option<String>,
+}
+
+/// The `DispositionRecords` struct holds a vector of type [`DispositionRecord`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct DispositionRecords(Vec<DispositionRecord>);
+
+impl DispositionRecords {
+    /// The `resolve` method groups the addresses in `addresses` sharing a label, or that are near
+    /// duplicates when `near` is true, and proposes which member of each group to keep using the
+    /// strategy `resolution`.  The kept record is marked [`Disposition::Keep`] and the others
+    /// [`Disposition::Drop`].  Groups where the strategy cannot pick a single record, because
+    /// the deciding value is missing or tied, are marked [`Disposition::Review`] with a note.
+    pub fn resolve<T: Address + Edited>(
+        addresses: &[T],
+        near: bool,
+        resolution: Resolution,
+    ) -> Self {
+        let groups = if near {
+            let normals = addresses.iter().map(Normal::new).collect::<Vec<Normal>>();
+            near_groups(&normals)
+        } else {
+            let mut order = Vec::new();
+            let mut labels: HashMap<String, Vec<usize>> = HashMap::new();
+            for (i, address) in addresses.iter().enumerate() {
+                let members = labels.entry(address.label()).or_default();
+                if members.is_empty() {
+                    order.push(address.label());
+                }
+                members.push(i);
+            }
+            order
+                .iter()
+                .filter_map(|label| labels.remove(label))
+                .filter(|m| m.len() > 1)
+                .collect()
+        };
+        let mut records = Vec::new();
+        for (group, members) in groups.iter().enumerate() {
+            let (keep, note) = choose(addresses, members, resolution);
+            for i in members {
+                let address = &addresses[*i];
+                let disposition = match keep {
+                    Some(k) if k == *i => Disposition::Keep,
+                    Some(_) => Disposition::Drop,
+                    None => Disposition::Review,
+                };
+                records.push(DispositionRecord {
+                    group,
+                    disposition,
+                    label: address.label(),
+                    object_id: address.object_id(),
+                    global_id: address.global_id(),
+                    last_edited: address.last_edited(),
+                    status: *address.status(),
+                    note: note.clone(),
+                });
+            }
+        }
+        let records = Self(records);
+        for disposition in [Disposition::Keep, Disposition::Drop, Disposition::Review] {
+            info!("{disposition}: {} records.", records.count(disposition));
+        }
+        records
+    }
+
+    /// The `count` method returns the number of records with the disposition `disposition`.
+    pub fn count(&self, disposition: Disposition) -> usize {
+        self.iter().filter(|r| r.disposition == disposition).count()
+    }
+
+    /// The `filter` method returns the subset of records with the disposition named by
+    /// `filter`, either "keep", "drop" or "review".  Filtering by "drop" yields the list of
+    /// records to delete.
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "keep" => self.retain(|r| r.disposition == Disposition::Keep),
+            "drop" => self.retain(|r| r.disposition == Disposition::Drop),
+            "review" => self.retain(|r| r.disposition == Disposition::Review),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+// Returns the index of the member of `members` to keep under `resolution`, or `None` with the
+// reason if the group needs review.
+fn choose<T: Edited>(
+    addresses: &[T],
+    members: &[usize],
+    resolution: Resolution,
+) -> (Option<usize>, Option<String>) {
+    match resolution {
+        Resolution::KeepNewest => unique_max(
+            members,
+            &members
+                .iter()
+                .map(|i| {
+                    addresses[*i]
+                        .last_edited()
+                        .and_then(|t| parse_timestamp(&t))
+                })
+                .collect::<Vec<Option<NaiveDateTime>>>(),
+            "no edit dates",
+            "tied edit dates",
+        ),
+        Resolution::KeepLowestOid => unique_max(
+            members,
+            &members
+                .iter()
+                .map(|i| addresses[*i].object_id().map(Reverse))
+                .collect::<Vec<Option<Reverse<i64>>>>(),
+            "no object ids",
+            "tied object ids",
+        ),
+        Resolution::Manual => (None, Some("manual review".to_string())),
+    }
+}
+
+// Returns the member of `members` with the unique greatest key in `keys`, treating members
+// without a key as least, or `None` with the note `missing` or `tied` if there is none.
+fn unique_max<K: Ord>(
+    members: &[usize],
+    keys: &[Option<K>],
+    missing: &str,
+    tied: &str,
+) -> (Option<usize>, Option<String>) {
+    match keys.iter().flatten().max() {
+        None => (None, Some(missing.to_string())),
+        Some(best) if keys.iter().flatten().filter(|k| *k == best).count() > 1 => {
+            (None, Some(tied.to_string()))
+        }
+        Some(best) => (
+            keys.iter()
+                .position(|k| k.as_ref() == Some(best))
+                .map(|p| members[p]),
+            None,
+        ),
+    }
+}
+
+// Returns the root of the set containing `i`, compressing the path along the way.
+fn root(parents: &mut [usize], i: usize) -> usize {
+    let mut r = i;
+    while parents[r] != r {
+        r = parents[r];
+    }
+    let mut i = i;
+    while parents[i] != r {
+        let next = parents[i];
+        parents[i] = r;
+        i = next;
+    }
+    r
+}
+
+impl IntoCsv<DispositionRecords> for DispositionRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for DispositionRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GrantsPassSpatialAddresses, IntoBin, SpatialAddresses, StreetNamePostType,
+        StreetNamePreDirectional,
+    };
+    use tracing::info;
+
+    #[test]
+    fn near_duplicates() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let records = DuplicateRecords::near(&city_addresses);
+        info!("Near duplicates in city addresses: {}", records.len());
+
+        let base =
+            |directional: Option<StreetNamePreDirectional>, name: &str, unit: Option<&str>| {
+                let mut address = city_addresses[0].clone();
+                *address.number_mut() = 100;
+                *address.number_suffix_mut() = None;
+                *address.directional_mut() = directional;
+                *address.street_name_mut() = name.to_string();
+                *address.street_type_mut() = Some(StreetNamePostType::STREET);
+                *address.subaddress_id_mut() = unit.map(|u| u.to_string());
+                address
+            };
+        let addresses = vec![
+            base(Some(StreetNamePreDirectional::NORTH), "MAIN", Some("3")),
+            base(None, "N  Main", Some("03")),
+            base(Some(StreetNamePreDirectional::NORTH), "MAINE", Some("3")),
+            base(Some(StreetNamePreDirectional::NORTH), "MAIN", Some("4")),
+            base(Some(StreetNamePreDirectional::NORTH), "K", None),
+            base(Some(StreetNamePreDirectional::NORTH), "J", None),
+        ];
+        let records = DuplicateRecords::near(&addresses);
+        assert_eq!(records.len(), 3);
+        assert!(records.iter().all(|r| r.group == 0));
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_duplicates() -> anyhow::Result<()> {
+        assert_eq!(
+            Resolution::from_name("keep-newest"),
+            Some(Resolution::KeepNewest)
+        );
+        assert_eq!(
+            Resolution::from_name("keep_lowest_oid"),
+            Some(Resolution::KeepLowestOid)
+        );
+        assert_eq!(Resolution::from_name("keep-oldest"), None);
+
+        let addresses = GrantsPassSpatialAddresses::from_csv("data/city_addresses_20241007.csv")?;
+        let records = DispositionRecords::resolve(&addresses, false, Resolution::KeepNewest);
+        assert!(!records.is_empty());
+        // Each group keeps exactly one record, or leaves every member for review.
+        let groups = records.iter().map(|r| r.group).max().unwrap_or_default() + 1;
+        for group in 0..groups {
+            let members = records
+                .iter()
+                .filter(|r| r.group == group)
+                .collect::<Vec<&DispositionRecord>>();
+            assert!(members.len() > 1);
+            assert!(members.iter().all(|r| r.label == members[0].label));
+            let kept = members
+                .iter()
+                .filter(|r| r.disposition == Disposition::Keep)
+                .count();
+            let review = members
+                .iter()
+                .filter(|r| r.disposition == Disposition::Review)
+                .count();
+            assert!(kept == 1 || review == members.len());
+        }
+        // Most duplicates in the sample were loaded in bulk, with tied edit dates.
+        assert_eq!(records.count(Disposition::Keep), 1);
+        assert_eq!(records.count(Disposition::Drop), 1);
+        assert!(
+            records
+                .iter()
+                .any(|r| r.note.as_deref() == Some("tied edit dates"))
+        );
+
+        // The city export carries no Object IDs.
+        let records = DispositionRecords::resolve(&addresses, false, Resolution::KeepLowestOid);
+        assert_eq!(records.count(Disposition::Review), records.len());
+        assert_eq!(records[0].note.as_deref(), Some("no object ids"));
+        let drops = records.filter("drop");
+        assert!(drops.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+//! The `enrich` module tags address points with the attributes of the administrative boundaries
+//! containing them, such as city limits, fire districts or school districts, replacing a spatial
+//! join in a desktop GIS.
+use crate::{
+    AddressErrorKind, Boundary, Builder, Cartesian, Csv, Geographic, IntoCsv, IntoJson, Io,
+    SpatialAddress, SpatialAddressRaw, open_csv, to_json, to_ndjson, write_atomic,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// The `ZoneLayer` struct holds a layer of polygons, each tagged with the value of an attribute,
+/// such as the name of a fire district.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneLayer {
+    /// The `name` field holds the name of the layer, used as the column name of the output.
+    pub name: String,
+    /// The `zones` field holds the attribute value and boundary of each polygon in the layer.
+    pub zones: Vec<(String, Boundary)>,
+}
+
+impl ZoneLayer {
+    /// The `from_path` method reads the layer named `name` from the GeoJSON file or polygon
+    /// shapefile at `path`.  Each polygon takes the value of its attribute named `field`.  When
+    /// `field` is `None`, the polygons are read as a single boundary with the value "true", for
+    /// layers such as city limits where only containment matters.
+    pub fn from_path<P: AsRef<Path>>(
+        name: &str,
+        path: P,
+        field: Option<&str>,
+    ) -> Result<Self, AddressErrorKind> {
+        let zones = match field {
+            Some(field) => Boundary::from_features(path, field)?,
+            None => vec![("true".to_string(), Boundary::from_path(path)?)],
+        };
+        Ok(Self {
+            name: name.to_string(),
+            zones,
+        })
+    }
+
+    /// The `parse` method reads a layer from a specification of the form `name=path` or
+    /// `name:field=path`, such as "fire_district:DISTRICT=data/fire.shp".
+    pub fn parse(spec: &str) -> Result<Self, AddressErrorKind> {
+        let Some((name, path)) = spec.split_once('=') else {
+            return Err(Builder::new(
+                format!("expected 'name:field=path' in '{spec}'"),
+                "ZoneLayer".to_string(),
+                line!(),
+                file!().to_string(),
+            )
+            .into());
+        };
+        let (name, field) = match name.split_once(':') {
+            Some((name, field)) => (name.trim(), Some(field.trim())),
+            None => (name.trim(), None),
+        };
+        Self::from_path(name, PathBuf::from(path.trim()), field)
+    }
+
+    /// The `zone` method returns the attribute value of the first polygon in the layer containing
+    /// `address`, if any.
+    pub fn zone<T: Cartesian + Geographic>(&self, address: &T) -> Option<&str> {
+        self.zones
+            .iter()
+            .find(|(_, boundary)| boundary.contains(address))
+            .map(|(value, _)| value.as_str())
+    }
+}
+
+/// The `EnrichedAddress` struct holds an address with the attribute values of the polygons
+/// containing it.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnrichedAddress {
+    /// The `address` field holds the address.
+    #[serde(flatten)]
+    pub address: SpatialAddressRaw,
+    /// The `zones` field maps the name of each layer to the value of the polygon containing the
+    /// address, or `None` if the address falls outside of the layer.
+    #[serde(flatten)]
+    pub zones: BTreeMap<String, Option<String>>,
+}
+
+/// The `EnrichedAddresses` struct holds a vector of type [`EnrichedAddress`].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Deref, DerefMut)]
+pub struct EnrichedAddresses(Vec<EnrichedAddress>);
+
+impl EnrichedAddresses {
+    /// The `new` method tags each address in `addresses` with the value of the polygon
+    /// containing it in each layer of `layers`.
+    pub fn new(addresses: &[SpatialAddress], layers: &[ZoneLayer]) -> Self {
+        let records = addresses
+            .iter()
+            .map(|address| EnrichedAddress {
+                address: SpatialAddressRaw::from(address.clone()),
+                zones: layers
+                    .iter()
+                    .map(|layer| (layer.name.clone(), layer.zone(address).map(str::to_string)))
+                    .collect(),
+            })
+            .collect::<Vec<EnrichedAddress>>();
+        for layer in layers {
+            let inside = records
+                .iter()
+                .filter(|r| r.zones.get(&layer.name).is_some_and(Option::is_some))
+                .count();
+            info!(
+                "{} of {} addresses inside {}.",
+                inside,
+                records.len(),
+                layer.name
+            );
+        }
+        Self(records)
+    }
+
+    /// The `outside` method returns the number of addresses outside of every polygon in the
+    /// layer named `layer`.
+    pub fn outside(&self, layer: &str) -> usize {
+        self.iter()
+            .filter(|r| r.zones.get(layer).is_none_or(Option::is_none))
+            .count()
+    }
+
+    // Returns the names of the layers held by the records, in the order of the columns.
+    fn layers(&self) -> Vec<String> {
+        let mut layers = self
+            .iter()
+            .flat_map(|r| r.zones.keys().cloned())
+            .collect::<Vec<String>>();
+        layers.sort();
+        layers.dedup();
+        layers
+    }
+}
+
+// An address paired with the name and value of each extra column of its row.
+pub(crate) type ColumnRow = (SpatialAddressRaw, Vec<(String, String)>);
+
+// Reads the address fields of each row of the csv file at `path` as a [`SpatialAddressRaw`],
+// paired with the name and value of every other column.  Rows that fail to deserialize are
+// dropped, consistent with [`from_csv`](crate::from_csv).
+pub(crate) fn read_columns(path: &Path) -> Result<Vec<ColumnRow>, Io> {
+    let io = |source: std::io::Error| Io::new(path.into(), source, line!(), file!().into());
+    let mut rdr = csv::Reader::from_reader(open_csv(path)?);
+    let headers = rdr.headers().map_err(|source| io(source.into()))?.clone();
+    let fields = crate::shapefile::field_names::<SpatialAddressRaw>();
+    let mut records = Vec::new();
+    for row in rdr.records() {
+        let row = row.map_err(|source| io(source.into()))?;
+        let Ok(address) = row.deserialize::<SpatialAddressRaw>(Some(&headers)) else {
+            continue;
+        };
+        let columns = headers
+            .iter()
+            .zip(row.iter())
+            .filter(|(header, _)| !fields.contains(header))
+            .map(|(header, value)| (header.to_string(), value.to_string()))
+            .collect();
+        records.push((address, columns));
+    }
+    Ok(records)
+}
+
+// Writes the address fields of each record in `records` to the csv file at `path`, followed by
+// the values of the extra `columns` of the record.
+pub(crate) fn write_columns(
+    path: &Path,
+    records: &[(&SpatialAddressRaw, Vec<String>)],
+    columns: &[String],
+) -> Result<(), AddressErrorKind> {
+    let csv_error = |source| Csv::new(path.into(), source, line!(), file!().into());
+    // The address fields are serialized to an in-memory csv file, so they follow the same
+    // serialization rules as other address exports, then extended with the extra columns.
+    let mut buffer = csv::Writer::from_writer(Vec::new());
+    for (address, _) in records {
+        buffer.serialize(address).map_err(csv_error)?;
+    }
+    let buffer = buffer
+        .into_inner()
+        .map_err(|source| Io::new(path.into(), source.into_error(), line!(), file!().into()))?;
+    let mut rdr = csv::Reader::from_reader(&buffer[..]);
+    write_atomic(path, |partial| -> Result<(), AddressErrorKind> {
+        let mut wtr = csv::Writer::from_path(partial).map_err(csv_error)?;
+        if !records.is_empty() {
+            let mut headers = rdr.headers().map_err(csv_error)?.clone();
+            columns.iter().for_each(|column| headers.push_field(column));
+            wtr.write_record(&headers).map_err(csv_error)?;
+        }
+        for (row, (_, values)) in rdr.records().zip(records.iter()) {
+            let mut row = row.map_err(csv_error)?;
+            values.iter().for_each(|value| row.push_field(value));
+            wtr.write_record(&row).map_err(csv_error)?;
+        }
+        wtr.flush()
+            .map_err(|source| Io::new(partial.into(), source, line!(), file!().into()))?;
+        Ok(())
+    })
+}
+
+impl IntoCsv<EnrichedAddresses> for EnrichedAddresses {
+    /// Reads the address fields of each row as a [`SpatialAddressRaw`], and every other column as
+    /// a layer.  Rows that fail to deserialize are dropped, consistent with
+    /// [`from_csv`](crate::from_csv).
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = read_columns(path.as_ref())?
+            .into_iter()
+            .map(|(address, columns)| EnrichedAddress {
+                address,
+                zones: columns
+                    .into_iter()
+                    .map(|(layer, value)| (layer, (!value.is_empty()).then_some(value)))
+                    .collect(),
+            })
+            .collect();
+        Ok(Self(records))
+    }
+
+    /// Writes the address fields of each record, followed by a column for each layer.
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        let layers = self.layers();
+        let records = self
+            .iter()
+            .map(|record| {
+                let values = layers
+                    .iter()
+                    .map(|layer| {
+                        record
+                            .zones
+                            .get(layer)
+                            .cloned()
+                            .flatten()
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                (&record.address, values)
+            })
+            .collect::<Vec<_>>();
+        write_columns(path.as_ref(), &records, &layers)
+    }
+}
+
+impl IntoJson for EnrichedAddresses {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JacksonCountySpatialAddresses, SpatialAddresses};
+
+    #[test]
+    fn enrich_layers() -> anyhow::Result<()> {
+        let addresses = JacksonCountySpatialAddresses::from_csv("data/jackson_county_sample.csv")?;
+        let addresses = SpatialAddresses::from(&addresses[..]);
+        let layers = vec![
+            ZoneLayer::parse("service_area=data/boundary_sample.geojson")?,
+            ZoneLayer::parse("city:name=data/boundary_sample.shp")?,
+        ];
+        assert_eq!(layers[1].zones[0].0, "MEDFORD");
+        let mut records = EnrichedAddresses::new(&addresses, &layers);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].zones["service_area"].as_deref(), Some("true"));
+        assert_eq!(records[1].zones["service_area"], None);
+        assert_eq!(records.outside("service_area"), 1);
+        assert_eq!(records.outside("city"), 2);
+        assert!(ZoneLayer::parse("city:missing=data/boundary_sample.shp").is_err());
+
+        let path = std::env::temp_dir().join("destination_enriched.csv");
+        records.to_csv(&path)?;
+        let read = EnrichedAddresses::from_csv(&path)?;
+        assert_eq!(read, records);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
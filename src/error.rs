@@ -21,26 +21,71 @@ macro_rules! impl_address_error {
     };
 }
 
-impl_address_error!(Bincode, Io, Nom);
+impl_address_error!(
+    Arguments,
+    Bincode,
+    Builder,
+    Config,
+    Database,
+    Gdal,
+    Io,
+    Json,
+    Nom,
+    Parquet,
+    Projection,
+    Service,
+    Shapefile,
+    Spreadsheet
+);
 
 /// The `AddressErrorKind` enum contains the individual error type associated with the library operation.
 #[derive(Debug, derive_more::From, derive_more::Display, derive_more::Error)]
 pub enum AddressErrorKind {
+    /// The `Arguments` variant contains an [`Arguments`] error.
+    #[from(Arguments)]
+    Arguments(Arguments),
     /// The `Bincode` variant contains a [`Bincode`] error.
     #[from(Bincode)]
     Bincode(Bincode),
     /// The `Builder` variant contains a [`Builder`] error.
     #[from(Builder)]
-    Builder,
+    Builder(Builder),
+    /// The `Config` variant contains a [`Config`] error.
+    #[from(Config)]
+    Config(Config),
     /// The `Csv` variant contains a [`Csv`] error.
     #[from(Csv)]
     Csv(Csv),
+    /// The `Database` variant contains a [`Database`] error.
+    #[from(Database)]
+    Database(Database),
+    /// The `Gdal` variant contains a [`Gdal`] error.
+    #[from(Gdal)]
+    Gdal(Gdal),
     /// The `Io` variant contains an [`Io`] error.
     #[from(Io)]
     Io(Io),
+    /// The `Json` variant contains a [`Json`] error.
+    #[from(Json)]
+    Json(Json),
     /// The `Nom` variant contains an [`Nom`] error.
     #[from(Nom)]
     Nom(Nom),
+    /// The `Parquet` variant contains a [`Parquet`] error.
+    #[from(Parquet)]
+    Parquet(Parquet),
+    /// The `Projection` variant contains a [`Projection`] error.
+    #[from(Projection)]
+    Projection(Projection),
+    /// The `Service` variant contains a [`Service`] error.
+    #[from(Service)]
+    Service(Service),
+    /// The `Shapefile` variant contains a [`Shapefile`] error.
+    #[from(Shapefile)]
+    Shapefile(Shapefile),
+    /// The `Spreadsheet` variant contains a [`Spreadsheet`] error.
+    #[from(Spreadsheet)]
+    Spreadsheet(Spreadsheet),
 }
 
 /// The `Io` struct contains error information associated with input/output calls.
@@ -63,6 +108,26 @@ pub struct Csv {
     file: String,
 }
 
+/// The `Json` struct contains error information associated with the `serde_json` crate.
+#[derive(Debug, derive_more::Display, derive_more::Error, derive_new::new)]
+#[display("json error at path {path:?} in line {line} of {file}")]
+pub struct Json {
+    path: std::path::PathBuf,
+    source: serde_json::Error,
+    line: u32,
+    file: String,
+}
+
+/// The `Parquet` struct contains error information associated with the `parquet` crate.
+#[derive(Debug, derive_more::Display, derive_more::Error, derive_new::new)]
+#[display("parquet error at path {path:?} in line {line} of {file}")]
+pub struct Parquet {
+    path: std::path::PathBuf,
+    source: parquet::errors::ParquetError,
+    line: u32,
+    file: String,
+}
+
 /// The `Bincode` struct contains error information associated with the `bincode` crate.
 #[derive(Debug, derive_more::Display, derive_more::Error, derive_new::new)]
 #[display("bincode error: {source:?} at line {line} in {file}")]
@@ -88,6 +153,137 @@ impl std::error::Error for Builder {
     }
 }
 
+/// The `Shapefile` struct contains error information about a malformed or unsupported shapefile.
+#[derive(Debug, derive_more::Display, derive_new::new)]
+#[display("shapefile error at path {path:?}: {issue} in line {line} of {file}")]
+pub struct Shapefile {
+    path: std::path::PathBuf,
+    issue: String,
+    line: u32,
+    file: String,
+}
+
+impl std::error::Error for Shapefile {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+/// The `Spreadsheet` struct contains error information about an Excel workbook that could not be
+/// read, or that lacks a requested sheet or column.
+#[derive(Debug, derive_more::Display, derive_new::new)]
+#[display("spreadsheet error at path {path:?}: {issue} in line {line} of {file}")]
+pub struct Spreadsheet {
+    path: std::path::PathBuf,
+    issue: String,
+    line: u32,
+    file: String,
+}
+
+impl std::error::Error for Spreadsheet {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+/// The `Gdal` struct contains error information about a dataset that GDAL failed to read.
+#[derive(Debug, derive_more::Display, derive_new::new)]
+#[display("gdal error at path {path:?}: {issue} in line {line} of {file}")]
+pub struct Gdal {
+    path: std::path::PathBuf,
+    issue: String,
+    line: u32,
+    file: String,
+}
+
+impl std::error::Error for Gdal {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+/// The `Database` struct contains error information about a failed database operation.
+#[derive(Debug, derive_more::Display, derive_new::new)]
+#[display("database error for table {table}: {issue} in line {line} of {file}")]
+pub struct Database {
+    table: String,
+    issue: String,
+    line: u32,
+    file: String,
+}
+
+impl std::error::Error for Database {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+/// The `Projection` struct contains error information about a failed coordinate transformation.
+#[derive(Debug, derive_more::Display, derive_new::new)]
+#[display("projection error for EPSG:{epsg}: {issue} in line {line} of {file}")]
+pub struct Projection {
+    epsg: u32,
+    issue: String,
+    line: u32,
+    file: String,
+}
+
+impl std::error::Error for Projection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+/// The `Service` struct contains error information about a failed request to a web service.
+#[derive(Debug, derive_more::Display, derive_new::new)]
+#[display("service error at {url}: {issue} in line {line} of {file}")]
+pub struct Service {
+    url: String,
+    issue: String,
+    line: u32,
+    file: String,
+}
+
+impl std::error::Error for Service {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+/// The `Arguments` struct contains error information about malformed command-line arguments,
+/// including requests for help or version information.
+#[derive(Debug, derive_more::Display, derive_more::Error, derive_new::new)]
+#[display("argument error in line {line} of {file}")]
+pub struct Arguments {
+    source: clap::Error,
+    line: u32,
+    file: String,
+}
+
+impl Arguments {
+    /// The `exit` method prints the message of the error, or the requested help or version
+    /// information, and exits the process with the code clap assigns to the error.
+    pub fn exit(&self) -> ! {
+        self.source.exit()
+    }
+}
+
+/// The `Config` struct contains error information about a malformed configuration file.
+#[derive(Debug, derive_more::Display, derive_new::new)]
+#[display("config error at path {path:?}: {issue} in line {line} of {file}")]
+pub struct Config {
+    path: std::path::PathBuf,
+    issue: String,
+    line: u32,
+    file: String,
+}
+
+impl std::error::Error for Config {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
 /// The `Nom` struct contains error information associated with the `nom` crate.
 #[derive(Debug, derive_more::Display, derive_more::Error)]
 #[display("nom parsing error: {description} in line {line} of {file}")]
@@ -0,0 +1,219 @@
+//! The `esn` module checks address points against Emergency Service Number (ESN) polygons,
+//! flagging addresses whose recorded ESN disagrees with the polygon containing them, and addresses
+//! close enough to the edge of another ESN that a dispatcher could route the call wrong.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, Cartesian, Geographic, IntoCsv, IntoJson, Io,
+    ZoneLayer, crs::geolocated, from_csv, progress_bar, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use tracing::info;
+
+/// The `ESN_BUFFER` constant holds the default distance from the edge of another ESN within which
+/// an address needs manual verification, in the units of the projected coordinates of the ESN
+/// polygons, or in meters for geographic polygons.
+pub const ESN_BUFFER: f64 = 30.0;
+
+/// The `EsnConflictKind` enum describes the reason an address needs review, in order of
+/// priority.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+    strum::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EsnConflictKind {
+    /// The ESN recorded for the address differs from the ESN of the polygon containing it.
+    #[default]
+    #[display("mismatch")]
+    Mismatch,
+    /// The address falls outside of every ESN polygon.
+    #[display("outside")]
+    Outside,
+    /// The address lies within the buffer distance of a polygon with another ESN.
+    #[display("boundary")]
+    Boundary,
+}
+
+geolocated! {
+    /// The `EsnConflict` struct reports an address needing review against the ESN polygons.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct EsnConflict {
+        /// The `rank` field holds the position of the address in the review list, starting at one.
+        pub rank: usize,
+        /// The `kind` field holds the reason the address needs review.
+        pub kind: EsnConflictKind,
+        /// The `label` field holds the address label.
+        pub label: String,
+        /// The `recorded` field holds the ESN recorded for the address, if any.
+        pub recorded: Option<String>,
+        /// The `polygon` field holds the ESN of the polygon containing the address, if any.
+        pub polygon: Option<String>,
+        /// The `nearest` field holds the ESN of the nearest polygon with a different ESN than the
+        /// polygon containing the address.
+        pub nearest: Option<String>,
+        /// The `distance` field holds the distance to the polygon of the `nearest` ESN.
+        pub distance: Option<f64>,
+        /// The `x` field holds the projected X coordinate of the address.
+        pub x: f64,
+        /// The `y` field holds the projected Y coordinate of the address.
+        pub y: f64,
+    }
+}
+
+/// The `EsnConflicts` struct holds a vector of type [`EsnConflict`], ranked for review.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct EsnConflicts(Vec<EsnConflict>);
+
+impl EsnConflicts {
+    /// The `detect` method checks each address in `addresses`, paired with its recorded ESN, if
+    /// any, against the ESN polygons of `layer`.  Addresses with a recorded ESN differing from the
+    /// polygon containing them, addresses outside of every polygon, and addresses within `buffer`
+    /// of a polygon with another ESN are reported.  The list is ranked by the kind of conflict,
+    /// then by distance to the nearest other ESN, closest first.  Retired addresses are skipped.
+    pub fn detect<T: Address + Cartesian + Geographic + Sync>(
+        addresses: &[(T, Option<String>)],
+        layer: &ZoneLayer,
+        buffer: f64,
+    ) -> Self {
+        let mut records = addresses
+            .par_iter()
+            .progress_with(progress_bar(addresses.len(), "Checking ESN boundaries."))
+            .filter(|(address, _)| address.status() != &AddressStatus::Retired)
+            .filter_map(|(address, recorded)| {
+                let recorded = recorded
+                    .as_ref()
+                    .map(|r| r.trim().to_string())
+                    .filter(|r| !r.is_empty());
+                let polygon = layer.zone(address).map(str::to_string);
+                let nearest = layer
+                    .zones
+                    .iter()
+                    .filter(|(value, _)| Some(value) != polygon.as_ref())
+                    .map(|(value, boundary)| (value, boundary.edge_distance(address)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .filter(|(_, distance)| distance.is_finite());
+                let kind = match (&recorded, &polygon) {
+                    (_, None) => EsnConflictKind::Outside,
+                    (Some(recorded), Some(polygon)) if !recorded.eq_ignore_ascii_case(polygon) => {
+                        EsnConflictKind::Mismatch
+                    }
+                    _ if nearest.is_some_and(|(_, distance)| distance < buffer) => {
+                        EsnConflictKind::Boundary
+                    }
+                    _ => return None,
+                };
+                Some(EsnConflict {
+                    rank: 0,
+                    kind,
+                    label: address.label(),
+                    recorded,
+                    polygon,
+                    nearest: nearest.map(|(value, _)| value.clone()),
+                    distance: nearest.map(|(_, distance)| distance),
+                    x: address.x(),
+                    y: address.y(),
+                    lat: None,
+                    lon: None,
+                })
+            })
+            .collect::<Vec<EsnConflict>>();
+        records.sort_by(|a, b| {
+            a.kind.cmp(&b.kind).then(
+                a.distance
+                    .unwrap_or(f64::INFINITY)
+                    .total_cmp(&b.distance.unwrap_or(f64::INFINITY)),
+            )
+        });
+        for (i, record) in records.iter_mut().enumerate() {
+            record.rank = i + 1;
+        }
+        info!("{} addresses need ESN review.", records.len());
+        Self(records)
+    }
+
+    /// The `filter` method returns the subset of records with the conflict kind named `filter`,
+    /// such as "mismatch".
+    pub fn filter(mut self, filter: &str) -> Self {
+        if EsnConflictKind::iter().any(|kind| kind.to_string() == filter) {
+            self.retain(|r| r.kind.to_string() == filter);
+        } else {
+            info!("Invalid filter provided.");
+        }
+        self
+    }
+}
+
+impl IntoCsv<EsnConflicts> for EsnConflicts {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for EsnConflicts {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MSAG_ESN_FIELD, SpatialAddress, address::riverside};
+
+    #[test]
+    fn esn_conflicts() -> anyhow::Result<()> {
+        let esn = ZoneLayer::from_path("esn", "data/esn_sample.geojson", Some(MSAG_ESN_FIELD))?;
+        let address = |number: i64, longitude: f64, recorded: Option<&str>| {
+            let address = SpatialAddress {
+                address: riverside(number),
+                latitude: 42.44,
+                longitude,
+                ..Default::default()
+            };
+            (address, recorded.map(str::to_string))
+        };
+        let addresses = vec![
+            address(100, -123.36, Some("100")),
+            address(110, -123.3301, Some("100")),
+            address(120, -123.36, Some("200")),
+            address(130, -123.20, None),
+            address(140, -123.3302, None),
+        ];
+        let conflicts = EsnConflicts::detect(&addresses, &esn, ESN_BUFFER);
+        assert_eq!(conflicts.len(), 4);
+        assert_eq!(conflicts[0].kind, EsnConflictKind::Mismatch);
+        assert_eq!(conflicts[0].label, "120 RIVERSIDE AVE");
+        assert_eq!(conflicts[1].kind, EsnConflictKind::Outside);
+        assert_eq!(conflicts[1].nearest.as_deref(), Some("200"));
+        // About 8 meters from ESN 200, then about 16 meters.
+        assert_eq!(conflicts[2].label, "110 RIVERSIDE AVE");
+        assert_eq!(conflicts[3].rank, 4);
+        assert!(conflicts[2].distance.unwrap() < conflicts[3].distance.unwrap());
+        assert!((conflicts[2].distance.unwrap() - 8.2).abs() < 0.5);
+        assert_eq!(conflicts.filter("boundary").len(), 2);
+        Ok(())
+    }
+}
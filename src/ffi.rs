@@ -0,0 +1,185 @@
+//! The `ffi` module exposes address parsing, standardization and the comparison of a pair of
+//! addresses through a C ABI, built with the `ffi` feature, so applications such as the ArcGIS
+//! Pro add-in can call the library in process rather than running the command line for each
+//! edit.  Build the shared library with
+//! `cargo rustc --release --lib --features ffi --crate-type cdylib`, and declare the functions
+//! from `include/where.h`.
+//!
+//! Results are returned as JSON strings with the field names of the serialized types, allocated
+//! by the library and released with [`where_free_string`].  A function that fails returns a null
+//! pointer, and [`where_last_error`] describes the failure on the calling thread.
+use crate::{CommonAddress, MatchScoring, Standardizer};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{UnwindSafe, catch_unwind};
+use std::ptr::null_mut;
+
+thread_local! {
+    // The message of the most recent failure on this thread.
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Allocates `text` as a C string for the caller, or returns a null pointer if `text` holds a
+// null byte.
+fn to_c(text: String) -> *mut c_char {
+    CString::new(text)
+        .map(CString::into_raw)
+        .unwrap_or(null_mut())
+}
+
+// Reads the C string at `input` as UTF-8.
+//
+// # Safety
+//
+// `input` must be null or point to a null-terminated string valid for the duration of the call.
+unsafe fn read(input: *const c_char, name: &str) -> Result<String, String> {
+    if input.is_null() {
+        return Err(format!("{name} is null"));
+    }
+    // SAFETY: The caller guarantees `input` points to a null-terminated string.
+    let input = unsafe { CStr::from_ptr(input) };
+    input
+        .to_str()
+        .map(str::to_string)
+        .map_err(|e| format!("{name} is not valid UTF-8: {e}"))
+}
+
+// Runs `f`, returning its value as a JSON string, or recording the failure and returning a null
+// pointer.  A panic is recorded as a failure rather than unwinding into the caller.
+fn respond<T: Serialize, F: FnOnce() -> Result<T, String> + UnwindSafe>(f: F) -> *mut c_char {
+    let result = catch_unwind(f)
+        .unwrap_or_else(|_| Err("internal error".to_string()))
+        .and_then(|value| serde_json::to_string(&value).map_err(|e| e.to_string()));
+    match result {
+        Ok(json) => {
+            LAST_ERROR.with(|error| error.borrow_mut().take());
+            to_c(json)
+        }
+        Err(message) => {
+            LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+            null_mut()
+        }
+    }
+}
+
+/// The `where_parse_address` function parses the one-line address `input` into its components,
+/// returned as a JSON object.  Returns a null pointer if the address fails to parse.
+///
+/// # Safety
+///
+/// `input` must be null or point to a null-terminated string.  The result must be released with
+/// [`where_free_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn where_parse_address(input: *const c_char) -> *mut c_char {
+    // SAFETY: The caller upholds the contract of `read`.
+    let input = unsafe { read(input, "input") };
+    respond(|| CommonAddress::parse(&input?).map_err(|e| e.to_string()))
+}
+
+/// The `where_standardize` function parses the one-line address `input` and applies the
+/// built-in standardization rules, converting Josephine County naming conventions to city naming
+/// conventions.  The standardized address is returned as a JSON object.  Returns a null pointer
+/// if the address fails to parse.
+///
+/// # Safety
+///
+/// `input` must be null or point to a null-terminated string.  The result must be released with
+/// [`where_free_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn where_standardize(input: *const c_char) -> *mut c_char {
+    // SAFETY: The caller upholds the contract of `read`.
+    let input = unsafe { read(input, "input") };
+    respond(|| {
+        let mut address = CommonAddress::parse(&input?).map_err(|e| e.to_string())?;
+        Standardizer::josephine_county().apply(&mut address);
+        Ok(address)
+    })
+}
+
+/// The `where_match_pair` function parses the one-line addresses `address` and `other`, and
+/// scores `other` as a match for `address`, as in [`MatchScoring::compare`].  Street names agree
+/// at a similarity of `threshold` or above, where 1 requires exact agreement.  The
+/// [`AddressMatch`](crate::AddressMatch) is returned as a JSON object.  Returns a null pointer if
+/// either address fails to parse, or if `threshold` falls outside of the range from 0 to 1.
+///
+/// # Safety
+///
+/// `address` and `other` must be null or point to null-terminated strings.  The result must be
+/// released with [`where_free_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn where_match_pair(
+    address: *const c_char,
+    other: *const c_char,
+    threshold: f64,
+) -> *mut c_char {
+    // SAFETY: The caller upholds the contract of `read`.
+    let address = unsafe { read(address, "address") };
+    // SAFETY: The caller upholds the contract of `read`.
+    let other = unsafe { read(other, "other") };
+    respond(|| {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(format!("threshold {threshold} must be between 0 and 1"));
+        }
+        let address = CommonAddress::parse(&address?).map_err(|e| e.to_string())?;
+        let other = CommonAddress::parse(&other?).map_err(|e| e.to_string())?;
+        Ok(MatchScoring::new(threshold).compare(&address, &other))
+    })
+}
+
+/// The `where_last_error` function returns the message of the most recent failure on the calling
+/// thread, or a null pointer if the most recent call succeeded.
+///
+/// # Safety
+///
+/// The result must be released with [`where_free_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn where_last_error() -> *mut c_char {
+    LAST_ERROR.with(|error| error.borrow().clone().map(to_c).unwrap_or(null_mut()))
+}
+
+/// The `where_free_string` function releases a string returned by the library.  Passing a null
+/// pointer does nothing.
+///
+/// # Safety
+///
+/// `text` must be null or a pointer returned by a function of this library, not released before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn where_free_string(text: *mut c_char) {
+    if !text.is_null() {
+        // SAFETY: The caller guarantees `text` came from `CString::into_raw` in this library.
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffi_round_trip() -> anyhow::Result<()> {
+        use std::ffi::{CStr, CString};
+
+        let take = |text: *mut std::ffi::c_char| {
+            assert!(!text.is_null());
+            let value = unsafe { CStr::from_ptr(text) }.to_str().map(str::to_string);
+            unsafe { where_free_string(text) };
+            value
+        };
+        let input = CString::new("1450 NE 7th St Apt B, Grants Pass, OR 97526")?;
+        let parsed: serde_json::Value =
+            serde_json::from_str(&take(unsafe { where_parse_address(input.as_ptr()) })?)?;
+        assert_eq!(parsed["number"], 1450);
+        assert_eq!(parsed["street_name"], "7TH");
+        let other = CString::new("1450 NE 7th St Apt B, Grants Pass, OR 97526")?;
+        let matched: serde_json::Value = serde_json::from_str(&take(unsafe {
+            where_match_pair(input.as_ptr(), other.as_ptr(), 1.0)
+        })?)?;
+        assert_eq!(matched["coincident"], true);
+        // Failures return null, with the reason available from the last error.
+        assert!(unsafe { where_match_pair(input.as_ptr(), other.as_ptr(), 2.0) }.is_null());
+        assert!(take(unsafe { where_last_error() })?.contains("threshold"));
+        assert!(unsafe { where_parse_address(std::ptr::null()) }.is_null());
+        Ok(())
+    }
+}
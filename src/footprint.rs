@@ -0,0 +1,313 @@
+//! The `footprint` module checks address points against a layer of building footprints,
+//! reporting the points that fall outside of every footprint with the offset to the nearest one.
+//! An address point placed on its structure serves dispatch and field crews better than one
+//! dropped in a yard or at the centroid of a parcel, and the snapped coordinates give editors a
+//! candidate location to review.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, Boundary, Cartesian, IntoCsv, IntoJson, Io,
+    SpatialIndex, crs::geolocated, from_csv, progress_bar, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{info, warn};
+
+/// The `FOOTPRINT_DISTANCE` constant holds the default search distance for building footprints
+/// around an address, in the units of the projected coordinates.
+pub const FOOTPRINT_DISTANCE: f64 = 200.0;
+
+/// The `Footprint` struct holds the outline of a building, with an identifier.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Footprint {
+    /// The `id` field holds the identifier of the footprint.
+    pub id: String,
+    /// The `boundary` field holds the polygons of the footprint.
+    pub boundary: Boundary,
+}
+
+impl Footprint {
+    /// The `center` method returns the center of the bounding box of the footprint, or `None`
+    /// for an empty footprint.
+    pub fn center(&self) -> Option<[f64; 2]> {
+        let mut points = self.boundary.rings.iter().flatten();
+        let first = *points.next()?;
+        let [min_x, min_y, max_x, max_y] = points.fold(
+            [first[0], first[1], first[0], first[1]],
+            |[min_x, min_y, max_x, max_y], [x, y]| {
+                [min_x.min(*x), min_y.min(*y), max_x.max(*x), max_y.max(*y)]
+            },
+        );
+        Some([(min_x + max_x) / 2.0, (min_y + max_y) / 2.0])
+    }
+}
+
+/// The `Footprints` struct holds a vector of type [`Footprint`].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Deref, DerefMut)]
+pub struct Footprints(Vec<Footprint>);
+
+impl Footprints {
+    /// The `from_path` method reads the building footprints of the GeoJSON file or polygon
+    /// shapefile at `path`, taking the identifier of each footprint from the attribute named
+    /// `id_field`.  Without `id_field`, or for a footprint with an empty identifier, the
+    /// identifier is the position of the footprint in the file, starting at one.  Footprints
+    /// are expected in the projected coordinates of the address points.
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        id_field: Option<&str>,
+    ) -> Result<Self, AddressErrorKind> {
+        let features = Boundary::from_features(path, id_field.unwrap_or_default())?;
+        if features.iter().any(|(_, boundary)| boundary.geographic) {
+            warn!("Footprints are geographic, expected projected coordinates.");
+        }
+        let footprints = features
+            .into_iter()
+            .enumerate()
+            .map(|(i, (id, boundary))| Footprint {
+                id: match id.trim() {
+                    "" => (i + 1).to_string(),
+                    id => id.to_string(),
+                },
+                boundary,
+            })
+            .collect::<Vec<Footprint>>();
+        info!("Footprints read: {}", footprints.len());
+        Ok(Self(footprints))
+    }
+}
+
+impl From<Vec<Footprint>> for Footprints {
+    fn from(footprints: Vec<Footprint>) -> Self {
+        Self(footprints)
+    }
+}
+
+/// The `FootprintStatus` enum describes how an address point relates to the building footprints
+/// around it.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FootprintStatus {
+    /// The address point falls outside of every footprint, with a footprint within the search
+    /// distance.
+    #[default]
+    #[display("outside")]
+    Outside,
+    /// No footprint lies within the search distance of the address point.
+    #[display("distant")]
+    Distant,
+}
+
+geolocated! {
+    /// The `FootprintRecord` struct reports an address point outside of every building footprint.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct FootprintRecord {
+        /// The `label` field holds the label of the address.
+        pub label: String,
+        /// The `status` field holds the relation of the address to the nearby footprints.
+        pub status: FootprintStatus,
+        /// The `footprint` field holds the identifier of the nearest footprint, if any.
+        pub footprint: Option<String>,
+        /// The `offset` field holds the distance from the address to the edge of the nearest
+        /// footprint, if any.
+        pub offset: Option<f64>,
+        /// The `snapped_x` field holds the X coordinate of the nearest point on the edge of the
+        /// nearest footprint, when snapping is requested.
+        pub snapped_x: Option<f64>,
+        /// The `snapped_y` field holds the Y coordinate of the nearest point on the edge of the
+        /// nearest footprint, when snapping is requested.
+        pub snapped_y: Option<f64>,
+        /// The `x` field holds the X coordinate of the address.
+        pub x: f64,
+        /// The `y` field holds the Y coordinate of the address.
+        pub y: f64,
+    }
+}
+
+/// The `FootprintRecords` struct holds a vector of type [`FootprintRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct FootprintRecords(Vec<FootprintRecord>);
+
+impl FootprintRecords {
+    /// The `check` method reports each address in `addresses` falling outside of every footprint
+    /// in `footprints`, with the offset to the nearest footprint within `distance`.  When `snap`
+    /// is true, the record carries the nearest point on the edge of that footprint as the
+    /// snapped coordinates.  Uses a [`SpatialIndex`] over the footprint vertices to select
+    /// candidates, so a footprint is found if any of its vertices lies within `distance`.
+    /// Retired addresses are skipped.
+    pub fn check<T: Address + Cartesian + Sync>(
+        addresses: &[T],
+        footprints: &Footprints,
+        distance: f64,
+        snap: bool,
+    ) -> Self {
+        let mut vertices = Vec::new();
+        let mut owners = Vec::new();
+        for (i, footprint) in footprints.iter().enumerate() {
+            for point in footprint.boundary.rings.iter().flatten() {
+                vertices.push(*point);
+                owners.push(i);
+            }
+        }
+        let index = SpatialIndex::new(vertices, distance);
+        let addresses = addresses
+            .iter()
+            .filter(|address| address.status() != &AddressStatus::Retired)
+            .collect::<Vec<&T>>();
+        let records = addresses
+            .par_iter()
+            .progress_with(progress_bar(addresses.len(), "Checking footprints."))
+            .filter_map(|address| {
+                let (x, y) = (address.x(), address.y());
+                let mut candidates = index
+                    .within([x, y], distance)
+                    .into_iter()
+                    .map(|i| owners[i])
+                    .collect::<Vec<usize>>();
+                candidates.dedup();
+                if candidates
+                    .iter()
+                    .any(|i| footprints[*i].boundary.contains_point(x, y))
+                {
+                    return None;
+                }
+                let nearest = candidates
+                    .iter()
+                    .filter_map(|i| {
+                        footprints[*i]
+                            .boundary
+                            .nearest_point(x, y)
+                            .map(|(point, offset)| (*i, point, offset))
+                    })
+                    .filter(|(_, _, offset)| *offset <= distance)
+                    .min_by(|a, b| a.2.total_cmp(&b.2));
+                let snapped = nearest.filter(|_| snap).map(|(_, point, _)| point);
+                Some(FootprintRecord {
+                    label: address.label(),
+                    status: match nearest {
+                        Some(_) => FootprintStatus::Outside,
+                        None => FootprintStatus::Distant,
+                    },
+                    footprint: nearest.map(|(i, _, _)| footprints[i].id.clone()),
+                    offset: nearest.map(|(_, _, offset)| offset),
+                    snapped_x: snapped.map(|point| point[0]),
+                    snapped_y: snapped.map(|point| point[1]),
+                    x,
+                    y,
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<FootprintRecord>>();
+        info!(
+            "{} of {} addresses outside of every footprint.",
+            records.len(),
+            addresses.len()
+        );
+        Self(records)
+    }
+
+    /// The `count` method returns the number of records with the status `status`.
+    pub fn count(&self, status: FootprintStatus) -> usize {
+        self.iter().filter(|r| r.status == status).count()
+    }
+
+    /// The `filter` method returns the subset of records with the status named by `filter`,
+    /// either "outside" or "distant".
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "outside" => self.retain(|r| r.status == FootprintStatus::Outside),
+            "distant" => self.retain(|r| r.status == FootprintStatus::Distant),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+impl IntoCsv<FootprintRecords> for FootprintRecords {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for FootprintRecords {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::riverside_point;
+
+    #[test]
+    fn footprint_offsets() -> anyhow::Result<()> {
+        let square = |x: f64, y: f64| Boundary {
+            rings: vec![vec![
+                [x, y],
+                [x + 40.0, y],
+                [x + 40.0, y + 40.0],
+                [x, y + 40.0],
+                [x, y],
+            ]],
+            geographic: false,
+        };
+        let footprints = Footprints::from(vec![
+            Footprint {
+                id: "1".to_string(),
+                boundary: square(0.0, 0.0),
+            },
+            Footprint {
+                id: "2".to_string(),
+                boundary: square(100.0, 0.0),
+            },
+        ]);
+        let addresses = [
+            riverside_point(100, 20.0, 20.0),
+            riverside_point(200, 20.0, 50.0),
+            riverside_point(300, 120.0, -5.0),
+            riverside_point(400, 1000.0, 1000.0),
+        ];
+        let records = FootprintRecords::check(&addresses, &footprints, 100.0, true);
+        // The address inside a footprint is not reported.
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].status, FootprintStatus::Outside);
+        assert_eq!(records[0].footprint.as_deref(), Some("1"));
+        assert_eq!(records[0].offset, Some(10.0));
+        assert_eq!(
+            records[0].snapped_x.zip(records[0].snapped_y),
+            Some((20.0, 40.0))
+        );
+        assert_eq!(records[1].footprint.as_deref(), Some("2"));
+        assert_eq!(records[1].offset, Some(5.0));
+        assert_eq!(records[2].status, FootprintStatus::Distant);
+        assert!(records[2].offset.is_none());
+        assert_eq!(records.count(FootprintStatus::Outside), 2);
+        let unsnapped = FootprintRecords::check(&addresses, &footprints, 100.0, false);
+        assert!(unsnapped[0].snapped_x.is_none());
+        assert_eq!(records.filter("distant").len(), 1);
+        Ok(())
+    }
+}
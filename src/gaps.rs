@@ -0,0 +1,523 @@
+//! The `gaps` module reports unassigned address numbers along each street, so addressing staff
+//! can see which numbers remain available for new development.
+use crate::{
+    Address, AddressErrorKind, Cartesian, IntoCsv, IntoJson, Io, Parity, RetiredAddresses,
+    StreetNamePostType, StreetNamePreDirectional, crs::geolocated, dictionary::normalize, from_csv,
+    to_csv, to_json, to_ndjson,
+};
+use chrono::NaiveDate;
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use tracing::info;
+
+/// The `AddressGap` struct holds a run of unassigned address numbers of the same parity, between
+/// two assigned numbers on the same street.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct AddressGap {
+    /// The `street` field holds the complete street name.
+    pub street: String,
+    /// The `parity` field holds the parity of the numbers in the gap, odd or even.
+    pub parity: Parity,
+    /// The `start` field holds the first unassigned number in the gap.
+    pub start: i64,
+    /// The `end` field holds the last unassigned number in the gap.
+    pub end: i64,
+    /// The `count` field holds the number of unassigned numbers in the gap.
+    pub count: i64,
+}
+
+/// The `AddressGaps` struct holds a vector of type [`AddressGap`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct AddressGaps(Vec<AddressGap>);
+
+// Street name components used to group addresses by street.
+type StreetKey = (
+    Option<StreetNamePreDirectional>,
+    String,
+    Option<StreetNamePostType>,
+);
+
+impl AddressGaps {
+    /// The `from_addresses` method groups `addresses` by street, using the pre-directional,
+    /// street name and post type, and reports the unassigned numbers between the lowest and
+    /// highest assigned numbers of each parity.  Odd and even numbers are considered separately,
+    /// because they typically fall on opposite sides of the street.  Every address counts as
+    /// assigned regardless of status, and numbers shared by several subaddresses count once.
+    pub fn from_addresses<T: Address>(addresses: &[T]) -> Self {
+        let mut streets: BTreeMap<StreetKey, (String, BTreeSet<i64>)> = BTreeMap::new();
+        for address in addresses.iter().filter(|a| a.number() > 0) {
+            let key = (
+                *address.directional(),
+                address.street_name().to_uppercase(),
+                *address.street_type(),
+            );
+            streets
+                .entry(key)
+                .or_insert_with(|| (address.complete_street_name(false), BTreeSet::new()))
+                .1
+                .insert(address.number());
+        }
+        info!("Addresses grouped into {} streets.", streets.len());
+
+        let mut records = Vec::new();
+        for (street, numbers) in streets.values() {
+            for parity in [Parity::Odd, Parity::Even] {
+                let assigned = numbers
+                    .iter()
+                    .filter(|n| parity.matches(**n))
+                    .collect::<Vec<&i64>>();
+                for pair in assigned.windows(2) {
+                    let (low, high) = (*pair[0], *pair[1]);
+                    if high - low > 2 {
+                        records.push(AddressGap {
+                            street: street.clone(),
+                            parity,
+                            start: low + 2,
+                            end: high - 2,
+                            count: (high - low) / 2 - 1,
+                        });
+                    }
+                }
+            }
+        }
+        Self(records)
+    }
+
+    /// The `filter` method returns the gaps on streets whose name contains `filter`, ignoring
+    /// case.
+    pub fn filter(mut self, filter: &str) -> Self {
+        let filter = filter.to_uppercase();
+        self.retain(|gap| gap.street.to_uppercase().contains(&filter));
+        self
+    }
+}
+
+impl IntoCsv<AddressGaps> for AddressGaps {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for AddressGaps {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+/// The `ASSIGN_NEIGHBORS` constant holds the number of nearby address points on the same street
+/// used to suggest a number for a parcel.
+pub const ASSIGN_NEIGHBORS: usize = 10;
+
+/// The `ParcelPoint` struct represents a new parcel awaiting an address number, read from a csv
+/// file with columns "street", "x" and "y", and an optional "id".  The `street` field holds the
+/// complete name of the street the parcel fronts, such as "NE 7TH ST" or "NORTHEAST 7TH STREET".
+/// The coordinates share the projection of the address points.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ParcelPoint {
+    /// The `id` field holds an optional identifier for the parcel.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// The `street` field holds the complete name of the street the parcel fronts.
+    pub street: String,
+    /// The `x` field represents the cartesian X portion of the projected coordinates.
+    pub x: f64,
+    /// The `y` field represents the cartesian Y portion of the projected coordinates.
+    pub y: f64,
+}
+
+impl Cartesian for ParcelPoint {
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+/// The `ParcelPoints` struct holds a vector of type [`ParcelPoint`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct ParcelPoints(Vec<ParcelPoint>);
+
+impl IntoCsv<ParcelPoints> for ParcelPoints {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+geolocated! {
+    /// The `AddressSuggestion` struct holds the address number proposed for a [`ParcelPoint`].
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct AddressSuggestion {
+        /// The `id` field holds the identifier of the parcel, if any.
+        pub id: Option<String>,
+        /// The `street` field holds the street name of the parcel.
+        pub street: String,
+        /// The `number` field holds the proposed address number, or `None` if no valid number is
+        /// available.
+        pub number: Option<i64>,
+        /// The `parity` field holds the parity of the side of the street the parcel lies on.
+        pub parity: Parity,
+        /// The `estimate` field holds the address number interpolated from the position of the
+        /// parcel between neighboring address points.
+        pub estimate: Option<f64>,
+        /// The `lower` field holds the nearest assigned number below the estimate on the same side.
+        pub lower: Option<i64>,
+        /// The `upper` field holds the nearest assigned number above the estimate on the same side.
+        pub upper: Option<i64>,
+        /// The `interval` field holds the typical spacing between neighboring numbers on the same
+        /// side.
+        pub interval: i64,
+        /// The `note` field explains why no number was proposed, or qualifies the proposal.
+        pub note: Option<String>,
+        /// The `x` field holds the X coordinate of the parcel.
+        pub x: f64,
+        /// The `y` field holds the Y coordinate of the parcel.
+        pub y: f64,
+    }
+}
+
+/// The `AddressSuggestions` struct holds a vector of type [`AddressSuggestion`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct AddressSuggestions(Vec<AddressSuggestion>);
+
+impl AddressSuggestions {
+    /// The `suggest` method proposes an address number for each parcel in `parcels`, using the
+    /// address points in `addresses` on the street the parcel fronts.
+    ///
+    /// The nearest [`ASSIGN_NEIGHBORS`] address points on the street define the local direction
+    /// of the street.  The number is interpolated from the position of the parcel along the
+    /// street, and the parity follows from the side of the street the parcel lies on, compared to
+    /// the odd and even neighbors.  The proposal keeps to the spacing between neighboring numbers
+    /// on the same side where possible, falls strictly between the nearest assigned numbers of
+    /// the same parity, and is never already assigned.
+    pub fn suggest<T: Address + Cartesian>(parcels: &[ParcelPoint], addresses: &[T]) -> Self {
+        let mut streets: HashMap<String, Vec<&T>> = HashMap::new();
+        for address in addresses.iter().filter(|a| a.number() > 0) {
+            let full = normalize(&address.complete_street_name(false));
+            let abbreviated = normalize(&address.complete_street_name(true));
+            if abbreviated != full {
+                streets.entry(abbreviated).or_default().push(address);
+            }
+            streets.entry(full).or_default().push(address);
+        }
+        let records = parcels
+            .iter()
+            .map(|parcel| {
+                let street = streets
+                    .get(&normalize(&parcel.street))
+                    .map(|s| &s[..])
+                    .unwrap_or_default();
+                suggest(parcel, street)
+            })
+            .collect::<Vec<AddressSuggestion>>();
+        Self(records)
+    }
+
+    /// The `check_retired` method notes each proposed number that matches an address in
+    /// `registry` retired fewer than `years` before the date `on`, returning the number of
+    /// proposals noted.  The proposal is kept, so staff can choose another number.
+    pub fn check_retired(
+        &mut self,
+        registry: &RetiredAddresses,
+        on: NaiveDate,
+        years: u32,
+    ) -> usize {
+        let mut count = 0;
+        for suggestion in self.iter_mut() {
+            let Some(number) = suggestion.number else {
+                continue;
+            };
+            if let Some(retired) =
+                registry.collision_on_street(number, &suggestion.street, on, years)
+            {
+                let warning = format!("matches address retired on {}", retired.retired);
+                suggestion.note = Some(match suggestion.note.take() {
+                    Some(note) => format!("{note}; {warning}"),
+                    None => warning,
+                });
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+impl IntoCsv<AddressSuggestions> for AddressSuggestions {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for AddressSuggestions {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+// Proposes a number for `parcel` from the address points `street` on the street it fronts.
+fn suggest<T: Address + Cartesian>(parcel: &ParcelPoint, street: &[&T]) -> AddressSuggestion {
+    let mut suggestion = AddressSuggestion {
+        id: parcel.id.clone(),
+        street: parcel.street.clone(),
+        interval: 2,
+        x: parcel.x,
+        y: parcel.y,
+        ..Default::default()
+    };
+    let mut neighbors = street.to_vec();
+    neighbors.sort_by(|a, b| a.distance(parcel).total_cmp(&b.distance(parcel)));
+    neighbors.truncate(ASSIGN_NEIGHBORS);
+    if neighbors.len() < 2 {
+        suggestion.note = Some("fewer than two address points on the street".into());
+        return suggestion;
+    }
+
+    // Local direction of the street, from the principal axis of the neighboring points.
+    let n = neighbors.len() as f64;
+    let mx = neighbors.iter().map(|a| a.x()).sum::<f64>() / n;
+    let my = neighbors.iter().map(|a| a.y()).sum::<f64>() / n;
+    let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+    for a in &neighbors {
+        let (dx, dy) = (a.x() - mx, a.y() - my);
+        sxx += dx * dx;
+        syy += dy * dy;
+        sxy += dx * dy;
+    }
+    let theta = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let (ux, uy) = (theta.cos(), theta.sin());
+    // Position along (`s`) and across (`c`) the street, relative to the centroid.
+    let project = |x: f64, y: f64| {
+        let (dx, dy) = (x - mx, y - my);
+        (dx * ux + dy * uy, dy * ux - dx * uy)
+    };
+    let positions = neighbors
+        .iter()
+        .map(|a| (project(a.x(), a.y()), a.number()))
+        .collect::<Vec<((f64, f64), i64)>>();
+    let (s, c) = project(parcel.x, parcel.y);
+
+    // Least-squares fit of the address number to the position along the street.
+    let ms = positions.iter().map(|((s, _), _)| s).sum::<f64>() / n;
+    let mn = positions
+        .iter()
+        .map(|(_, number)| *number as f64)
+        .sum::<f64>()
+        / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for ((si, _), number) in &positions {
+        covariance += (si - ms) * (*number as f64 - mn);
+        variance += (si - ms).powi(2);
+    }
+    if variance <= f64::EPSILON {
+        suggestion.note = Some("neighboring address points coincide".into());
+        return suggestion;
+    }
+    let estimate = mn + covariance / variance * (s - ms);
+    suggestion.estimate = Some(estimate);
+
+    // Side of the street, from the mean offset of the odd and even neighbors.
+    let side = |parity: Parity| {
+        let offsets = positions
+            .iter()
+            .filter(|(_, number)| parity.matches(*number))
+            .map(|((_, ci), _)| *ci)
+            .collect::<Vec<f64>>();
+        (!offsets.is_empty()).then(|| offsets.iter().sum::<f64>() / offsets.len() as f64)
+    };
+    suggestion.parity = match (side(Parity::Odd), side(Parity::Even)) {
+        (Some(odd), Some(even)) if (c - odd).abs() <= (c - even).abs() => Parity::Odd,
+        (Some(_), Some(_)) => Parity::Even,
+        (Some(_), None) => {
+            suggestion.note = Some("only odd numbers nearby".into());
+            Parity::Odd
+        }
+        _ => {
+            suggestion.note = Some("only even numbers nearby".into());
+            Parity::Even
+        }
+    };
+    let parity = suggestion.parity;
+
+    // Typical spacing between neighboring numbers on the same side.
+    let mut side_numbers = neighbors
+        .iter()
+        .map(|a| a.number())
+        .filter(|number| parity.matches(*number))
+        .collect::<Vec<i64>>();
+    side_numbers.sort_unstable();
+    side_numbers.dedup();
+    let mut steps = side_numbers
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .collect::<Vec<i64>>();
+    steps.sort_unstable();
+    if !steps.is_empty() {
+        suggestion.interval = steps[steps.len() / 2];
+    }
+
+    let assigned = street
+        .iter()
+        .map(|a| a.number())
+        .filter(|number| parity.matches(*number))
+        .collect::<BTreeSet<i64>>();
+    let lower = assigned
+        .range(..=estimate.floor() as i64)
+        .next_back()
+        .copied();
+    let upper = assigned.range(estimate.ceil() as i64..).next().copied();
+    let (lower, upper) = match (lower, upper) {
+        // The estimate falls on an assigned number; look for room on the nearer side.
+        (Some(l), Some(u)) if l == u => {
+            if estimate >= l as f64 {
+                (Some(l), assigned.range(l + 1..).next().copied())
+            } else {
+                (assigned.range(..l).next_back().copied(), Some(l))
+            }
+        }
+        bounds => bounds,
+    };
+    suggestion.lower = lower;
+    suggestion.upper = upper;
+    let low = lower.map_or(if parity == Parity::Odd { 1 } else { 2 }, |l| l + 2);
+    let high = upper.map_or(i64::MAX, |u| u - 2);
+    if low > high {
+        suggestion.note = Some(format!(
+            "no {} number free between {} and {}",
+            if parity == Parity::Odd { "odd" } else { "even" },
+            lower.unwrap_or_default(),
+            upper.unwrap_or_default()
+        ));
+        return suggestion;
+    }
+
+    // Prefer a number on the spacing grid of the nearest assigned number, then the nearest
+    // number of the right parity.
+    let interval = suggestion.interval.max(2);
+    let on_grid = lower.or(upper).map(|anchor| {
+        anchor + ((estimate - anchor as f64) / interval as f64).round() as i64 * interval
+    });
+    let mut nearest = estimate.round() as i64;
+    if !parity.matches(nearest) {
+        nearest += if estimate >= nearest as f64 { 1 } else { -1 };
+    }
+    let number = on_grid
+        .filter(|n| (low..=high).contains(n))
+        .unwrap_or(nearest.clamp(low, high));
+    suggestion.number = Some(number);
+    suggestion
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CommonAddress, IntoBin, SpatialAddress, SpatialAddresses, address::riverside_point,
+    };
+
+    #[test]
+    fn address_number_gaps() -> anyhow::Result<()> {
+        let street = |number: i64, street_name: &str| CommonAddress {
+            number,
+            street_name: street_name.to_string(),
+            street_type: Some(StreetNamePostType::AVENUE),
+            ..Default::default()
+        };
+        let addresses = [101, 105, 111, 111, 100, 102, 110]
+            .iter()
+            .map(|n| street(*n, "RIVERSIDE"))
+            .chain([street(200, "PARK"), street(210, "PARK")])
+            .collect::<Vec<CommonAddress>>();
+        let gaps = AddressGaps::from_addresses(&addresses);
+        assert_eq!(gaps.len(), 4);
+        let odd = gaps
+            .iter()
+            .filter(|g| g.parity == Parity::Odd)
+            .map(|g| (g.start, g.end, g.count))
+            .collect::<Vec<(i64, i64, i64)>>();
+        assert_eq!(odd, vec![(103, 103, 1), (107, 109, 2)]);
+        let even = gaps.clone().filter("riverside");
+        assert_eq!(even.len(), 3);
+        assert!(
+            even.iter()
+                .any(|g| (g.start, g.end, g.count) == (104, 108, 3))
+        );
+        let park = gaps.filter("park");
+        assert_eq!((park[0].start, park[0].end, park[0].count), (202, 208, 4));
+
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let gaps = AddressGaps::from_addresses(&city_addresses);
+        assert!(!gaps.is_empty());
+        assert!(
+            gaps.iter()
+                .all(|g| g.start <= g.end && g.parity.matches(g.start))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn assign_address_numbers() -> anyhow::Result<()> {
+        // Odd numbers on the north side, even numbers on the south side, 109 unassigned.
+        let addresses = [(101, 0.0), (105, 40.0), (113, 120.0)]
+            .iter()
+            .map(|(n, x)| riverside_point(*n, *x, 20.0))
+            .chain(
+                [(100, 0.0), (104, 40.0), (108, 80.0), (112, 120.0)]
+                    .iter()
+                    .map(|(n, x)| riverside_point(*n, *x, -20.0)),
+            )
+            .collect::<Vec<SpatialAddress>>();
+        let parcel = |street: &str, x: f64, y: f64| ParcelPoint {
+            id: None,
+            street: street.to_string(),
+            x,
+            y,
+        };
+        let mut parcels = ParcelPoints::default();
+        parcels.extend([
+            parcel("Riverside Ave", 80.0, 20.0),
+            parcel("RIVERSIDE AVENUE", 60.0, -20.0),
+            parcel("RIVERSIDE AVE", 40.0, 20.0),
+            parcel("PARK AVE", 0.0, 0.0),
+        ]);
+        let suggestions = AddressSuggestions::suggest(&parcels, &addresses);
+        assert_eq!(suggestions.len(), 4);
+        assert_eq!(suggestions[0].number, Some(109));
+        assert_eq!(suggestions[0].parity, Parity::Odd);
+        assert_eq!(
+            (suggestions[0].lower, suggestions[0].upper),
+            (Some(105), Some(113))
+        );
+        assert_eq!(suggestions[1].number, Some(106));
+        assert_eq!(suggestions[1].parity, Parity::Even);
+        assert_eq!(suggestions[2].number, Some(103));
+        assert_eq!(suggestions[3].number, None);
+        assert!(suggestions[3].note.is_some());
+        Ok(())
+    }
+}
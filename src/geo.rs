@@ -1,8 +1,10 @@
 //! The `geo` module defines spatial address types, and implements traits from the `galileo` crate for these types.
 use crate::{
     Address, AddressDelta, AddressDeltas, AddressError, AddressErrorKind, AddressStatus, Addresses,
-    Bincode, CommonAddress, IntoBin, State, StreetNamePostType, StreetNamePreDirectional,
-    StreetNamePreModifier, StreetNamePreType, StreetSeparator, SubaddressType, from_bin, to_bin,
+    Bincode, CommonAddress, Geolocate, IntoBin, IntoGeoJson, IntoJson, Projection, SpatialIndex,
+    State, StreetNamePostType, StreetNamePreDirectional, StreetNamePreModifier, StreetNamePreType,
+    StreetSeparator, SubaddressType, WGS84, from_bin, geolocate, progress_bar, to_bin, to_geojson,
+    to_json, to_ndjson, transform,
 };
 use derive_more::{Deref, DerefMut};
 use indicatif::ParallelProgressIterator;
@@ -46,7 +48,7 @@ pub trait Cartesian {
         let records = others
             .par_iter()
             .filter(|v| v.label() == self.label())
-            .map(|v| AddressDelta::new(v, v.distance(self)))
+            .map(|v| AddressDelta::between(self, v))
             .filter(|d| d.delta > min)
             .collect::<Vec<AddressDelta>>();
         AddressDeltas::new(records)
@@ -65,13 +67,9 @@ pub trait Cartesian {
         other: &[U],
         min: f64,
     ) -> AddressDeltas {
-        let style = indicatif::ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {'Calculating deltas...'}",
-        )
-        .unwrap();
         let records_raw = values
             .par_iter()
-            .progress_with_style(style)
+            .progress_with(progress_bar(values.len(), "Calculating deltas..."))
             .map(|v| Cartesian::delta(v, other, min))
             .collect::<Vec<AddressDeltas>>();
         let mut records = Vec::new();
@@ -81,6 +79,38 @@ pub trait Cartesian {
             .for_each(drop);
         AddressDeltas::new(records)
     }
+
+    /// The `deltas_within` method works like [`Cartesian::deltas`], but only measures the distance
+    /// to addresses in `other` within `radius` of each address in `values`, using a
+    /// [`SpatialIndex`] to select candidates.  The `radius` is in the units of the projection, and
+    /// should exceed the largest drift of interest.
+    fn deltas_within<
+        T: Cartesian + Address + Clone + Sync + Send,
+        U: Cartesian + Address + Clone + Sync + Send,
+    >(
+        values: &[T],
+        other: &[U],
+        min: f64,
+        radius: f64,
+    ) -> AddressDeltas {
+        let index = SpatialIndex::from_cartesian(other, radius);
+        let records = values
+            .par_iter()
+            .progress_with(progress_bar(values.len(), "Calculating deltas..."))
+            .flat_map_iter(|v| {
+                let label = v.label();
+                index
+                    .within([v.x(), v.y()], radius)
+                    .into_iter()
+                    .map(|i| &other[i])
+                    .filter(move |o| o.label() == label)
+                    .map(|o| AddressDelta::between(v, o))
+                    .filter(|d| d.delta > min)
+                    .collect::<Vec<AddressDelta>>()
+            })
+            .collect::<Vec<AddressDelta>>();
+        AddressDeltas::new(records)
+    }
 }
 
 /// The `GeoAddress` struct defines a common address that has associated geographic coordinates.
@@ -275,7 +305,7 @@ impl IntoBin<GeoAddress> for GeoAddress {
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, 1, path)
     }
 }
 
@@ -468,7 +498,7 @@ impl IntoBin<AddressPoint> for AddressPoint {
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, 1, path)
     }
 }
 
@@ -650,6 +680,17 @@ impl Cartesian for SpatialAddress {
     }
 }
 
+impl Geolocate for SpatialAddress {
+    fn projected(&self) -> Option<(f64, f64)> {
+        Some((self.x, self.y))
+    }
+
+    fn set_geographic(&mut self, longitude: f64, latitude: f64) {
+        self.latitude = latitude;
+        self.longitude = longitude;
+    }
+}
+
 impl<T: Address + Geographic + Cartesian + Clone> From<&T> for SpatialAddress {
     fn from(data: &T) -> Self {
         let address = CommonAddress::from(data);
@@ -668,6 +709,10 @@ impl<T: Address + Geographic + Cartesian + Clone> From<&T> for SpatialAddress {
 }
 
 /// The `SpatialAddresses` struct holds a vector of type [`SpatialAddress`].
+///
+/// The `epsg` field records the coordinate reference system of the projected `x` and `y`
+/// coordinates, when known.  The code is not persisted by [`IntoBin::save`], so binary files
+/// from earlier versions still load.
 #[derive(
     Debug,
     Clone,
@@ -680,7 +725,68 @@ impl<T: Address + Geographic + Cartesian + Clone> From<&T> for SpatialAddress {
     derive_more::Deref,
     derive_more::DerefMut,
 )]
-pub struct SpatialAddresses(Vec<SpatialAddress>);
+pub struct SpatialAddresses {
+    #[deref]
+    #[deref_mut]
+    records: Vec<SpatialAddress>,
+    #[new(default)]
+    #[serde(skip)]
+    epsg: Option<u32>,
+}
+
+impl SpatialAddresses {
+    /// The `epsg` method returns the EPSG code of the coordinate reference system for the
+    /// projected coordinates, if known.
+    pub fn epsg(&self) -> Option<u32> {
+        self.epsg
+    }
+
+    /// The `with_epsg` method declares the coordinate reference system of the projected
+    /// coordinates as `epsg`, without changing the coordinates.
+    pub fn with_epsg(mut self, epsg: u32) -> Self {
+        self.epsg = Some(epsg);
+        self
+    }
+
+    /// The `reproject` method transforms the projected coordinates of each address from the
+    /// current coordinate reference system to the system `to_epsg`, using [`crate::transform`].
+    /// Geographic coordinates are unchanged.  Errors if the current system is unknown, or if
+    /// either system is unsupported.
+    pub fn reproject(&mut self, to_epsg: u32) -> Result<(), AddressError> {
+        let Some(from_epsg) = self.epsg else {
+            return Err(Projection::new(
+                to_epsg,
+                "source coordinate reference system unknown".into(),
+                line!(),
+                file!().into(),
+            )
+            .into());
+        };
+        for address in self.records.iter_mut() {
+            (address.x, address.y) = transform(from_epsg, to_epsg, address.x, address.y)?;
+        }
+        self.epsg = Some(to_epsg);
+        Ok(())
+    }
+
+    /// The `geolocate` method replaces the geographic coordinates of each address with WGS84
+    /// coordinates computed from the projected coordinates, so the latitude and longitude agree
+    /// with the points whatever the source recorded.  Errors if the current system is unknown,
+    /// or unsupported.
+    pub fn geolocate(&mut self) -> Result<(), AddressError> {
+        let Some(epsg) = self.epsg else {
+            return Err(Projection::new(
+                WGS84,
+                "source coordinate reference system unknown".into(),
+                line!(),
+                file!().into(),
+            )
+            .into());
+        };
+        geolocate(&mut self.records, epsg)?;
+        Ok(())
+    }
+}
 
 impl Addresses<SpatialAddress> for SpatialAddresses {}
 
@@ -694,7 +800,23 @@ impl IntoBin<SpatialAddresses> for SpatialAddresses {
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
+    }
+}
+
+impl IntoGeoJson for SpatialAddresses {
+    fn to_geojson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_geojson(&self.records, path)
+    }
+}
+
+impl IntoJson for SpatialAddresses {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.records, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.records, path)
     }
 }
 
@@ -704,6 +826,41 @@ impl<T: Address + Geographic + Cartesian + Clone + Sized> From<&[T]> for Spatial
             .iter()
             .map(SpatialAddress::from)
             .collect::<Vec<SpatialAddress>>();
-        Self(records)
+        Self::new(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::riverside_point;
+
+    #[test]
+    fn drift_bearing() -> anyhow::Result<()> {
+        let source = [
+            riverside_point(100, 0.0, 0.0),
+            riverside_point(102, 0.0, 0.0),
+            riverside_point(104, 0.0, 0.0),
+        ];
+        let target = [
+            riverside_point(100, 0.0, 10.0),
+            riverside_point(102, 3.0, 0.0),
+            riverside_point(104, -0.5, 0.0),
+        ];
+        let deltas = <SpatialAddress as Cartesian>::deltas(&source, &target, f64::MIN);
+        let bearing = |label: &str| {
+            deltas
+                .iter()
+                .find(|d| d.label == label)
+                .and_then(|d| d.bearing)
+        };
+        assert_eq!(bearing("100 RIVERSIDE AVE"), Some(0.0));
+        assert_eq!(bearing("102 RIVERSIDE AVE"), Some(90.0));
+        assert_eq!(bearing("104 RIVERSIDE AVE"), Some(270.0));
+        let within = <SpatialAddress as Cartesian>::deltas_within(&source, &target, 1.0, 20.0);
+        assert_eq!(within.len(), 2);
+        assert!(within.iter().all(|d| d.bearing.is_some()));
+        assert!(AddressDelta::new(&source[0], 1.0).bearing.is_none());
+        Ok(())
     }
 }
@@ -0,0 +1,184 @@
+//! The `geodatabase` module reads features from an Esri File Geodatabase through GDAL, so the
+//! enterprise exports can be read directly rather than through a csv export.  Reading requires
+//! the crate to be built with the `gdal` feature, which links against the GDAL library, so the
+//! base crate stays free of native dependencies.
+//!
+//! As with shapefiles, the attributes of each feature are translated into csv records in memory,
+//! so import types deserialize from a geodatabase using the same field names and deserialization
+//! rules as from a csv export.
+use crate::{AddressErrorKind, Gdal};
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+
+/// The `is_geodatabase` function returns true if `path` names a File Geodatabase, a directory
+/// with a `.gdb` extension, or a layer within one, as in "exports.gdb/AddressPoints".
+pub fn is_geodatabase<P: AsRef<Path>>(path: P) -> bool {
+    split_layer(path.as_ref()).is_some()
+}
+
+// Splits a path naming a File Geodatabase into the path to the geodatabase and the name of the
+// layer following it, if any.  Returns `None` if no component of the path has a `.gdb`
+// extension.
+fn split_layer(path: &Path) -> Option<(PathBuf, Option<String>)> {
+    let mut dataset = PathBuf::new();
+    let mut components = path.components();
+    for component in components.by_ref() {
+        dataset.push(component);
+        if dataset
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gdb"))
+        {
+            let layer = components.as_path().to_string_lossy().to_string();
+            return Some((dataset, Some(layer).filter(|l| !l.is_empty())));
+        }
+    }
+    None
+}
+
+/// The `from_geodatabase` function reads records of type `T` from the File Geodatabase layer
+/// named by `path`, as in "exports.gdb/AddressPoints", or from the first layer if `path` names
+/// only the geodatabase.  Returns an error if the crate is built without the `gdal` feature.
+pub fn from_geodatabase<T: DeserializeOwned + Clone, P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<T>, AddressErrorKind> {
+    let path = path.as_ref();
+    let Some((dataset, layer)) = split_layer(path) else {
+        return Err(Gdal::new(
+            path.into(),
+            "not a file geodatabase".into(),
+            line!(),
+            file!().into(),
+        )
+        .into());
+    };
+    #[cfg(feature = "gdal")]
+    {
+        from_gdal(dataset, layer.as_deref())
+    }
+    #[cfg(not(feature = "gdal"))]
+    {
+        let _ = layer;
+        Err(Gdal::new(
+            dataset,
+            "reading a file geodatabase requires the gdal feature".into(),
+            line!(),
+            file!().into(),
+        )
+        .into())
+    }
+}
+
+/// Generic function to deserialize data types from a vector layer read by GDAL, such as a layer
+/// of a File Geodatabase.  Reads the layer named `layer` of the dataset at `path`, or the first
+/// layer if `layer` is `None`.
+///
+/// Point geometry populates the `x` and `y` fields of the record, and other geometry populates
+/// the `wkt` field as well-known text.  Coordinates are read in two dimensions.  Attribute names
+/// match the fields of type `T` as for a shapefile.  Records that fail to deserialize are
+/// dropped, consistent with [`from_csv`](crate::from_csv).
+#[cfg(feature = "gdal")]
+pub fn from_gdal<T: DeserializeOwned + Clone, P: AsRef<Path>>(
+    path: P,
+    layer: Option<&str>,
+) -> Result<Vec<T>, AddressErrorKind> {
+    use crate::shapefile::{column, field_names, from_rows, match_field};
+    use gdal::Dataset;
+    use gdal::vector::LayerAccess;
+    use tracing::info;
+
+    let path = path.as_ref();
+    let error = |source: gdal::errors::GdalError, line: u32| {
+        Gdal::new(path.into(), source.to_string(), line, file!().into())
+    };
+    info!("Reading layer {:?} from {:?}", layer, path);
+    let dataset = Dataset::open(path).map_err(|e| error(e, line!()))?;
+    let mut layer = match layer {
+        Some(name) => dataset.layer_by_name(name),
+        None => dataset.layer(0),
+    }
+    .map_err(|e| error(e, line!()))?;
+    let names = layer
+        .defn()
+        .fields()
+        .map(|field| field.name())
+        .collect::<Vec<String>>();
+
+    let mut rows = Vec::new();
+    let mut shapes = Vec::new();
+    for feature in layer.features() {
+        let row = (0..names.len())
+            .map(|i| {
+                feature
+                    .field_as_string(i as i32)
+                    .map(|value| value.unwrap_or_default())
+            })
+            .collect::<Result<Vec<String>, gdal::errors::GdalError>>()
+            .map_err(|e| error(e, line!()))?;
+        let shape = match feature.geometry() {
+            Some(geometry) if !geometry.is_empty() => {
+                let mut geometry = geometry.clone();
+                geometry.flatten_to_2d();
+                if geometry.geometry_name() == "POINT" {
+                    let (x, y, _) = geometry.get_point(0);
+                    Some(Shape::Point(x, y))
+                } else {
+                    Some(Shape::Wkt(geometry.wkt().map_err(|e| error(e, line!()))?))
+                }
+            }
+            _ => None,
+        };
+        rows.push(row);
+        shapes.push(shape);
+    }
+
+    // Map the attribute names onto the field names expected by the target type.
+    let fields = field_names::<T>();
+    let mut headers = names
+        .iter()
+        .map(|name| match_field(name, fields))
+        .collect::<Vec<String>>();
+    let lines = shapes.iter().any(|s| matches!(s, Some(Shape::Wkt(_))));
+    let (x, y, wkt) = if lines {
+        (0, 0, column(&mut headers, "wkt"))
+    } else {
+        (column(&mut headers, "x"), column(&mut headers, "y"), 0)
+    };
+    for (row, shape) in rows.iter_mut().zip(shapes) {
+        row.resize(headers.len(), String::new());
+        match shape {
+            Some(Shape::Point(px, py)) => {
+                row[x] = px.to_string();
+                row[y] = py.to_string();
+            }
+            Some(Shape::Wkt(text)) => row[wkt] = text,
+            None => {}
+        }
+    }
+    info!("Features read: {}", rows.len());
+    from_rows(&headers, rows, path)
+}
+
+// Geometry of a feature.
+#[cfg(feature = "gdal")]
+enum Shape {
+    Point(f64, f64),
+    Wkt(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FromShapefile, GrantsPassSpatialAddresses};
+
+    #[test]
+    fn geodatabase_paths() -> anyhow::Result<()> {
+        assert!(is_geodatabase("data/exports.gdb"));
+        assert!(is_geodatabase("data/EXPORTS.GDB/AddressPoints"));
+        assert!(!is_geodatabase("data/shapefile_sample.shp"));
+        assert!(!is_geodatabase("data/address_sample.csv"));
+        // A missing geodatabase fails to read, with or without the gdal feature.
+        let read = GrantsPassSpatialAddresses::from_source("data/missing.gdb/AddressPoints");
+        assert!(read.is_err());
+        Ok(())
+    }
+}
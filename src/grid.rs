@@ -0,0 +1,114 @@
+//! The `grid` module locates addresses in the cells of a map-book grid, the page and cell
+//! references used by fire department dispatch (CAD) systems to direct responders.
+use crate::{AddressErrorKind, Cartesian, Geographic, ZoneLayer, from_geographic};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The `GRID_FIELD` constant holds the default name of the attribute holding the grid reference
+/// in a map grid polygon layer.
+pub const GRID_FIELD: &str = "GRID";
+
+/// The `MapGrid` struct holds the cells of a map-book grid, each polygon tagged with its grid
+/// reference, such as "24-B3".
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapGrid {
+    /// The `layer` field holds the grid cells as a [`ZoneLayer`] named "grid".
+    pub layer: ZoneLayer,
+    /// The `epsg` field holds the EPSG code of the projected coordinates of the grid, used to
+    /// place geographic points in a grid read from a projected shapefile.  GeoJSON grids are
+    /// always geographic.
+    pub epsg: Option<u32>,
+}
+
+impl MapGrid {
+    /// The `from_path` method reads the grid cells from the GeoJSON file or polygon shapefile at
+    /// `path`, with the grid reference of each cell in the attribute named `field`.
+    pub fn from_path<P: AsRef<Path>>(path: P, field: &str) -> Result<Self, AddressErrorKind> {
+        Ok(Self {
+            layer: ZoneLayer::from_path("grid", path, Some(field))?,
+            epsg: None,
+        })
+    }
+
+    /// The `with_epsg` method sets the value of the `epsg` field.
+    pub fn with_epsg(mut self, epsg: Option<u32>) -> Self {
+        self.epsg = epsg;
+        self
+    }
+
+    /// The `len` method returns the number of cells in the grid.
+    pub fn len(&self) -> usize {
+        self.layer.zones.len()
+    }
+
+    /// The `is_empty` method returns `true` if the grid has no cells.
+    pub fn is_empty(&self) -> bool {
+        self.layer.zones.is_empty()
+    }
+
+    /// The `cell` method returns the grid reference of the cell containing `address`, if any.
+    pub fn cell<T: Cartesian + Geographic>(&self, address: &T) -> Option<&str> {
+        self.layer.zone(address)
+    }
+
+    /// The `cell_at` method returns the grid reference of the cell containing the point at
+    /// `longitude` and `latitude`.  A projected grid contains no points unless the `epsg` field
+    /// is set.
+    pub fn cell_at(&self, longitude: f64, latitude: f64) -> Option<&str> {
+        let projected = self
+            .epsg
+            .and_then(|epsg| from_geographic(epsg, longitude, latitude).ok());
+        self.layer
+            .zones
+            .iter()
+            .find(|(_, boundary)| {
+                if boundary.geographic {
+                    boundary.contains_point(longitude, latitude)
+                } else {
+                    projected.is_some_and(|(x, y)| boundary.contains_point(x, y))
+                }
+            })
+            .map(|(cell, _)| cell.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Address, AddressService, EnrichedAddresses, GEOCODE_THRESHOLD, Geocoder, IntoBin,
+        MatchType, SpatialAddresses,
+    };
+
+    #[test]
+    fn map_grid_references() -> anyhow::Result<()> {
+        let grid = MapGrid::from_path("data/grid_sample.geojson", GRID_FIELD)?;
+        assert_eq!(grid.len(), 4);
+        assert_eq!(grid.cell_at(-123.34, 42.45), Some("24-A1"));
+        assert_eq!(grid.cell_at(-123.20, 42.45), None);
+
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let geocoder = Geocoder::new(&city_addresses, GEOCODE_THRESHOLD).with_grid(Some(&grid));
+        let record = geocoder.geocode("322 SE K Street, Grants Pass, OR 97526");
+        assert_eq!(record.match_type, MatchType::Exact);
+        assert_eq!(record.grid.as_deref(), Some("24-B2"));
+        let unmatched = geocoder.geocode("322 Nowhere Boulevard, Grants Pass");
+        assert!(unmatched.grid.is_none());
+
+        // Address exports carry the grid reference as a column.
+        let address = city_addresses
+            .iter()
+            .find(|a| a.label() == "322 SE K ST")
+            .expect("322 SE K ST")
+            .clone();
+        assert_eq!(grid.cell(&address), Some("24-B2"));
+        let records = EnrichedAddresses::new(&[address], std::slice::from_ref(&grid.layer));
+        assert_eq!(records[0].zones["grid"].as_deref(), Some("24-B2"));
+
+        let service =
+            AddressService::new(city_addresses.to_vec(), GEOCODE_THRESHOLD).with_grid(grid);
+        let located = service.geocode("322 SE K Street, Grants Pass", Some(97526));
+        assert_eq!(located.grid.as_deref(), Some("24-B2"));
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+//! The `grpc` module answers streamed match requests over gRPC, built with the `grpc` feature, so
+//! batch clients such as an ETL job can send many records over one connection without writing
+//! them to a file first.  The messages and service are defined in `proto/destination.proto`, and
+//! the generated client lets Rust programs call the service.
+use crate::{Address, AddressErrorKind, AddressService, GeocodeRecord, Geographic, Io};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{info, warn};
+
+// Code generated from the protocol buffer definitions.
+mod proto {
+    tonic::include_proto!("destination");
+}
+
+pub use proto::address_matcher_client::AddressMatcherClient;
+use proto::address_matcher_server::{AddressMatcher, AddressMatcherServer};
+pub use proto::{MatchRequest, MatchResult};
+
+/// The `GRPC_BUFFER` constant holds the number of results held for a client before geocoding
+/// waits for the client to read them.
+pub const GRPC_BUFFER: usize = 1024;
+
+impl MatchResult {
+    /// The `from_record` method creates the result for the request with identifier `id` from the
+    /// [`GeocodeRecord`] `record`.
+    pub fn from_record(id: String, record: GeocodeRecord) -> Self {
+        Self {
+            id,
+            input: record.input,
+            match_type: record.match_type.to_string(),
+            confidence: record.confidence,
+            accuracy: record.accuracy,
+            label: record.label,
+            latitude: record.latitude,
+            longitude: record.longitude,
+            grid: record.grid,
+        }
+    }
+}
+
+// Answers the `AddressMatcher` service from an `AddressService`.
+struct Matcher<T: Address + Geographic + Send + Sync + 'static>(Arc<AddressService<T>>);
+
+#[tonic::async_trait]
+impl<T: Address + Geographic + Send + Sync + 'static> AddressMatcher for Matcher<T> {
+    type MatchAddressesStream = ReceiverStream<Result<MatchResult, Status>>;
+
+    async fn match_addresses(
+        &self,
+        request: Request<Streaming<MatchRequest>>,
+    ) -> Result<Response<Self::MatchAddressesStream>, Status> {
+        let mut requests = request.into_inner();
+        let service = self.0.clone();
+        let (sender, receiver) = mpsc::channel(GRPC_BUFFER);
+        tokio::spawn(async move {
+            let mut count = 0;
+            loop {
+                let result = match requests.message().await {
+                    Ok(Some(request)) => {
+                        let record = service.geocode(&request.address, request.zip);
+                        Ok(MatchResult::from_record(request.id, record))
+                    }
+                    Ok(None) => break,
+                    Err(status) => {
+                        warn!("Match stream interrupted: {status}");
+                        Err(status)
+                    }
+                };
+                let failed = result.is_err();
+                // Stop once the client hangs up or the request stream fails.
+                if sender.send(result).await.is_err() || failed {
+                    break;
+                }
+                count += 1;
+            }
+            info!("Match stream closed after {count} requests.");
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+impl<T: Address + Geographic + Send + Sync + 'static> AddressService<T> {
+    /// The `serve_grpc` method answers HTTP requests at the socket address `bind`, as in
+    /// [`AddressService::serve`], and streamed match requests over gRPC at the socket address
+    /// `grpc`, such as "127.0.0.1:50051", until the program exits.  Each request is geocoded as
+    /// for the `/geocode` endpoint.
+    pub fn serve_grpc(self, bind: &str, grpc: &str) -> Result<(), AddressErrorKind> {
+        let io = |path: &str, source| Io::new(path.into(), source, line!(), file!().into());
+        let runtime = tokio::runtime::Runtime::new().map_err(|e| io(bind, e))?;
+        runtime.block_on(async {
+            let http = TcpListener::bind(bind).await.map_err(|e| io(bind, e))?;
+            let rpc = TcpListener::bind(grpc).await.map_err(|e| io(grpc, e))?;
+            self.serve_listeners(http, rpc, std::future::pending())
+                .await
+        })
+    }
+
+    // Answers HTTP requests on the listener `http` and gRPC requests on the listener `grpc`,
+    // until either server fails or `shutdown` completes.
+    async fn serve_listeners(
+        self,
+        http: TcpListener,
+        grpc: TcpListener,
+        shutdown: impl Future<Output = ()> + Send,
+    ) -> Result<(), AddressErrorKind> {
+        let io = |address: &str, source| Io::new(address.into(), source, line!(), file!().into());
+        let bind = http.local_addr().map(|a| a.to_string()).unwrap_or_default();
+        let address = grpc.local_addr().map(|a| a.to_string()).unwrap_or_default();
+        let service = Arc::new(self);
+        info!(
+            "Serving {} addresses at {} and over gRPC at {}",
+            service.len(),
+            bind,
+            address
+        );
+        let http = axum::serve(http, AddressService::routes(service.clone()));
+        let matcher = AddressMatcherServer::new(Matcher(service));
+        let rpc = tonic::transport::Server::builder()
+            .add_service(matcher)
+            .serve_with_incoming(TcpIncoming::from(grpc));
+        tokio::select! {
+            result = async {
+                tokio::try_join!(async { http.await.map_err(|e| io(&bind, e)) }, async {
+                    rpc.await.map_err(|e| io(&address, std::io::Error::other(e)))
+                })
+            } => {
+                result?;
+            }
+            () = shutdown => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GEOCODE_THRESHOLD, IntoBin, SpatialAddresses};
+
+    #[test]
+    fn grpc_match_stream() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let service = AddressService::new(city_addresses.to_vec(), GEOCODE_THRESHOLD);
+        let runtime = tokio::runtime::Runtime::new()?;
+        let results = runtime.block_on(async {
+            // Port 0 lets the system choose free ports, so parallel test runs do not collide.
+            let http = TcpListener::bind("127.0.0.1:0").await?;
+            let grpc = TcpListener::bind("127.0.0.1:0").await?;
+            let url = format!("http://{}", grpc.local_addr()?);
+            let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+            let server = tokio::spawn(service.serve_listeners(http, grpc, async {
+                let _ = stopped.await;
+            }));
+            // The listeners are bound before the client connects, so no retry is needed, and the
+            // deadline fails the test rather than hanging it if the server stops answering.
+            let deadline = std::time::Duration::from_secs(30);
+            let results = tokio::time::timeout(deadline, request_matches(&url)).await;
+            let _ = stop.send(());
+            server.await??;
+            results?
+        })?;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, "0");
+        assert_eq!(results[0].match_type, "exact");
+        assert!(results[0].latitude.is_some());
+        assert_eq!(results[1].match_type, "unmatched");
+        Ok(())
+    }
+
+    // Sends two match requests to the gRPC service at `url` and collects the results.
+    async fn request_matches(url: &str) -> anyhow::Result<Vec<MatchResult>> {
+        let mut client = AddressMatcherClient::connect(url.to_string()).await?;
+        let requests = ["322 SE K Street, Grants Pass", "99999 Nowhere Lane"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, address)| MatchRequest {
+                id: i.to_string(),
+                address: address.to_string(),
+                zip: Some(97526),
+            })
+            .collect::<Vec<MatchRequest>>();
+        let mut stream = client
+            .match_addresses(tokio_stream::iter(requests))
+            .await?
+            .into_inner();
+        let mut results = Vec::new();
+        while let Some(result) = stream.message().await? {
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
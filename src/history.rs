@@ -0,0 +1,565 @@
+//! The `history` module tracks the lifecycle of addresses across dated snapshots, or vintages, of
+//! an address dataset.  Addresses are followed by a stable feature ID, such as the ESRI Global ID,
+//! so that an address keeps its identity when it is renumbered, renamed or moved.  Conversely,
+//! the module audits addresses that kept their civic address but lost their identifiers between
+//! vintages, which breaks that tracking and any join on the identifiers.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, Cartesian, GrantsPassSpatialAddress, IntoCsv,
+    IntoJson, Io, SpatialAddress, crs::geolocated, from_csv, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use strum::IntoEnumIterator;
+use tracing::info;
+
+/// The `HISTORY_THRESHOLD` constant holds the default distance an address point must move
+/// between vintages to report a [`LifecycleEvent::Moved`] event, in the units of the projected
+/// coordinates.
+pub const HISTORY_THRESHOLD: f64 = 10.0;
+
+/// The `LifecycleEvent` enum describes a change to an address between two vintages.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+    strum::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleEvent {
+    /// The address first appears in the vintage.
+    #[default]
+    #[display("created")]
+    Created,
+    /// The address is absent from the vintage, or its status changed to retired.
+    #[display("retired")]
+    Retired,
+    /// The complete address number changed.
+    #[display("renumbered")]
+    Renumbered,
+    /// The complete street name changed.
+    #[display("renamed")]
+    Renamed,
+    /// The address point moved farther than the threshold.
+    #[display("moved")]
+    Moved,
+}
+
+/// The `Vintage` struct holds a snapshot of an address dataset, keyed by feature ID.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vintage {
+    /// The `name` field identifies the snapshot, such as the file stem of its export.
+    pub name: String,
+    /// The `records` field maps the feature ID of each address to the address.
+    pub records: BTreeMap<String, SpatialAddress>,
+    /// The `object_ids` field maps the feature ID of each address to its ESRI Object ID, for
+    /// addresses exported with one.
+    #[serde(default)]
+    pub object_ids: BTreeMap<String, i64>,
+}
+
+impl Vintage {
+    /// The `new` method creates a vintage named `name` from pairs of feature IDs and addresses.
+    /// When an ID repeats, the last address with the ID is kept.
+    pub fn new<I: IntoIterator<Item = (String, SpatialAddress)>>(name: &str, records: I) -> Self {
+        Self {
+            name: name.to_string(),
+            records: records.into_iter().collect(),
+            object_ids: BTreeMap::new(),
+        }
+    }
+
+    /// The `from_grants_pass` method creates a vintage named `name` from City of Grants Pass
+    /// addresses, keyed by their Global ID.
+    pub fn from_grants_pass(name: &str, addresses: &[GrantsPassSpatialAddress]) -> Self {
+        let mut vintage = Self::new(
+            name,
+            addresses
+                .iter()
+                .map(|a| (a.global_id.clone(), SpatialAddress::from(a))),
+        );
+        vintage.object_ids = addresses
+            .iter()
+            .filter_map(|a| a.object_id.map(|id| (a.global_id.clone(), id)))
+            .collect();
+        vintage
+    }
+
+    // Maps the label of each address not retired to its feature ID, leaving out labels held by
+    // more than one address, since their records cannot be paired by civic address.
+    fn civic_ids(&self) -> BTreeMap<String, &String> {
+        let mut ids: BTreeMap<String, Option<&String>> = BTreeMap::new();
+        for (id, address) in &self.records {
+            if address.status() == &AddressStatus::Retired {
+                continue;
+            }
+            ids.entry(address.label())
+                .and_modify(|entry| *entry = None)
+                .or_insert(Some(id));
+        }
+        ids.into_iter()
+            .filter_map(|(label, id)| id.map(|id| (label, id)))
+            .collect()
+    }
+}
+
+geolocated! {
+    /// The `LifecycleRecord` struct reports a single change to an address between two vintages.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct LifecycleRecord {
+        /// The `id` field holds the feature ID of the address.
+        pub id: String,
+        /// The `vintage` field holds the name of the vintage where the change first appears.
+        pub vintage: String,
+        /// The `event` field describes the change.
+        pub event: LifecycleEvent,
+        /// The `previous` field holds the label of the address in the prior vintage, empty for
+        /// created addresses.
+        pub previous: String,
+        /// The `label` field holds the label of the address in the vintage, empty for addresses
+        /// absent from the vintage.
+        pub label: String,
+        /// The `distance` field holds the distance the address point moved since the prior vintage,
+        /// for addresses present in both.
+        pub distance: Option<f64>,
+        /// The `x` field holds the projected X coordinate of the address, as of the latest vintage
+        /// holding the address.
+        pub x: f64,
+        /// The `y` field holds the projected Y coordinate of the address, as of the latest vintage
+        /// holding the address.
+        pub y: f64,
+    }
+}
+
+/// The `LifecycleRecords` struct holds a vector of type [`LifecycleRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct LifecycleRecords(Vec<LifecycleRecord>);
+
+impl LifecycleRecords {
+    /// The `track` method compares each vintage in `vintages` against the one before it, oldest
+    /// first, and returns a record for each change to an address.  Addresses in the first vintage
+    /// form the baseline and are not reported as created.  An address whose point moved farther
+    /// than `threshold`, in the units of the projected coordinates, is reported as moved.  Records
+    /// are ordered by vintage, then by feature ID.
+    pub fn track(vintages: &[Vintage], threshold: f64) -> Self {
+        let mut records = Vec::new();
+        for pair in vintages.windows(2) {
+            let (prior, vintage) = (&pair[0], &pair[1]);
+            let mut ids = prior.records.keys().collect::<Vec<&String>>();
+            ids.extend(
+                vintage
+                    .records
+                    .keys()
+                    .filter(|id| !prior.records.contains_key(*id)),
+            );
+            ids.sort();
+            for id in ids {
+                let before = prior.records.get(id);
+                let after = vintage.records.get(id);
+                records.extend(changes(before, after, threshold).into_iter().map(
+                    |(event, distance)| {
+                        let point = after.or(before).cloned().unwrap_or_default();
+                        LifecycleRecord {
+                            id: id.clone(),
+                            vintage: vintage.name.clone(),
+                            event,
+                            previous: before.map(|a| a.label()).unwrap_or_default(),
+                            label: after.map(|a| a.label()).unwrap_or_default(),
+                            distance,
+                            x: point.x,
+                            y: point.y,
+                            lat: None,
+                            lon: None,
+                        }
+                    },
+                ));
+            }
+        }
+        info!("{} lifecycle events found.", records.len());
+        Self(records)
+    }
+
+    /// The `filter` method returns the subset of records with the event named `filter`, such as
+    /// "renamed".
+    pub fn filter(mut self, filter: &str) -> Self {
+        if LifecycleEvent::iter().any(|event| event.to_string() == filter) {
+            self.retain(|r| r.event.to_string() == filter);
+        } else {
+            info!("Invalid filter provided.");
+        }
+        self
+    }
+}
+
+// Returns the events, with the distance moved, describing the change from `before` to `after`.
+fn changes(
+    before: Option<&SpatialAddress>,
+    after: Option<&SpatialAddress>,
+    threshold: f64,
+) -> Vec<(LifecycleEvent, Option<f64>)> {
+    let (before, after) = match (before, after) {
+        (None, Some(_)) => return vec![(LifecycleEvent::Created, None)],
+        (Some(_), None) => return vec![(LifecycleEvent::Retired, None)],
+        (Some(before), Some(after)) => (before, after),
+        (None, None) => return Vec::new(),
+    };
+    let distance = Some(before.distance(after));
+    let mut events = Vec::new();
+    if after.status() == &AddressStatus::Retired && before.status() != &AddressStatus::Retired {
+        events.push(LifecycleEvent::Retired);
+    }
+    if before.complete_address_number() != after.complete_address_number() {
+        events.push(LifecycleEvent::Renumbered);
+    }
+    if before.complete_street_name(false) != after.complete_street_name(false) {
+        events.push(LifecycleEvent::Renamed);
+    }
+    if before.distance(after) > threshold {
+        events.push(LifecycleEvent::Moved);
+    }
+    events.into_iter().map(|e| (e, distance)).collect()
+}
+
+impl IntoCsv<LifecycleRecords> for LifecycleRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for LifecycleRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+/// The `IdChangeKind` enum describes how the identifiers of an address broke between two
+/// vintages, as when a feature is accidentally deleted and recreated in ArcGIS.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+    strum::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum IdChangeKind {
+    /// The civic address persisted under a new Global ID.
+    #[default]
+    #[display("global_id_changed")]
+    GlobalIdChanged,
+    /// The civic address kept its Global ID under a new Object ID.
+    #[display("object_id_changed")]
+    ObjectIdChanged,
+    /// The Object ID of an address now belongs to a different feature at another address.
+    #[display("object_id_reused")]
+    ObjectIdReused,
+}
+
+geolocated! {
+    /// The `IdChange` struct reports an address whose identifiers changed between two vintages,
+    /// which breaks joins made by the identifiers downstream.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct IdChange {
+        /// The `kind` field holds how the identifiers changed.
+        pub kind: IdChangeKind,
+        /// The `label` field holds the label of the address in the later vintage.
+        pub label: String,
+        /// The `previous` field holds the label of the address in the earlier vintage.  For a
+        /// reused Object ID, this is the address formerly holding the ID.
+        pub previous: String,
+        /// The `old_global_id` field holds the Global ID of the address in the earlier vintage.
+        pub old_global_id: String,
+        /// The `new_global_id` field holds the Global ID of the address in the later vintage.
+        pub new_global_id: String,
+        /// The `old_object_id` field holds the Object ID of the address in the earlier vintage, if
+        /// exported.
+        pub old_object_id: Option<i64>,
+        /// The `new_object_id` field holds the Object ID of the address in the later vintage, if
+        /// exported.
+        pub new_object_id: Option<i64>,
+        /// The `x` field holds the projected X coordinate of the address in the later vintage.
+        pub x: f64,
+        /// The `y` field holds the projected Y coordinate of the address in the later vintage.
+        pub y: f64,
+    }
+}
+
+/// The `IdChanges` struct holds a vector of type [`IdChange`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct IdChanges(Vec<IdChange>);
+
+impl IdChanges {
+    /// The `audit` method reports the addresses of `old` whose identifiers are not stable in
+    /// `new`.  Addresses not retired are paired by label, and an address whose label persisted is
+    /// reported if its Global ID changed, or else if its Object ID changed.  Labels held by more
+    /// than one address in a vintage are not paired.  An Object ID held in both vintages by
+    /// features with different Global IDs and labels is reported as reused.  Records are ordered
+    /// by kind, then by label.
+    pub fn audit(old: &Vintage, new: &Vintage) -> Self {
+        let record = |kind, old_id: &String, new_id: &String| {
+            let before = old.records.get(old_id).cloned().unwrap_or_default();
+            let after = new.records.get(new_id).cloned().unwrap_or_default();
+            IdChange {
+                kind,
+                label: after.label(),
+                previous: before.label(),
+                old_global_id: old_id.clone(),
+                new_global_id: new_id.clone(),
+                old_object_id: old.object_ids.get(old_id).copied(),
+                new_object_id: new.object_ids.get(new_id).copied(),
+                x: after.x,
+                y: after.y,
+                lat: None,
+                lon: None,
+            }
+        };
+        let mut records = Vec::new();
+        let later = new.civic_ids();
+        for (label, old_id) in old.civic_ids() {
+            let Some(new_id) = later.get(&label) else {
+                continue;
+            };
+            if old_id != *new_id {
+                records.push(record(IdChangeKind::GlobalIdChanged, old_id, new_id));
+            } else if let (Some(before), Some(after)) =
+                (old.object_ids.get(old_id), new.object_ids.get(old_id))
+                && before != after
+            {
+                records.push(record(IdChangeKind::ObjectIdChanged, old_id, old_id));
+            }
+        }
+        let holders = new
+            .object_ids
+            .iter()
+            .map(|(id, object_id)| (*object_id, id))
+            .collect::<BTreeMap<i64, &String>>();
+        for (old_id, object_id) in &old.object_ids {
+            let Some(new_id) = holders.get(object_id) else {
+                continue;
+            };
+            let (Some(before), Some(after)) = (old.records.get(old_id), new.records.get(*new_id))
+            else {
+                continue;
+            };
+            if old_id != *new_id && before.label() != after.label() {
+                records.push(record(IdChangeKind::ObjectIdReused, old_id, new_id));
+            }
+        }
+        records.sort_by(|a, b| (a.kind, &a.label).cmp(&(b.kind, &b.label)));
+        info!("{} identifier changes found.", records.len());
+        Self(records)
+    }
+
+    /// The `count` method returns the number of records with the kind `kind`.
+    pub fn count(&self, kind: IdChangeKind) -> usize {
+        self.iter().filter(|r| r.kind == kind).count()
+    }
+
+    /// The `filter` method returns the subset of records with the kind named by `filter`, such as
+    /// "global_id_changed".
+    pub fn filter(mut self, filter: &str) -> Self {
+        if IdChangeKind::iter().any(|kind| kind.to_string() == filter) {
+            self.retain(|r| r.kind.to_string() == filter);
+        } else {
+            info!("Invalid filter provided.");
+        }
+        self
+    }
+}
+
+impl IntoCsv<IdChanges> for IdChanges {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for IdChanges {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cli, Command, CommonAddress, StreetNamePostType};
+    use clap::Parser;
+
+    #[test]
+    fn lifecycle_history() -> anyhow::Result<()> {
+        let address = |number: i64, street_name: &str, x: f64| SpatialAddress {
+            address: CommonAddress {
+                number,
+                street_name: street_name.to_string(),
+                street_type: Some(StreetNamePostType::STREET),
+                status: AddressStatus::Current,
+                ..Default::default()
+            },
+            x,
+            ..Default::default()
+        };
+        let first = Vintage::new(
+            "2023",
+            [
+                ("{A}".to_string(), address(100, "A", 0.0)),
+                ("{B}".to_string(), address(200, "B", 0.0)),
+                ("{C}".to_string(), address(300, "C", 0.0)),
+                ("{D}".to_string(), address(400, "D", 0.0)),
+            ],
+        );
+        let mut retired = address(400, "D", 0.0);
+        retired.address.status = AddressStatus::Retired;
+        let second = Vintage::new(
+            "2024",
+            [
+                ("{A}".to_string(), address(102, "A", 5.0)),
+                ("{B}".to_string(), address(200, "BIRCH", 50.0)),
+                ("{D}".to_string(), retired),
+                ("{E}".to_string(), address(500, "E", 0.0)),
+            ],
+        );
+        let records = LifecycleRecords::track(&[first.clone(), second], HISTORY_THRESHOLD);
+        let events = records
+            .iter()
+            .map(|r| (r.id.as_str(), r.event))
+            .collect::<Vec<(&str, LifecycleEvent)>>();
+        assert_eq!(
+            events,
+            vec![
+                ("{A}", LifecycleEvent::Renumbered),
+                ("{B}", LifecycleEvent::Renamed),
+                ("{B}", LifecycleEvent::Moved),
+                ("{C}", LifecycleEvent::Retired),
+                ("{D}", LifecycleEvent::Retired),
+                ("{E}", LifecycleEvent::Created),
+            ]
+        );
+        assert_eq!(records[0].previous, "100 A ST");
+        assert_eq!(records[0].label, "102 A ST");
+        assert_eq!(records[2].distance, Some(50.0));
+        assert_eq!(records[3].label, "");
+        assert!(records.iter().all(|r| r.vintage == "2024"));
+        assert_eq!(records.clone().filter("retired").len(), 2);
+        assert!(LifecycleRecords::track(&[first], HISTORY_THRESHOLD).is_empty());
+
+        let path = std::env::temp_dir().join("destination_history.csv");
+        let mut written = records.clone();
+        written.to_csv(&path)?;
+        assert_eq!(LifecycleRecords::from_csv(&path)?, records);
+        std::fs::remove_file(&path)?;
+
+        let cli = Cli::try_parse_from([
+            "destination",
+            "history",
+            "-s",
+            "2023.csv",
+            "2024.csv",
+            "2025.csv",
+            "--threshold",
+            "3",
+        ])?;
+        assert_eq!(
+            cli.command,
+            Command::History {
+                snapshots: vec!["2024.csv".into(), "2025.csv".into()],
+                threshold: Some(3.0)
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn id_stability_audit() -> anyhow::Result<()> {
+        let address = |number: i64, street_name: &str| SpatialAddress {
+            address: CommonAddress {
+                number,
+                street_name: street_name.to_string(),
+                street_type: Some(StreetNamePostType::STREET),
+                status: AddressStatus::Current,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut old = Vintage::new(
+            "2023",
+            [
+                ("{A}".to_string(), address(100, "A")),
+                ("{B}".to_string(), address(200, "B")),
+                ("{C}".to_string(), address(300, "C")),
+                ("{D}".to_string(), address(400, "D")),
+            ],
+        );
+        old.object_ids = [("{A}", 1), ("{B}", 2), ("{C}", 3), ("{D}", 4)]
+            .into_iter()
+            .map(|(id, object_id)| (id.to_string(), object_id))
+            .collect();
+        // A was deleted and recreated, B was reloaded, and D's Object ID went to a new address.
+        let mut new = Vintage::new(
+            "2024",
+            [
+                ("{A2}".to_string(), address(100, "A")),
+                ("{B}".to_string(), address(200, "B")),
+                ("{C}".to_string(), address(300, "C")),
+                ("{E}".to_string(), address(500, "E")),
+            ],
+        );
+        new.object_ids = [("{A2}", 5), ("{B}", 6), ("{C}", 3), ("{E}", 4)]
+            .into_iter()
+            .map(|(id, object_id)| (id.to_string(), object_id))
+            .collect();
+        let changes = IdChanges::audit(&old, &new);
+        let kinds = changes
+            .iter()
+            .map(|r| (r.kind, r.label.as_str()))
+            .collect::<Vec<(IdChangeKind, &str)>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (IdChangeKind::GlobalIdChanged, "100 A ST"),
+                (IdChangeKind::ObjectIdChanged, "200 B ST"),
+                (IdChangeKind::ObjectIdReused, "500 E ST"),
+            ]
+        );
+        assert_eq!(changes[0].new_global_id, "{A2}");
+        assert_eq!(changes[0].old_object_id, Some(1));
+        assert_eq!(changes[2].previous, "400 D ST");
+        assert_eq!(changes.count(IdChangeKind::ObjectIdReused), 1);
+        assert_eq!(changes.filter("object_id_changed").len(), 1);
+        Ok(())
+    }
+}
@@ -1,9 +1,14 @@
 use crate::{
     AddressError, AddressErrorKind, AddressStatus, Bincode, CommonAddress, CommonAddresses,
-    GeoAddress, GeoAddresses, IntoBin, IntoCsv, Io, SpatialAddress, SpatialAddresses, State,
-    StreetNamePostType, StreetNamePreDirectional, StreetNamePreModifier, StreetNamePreType,
-    StreetSeparator, SubaddressType, deserialize_arcgis_data, from_bin, from_csv, to_bin, to_csv,
+    FromArcGis, FromShapefile, GeoAddress, GeoAddresses, IntoBin, IntoCsv, IntoJson, IntoParquet,
+    Io, SpatialAddress, SpatialAddresses, State, StreetNamePostType, StreetNamePreDirectional,
+    StreetNamePreModifier, StreetNamePreType, StreetSeparator, SubaddressType,
+    deserialize_arcgis_data, from_arcgis_service, from_bin, from_csv, from_parquet, from_shapefile,
+    to_bin, to_csv, to_json, to_ndjson, to_parquet,
 };
+#[cfg(feature = "io")]
+use crate::{IntoPostgis, IntoSqlite, from_postgis, from_sqlite, to_postgis, to_sqlite};
+
 /// The `SpatialAddressRaw` struct defines the fields of a valid address, following the FGDC standard,
 /// with the inclusion of NENA-required fields for emergency response.
 #[derive(Debug, Default, Clone, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize)]
@@ -121,6 +126,34 @@ impl From<SpatialAddressRaw> for SpatialAddress {
     }
 }
 
+impl From<SpatialAddress> for SpatialAddressRaw {
+    fn from(value: SpatialAddress) -> Self {
+        let address = value.address;
+        Self {
+            number: address.number,
+            number_suffix: address.number_suffix,
+            directional: address.directional,
+            pre_modifier: address.pre_modifier,
+            pre_type: address.pre_type,
+            separator: address.separator,
+            street_name: address.street_name,
+            street_type: address.street_type,
+            subaddress_type: address.subaddress_type,
+            subaddress_id: address.subaddress_id,
+            floor: address.floor,
+            building: address.building,
+            zip: address.zip,
+            postal_community: address.postal_community,
+            state: address.state,
+            status: address.status,
+            latitude: value.latitude,
+            longitude: value.longitude,
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
 /// The `SpatialAddressesRaw` struct holds a vector of type [`SpatialAddressRaw`].
 #[derive(
     Debug,
@@ -165,6 +198,109 @@ impl From<SpatialAddressesRaw> for SpatialAddresses {
     }
 }
 
+impl From<&SpatialAddresses> for SpatialAddressesRaw {
+    fn from(value: &SpatialAddresses) -> Self {
+        let raw = value
+            .iter()
+            .map(|x| SpatialAddressRaw::from(x.clone()))
+            .collect::<Vec<SpatialAddressRaw>>();
+        Self(raw)
+    }
+}
+
+impl FromShapefile for SpatialAddresses {
+    fn from_shapefile<P: AsRef<std::path::Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        Ok(Self::from(SpatialAddressesRaw::from_shapefile(path)?))
+    }
+}
+
+impl FromArcGis for SpatialAddresses {
+    async fn from_arcgis_service<F, Fut>(url: &str, post: F) -> Result<Self, AddressErrorKind>
+    where
+        F: FnMut(String, String) -> Fut,
+        Fut: Future<Output = Result<String, AddressErrorKind>>,
+    {
+        Ok(Self::from(
+            SpatialAddressesRaw::from_arcgis_service(url, post).await?,
+        ))
+    }
+}
+
+#[cfg(feature = "io")]
+impl IntoPostgis<SpatialAddresses> for SpatialAddresses {
+    fn from_postgis(dsn: &str, table: &str) -> Result<Self, AddressError> {
+        Ok(Self::from(SpatialAddressesRaw::from_postgis(dsn, table)?))
+    }
+
+    fn to_postgis(&self, dsn: &str, table: &str) -> Result<(), AddressError> {
+        SpatialAddressesRaw::from(self).to_postgis(dsn, table)
+    }
+}
+
+#[cfg(feature = "io")]
+impl IntoPostgis<SpatialAddressesRaw> for SpatialAddressesRaw {
+    fn from_postgis(dsn: &str, table: &str) -> Result<Self, AddressError> {
+        let records = from_postgis(dsn, table)?;
+        Ok(Self(records))
+    }
+
+    fn to_postgis(&self, dsn: &str, table: &str) -> Result<(), AddressError> {
+        Ok(to_postgis(&self.0, dsn, table)?)
+    }
+}
+
+impl IntoParquet<SpatialAddresses> for SpatialAddresses {
+    fn from_parquet<P: AsRef<std::path::Path>>(path: P) -> Result<Self, AddressError> {
+        Ok(Self::from(SpatialAddressesRaw::from_parquet(path)?))
+    }
+
+    fn to_parquet<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressError> {
+        SpatialAddressesRaw::from(self).to_parquet(path)
+    }
+}
+
+impl IntoParquet<SpatialAddressesRaw> for SpatialAddressesRaw {
+    fn from_parquet<P: AsRef<std::path::Path>>(path: P) -> Result<Self, AddressError> {
+        let records = from_parquet(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_parquet<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressError> {
+        Ok(to_parquet(&self.0, path)?)
+    }
+}
+
+#[cfg(feature = "io")]
+impl IntoSqlite<SpatialAddresses> for SpatialAddresses {
+    fn from_sqlite<P: AsRef<std::path::Path>>(path: P, table: &str) -> Result<Self, AddressError> {
+        Ok(Self::from(SpatialAddressesRaw::from_sqlite(path, table)?))
+    }
+
+    fn to_sqlite<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        table: &str,
+    ) -> Result<(), AddressError> {
+        SpatialAddressesRaw::from(self).to_sqlite(path, table)
+    }
+}
+
+#[cfg(feature = "io")]
+impl IntoSqlite<SpatialAddressesRaw> for SpatialAddressesRaw {
+    fn from_sqlite<P: AsRef<std::path::Path>>(path: P, table: &str) -> Result<Self, AddressError> {
+        let records = from_sqlite(path, table)?;
+        Ok(Self(records))
+    }
+
+    fn to_sqlite<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        table: &str,
+    ) -> Result<(), AddressError> {
+        Ok(to_sqlite(&self.0, path, table)?)
+    }
+}
+
 impl IntoBin<SpatialAddressesRaw> for SpatialAddressesRaw {
     fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, AddressError> {
         match from_bin(path) {
@@ -175,7 +311,7 @@ impl IntoBin<SpatialAddressesRaw> for SpatialAddressesRaw {
     }
 
     fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -189,3 +325,31 @@ impl IntoCsv<SpatialAddressesRaw> for SpatialAddressesRaw {
         to_csv(&mut self.0, path.as_ref().into())
     }
 }
+
+impl IntoJson for SpatialAddressesRaw {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+impl FromShapefile for SpatialAddressesRaw {
+    fn from_shapefile<P: AsRef<std::path::Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        let records = from_shapefile(path)?;
+        Ok(Self(records))
+    }
+}
+
+impl FromArcGis for SpatialAddressesRaw {
+    async fn from_arcgis_service<F, Fut>(url: &str, post: F) -> Result<Self, AddressErrorKind>
+    where
+        F: FnMut(String, String) -> Fut,
+        Fut: Future<Output = Result<String, AddressErrorKind>>,
+    {
+        let records = from_arcgis_service(url, post).await?;
+        Ok(Self(records))
+    }
+}
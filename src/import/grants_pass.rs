@@ -2,9 +2,10 @@
 //! Pass.
 use crate::{
     Address, AddressError, AddressErrorKind, AddressStatus, Addresses, Bincode, Cartesian,
-    Geographic, IntoBin, IntoCsv, Io, State, StreetNamePostType, StreetNamePreDirectional,
-    StreetNamePreModifier, StreetNamePreType, StreetSeparator, SubaddressType,
-    deserialize_arcgis_data, from_bin, from_csv, to_bin, to_csv,
+    FromArcGis, FromShapefile, Geographic, IntoBin, IntoCsv, Io, State, StreetNamePostType,
+    StreetNamePreDirectional, StreetNamePreModifier, StreetNamePreType, StreetSeparator,
+    SubaddressType, deserialize_arcgis_data, from_arcgis_service, from_bin, from_csv,
+    from_shapefile, to_bin, to_csv,
 };
 use derive_more::{Deref, DerefMut};
 use serde::{Deserialize, Serialize};
@@ -284,7 +285,7 @@ impl IntoBin<GrantsPassAddresses> for GrantsPassAddresses {
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -367,6 +368,9 @@ pub struct GrantsPassSpatialAddress {
     /// The `notes` field holds any text note associated with the address.
     #[serde(deserialize_with = "deserialize_arcgis_data", rename = "NOTES")]
     pub notes: Option<String>,
+    /// The `object_id` field holds the ESRI Object ID of the feature, if present in the export.
+    #[serde(default, deserialize_with = "csv::invalid_option", rename = "OBJECTID")]
+    pub object_id: Option<i64>,
     /// The `global_id` field holds the ESRI Global ID associated with the feature.
     #[serde(rename(serialize = "GlobalID", deserialize = "GlobalID"))]
     pub global_id: String,
@@ -608,7 +612,7 @@ impl IntoBin<GrantsPassSpatialAddresses> for GrantsPassSpatialAddresses {
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -622,3 +626,21 @@ impl IntoCsv<GrantsPassSpatialAddresses> for GrantsPassSpatialAddresses {
         to_csv(&mut self.0, path.as_ref().into())
     }
 }
+
+impl FromShapefile for GrantsPassSpatialAddresses {
+    fn from_shapefile<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        let records = from_shapefile(path)?;
+        Ok(Self(records))
+    }
+}
+
+impl FromArcGis for GrantsPassSpatialAddresses {
+    async fn from_arcgis_service<F, Fut>(url: &str, post: F) -> Result<Self, AddressErrorKind>
+    where
+        F: FnMut(String, String) -> Fut,
+        Fut: Future<Output = Result<String, AddressErrorKind>>,
+    {
+        let records = from_arcgis_service(url, post).await?;
+        Ok(Self(records))
+    }
+}
@@ -210,7 +210,7 @@ impl IntoBin<Businesses> for Businesses {
     }
 
     fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
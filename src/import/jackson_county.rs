@@ -0,0 +1,591 @@
+//! The `jackson_county` module contains data types for importing addresses from Jackson County.
+//! The county records directionals and post types as postal abbreviations, read into the full
+//! names used by the other address types.
+use crate::{
+    Address, AddressError, AddressErrorKind, AddressStatus, Addresses, Bincode, Cartesian,
+    FromArcGis, FromShapefile, Geographic, IntoBin, IntoCsv, Io, State, StreetNamePostType,
+    StreetNamePreDirectional, StreetNamePreModifier, StreetNamePreType, StreetSeparator,
+    SubaddressType, deserialize_arcgis_data, from_arcgis_service, from_bin, from_csv,
+    from_shapefile, to_bin, to_csv, zero_floor,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The `JacksonCountyAddress` struct represents an address site point for Jackson County.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub struct JacksonCountyAddress {
+    /// The `address_number` field represents the address number component of the complete address
+    /// number.
+    #[serde(rename = "ADD_NUM")]
+    pub address_number: i64,
+    /// The `address_number_suffix` field represents the address number suffix component of the
+    /// complete address number.
+    #[serde(deserialize_with = "deserialize_arcgis_data", rename = "ADD_NUM_SUF")]
+    pub address_number_suffix: Option<String>,
+    /// The `street_name_pre_directional` field represents the street name pre directional
+    /// component of the complete street name.  The county records the postal abbreviation, such as
+    /// "N" or "SW".
+    #[serde(
+        deserialize_with = "StreetNamePreDirectional::deserialize_mixed",
+        rename = "PRE_DIR"
+    )]
+    pub street_name_pre_directional: Option<StreetNamePreDirectional>,
+    /// The `street_name_pre_modifier` field represents the street name pre modifier component of
+    /// the complete street name.
+    #[serde(
+        deserialize_with = "StreetNamePreModifier::deserialize_mixed",
+        rename = "PRE_MOD"
+    )]
+    pub street_name_pre_modifier: Option<StreetNamePreModifier>,
+    /// The `street_name_pre_type` field represents the street name pre type component of the
+    /// complete street name.
+    #[serde(
+        deserialize_with = "StreetNamePreType::deserialize_mixed",
+        rename = "PRE_TYPE"
+    )]
+    pub street_name_pre_type: Option<StreetNamePreType>,
+    /// The `street_name_separator` field represents the separator element component of the
+    /// complete street name.
+    #[serde(
+        deserialize_with = "StreetSeparator::deserialize_mixed",
+        rename = "PRE_SEP"
+    )]
+    pub street_name_separator: Option<StreetSeparator>,
+    /// The `street_name` field represents the street name component of the complete street name.
+    #[serde(rename = "ST_NAME")]
+    pub street_name: String,
+    /// The `street_name_post_type` field represents the street name post type component of the
+    /// complete street name.  The county records the postal abbreviation, such as "AVE" or "DR".
+    #[serde(
+        deserialize_with = "StreetNamePostType::deserialize_mixed",
+        rename = "ST_TYPE"
+    )]
+    pub street_name_post_type: Option<StreetNamePostType>,
+    /// The `subaddress_type` field represents the subaddress type component of the complete
+    /// subaddress.
+    #[serde(
+        deserialize_with = "SubaddressType::deserialize_mixed",
+        rename = "UNIT_TYPE"
+    )]
+    pub subaddress_type: Option<SubaddressType>,
+    /// The `subaddress_identifier` field represents the subaddress identifier component of the
+    /// complete subaddress.
+    #[serde(deserialize_with = "deserialize_arcgis_data", rename = "UNIT_ID")]
+    pub subaddress_identifier: Option<String>,
+    /// The `floor` field represents the floor identifier, corresponding to the `Floor` field from
+    /// the NENA standard.
+    #[serde(deserialize_with = "zero_floor", rename = "FLOOR")]
+    pub floor: Option<i64>,
+    /// The `building` field represents the building identifier of the address.
+    #[serde(deserialize_with = "deserialize_arcgis_data", rename = "BLDG")]
+    pub building: Option<String>,
+    /// The `postal_community` field represents the postal community component of the address.
+    #[serde(rename = "CITY")]
+    pub postal_community: String,
+    /// The `zip_code` field represents the postal zip code of the address.
+    #[serde(rename = "ZIP")]
+    pub zip_code: i64,
+    /// The `state_name` field represents the state name component of the address.
+    #[serde(deserialize_with = "State::deserialize_mixed", rename = "STATE")]
+    pub state_name: State,
+    /// The `status` field represents the status of the address.  The county export does not
+    /// carry a status, so addresses default to [`AddressStatus::Other`] unless the column is
+    /// present.
+    #[serde(default, rename = "STATUS")]
+    pub status: AddressStatus,
+}
+
+impl Address for JacksonCountyAddress {
+    fn number(&self) -> i64 {
+        self.address_number
+    }
+
+    fn number_mut(&mut self) -> &mut i64 {
+        &mut self.address_number
+    }
+
+    fn number_suffix(&self) -> &Option<String> {
+        &self.address_number_suffix
+    }
+
+    fn number_suffix_mut(&mut self) -> &mut Option<String> {
+        &mut self.address_number_suffix
+    }
+
+    fn directional(&self) -> &Option<StreetNamePreDirectional> {
+        &self.street_name_pre_directional
+    }
+
+    fn directional_mut(&mut self) -> &mut Option<StreetNamePreDirectional> {
+        &mut self.street_name_pre_directional
+    }
+
+    fn street_name_pre_modifier(&self) -> &Option<StreetNamePreModifier> {
+        &self.street_name_pre_modifier
+    }
+
+    fn street_name_pre_modifier_mut(&mut self) -> &mut Option<StreetNamePreModifier> {
+        &mut self.street_name_pre_modifier
+    }
+
+    fn street_name_pre_type(&self) -> &Option<StreetNamePreType> {
+        &self.street_name_pre_type
+    }
+
+    fn street_name_pre_type_mut(&mut self) -> &mut Option<StreetNamePreType> {
+        &mut self.street_name_pre_type
+    }
+
+    fn street_name_separator(&self) -> &Option<StreetSeparator> {
+        &self.street_name_separator
+    }
+
+    fn street_name_separator_mut(&mut self) -> &mut Option<StreetSeparator> {
+        &mut self.street_name_separator
+    }
+
+    fn street_name(&self) -> &String {
+        &self.street_name
+    }
+
+    fn street_name_mut(&mut self) -> &mut String {
+        &mut self.street_name
+    }
+
+    fn street_type(&self) -> &Option<StreetNamePostType> {
+        &self.street_name_post_type
+    }
+
+    fn street_type_mut(&mut self) -> &mut Option<StreetNamePostType> {
+        &mut self.street_name_post_type
+    }
+
+    fn subaddress_id(&self) -> &Option<String> {
+        &self.subaddress_identifier
+    }
+
+    fn subaddress_id_mut(&mut self) -> &mut Option<String> {
+        &mut self.subaddress_identifier
+    }
+
+    fn subaddress_type(&self) -> &Option<SubaddressType> {
+        &self.subaddress_type
+    }
+
+    fn subaddress_type_mut(&mut self) -> &mut Option<SubaddressType> {
+        &mut self.subaddress_type
+    }
+
+    fn floor(&self) -> &Option<i64> {
+        &self.floor
+    }
+
+    fn floor_mut(&mut self) -> &mut Option<i64> {
+        &mut self.floor
+    }
+
+    fn building(&self) -> &Option<String> {
+        &self.building
+    }
+
+    fn building_mut(&mut self) -> &mut Option<String> {
+        &mut self.building
+    }
+
+    fn zip(&self) -> i64 {
+        self.zip_code
+    }
+
+    fn zip_mut(&mut self) -> &mut i64 {
+        &mut self.zip_code
+    }
+
+    fn postal_community(&self) -> &String {
+        &self.postal_community
+    }
+
+    fn postal_community_mut(&mut self) -> &mut String {
+        &mut self.postal_community
+    }
+
+    fn state(&self) -> &State {
+        &self.state_name
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state_name
+    }
+
+    fn status(&self) -> &AddressStatus {
+        &self.status
+    }
+
+    fn status_mut(&mut self) -> &mut AddressStatus {
+        &mut self.status
+    }
+}
+
+/// The `JacksonCountyAddresses` struct holds a vector of type [`JacksonCountyAddress`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Deserialize, Serialize, Deref, DerefMut)]
+pub struct JacksonCountyAddresses(Vec<JacksonCountyAddress>);
+
+impl Addresses<JacksonCountyAddress> for JacksonCountyAddresses {}
+
+impl IntoBin<JacksonCountyAddresses> for JacksonCountyAddresses {
+    fn load<P: AsRef<Path>>(path: P) -> Result<Self, AddressError> {
+        match from_bin(path) {
+            Ok(records) => bincode::deserialize::<Self>(&records)
+                .map_err(|source| Bincode::new(source, line!(), file!().into()).into()),
+            Err(source) => Err(AddressErrorKind::from(source).into()),
+        }
+    }
+
+    fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
+        to_bin(self, self.len(), path)
+    }
+}
+
+impl IntoCsv<JacksonCountyAddresses> for JacksonCountyAddresses {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+/// The `JacksonCountySpatialAddress` struct represents an address site point for Jackson County
+/// that includes geographic and projected coordinate information.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub struct JacksonCountySpatialAddress {
+    /// The `address_number` field represents the address number component of the complete address
+    /// number.
+    #[serde(rename = "ADD_NUM")]
+    pub address_number: i64,
+    /// The `address_number_suffix` field represents the address number suffix component of the
+    /// complete address number.
+    #[serde(deserialize_with = "deserialize_arcgis_data", rename = "ADD_NUM_SUF")]
+    pub address_number_suffix: Option<String>,
+    /// The `street_name_pre_directional` field represents the street name pre directional
+    /// component of the complete street name.  The county records the postal abbreviation, such as
+    /// "N" or "SW".
+    #[serde(
+        deserialize_with = "StreetNamePreDirectional::deserialize_mixed",
+        rename = "PRE_DIR"
+    )]
+    pub street_name_pre_directional: Option<StreetNamePreDirectional>,
+    /// The `street_name_pre_modifier` field represents the street name pre modifier component of
+    /// the complete street name.
+    #[serde(
+        deserialize_with = "StreetNamePreModifier::deserialize_mixed",
+        rename = "PRE_MOD"
+    )]
+    pub street_name_pre_modifier: Option<StreetNamePreModifier>,
+    /// The `street_name_pre_type` field represents the street name pre type component of the
+    /// complete street name.
+    #[serde(
+        deserialize_with = "StreetNamePreType::deserialize_mixed",
+        rename = "PRE_TYPE"
+    )]
+    pub street_name_pre_type: Option<StreetNamePreType>,
+    /// The `street_name_separator` field represents the separator element component of the
+    /// complete street name.
+    #[serde(
+        deserialize_with = "StreetSeparator::deserialize_mixed",
+        rename = "PRE_SEP"
+    )]
+    pub street_name_separator: Option<StreetSeparator>,
+    /// The `street_name` field represents the street name component of the complete street name.
+    #[serde(rename = "ST_NAME")]
+    pub street_name: String,
+    /// The `street_name_post_type` field represents the street name post type component of the
+    /// complete street name.  The county records the postal abbreviation, such as "AVE" or "DR".
+    #[serde(
+        deserialize_with = "StreetNamePostType::deserialize_mixed",
+        rename = "ST_TYPE"
+    )]
+    pub street_name_post_type: Option<StreetNamePostType>,
+    /// The `subaddress_type` field represents the subaddress type component of the complete
+    /// subaddress.
+    #[serde(
+        deserialize_with = "SubaddressType::deserialize_mixed",
+        rename = "UNIT_TYPE"
+    )]
+    pub subaddress_type: Option<SubaddressType>,
+    /// The `subaddress_identifier` field represents the subaddress identifier component of the
+    /// complete subaddress.
+    #[serde(deserialize_with = "deserialize_arcgis_data", rename = "UNIT_ID")]
+    pub subaddress_identifier: Option<String>,
+    /// The `floor` field represents the floor identifier, corresponding to the `Floor` field from
+    /// the NENA standard.
+    #[serde(deserialize_with = "zero_floor", rename = "FLOOR")]
+    pub floor: Option<i64>,
+    /// The `building` field represents the building identifier of the address.
+    #[serde(deserialize_with = "deserialize_arcgis_data", rename = "BLDG")]
+    pub building: Option<String>,
+    /// The `postal_community` field represents the postal community component of the address.
+    #[serde(rename = "CITY")]
+    pub postal_community: String,
+    /// The `zip_code` field represents the postal zip code of the address.
+    #[serde(rename = "ZIP")]
+    pub zip_code: i64,
+    /// The `state_name` field represents the state name component of the address.
+    #[serde(deserialize_with = "State::deserialize_mixed", rename = "STATE")]
+    pub state_name: State,
+    /// The `status` field represents the status of the address.  The county export does not
+    /// carry a status, so addresses default to [`AddressStatus::Other`] unless the column is
+    /// present.
+    #[serde(default, rename = "STATUS")]
+    pub status: AddressStatus,
+    /// The `x` field represents the cartesian X portion of the projected coordinates of the
+    /// address.
+    #[serde(rename = "POINT_X")]
+    pub x: f64,
+    /// The `y` field represents the cartesian Y portion of the projected coordinates of the
+    /// address.
+    #[serde(rename = "POINT_Y")]
+    pub y: f64,
+    /// The `lat` field represents the latitude of the geographic coordinates for the address.
+    #[serde(rename = "LAT")]
+    pub lat: f64,
+    /// The `lon` field represents the longitude of the geographic coordinates for the address.
+    #[serde(rename = "LON")]
+    pub lon: f64,
+}
+
+impl Address for JacksonCountySpatialAddress {
+    fn number(&self) -> i64 {
+        self.address_number
+    }
+
+    fn number_mut(&mut self) -> &mut i64 {
+        &mut self.address_number
+    }
+
+    fn number_suffix(&self) -> &Option<String> {
+        &self.address_number_suffix
+    }
+
+    fn number_suffix_mut(&mut self) -> &mut Option<String> {
+        &mut self.address_number_suffix
+    }
+
+    fn directional(&self) -> &Option<StreetNamePreDirectional> {
+        &self.street_name_pre_directional
+    }
+
+    fn directional_mut(&mut self) -> &mut Option<StreetNamePreDirectional> {
+        &mut self.street_name_pre_directional
+    }
+
+    fn street_name_pre_modifier(&self) -> &Option<StreetNamePreModifier> {
+        &self.street_name_pre_modifier
+    }
+
+    fn street_name_pre_modifier_mut(&mut self) -> &mut Option<StreetNamePreModifier> {
+        &mut self.street_name_pre_modifier
+    }
+
+    fn street_name_pre_type(&self) -> &Option<StreetNamePreType> {
+        &self.street_name_pre_type
+    }
+
+    fn street_name_pre_type_mut(&mut self) -> &mut Option<StreetNamePreType> {
+        &mut self.street_name_pre_type
+    }
+
+    fn street_name_separator(&self) -> &Option<StreetSeparator> {
+        &self.street_name_separator
+    }
+
+    fn street_name_separator_mut(&mut self) -> &mut Option<StreetSeparator> {
+        &mut self.street_name_separator
+    }
+
+    fn street_name(&self) -> &String {
+        &self.street_name
+    }
+
+    fn street_name_mut(&mut self) -> &mut String {
+        &mut self.street_name
+    }
+
+    fn street_type(&self) -> &Option<StreetNamePostType> {
+        &self.street_name_post_type
+    }
+
+    fn street_type_mut(&mut self) -> &mut Option<StreetNamePostType> {
+        &mut self.street_name_post_type
+    }
+
+    fn subaddress_id(&self) -> &Option<String> {
+        &self.subaddress_identifier
+    }
+
+    fn subaddress_id_mut(&mut self) -> &mut Option<String> {
+        &mut self.subaddress_identifier
+    }
+
+    fn subaddress_type(&self) -> &Option<SubaddressType> {
+        &self.subaddress_type
+    }
+
+    fn subaddress_type_mut(&mut self) -> &mut Option<SubaddressType> {
+        &mut self.subaddress_type
+    }
+
+    fn floor(&self) -> &Option<i64> {
+        &self.floor
+    }
+
+    fn floor_mut(&mut self) -> &mut Option<i64> {
+        &mut self.floor
+    }
+
+    fn building(&self) -> &Option<String> {
+        &self.building
+    }
+
+    fn building_mut(&mut self) -> &mut Option<String> {
+        &mut self.building
+    }
+
+    fn zip(&self) -> i64 {
+        self.zip_code
+    }
+
+    fn zip_mut(&mut self) -> &mut i64 {
+        &mut self.zip_code
+    }
+
+    fn postal_community(&self) -> &String {
+        &self.postal_community
+    }
+
+    fn postal_community_mut(&mut self) -> &mut String {
+        &mut self.postal_community
+    }
+
+    fn state(&self) -> &State {
+        &self.state_name
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state_name
+    }
+
+    fn status(&self) -> &AddressStatus {
+        &self.status
+    }
+
+    fn status_mut(&mut self) -> &mut AddressStatus {
+        &mut self.status
+    }
+}
+
+impl Cartesian for JacksonCountySpatialAddress {
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+impl Geographic for JacksonCountySpatialAddress {
+    fn latitude(&self) -> f64 {
+        self.lat
+    }
+
+    fn longitude(&self) -> f64 {
+        self.lon
+    }
+}
+
+/// The `JacksonCountySpatialAddresses` struct holds a vector of type [`JacksonCountySpatialAddress`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Deserialize, Serialize, Deref, DerefMut)]
+pub struct JacksonCountySpatialAddresses(Vec<JacksonCountySpatialAddress>);
+
+impl Addresses<JacksonCountySpatialAddress> for JacksonCountySpatialAddresses {}
+
+impl IntoBin<JacksonCountySpatialAddresses> for JacksonCountySpatialAddresses {
+    fn load<P: AsRef<Path>>(path: P) -> Result<Self, AddressError> {
+        match from_bin(path) {
+            Ok(records) => bincode::deserialize::<Self>(&records)
+                .map_err(|source| Bincode::new(source, line!(), file!().into()).into()),
+            Err(source) => Err(AddressErrorKind::from(source).into()),
+        }
+    }
+
+    fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
+        to_bin(self, self.len(), path)
+    }
+}
+
+impl IntoCsv<JacksonCountySpatialAddresses> for JacksonCountySpatialAddresses {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl FromShapefile for JacksonCountySpatialAddresses {
+    fn from_shapefile<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        let records = from_shapefile(path)?;
+        Ok(Self(records))
+    }
+}
+
+impl FromArcGis for JacksonCountySpatialAddresses {
+    async fn from_arcgis_service<F, Fut>(url: &str, post: F) -> Result<Self, AddressErrorKind>
+    where
+        F: FnMut(String, String) -> Fut,
+        Fut: Future<Output = Result<String, AddressErrorKind>>,
+    {
+        let records = from_arcgis_service(url, post).await?;
+        Ok(Self(records))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommonAddresses, SpatialAddresses};
+
+    #[test]
+    fn jackson_county_addresses() -> anyhow::Result<()> {
+        let addresses = JacksonCountySpatialAddresses::from_csv("data/jackson_county_sample.csv")?;
+        assert_eq!(addresses.len(), 3);
+        assert_eq!(
+            addresses[0].street_name_pre_directional,
+            Some(StreetNamePreDirectional::NORTH)
+        );
+        assert_eq!(
+            addresses[0].street_name_post_type,
+            Some(StreetNamePostType::AVENUE)
+        );
+        assert_eq!(
+            addresses[1].subaddress_type,
+            Some(SubaddressType::Apartment)
+        );
+        assert_eq!(addresses[1].subaddress_identifier, Some("12".to_string()));
+        assert_eq!(addresses[1].building, Some("B".to_string()));
+        assert_eq!(addresses[2].address_number_suffix, Some("1/2".to_string()));
+        assert_eq!(addresses[2].floor, None);
+        assert_eq!(addresses[2].status, AddressStatus::Other);
+
+        let common = CommonAddresses::from(&addresses[..]);
+        assert_eq!(common[0].label(), "120 N CENTRAL AVE");
+        let spatial = SpatialAddresses::from(&addresses[..]);
+        assert_eq!(spatial[2].postal_community(), "ASHLAND");
+        Ok(())
+    }
+}
@@ -2,9 +2,10 @@
 //! Josephine County.
 use crate::{
     Address, AddressError, AddressErrorKind, AddressStatus, Addresses, Bincode, Cartesian,
-    Geographic, IntoBin, IntoCsv, Io, State, StreetNamePostType, StreetNamePreDirectional,
-    StreetNamePreModifier, StreetNamePreType, StreetSeparator, SubaddressType,
-    deserialize_arcgis_data, from_bin, from_csv, to_bin, to_csv, zero_floor,
+    FromArcGis, FromShapefile, Geographic, IntoBin, IntoCsv, Io, State, StreetNamePostType,
+    StreetNamePreDirectional, StreetNamePreModifier, StreetNamePreType, StreetSeparator,
+    SubaddressType, deserialize_arcgis_data, from_arcgis_service, from_bin, from_csv,
+    from_shapefile, to_bin, to_csv, zero_floor,
 };
 use derive_more::{Deref, DerefMut};
 use serde::{Deserialize, Serialize};
@@ -240,7 +241,7 @@ impl IntoBin<JosephineCountyAddresses2024> for JosephineCountyAddresses2024 {
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -336,6 +337,28 @@ pub struct JosephineCountySpatialAddress2024 {
     /// The `status` field represents the local status of the address as determined by the relevant
     /// addressing authority.
     pub status: AddressStatus,
+    /// The `esn` field represents the Emergency Service Number recorded for the address, used to
+    /// route emergency calls.
+    #[serde(default, deserialize_with = "deserialize_arcgis_data")]
+    pub esn: Option<String>,
+    /// The `global_id` field holds the ESRI Global ID associated with the feature, if present.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_arcgis_data",
+        rename = "globalid"
+    )]
+    pub global_id: Option<String>,
+    /// The `object_id` field holds the ESRI Object ID of the feature, if present in the export.
+    #[serde(default, deserialize_with = "csv::invalid_option", rename = "objectid")]
+    pub object_id: Option<i64>,
+    /// The `date_updated` field holds the date-time stamp of the last update to the feature, if
+    /// present.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_arcgis_data",
+        rename = "dateupdate"
+    )]
+    pub date_updated: Option<String>,
     /// The `x` field represents the cartesian X portion of the projected coordinates of the
     /// address.
     pub x: f64,
@@ -517,7 +540,7 @@ impl IntoBin<JosephineCountySpatialAddresses2024> for JosephineCountySpatialAddr
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -532,6 +555,24 @@ impl IntoCsv<JosephineCountySpatialAddresses2024> for JosephineCountySpatialAddr
     }
 }
 
+impl FromShapefile for JosephineCountySpatialAddresses2024 {
+    fn from_shapefile<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind> {
+        let records = from_shapefile(path)?;
+        Ok(Self(records))
+    }
+}
+
+impl FromArcGis for JosephineCountySpatialAddresses2024 {
+    async fn from_arcgis_service<F, Fut>(url: &str, post: F) -> Result<Self, AddressErrorKind>
+    where
+        F: FnMut(String, String) -> Fut,
+        Fut: Future<Output = Result<String, AddressErrorKind>>,
+    {
+        let records = from_arcgis_service(url, post).await?;
+        Ok(Self(records))
+    }
+}
+
 /// The `JosephineCountyAddress` struct represents an address site point for Josephine County,
 /// prior to the schema adopted by the agency in April of 2024.
 #[derive(Clone, Debug, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
@@ -767,7 +808,7 @@ impl IntoBin<JosephineCountyAddresses> for JosephineCountyAddresses {
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -1051,7 +1092,7 @@ impl IntoBin<JosephineCountySpatialAddresses> for JosephineCountySpatialAddresse
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -0,0 +1,341 @@
+//! The `mapped` module contains a loader for address files with an arbitrary CSV schema.  A TOML
+//! column map names the CSV header that holds each address field, and the format used to read
+//! its values, so a new source can be read without a dedicated address type.
+//!
+//! ```toml
+//! [[column]]
+//! header = "ADD_NUM"
+//! field = "number"
+//!
+//! [[column]]
+//! header = "PRE_DIR"
+//! field = "directional"
+//! format = "abbreviated"
+//!
+//! [[column]]
+//! header = "UNIT"
+//! field = "subaddress_id"
+//! format = "arcgis"
+//! ```
+use crate::{
+    Address, AddressError, AddressErrorKind, Addresses, Bincode, Config, Csv, IntoBin, ParseError,
+    SpatialAddress, State, StreetNamePostType, StreetNamePreDirectional, StreetNamePreModifier,
+    StreetNamePreType, StreetSeparator, SubaddressType, from_bin, open_csv, to_bin,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+/// The `AddressField` enum names the address field that a mapped CSV column populates.  The
+/// variants follow the field names of [`crate::CommonAddress`], plus the coordinates of
+/// [`SpatialAddress`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressField {
+    /// The address number.
+    Number,
+    /// The address number suffix.
+    NumberSuffix,
+    /// The street name pre directional.
+    Directional,
+    /// The street name pre modifier.
+    PreModifier,
+    /// The street name pre type.
+    PreType,
+    /// The street name separator.
+    Separator,
+    /// The street name.
+    StreetName,
+    /// The street name post type.
+    StreetType,
+    /// The subaddress type.
+    SubaddressType,
+    /// The subaddress identifier.
+    SubaddressId,
+    /// The floor identifier.
+    Floor,
+    /// The building identifier.
+    Building,
+    /// The postal zip code.
+    Zip,
+    /// The postal community.
+    PostalCommunity,
+    /// The state name.
+    State,
+    /// The address status.
+    Status,
+    /// The latitude of the geographic coordinates.
+    Latitude,
+    /// The longitude of the geographic coordinates.
+    Longitude,
+    /// The cartesian X portion of the projected coordinates.
+    X,
+    /// The cartesian Y portion of the projected coordinates.
+    Y,
+}
+
+/// The `ColumnFormat` enum selects how the values of a mapped CSV column are read, mirroring the
+/// deserializers used by the agency address types.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnFormat {
+    /// Reads the value as written.  Street name components accept any spelling recognized by
+    /// their `match_mixed` functions, and empty values read as missing.
+    #[default]
+    Plain,
+    /// Reads street name components that observe the official postal abbreviation, such as "NW"
+    /// or "APT".  Components without an abbreviation matcher fall back to `match_mixed`.
+    Abbreviated,
+    /// Reads the "<Null>" placeholder written by ArcGIS exports as a missing value.  See
+    /// [`crate::deserialize_arcgis_data`].
+    Arcgis,
+    /// Reads a floor of zero as a missing value.  See [`crate::zero_floor`].
+    ZeroFloor,
+}
+
+/// The `ColumnMapping` struct maps the CSV column named `header` to the address field `field`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ColumnMapping {
+    /// The `header` field is the name of the column in the CSV header row.
+    pub header: String,
+    /// The `field` field is the address field populated by the column.
+    pub field: AddressField,
+    /// The `format` field selects how values in the column are read.
+    #[serde(default)]
+    pub format: ColumnFormat,
+}
+
+impl ColumnMapping {
+    // Reads `value` into the mapped field of `address`, returning a description of the problem
+    // when the value cannot be read.
+    fn assign(&self, address: &mut SpatialAddress, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        let text = match (self.format, value) {
+            (_, "") => None,
+            (ColumnFormat::Arcgis, "<Null>") => None,
+            (_, other) => Some(other),
+        };
+        let abbreviated = self.format == ColumnFormat::Abbreviated;
+        let fail = || format!("invalid {:?} value {value:?}", self.field);
+        match self.field {
+            AddressField::Number => {
+                *address.number_mut() = text.and_then(|t| t.parse().ok()).ok_or_else(fail)?
+            }
+            AddressField::NumberSuffix => *address.number_suffix_mut() = text.map(String::from),
+            AddressField::Directional => {
+                *address.directional_mut() = text.and_then(|t| {
+                    if abbreviated {
+                        StreetNamePreDirectional::match_abbreviated(t)
+                    } else {
+                        StreetNamePreDirectional::match_mixed(t)
+                    }
+                })
+            }
+            AddressField::PreModifier => {
+                *address.street_name_pre_modifier_mut() =
+                    text.and_then(StreetNamePreModifier::match_mixed)
+            }
+            AddressField::PreType => {
+                *address.street_name_pre_type_mut() = text.and_then(StreetNamePreType::match_mixed)
+            }
+            AddressField::Separator => {
+                *address.street_name_separator_mut() = text.and_then(StreetSeparator::match_mixed)
+            }
+            AddressField::StreetName => {
+                *address.street_name_mut() = text.ok_or_else(fail)?.to_string()
+            }
+            AddressField::StreetType => {
+                *address.street_type_mut() = text.and_then(StreetNamePostType::match_mixed)
+            }
+            AddressField::SubaddressType => {
+                *address.subaddress_type_mut() = text.and_then(|t| {
+                    if abbreviated {
+                        SubaddressType::match_abbreviated(t)
+                    } else {
+                        SubaddressType::match_mixed(t)
+                    }
+                })
+            }
+            AddressField::SubaddressId => *address.subaddress_id_mut() = text.map(String::from),
+            AddressField::Floor => {
+                let floor = match text {
+                    Some(t) => Some(t.parse::<i64>().map_err(|_| fail())?),
+                    None => None,
+                };
+                *address.floor_mut() = match self.format {
+                    ColumnFormat::ZeroFloor => floor.filter(|f| *f != 0),
+                    _ => floor,
+                };
+            }
+            AddressField::Building => *address.building_mut() = text.map(String::from),
+            AddressField::Zip => {
+                *address.zip_mut() = text.and_then(|t| t.parse().ok()).ok_or_else(fail)?
+            }
+            AddressField::PostalCommunity => {
+                *address.postal_community_mut() = text.unwrap_or_default().to_string()
+            }
+            AddressField::State => {
+                let state = if abbreviated {
+                    text.and_then(State::match_abbreviated)
+                } else {
+                    text.and_then(State::match_mixed)
+                };
+                *address.state_mut() = state.ok_or_else(fail)?;
+            }
+            AddressField::Status => {
+                *address.status_mut() = match text {
+                    Some(t) => t.parse().map_err(|_| fail())?,
+                    None => Default::default(),
+                }
+            }
+            AddressField::Latitude => address.latitude = parse_coordinate(text).ok_or_else(fail)?,
+            AddressField::Longitude => {
+                address.longitude = parse_coordinate(text).ok_or_else(fail)?
+            }
+            AddressField::X => address.x = parse_coordinate(text).ok_or_else(fail)?,
+            AddressField::Y => address.y = parse_coordinate(text).ok_or_else(fail)?,
+        }
+        Ok(())
+    }
+}
+
+// Coordinates are required when mapped, so a missing value is an error rather than zero.
+fn parse_coordinate(text: Option<&str>) -> Option<f64> {
+    text.and_then(|t| t.parse().ok())
+}
+
+/// The `ColumnMap` struct holds the column mappings for a CSV address schema, read from the
+/// `[[column]]` tables of a TOML file.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ColumnMap {
+    /// The `columns` field holds the mapping for each CSV column read into an address.
+    #[serde(default, rename = "column")]
+    pub columns: Vec<ColumnMapping>,
+}
+
+impl ColumnMap {
+    /// The `from_toml` method reads a column map from the TOML file at `path`.
+    pub fn from_toml<P: AsRef<Path>>(path: P) -> Result<Self, AddressError> {
+        info!("Reading column map from {:?}", path.as_ref());
+        let map: Self = crate::config::from_toml(path)?;
+        info!("Columns mapped: {}", map.columns.len());
+        Ok(map)
+    }
+}
+
+/// The `MappedAddresses` struct holds a vector of [`SpatialAddress`] read from a CSV file using a
+/// [`ColumnMap`].  Fields without a mapped column keep their default values.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Deserialize, Serialize, Deref, DerefMut)]
+pub struct MappedAddresses(Vec<SpatialAddress>);
+
+impl MappedAddresses {
+    /// The `from_csv_with_map` method reads the CSV file at `path`, populating the fields of each
+    /// address from the columns named in the TOML column map at `mapping`.  Rows with values that
+    /// cannot be read are handled according to [`crate::set_on_error`], as with
+    /// [`crate::from_csv`].  Returns an error if the file
+    /// lacks a mapped header.
+    pub fn from_csv_with_map<P: AsRef<Path>, Q: AsRef<Path>>(
+        path: P,
+        mapping: Q,
+    ) -> Result<Self, AddressError> {
+        let map = ColumnMap::from_toml(&mapping)?;
+        let path = path.as_ref();
+        let mut rdr = csv::Reader::from_reader(open_csv(path)?);
+        let headers = rdr
+            .headers()
+            .map_err(|source| {
+                AddressErrorKind::from(Csv::new(path.into(), source, line!(), file!().into()))
+            })?
+            .clone();
+        let mut columns = Vec::new();
+        for column in &map.columns {
+            match headers.iter().position(|h| h.trim() == column.header) {
+                Some(index) => columns.push((index, column)),
+                None => {
+                    let issue = format!("header {} not found in {path:?}", column.header);
+                    return Err(AddressErrorKind::from(Config::new(
+                        mapping.as_ref().into(),
+                        issue,
+                        line!(),
+                        file!().into(),
+                    ))
+                    .into());
+                }
+            }
+        }
+
+        let mut records = Vec::new();
+        let mut dropped = 0;
+        for result in rdr.records() {
+            let row = result.map_err(|source| {
+                AddressErrorKind::from(Csv::new(path.into(), source, line!(), file!().into()))
+            })?;
+            let mut address = SpatialAddress::default();
+            let read = columns.iter().try_for_each(|(index, column)| {
+                column
+                    .assign(&mut address, &row[*index])
+                    .map_err(|reason| (column.header.clone(), reason))
+            });
+            match read {
+                Ok(()) => records.push(address),
+                Err((column, reason)) => {
+                    let error = ParseError {
+                        path: path.to_string_lossy().to_string(),
+                        row: row.position().map(|pos| pos.line()),
+                        column: Some(column),
+                        reason,
+                    };
+                    error.handle()?;
+                    dropped += 1;
+                }
+            }
+        }
+        info!("{} records dropped.", dropped);
+        Ok(Self(records))
+    }
+}
+
+impl Addresses<SpatialAddress> for MappedAddresses {}
+
+impl IntoBin<MappedAddresses> for MappedAddresses {
+    fn load<P: AsRef<Path>>(path: P) -> Result<Self, AddressError> {
+        match from_bin(path) {
+            Ok(records) => bincode::deserialize::<Self>(&records)
+                .map_err(|source| Bincode::new(source, line!(), file!().into()).into()),
+            Err(source) => Err(AddressErrorKind::from(source).into()),
+        }
+    }
+
+    fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
+        to_bin(self, self.len(), path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoCsv, JacksonCountySpatialAddresses, SpatialAddresses};
+
+    #[test]
+    fn mapped_addresses() -> anyhow::Result<()> {
+        let path = "data/jackson_county_sample.csv";
+        let mapped = MappedAddresses::from_csv_with_map(path, "data/mapping_sample.toml")?;
+        let jackson = JacksonCountySpatialAddresses::from_csv(path)?;
+        assert_eq!(mapped.len(), 3);
+        assert_eq!(
+            SpatialAddresses::from(&mapped[..]),
+            SpatialAddresses::from(&jackson[..])
+        );
+
+        // A header missing from the file is an error rather than a silent default.
+        let mapping = std::env::temp_dir().join("destination_mapping.toml");
+        std::fs::write(
+            &mapping,
+            "[[column]]\nheader = \"ADDRESS_NUMBER\"\nfield = \"number\"\n",
+        )?;
+        assert!(MappedAddresses::from_csv_with_map(path, &mapping).is_err());
+        std::fs::remove_file(&mapping)?;
+        Ok(())
+    }
+}
@@ -3,16 +3,23 @@ mod common;
 mod fire_inspection;
 mod grants_pass;
 mod grants_pass_business;
+mod jackson_county;
 mod josephine_county;
+mod mapped;
 
-pub use common::SpatialAddressesRaw;
+pub use common::{SpatialAddressRaw, SpatialAddressesRaw};
 pub use fire_inspection::{FireInspection, FireInspectionRaw, FireInspections};
 pub use grants_pass::{
     GrantsPassAddress, GrantsPassAddresses, GrantsPassSpatialAddress, GrantsPassSpatialAddresses,
 };
 pub use grants_pass_business::{Business, Businesses};
+pub use jackson_county::{
+    JacksonCountyAddress, JacksonCountyAddresses, JacksonCountySpatialAddress,
+    JacksonCountySpatialAddresses,
+};
 pub use josephine_county::{
     JosephineCountyAddress, JosephineCountyAddress2024, JosephineCountyAddresses,
     JosephineCountyAddresses2024, JosephineCountySpatialAddress, JosephineCountySpatialAddress2024,
     JosephineCountySpatialAddresses, JosephineCountySpatialAddresses2024,
 };
+pub use mapped::{AddressField, ColumnFormat, ColumnMap, ColumnMapping, MappedAddresses};
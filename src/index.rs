@@ -0,0 +1,243 @@
+//! The `index` module contains a spatial index for selecting candidate addresses near a point,
+//! so comparisons only visit nearby records rather than the full data set, and a prefix index
+//! for completing partially entered addresses.
+use crate::{Address, Cartesian, Geographic, crs::METERS_PER_DEGREE};
+use std::collections::{HashMap, HashSet};
+
+/// The `SpatialIndex` struct is a uniform grid over a set of points.  Each point is assigned to a
+/// square cell, and a radius query only visits the cells overlapping the search area.  For point
+/// data of roughly even density, as with address points, the grid answers radius queries in near
+/// constant time.
+///
+/// The index stores the position of each point in the slice used to build it, so query results
+/// are indices into that slice.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialIndex {
+    // Width of a grid cell, in the units of the indexed coordinates.
+    cell: f64,
+    // Reference latitude used to project geographic coordinates, if any.
+    latitude: Option<f64>,
+    // Indexed coordinates.
+    points: Vec<[f64; 2]>,
+    // Indices of points by grid cell.
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl SpatialIndex {
+    /// The `new` method creates a grid index over `points`, with a cell width of `cell`.  A cell
+    /// width close to the expected search radius gives the best performance.
+    pub fn new(points: Vec<[f64; 2]>, cell: f64) -> Self {
+        let cell = if cell > 0.0 { cell } else { 1.0 };
+        let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, point) in points.iter().enumerate() {
+            cells.entry(Self::key(point, cell)).or_default().push(i);
+        }
+        Self {
+            cell,
+            latitude: None,
+            points,
+            cells,
+        }
+    }
+
+    /// The `from_cartesian` method indexes the projected coordinates of `values`.  Queries use the
+    /// units of the projection.
+    pub fn from_cartesian<T: Cartesian>(values: &[T], cell: f64) -> Self {
+        let points = values.iter().map(|v| [v.x(), v.y()]).collect();
+        Self::new(points, cell)
+    }
+
+    /// The `from_geographic` method indexes the geographic coordinates of `values`.  Coordinates
+    /// are projected to meters using an equirectangular approximation centered on the mean
+    /// latitude of the values, which is accurate at the scale of a county.  Queries use meters.
+    pub fn from_geographic<T: Geographic>(values: &[T], cell: f64) -> Self {
+        let latitude = if values.is_empty() {
+            0.0
+        } else {
+            values.iter().map(|v| v.latitude()).sum::<f64>() / values.len() as f64
+        };
+        let points = values
+            .iter()
+            .map(|v| Self::project(v.longitude(), v.latitude(), latitude))
+            .collect();
+        let mut index = Self::new(points, cell);
+        index.latitude = Some(latitude);
+        index
+    }
+
+    /// The `within` method returns the indices of points within `radius` of `point`.  For an index
+    /// built with [`SpatialIndex::from_geographic`], `point` is geographic and `radius` is in
+    /// meters.
+    pub fn within(&self, point: [f64; 2], radius: f64) -> Vec<usize> {
+        let point = match self.latitude {
+            Some(latitude) => Self::project(point[0], point[1], latitude),
+            None => point,
+        };
+        let (min_x, min_y) = Self::key(&[point[0] - radius, point[1] - radius], self.cell);
+        let (max_x, max_y) = Self::key(&[point[0] + radius, point[1] + radius], self.cell);
+        let mut found = Vec::new();
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if let Some(members) = self.cells.get(&(x, y)) {
+                    for i in members {
+                        let other = self.points[*i];
+                        let distance =
+                            ((other[0] - point[0]).powi(2) + (other[1] - point[1]).powi(2)).sqrt();
+                        if distance <= radius {
+                            found.push(*i);
+                        }
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    /// The `len` method returns the number of indexed points.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// The `is_empty` method returns true if the index contains no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    fn key(point: &[f64; 2], cell: f64) -> (i64, i64) {
+        (
+            (point[0] / cell).floor() as i64,
+            (point[1] / cell).floor() as i64,
+        )
+    }
+
+    fn project(longitude: f64, latitude: f64, reference: f64) -> [f64; 2] {
+        [
+            longitude * METERS_PER_DEGREE * reference.to_radians().cos(),
+            latitude * METERS_PER_DEGREE,
+        ]
+    }
+}
+
+/// The `PrefixIndex` struct indexes the complete street addresses of a set of addresses for
+/// completion from a prefix, as typed into an intake form.  Each address is indexed under its
+/// label, such as "322 SE K ST", and under the label with the street name spelled out, such as
+/// "322 SOUTHEAST K STREET", so either form completes.
+///
+/// The keys are held in sorted order, so the keys sharing a prefix form a contiguous range found
+/// by binary search, as with a trie or finite state transducer but without the node overhead.
+/// The index stores the position of each address in the slice used to build it, so completions
+/// are indices into that slice.
+#[derive(Debug, Clone, Default)]
+pub struct PrefixIndex {
+    // Normalized keys and the index of the address each names, sorted by key.
+    keys: Vec<(String, usize)>,
+}
+
+impl PrefixIndex {
+    /// The `from_addresses` method creates a prefix index over the labels of `addresses`.
+    pub fn from_addresses<T: Address>(addresses: &[T]) -> Self {
+        let mut keys = Vec::with_capacity(addresses.len() * 2);
+        for (i, address) in addresses.iter().enumerate() {
+            let label = address.label();
+            let spelled = label.replacen(
+                &address.complete_street_name(true),
+                &address.complete_street_name(false),
+                1,
+            );
+            let label = Self::normalize(&label);
+            let spelled = Self::normalize(&spelled);
+            if spelled != label {
+                keys.push((spelled, i));
+            }
+            keys.push((label, i));
+        }
+        keys.sort_unstable();
+        Self { keys }
+    }
+
+    /// The `complete` method returns the indices of up to `limit` addresses with a key beginning
+    /// with `prefix`, ignoring case, punctuation and extra whitespace.  Completions are ranked
+    /// by the length of the key, so an exact match comes first, followed by the shortest
+    /// completions, with ties in alphabetical order.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<usize> {
+        let prefix = Self::normalize(prefix);
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+        let start = self
+            .keys
+            .partition_point(|(key, _)| key.as_str() < prefix.as_str());
+        let mut matches = self.keys[start..]
+            .iter()
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .collect::<Vec<&(String, usize)>>();
+        matches.sort_by(|a, b| a.0.len().cmp(&b.0.len()).then(a.cmp(b)));
+        let mut seen = HashSet::new();
+        matches
+            .into_iter()
+            .map(|(_, i)| *i)
+            .filter(|i| seen.insert(*i))
+            .take(limit)
+            .collect()
+    }
+
+    /// The `len` method returns the number of indexed keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// The `is_empty` method returns true if the index contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // Converts `text` to upper case, treating punctuation other than hyphens and slashes as
+    // whitespace, and collapsing runs of whitespace to a single space.
+    fn normalize(text: &str) -> String {
+        text.to_uppercase()
+            .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '/'))
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Addresses, IntoBin, SpatialAddresses};
+
+    #[test]
+    fn spatial_index_within() {
+        let points = vec![[0.0, 0.0], [3.0, 4.0], [10.0, 0.0], [-2.0, -2.0]];
+        let index = SpatialIndex::new(points, 5.0);
+        assert_eq!(index.within([0.0, 0.0], 5.0), vec![0, 1, 3]);
+        assert_eq!(index.within([10.0, 1.0], 1.0), vec![2]);
+    }
+
+    #[test]
+    fn complete_address_prefix() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let index = PrefixIndex::from_addresses(&city_addresses);
+        assert!(index.len() >= city_addresses.len());
+        let completions = index.complete("322 SE K", 5);
+        assert!(!completions.is_empty() && completions.len() <= 5);
+        assert_eq!(city_addresses[completions[0]].label(), "322 SE K ST");
+        let spelled = index.complete("322 Southeast K Str", 5);
+        assert_eq!(spelled, index.complete("322 SE K ST", 5));
+        let numbers = index.complete("32", 20);
+        assert_eq!(numbers.len(), 20);
+        let labels = numbers
+            .iter()
+            .map(|i| city_addresses[*i].label())
+            .collect::<Vec<String>>();
+        assert!(labels.iter().all(|label| label.starts_with("32")));
+        assert!(labels.windows(2).all(|w| w[0].len() <= w[1].len()));
+        assert!(index.complete("", 5).is_empty());
+        assert!(index.complete("99999 NOWHERE", 5).is_empty());
+        let suggested = city_addresses.suggest("322 SE K", 1);
+        assert_eq!(suggested[0].label(), "322 SE K ST");
+        Ok(())
+    }
+}
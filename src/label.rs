@@ -0,0 +1,509 @@
+//! The `label` module formats address labels from templates, so the same address data can
+//! produce mailing labels, map labels and the display strings of a CAD system without a separate
+//! export for each.
+//!
+//! A template mixes literal text with placeholders in braces, as in
+//! `"{number} {suffix} {predir} {name} {posttype} {unit_type} {unit}"`.  The placeholders are:
+//!
+//! * `number` and `suffix`, the address number and address number suffix,
+//! * `predir`, `premod`, `pretype`, `separator`, `name` and `posttype`, the components of the
+//!   complete street name, and `street`, the complete street name,
+//! * `unit_type` and `unit`, the subaddress type and identifier, where `unit_type` reads "#" for
+//!   a unit without a type, as in [`Address::label`],
+//! * `building` and `floor`,
+//! * `city`, `state` and `zip`, the postal community, state abbreviation and zip code,
+//! * `label`, the label from [`Address::label`].
+//!
+//! Directionals, post types and subaddress types are abbreviated, unless the placeholder ends in
+//! `:full`, as in `{posttype:full}`.  Text inside square brackets is written only if every
+//! placeholder in the brackets has a value, as in `"{label}[ BLDG {building}]"`.  Components the
+//! address lacks are left out, and runs of whitespace collapse to a single space.  Double a brace
+//! or bracket to write it literally.
+use crate::{
+    Address, AddressErrorKind, Builder, IntoCsv, IntoJson, Io, SpatialAddress, SpatialAddressRaw,
+    enrich::{read_columns, write_columns},
+    to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::Path;
+use std::str::FromStr;
+
+// Returns a builder error for a label template reporting `issue`.
+fn invalid(issue: String, line: u32) -> Builder {
+    Builder::new(
+        issue,
+        "LabelTemplate".to_string(),
+        line,
+        file!().to_string(),
+    )
+}
+
+// The address components available to a template.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Field {
+    Number,
+    Suffix,
+    PreDirectional,
+    PreModifier,
+    PreType,
+    Separator,
+    Name,
+    PostType,
+    Street,
+    UnitType,
+    Unit,
+    Building,
+    Floor,
+    City,
+    State,
+    Zip,
+    Label,
+}
+
+impl FromStr for Field {
+    type Err = Builder;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let field = match name {
+            "number" => Self::Number,
+            "suffix" => Self::Suffix,
+            "predir" => Self::PreDirectional,
+            "premod" => Self::PreModifier,
+            "pretype" => Self::PreType,
+            "separator" => Self::Separator,
+            "name" => Self::Name,
+            "posttype" => Self::PostType,
+            "street" => Self::Street,
+            "unit_type" => Self::UnitType,
+            "unit" => Self::Unit,
+            "building" => Self::Building,
+            "floor" => Self::Floor,
+            "city" => Self::City,
+            "state" => Self::State,
+            "zip" => Self::Zip,
+            "label" => Self::Label,
+            _ => {
+                return Err(invalid(
+                    format!("unknown placeholder '{{{name}}}'"),
+                    line!(),
+                ));
+            }
+        };
+        Ok(field)
+    }
+}
+
+impl Field {
+    // Returns the value of the field for `address`, spelled out in full if `full` is set.
+    fn value<T: Address>(&self, address: &T, full: bool) -> Option<String> {
+        match self {
+            Self::Number => Some(address.number().to_string()),
+            Self::Suffix => address.number_suffix().clone(),
+            Self::PreDirectional => address.directional().map(|directional| match full {
+                true => directional.to_string(),
+                false => directional.abbreviate(),
+            }),
+            Self::PreModifier => address.street_name_pre_modifier().map(|m| m.upper()),
+            Self::PreType => address.street_name_pre_type().map(|t| t.upper()),
+            Self::Separator => address.street_name_separator().map(|s| s.upper()),
+            Self::Name => Some(address.street_name().clone()),
+            Self::PostType => address.street_type().map(|post_type| match full {
+                true => post_type.to_string(),
+                false => post_type.abbreviate(),
+            }),
+            Self::Street => Some(address.complete_street_name(!full)),
+            Self::UnitType => match (address.subaddress_type(), address.subaddress_id()) {
+                (Some(kind), _) if full => Some(kind.upper()),
+                (Some(kind), _) => Some(kind.abbreviate()),
+                (None, Some(_)) => Some("#".to_string()),
+                (None, None) => None,
+            },
+            Self::Unit => address.subaddress_id().clone(),
+            Self::Building => address.building().clone(),
+            Self::Floor => address.floor().map(|floor| floor.to_string()),
+            Self::City => Some(address.postal_community().clone()),
+            Self::State => Some(address.state().abbreviate()),
+            Self::Zip => (address.zip() > 0).then(|| address.zip().to_string()),
+            Self::Label => Some(address.label()),
+        }
+        .filter(|value| !value.trim().is_empty())
+    }
+}
+
+// A piece of a parsed template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field, bool),
+    Group(Vec<Part>),
+}
+
+// Writes `parts` for `address` to `label`, returning false if a placeholder lacks a value.
+fn render_parts<T: Address>(parts: &[Part], address: &T, label: &mut String) -> bool {
+    let mut complete = true;
+    for part in parts {
+        match part {
+            Part::Text(text) => label.push_str(text),
+            Part::Field(field, full) => match field.value(address, *full) {
+                Some(value) => label.push_str(&value),
+                None => complete = false,
+            },
+            Part::Group(parts) => {
+                let mut group = String::new();
+                if render_parts(parts, address, &mut group) {
+                    label.push_str(&group);
+                }
+            }
+        }
+    }
+    complete
+}
+
+/// The `LabelTemplate` struct holds a parsed label template.  See the [module
+/// documentation](crate::label) for the template syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelTemplate(Vec<Part>);
+
+impl LabelTemplate {
+    /// The `parse` method reads the template `template`.  Errors if a placeholder is unknown, or
+    /// if a brace or bracket is unmatched.
+    pub fn parse(template: &str) -> Result<Self, Builder> {
+        let mut parts = Vec::new();
+        // The parts outside of an open group, while reading the group.
+        let mut outer: Option<Vec<Part>> = None;
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            if matches!(c, '{' | '}' | '[' | ']') && chars.peek() == Some(&c) {
+                chars.next();
+                text.push(c);
+                continue;
+            }
+            if !matches!(c, '{' | '}' | '[' | ']') {
+                text.push(c);
+                continue;
+            }
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+            match c {
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => {
+                                return Err(invalid(
+                                    format!("unterminated placeholder in '{template}'"),
+                                    line!(),
+                                ));
+                            }
+                        }
+                    }
+                    let (name, full) = match spec.split_once(':') {
+                        Some((name, "full")) => (name, true),
+                        Some((name, "abbr")) => (name, false),
+                        Some((_, spelling)) => {
+                            return Err(invalid(
+                                format!("unknown spelling '{spelling}' in '{{{spec}}}'"),
+                                line!(),
+                            ));
+                        }
+                        None => (spec.as_str(), false),
+                    };
+                    parts.push(Part::Field(name.trim().parse()?, full));
+                }
+                '[' if outer.is_none() => outer = Some(std::mem::take(&mut parts)),
+                ']' if outer.is_some() => {
+                    let group = std::mem::replace(&mut parts, outer.take().unwrap_or_default());
+                    parts.push(Part::Group(group));
+                }
+                _ => {
+                    return Err(invalid(format!("unmatched '{c}' in '{template}'"), line!()));
+                }
+            }
+        }
+        if outer.is_some() {
+            return Err(invalid(format!("unmatched '[' in '{template}'"), line!()));
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self(parts))
+    }
+
+    /// The `mailing` method returns the template for mailing labels, the one-line delivery
+    /// address followed by the city, state and zip code, as in
+    /// "1450 NE 7TH ST APT B GRANTS PASS OR 97526".
+    pub fn mailing() -> Self {
+        Self::preset(concat!(
+            "{number} {suffix} {predir} {premod} {pretype} {separator} {name} {posttype} ",
+            "{unit_type} {unit} {city} {state} {zip}"
+        ))
+    }
+
+    /// The `map` method returns the template for map labels at address points, the complete
+    /// address number and unit, as in "1450 B".
+    pub fn map() -> Self {
+        Self::preset("{number} {suffix} {unit}")
+    }
+
+    /// The `cad` method returns the template for the display of a computer-aided dispatch
+    /// system, the address label with any building and the city, as in
+    /// "1450 NE 7TH ST BLDG 2 APT B, GRANTS PASS".
+    pub fn cad() -> Self {
+        Self::preset("{number} {suffix} {street}[ BLDG {building}] {unit_type} {unit}[, {city}]")
+    }
+
+    // Parses a built-in template, which is known to be valid.
+    fn preset(template: &str) -> Self {
+        Self::parse(template).unwrap_or_else(|_| Self(Vec::new()))
+    }
+
+    /// The `from_name` method returns the built-in template named `name`, either "mailing", "map"
+    /// or "cad", or else parses `name` as a template.
+    pub fn from_name(name: &str) -> Result<Self, Builder> {
+        match name {
+            "mailing" => Ok(Self::mailing()),
+            "map" => Ok(Self::map()),
+            "cad" => Ok(Self::cad()),
+            _ => Self::parse(name),
+        }
+    }
+
+    /// The `render` method returns the label of `address` formatted by the template.
+    pub fn render<T: Address>(&self, address: &T) -> String {
+        let mut label = String::new();
+        render_parts(&self.0, address, &mut label);
+        label.split_whitespace().collect::<Vec<&str>>().join(" ")
+    }
+}
+
+/// The `LabelColumn` struct holds a label template and the name of the column holding its
+/// labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelColumn {
+    /// The `name` field holds the name of the column.
+    pub name: String,
+    /// The `template` field holds the template formatting the labels.
+    pub template: LabelTemplate,
+}
+
+impl LabelColumn {
+    /// The `parse` method reads a column from a specification of the form `name=template`, where
+    /// `template` is a template or the name of a built-in template, as in "map_label=map".  A
+    /// built-in template given alone, as in "mailing", writes to a column of the same name.
+    pub fn parse(spec: &str) -> Result<Self, AddressErrorKind> {
+        let (name, template) = match spec.split_once('=') {
+            Some((name, template)) => (name.trim(), template),
+            None if matches!(spec.trim(), "mailing" | "map" | "cad") => (spec.trim(), spec.trim()),
+            None => {
+                return Err(
+                    invalid(format!("expected 'name=template' in '{spec}'"), line!()).into(),
+                );
+            }
+        };
+        Ok(Self {
+            name: name.to_string(),
+            template: LabelTemplate::from_name(template)?,
+        })
+    }
+}
+
+/// The `LabeledAddress` struct holds an address with its labels.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabeledAddress {
+    /// The `address` field holds the address.
+    #[serde(flatten)]
+    pub address: SpatialAddressRaw,
+    /// The `labels` field holds the name of each label column paired with the label of the
+    /// address, in the order of the columns.
+    #[serde(
+        flatten,
+        serialize_with = "serialize_labels",
+        deserialize_with = "deserialize_labels"
+    )]
+    pub labels: Vec<(String, String)>,
+}
+
+impl LabeledAddress {
+    /// The `label` method returns the label of the address in the column `name`, or `None` if
+    /// the record has no such column.
+    pub fn label(&self, name: &str) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(column, _)| column == name)
+            .map(|(_, label)| label.as_str())
+    }
+}
+
+// Writes the labels as a map, keeping the order of the columns.
+fn serialize_labels<S: Serializer>(
+    labels: &[(String, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(labels.iter().map(|(column, label)| (column, label)))
+}
+
+// Reads the labels from a map, keeping the order of the columns.
+fn deserialize_labels<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(String, String)>, D::Error> {
+    struct Labels;
+
+    impl<'de> Visitor<'de> for Labels {
+        type Value = Vec<(String, String)>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a map of label columns to labels")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut labels = Vec::new();
+            while let Some(entry) = map.next_entry::<String, String>()? {
+                labels.push(entry);
+            }
+            Ok(labels)
+        }
+    }
+
+    deserializer.deserialize_map(Labels)
+}
+
+/// The `LabeledAddresses` struct holds a vector of type [`LabeledAddress`].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Deref, DerefMut)]
+pub struct LabeledAddresses(Vec<LabeledAddress>);
+
+impl LabeledAddresses {
+    /// The `new` method formats the label of each address in `addresses` for each column of
+    /// `columns`.
+    pub fn new(addresses: &[SpatialAddress], columns: &[LabelColumn]) -> Self {
+        let records = addresses
+            .iter()
+            .map(|address| LabeledAddress {
+                address: SpatialAddressRaw::from(address.clone()),
+                labels: columns
+                    .iter()
+                    .map(|column| (column.name.clone(), column.template.render(address)))
+                    .collect(),
+            })
+            .collect();
+        Self(records)
+    }
+
+    // Returns the names of the label columns held by the records, in the order of the columns.
+    fn columns(&self) -> Vec<String> {
+        let mut columns = Vec::new();
+        for (column, _) in self.iter().flat_map(|r| r.labels.iter()) {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+        columns
+    }
+}
+
+impl IntoCsv<LabeledAddresses> for LabeledAddresses {
+    /// Reads the address fields of each row as a [`SpatialAddressRaw`], and every other column as
+    /// a label.  Rows that fail to deserialize are dropped, consistent with
+    /// [`from_csv`](crate::from_csv).
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = read_columns(path.as_ref())?
+            .into_iter()
+            .map(|(address, columns)| LabeledAddress {
+                address,
+                labels: columns,
+            })
+            .collect();
+        Ok(Self(records))
+    }
+
+    /// Writes the address fields of each record, followed by a column for each label.
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        let columns = self.columns();
+        let records = self
+            .iter()
+            .map(|record| {
+                let values = columns
+                    .iter()
+                    .map(|column| record.label(column).unwrap_or_default().to_string())
+                    .collect();
+                (&record.address, values)
+            })
+            .collect::<Vec<_>>();
+        write_columns(path.as_ref(), &records, &columns)
+    }
+}
+
+impl IntoJson for LabeledAddresses {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommonAddress;
+
+    #[test]
+    fn label_templates() -> anyhow::Result<()> {
+        let address = SpatialAddress {
+            address: CommonAddress::parse("1450 NE 7th St Apt B, Grants Pass, OR 97526")?,
+            ..Default::default()
+        };
+        let template = LabelTemplate::parse("{number}{suffix} {predir:full} {name} {posttype}")?;
+        assert_eq!(template.render(&address), "1450 NORTHEAST 7TH ST");
+        assert_eq!(
+            LabelTemplate::mailing().render(&address),
+            "1450 NE 7TH ST APT B GRANTS PASS OR 97526"
+        );
+        assert_eq!(LabelTemplate::map().render(&address), "1450 B");
+        assert_eq!(
+            LabelTemplate::cad().render(&address),
+            "1450 NE 7TH ST APT B, GRANTS PASS"
+        );
+        // Bracketed text is dropped when a placeholder inside lacks a value.
+        let template = LabelTemplate::parse("{label}[ BLDG {building}] {{{zip}}}")?;
+        assert_eq!(template.render(&address), "1450 NE 7TH ST APT B {97526}");
+        assert!(LabelTemplate::parse("{number} {street_nam}").is_err());
+        assert!(LabelTemplate::parse("{number").is_err());
+        assert!(LabelTemplate::parse("{number} [{unit}").is_err());
+        assert!(LabelTemplate::parse("{posttype:long}").is_err());
+
+        // Columns keep the order given, rather than sorting by name.
+        let columns = vec![
+            LabelColumn::parse("map")?,
+            LabelColumn::parse("display={street:full}")?,
+            LabelColumn::parse("cad")?,
+        ];
+        assert!(LabelColumn::parse("{number}").is_err());
+        let mut records = LabeledAddresses::new(&[address], &columns);
+        assert_eq!(records[0].label("map"), Some("1450 B"));
+        assert_eq!(records[0].label("display"), Some("NORTHEAST 7TH STREET"));
+        assert_eq!(records.columns(), vec!["map", "display", "cad"]);
+        let path = std::env::temp_dir().join("destination_labeled.csv");
+        records.to_csv(&path)?;
+        let headers = csv::Reader::from_path(&path)?.headers()?.clone();
+        let labels = headers
+            .iter()
+            .skip(headers.len() - 3)
+            .collect::<Vec<&str>>();
+        assert_eq!(labels, vec!["map", "display", "cad"]);
+        let read = LabeledAddresses::from_csv(&path)?;
+        assert_eq!(read, records);
+        let json = serde_json::to_string(&records[0])?;
+        let position = |key: &str| json.find(&format!("\"{key}\":")).unwrap_or_default();
+        assert!(0 < position("map") && position("map") < position("display"));
+        assert!(position("display") < position("cad"));
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
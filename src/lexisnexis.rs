@@ -1,7 +1,8 @@
 //! The `lexisnexis` module produces address range reports for the LexisNexis dispatch service.
 use crate::{
-    Address, AddressError, AddressErrorKind, Addresses, Bincode, Builder, IntoBin, IntoCsv, Io,
-    from_bin, from_csv, to_bin, to_csv,
+    Address, AddressError, AddressErrorKind, Addresses, Bincode, Boundary, Builder, Cartesian,
+    CommonAddresses, Config, Csv, Geographic, IntoBin, IntoCsv, IntoJson, Io, from_bin, from_csv,
+    to_bin, to_csv, to_json, to_ndjson, write_atomic,
 };
 use derive_more::{Deref, DerefMut};
 use serde::{Deserialize, Serialize};
@@ -155,73 +156,101 @@ impl LexisNexisItemBuilder {
 pub struct LexisNexisItem {
     /// The `address_number_from` field represents the lower range of address numbers associated
     /// with the service area.
-    #[serde(rename(serialize = "StNumFrom"))]
+    #[serde(rename(serialize = "StNumFrom"), alias = "StNumFrom")]
     pub address_number_from: i64,
     /// The `address_number_to` field represents the upper range of address numbers associated
     /// with the service area.
-    #[serde(rename(serialize = "StNumTo"))]
+    #[serde(rename(serialize = "StNumTo"), alias = "StNumTo")]
     pub address_number_to: i64,
     /// The `street_name_pre_directional` field represents the street name pre directional
     /// associated with the service area.
-    #[serde(rename(serialize = "StPreDirection"))]
+    #[serde(rename(serialize = "StPreDirection"), alias = "StPreDirection")]
     pub street_name_pre_directional: Option<String>,
     /// The `street_name` field represents the street name component of the complete street name
     /// associated with the service area.
-    #[serde(rename(serialize = "StName"))]
+    #[serde(rename(serialize = "StName"), alias = "StName")]
     pub street_name: String,
     /// The `street_name_post_type` field represents the street name post type component of the
     /// complete street name associated with the service area.
-    #[serde(rename(serialize = "StType"))]
+    #[serde(rename(serialize = "StType"), alias = "StType")]
     pub street_name_post_type: String,
     /// The `street_name_post_directional` field represents the street name post directional component of
     /// the complete street name.  The City of Grants Pass does not issue addresses using a street
     /// name post directional component, but Josephine County does have some examples in their
     /// records.
-    #[serde(rename(serialize = "StPostDirection"))]
+    #[serde(rename(serialize = "StPostDirection"), alias = "StPostDirection")]
     pub street_name_post_directional: Option<String>,
     /// The `postal_community` field represents either the unincorporated or incorporated
     /// municipality name associated with the service area.
-    #[serde(rename(serialize = "City"))]
+    #[serde(rename(serialize = "City"), alias = "City")]
     pub postal_community: String,
     /// The `beat` field represents the police response jurisdiction associated with the service
     /// area.  The City of Grants Pass does not use this field directly, but its presence is a
     /// requirement of the LexisNexis schema.
-    #[serde(rename(serialize = "Beat"))]
+    #[serde(rename(serialize = "Beat"), alias = "Beat")]
     pub beat: Option<String>,
     /// The `area` field represents the service
     /// area.  The City of Grants Pass does not use this field directly, but its presence is a
     /// requirement of the LexisNexis schema.
-    #[serde(rename(serialize = "Area"))]
+    #[serde(rename(serialize = "Area"), alias = "Area")]
     pub area: Option<String>,
     /// The `district` field represents the service
     /// district.  The City of Grants Pass does not use this field directly, but its presence is a
     /// requirement of the LexisNexis schema.
-    #[serde(rename(serialize = "District"))]
+    #[serde(rename(serialize = "District"), alias = "District")]
     pub district: Option<String>,
     /// The `zone` field represents the service
     /// zone.  The City of Grants Pass does not use this field directly, but its presence is a
     /// requirement of the LexisNexis schema.
-    #[serde(rename(serialize = "Zone"))]
+    #[serde(rename(serialize = "Zone"), alias = "Zone")]
     pub zone: Option<String>,
     /// The `zip_code` field represents the postal zip code associated with the service area.
-    #[serde(rename(serialize = "Zipcode"))]
+    #[serde(rename(serialize = "Zipcode"), alias = "Zipcode")]
     pub zip_code: i64,
     /// The `commonplace` field represents a common name associated with the service area.  The
     /// City of Grants Pass does not use this field directly, but its presence is a requirement of
     /// the LexisNexis schema.
-    #[serde(rename(serialize = "CommonPlace"))]
+    #[serde(rename(serialize = "CommonPlace"), alias = "CommonPlace")]
     pub commonplace: Option<String>,
     /// The `address_number` field may possibly serve to represent a service area with an address
     /// range of one, but the City of Grants Pass reports these ranges using a single value for the
     /// _from and _to fields, so this field is currently unused.  Its presence is a requirement of
     /// the LexisNexis schema.
-    #[serde(rename(serialize = "StNum"))]
+    #[serde(rename(serialize = "StNum"), alias = "StNum")]
     pub address_number: Option<i64>,
-    /// The `id` field is an internal unique id.
-    #[serde(skip_serializing)]
+    /// The `id` field is an internal unique id.  Items read from a csv file receive a nil id.
+    #[serde(skip_serializing, default)]
     pub id: uuid::Uuid,
 }
 
+impl LexisNexisItem {
+    // Identifies the street and postal area of the row.
+    fn street(&self) -> (&Option<String>, &str, &str, &Option<String>, &str, i64) {
+        (
+            &self.street_name_pre_directional,
+            &self.street_name,
+            &self.street_name_post_type,
+            &self.street_name_post_directional,
+            &self.postal_community,
+            self.zip_code,
+        )
+    }
+
+    // Returns true if the rows hold the same values, ignoring the internal id.
+    fn same(&self, other: &Self) -> bool {
+        Self {
+            id: other.id,
+            ..self.clone()
+        } == *other
+    }
+
+    // Returns true if the address number ranges of the rows overlap.
+    fn overlaps(&self, other: &Self) -> bool {
+        self.address_number_from <= other.address_number_to
+            && other.address_number_from <= self.address_number_to
+    }
+}
+
 /// The `LexisNexis` struct holds a vector of [`LexisNexisItem`] objects, for serialization into a
 /// .csv file.
 #[derive(
@@ -295,6 +324,65 @@ impl LexisNexis {
         }
         Ok(LexisNexis(records))
     }
+
+    /// The `from_boundary` method creates a [`LexisNexis`] struct from the service area polygon
+    /// `boundary`.  Addresses in `addresses` inside the boundary are included in the range
+    /// selection, while addresses outside the boundary are excluded, along with the addresses in
+    /// `exclude`.
+    pub fn from_boundary<T: Address + Cartesian + Geographic + Clone>(
+        addresses: &[T],
+        boundary: &Boundary,
+        exclude: &CommonAddresses,
+    ) -> Result<LexisNexis, Builder> {
+        let (inside, outside) = boundary.partition(addresses);
+        tracing::info!(
+            "Addresses inside boundary: {}, outside: {}",
+            inside.len(),
+            outside.len()
+        );
+        let include = CommonAddresses::from(&inside[..]);
+        let mut exclude = exclude.clone();
+        exclude.extend(CommonAddresses::from(&outside[..]).iter().cloned());
+        Self::from_addresses(&include, &exclude)
+    }
+
+    /// The `diff` method compares the table to the `previous` submission, returning the rows
+    /// added, removed and changed.  Rows are compared by street, so a range on a street that
+    /// overlaps a range of the previous submission on the same street is reported as a change if
+    /// any value differs.  Each previous row pairs with at most one row of the table, and rows
+    /// without a pair are added or removed.
+    pub fn diff(&self, previous: &LexisNexis) -> LexisNexisChanges {
+        let mut paired = vec![false; previous.len()];
+        let mut changes = Vec::new();
+        for item in self.iter() {
+            let candidates = previous
+                .iter()
+                .enumerate()
+                .filter(|(i, p)| !paired[*i] && p.street() == item.street())
+                .collect::<Vec<(usize, &LexisNexisItem)>>();
+            if let Some((i, _)) = candidates.iter().find(|(_, p)| p.same(item)) {
+                paired[*i] = true;
+            } else if let Some((i, p)) = candidates.iter().find(|(_, p)| p.overlaps(item)) {
+                paired[*i] = true;
+                changes.push(LexisNexisChange::new(
+                    LexisNexisChangeKind::Change,
+                    item,
+                    Some(p),
+                ));
+            } else {
+                changes.push(LexisNexisChange::new(LexisNexisChangeKind::Add, item, None));
+            }
+        }
+        for (item, _) in previous.iter().zip(paired).filter(|(_, p)| !p) {
+            changes.push(LexisNexisChange::new(
+                LexisNexisChangeKind::Remove,
+                item,
+                None,
+            ));
+        }
+        tracing::info!("Changes since previous submission: {}", changes.len());
+        LexisNexisChanges(changes)
+    }
 }
 
 impl IntoBin<LexisNexis> for LexisNexis {
@@ -307,7 +395,7 @@ impl IntoBin<LexisNexis> for LexisNexis {
     }
 
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError> {
-        to_bin(self, path)
+        to_bin(self, self.len(), path)
     }
 }
 
@@ -322,6 +410,279 @@ impl IntoCsv<LexisNexis> for LexisNexis {
     }
 }
 
+impl IntoJson for LexisNexis {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+/// The `LexisNexisChangeKind` enum describes how a row of the LexisNexis table differs from the
+/// previous submission.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Deserialize,
+    Serialize,
+    derive_more::Display,
+)]
+pub enum LexisNexisChangeKind {
+    /// The row is new to the table.
+    #[default]
+    #[display("Add")]
+    Add,
+    /// The row was in the previous submission, but is absent from the table.
+    #[display("Remove")]
+    Remove,
+    /// The row overlaps a row of the previous submission on the same street, but its range or
+    /// values differ.
+    #[display("Change")]
+    Change,
+}
+
+/// The `LexisNexisChange` struct reports a row added, removed or changed since the previous
+/// submission of the LexisNexis table.  Columns follow [`LexisNexisItem`], preceded by the kind of
+/// change and, for changed rows, the previous address number range.
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct LexisNexisChange {
+    /// The `change` field holds the kind of change.
+    #[serde(rename = "Change")]
+    pub change: LexisNexisChangeKind,
+    /// The `previous_from` field holds the lower bound of the previous range of a changed row.
+    #[serde(rename = "PrevStNumFrom")]
+    pub previous_from: Option<i64>,
+    /// The `previous_to` field holds the upper bound of the previous range of a changed row.
+    #[serde(rename = "PrevStNumTo")]
+    pub previous_to: Option<i64>,
+    /// The `address_number_from` field holds the lower bound of the range.
+    #[serde(rename = "StNumFrom")]
+    pub address_number_from: i64,
+    /// The `address_number_to` field holds the upper bound of the range.
+    #[serde(rename = "StNumTo")]
+    pub address_number_to: i64,
+    /// The `street_name_pre_directional` field holds the street name pre directional.
+    #[serde(rename = "StPreDirection")]
+    pub street_name_pre_directional: Option<String>,
+    /// The `street_name` field holds the street name.
+    #[serde(rename = "StName")]
+    pub street_name: String,
+    /// The `street_name_post_type` field holds the street name post type.
+    #[serde(rename = "StType")]
+    pub street_name_post_type: String,
+    /// The `street_name_post_directional` field holds the street name post directional.
+    #[serde(rename = "StPostDirection")]
+    pub street_name_post_directional: Option<String>,
+    /// The `postal_community` field holds the municipality name.
+    #[serde(rename = "City")]
+    pub postal_community: String,
+    /// The `beat` field holds the police beat.
+    #[serde(rename = "Beat")]
+    pub beat: Option<String>,
+    /// The `area` field holds the service area.
+    #[serde(rename = "Area")]
+    pub area: Option<String>,
+    /// The `district` field holds the service district.
+    #[serde(rename = "District")]
+    pub district: Option<String>,
+    /// The `zone` field holds the service zone.
+    #[serde(rename = "Zone")]
+    pub zone: Option<String>,
+    /// The `zip_code` field holds the postal zip code.
+    #[serde(rename = "Zipcode")]
+    pub zip_code: i64,
+    /// The `commonplace` field holds a common name for the service area.
+    #[serde(rename = "CommonPlace")]
+    pub commonplace: Option<String>,
+    /// The `address_number` field holds the single address number of the row, if any.
+    #[serde(rename = "StNum")]
+    pub address_number: Option<i64>,
+}
+
+impl LexisNexisChange {
+    /// The `new` method creates a change of kind `change` for the row `item`.  For changed rows,
+    /// `previous` holds the row of the previous submission.
+    pub fn new(
+        change: LexisNexisChangeKind,
+        item: &LexisNexisItem,
+        previous: Option<&LexisNexisItem>,
+    ) -> Self {
+        let item = item.clone();
+        Self {
+            change,
+            previous_from: previous.map(|p| p.address_number_from),
+            previous_to: previous.map(|p| p.address_number_to),
+            address_number_from: item.address_number_from,
+            address_number_to: item.address_number_to,
+            street_name_pre_directional: item.street_name_pre_directional,
+            street_name: item.street_name,
+            street_name_post_type: item.street_name_post_type,
+            street_name_post_directional: item.street_name_post_directional,
+            postal_community: item.postal_community,
+            beat: item.beat,
+            area: item.area,
+            district: item.district,
+            zone: item.zone,
+            zip_code: item.zip_code,
+            commonplace: item.commonplace,
+            address_number: item.address_number,
+        }
+    }
+}
+
+/// The `LexisNexisChanges` struct holds a vector of [`LexisNexisChange`] objects, the change log
+/// between two submissions of the LexisNexis table.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Deserialize,
+    Serialize,
+    Deref,
+    DerefMut,
+)]
+pub struct LexisNexisChanges(Vec<LexisNexisChange>);
+
+impl LexisNexisChanges {
+    /// The `count` method returns the number of changes of kind `change`.
+    pub fn count(&self, change: LexisNexisChangeKind) -> usize {
+        self.iter().filter(|c| c.change == change).count()
+    }
+}
+
+impl IntoCsv<LexisNexisChanges> for LexisNexisChanges {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for LexisNexisChanges {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+/// The `LexisNexisColumn` struct names a column of the LexisNexis output, read from the
+/// `[[column]]` tables of a [`LexisNexisTemplate`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct LexisNexisColumn {
+    /// The `field` field holds the column of [`LexisNexisItem`] or [`LexisNexisChange`] written
+    /// to the output, as named in the standard schema, such as "StNumFrom" or "City".
+    pub field: String,
+    /// The `header` field holds the header of the column in the output.  Defaults to `field`.
+    #[serde(default)]
+    pub header: Option<String>,
+}
+
+/// The `LexisNexisTemplate` struct describes the schema of the LexisNexis output, so changes to
+/// the columns required by the vendor need only a new template.  The columns of the output
+/// follow the order of the `[[column]]` tables, and fields without a table are left out.
+///
+/// ```toml
+/// [[column]]
+/// field = "StNumFrom"
+/// header = "FROM_NUM"
+///
+/// [[column]]
+/// field = "StName"
+/// header = "STREET"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct LexisNexisTemplate {
+    /// The `columns` field holds the columns of the output, in order.
+    #[serde(default, rename = "column")]
+    pub columns: Vec<LexisNexisColumn>,
+}
+
+impl LexisNexisTemplate {
+    /// The `from_toml` method reads an output template from the TOML file at `path`.
+    pub fn from_toml<P: AsRef<Path>>(path: P) -> Result<Self, AddressError> {
+        tracing::info!("Reading output template from {:?}", path.as_ref());
+        let template: Self = crate::config::from_toml(path)?;
+        tracing::info!("Columns in template: {}", template.columns.len());
+        Ok(template)
+    }
+
+    /// The `headers` method returns the headers of the output columns.
+    pub fn headers(&self) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|c| c.header.clone().unwrap_or_else(|| c.field.clone()))
+            .collect()
+    }
+
+    /// The `to_csv` method writes `records` to a csv file at location `path`, with the columns
+    /// and headers of the template.  Returns an error if a field of the template is not a column
+    /// of the records.
+    pub fn to_csv<T: Serialize + Default, P: AsRef<Path>>(
+        &self,
+        records: &[T],
+        path: P,
+    ) -> Result<(), AddressErrorKind> {
+        let path = path.as_ref();
+        let csv_error = |source| Csv::new(path.into(), source, line!(), file!().into());
+        // Records are written in the standard schema to a buffer, then read back by column.
+        let mut buffer = csv::Writer::from_writer(Vec::new());
+        buffer.serialize(T::default()).map_err(csv_error)?;
+        for record in records {
+            buffer.serialize(record).map_err(csv_error)?;
+        }
+        let buffer = buffer
+            .into_inner()
+            .map_err(|e| Io::new(path.into(), e.into_error(), line!(), file!().into()))?;
+        let mut rdr = csv::Reader::from_reader(buffer.as_slice());
+        let fields = rdr.headers().map_err(csv_error)?.clone();
+        let mut indices = Vec::with_capacity(self.columns.len());
+        for column in &self.columns {
+            match fields.iter().position(|f| f == column.field) {
+                Some(index) => indices.push(index),
+                None => {
+                    let issue =
+                        format!("template field {} is not a LexisNexis column", column.field);
+                    return Err(Config::new(path.into(), issue, line!(), file!().into()).into());
+                }
+            }
+        }
+
+        write_atomic(path, |partial| -> Result<(), AddressErrorKind> {
+            let mut wtr = csv::Writer::from_path(partial).map_err(csv_error)?;
+            wtr.write_record(self.headers()).map_err(csv_error)?;
+            // The first row holds the default record used to read the headers.
+            for row in rdr.records().skip(1) {
+                let row = row.map_err(csv_error)?;
+                wtr.write_record(indices.iter().map(|i| &row[*i]))
+                    .map_err(csv_error)?;
+            }
+            wtr.flush()
+                .map_err(|source| Io::new(partial.into(), source, line!(), file!().into()))?;
+            Ok(())
+        })
+    }
+}
+
 /// The `LexisNexisRangeItem` represents an address number `num`, and whether to include the number
 /// in the range selection.
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
@@ -408,3 +769,102 @@ impl LexisNexisRange {
         rngs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JacksonCountySpatialAddresses;
+
+    #[test]
+    fn lexisnexis_boundary() -> anyhow::Result<()> {
+        let addresses = JacksonCountySpatialAddresses::from_csv("data/jackson_county_sample.csv")?;
+        let geojson = Boundary::from_path("data/boundary_sample.geojson")?;
+        assert!(geojson.geographic);
+        assert_eq!(geojson.rings.len(), 1);
+        let inside = addresses
+            .iter()
+            .map(|a| geojson.contains(a))
+            .collect::<Vec<bool>>();
+        assert_eq!(inside, vec![true, false, true]);
+
+        // The shapefile boundary is projected, with a hole around the Ashland address.
+        let shapefile = Boundary::from_path("data/boundary_sample.shp")?;
+        assert!(!shapefile.geographic);
+        assert_eq!(shapefile.rings.len(), 2);
+        let (inside, outside) = shapefile.partition(&addresses);
+        assert_eq!(inside.len(), 1);
+        assert_eq!(outside.len(), 2);
+
+        let lx = LexisNexis::from_boundary(&addresses, &geojson, &CommonAddresses::default())?;
+        assert_eq!(lx.len(), 2);
+        assert_eq!(lx[0].street_name, "CENTRAL");
+        assert_eq!(lx[1].street_name, "MAIN");
+        Ok(())
+    }
+
+    #[test]
+    fn lexisnexis_diff() -> anyhow::Result<()> {
+        let addresses = JacksonCountySpatialAddresses::from_csv("data/jackson_county_sample.csv")?;
+        let exclude = CommonAddresses::default();
+        let boundary = Boundary::from_path("data/boundary_sample.geojson")?;
+        let mut lx = LexisNexis::from_boundary(&addresses, &boundary, &exclude)?;
+        let path = std::env::temp_dir().join("destination_lexisnexis_previous.csv");
+        lx.to_csv(&path)?;
+        let previous = LexisNexis::from_csv(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(previous.len(), 2);
+        assert!(lx.diff(&previous).is_empty());
+
+        // The shapefile boundary drops the Ashland address, and the Medford range widens.
+        let boundary = Boundary::from_path("data/boundary_sample.shp")?;
+        let mut current = LexisNexis::from_boundary(&addresses, &boundary, &exclude)?;
+        current[0].address_number_to = 130;
+        let changes = current.diff(&previous);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].change, LexisNexisChangeKind::Change);
+        assert_eq!(changes[0].previous_to, Some(120));
+        assert_eq!(changes[0].address_number_to, 130);
+        assert_eq!(changes[1].change, LexisNexisChangeKind::Remove);
+        assert_eq!(changes[1].street_name, "MAIN");
+
+        let changes = previous.diff(&LexisNexis::default());
+        assert_eq!(changes.count(LexisNexisChangeKind::Add), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn lexisnexis_template() -> anyhow::Result<()> {
+        let addresses = JacksonCountySpatialAddresses::from_csv("data/jackson_county_sample.csv")?;
+        let boundary = Boundary::from_path("data/boundary_sample.geojson")?;
+        let lx = LexisNexis::from_boundary(&addresses, &boundary, &CommonAddresses::default())?;
+        let mut template = LexisNexisTemplate::from_toml("data/lexisnexis_template.toml")?;
+        assert_eq!(
+            template.headers(),
+            vec!["STREET", "SUFFIX", "FROM_NUM", "TO_NUM", "City"]
+        );
+        let path = std::env::temp_dir().join("destination_lexisnexis_template.csv");
+        template.to_csv(&lx, &path)?;
+        let text = std::fs::read_to_string(&path)?;
+        let lines = text.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[0], "STREET,SUFFIX,FROM_NUM,TO_NUM,City");
+        assert_eq!(lines[1], "CENTRAL,AVE,120,120,MEDFORD");
+        assert_eq!(lines.len(), 3);
+
+        let changes = lx.diff(&LexisNexis::default());
+        template.columns.insert(
+            0,
+            LexisNexisColumn {
+                field: "Change".to_string(),
+                header: None,
+            },
+        );
+        template.to_csv(&changes, &path)?;
+        let text = std::fs::read_to_string(&path)?;
+        assert!(text.starts_with("Change,STREET"));
+
+        template.columns[0].field = "Missing".to_string();
+        assert!(template.to_csv(&lx, &path).is_err());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
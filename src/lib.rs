@@ -4,46 +4,228 @@
 )]
 #![doc(html_playground_url = "https://play.rust-lang.org/")]
 #![doc = include_str!("../README.md")]
+mod access;
+mod activity;
 mod address;
 mod address_components;
+mod alias;
+mod arcgis;
+mod boundary;
 mod business;
+mod centerline;
 mod cli;
+mod cluster;
 mod compare;
+mod config;
+mod crs;
+mod dictionary;
+mod diff;
+mod duplicate;
+mod enrich;
 mod error;
+mod esn;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod footprint;
+mod gaps;
 mod geo;
+mod geodatabase;
+mod grid;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod history;
 mod import;
+mod index;
+mod label;
 mod lexisnexis;
+mod manifest;
+mod msag;
+mod notes;
+mod notification;
+mod orphan;
+mod parcel;
+mod parquet;
+mod parse_error;
 mod parser;
+mod phonetic;
+mod pipeline;
+#[cfg(feature = "io")]
+mod postgis;
+mod profile;
+mod provenance;
+#[cfg(feature = "python")]
+mod python;
+mod report;
+mod retired;
+mod serve;
+mod shapefile;
+mod sign;
+mod sql;
+#[cfg(feature = "io")]
+mod sqlite;
+mod stack;
+mod standardize;
+mod stream;
+mod summary;
+mod units;
 mod utils;
+mod vacancy;
+mod validate;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "io")]
+mod watch;
 
+pub use access::{
+    ACCESS_DISTANCE, AccessPoint, AccessPoints, AccessRecord, AccessRecords, AccessStatus,
+};
+pub use activity::{EditorActivities, EditorActivity};
 pub use address::{
-    Address, AddressDelta, AddressDeltas, Addresses, CommonAddress, CommonAddresses,
-    PartialAddress, PartialAddresses,
+    Address, AddressDelta, AddressDeltas, Addresses, CommonAddress, CommonAddresses, DriftSummary,
+    PartialAddress, PartialAddresses, normalize_text, street_name_similarity,
 };
 pub use address_components::{
     AddressStatus, PostalCommunity, State, StreetNamePostType, StreetNamePreDirectional,
     StreetNamePreModifier, StreetNamePreType, StreetSeparator, SubaddressType, zero_floor,
 };
-pub use business::{BusinessLicense, BusinessLicenses, BusinessMatchRecord, BusinessMatchRecords};
-pub use cli::Cli;
+pub use alias::{StreetAlias, StreetAliases};
+pub use arcgis::{
+    ARCGIS_EDIT_CHUNK, ARCGIS_PAGE_SIZE, EditSummary, FromArcGis, from_arcgis_service, is_service,
+    push_match_records,
+};
+#[cfg(feature = "io")]
+pub use arcgis::{block_on, http_post};
+pub use boundary::Boundary;
+pub use business::{
+    BusinessAddressKind, BusinessLicense, BusinessLicenses, BusinessMatchRecord,
+    BusinessMatchRecords, is_xlsx, normalize_business_name,
+};
+pub use centerline::{
+    Centerline, Centerlines, Parity, RangeViolation, RangeViolationKind, RangeViolations,
+};
+pub use cli::{Cli, Command};
+pub use cluster::{
+    CLUSTER_DISPLACEMENT, CLUSTER_DISTANCE, CLUSTER_EXAMPLES, CLUSTER_SIZE, DriftCluster,
+    DriftClusters,
+};
 pub use compare::{
-    AddressMatch, FireInspectionMatch, FireInspectionMatchRecord, FireInspectionMatchRecords,
-    FireInspectionMatches, MatchPartialRecord, MatchPartialRecords, MatchRecord, MatchRecords,
-    MatchStatus, Mismatch,
+    ASSET_AMBIGUITY, ASSET_CONFIDENT_DISTANCE, ASSET_DISTANCE, AddressMatch, AssetAssignment,
+    AssetAssignments, AssetConfidence, BENCH_TOLERANCE, BLOCK_BUCKET, BenchRecord, BenchRecords,
+    BenchRow, BenchSummary, Blocking, BlockingStrategy, CHECKPOINT_INTERVAL, FieldDiff,
+    FieldWeight, FireInspectionMatch, FireInspectionMatchRecord, FireInspectionMatchRecords,
+    FireInspectionMatches, GEOCODE_THRESHOLD, GeocodeInput, GeocodeInputs, GeocodeRecord,
+    GeocodeRecords, Geocoder, INTERSECTION_DISTANCE, MatchCache, MatchConfig, MatchPartialRecord,
+    MatchPartialRecords, MatchRecord, MatchRecords, MatchScoring, MatchStatus, MatchType,
+    MatchWeights, Mismatch, NEAREST_DISTANCE, NEAREST_NEIGHBORS, NearestRecord, NearestRecords,
+    NearestStatus, OverlapRecord, OverlapRecords, REVERSE_DISTANCE, ReconcileRecord,
+    ReconcileRecords, ReconcileStatus, RelationshipRecord, RelationshipRecords, ReversePoint,
+    ReversePoints, ReverseRecord, ReverseRecords, Stewardship, TargetMatchRecord,
+    TargetMatchRecords, TargetStatus, TruthRecord, TruthRecords, parse_intersection,
+};
+pub use config::{Dataset, RunConfig, RunParameters};
+pub use crs::{
+    EPSG_CODES, Geolocate, Units, WGS84, from_geographic, geolocate, to_geographic, transform,
+};
+pub use dictionary::{
+    SUGGESTION_DISTANCE, SUGGESTION_LIMIT, StreetDictionary, StreetEntry, StreetSuggestion,
+    StreetSuggestions,
+};
+pub use diff::{ChangeKind, DIFF_KEY, LayerChange, LayerChanges, LayerVintage};
+pub use duplicate::{
+    Disposition, DispositionRecord, DispositionRecords, DuplicateRecord, DuplicateRecords, Edited,
+    Resolution,
+};
+pub use enrich::{EnrichedAddress, EnrichedAddresses, ZoneLayer};
+pub use error::{
+    AddressError, AddressErrorKind, Arguments, Bincode, Builder, Config, Csv, Database, Gdal, Io,
+    Json, Nom, Parquet, Projection, Service, Shapefile, Spreadsheet,
+};
+pub use esn::{ESN_BUFFER, EsnConflict, EsnConflictKind, EsnConflicts};
+#[cfg(feature = "ffi")]
+pub use ffi::{
+    where_free_string, where_last_error, where_match_pair, where_parse_address, where_standardize,
+};
+pub use footprint::{
+    FOOTPRINT_DISTANCE, Footprint, FootprintRecord, FootprintRecords, FootprintStatus, Footprints,
+};
+pub use gaps::{
+    ASSIGN_NEIGHBORS, AddressGap, AddressGaps, AddressSuggestion, AddressSuggestions, ParcelPoint,
+    ParcelPoints,
+};
+pub use geo::{
+    AddressPoint, AddressPoints, Cartesian, GeoAddress, GeoAddresses, Geographic, SpatialAddress,
+    SpatialAddresses,
+};
+#[cfg(feature = "gdal")]
+pub use geodatabase::from_gdal;
+pub use geodatabase::{from_geodatabase, is_geodatabase};
+pub use grid::{GRID_FIELD, MapGrid};
+#[cfg(feature = "grpc")]
+pub use grpc::{AddressMatcherClient, GRPC_BUFFER, MatchRequest, MatchResult};
+pub use history::{
+    HISTORY_THRESHOLD, IdChange, IdChangeKind, IdChanges, LifecycleEvent, LifecycleRecord,
+    LifecycleRecords, Vintage,
 };
-pub use error::{AddressError, AddressErrorKind, Bincode, Builder, Csv, Io, Nom};
-pub use geo::{Cartesian, GeoAddress, GeoAddresses, Geographic, SpatialAddress, SpatialAddresses};
 pub use import::{
-    Business, Businesses, FireInspection, FireInspectionRaw, FireInspections, GrantsPassAddress,
-    GrantsPassAddresses, GrantsPassSpatialAddress, GrantsPassSpatialAddresses,
+    AddressField, Business, Businesses, ColumnFormat, ColumnMap, ColumnMapping, FireInspection,
+    FireInspectionRaw, FireInspections, GrantsPassAddress, GrantsPassAddresses,
+    GrantsPassSpatialAddress, GrantsPassSpatialAddresses, JacksonCountyAddress,
+    JacksonCountyAddresses, JacksonCountySpatialAddress, JacksonCountySpatialAddresses,
     JosephineCountyAddress, JosephineCountyAddress2024, JosephineCountyAddresses,
     JosephineCountyAddresses2024, JosephineCountySpatialAddress, JosephineCountySpatialAddress2024,
-    JosephineCountySpatialAddresses, JosephineCountySpatialAddresses2024, SpatialAddressesRaw,
+    JosephineCountySpatialAddresses, JosephineCountySpatialAddresses2024, MappedAddresses,
+    SpatialAddressRaw, SpatialAddressesRaw,
 };
+pub use index::{PrefixIndex, SpatialIndex};
+pub use label::{LabelColumn, LabelTemplate, LabeledAddress, LabeledAddresses};
 pub use lexisnexis::{
-    LexisNexis, LexisNexisItem, LexisNexisItemBuilder, LexisNexisRange, LexisNexisRangeItem,
+    LexisNexis, LexisNexisChange, LexisNexisChangeKind, LexisNexisChanges, LexisNexisColumn,
+    LexisNexisItem, LexisNexisItemBuilder, LexisNexisRange, LexisNexisRangeItem,
+    LexisNexisTemplate,
+};
+pub use manifest::{Manifest, Step};
+pub use msag::{MSAG_ESN_FIELD, Msag, MsagRow};
+pub use notes::{NOTE_RULES, NoteAction, NoteQueue, NoteTask};
+pub use notification::{DRIFT_OFFENDERS, NOTIFY_COMMANDS, Notification, NotifyConfig};
+pub use orphan::{ORPHAN_EXAMPLES, OrphanStreet, OrphanStreets};
+pub use parcel::{
+    PARCEL_SITUS_FIELD, PARCEL_TAXLOT_FIELD, Parcel, ParcelIssue, ParcelIssueKind, ParcelIssues,
+    Parcels,
+};
+pub use parquet::{IntoParquet, from_parquet, is_parquet, to_parquet};
+pub use parse_error::{
+    OnError, ParseError, ParseErrors, on_error, set_on_error, take_dropped_rows, take_parse_errors,
 };
 pub use parser::{Parse, deserialize_phone_number, parse_phone_number};
+pub use phonetic::{metaphone, soundex};
+pub use pipeline::{ComparisonBuilder, DriftBuilder, LexisNexisBuilder};
+#[cfg(feature = "io")]
+pub use postgis::{IntoPostgis, from_postgis, postgis_table, to_postgis};
+pub use profile::{Profile, StandardizedAddress, StandardizedAddresses};
+pub use provenance::{FileDigest, PROVENANCE_EXTENSION, Provenance, sha256};
+pub use report::{DRIFT_BINS, DriftBin, DriftReport};
+pub use retired::{RETIRED_YEARS, RetiredAddress, RetiredAddresses};
+pub use serve::{AddressCompletion, AddressQuery, AddressService};
+pub use shapefile::{FromShapefile, from_shapefile, is_shapefile};
+pub use sign::{
+    SIGN_ID_FIELD, SIGN_STREET_FIELD, Sign, SignGap, SignGapKind, SignGaps, SignInventory,
+};
+#[cfg(feature = "io")]
+pub use sqlite::{IntoSqlite, from_sqlite, sqlite_table, to_sqlite};
+pub use stack::{STACK_TOLERANCE, Stack, Stacks};
+pub use standardize::{Rule, Standardizer, UnitFormatRecord, UnitFormatRecords, UnitPolicy};
+pub use stream::{AddressIter, stream_duplicates, to_stream};
+pub use summary::{ExitStatus, RunSummary};
+pub use units::UnitRange;
 pub use utils::{
-    IntoBin, IntoCsv, deserialize_arcgis_data, from_bin, from_csv, to_bin, to_csv, trace_init,
+    Compression, DATA_MAGIC, DATA_VERSION, DataHeader, IntoBin, IntoCsv, IntoGeoJson, IntoJson,
+    OutputColumns, deserialize_arcgis_data, from_bin, from_csv, is_quiet, open_csv, partial_path,
+    progress_bar, read_progress, set_output_columns, set_quiet, source_files, to_bin, to_csv,
+    to_geojson, to_json, to_ndjson, trace_init, write_atomic,
+};
+pub use vacancy::{Occupancy, VacancyRecord, VacancyRecords};
+pub use validate::{
+    COMMUNITY_FIELD, ValidationRecord, ValidationRecords, ValidationRule, ZIP_FIELD,
 };
+#[cfg(feature = "io")]
+pub use watch::{WATCH_DEBOUNCE, timestamped, watch_paths};
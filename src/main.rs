@@ -1,381 +1,106 @@
-use clap::Parser;
 use destination::{
-    Addresses, BusinessLicenses, BusinessMatchRecords, Cartesian, Cli, CommonAddresses,
-    GeoAddresses, GrantsPassAddresses, GrantsPassSpatialAddresses, IntoBin, IntoCsv,
-    JosephineCountyAddresses, JosephineCountyAddresses2024, JosephineCountySpatialAddresses2024,
-    LexisNexis, MatchPartialRecords, MatchRecords, SpatialAddress, SpatialAddresses,
-    SpatialAddressesRaw, trace_init,
+    AddressErrorKind, Cli, Command, ExitStatus, Provenance, RunConfig, RunSummary, WATCH_DEBOUNCE,
+    take_dropped_rows, timestamped, trace_init, watch_paths,
 };
-use tracing::{error, info, trace, warn};
+use std::process::ExitCode;
+use tracing::{error, info};
 
-fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
-    trace_init();
-
-    match cli.command.as_str() {
-        "filter" => {
-            if let Some(filter) = cli.filter {
-                info!("Filtering records.");
-                if let Some(source) = cli.source_type {
-                    match source.as_str() {
-                        "business" => {
-                            let match_records = BusinessMatchRecords::from_csv(cli.source.clone())?;
-                            info!("Source records read: {} entries.", match_records.len());
-                            let mut filtered = match_records.filter(&filter);
-                            info!("Records remaining: {} entries.", filtered.len());
-                            filtered.to_csv(cli.output)?;
-                        }
-                        "partial" => {
-                            let match_records = MatchPartialRecords::from_csv(cli.source.clone())?;
-                            info!("Source records read: {} entries.", match_records.len());
-                            let mut filtered = match_records.clone().filter(&filter);
-                            info!("Records remaining: {} entries.", filtered.len());
-                            filtered.to_csv(cli.output)?;
-                        }
-                        "full" => {
-                            let match_records = MatchRecords::from_csv(cli.source.clone())?;
-                            info!("Source records read: {} entries.", match_records.len());
-                            let mut filtered = match_records.clone().filter(&filter);
-                            info!("Records remaining: {} entries.", filtered.len());
-                            filtered.to_csv(cli.output)?;
-                        }
-                        _ => warn!("Unrecognized source type: {source}"),
-                    }
-                }
-            } else {
-                warn!("Filter parameter (-f or --filter) must be set.");
-            }
-        }
-        "drift" => {
-            info!("Calculating spatial drift between datasets.");
-            trace!("Reading source addresses.");
-            let mut source_addresses = SpatialAddresses::default();
-            if let Some(source_type) = &cli.source_type {
-                match source_type.as_str() {
-                    "grants_pass" => {
-                        source_addresses = SpatialAddresses::from(
-                            &GrantsPassSpatialAddresses::from_csv(&cli.source)?[..],
-                        )
-                    }
-                    "josephine_county" => {
-                        source_addresses = SpatialAddresses::from(
-                            &JosephineCountySpatialAddresses2024::from_csv(&cli.source)?[..],
-                        )
-                    }
-                    _ => error!("Invalid source data type."),
-                }
-            } else {
-                error!("No source data type provided.");
-            }
-
-            trace!("Reading target addresses.");
-            let mut target_addresses = SpatialAddresses::default();
-            if let Some(target) = &cli.target {
-                if let Some(target_type) = &cli.target_type {
-                    match target_type.as_str() {
-                        "grants_pass" => {
-                            target_addresses = SpatialAddresses::from(
-                                &GrantsPassSpatialAddresses::from_csv(target)?[..],
-                            )
-                        }
-                        "josephine_county" => {
-                            target_addresses = SpatialAddresses::from(
-                                &JosephineCountySpatialAddresses2024::from_csv(target)?[..],
-                            )
-                        }
-                        _ => error!("Invalid target data type."),
-                    }
-                } else {
-                    error!("No target data type provided.");
-                }
-            } else {
-                error!("No target data specified.");
-            }
+mod commands;
 
-            let mut deltas =
-                <SpatialAddress as Cartesian>::deltas(&source_addresses, &target_addresses, 99.0);
-            deltas.to_csv(cli.output.clone())?;
+fn main() -> ExitCode {
+    trace_init();
+    let cli = match Cli::load() {
+        Ok(cli) => cli,
+        Err(AddressErrorKind::Arguments(e)) => e.exit(),
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::from(ExitStatus::Failure.code());
         }
-        "lexisnexis" => {
-            info!("Reading source records.");
-            let mut source_addresses = CommonAddresses::default();
-            if let Some(source_type) = &cli.source_type {
-                match source_type.as_str() {
-                    "grants_pass" => {
-                        source_addresses = CommonAddresses::from(
-                            &GrantsPassAddresses::from_csv(cli.source.clone())?[..],
-                        )
-                    }
-                    "josephine_county" => {
-                        source_addresses = CommonAddresses::from(
-                            &JosephineCountyAddresses2024::from_csv(cli.source.clone())?[..],
-                        )
-                    }
-                    "common" => {
-                        source_addresses = CommonAddresses::from(SpatialAddressesRaw::from_csv(
-                            cli.source.clone(),
-                        )?)
-                    }
-                    _ => error!("Unrecognized file format."),
-                }
-            }
+    };
+    if matches!(
+        cli.command,
+        Command::Compare { watch: true, .. } | Command::Drift { watch: true, .. }
+    ) {
+        return watch(cli);
+    }
+    ExitCode::from(execute(cli).code())
+}
 
-            info!("Source records read: {} entries.", source_addresses.len());
+// Runs the command in `cli` and reports the summary of the run.
+fn execute(cli: Cli) -> ExitStatus {
+    let mut run_summary = RunSummary::new(&cli.command.to_string());
+    let started = std::time::SystemTime::now();
+    if let Err(e) = commands::run(cli.clone(), &mut run_summary) {
+        run_summary.fail(ExitStatus::Failure, &format!("{e:?}"));
+    }
+    let status = run_summary.finish(take_dropped_rows());
+    if status < ExitStatus::MissingInput {
+        provenance(&cli, &run_summary, started);
+    }
+    // The summary goes to stderr as a single line, for monitoring scheduled runs.
+    eprintln!("{run_summary}");
+    notify(&cli, &run_summary);
+    status
+}
 
-            trace!("Reading exclusion addresses.");
-            let mut target_addresses = CommonAddresses::default();
-            if let Some(target) = &cli.target {
-                if let Some(target_type) = &cli.target_type {
-                    match target_type.as_str() {
-                        "grants_pass" => {
-                            target_addresses =
-                                CommonAddresses::from(&GrantsPassAddresses::from_csv(target)?[..])
-                        }
-                        "josephine_county" => {
-                            target_addresses = CommonAddresses::from(
-                                &JosephineCountyAddresses::from_csv(target)?[..],
-                            )
-                        }
-                        "common" => {
-                            target_addresses =
-                                CommonAddresses::from(SpatialAddressesRaw::from_csv(target)?)
-                        }
-                        _ => error!("Invalid target data type."),
-                    }
-                } else {
-                    error!("No target data type provided.");
-                }
-            } else {
-                error!("No target data specified.");
+// Writes a provenance sidecar beside each output file written by the run, skipping outputs left
+// unchanged since `started`.  A failed sidecar is logged and does not change the exit code.
+fn provenance(cli: &Cli, run_summary: &RunSummary, started: std::time::SystemTime) {
+    for output in cli.outputs() {
+        let written = std::fs::metadata(&output)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified >= started);
+        if written {
+            match Provenance::new(&output, cli, run_summary).write() {
+                Ok(()) => info!("Provenance written to {:?}", Provenance::sidecar(&output)),
+                Err(e) => error!("Provenance not written for {:?}: {e}", output),
             }
-            info!(
-                "Exclusion records read: {} entries.",
-                target_addresses.len()
-            );
-            let mut lx = LexisNexis::from_addresses(&source_addresses, &target_addresses)?;
-            lx.to_csv(cli.output)?;
         }
-        "save" => {
-            info!("Loading and saving addresses...");
-            trace!("Reading source addresses.");
-            let mut source_addresses = SpatialAddresses::default();
-            if let Some(source_type) = &cli.source_type {
-                match source_type.as_str() {
-                    "grants_pass" => {
-                        source_addresses = SpatialAddresses::from(
-                            &GrantsPassSpatialAddresses::from_csv(&cli.source)?[..],
-                        )
-                    }
-                    "josephine_county" => {
-                        source_addresses = SpatialAddresses::from(
-                            &JosephineCountySpatialAddresses2024::from_csv(&cli.source)?[..],
-                        );
-                        source_addresses.standardize();
-                    }
-                    _ => error!("Invalid source data type."),
-                }
-            } else {
-                error!("No source data type provided.");
-            }
-            if !source_addresses.is_empty() {
-                source_addresses.save(&cli.output)?;
-                info!("Addresses saved to {:?}", &cli.output);
-            } else {
-                warn!("All records dropped.  Aborting save.");
-            }
-        }
-        "orphan_streets" => {
-            info!("Reading source records.");
-            let mut source_addresses = CommonAddresses::default();
-            if let Some(source_type) = &cli.source_type {
-                match source_type.as_str() {
-                    "grants_pass" => {
-                        source_addresses = CommonAddresses::from(
-                            &GrantsPassSpatialAddresses::from_csv(cli.source.clone())?[..],
-                        )
-                    }
-                    "josephine_county" => {
-                        source_addresses = CommonAddresses::from(
-                            &JosephineCountySpatialAddresses2024::from_csv(cli.source.clone())?[..],
-                        )
-                    }
-                    _ => error!("Unrecognized file format."),
-                }
-            }
-
-            info!("Source records read: {} entries.", source_addresses.len());
+    }
+}
 
-            trace!("Reading exclusion addresses.");
-            let mut target_addresses = CommonAddresses::default();
-            if let Some(target) = &cli.target {
-                if let Some(target_type) = &cli.target_type {
-                    match target_type.as_str() {
-                        "josephine_county" => {
-                            target_addresses = CommonAddresses::from(
-                                &JosephineCountySpatialAddresses2024::from_csv(target)?[..],
-                            )
-                        }
-                        _ => error!("Invalid target data type."),
-                    }
-                } else {
-                    error!("No target data type provided.");
-                }
-            } else {
-                error!("No target data specified.");
+// Sends the summary of a finished run to the destinations in the `[notify]` table of the
+// configuration file, if any.  A failed notification is logged and does not change the exit code.
+fn notify(cli: &Cli, run_summary: &RunSummary) {
+    let Some(path) = &cli.config else {
+        return;
+    };
+    match RunConfig::from_toml(path) {
+        Ok(config) if config.notify.applies(&run_summary.command) => {
+            if let Err(e) = config.notify.send(run_summary) {
+                error!("Notification not sent: {e}");
             }
-            info!(
-                "Exclusion records read: {} entries.",
-                target_addresses.len()
-            );
-            let orphans = &source_addresses.orphan_streets(&target_addresses);
-            info!("{:?}", orphans);
         }
-        "duplicates" => {
-            info!("Reading source records.");
-            let mut source_addresses = CommonAddresses::default();
-            if let Some(source_type) = &cli.source_type {
-                match source_type.as_str() {
-                    "grants_pass" => {
-                        source_addresses = CommonAddresses::from(
-                            &GrantsPassSpatialAddresses::from_csv(cli.source.clone())?[..],
-                        )
-                    }
-                    "josephine_county" => {
-                        source_addresses = CommonAddresses::from(
-                            &JosephineCountySpatialAddresses2024::from_csv(cli.source.clone())?[..],
-                        )
-                    }
-                    _ => error!("Unrecognized file format."),
-                }
-            }
+        Ok(_) => {}
+        Err(e) => error!("Notification not sent: {e}"),
+    }
+}
 
-            info!("Source records read: {} entries.", source_addresses.len());
-            info!("Screening addresses for duplicate records.");
-            let mut duplicates = CommonAddresses::from(&source_addresses.filter("duplicate")[..]);
-            info!("Duplicate records: {:?}", duplicates.len());
-            info!("Output file: {:?}", cli.output);
-            duplicates.to_csv(cli.output)?;
-        }
-        "business" => {
-            info!("Matching business addresses.");
-            info!("Reading source records.");
-            let source_addresses = BusinessLicenses::from_csv(cli.source.clone())?;
-            info!("Source records read: {} entries.", source_addresses.len());
-            let mut source_addresses = source_addresses.deduplicate();
-            source_addresses.detype_subaddresses()?;
-            info!(
-                "Records deduplicated: {} remaining.",
-                source_addresses.len()
-            );
-            info!("Reading comparison records.");
-            let mut target_addresses = GeoAddresses::default();
-            if let Some(target) = &cli.target {
-                if let Some(target_type) = &cli.target_type {
-                    match target_type.as_str() {
-                        "grants_pass" => {
-                            target_addresses = GeoAddresses::from(
-                                &GrantsPassSpatialAddresses::from_csv(target)?[..],
-                            )
-                        }
-                        _ => info!("Unrecognized file format."),
-                    }
-                }
-                info!("Target records read: {} entries.", target_addresses.len());
-            }
-            if let Some(alternate) = cli.alternate {
-                info!("Comparing multiple targets.");
-                let mut alt_target = GeoAddresses::default();
-                if let Some(target_type) = &cli.alternate_type {
-                    match target_type.as_str() {
-                        "grants_pass" => {
-                            alt_target = GeoAddresses::from(
-                                &GrantsPassSpatialAddresses::from_csv(alternate)?[..],
-                            )
-                        }
-                        _ => error!("Unrecognized file format."),
-                    }
-                }
-                info!(
-                    "Alternate target records read: {} entries.",
-                    alt_target.len()
-                );
-                info!("Comparing records.");
-                let mut match_records = BusinessMatchRecords::compare_chain(
-                    &source_addresses,
-                    &[&target_addresses, &alt_target],
-                );
-                info!("{:?} records categorized.", match_records.len());
-                info!("Output file: {:?}", cli.output);
-                match_records.to_csv(cli.output)?;
-            } else {
-                info!("Comparing records.");
-                let mut match_records =
-                    BusinessMatchRecords::compare(&source_addresses, &target_addresses);
-                info!("{:?} records categorized.", match_records.len());
-                info!("Output file: {:?}", cli.output);
-                match_records.to_csv(cli.output)?;
+// Runs the command in `cli` each time the source or target files change, copying each output
+// file to a timestamped file beside it.  Runs until interrupted, or until the files cannot be
+// watched.
+fn watch(cli: Cli) -> ExitCode {
+    let paths = [cli.source.clone(), cli.target.clone()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<std::path::PathBuf>>();
+    let result = watch_paths(&paths, WATCH_DEBOUNCE, None, || {
+        let time = chrono::Local::now();
+        let status = execute(cli.clone());
+        if status < ExitStatus::MissingInput && cli.output.is_file() {
+            let copy = timestamped(&cli.output, &time);
+            match std::fs::copy(&cli.output, &copy) {
+                Ok(_) => info!("Output copied to {:?}", copy),
+                Err(e) => error!("Output not copied to {:?}: {e}", copy),
             }
         }
-        "compare" => {
-            info!("Reading source records.");
-            let mut source = GeoAddresses::default();
-            if let Some(source_type) = &cli.source_type {
-                match source_type.as_str() {
-                    "grants_pass" => {
-                        source = GeoAddresses::from(
-                            &GrantsPassSpatialAddresses::from_csv(cli.source.clone())?[..],
-                        )
-                    }
-                    "josephine_county" => {
-                        source = GeoAddresses::from(
-                            &JosephineCountySpatialAddresses2024::from_csv(cli.source.clone())?[..],
-                        )
-                    }
-                    "common" => {
-                        source =
-                            GeoAddresses::from(SpatialAddressesRaw::from_csv(cli.source.clone())?)
-                    }
-                    _ => error!("Unrecognized file format."),
-                }
-            }
-            info!("Reading target records.");
-            let mut target = GeoAddresses::default();
-            if let Some(target_type) = &cli.target_type {
-                if let Some(target_path) = &cli.target {
-                    match target_type.as_str() {
-                        "grants_pass" => {
-                            target = GeoAddresses::from(
-                                &GrantsPassSpatialAddresses::from_csv(target_path)?[..],
-                            )
-                        }
-                        "josephine_county" => {
-                            target = GeoAddresses::from(
-                                &JosephineCountySpatialAddresses2024::from_csv(target_path)?[..],
-                            );
-                            target.standardize();
-                        }
-                        "common" => {
-                            target = GeoAddresses::from(SpatialAddressesRaw::from_csv(target_path)?)
-                        }
-                        _ => error!("Unrecognized file format."),
-                    }
-                }
-            }
-            info!("Comparing records.");
-
-            info!("Remove retired addresses from source.");
-            info!("Source records prior: {}", source.len());
-            source.filter_field("active", "");
-            // source = GeoAddresses::from(&source.filter("active")[..]);
-            info!("Source records post: {}", source.len());
-
-            let mut match_records = MatchRecords::compare(&source, &target);
-            info!("{:?} records categorized.", match_records.len());
-            info!("Output file: {:?}", cli.output);
-            match_records.to_csv(cli.output)?;
+        true
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::from(ExitStatus::Failure.code())
         }
-        _ => {}
     }
-
-    Ok(())
 }
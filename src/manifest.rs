@@ -0,0 +1,347 @@
+//! The `manifest` module runs a sequence of operations described in a TOML manifest, sharing
+//! loaded datasets between steps so each file is read once per run.
+//!
+//! Each step names its operation in the `op` key.  Datasets and results are held by name, and
+//! later steps refer to them by that name:
+//!
+//! ```toml
+//! [datasets.city]
+//! path = "data/city_addresses_20241007.csv"
+//! type = "grants_pass"
+//!
+//! [[steps]]
+//! op = "load"
+//! name = "city"
+//!
+//! [[steps]]
+//! op = "load"
+//! name = "county"
+//! path = "data/county_addresses_20241007.csv"
+//! type = "josephine_county"
+//!
+//! [[steps]]
+//! op = "standardize"
+//! name = "county"
+//!
+//! [[steps]]
+//! op = "compare"
+//! source = "city"
+//! target = "county"
+//! name = "matches"
+//!
+//! [[steps]]
+//! op = "filter"
+//! input = "matches"
+//! filter = "missing"
+//! name = "missing"
+//!
+//! [[steps]]
+//! op = "save"
+//! input = "missing"
+//! output = "missing.csv"
+//! ```
+use crate::{
+    AddressError, Addresses, Config, Dataset, FromShapefile, GrantsPassSpatialAddresses, IntoBin,
+    IntoCsv, IntoGeoJson, JacksonCountySpatialAddresses, JosephineCountySpatialAddresses2024,
+    MatchConfig, MatchRecords, MatchScoring, SpatialAddresses, SpatialAddressesRaw, Standardizer,
+    StreetAliases, config::from_toml,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// The `Step` enum describes a single operation in a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Step {
+    /// The `Load` variant reads spatial addresses into the dataset `name`.  If `path` is absent,
+    /// the path and type come from the manifest dataset of the same name.  The `address_type`
+    /// field is read from the `type` key, and accepts 'grants_pass', 'josephine_county' and
+    /// 'common'.
+    Load {
+        /// Name of the loaded dataset.
+        name: String,
+        /// Path to the address file.
+        #[serde(default)]
+        path: Option<PathBuf>,
+        /// Address format of the file.
+        #[serde(rename = "type", default)]
+        address_type: Option<String>,
+    },
+    /// The `Standardize` variant converts the county naming conventions of dataset `name` to city
+    /// naming conventions.  If `rules` is set, the rules come from the TOML file at the path, and
+    /// otherwise from [`Standardizer::josephine_county`].
+    Standardize {
+        /// Name of the dataset to standardize.
+        name: String,
+        /// Path to a TOML file of standardization rules.
+        #[serde(default)]
+        rules: Option<PathBuf>,
+    },
+    /// The `Compare` variant compares dataset `source` against dataset `target`, storing the
+    /// match records as `name`.  The optional `fuzzy` and `radius` fields work as the
+    /// command-line flags of the same name.
+    Compare {
+        /// Name of the source dataset.
+        source: String,
+        /// Name of the target dataset.
+        target: String,
+        /// Name of the resulting match records.
+        name: String,
+        /// Similarity threshold for fuzzy street name matching.
+        #[serde(default)]
+        fuzzy: Option<f64>,
+        /// Search radius for candidate addresses, in meters.
+        #[serde(default)]
+        radius: Option<f64>,
+        /// Score at or above which a candidate matches.  See [`MatchScoring`].
+        #[serde(default)]
+        accept: Option<f64>,
+        /// Score at or above which a candidate needs review.
+        #[serde(default)]
+        review: Option<f64>,
+        /// Match street names below the similarity threshold that sound alike.
+        #[serde(default)]
+        phonetic: bool,
+        /// Fields that must agree to match, beyond the defaults.  See [`MatchConfig`].
+        #[serde(default)]
+        include: Vec<String>,
+        /// Fields reported as mismatches rather than preventing a match.
+        #[serde(default)]
+        exclude: Vec<String>,
+        /// Compare text fields ignoring case and extra whitespace.
+        #[serde(default)]
+        normalize: bool,
+        /// Path to a csv table of street name aliases.  See [`StreetAliases`].
+        #[serde(default)]
+        aliases: Option<PathBuf>,
+    },
+    /// The `Filter` variant filters the dataset or match records `input`, storing the result as
+    /// `name`.  Match records accept the filters of [`MatchRecords::filter`].  Addresses accept
+    /// "duplicate" and the fields of [`Addresses::filter_field`], compared against `value`.
+    Filter {
+        /// Name of the dataset or match records to filter.
+        input: String,
+        /// Filter to apply.
+        filter: String,
+        /// Field value for address filters.
+        #[serde(default)]
+        value: Option<String>,
+        /// Name of the filtered result.
+        name: String,
+    },
+    /// The `Save` variant writes the dataset or match records `input` to `output`.  With a
+    /// `format` of 'geojson' the records are written as GeoJSON.  Otherwise match records are
+    /// written to csv and addresses to a binary file.
+    Save {
+        /// Name of the dataset or match records to save.
+        input: String,
+        /// Path for the output file.
+        output: PathBuf,
+        /// Output format.
+        #[serde(default)]
+        format: Option<String>,
+    },
+}
+
+/// The `Manifest` struct holds a sequence of [`Step`] operations, along with named datasets the
+/// `load` steps may refer to.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    /// The `datasets` field maps dataset names to address files.
+    pub datasets: BTreeMap<String, Dataset>,
+    /// The `steps` field holds the operations to run, in order.
+    pub steps: Vec<Step>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+// Results held between steps of a manifest.
+enum Table {
+    Addresses(SpatialAddresses),
+    Matches(MatchRecords),
+}
+
+impl Manifest {
+    /// The `from_toml` method reads a manifest from the TOML file at `path`.
+    pub fn from_toml<P: AsRef<Path>>(path: P) -> Result<Self, AddressError> {
+        info!("Reading manifest from {:?}", path.as_ref());
+        let mut manifest: Self = from_toml(&path)?;
+        manifest.path = path.as_ref().into();
+        Ok(manifest)
+    }
+
+    /// The `run` method executes the steps of the manifest in order.  Returns an error for the
+    /// first step that fails.
+    pub fn run(&self) -> Result<(), AddressError> {
+        let mut tables: HashMap<String, Table> = HashMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            info!("Step {}: {:?}", i + 1, step);
+            match step {
+                Step::Load {
+                    name,
+                    path,
+                    address_type,
+                } => {
+                    let dataset = self.datasets.get(name);
+                    let path = path
+                        .clone()
+                        .or(dataset.map(|d| d.path.clone()))
+                        .ok_or_else(|| self.issue(&format!("no path for dataset {name}")))?;
+                    let address_type = address_type
+                        .clone()
+                        .or(dataset.and_then(|d| d.address_type.clone()))
+                        .ok_or_else(|| self.issue(&format!("no type for dataset {name}")))?;
+                    let addresses = match address_type.as_str() {
+                        "grants_pass" => SpatialAddresses::from(
+                            &GrantsPassSpatialAddresses::from_source(&path)?[..],
+                        ),
+                        "josephine_county" => SpatialAddresses::from(
+                            &JosephineCountySpatialAddresses2024::from_source(&path)?[..],
+                        ),
+                        "jackson_county" => SpatialAddresses::from(
+                            &JacksonCountySpatialAddresses::from_source(&path)?[..],
+                        ),
+                        "common" => {
+                            SpatialAddresses::from(SpatialAddressesRaw::from_source(&path)?)
+                        }
+                        "data" => SpatialAddresses::load(&path)?,
+                        _ => {
+                            return Err(self.issue(&format!("invalid data type {address_type}")));
+                        }
+                    };
+                    info!("Dataset {name} read: {} entries.", addresses.len());
+                    tables.insert(name.clone(), Table::Addresses(addresses));
+                }
+                Step::Standardize { name, rules } => {
+                    let Some(Table::Addresses(addresses)) = tables.get_mut(name) else {
+                        return Err(self.issue(&format!("no dataset named {name}")));
+                    };
+                    match rules {
+                        Some(path) => Standardizer::from_toml(path)?.standardize(addresses),
+                        None => Standardizer::josephine_county().standardize(addresses),
+                    }
+                }
+                Step::Compare {
+                    source,
+                    target,
+                    name,
+                    fuzzy,
+                    radius,
+                    accept,
+                    review,
+                    phonetic,
+                    include,
+                    exclude,
+                    normalize,
+                    aliases,
+                } => {
+                    let (Some(Table::Addresses(source)), Some(Table::Addresses(target))) =
+                        (tables.get(source), tables.get(target))
+                    else {
+                        return Err(self.issue(&format!("no datasets named {source} and {target}")));
+                    };
+                    let mut scoring = MatchScoring::new(fuzzy.unwrap_or(1.0))
+                        .with_review(*review)
+                        .with_phonetic(*phonetic);
+                    if let Some(accept) = accept {
+                        scoring = scoring.with_accept(*accept);
+                    }
+                    let mut config = MatchConfig::default().with_normalize(*normalize);
+                    for field in include {
+                        config = config
+                            .include(field)
+                            .map_err(|e| self.issue(&e.to_string()))?;
+                    }
+                    for field in exclude {
+                        config = config
+                            .exclude(field)
+                            .map_err(|e| self.issue(&e.to_string()))?;
+                    }
+                    scoring = scoring.with_config(config);
+                    if let Some(path) = aliases {
+                        scoring = scoring.with_aliases(Some(StreetAliases::from_csv(path)?));
+                    }
+                    let mut source = source.clone();
+                    source.filter_field("active", "");
+                    let match_records =
+                        MatchRecords::compare_scored(&source, target, *radius, &scoring);
+                    info!("{:?} records categorized.", match_records.len());
+                    tables.insert(name.clone(), Table::Matches(match_records));
+                }
+                Step::Filter {
+                    input,
+                    filter,
+                    value,
+                    name,
+                } => {
+                    let table = match tables.get(input) {
+                        Some(Table::Matches(records)) => {
+                            Table::Matches(records.clone().filter(filter))
+                        }
+                        Some(Table::Addresses(addresses)) if filter == "duplicate" => {
+                            Table::Addresses(SpatialAddresses::from(&addresses.filter(filter)[..]))
+                        }
+                        Some(Table::Addresses(addresses)) => {
+                            let mut addresses = addresses.clone();
+                            addresses.filter_field(filter, value.as_deref().unwrap_or_default());
+                            Table::Addresses(addresses)
+                        }
+                        None => return Err(self.issue(&format!("no input named {input}"))),
+                    };
+                    tables.insert(name.clone(), table);
+                }
+                Step::Save {
+                    input,
+                    output,
+                    format,
+                } => {
+                    let geojson = format.as_deref() == Some("geojson");
+                    match tables.get_mut(input) {
+                        Some(Table::Matches(records)) if geojson => records.to_geojson(output)?,
+                        Some(Table::Matches(records)) => records.to_csv(output)?,
+                        Some(Table::Addresses(addresses)) if geojson => {
+                            addresses.to_geojson(output)?
+                        }
+                        Some(Table::Addresses(addresses)) => addresses.save(output)?,
+                        None => return Err(self.issue(&format!("no input named {input}"))),
+                    }
+                    info!("Output file: {:?}", output);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn issue(&self, issue: &str) -> AddressError {
+        Config::new(self.path.clone(), issue.into(), line!(), file!().into()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MatchStatus;
+
+    #[test]
+    fn run_manifest() -> anyhow::Result<()> {
+        let mut manifest = Manifest::from_toml("data/manifest_sample.toml")?;
+        assert_eq!(manifest.steps.len(), 5);
+        let output = std::env::temp_dir().join("manifest_output.csv");
+        if let Some(Step::Save { output: path, .. }) = manifest.steps.last_mut() {
+            *path = output.clone();
+        }
+        manifest.run()?;
+        let records = MatchRecords::from_csv(&output)?;
+        assert_eq!(records.len(), 2);
+        assert!(
+            records
+                .iter()
+                .all(|r| r.match_status == MatchStatus::Matching)
+        );
+        std::fs::remove_file(&output)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+//! The `msag` module aggregates address points into the rows of a Master Street Address Guide
+//! (MSAG), the legacy table of street ranges by Emergency Service Number (ESN) that public safety
+//! answering points use to route emergency calls.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, Cartesian, Geographic, IntoCsv, IntoJson, Io, Parity,
+    ZoneLayer, from_csv, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::info;
+
+/// The `MSAG_ESN_FIELD` constant holds the default name of the attribute holding the ESN in an
+/// ESN polygon layer.
+pub const MSAG_ESN_FIELD: &str = "ESN";
+
+/// The `MsagRow` struct represents a range of address numbers on a street within a single
+/// community and ESN.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct MsagRow {
+    /// The `community` field holds the postal community of the range.
+    pub community: String,
+    /// The `directional` field holds the abbreviated street name pre directional, if any.
+    pub directional: Option<String>,
+    /// The `street_name` field holds the street name, including any premodifier, pretype and
+    /// separator elements.
+    pub street_name: String,
+    /// The `street_type` field holds the abbreviated street name post type, if any.
+    pub street_type: Option<String>,
+    /// The `low` field holds the lowest address number of the range.
+    pub low: i64,
+    /// The `high` field holds the highest address number of the range.
+    pub high: i64,
+    /// The `parity` field holds the side of the street covered by the range, written as the NENA
+    /// code "O", "E" or "B".
+    #[serde(
+        serialize_with = "Parity::serialize_code",
+        deserialize_with = "Parity::deserialize_mixed"
+    )]
+    pub parity: Parity,
+    /// The `esn` field holds the Emergency Service Number of the range, empty for addresses
+    /// outside of the ESN layer.
+    pub esn: String,
+    /// The `count` field holds the number of addresses in the range.
+    pub count: usize,
+}
+
+// Identifies the street and community of an address, in the order of the rows.
+type StreetKey = (String, Option<String>, String, Option<String>);
+
+// A run of address numbers on one side of a street sharing an ESN.
+#[derive(Debug, Clone)]
+struct Run {
+    low: i64,
+    high: i64,
+    esn: String,
+    count: usize,
+}
+
+/// The `Msag` struct holds a vector of type [`MsagRow`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct Msag(Vec<MsagRow>);
+
+impl Msag {
+    /// The `from_addresses` method aggregates `addresses` into MSAG rows, taking the ESN of each
+    /// address from the polygon of `esn` containing it.  Addresses on each side of a street form
+    /// a range until an address in another ESN intervenes.  When the odd and even ranges of a
+    /// street share an ESN, and no address in another ESN falls between them, they combine into
+    /// a single range covering both sides.  Retired addresses are skipped.
+    pub fn from_addresses<T: Address + Cartesian + Geographic>(
+        addresses: &[T],
+        esn: &ZoneLayer,
+    ) -> Self {
+        let mut streets: BTreeMap<StreetKey, Vec<(i64, String)>> = BTreeMap::new();
+        for address in addresses
+            .iter()
+            .filter(|a| a.status() != &AddressStatus::Retired)
+        {
+            let key = (
+                address.postal_community().clone(),
+                address.directional_abbreviated(),
+                address.common_street_name(),
+                address.street_type().map(|t| t.abbreviate()),
+            );
+            let value = esn.zone(address).unwrap_or_default().to_string();
+            streets
+                .entry(key)
+                .or_default()
+                .push((address.number(), value));
+        }
+        let mut rows = Vec::new();
+        for ((community, directional, street_name, street_type), mut numbers) in streets {
+            numbers.sort();
+            let odd = runs(numbers.iter().filter(|(n, _)| n % 2 != 0));
+            let even = runs(numbers.iter().filter(|(n, _)| n % 2 == 0));
+            let row = |run: Run, parity: Parity| MsagRow {
+                community: community.clone(),
+                directional: directional.clone(),
+                street_name: street_name.clone(),
+                street_type: street_type.clone(),
+                low: run.low,
+                high: run.high,
+                parity,
+                esn: run.esn,
+                count: run.count,
+            };
+            let mut street = Vec::new();
+            let mut merged = vec![false; even.len()];
+            for run in odd {
+                let pair = even.iter().enumerate().find(|(i, e)| {
+                    !merged[*i]
+                        && e.esn == run.esn
+                        && numbers.iter().all(|(n, value)| {
+                            *n < run.low.min(e.low)
+                                || *n > run.high.max(e.high)
+                                || *value == run.esn
+                        })
+                });
+                match pair {
+                    Some((i, e)) => {
+                        merged[i] = true;
+                        let both = Run {
+                            low: run.low.min(e.low),
+                            high: run.high.max(e.high),
+                            esn: run.esn,
+                            count: run.count + e.count,
+                        };
+                        street.push(row(both, Parity::Both));
+                    }
+                    None => street.push(row(run, Parity::Odd)),
+                }
+            }
+            for (run, _) in even.into_iter().zip(merged).filter(|(_, m)| !m) {
+                street.push(row(run, Parity::Even));
+            }
+            street.sort_by_key(|r| (r.low, r.high));
+            rows.extend(street);
+        }
+        info!("{} MSAG rows.", rows.len());
+        Self(rows)
+    }
+
+    /// The `filter` method returns the rows with the ESN `filter`.
+    pub fn filter(mut self, filter: &str) -> Self {
+        self.retain(|r| r.esn == filter);
+        self
+    }
+}
+
+// Groups sorted address numbers on one side of a street into runs sharing an ESN.
+fn runs<'a, I: Iterator<Item = &'a (i64, String)>>(numbers: I) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for (number, esn) in numbers {
+        match runs.last_mut() {
+            Some(run) if run.esn == *esn => {
+                run.high = *number;
+                run.count += 1;
+            }
+            _ => runs.push(Run {
+                low: *number,
+                high: *number,
+                esn: esn.clone(),
+                count: 1,
+            }),
+        }
+    }
+    runs
+}
+
+impl IntoCsv<Msag> for Msag {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for Msag {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommonAddress, SpatialAddress, address::riverside};
+
+    #[test]
+    fn msag_rows() -> anyhow::Result<()> {
+        let esn = ZoneLayer::from_path("esn", "data/esn_sample.geojson", Some(MSAG_ESN_FIELD))?;
+        let address = |number: i64, longitude: f64| SpatialAddress {
+            address: CommonAddress {
+                postal_community: "GRANTS PASS".to_string(),
+                ..riverside(number)
+            },
+            latitude: 42.44,
+            longitude,
+            ..Default::default()
+        };
+        // Odd and even sides share ESN 100 up to 300, where the street crosses into ESN 200.
+        let addresses = vec![
+            address(101, -123.35),
+            address(100, -123.35),
+            address(199, -123.34),
+            address(250, -123.34),
+            address(301, -123.32),
+            address(399, -123.31),
+            address(302, -123.32),
+            address(500, -123.20),
+        ];
+        let msag = Msag::from_addresses(&addresses, &esn);
+        assert_eq!(msag.len(), 3);
+        assert_eq!((msag[0].low, msag[0].high), (100, 250));
+        assert_eq!(msag[0].parity, Parity::Both);
+        assert_eq!(msag[0].esn, "100");
+        assert_eq!(msag[0].count, 4);
+        assert_eq!((msag[1].low, msag[1].high, msag[1].count), (301, 399, 3));
+        assert_eq!(msag[1].esn, "200");
+        assert_eq!(msag[2].esn, "");
+        assert_eq!(msag[2].parity, Parity::Even);
+        assert_eq!(msag.clone().filter("200").len(), 1);
+
+        let path = std::env::temp_dir().join("destination_msag.csv");
+        let mut rows = msag.clone();
+        rows.to_csv(&path)?;
+        assert_eq!(Msag::from_csv(&path)?, msag);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
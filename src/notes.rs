@@ -0,0 +1,279 @@
+//! The `notes` module mines the notification and notes fields of City of Grants Pass addresses
+//! for pending actions.  Addressing staff leave reminders in these fields, such as "need to
+//! delete soon?" or "need to site visit to confirm front door locations", that are otherwise lost
+//! when the addresses are converted for comparison.  Records with either field are classified by
+//! keyword into a work queue, oldest edit first.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, Cartesian, Geographic, GrantsPassSpatialAddress,
+    IntoCsv, IntoJson, Io, from_csv, retired::parse_timestamp, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use strum::IntoEnumIterator;
+use tracing::info;
+
+/// The `NOTE_RULES` constant holds the keyword rules classifying notes, in order of precedence.
+/// A note takes the action of the first rule with a keyword appearing in its text, ignoring case.
+pub const NOTE_RULES: [(NoteAction, &[&str]); 3] = [
+    (
+        NoteAction::Retire,
+        &[
+            "retire",
+            "delete",
+            "remove",
+            "demolish",
+            "vacate",
+            "no longer",
+        ],
+    ),
+    (
+        NoteAction::Renumber,
+        &[
+            "renumber",
+            "readdress",
+            "re-address",
+            "address change",
+            "change address",
+            "changing to",
+            "revised",
+            "amended",
+        ],
+    ),
+    (
+        NoteAction::Verify,
+        &["verif", "confirm", "site visit", "check", "review"],
+    ),
+];
+
+/// The `NoteAction` enum describes the action pending on an address, as read from its notes.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+    strum::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteAction {
+    /// The notes call for retiring or deleting the address.
+    #[display("retire")]
+    Retire,
+    /// The notes call for changing the number or street of the address.
+    #[display("renumber")]
+    Renumber,
+    /// The notes call for verifying the address, as by a site visit.
+    #[display("verify")]
+    Verify,
+    /// The notes match no rule, and need a person to read them.
+    #[default]
+    #[display("other")]
+    Other,
+}
+
+impl NoteAction {
+    /// The `classify` method returns the action of the first rule in [`NOTE_RULES`] with a
+    /// keyword appearing in `text`, with the keyword, or [`NoteAction::Other`] if none match.
+    pub fn classify(text: &str) -> (Self, Option<&'static str>) {
+        let text = text.to_lowercase();
+        NOTE_RULES
+            .iter()
+            .find_map(|(action, keywords)| {
+                keywords
+                    .iter()
+                    .find(|keyword| text.contains(*keyword))
+                    .map(|keyword| (*action, Some(*keyword)))
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The `NoteTask` struct holds an address with notes or a notification, and the action its notes
+/// call for.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct NoteTask {
+    /// The `action` field holds the action pending on the address.
+    pub action: NoteAction,
+    /// The `keyword` field holds the keyword of the rule classifying the notes, if any.
+    pub keyword: Option<String>,
+    /// The `label` field holds the label of the address.
+    pub label: String,
+    /// The `status` field holds the status of the address.
+    pub status: AddressStatus,
+    /// The `notes` field holds the text note on the address.
+    pub notes: Option<String>,
+    /// The `notification` field holds the link to the address notification.
+    pub notification: Option<String>,
+    /// The `last_edited_user` field holds the user ID of the last edit to the feature.
+    pub last_edited_user: String,
+    /// The `last_edited_date` field holds the date-time stamp of the last edit to the feature.
+    pub last_edited_date: String,
+    /// The `global_id` field holds the ESRI Global ID of the feature.
+    pub global_id: String,
+    /// The `x` field holds the projected X coordinate of the address.
+    pub x: f64,
+    /// The `y` field holds the projected Y coordinate of the address.
+    pub y: f64,
+    /// The `lat` field holds the WGS84 latitude of the address.
+    pub lat: f64,
+    /// The `lon` field holds the WGS84 longitude of the address.
+    pub lon: f64,
+}
+
+// Returns the trimmed value of an ArcGIS text field, or `None` if it is blank.
+fn text(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// The `NoteQueue` struct holds a vector of type [`NoteTask`], a work queue of pending actions.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct NoteQueue(Vec<NoteTask>);
+
+impl NoteQueue {
+    /// The `from_grants_pass` method creates a task for each address in `addresses` with notes or
+    /// a notification.  The notes are classified by [`NoteAction::classify`], falling back to the
+    /// notification when the notes match no rule, so a link to a verification form reads as
+    /// verify.  Tasks are sorted by the date of the last edit, oldest first, with unreadable dates
+    /// last.
+    pub fn from_grants_pass(addresses: &[GrantsPassSpatialAddress]) -> Self {
+        let mut records = addresses
+            .iter()
+            .filter_map(|address| {
+                let notes = text(&address.notes);
+                let notification = text(&address.notification);
+                if notes.is_none() && notification.is_none() {
+                    return None;
+                }
+                let (action, keyword) = match notes.as_deref().map(NoteAction::classify) {
+                    Some((NoteAction::Other, _)) | None => notification
+                        .as_deref()
+                        .map(NoteAction::classify)
+                        .unwrap_or_default(),
+                    Some(found) => found,
+                };
+                Some(NoteTask {
+                    action,
+                    keyword: keyword.map(str::to_string),
+                    label: address.label(),
+                    status: *address.status(),
+                    notes,
+                    notification,
+                    last_edited_user: address.last_edited_user.clone(),
+                    last_edited_date: address.last_edited_date.clone(),
+                    global_id: address.global_id.clone(),
+                    x: address.x(),
+                    y: address.y(),
+                    lat: address.latitude(),
+                    lon: address.longitude(),
+                })
+            })
+            .collect::<Vec<NoteTask>>();
+        records.sort_by_cached_key(|r| {
+            let edited = parse_timestamp(&r.last_edited_date);
+            (edited.is_none(), edited, r.label.clone())
+        });
+        info!("{} addresses with notes found.", records.len());
+        Self(records)
+    }
+
+    /// The `count` method returns the number of tasks with the action `action`.
+    pub fn count(&self, action: NoteAction) -> usize {
+        self.iter().filter(|r| r.action == action).count()
+    }
+
+    /// The `filter` method returns the subset of tasks with the action named by `filter`, either
+    /// "retire", "renumber", "verify" or "other".
+    pub fn filter(mut self, filter: &str) -> Self {
+        if NoteAction::iter().any(|action| action.to_string() == filter) {
+            self.retain(|r| r.action.to_string() == filter);
+        } else {
+            info!("Invalid filter provided.");
+        }
+        self
+    }
+}
+
+impl IntoCsv<NoteQueue> for NoteQueue {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for NoteQueue {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GrantsPassSpatialAddresses;
+
+    #[test]
+    fn note_work_queue() -> anyhow::Result<()> {
+        assert_eq!(
+            NoteAction::classify("need to delete soon?"),
+            (NoteAction::Retire, Some("delete"))
+        );
+        assert_eq!(
+            NoteAction::classify(
+                "Notified 05/05/2020 that Address will be changing to SW JEFFERSON"
+            )
+            .0,
+            NoteAction::Renumber
+        );
+        assert_eq!(NoteAction::classify("Building E").0, NoteAction::Other);
+        let addresses = GrantsPassSpatialAddresses::from_csv("data/city_addresses_20241007.csv")?;
+        let mut sample = addresses[..4].to_vec();
+        sample[0].notes = Some("Addresses were placed by aerials, need to site visit".to_string());
+        sample[0].last_edited_date = "9/19/2024 14:15:48".to_string();
+        sample[1].notes = Some("GP PUBLIC WORKS".to_string());
+        sample[1].notification =
+            Some("https://example.com/View/1/320_NW_B_ST_VERIFICATION".to_string());
+        sample[1].last_edited_date = "7/1/2024 18:54:44".to_string();
+        sample[2].notes = Some(" ".to_string());
+        sample[2].notification = None;
+        sample[3].notes = Some("no meter".to_string());
+        sample[3].last_edited_date = "unknown".to_string();
+        let queue = NoteQueue::from_grants_pass(&sample);
+        let actions = queue
+            .iter()
+            .map(|r| (r.action, r.label.as_str()))
+            .collect::<Vec<(NoteAction, &str)>>();
+        // Blank notes are skipped, and the oldest edit comes first.
+        assert_eq!(
+            actions,
+            vec![
+                (NoteAction::Verify, sample[1].label().as_str()),
+                (NoteAction::Verify, sample[0].label().as_str()),
+                (NoteAction::Other, sample[3].label().as_str()),
+            ]
+        );
+        assert_eq!(queue[0].keyword.as_deref(), Some("verif"));
+        assert_eq!(queue.count(NoteAction::Verify), 2);
+        assert_eq!(queue.filter("other").len(), 1);
+        Ok(())
+    }
+}
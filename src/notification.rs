@@ -0,0 +1,270 @@
+//! The `notification` module reports the outcome of a run to a webhook or by email, so that staff
+//! learn of new divergent or missing addresses, or of drifting address points, without reading
+//! the output of scheduled runs.  Notifications are configured in the `[notify]` table of the
+//! run configuration:
+//!
+//! ```toml
+//! [notify]
+//! webhook = "https://hooks.slack.com/services/T000/B000/XXXX"
+//! smtp = "smtps://mail.example.com:465"
+//! user = "gis:password"
+//! from = "gis@example.com"
+//! to = ["planning@example.com", "gis@example.com"]
+//! commands = ["compare", "drift"]
+//! ```
+use crate::RunSummary;
+#[cfg(feature = "io")]
+use crate::arcgis::{describe, http_request};
+#[cfg(feature = "io")]
+use crate::{AddressErrorKind, Service, block_on};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "io")]
+use tracing::info;
+
+/// The `NOTIFY_COMMANDS` constant lists the commands that send notifications when the
+/// configuration does not name any.
+pub const NOTIFY_COMMANDS: [&str; 2] = ["compare", "drift"];
+
+/// The `DRIFT_OFFENDERS` constant holds the number of addresses with the greatest drift reported
+/// in the summary of a `drift` run.
+pub const DRIFT_OFFENDERS: usize = 10;
+
+/// The `NotifyConfig` struct holds the destinations for notifications of finished runs, read
+/// from the `[notify]` table of a [`RunConfig`](crate::RunConfig).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// The `webhook` field holds the url of an incoming webhook, such as a Slack or Teams
+    /// channel, receiving the summary as a JSON object with a `text` field.
+    pub webhook: Option<String>,
+    /// The `smtp` field holds the url of the mail server, such as "smtp://mail.example.com:25",
+    /// "smtp://mail.example.com:587?tls=required" for a server requiring STARTTLS, or
+    /// "smtps://mail.example.com:465" for a server requiring TLS.
+    pub smtp: Option<String>,
+    /// The `user` field holds the credentials for the mail server, as "name:password".
+    pub user: Option<String>,
+    /// The `from` field holds the sender address of the email.
+    pub from: Option<String>,
+    /// The `to` field holds the recipient addresses of the email.
+    pub to: Vec<String>,
+    /// The `commands` field holds the names of the commands that send notifications, defaulting
+    /// to [`NOTIFY_COMMANDS`].
+    pub commands: Vec<String>,
+}
+
+impl NotifyConfig {
+    /// The `is_empty` method returns true if the configuration names no webhook or mail server.
+    pub fn is_empty(&self) -> bool {
+        self.webhook.is_none() && self.smtp.is_none()
+    }
+
+    /// The `applies` method returns true if a run of `command` sends notifications.
+    pub fn applies(&self, command: &str) -> bool {
+        if self.is_empty() {
+            false
+        } else if self.commands.is_empty() {
+            NOTIFY_COMMANDS.contains(&command)
+        } else {
+            self.commands.iter().any(|c| c == command)
+        }
+    }
+
+    /// The `send` method posts the notification for `summary` to the webhook and emails it to
+    /// the recipients, for each destination configured.  Returns the first error encountered,
+    /// after trying each destination.
+    #[cfg(feature = "io")]
+    pub fn send(&self, summary: &RunSummary) -> Result<(), AddressErrorKind> {
+        let notification = Notification::from(summary);
+        let mut result = Ok(());
+        if let Some(url) = &self.webhook {
+            let sent = notification.post(url);
+            if sent.is_ok() {
+                info!("Notification posted to webhook.");
+            }
+            result = result.and(sent);
+        }
+        if let Some(url) = &self.smtp {
+            let sent = self.email(url, &notification);
+            if sent.is_ok() {
+                info!("Notification emailed to {} recipients.", self.to.len());
+            }
+            result = result.and(sent);
+        }
+        result
+    }
+
+    // Sends `notification` through the mail server at `url`.
+    #[cfg(feature = "io")]
+    fn email(&self, url: &str, notification: &Notification) -> Result<(), AddressErrorKind> {
+        use lettre::transport::smtp::{SmtpTransport, authentication::Credentials};
+        use lettre::{Address, Transport, address::Envelope};
+        let error = |issue: String| Service::new(url.into(), issue, line!(), file!().into());
+        let from = self.from.clone().unwrap_or_default();
+        let sender = from
+            .parse::<Address>()
+            .map_err(|e| error(format!("invalid sender {from:?}: {e}")))?;
+        let recipients = self
+            .to
+            .iter()
+            .map(|to| {
+                to.parse::<Address>()
+                    .map_err(|e| error(format!("invalid recipient {to:?}: {e}")))
+            })
+            .collect::<Result<Vec<Address>, Service>>()?;
+        let envelope = Envelope::new(Some(sender), recipients).map_err(|e| error(e.to_string()))?;
+        let mut transport = SmtpTransport::from_url(url).map_err(|e| error(describe(&e)))?;
+        if let Some(user) = &self.user {
+            let (name, password) = user.split_once(':').unwrap_or((user, ""));
+            transport = transport.credentials(Credentials::new(name.into(), password.into()));
+        }
+        transport
+            .build()
+            .send_raw(&envelope, notification.email(&from, &self.to).as_bytes())
+            .map_err(|e| error(describe(&e)))?;
+        Ok(())
+    }
+}
+
+/// The `Notification` struct holds the text of a notification for a finished run.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    /// The `subject` field holds a one-line description of the outcome, such as
+    /// "compare: success".
+    pub subject: String,
+    /// The `body` field holds the counts, highlights and errors of the run, one per line.
+    pub body: String,
+}
+
+impl Notification {
+    /// The `payload` method returns the JSON object posted to a webhook.  The `text` field is
+    /// displayed by both Slack and Teams.
+    pub fn payload(&self) -> serde_json::Value {
+        serde_json::json!({ "text": format!("{}\n{}", self.subject, self.body) })
+    }
+
+    /// The `email` method returns the notification as an email message from `from` to `to`.
+    pub fn email(&self, from: &str, to: &[String]) -> String {
+        let body = self.body.replace('\n', "\r\n");
+        format!(
+            "From: {from}\r\nTo: {}\r\nSubject: {}\r\n\r\n{body}",
+            to.join(", "),
+            self.subject
+        )
+    }
+
+    // Posts the notification to the webhook at `url`.
+    #[cfg(feature = "io")]
+    fn post(&self, url: &str) -> Result<(), AddressErrorKind> {
+        let body = self.payload().to_string();
+        block_on(http_request(url.to_string(), "application/json", body))??;
+        Ok(())
+    }
+}
+
+impl From<&RunSummary> for Notification {
+    fn from(summary: &RunSummary) -> Self {
+        let mut lines = vec![format!(
+            "The {} command finished with status {} (exit code {}).",
+            summary.command,
+            summary.status,
+            summary.status.code()
+        )];
+        if let Some(written) = summary.written {
+            lines.push(format!("Records written: {written}"));
+        }
+        if summary.dropped > 0 {
+            lines.push(format!("Rows dropped: {}", summary.dropped));
+        }
+        for (category, count) in &summary.counts {
+            lines.push(format!("{category}: {count}"));
+        }
+        if !summary.highlights.is_empty() {
+            lines.push("Highlights:".to_string());
+            lines.extend(summary.highlights.iter().map(|h| format!("  {h}")));
+        }
+        if !summary.errors.is_empty() {
+            lines.push("Errors:".to_string());
+            lines.extend(summary.errors.iter().map(|e| format!("  {e}")));
+        }
+        Self {
+            subject: format!("{}: {}", summary.command, summary.status),
+            body: lines.join("\n"),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "io"))]
+mod tests {
+    use super::*;
+    use crate::RunConfig;
+
+    #[test]
+    fn notify_run_summary() -> anyhow::Result<()> {
+        let config = RunConfig::from_toml("data/config_sample.toml")?;
+        assert!(config.notify.applies("compare"));
+        assert!(config.notify.applies("drift"));
+        assert!(!config.notify.applies("validate"));
+        assert!(!NotifyConfig::default().applies("compare"));
+
+        let mut summary = RunSummary::new("drift");
+        summary.write(2);
+        summary.count("matched", 20);
+        summary.count("over_threshold", 2);
+        summary.highlight("1 NE A ST: 200.0 meters".to_string());
+        summary.highlight("2 NE A ST: 190.0 meters".to_string());
+        summary.finish(0);
+        let notification = Notification::from(&summary);
+        assert_eq!(notification.subject, "drift: success");
+        let lines = notification.body.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines,
+            vec![
+                "The drift command finished with status success (exit code 0).",
+                "Records written: 2",
+                "matched: 20",
+                "over_threshold: 2",
+                "Highlights:",
+                "  1 NE A ST: 200.0 meters",
+                "  2 NE A ST: 190.0 meters",
+            ]
+        );
+        let email = notification.email("gis@example.com", &config.notify.to);
+        assert!(email.starts_with(
+            "From: gis@example.com\r\nTo: gis@example.com, planning@example.com\r\nSubject: drift: success\r\n\r\n"
+        ));
+
+        // Receive the webhook request on a local port, answering with an empty success.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/hooks/addresses", listener.local_addr()?);
+        let server = std::thread::spawn(move || -> std::io::Result<String> {
+            use std::io::{Read, Write};
+            let (mut stream, _) = listener.accept()?;
+            stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            while !String::from_utf8_lossy(&request).contains("}") {
+                let read = stream.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")?;
+            Ok(String::from_utf8_lossy(&request).into_owned())
+        });
+        let notify = NotifyConfig {
+            webhook: Some(url),
+            ..Default::default()
+        };
+        notify.send(&summary)?;
+        let request = server.join().expect("webhook server panicked")?;
+        assert!(request.starts_with("POST /hooks/addresses"));
+        assert!(request.contains("application/json"));
+        let body = request.split("\r\n\r\n").nth(1).unwrap_or_default();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(body)?,
+            notification.payload()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+//! The `orphan` module reports streets named in one set of addresses but absent from another,
+//! such as city streets missing from the county address list.
+use crate::{
+    Address, AddressErrorKind, IntoCsv, IntoJson, Io, from_csv, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use tracing::info;
+
+/// The `ORPHAN_EXAMPLES` constant holds the number of example addresses listed for each orphan
+/// street.
+pub const ORPHAN_EXAMPLES: usize = 3;
+
+/// The `OrphanStreet` struct represents a street present in the source addresses but absent from
+/// the addresses used for comparison.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct OrphanStreet {
+    /// The `street` field holds the complete street name.
+    pub street: String,
+    /// The `count` field holds the number of source addresses on the street.
+    pub count: usize,
+    /// The `examples` field holds the labels of up to [`ORPHAN_EXAMPLES`] addresses on the street,
+    /// separated by semicolons.  Address records do not carry an object ID, so the label
+    /// identifies the example.
+    pub examples: String,
+}
+
+/// The `OrphanStreets` struct holds a vector of type [`OrphanStreet`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct OrphanStreets(Vec<OrphanStreet>);
+
+impl OrphanStreets {
+    /// The `from_addresses` method reports the complete street names in `addresses` that do not
+    /// appear in `other`, with the number of addresses on each street and a few example
+    /// addresses.  Streets are sorted by name.
+    pub fn from_addresses<T: Address, U: Address>(addresses: &[T], other: &[U]) -> Self {
+        let known = other
+            .iter()
+            .map(|a| a.complete_street_name(false))
+            .collect::<HashSet<String>>();
+        let mut streets: BTreeMap<String, (usize, Vec<String>)> = BTreeMap::new();
+        for address in addresses {
+            let street = address.complete_street_name(false);
+            if known.contains(&street) {
+                continue;
+            }
+            let (count, examples) = streets.entry(street).or_default();
+            *count += 1;
+            if examples.len() < ORPHAN_EXAMPLES {
+                examples.push(address.label());
+            }
+        }
+        info!("{} orphan streets found.", streets.len());
+        let records = streets
+            .into_iter()
+            .map(|(street, (count, examples))| OrphanStreet {
+                street,
+                count,
+                examples: examples.join("; "),
+            })
+            .collect::<Vec<OrphanStreet>>();
+        Self(records)
+    }
+}
+
+impl IntoCsv<OrphanStreets> for OrphanStreets {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for OrphanStreets {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Addresses, CommonAddress, CommonAddresses, StreetNamePostType};
+
+    #[test]
+    fn orphan_street_records() -> anyhow::Result<()> {
+        let address = |number: i64, street_name: &str| CommonAddress {
+            number,
+            street_name: street_name.to_string(),
+            street_type: Some(StreetNamePostType::AVENUE),
+            ..Default::default()
+        };
+        let city = [100, 102, 104, 106]
+            .iter()
+            .map(|n| address(*n, "RIVERSIDE"))
+            .chain([address(200, "PARK"), address(300, "ELM")])
+            .collect::<Vec<CommonAddress>>();
+        let county = [address(500, "PARK")];
+        let orphans = OrphanStreets::from_addresses(&city, &county);
+        assert_eq!(orphans.len(), 2);
+        assert_eq!(orphans[0].street, "ELM AVENUE");
+        assert_eq!(orphans[1].street, "RIVERSIDE AVENUE");
+        assert_eq!(orphans[1].count, 4);
+        assert_eq!(orphans[1].examples.split("; ").count(), ORPHAN_EXAMPLES);
+        assert_eq!(
+            CommonAddresses::from(&city[..])
+                .orphan_streets(&CommonAddresses::from(&county[..]))
+                .len(),
+            orphans.len()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,454 @@
+//! The `parcel` module joins address points to the tax lot parcels containing them, and checks the
+//! situs address recorded for each parcel against the address points on it, reporting parcels
+//! without an address, addresses outside of every parcel, and disagreements between the datasets.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, Boundary, Cartesian, Geographic, Geolocate, IntoCsv,
+    IntoJson, Io, Parse, SpatialIndex, from_csv, normalize_text, progress_bar, to_csv, to_json,
+    to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use strum::IntoEnumIterator;
+use tracing::info;
+
+/// The `PARCEL_TAXLOT_FIELD` constant holds the default name of the attribute holding the map tax
+/// lot number of each parcel.
+pub const PARCEL_TAXLOT_FIELD: &str = "MAPTAXLOT";
+
+/// The `PARCEL_SITUS_FIELD` constant holds the default name of the attribute holding the situs
+/// address of each parcel.
+pub const PARCEL_SITUS_FIELD: &str = "SITUS";
+
+/// The `Parcel` struct holds the boundary of a tax lot, with its map tax lot number and situs
+/// address.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Parcel {
+    /// The `taxlot` field holds the map tax lot number of the parcel.
+    pub taxlot: String,
+    /// The `situs` field holds the situs address of the parcel, if any, such as "322 SE K ST".
+    pub situs: Option<String>,
+    /// The `boundary` field holds the polygons of the parcel.
+    pub boundary: Boundary,
+    // The minimum and maximum x and y of the vertices, for skipping distant parcels.
+    bounds: [f64; 4],
+}
+
+impl Parcel {
+    /// The `new` method creates a parcel with map tax lot number `taxlot` and situs address
+    /// `situs`, bounded by `boundary`.  An empty situs address reads as `None`.
+    pub fn new(taxlot: &str, situs: Option<&str>, boundary: Boundary) -> Self {
+        let mut bounds = [
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ];
+        for [x, y] in boundary.rings.iter().flatten() {
+            bounds = [
+                bounds[0].min(*x),
+                bounds[1].min(*y),
+                bounds[2].max(*x),
+                bounds[3].max(*y),
+            ];
+        }
+        Self {
+            taxlot: taxlot.trim().to_string(),
+            situs: situs
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            boundary,
+            bounds,
+        }
+    }
+
+    /// The `contains` method returns true if `address` lies inside the parcel.
+    pub fn contains<T: Cartesian + Geographic>(&self, address: &T) -> bool {
+        if self.boundary.geographic {
+            self.contains_point(address.longitude(), address.latitude())
+        } else {
+            self.contains_point(address.x(), address.y())
+        }
+    }
+
+    /// The `contains_point` method returns true if the point at `x` and `y`, in the coordinates
+    /// of the parcel boundary, lies inside the parcel.
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        let [min_x, min_y, max_x, max_y] = self.bounds;
+        (min_x..=max_x).contains(&x)
+            && (min_y..=max_y).contains(&y)
+            && self.boundary.contains_point(x, y)
+    }
+
+    /// The `center` method returns the center of the bounding box of the parcel, with the
+    /// distance from the center to a corner of the box.
+    pub fn center(&self) -> ([f64; 2], f64) {
+        let [min_x, min_y, max_x, max_y] = self.bounds;
+        (
+            [(min_x + max_x) / 2.0, (min_y + max_y) / 2.0],
+            (max_x - min_x).hypot(max_y - min_y) / 2.0,
+        )
+    }
+
+    /// The `situs_matches` method returns true if the situs address of the parcel names the
+    /// address number and street of `address`, ignoring the subaddress, case and extra whitespace.
+    /// Returns false if the parcel has no situs address, or if it does not parse.
+    pub fn situs_matches<T: Address + ?Sized>(&self, address: &T) -> bool {
+        let Some(situs) = &self.situs else {
+            return false;
+        };
+        match Parse::address(&normalize_text(situs)) {
+            Ok((_, mut partial)) if partial.address_number.is_some() => {
+                partial.subaddress_type = None;
+                partial.subaddress_identifier = None;
+                partial.building = None;
+                partial.floor = None;
+                partial.label()
+                    == format!(
+                        "{} {}",
+                        address.complete_address_number(),
+                        address.complete_street_name(true)
+                    )
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The `Parcels` struct holds a vector of type [`Parcel`], with a [`SpatialIndex`] over the
+/// centers of the parcels for finding the parcels near an address.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Deref)]
+#[serde(from = "Vec<Parcel>", into = "Vec<Parcel>")]
+pub struct Parcels {
+    #[deref]
+    parcels: Vec<Parcel>,
+    // Grid index over the centers of the parcel bounding boxes.
+    index: SpatialIndex,
+    // Largest distance from the center of a parcel to a corner of its bounding box, so a query
+    // of this radius around a point finds every parcel that may contain it.
+    reach: f64,
+}
+
+impl Parcels {
+    /// The `from_path` method reads the parcels of the GeoJSON file or polygon shapefile at
+    /// `path`, taking the map tax lot number from the attribute named `taxlot_field` and the
+    /// situs address from the attribute named `situs_field`.
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        taxlot_field: &str,
+        situs_field: &str,
+    ) -> Result<Self, AddressErrorKind> {
+        let path = path.as_ref();
+        // Each read yields the features in file order, so the attributes pair up by position.
+        let taxlots = Boundary::from_features(path, taxlot_field)?;
+        let situs = Boundary::from_features(path, situs_field)?;
+        let parcels = taxlots
+            .into_iter()
+            .zip(situs)
+            .map(|((taxlot, boundary), (situs, _))| Parcel::new(&taxlot, Some(&situs), boundary))
+            .collect::<Vec<Parcel>>();
+        info!("Parcels read: {}", parcels.len());
+        Ok(Self::from(parcels))
+    }
+
+    /// The `parcel` method returns the index of the first parcel containing `address`, if any.
+    /// Only the parcels whose bounding box may hold the address are tested.
+    pub fn parcel<T: Cartesian + Geographic>(&self, address: &T) -> Option<usize> {
+        let point = match self.parcels.first() {
+            Some(parcel) if parcel.boundary.geographic => [address.longitude(), address.latitude()],
+            _ => [address.x(), address.y()],
+        };
+        self.index
+            .within(point, self.reach)
+            .into_iter()
+            .find(|i| self.parcels[*i].contains(address))
+    }
+}
+
+impl PartialEq for Parcels {
+    fn eq(&self, other: &Self) -> bool {
+        self.parcels == other.parcels
+    }
+}
+
+impl From<Vec<Parcel>> for Parcels {
+    fn from(parcels: Vec<Parcel>) -> Self {
+        let centers = parcels
+            .iter()
+            .map(|parcel| parcel.center())
+            .collect::<Vec<_>>();
+        let reach = centers
+            .iter()
+            .map(|(_, radius)| *radius)
+            .fold(0.0, f64::max);
+        let index = SpatialIndex::new(
+            centers.into_iter().map(|(center, _)| center).collect(),
+            reach,
+        );
+        Self {
+            parcels,
+            index,
+            reach,
+        }
+    }
+}
+
+impl From<Parcels> for Vec<Parcel> {
+    fn from(parcels: Parcels) -> Self {
+        parcels.parcels
+    }
+}
+
+/// The `ParcelIssueKind` enum describes the reason a parcel or address needs review.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+    strum::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ParcelIssueKind {
+    /// The tax lot recorded for the address differs from the parcel containing it.
+    #[default]
+    #[display("taxlot")]
+    Taxlot,
+    /// The situs address of the parcel names none of the addresses on the parcel.
+    #[display("situs")]
+    Situs,
+    /// The address falls outside of every parcel.
+    #[display("outside")]
+    Outside,
+    /// No address point falls inside the parcel.
+    #[display("unaddressed")]
+    Unaddressed,
+}
+
+/// The `ParcelIssue` struct reports a parcel or address needing review.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ParcelIssue {
+    /// The `kind` field holds the reason the parcel or address needs review.
+    pub kind: ParcelIssueKind,
+    /// The `label` field holds the address label, or `None` for an unaddressed parcel.
+    pub label: Option<String>,
+    /// The `recorded` field holds the tax lot recorded for the address, if any.
+    pub recorded: Option<String>,
+    /// The `taxlot` field holds the map tax lot number of the parcel, if any.
+    pub taxlot: Option<String>,
+    /// The `situs` field holds the situs address of the parcel, if any.
+    pub situs: Option<String>,
+    /// The `x` field holds the projected X coordinate of the address, if any.
+    pub x: Option<f64>,
+    /// The `y` field holds the projected Y coordinate of the address, if any.
+    pub y: Option<f64>,
+    /// The `lat` field holds the WGS84 latitude of the address, if any, computed from the projected
+    /// coordinates when their coordinate reference system is known.
+    #[serde(default)]
+    pub lat: Option<f64>,
+    /// The `lon` field holds the WGS84 longitude of the address, if any.
+    #[serde(default)]
+    pub lon: Option<f64>,
+}
+
+impl Geolocate for ParcelIssue {
+    fn projected(&self) -> Option<(f64, f64)> {
+        self.x.zip(self.y)
+    }
+
+    fn set_geographic(&mut self, longitude: f64, latitude: f64) {
+        self.lat = Some(latitude);
+        self.lon = Some(longitude);
+    }
+}
+
+/// The `ParcelIssues` struct holds a vector of type [`ParcelIssue`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct ParcelIssues(Vec<ParcelIssue>);
+
+impl ParcelIssues {
+    /// The `check` method joins each address in `addresses`, paired with its recorded tax lot,
+    /// if any, to the parcel in `parcels` containing it.  Addresses with a recorded tax lot
+    /// differing from their parcel, addresses outside of every parcel, and addresses on a parcel
+    /// whose situs address names none of the addresses on it are reported, followed by the
+    /// parcels with no address.  Tax lots compare ignoring case and whitespace.  Retired
+    /// addresses are skipped.
+    pub fn check<T: Address + Cartesian + Geographic + Sync>(
+        addresses: &[(T, Option<String>)],
+        parcels: &Parcels,
+    ) -> Self {
+        let addresses = addresses
+            .iter()
+            .filter(|(address, _)| address.status() != &AddressStatus::Retired)
+            .collect::<Vec<&(T, Option<String>)>>();
+        let joined = addresses
+            .par_iter()
+            .progress_with(progress_bar(addresses.len(), "Joining parcels."))
+            .map(|(address, _)| parcels.parcel(address))
+            .collect::<Vec<Option<usize>>>();
+        // A parcel agrees with its addresses if its situs names any of them, or if it has none.
+        let mut agrees = parcels
+            .iter()
+            .map(|parcel| parcel.situs.is_none())
+            .collect::<Vec<bool>>();
+        let mut addressed = vec![false; parcels.len()];
+        for ((address, _), parcel) in addresses.iter().zip(&joined) {
+            if let Some(i) = parcel {
+                addressed[*i] = true;
+                agrees[*i] = agrees[*i] || parcels[*i].situs_matches(address);
+            }
+        }
+        let mut records = Vec::new();
+        for ((address, recorded), parcel) in addresses.iter().zip(&joined) {
+            let recorded = recorded
+                .as_ref()
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty());
+            let parcel = parcel.map(|i| (i, &parcels[i]));
+            let kind = match (&recorded, parcel) {
+                (_, None) => ParcelIssueKind::Outside,
+                (Some(recorded), Some((_, parcel)))
+                    if compact(recorded) != compact(&parcel.taxlot) =>
+                {
+                    ParcelIssueKind::Taxlot
+                }
+                (_, Some((i, _))) if !agrees[i] => ParcelIssueKind::Situs,
+                _ => continue,
+            };
+            records.push(ParcelIssue {
+                kind,
+                label: Some(address.label()),
+                recorded,
+                taxlot: parcel.map(|(_, p)| p.taxlot.clone()),
+                situs: parcel.and_then(|(_, p)| p.situs.clone()),
+                x: Some(address.x()),
+                y: Some(address.y()),
+                ..Default::default()
+            });
+        }
+        for (parcel, _) in parcels.iter().zip(&addressed).filter(|(_, a)| !**a) {
+            records.push(ParcelIssue {
+                kind: ParcelIssueKind::Unaddressed,
+                taxlot: Some(parcel.taxlot.clone()),
+                situs: parcel.situs.clone(),
+                ..Default::default()
+            });
+        }
+        records.sort_by_key(|r| r.kind);
+        info!("{} parcel issues reported.", records.len());
+        Self(records)
+    }
+
+    /// The `filter` method returns the subset of records with the issue kind named `filter`,
+    /// such as "situs".
+    pub fn filter(mut self, filter: &str) -> Self {
+        if ParcelIssueKind::iter().any(|kind| kind.to_string() == filter) {
+            self.retain(|r| r.kind.to_string() == filter);
+        } else {
+            info!("Invalid filter provided.");
+        }
+        self
+    }
+}
+
+// Removes whitespace from `taxlot` and uppercases it, so tax lot numbers formatted with and
+// without spaces compare equal.
+fn compact(taxlot: &str) -> String {
+    taxlot
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+impl IntoCsv<ParcelIssues> for ParcelIssues {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for ParcelIssues {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoBin, SpatialAddress, SpatialAddresses};
+
+    #[test]
+    fn parcel_situs_checks() -> anyhow::Result<()> {
+        let parcels = Parcels::from_path(
+            "data/parcel_sample.geojson",
+            PARCEL_TAXLOT_FIELD,
+            PARCEL_SITUS_FIELD,
+        )?;
+        assert_eq!(parcels.len(), 4);
+        assert_eq!(parcels[1].situs.as_deref(), Some("315 SE K STREET"));
+        assert!(parcels[3].situs.is_none());
+
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let recorded = |label: &str| match label {
+            "305 SE K ST" => Some("36-05-17-AB-101".to_string()),
+            "315 SE K ST" => Some("36-05-17-ab-200 ".to_string()),
+            _ => None,
+        };
+        let addresses = city_addresses
+            .iter()
+            .filter(|a| a.street_name() == "K" && (300..420).contains(&a.number()))
+            .map(|a| (a.clone(), recorded(&a.label())))
+            .collect::<Vec<(SpatialAddress, Option<String>)>>();
+        let issues = ParcelIssues::check(&addresses, &parcels);
+
+        // The recorded tax lot of 305 differs from its parcel, while 315 differs only in format.
+        let taxlots = issues.clone().filter("taxlot");
+        assert_eq!(taxlots.len(), 1);
+        assert_eq!(taxlots[0].label.as_deref(), Some("305 SE K ST"));
+        assert_eq!(taxlots[0].taxlot.as_deref(), Some("36-05-17-AB-100"));
+        // The situs of the parcel under 414 names another address.
+        let situs = issues.clone().filter("situs");
+        assert_eq!(situs.len(), 1);
+        assert_eq!(situs[0].label.as_deref(), Some("414 SE K ST"));
+        assert_eq!(situs[0].situs.as_deref(), Some("999 SE K ST"));
+        let unaddressed = issues.clone().filter("unaddressed");
+        assert_eq!(unaddressed.len(), 1);
+        assert_eq!(unaddressed[0].taxlot.as_deref(), Some("36-05-17-AB-400"));
+        let outside = issues.filter("outside");
+        assert!(
+            outside
+                .iter()
+                .any(|i| i.label.as_deref() == Some("322 SE K ST"))
+        );
+        assert!(outside.iter().all(|i| i.taxlot.is_none()));
+
+        // The index finds the same parcel as testing every parcel in turn.
+        for (address, _) in &addresses {
+            assert_eq!(
+                parcels.parcel(address),
+                parcels.iter().position(|parcel| parcel.contains(address))
+            );
+        }
+        assert!(addresses.iter().any(|(a, _)| parcels.parcel(a).is_some()));
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! The `parse_error` module controls how csv readers respond to rows that fail to parse, and
+//! collects a report of the failed rows so dirty exports can be processed and fixed afterward.
+use crate::{AddressErrorKind, IntoCsv, IntoJson, Io, from_csv, to_csv, to_json, to_ndjson};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use tracing::trace;
+
+/// The `OnError` enum selects how csv readers respond to a row that fails to parse.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize, derive_more::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    /// Drops the row and continues reading.
+    #[default]
+    #[display("skip")]
+    Skip,
+    /// Stops reading, returning an error for the row.
+    #[display("abort")]
+    Abort,
+    /// Drops the row and continues reading, recording the row in the report returned by
+    /// [`take_parse_errors`].
+    #[display("collect")]
+    Collect,
+}
+
+impl OnError {
+    /// The `from_name` method returns the mode named `name`, one of "skip", "abort" or "collect".
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "skip" => Some(Self::Skip),
+            "abort" => Some(Self::Abort),
+            "collect" => Some(Self::Collect),
+            _ => None,
+        }
+    }
+}
+
+// Csv readers run on the calling thread, so the mode and the collected rows are kept per
+// thread.
+thread_local! {
+    static ON_ERROR: Cell<OnError> = const { Cell::new(OnError::Skip) };
+    static PARSE_ERRORS: RefCell<Vec<ParseError>> = const { RefCell::new(Vec::new()) };
+    static DROPPED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The `set_on_error` function sets how csv readers on the current thread respond to rows that
+/// fail to parse.
+pub fn set_on_error(mode: OnError) {
+    ON_ERROR.with(|cell| cell.set(mode));
+}
+
+/// The `on_error` function returns the parse error mode of the current thread.  See
+/// [`set_on_error`].
+pub fn on_error() -> OnError {
+    ON_ERROR.with(|cell| cell.get())
+}
+
+/// The `take_parse_errors` function returns the rows collected on the current thread under
+/// [`OnError::Collect`] since the last call, clearing the report.
+pub fn take_parse_errors() -> ParseErrors {
+    ParseErrors(PARSE_ERRORS.with(|cell| cell.take()))
+}
+
+/// The `take_dropped_rows` function returns the number of rows dropped on the current thread under
+/// [`OnError::Skip`] or [`OnError::Collect`] since the last call, resetting the count.
+pub fn take_dropped_rows() -> usize {
+    DROPPED.with(|cell| cell.take())
+}
+
+/// The `ParseError` struct describes a row of a csv file that failed to parse.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ParseError {
+    /// The `path` field holds the path to the csv file.
+    pub path: String,
+    /// The `row` field holds the line number of the row in the file, counting the header as line 1.
+    pub row: Option<u64>,
+    /// The `column` field holds the header of the column that failed to parse, if known.
+    pub column: Option<String>,
+    /// The `reason` field describes why the row failed to parse.
+    pub reason: String,
+}
+
+impl ParseError {
+    /// The `from_csv_error` method describes the csv error `error` from reading the file at `path`,
+    /// naming the failed column from `headers` when the error identifies a field.
+    pub fn from_csv_error(
+        path: &Path,
+        error: &csv::Error,
+        headers: Option<&csv::StringRecord>,
+    ) -> Self {
+        let row = error.position().map(|pos| pos.line());
+        let (column, reason) = match error.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => {
+                let column = err
+                    .field()
+                    .and_then(|i| headers.and_then(|h| h.get(i as usize)))
+                    .map(String::from);
+                (column, err.kind().to_string())
+            }
+            _ => (None, error.to_string()),
+        };
+        Self {
+            path: path.to_string_lossy().to_string(),
+            row,
+            column,
+            reason,
+        }
+    }
+
+    /// The `handle` method responds to the failed row according to the mode set by
+    /// [`set_on_error`], returning an error under [`OnError::Abort`].  Under
+    /// [`OnError::Collect`], the row is added to the report returned by [`take_parse_errors`].
+    pub fn handle(self) -> Result<(), Io> {
+        trace!("Dropping: {}", self);
+        let mode = on_error();
+        if mode != OnError::Abort {
+            DROPPED.with(|cell| cell.set(cell.get() + 1));
+        }
+        match mode {
+            OnError::Skip => Ok(()),
+            OnError::Abort => Err(Io::new(
+                self.path.clone().into(),
+                std::io::Error::new(std::io::ErrorKind::InvalidData, self.to_string()),
+                line!(),
+                file!().into(),
+            )),
+            OnError::Collect => {
+                PARSE_ERRORS.with(|cell| cell.borrow_mut().push(self));
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(row) = self.row {
+            write!(f, "row {row}")?;
+        } else {
+            write!(f, "row unknown")?;
+        }
+        if let Some(column) = &self.column {
+            write!(f, ", column {column}")?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// The `ParseErrors` struct holds a vector of type [`ParseError`].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Deref, DerefMut)]
+pub struct ParseErrors(Vec<ParseError>);
+
+impl IntoCsv<ParseErrors> for ParseErrors {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for ParseErrors {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AddressIter, JacksonCountySpatialAddress, JacksonCountySpatialAddresses};
+
+    #[test]
+    fn parse_error_modes() -> anyhow::Result<()> {
+        let sample = std::fs::read_to_string("data/jackson_county_sample.csv")?;
+        let dirty = sample.replacen("4150,", "41X0,", 1);
+        let path = std::env::temp_dir().join("destination_dirty_addresses.csv");
+        std::fs::write(&path, dirty)?;
+
+        assert_eq!(on_error(), OnError::Skip);
+        let addresses = JacksonCountySpatialAddresses::from_csv(&path)?;
+        assert_eq!(addresses.len(), 2);
+        assert!(take_parse_errors().is_empty());
+
+        set_on_error(OnError::Collect);
+        let addresses = JacksonCountySpatialAddresses::from_csv(&path)?;
+        assert_eq!(addresses.len(), 2);
+        let mut records = AddressIter::<JacksonCountySpatialAddress>::from_csv(&path)?;
+        assert_eq!(records.by_ref().count(), 2);
+        records.check()?;
+        let errors = take_parse_errors();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(take_dropped_rows(), 3);
+        assert_eq!(errors[0].row, Some(3));
+        assert_eq!(errors[0].column, Some("ADD_NUM".to_string()));
+
+        set_on_error(OnError::Abort);
+        assert!(JacksonCountySpatialAddresses::from_csv(&path).is_err());
+        let mut records = AddressIter::<JacksonCountySpatialAddress>::from_csv(&path)?;
+        assert_eq!(records.by_ref().count(), 1);
+        assert!(records.check().is_err());
+        assert_eq!(take_dropped_rows(), 0);
+        set_on_error(OnError::Skip);
+        assert_eq!(OnError::from_name("COLLECT"), Some(OnError::Collect));
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
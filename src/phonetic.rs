@@ -0,0 +1,198 @@
+//! The `phonetic` module encodes street names by their sound, so that names spelled differently
+//! by the city and the county, such as "THOMPSON" and "THOMSEN", compare as equal.  The codes
+//! serve as blocking keys and as an alternative to string similarity when matching street names.
+/// The `soundex` function returns the American Soundex code of `name`, a letter followed by three
+/// digits.  Characters other than ASCII letters are ignored, and a name without letters returns
+/// an empty string.
+///
+/// # Examples
+///
+/// ```
+/// use destination::soundex;
+/// assert_eq!(soundex("Robert"), "R163");
+/// assert_eq!(soundex("Rupert"), "R163");
+/// assert_eq!(soundex("Ashcraft"), "A261");
+/// ```
+pub fn soundex(name: &str) -> String {
+    let code = |c: char| match c {
+        'B' | 'F' | 'P' | 'V' => Some('1'),
+        'C' | 'G' | 'J' | 'K' | 'Q' | 'S' | 'X' | 'Z' => Some('2'),
+        'D' | 'T' => Some('3'),
+        'L' => Some('4'),
+        'M' | 'N' => Some('5'),
+        'R' => Some('6'),
+        _ => None,
+    };
+    let mut letters = name
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase());
+    let Some(first) = letters.next() else {
+        return String::new();
+    };
+    let mut result = first.to_string();
+    let mut last = code(first);
+    for c in letters {
+        let digit = code(c);
+        if digit.is_some() && digit != last {
+            result.extend(digit);
+            if result.len() == 4 {
+                break;
+            }
+        }
+        // H and W do not separate letters with the same code, but vowels do.
+        if c != 'H' && c != 'W' {
+            last = digit;
+        }
+    }
+    format!("{result:0<4}")
+}
+
+/// The `metaphone` function returns the Metaphone code of `name`, following the rules published
+/// by Lawrence Philips, with "0" standing for the "TH" sound and "X" for the "SH" sound.  In
+/// addition, a "P" between "M" and "S" or "T" is silent, as in "THOMPSON" or "SAMPSON".
+/// Characters other than ASCII letters are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use destination::metaphone;
+/// assert_eq!(metaphone("Thompson"), "0MSN");
+/// assert_eq!(metaphone("Thomsen"), "0MSN");
+/// assert_eq!(metaphone("Knight"), "NT");
+/// assert_eq!(metaphone("Phillips"), "FLPS");
+/// ```
+pub fn metaphone(name: &str) -> String {
+    let mut word = name
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase())
+        .collect::<Vec<char>>();
+    // Adjacent duplicate letters sound once, except for "CC", as in "ACCENT".
+    word.dedup_by(|b, a| a == b && *a != 'C');
+    // Skip the silent first letter of initial "AE", "GN", "KN", "PN" and "WR".
+    let start = match word.as_slice() {
+        ['A', 'E', ..] | ['G', 'N', ..] | ['K', 'N', ..] | ['P', 'N', ..] | ['W', 'R', ..] => 1,
+        _ => 0,
+    };
+    let at = |i: usize| word.get(i).copied();
+    let is_vowel = |c: Option<char>| matches!(c, Some('A' | 'E' | 'I' | 'O' | 'U'));
+    let is_front = |c: Option<char>| matches!(c, Some('E' | 'I' | 'Y'));
+    let mut code = String::new();
+    for (i, &c) in word.iter().enumerate().skip(start) {
+        let (prev, next, after) = (i.checked_sub(1).and_then(at), at(i + 1), at(i + 2));
+        match c {
+            'A' | 'E' | 'I' | 'O' | 'U' => {
+                if i == start {
+                    code.push(c);
+                }
+            }
+            // Silent in a final "MB", as in "PLUMB".
+            'B' if prev == Some('M') && next.is_none() => {}
+            'C' if next == Some('I') && after == Some('A') => code.push('X'),
+            'C' if next == Some('H') => {
+                // "SCH" sounds as "SK".
+                code.push(if prev == Some('S') { 'K' } else { 'X' });
+            }
+            'C' if is_front(next) => {
+                // Silent in "SCE", "SCI" and "SCY".
+                if prev != Some('S') {
+                    code.push('S');
+                }
+            }
+            'C' => code.push('K'),
+            'D' if next == Some('G') && is_front(after) => code.push('J'),
+            'D' => code.push('T'),
+            // Silent in "GH" before a consonant or at the end, as in "KNIGHT".
+            'G' if next == Some('H') && !is_vowel(after) => {}
+            // Silent in a final "GN" or "GNED", as in "SIGN".
+            'G' if next == Some('N')
+                && (after.is_none()
+                    || (after == Some('E') && at(i + 3) == Some('D') && at(i + 4).is_none())) => {}
+            // Silent in "DGE", "DGI" and "DGY", already sounded by the "D".
+            'G' if prev == Some('D') && is_front(next) => {}
+            'G' if is_front(next) => code.push('J'),
+            'G' => code.push('K'),
+            'H' => {
+                let voiced = !matches!(prev, Some('C' | 'G' | 'P' | 'S' | 'T'));
+                let after_vowel = is_vowel(prev) && !is_vowel(next);
+                if voiced && !after_vowel {
+                    code.push('H');
+                }
+            }
+            'K' if prev == Some('C') => {}
+            'P' if next == Some('H') => code.push('F'),
+            'P' if prev == Some('M') && matches!(next, Some('S' | 'T')) => {}
+            'Q' => code.push('K'),
+            'S' if next == Some('H') => code.push('X'),
+            'S' if next == Some('I') && matches!(after, Some('O' | 'A')) => code.push('X'),
+            'T' if next == Some('I') && matches!(after, Some('O' | 'A')) => code.push('X'),
+            'T' if next == Some('H') => code.push('0'),
+            // Silent in "TCH", as in "HATCH".
+            'T' if next == Some('C') && after == Some('H') => {}
+            'V' => code.push('F'),
+            'W' | 'Y' if !is_vowel(next) => {}
+            'X' if i == start => code.push('S'),
+            'X' => code.push_str("KS"),
+            'Z' => code.push('S'),
+            _ => code.push(c),
+        }
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Blocking, BlockingStrategy, CommonAddress, MatchRecords, MatchScoring, MatchStatus,
+        SpatialAddress, StreetNamePostType,
+    };
+
+    #[test]
+    fn phonetic_matching() -> anyhow::Result<()> {
+        assert_eq!(metaphone("THOMPSON"), metaphone("THOMSEN"));
+        assert_ne!(soundex("THOMPSON"), soundex("THOMSEN"));
+        assert_eq!(metaphone("WRIGHT"), "RT");
+        assert_eq!(metaphone("SCHOOL"), "SKL");
+        assert_eq!(metaphone("CHURCH"), "XRX");
+        assert_eq!(metaphone("BRIDGE"), "BRJ");
+        assert_eq!(metaphone(""), "");
+        assert_ne!(metaphone("MAPLE"), metaphone("MABLE"));
+
+        let address = |street_name: &str| SpatialAddress {
+            address: CommonAddress {
+                number: 100,
+                street_name: street_name.to_string(),
+                street_type: Some(StreetNamePostType::ROAD),
+                zip: 97527,
+                postal_community: "GRANTS PASS".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let city = address("THOMPSON");
+        let county = vec![address("THOMSEN")];
+        let scoring = MatchScoring::new(0.95);
+        assert!(!scoring.compare(&city, &county[0]).coincident);
+        let records = MatchRecords::new_scored(&city, &county, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Missing);
+
+        // Phonetic matching accepts the spelling, but reports the street name as divergent.
+        let scoring = scoring.with_phonetic(true);
+        let result = scoring.compare(&city, &county[0]);
+        assert!(result.coincident);
+        assert!(result.similarity.is_some_and(|s| s < 0.95));
+        let records = MatchRecords::new_scored(&city, &county, &scoring);
+        assert_eq!(records[0].match_status, MatchStatus::Divergent);
+        assert!(!scoring.compare(&city, &address("TAYLOR")).coincident);
+
+        // Zip metaphone blocking keeps the spellings in the same block.
+        let strategy = Blocking::parse("zip_metaphone")?;
+        assert_eq!(strategy.key(&city), strategy.key(&county[0]));
+        let blocked = MatchRecords::compare_blocked(&[city], &county, &strategy, &scoring);
+        assert_eq!(blocked[0].match_status, MatchStatus::Divergent);
+        assert!(Blocking::parse("zip_metaphone:2").is_err());
+        Ok(())
+    }
+}
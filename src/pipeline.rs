@@ -0,0 +1,412 @@
+//! The `pipeline` module exposes the comparisons run by the `compare`, `drift` and `lexisnexis`
+//! commands as builders, so other tools can embed the matching logic without constructing
+//! command line arguments or calling the binary.  Each builder takes addresses already read into
+//! memory, collects the options of the command, and produces the same records the command writes.
+//!
+//! ```
+//! use destination::{ComparisonBuilder, GeoAddresses};
+//!
+//! let records = ComparisonBuilder::new()
+//!     .source(GeoAddresses::default())
+//!     .target(GeoAddresses::default())
+//!     .fuzzy(0.9)
+//!     .run()
+//!     .unwrap();
+//! assert!(records.is_empty());
+//! ```
+use crate::{
+    AddressDeltas, AddressError, Addresses, Blocking, Boundary, Builder, Cartesian,
+    CommonAddresses, GeoAddresses, LexisNexis, MatchConfig, MatchRecords, MatchScoring,
+    ReconcileRecords, STACK_TOLERANCE, SpatialAddress, SpatialAddresses, Stacks, StreetAliases,
+    Units,
+};
+use tracing::info;
+
+// Returns a builder error for `target` reporting `issue`.
+fn invalid(issue: String, target: &str, line: u32) -> Builder {
+    Builder::new(issue, target.to_string(), line, file!().to_string())
+}
+
+/// The `ComparisonBuilder` struct collects the options of the `compare` command, matching the
+/// source addresses against the target addresses.  Retired source addresses are left out of the
+/// comparison, as from the command line.
+#[derive(Debug, Default, Clone)]
+pub struct ComparisonBuilder {
+    source: Option<GeoAddresses>,
+    target: Option<GeoAddresses>,
+    fuzzy: Option<f64>,
+    radius: Option<f64>,
+    accept: Option<f64>,
+    review: Option<f64>,
+    phonetic: bool,
+    config: MatchConfig,
+    aliases: Option<StreetAliases>,
+    blocking: Option<Blocking>,
+}
+
+impl ComparisonBuilder {
+    /// The `new` method creates a `ComparisonBuilder` matching street names exactly, without a
+    /// search radius or blocking.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `source` method sets the addresses to match.
+    pub fn source(mut self, source: GeoAddresses) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// The `target` method sets the addresses to match against.
+    pub fn target(mut self, target: GeoAddresses) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// The `fuzzy` method sets the similarity threshold between 0 and 1 at which street names
+    /// agree, as for the `--fuzzy` argument.
+    pub fn fuzzy(mut self, threshold: f64) -> Self {
+        self.fuzzy = Some(threshold);
+        self
+    }
+
+    /// The `radius` method limits candidates to target addresses within `radius` meters of the
+    /// source address.
+    pub fn radius(mut self, radius: f64) -> Self {
+        self.radius = Some(radius);
+        self
+    }
+
+    /// The `accept` method sets the score at or above which a candidate matches.
+    pub fn accept(mut self, accept: f64) -> Self {
+        self.accept = Some(accept);
+        self
+    }
+
+    /// The `review` method sets the score at or above which a candidate below the accept
+    /// threshold needs review.
+    pub fn review(mut self, review: f64) -> Self {
+        self.review = Some(review);
+        self
+    }
+
+    /// The `phonetic` method sets whether street names that sound alike agree.
+    pub fn phonetic(mut self, phonetic: bool) -> Self {
+        self.phonetic = phonetic;
+        self
+    }
+
+    /// The `config` method sets the fields that must agree for addresses to match.
+    pub fn config(mut self, config: MatchConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The `aliases` method sets the table of street names known to refer to the same street.
+    pub fn aliases(mut self, aliases: StreetAliases) -> Self {
+        self.aliases = Some(aliases);
+        self
+    }
+
+    /// The `blocking` method compares each source address only against the target addresses
+    /// sharing its block under `strategy`.  Blocking takes precedence over the radius.
+    pub fn blocking(mut self, strategy: Blocking) -> Self {
+        self.blocking = Some(strategy);
+        self
+    }
+
+    /// The `scoring` method returns the [`MatchScoring`] built from the options.  Errors if a
+    /// threshold falls outside of the range from 0 to 1.
+    pub fn scoring(&self) -> Result<MatchScoring, Builder> {
+        let target = "MatchScoring";
+        let thresholds = [
+            ("fuzzy", self.fuzzy),
+            ("accept", self.accept),
+            ("review", self.review),
+        ];
+        for (name, value) in thresholds {
+            if let Some(value) = value
+                && !(0.0..=1.0).contains(&value)
+            {
+                return Err(invalid(
+                    format!("{name} threshold {value} must be between 0 and 1"),
+                    target,
+                    line!(),
+                ));
+            }
+        }
+        let mut scoring = MatchScoring::new(self.fuzzy.unwrap_or(1.0))
+            .with_review(self.review)
+            .with_phonetic(self.phonetic)
+            .with_config(self.config)
+            .with_aliases(self.aliases.clone());
+        if let Some(accept) = self.accept {
+            scoring = scoring.with_accept(accept);
+        }
+        Ok(scoring)
+    }
+
+    // Returns the source addresses without retired addresses, and the target addresses.
+    fn addresses(&self) -> Result<(GeoAddresses, &GeoAddresses), Builder> {
+        let target = "ComparisonBuilder";
+        let Some(source) = &self.source else {
+            return Err(invalid("source addresses not set".into(), target, line!()));
+        };
+        let Some(other) = &self.target else {
+            return Err(invalid("target addresses not set".into(), target, line!()));
+        };
+        let mut source = source.clone();
+        source.filter_field("active", "");
+        info!("Source records to compare: {}", source.len());
+        Ok((source, other))
+    }
+
+    /// The `run` method matches each source address against the target addresses, as from the
+    /// `compare` command.  Errors if the source or target addresses are not set, or if the
+    /// scoring options are invalid.
+    pub fn run(&self) -> Result<MatchRecords, AddressError> {
+        let scoring = self.scoring()?;
+        let (source, target) = self.addresses()?;
+        let records = match &self.blocking {
+            Some(strategy) => MatchRecords::compare_blocked(&source, target, strategy, &scoring),
+            None => MatchRecords::compare_scored(&source, target, self.radius, &scoring),
+        };
+        Ok(records)
+    }
+
+    /// The `reconcile` method matches the source and target addresses in both directions, as
+    /// from the `compare` command with the `--bidirectional` flag.  Blocking does not apply.
+    pub fn reconcile(&self) -> Result<ReconcileRecords, AddressError> {
+        let scoring = self.scoring()?;
+        let (source, target) = self.addresses()?;
+        Ok(ReconcileRecords::compare(
+            &source,
+            target,
+            self.radius,
+            &scoring,
+        ))
+    }
+}
+
+/// The `DriftBuilder` struct collects the options of the `drift` command, measuring the distance
+/// between each source address and the target addresses sharing its label.
+#[derive(Debug, Default, Clone)]
+pub struct DriftBuilder {
+    source: Option<SpatialAddresses>,
+    target: Option<SpatialAddresses>,
+    radius: Option<f64>,
+    min: Option<f64>,
+    units: Option<Units>,
+    stacks: bool,
+}
+
+impl DriftBuilder {
+    /// The `new` method creates a `DriftBuilder` measuring every matching pair, in the units of
+    /// the source coordinates.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `source` method sets the addresses to measure from.  If the coordinate reference
+    /// system of both the source and target is known, the target is reprojected to the source.
+    pub fn source(mut self, source: SpatialAddresses) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// The `target` method sets the addresses to measure to.
+    pub fn target(mut self, target: SpatialAddresses) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// The `radius` method limits the target addresses measured to those within `radius` of the
+    /// source address, in the units of the source coordinates.
+    pub fn radius(mut self, radius: f64) -> Self {
+        self.radius = Some(radius);
+        self
+    }
+
+    /// The `min_threshold` method drops distances at or below `min`, in the reporting units, as
+    /// jitter from rounding or digitizing.
+    pub fn min_threshold(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// The `units` method sets the units of the distances reported.
+    pub fn units(mut self, units: Units) -> Self {
+        self.units = Some(units);
+        self
+    }
+
+    /// The `stacks` method sets whether to measure one distance per structure, grouping the
+    /// source addresses stacked at a point, as for the `--stacks` flag.
+    pub fn stacks(mut self, stacks: bool) -> Self {
+        self.stacks = stacks;
+        self
+    }
+
+    /// The `run` method returns the distances between matching addresses, as from the `drift`
+    /// command before applying the reporting threshold.  Summarize the distances with
+    /// [`AddressDeltas::summary`].  Errors if the source or target addresses are not set, or if
+    /// the target cannot be reprojected.
+    pub fn run(&self) -> Result<AddressDeltas, AddressError> {
+        let target = "DriftBuilder";
+        let Some(source) = &self.source else {
+            return Err(invalid("source addresses not set".into(), target, line!()).into());
+        };
+        let Some(other) = &self.target else {
+            return Err(invalid("target addresses not set".into(), target, line!()).into());
+        };
+        let mut other = other.clone();
+        if let (Some(from), Some(to)) = (other.epsg(), source.epsg())
+            && from != to
+        {
+            info!("Reprojecting target from EPSG:{from} to EPSG:{to}.");
+            other.reproject(to)?;
+        }
+        let mut deltas = if self.stacks {
+            Stacks::group(source, STACK_TOLERANCE).deltas(&other, f64::MIN, self.radius)
+        } else {
+            match self.radius {
+                Some(radius) => {
+                    <SpatialAddress as Cartesian>::deltas_within(source, &other, f64::MIN, radius)
+                }
+                None => <SpatialAddress as Cartesian>::deltas(source, &other, f64::MIN),
+            }
+        };
+        let projected = source.epsg().and_then(Units::from_epsg).unwrap_or_default();
+        if let Some(units) = self.units
+            && units != projected
+        {
+            deltas.scale(projected.meters() / units.meters());
+        }
+        if let Some(min) = self.min {
+            deltas.retain(|d| d.delta > min);
+        }
+        Ok(deltas)
+    }
+}
+
+/// The `LexisNexisBuilder` struct collects the options of the `lexisnexis` command, producing
+/// the table of address ranges inside the service area.
+#[derive(Debug, Default, Clone)]
+pub struct LexisNexisBuilder {
+    source: Option<SpatialAddresses>,
+    exclude: CommonAddresses,
+    boundary: Option<Boundary>,
+}
+
+impl LexisNexisBuilder {
+    /// The `new` method creates a `LexisNexisBuilder` without exclusions or a boundary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `source` method sets the addresses to include in the range selection.
+    pub fn source(mut self, source: SpatialAddresses) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// The `exclude` method sets the addresses to exclude from the range selection.
+    pub fn exclude(mut self, exclude: CommonAddresses) -> Self {
+        self.exclude = exclude;
+        self
+    }
+
+    /// The `boundary` method sets the service area polygon.  Source addresses outside of the
+    /// boundary are excluded from the range selection.
+    pub fn boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = Some(boundary);
+        self
+    }
+
+    /// The `run` method returns the table of address ranges, as from the `lexisnexis` command.
+    /// Compare the table with a previous submission using [`LexisNexis::diff`].  Errors if the
+    /// source addresses are not set.
+    pub fn run(&self) -> Result<LexisNexis, AddressError> {
+        let Some(source) = &self.source else {
+            return Err(invalid(
+                "source addresses not set".into(),
+                "LexisNexisBuilder",
+                line!(),
+            )
+            .into());
+        };
+        let table = match &self.boundary {
+            Some(boundary) => LexisNexis::from_boundary(source, boundary, &self.exclude)?,
+            None => LexisNexis::from_addresses(&CommonAddresses::from(&source[..]), &self.exclude)?,
+        };
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntoBin;
+
+    #[test]
+    fn pipeline_builders() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let county_addresses = SpatialAddresses::load("data/county_addresses.data")?;
+        let source = GeoAddresses::from(&city_addresses[0..200]);
+        let target = GeoAddresses::from(&county_addresses[..]);
+        let records = ComparisonBuilder::new()
+            .source(source.clone())
+            .target(target.clone())
+            .fuzzy(0.9)
+            .radius(500.0)
+            .run()?;
+        let mut active = source.clone();
+        active.filter_field("active", "");
+        let expected =
+            MatchRecords::compare_scored(&active, &target, Some(500.0), &MatchScoring::new(0.9));
+        let statuses = |records: &MatchRecords| {
+            records
+                .iter()
+                .map(|r| (r.address_label.clone(), r.match_status.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(statuses(&records), statuses(&expected));
+        // Missing inputs and thresholds out of range are errors rather than fallbacks.
+        assert!(
+            ComparisonBuilder::new()
+                .source(source.clone())
+                .run()
+                .is_err()
+        );
+        let builder = ComparisonBuilder::new().source(source).target(target);
+        assert!(builder.clone().fuzzy(1.5).run().is_err());
+
+        let sample = SpatialAddresses::from(&city_addresses[0..200]);
+        let deltas = DriftBuilder::new()
+            .source(sample.clone())
+            .target(sample.clone())
+            .run()?;
+        // Each address matches itself, at no distance.
+        let zero = deltas.iter().filter(|d| d.delta == 0.0).count();
+        assert!(zero >= sample.len());
+        let jitter = DriftBuilder::new()
+            .source(sample.clone())
+            .target(sample.clone())
+            .min_threshold(f64::MAX)
+            .run()?;
+        assert!(jitter.is_empty());
+
+        let table = LexisNexisBuilder::new().source(sample.clone()).run()?;
+        let common = CommonAddresses::from(&sample[..]);
+        let expected = LexisNexis::from_addresses(&common, &CommonAddresses::default())?;
+        let ranges = |table: &LexisNexis| {
+            table
+                .iter()
+                .map(|item| (item.address_number_from, item.address_number_to))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ranges(&table), ranges(&expected));
+        assert!(LexisNexisBuilder::new().run().is_err());
+        Ok(())
+    }
+}
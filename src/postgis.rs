@@ -0,0 +1,202 @@
+//! The `postgis` module reads and writes tables in a PostgreSQL database with the PostGIS
+//! extension.  Records are written with a binary `COPY` and read back as csv, so tables use the
+//! same column names and deserialization rules as a csv export.
+//!
+//! The `dsn` parameter accepts a connection string such as "postgresql://user@host/gis" or
+//! "host=localhost user=gis dbname=gis".  When the connection string holds no password, the
+//! password is read from the `PGPASSWORD` environment variable.  Connections are not encrypted.
+use crate::{
+    AddressError, AddressErrorKind, Csv, Database, Io,
+    shapefile::from_buffer,
+    sql::{Kind, Table, identifier, quote},
+};
+use postgres::binary_copy::BinaryCopyInWriter;
+use postgres::types::{ToSql, Type};
+use postgres::{Client, NoTls};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::io::Read;
+use tracing::info;
+
+/// The `IntoPostgis` trait indicates the type can be read from and written to a PostGIS table.
+pub trait IntoPostgis<T> {
+    /// The `from_postgis` method attempts to deserialize the data from the table `table` of the
+    /// database at `dsn`.
+    fn from_postgis(dsn: &str, table: &str) -> Result<T, AddressError>;
+    /// The `to_postgis` method attempts to write the data to the table `table` of the database at
+    /// `dsn`, replacing any existing table of the same name.
+    fn to_postgis(&self, dsn: &str, table: &str) -> Result<(), AddressError>;
+}
+
+/// Generic function to deserialize data types from a PostGIS table.  Called by methods to avoid
+/// code duplication.  Columns of the table match fields of `T` by name.  Records that fail to
+/// deserialize are dropped, consistent with [`from_csv`](crate::from_csv).
+pub fn from_postgis<T: DeserializeOwned>(
+    dsn: &str,
+    table: &str,
+) -> Result<Vec<T>, AddressErrorKind> {
+    info!("Reading table {table}.");
+    let name = identifier(table, true)?;
+    let error = |source| database_error(table, source);
+    let mut client = connect(dsn, table)?;
+    let mut buffer = Vec::new();
+    client
+        .copy_out(&format!(
+            "COPY (SELECT * FROM {name}) TO STDOUT WITH (FORMAT csv, HEADER true)"
+        ))
+        .map_err(error)?
+        .read_to_end(&mut buffer)
+        .map_err(|source| Io::new(table.into(), source, line!(), file!().into()))?;
+    Ok(from_buffer(&buffer))
+}
+
+/// Generic function to write data types to a PostGIS table.  Called by methods to avoid code
+/// duplication.  The table is created as described by [`postgis_table`], and the records are
+/// loaded with a binary `COPY`, in a single transaction.  When the records have `latitude` and
+/// `longitude` fields, the table gains a point column `geom` in WGS84, with a spatial index.  An
+/// empty slice creates the table with the columns of `T` and no rows.
+pub fn to_postgis<T: Serialize + Default>(
+    records: &[T],
+    dsn: &str,
+    table: &str,
+) -> Result<(), AddressErrorKind> {
+    let name = identifier(table, true)?;
+    let contents = Table::from_records(records, table)?;
+    info!("Writing {} records to table {table}.", records.len());
+    let error = |source| database_error(table, source);
+    let mut client = connect(dsn, table)?;
+    let mut transaction = client.transaction().map_err(error)?;
+    transaction
+        .batch_execute(&format!(
+            "DROP TABLE IF EXISTS {name};\n{}",
+            create_table(&name, &contents)
+        ))
+        .map_err(error)?;
+
+    let types = contents
+        .kinds
+        .iter()
+        .map(|kind| match kind {
+            Kind::Integer => Type::INT8,
+            Kind::Real => Type::FLOAT8,
+            Kind::Text => Type::TEXT,
+        })
+        .collect::<Vec<Type>>();
+    let sink = transaction
+        .copy_in(&format!("COPY {name} FROM STDIN WITH (FORMAT binary)"))
+        .map_err(error)?;
+    let mut writer = BinaryCopyInWriter::new(sink, &types);
+    let mut rdr = csv::Reader::from_reader(contents.data.as_slice());
+    for row in rdr.records() {
+        let row = row.map_err(|source| Csv::new(table.into(), source, line!(), file!().into()))?;
+        // Empty values are null, typed to match the column.
+        let values = row
+            .iter()
+            .zip(&contents.kinds)
+            .map(|(value, kind)| -> Box<dyn ToSql + Sync> {
+                match kind {
+                    Kind::Integer => Box::new(value.parse::<i64>().ok()),
+                    Kind::Real => Box::new(value.parse::<f64>().ok()),
+                    Kind::Text => Box::new((!value.is_empty()).then(|| value.to_string())),
+                }
+            })
+            .collect::<Vec<Box<dyn ToSql + Sync>>>();
+        writer.write_raw(values).map_err(error)?;
+    }
+    writer.finish().map_err(error)?;
+
+    if [contents.kind("latitude"), contents.kind("longitude")]
+        .iter()
+        .all(|kind| matches!(kind, Some(Kind::Integer | Kind::Real)))
+    {
+        let index = quote(&format!("{}_geom", table.replace('.', "_")));
+        transaction
+            .batch_execute(&format!(
+                "ALTER TABLE {name} ADD COLUMN geom geometry(Point, 4326);\nUPDATE {name} SET geom = ST_SetSRID(ST_MakePoint(longitude, latitude), 4326);\nCREATE INDEX {index} ON {name} USING GIST (geom);"
+            ))
+            .map_err(error)?;
+    }
+    transaction.commit().map_err(error)?;
+    Ok(())
+}
+
+/// The `postgis_table` function returns the statement that creates the table `table` for
+/// `records`.  The table may be qualified by a schema, as in "addresses.city".  Columns holding
+/// only integers are typed `bigint`, columns holding only numbers are typed `double precision`,
+/// and other columns are typed `text`.
+pub fn postgis_table<T: Serialize + Default>(
+    records: &[T],
+    table: &str,
+) -> Result<String, AddressErrorKind> {
+    let name = identifier(table, true)?;
+    let contents = Table::from_records(records, table)?;
+    Ok(create_table(&name, &contents))
+}
+
+// Returns the statement creating the table `name` with the columns of `contents`.
+fn create_table(name: &str, contents: &Table) -> String {
+    let columns = contents
+        .headers
+        .iter()
+        .zip(&contents.kinds)
+        .map(|(header, kind)| {
+            let kind = match kind {
+                Kind::Integer => "bigint",
+                Kind::Real => "double precision",
+                Kind::Text => "text",
+            };
+            format!("{} {kind}", quote(header))
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("CREATE TABLE {name} ({columns});")
+}
+
+// Opens a connection to the database at `dsn`, taking the password from `PGPASSWORD` when the
+// connection string holds none.
+fn connect(dsn: &str, table: &str) -> Result<Client, AddressErrorKind> {
+    let error = |source| database_error(table, source);
+    let mut config = dsn.parse::<postgres::Config>().map_err(error)?;
+    if config.get_password().is_none()
+        && let Ok(password) = std::env::var("PGPASSWORD")
+    {
+        config.password(password);
+    }
+    Ok(config.connect(NoTls).map_err(error)?)
+}
+
+// Reports a failed operation on the table `table`, with the cause given by the server.
+fn database_error(table: &str, source: postgres::Error) -> Database {
+    let issue = match std::error::Error::source(&source) {
+        Some(cause) => format!("{source}: {cause}"),
+        None => source.to_string(),
+    };
+    Database::new(table.into(), issue, line!(), file!().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoBin, IntoCsv, SpatialAddresses, SpatialAddressesRaw};
+
+    #[test]
+    fn postgis_create_table() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let addresses = SpatialAddresses::from(&city_addresses[0..100]);
+        let mut raw = SpatialAddressesRaw::from(&addresses);
+        // Tables use the columns of the common format, so records read back unchanged.
+        let path = std::env::temp_dir().join("destination_postgis_sample.csv");
+        raw.to_csv(&path)?;
+        let read = SpatialAddresses::from(SpatialAddressesRaw::from_csv(&path)?);
+        assert_eq!(read, addresses);
+        std::fs::remove_file(&path)?;
+
+        let create = postgis_table(&raw, "addresses.city")?;
+        assert!(create.starts_with("CREATE TABLE \"addresses\".\"city\" ("));
+        assert!(create.contains("\"number\" bigint"));
+        assert!(create.contains("\"street_name\" text"));
+        assert!(create.contains("\"latitude\" double precision"));
+        assert!(postgis_table(&raw, "city; DROP TABLE city").is_err());
+        Ok(())
+    }
+}
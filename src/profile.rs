@@ -0,0 +1,193 @@
+//! The `profile` module writes standardized address labels following a spelling profile, either
+//! the full spelling of the FGDC address standard or the abbreviated mailing format of USPS
+//! Publication 28.
+use crate::{
+    Address, AddressErrorKind, IntoCsv, IntoJson, Io, from_csv, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The `Profile` enum lists the spelling conventions for standardized address labels.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, derive_more::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Directionals, post types and subaddress types spelled out in full, as in the FGDC United
+    /// States Thoroughfare, Landmark, and Postal Address Data Standard.
+    #[default]
+    #[display("fgdc")]
+    Fgdc,
+    /// Directionals, post types and subaddress types abbreviated per USPS Publication 28, in
+    /// uppercase without punctuation, for mailing labels.
+    #[display("usps")]
+    Usps,
+}
+
+impl Profile {
+    /// The `street` method returns the complete street name of `address` spelled according to the
+    /// profile.
+    pub fn street<T: Address>(&self, address: &T) -> String {
+        match self {
+            Self::Fgdc => address.complete_street_name(false),
+            Self::Usps => mailing(&address.complete_street_name(true)),
+        }
+    }
+
+    /// The `label` method returns the address label of `address`, consisting of the complete
+    /// address number, complete street name and complete subaddress, spelled according to the
+    /// profile.  The USPS profile follows [`Address::label`], uppercased with punctuation
+    /// removed, except for the hyphen, slash and the "#" unit designator.
+    pub fn label<T: Address>(&self, address: &T) -> String {
+        match self {
+            Self::Fgdc => {
+                let mut label = format!(
+                    "{} {}",
+                    address.complete_address_number(),
+                    address.complete_street_name(false)
+                );
+                let subaddress = match (address.subaddress_type(), address.subaddress_id()) {
+                    (Some(kind), Some(id)) => Some(format!("{} {id}", kind.upper())),
+                    (Some(kind), None) => Some(kind.upper()),
+                    (None, Some(id)) => Some(format!("#{id}")),
+                    (None, None) => address.building().as_ref().map(|v| format!("BUILDING {v}")),
+                };
+                if let Some(subaddress) = subaddress {
+                    label.push(' ');
+                    label.push_str(&subaddress);
+                }
+                label
+            }
+            Self::Usps => mailing(&address.label()),
+        }
+    }
+}
+
+/// The `StandardizedAddress` struct holds the label and mailing components of an address spelled
+/// according to a [`Profile`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct StandardizedAddress {
+    /// The `label` field holds the standardized address label.
+    pub label: String,
+    /// The `street` field holds the standardized complete street name.
+    pub street: String,
+    /// The `postal_community` field holds the postal community of the address.
+    pub postal_community: String,
+    /// The `state` field holds the postal abbreviation of the state.
+    pub state: String,
+    /// The `zip` field holds the zip code of the address.
+    pub zip: i64,
+}
+
+impl StandardizedAddress {
+    /// The `new` method standardizes `address` according to `profile`.
+    pub fn new<T: Address>(address: &T, profile: Profile) -> Self {
+        let postal_community = match profile {
+            Profile::Fgdc => address.postal_community().clone(),
+            Profile::Usps => mailing(address.postal_community()),
+        };
+        Self {
+            label: profile.label(address),
+            street: profile.street(address),
+            postal_community,
+            state: address.state().abbreviate(),
+            zip: address.zip(),
+        }
+    }
+}
+
+/// The `StandardizedAddresses` struct holds a vector of type [`StandardizedAddress`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct StandardizedAddresses(Vec<StandardizedAddress>);
+
+impl StandardizedAddresses {
+    /// The `from_addresses` method standardizes each address in `addresses` according to
+    /// `profile`.
+    pub fn from_addresses<T: Address>(addresses: &[T], profile: Profile) -> Self {
+        Self(
+            addresses
+                .iter()
+                .map(|address| StandardizedAddress::new(address, profile))
+                .collect(),
+        )
+    }
+}
+
+impl IntoCsv<StandardizedAddresses> for StandardizedAddresses {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for StandardizedAddresses {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+// Uppercases `text`, removes punctuation other than the hyphen, slash and "#", and collapses runs
+// of whitespace, per USPS Publication 28.
+fn mailing(text: &str) -> String {
+    text.to_uppercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace() || matches!(c, '-' | '/' | '#'))
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommonAddress, StreetNamePostType, StreetNamePreDirectional, SubaddressType};
+
+    #[test]
+    fn usps_profile() -> anyhow::Result<()> {
+        let address = CommonAddress {
+            number: 1020,
+            directional: Some(StreetNamePreDirectional::NORTHEAST),
+            street_name: "St. Mary's".to_string(),
+            street_type: Some(StreetNamePostType::AVENUE),
+            subaddress_type: Some(SubaddressType::Apartment),
+            subaddress_id: Some("2".to_string()),
+            postal_community: "Grants Pass".to_string(),
+            zip: 97526,
+            ..Default::default()
+        };
+        assert_eq!(
+            Profile::Fgdc.label(&address),
+            "1020 NORTHEAST St. Mary's AVENUE APARTMENT 2"
+        );
+        assert_eq!(Profile::Usps.label(&address), "1020 NE ST MARYS AVE APT 2");
+        assert_eq!(Profile::Usps.street(&address), "NE ST MARYS AVE");
+        let records = StandardizedAddresses::from_addresses(&[address], Profile::Usps);
+        assert_eq!(records[0].postal_community, "GRANTS PASS");
+        assert_eq!(records[0].state, "OR");
+        assert_eq!(Profile::default(), Profile::Fgdc);
+        Ok(())
+    }
+}
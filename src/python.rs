@@ -0,0 +1,176 @@
+//! The `python` module exposes addresses, the address standardizer and the comparison of the
+//! `compare` command to Python, built with the `python` feature.  Build the wheel with
+//! `maturin build --release`, which reads the features from `pyproject.toml`.
+//!
+//! Records cross into Python as lists of dictionaries keyed by field name, so a comparison loads
+//! directly into a data frame:
+//!
+//! ```python
+//! import pandas as pd
+//! from destination import SpatialAddresses, compare
+//!
+//! city = SpatialAddresses.read("city.csv", "grants_pass")
+//! county = SpatialAddresses.read("county.csv", "josephine_county")
+//! county.standardize()
+//! matches = pd.DataFrame(compare(city, county, fuzzy=0.9))
+//! ```
+use crate::{
+    ComparisonBuilder, FromShapefile, GeoAddresses, GrantsPassSpatialAddresses, IntoBin, IntoCsv,
+    JacksonCountySpatialAddresses, JosephineCountySpatialAddresses2024, SpatialAddresses,
+    SpatialAddressesRaw, Standardizer,
+};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+
+// Converts an error from the library into a Python `RuntimeError`.
+fn py_error<E: std::fmt::Display>(error: E) -> PyErr {
+    PyRuntimeError::new_err(error.to_string())
+}
+
+// Converts a JSON value into the equivalent Python object.
+fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    let object = match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(value) => value.into_pyobject(py)?.to_owned().into_any(),
+        Value::Number(value) => match value.as_i64() {
+            Some(value) => value.into_pyobject(py)?.into_any(),
+            None => value
+                .as_f64()
+                .unwrap_or(f64::NAN)
+                .into_pyobject(py)?
+                .into_any(),
+        },
+        Value::String(value) => value.into_pyobject(py)?.into_any(),
+        Value::Array(values) => {
+            let list = PyList::empty(py);
+            for value in values {
+                list.append(to_python(py, value)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map {
+                dict.set_item(key, to_python(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    };
+    Ok(object)
+}
+
+// Converts each record into a Python dictionary keyed by field name.
+fn to_records<'py, T: Serialize>(py: Python<'py>, records: &[T]) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty(py);
+    for record in records {
+        let value = serde_json::to_value(record).map_err(py_error)?;
+        list.append(to_python(py, &value)?)?;
+    }
+    Ok(list)
+}
+
+/// The `PySpatialAddresses` struct wraps [`SpatialAddresses`] as the Python class
+/// `SpatialAddresses`.
+#[pyclass(name = "SpatialAddresses", module = "destination")]
+#[derive(Debug, Default, Clone)]
+pub struct PySpatialAddresses(SpatialAddresses);
+
+#[pymethods]
+impl PySpatialAddresses {
+    /// Reads addresses from the file at `path`, in the format named by `source_type`, either
+    /// "grants_pass", "josephine_county", "jackson_county", "common" or "data".
+    #[staticmethod]
+    #[pyo3(signature = (path, source_type = "common"))]
+    fn read(path: PathBuf, source_type: &str) -> PyResult<Self> {
+        let addresses = match source_type {
+            "grants_pass" => SpatialAddresses::from(
+                &GrantsPassSpatialAddresses::from_source(&path).map_err(py_error)?[..],
+            ),
+            "josephine_county" => SpatialAddresses::from(
+                &JosephineCountySpatialAddresses2024::from_source(&path).map_err(py_error)?[..],
+            ),
+            "jackson_county" => SpatialAddresses::from(
+                &JacksonCountySpatialAddresses::from_source(&path).map_err(py_error)?[..],
+            ),
+            "common" => {
+                SpatialAddresses::from(SpatialAddressesRaw::from_source(&path).map_err(py_error)?)
+            }
+            "data" => SpatialAddresses::load(&path).map_err(py_error)?,
+            _ => return Err(py_error(format!("Unrecognized source type: {source_type}"))),
+        };
+        Ok(Self(addresses))
+    }
+
+    /// Writes the addresses to `path` in the binary format read with the "data" source type.
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        self.0.save(path).map_err(py_error)
+    }
+
+    /// Writes the addresses to `path` as csv, in the format read with the "common" source type.
+    fn to_csv(&self, path: PathBuf) -> PyResult<()> {
+        SpatialAddressesRaw::from(&self.0)
+            .to_csv(path)
+            .map_err(py_error)
+    }
+
+    /// Applies the standardization rules in the TOML file at `rules`, or the built-in rules for
+    /// Josephine County addresses.
+    #[pyo3(signature = (rules = None))]
+    fn standardize(&mut self, rules: Option<PathBuf>) -> PyResult<()> {
+        match rules {
+            Some(path) => Standardizer::from_toml(path)
+                .map_err(py_error)?
+                .standardize(&mut self.0),
+            None => Standardizer::josephine_county().standardize(&mut self.0),
+        }
+        Ok(())
+    }
+
+    /// Returns the addresses as a list of dictionaries, with the fields of the "common" csv
+    /// format.
+    fn records<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        to_records(py, &SpatialAddressesRaw::from(&self.0))
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Matches each address in `source` against `target`, as from the `compare` command, and
+/// returns the match records as a list of dictionaries.  Street names agree at a similarity of
+/// `fuzzy` or above, and only candidates within `radius` meters are compared when set.
+#[pyfunction]
+#[pyo3(signature = (source, target, fuzzy = None, radius = None))]
+fn compare<'py>(
+    py: Python<'py>,
+    source: &PySpatialAddresses,
+    target: &PySpatialAddresses,
+    fuzzy: Option<f64>,
+    radius: Option<f64>,
+) -> PyResult<Bound<'py, PyList>> {
+    let mut builder = ComparisonBuilder::new()
+        .source(GeoAddresses::from(&source.0[..]))
+        .target(GeoAddresses::from(&target.0[..]));
+    if let Some(threshold) = fuzzy {
+        builder = builder.fuzzy(threshold);
+    }
+    if let Some(radius) = radius {
+        builder = builder.radius(radius);
+    }
+    // The comparison runs on the rayon pool, so other Python threads may run meanwhile.
+    let records = py.detach(|| builder.run()).map_err(py_error)?;
+    to_records(py, &records)
+}
+
+/// The `destination` Python module.
+#[pymodule]
+fn destination(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PySpatialAddresses>()?;
+    module.add_function(wrap_pyfunction!(compare, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,253 @@
+//! The `report` module summarizes the results of the `drift` command for data stewards, as a
+//! histogram of distances and tables of summary statistics, rendered as text, markdown or HTML.
+use crate::{
+    AddressDeltas, AddressErrorKind, DriftSummary, Io, Units, address::percentile, write_atomic,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::Path;
+
+/// The `DRIFT_BINS` constant holds the default number of histogram bins in a [`DriftReport`].
+pub const DRIFT_BINS: usize = 10;
+
+// Percentiles listed in the report.
+const PERCENTILES: [f64; 5] = [0.5, 0.75, 0.9, 0.95, 0.99];
+
+// Width of the longest bar in the text histogram, in characters.
+const BAR_WIDTH: usize = 40;
+
+/// The `DriftBin` struct holds the number of distances in a bin of a [`DriftReport`] histogram.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DriftBin {
+    /// The `lower` field holds the lower bound of the bin, inclusive.
+    pub lower: f64,
+    /// The `upper` field holds the upper bound of the bin, exclusive except for the last bin.
+    pub upper: f64,
+    /// The `count` field holds the number of distances in the bin.
+    pub count: usize,
+}
+
+/// The `DriftReport` struct holds the summary statistics, percentiles and histogram of the
+/// distances between matching addresses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DriftReport {
+    /// The `summary` field holds the summary statistics of the distances.
+    pub summary: DriftSummary,
+    /// The `percentiles` field holds pairs of percentiles, between 0 and 1, and the distance at
+    /// each percentile.
+    pub percentiles: Vec<(f64, f64)>,
+    /// The `bins` field holds the histogram of distances, in bins of equal width from zero to
+    /// the maximum distance.
+    pub bins: Vec<DriftBin>,
+}
+
+impl DriftReport {
+    /// The `new` method creates a report on the distances in `deltas`, in the units `units`,
+    /// counting distances greater than `threshold` and dividing the distances into `bins` bins.
+    pub fn new(deltas: &AddressDeltas, threshold: f64, units: Units, bins: usize) -> Self {
+        let summary = deltas.summary(threshold, units);
+        let mut values = deltas.iter().map(|d| d.delta).collect::<Vec<f64>>();
+        values.sort_by(|a, b| a.total_cmp(b));
+        if values.is_empty() {
+            return Self {
+                summary,
+                ..Default::default()
+            };
+        }
+        let percentiles = PERCENTILES
+            .iter()
+            .map(|p| (*p, percentile(&values, *p)))
+            .collect();
+        let bins = bins.max(1);
+        let width = match summary.max {
+            max if max > 0.0 => max / bins as f64,
+            _ => 1.0,
+        };
+        let mut histogram = (0..bins)
+            .map(|i| DriftBin {
+                lower: i as f64 * width,
+                upper: (i + 1) as f64 * width,
+                count: 0,
+            })
+            .collect::<Vec<DriftBin>>();
+        for value in values {
+            let i = ((value.max(0.0) / width) as usize).min(bins - 1);
+            histogram[i].count += 1;
+        }
+        Self {
+            summary,
+            percentiles,
+            bins: histogram,
+        }
+    }
+
+    /// The `histogram` method renders the histogram as text, with one line per bin, for display
+    /// in a terminal.
+    pub fn histogram(&self) -> String {
+        let most = self.bins.iter().map(|b| b.count).max().unwrap_or_default();
+        let mut text = String::new();
+        for bin in &self.bins {
+            let length = match most {
+                0 => 0,
+                _ => (bin.count * BAR_WIDTH).div_ceil(most),
+            };
+            let _ = writeln!(
+                text,
+                "{:>10.2} - {:<10.2} | {:<BAR_WIDTH$} {}",
+                bin.lower,
+                bin.upper,
+                "#".repeat(length),
+                bin.count
+            );
+        }
+        text
+    }
+
+    /// The `to_markdown` method renders the report as a markdown document.
+    pub fn to_markdown(&self) -> String {
+        let summary = &self.summary;
+        let units = summary.units;
+        let mut text = String::from("# Address Drift Report\n\n## Summary\n\n");
+        text.push_str("| Statistic | Value |\n| --- | ---: |\n");
+        for (name, value) in self.statistics() {
+            let _ = writeln!(text, "| {name} | {value} |");
+        }
+        let _ = write!(
+            text,
+            "\n## Percentiles\n\n| Percentile | Distance ({units}) |\n| ---: | ---: |\n"
+        );
+        for (p, value) in &self.percentiles {
+            let _ = writeln!(text, "| {:.0} | {value:.2} |", p * 100.0);
+        }
+        let _ = write!(
+            text,
+            "\n## Histogram\n\n| Distance ({units}) | Addresses |\n| --- | ---: |\n"
+        );
+        for bin in &self.bins {
+            let _ = writeln!(
+                text,
+                "| {:.2} - {:.2} | {} |",
+                bin.lower, bin.upper, bin.count
+            );
+        }
+        let _ = write!(text, "\n```text\n{}```\n", self.histogram());
+        text
+    }
+
+    /// The `to_html` method renders the report as a standalone HTML document.
+    pub fn to_html(&self) -> String {
+        let units = self.summary.units;
+        let most = self.bins.iter().map(|b| b.count).max().unwrap_or_default();
+        let mut text = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Address Drift Report</title>\n<style>\ntable { border-collapse: collapse; margin-bottom: 1em; }\nth, td { border: 1px solid #999; padding: 2px 8px; text-align: right; }\n.bar { background: #4682b4; height: 1em; }\n</style>\n</head>\n<body>\n<h1>Address Drift Report</h1>\n<h2>Summary</h2>\n<table>\n<tr><th>Statistic</th><th>Value</th></tr>\n",
+        );
+        for (name, value) in self.statistics() {
+            let _ = writeln!(text, "<tr><td>{name}</td><td>{value}</td></tr>");
+        }
+        let _ = write!(
+            text,
+            "</table>\n<h2>Percentiles</h2>\n<table>\n<tr><th>Percentile</th><th>Distance ({units})</th></tr>\n"
+        );
+        for (p, value) in &self.percentiles {
+            let _ = writeln!(
+                text,
+                "<tr><td>{:.0}</td><td>{value:.2}</td></tr>",
+                p * 100.0
+            );
+        }
+        let _ = write!(
+            text,
+            "</table>\n<h2>Histogram</h2>\n<table>\n<tr><th>Distance ({units})</th><th>Addresses</th><th></th></tr>\n"
+        );
+        for bin in &self.bins {
+            let percent = match most {
+                0 => 0,
+                _ => bin.count * 100 / most,
+            };
+            let _ = writeln!(
+                text,
+                "<tr><td>{:.2} - {:.2}</td><td>{}</td><td style=\"width: 300px; text-align: left\"><div class=\"bar\" style=\"width: {percent}%\"></div></td></tr>",
+                bin.lower, bin.upper, bin.count
+            );
+        }
+        text.push_str("</table>\n</body>\n</html>\n");
+        text
+    }
+
+    /// The `save` method writes the report to a file at location `path`, as HTML if the file
+    /// extension is "html" or "htm", and as markdown otherwise.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        let path = path.as_ref();
+        let html = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"));
+        let contents = if html {
+            self.to_html()
+        } else {
+            self.to_markdown()
+        };
+        write_atomic(path, |partial| {
+            std::fs::write(partial, contents)
+                .map_err(|source| Io::new(partial.into(), source, line!(), file!().into()).into())
+        })
+    }
+
+    // Names and formatted values of the summary statistics.
+    fn statistics(&self) -> Vec<(String, String)> {
+        let summary = &self.summary;
+        let units = summary.units;
+        vec![
+            ("Matching addresses".into(), summary.count.to_string()),
+            (format!("Mean ({units})"), format!("{:.2}", summary.mean)),
+            (
+                format!("Median ({units})"),
+                format!("{:.2}", summary.median),
+            ),
+            (
+                format!("95th percentile ({units})"),
+                format!("{:.2}", summary.p95),
+            ),
+            (format!("Maximum ({units})"), format!("{:.2}", summary.max)),
+            (
+                format!("Over {:.2} {units}", summary.threshold),
+                summary.over_threshold.to_string(),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AddressDelta, IntoBin, SpatialAddresses};
+
+    #[test]
+    fn drift_report() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let records = (1..=20)
+            .map(|i| AddressDelta::new(&city_addresses[i], i as f64 * 10.0))
+            .collect::<Vec<AddressDelta>>();
+        let deltas = AddressDeltas::new(records);
+        let report = DriftReport::new(&deltas, 99.0, Units::Meters, DRIFT_BINS);
+        assert_eq!(report.bins.len(), DRIFT_BINS);
+        assert_eq!(report.bins.iter().map(|b| b.count).sum::<usize>(), 20);
+        assert_eq!(report.bins[9].upper, 200.0);
+        assert_eq!(report.bins[9].count, 3);
+        assert!(report.percentiles.contains(&(0.5, 100.0)));
+        assert_eq!(report.histogram().lines().count(), DRIFT_BINS);
+
+        let path = std::env::temp_dir().join("destination_drift.md");
+        report.save(&path)?;
+        let markdown = std::fs::read_to_string(&path)?;
+        assert!(markdown.starts_with("# Address Drift Report"));
+        assert!(markdown.contains("| Over 99.00 meters | 11 |"));
+        std::fs::remove_file(&path)?;
+        let path = std::env::temp_dir().join("destination_drift.html");
+        report.save(&path)?;
+        assert!(std::fs::read_to_string(&path)?.starts_with("<!DOCTYPE html>"));
+        std::fs::remove_file(&path)?;
+        let empty = DriftReport::new(&AddressDeltas::default(), 99.0, Units::Meters, DRIFT_BINS);
+        assert!(empty.bins.is_empty());
+        Ok(())
+    }
+}
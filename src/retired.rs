@@ -0,0 +1,297 @@
+//! The `retired` module keeps a registry of retired addresses, so that a proposed address can be
+//! checked against the addresses retired in recent years.  The addressing ordinance forbids
+//! reissuing a retired address until [`RETIRED_YEARS`] have passed since its retirement.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, GrantsPassSpatialAddress, IntoCsv, IntoJson, Io,
+    dictionary::normalize, from_csv, to_csv, to_json, to_ndjson,
+};
+use chrono::{Months, NaiveDate, NaiveDateTime, NaiveTime};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// The `RETIRED_YEARS` constant holds the default number of years after its retirement during
+/// which an address may not be reissued.
+pub const RETIRED_YEARS: u32 = 5;
+
+/// The `RetiredAddress` struct records an address retired by the addressing authority.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RetiredAddress {
+    /// The `label` field holds the address label, including any subaddress.
+    pub label: String,
+    /// The `number` field holds the address number.
+    pub number: i64,
+    /// The `street` field holds the complete street name, spelled out.
+    pub street: String,
+    /// The `street_abbreviated` field holds the complete street name, using postal
+    /// abbreviations.
+    pub street_abbreviated: String,
+    /// The `retired` field holds the date the address was retired.
+    pub retired: NaiveDate,
+    /// The `id` field holds the feature ID of the retired address, if any.
+    pub id: Option<String>,
+}
+
+impl RetiredAddress {
+    /// The `new` method creates a record of `address`, retired on the date `retired`.
+    pub fn new<T: Address>(address: &T, retired: NaiveDate, id: Option<String>) -> Self {
+        Self {
+            label: address.label(),
+            number: address.number(),
+            street: address.complete_street_name(false),
+            street_abbreviated: address.complete_street_name(true),
+            retired,
+            id,
+        }
+    }
+
+    /// The `is_recent` method returns true if fewer than `years` have passed between the
+    /// retirement of the address and the date `on`.
+    pub fn is_recent(&self, on: NaiveDate, years: u32) -> bool {
+        self.retired
+            .checked_add_months(Months::new(years * 12))
+            .is_none_or(|expires| on < expires)
+    }
+
+    // Returns true if the record is for address number `number` on the street named `street`,
+    // spelled out or abbreviated.
+    fn is_on(&self, number: i64, street: &str) -> bool {
+        let street = normalize(street);
+        self.number == number
+            && (normalize(&self.street) == street || normalize(&self.street_abbreviated) == street)
+    }
+}
+
+/// The `RetiredAddresses` struct holds a vector of type [`RetiredAddress`], the registry of
+/// retired addresses.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct RetiredAddresses(Vec<RetiredAddress>);
+
+impl RetiredAddresses {
+    /// The `from_grants_pass` method records the City of Grants Pass addresses in `addresses`
+    /// with a status of retired.  The status changes when the address is retired, so the date
+    /// of the last edit to the feature serves as the date of retirement.  Addresses with an
+    /// unreadable edit date are skipped with a warning.
+    pub fn from_grants_pass(addresses: &[GrantsPassSpatialAddress]) -> Self {
+        let mut records = Vec::new();
+        for address in addresses
+            .iter()
+            .filter(|a| a.status == AddressStatus::Retired)
+        {
+            match parse_date(&address.last_edited_date) {
+                Some(retired) => records.push(RetiredAddress::new(
+                    address,
+                    retired,
+                    Some(address.global_id.clone()),
+                )),
+                None => warn!(
+                    "Unreadable retirement date for {}: {}",
+                    address.label(),
+                    address.last_edited_date
+                ),
+            }
+        }
+        info!("{} retired addresses found.", records.len());
+        let mut registry = Self::default();
+        registry.merge(Self(records));
+        registry
+    }
+
+    /// The `merge` method adds the records of `other` to the registry.  Records with the same
+    /// label and feature ID are kept once, with the latest date of retirement.  The registry is
+    /// sorted by label.
+    pub fn merge(&mut self, other: Self) {
+        let mut records: BTreeMap<(String, String), RetiredAddress> = BTreeMap::new();
+        for record in self.0.drain(..).chain(other.0) {
+            let key = (record.label.clone(), record.id.clone().unwrap_or_default());
+            match records.get(&key) {
+                Some(known) if known.retired >= record.retired => {}
+                _ => {
+                    records.insert(key, record);
+                }
+            }
+        }
+        self.0 = records.into_values().collect();
+    }
+
+    /// The `collision` method returns the most recent retirement of an address with the same
+    /// label as `address`, if fewer than `years` have passed between its retirement and the date
+    /// `on`.
+    pub fn collision<T: Address>(
+        &self,
+        address: &T,
+        on: NaiveDate,
+        years: u32,
+    ) -> Option<&RetiredAddress> {
+        let label = normalize(&address.label());
+        self.iter()
+            .filter(|r| normalize(&r.label) == label && r.is_recent(on, years))
+            .max_by_key(|r| r.retired)
+    }
+
+    /// The `collision_on_street` method returns the most recent retirement of address number
+    /// `number` on the street named `street`, if fewer than `years` have passed between its
+    /// retirement and the date `on`.  The street may be spelled out or abbreviated.
+    pub fn collision_on_street(
+        &self,
+        number: i64,
+        street: &str,
+        on: NaiveDate,
+        years: u32,
+    ) -> Option<&RetiredAddress> {
+        self.iter()
+            .filter(|r| r.is_on(number, street) && r.is_recent(on, years))
+            .max_by_key(|r| r.retired)
+    }
+}
+
+// Reads the date of an ArcGIS edit timestamp, written as "6/27/2023 17:38:41", "6/27/2023",
+// "2023-06-27" or as milliseconds since the epoch.
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    parse_timestamp(value).map(|t| t.date())
+}
+
+// Reads an ArcGIS edit timestamp, in any of the forms read by `parse_date`.  Dates without a time
+// read as midnight.
+pub(crate) fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    NaiveDateTime::parse_from_str(value, "%m/%d/%Y %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%m/%d/%Y %H:%M"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%m/%d/%Y").map(|d| d.and_time(NaiveTime::MIN))
+        })
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|d| d.and_time(NaiveTime::MIN))
+        })
+        .ok()
+        .or_else(|| {
+            let millis = value.parse::<i64>().ok()?;
+            chrono::DateTime::from_timestamp_millis(millis).map(|t| t.naive_utc())
+        })
+}
+
+impl IntoCsv<RetiredAddresses> for RetiredAddresses {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for RetiredAddresses {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AddressSuggestion, AddressSuggestions, GrantsPassSpatialAddresses, ValidationRecords,
+        address::riverside,
+    };
+
+    #[test]
+    fn retired_registry() -> anyhow::Result<()> {
+        let addresses = GrantsPassSpatialAddresses::from_csv("data/city_addresses_20241007.csv")?;
+        let registry = RetiredAddresses::from_grants_pass(&addresses);
+        assert_eq!(registry.len(), 111);
+        let record = registry
+            .iter()
+            .find(|r| r.label == "202 NW A ST")
+            .expect("retired address in registry");
+        assert_eq!(record.street, "NORTHWEST A STREET");
+        assert_eq!(
+            record.retired,
+            chrono::NaiveDate::from_ymd_opt(2024, 7, 1).unwrap()
+        );
+
+        let date = |year: i32| chrono::NaiveDate::from_ymd_opt(year, 7, 1).unwrap();
+        let mut retired = RetiredAddresses::default();
+        retired.extend([
+            RetiredAddress::new(&riverside(109), date(2020), None),
+            RetiredAddress::new(&riverside(109), date(2022), None),
+            RetiredAddress::new(&riverside(111), date(2010), None),
+        ]);
+        let mut registry = RetiredAddresses::default();
+        registry.merge(retired);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry[0].retired, date(2022));
+        assert!(
+            registry
+                .collision(&riverside(109), date(2026), RETIRED_YEARS)
+                .is_some()
+        );
+        assert!(
+            registry
+                .collision(&riverside(109), date(2027), RETIRED_YEARS)
+                .is_none()
+        );
+        assert!(
+            registry
+                .collision(&riverside(111), date(2026), RETIRED_YEARS)
+                .is_none()
+        );
+        assert!(
+            registry
+                .collision_on_street(109, "Riverside Ave", date(2026), RETIRED_YEARS)
+                .is_some()
+        );
+
+        let mut reissued = riverside(109);
+        let sources = vec![reissued.clone(), riverside(111)];
+        let mut records = ValidationRecords::default();
+        records.check_retired(&sources, &registry, date(2026), RETIRED_YEARS);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].rule, "ADDR-06");
+        assert_eq!(records[0].value, "2022-07-01");
+        reissued.status = AddressStatus::Retired;
+        let mut records = ValidationRecords::default();
+        records.check_retired(&[reissued], &registry, date(2026), RETIRED_YEARS);
+        assert!(records.is_empty());
+
+        let mut suggestions = AddressSuggestions::default();
+        suggestions.push(AddressSuggestion {
+            street: "RIVERSIDE AVENUE".to_string(),
+            number: Some(109),
+            ..Default::default()
+        });
+        assert_eq!(
+            suggestions.check_retired(&registry, date(2026), RETIRED_YEARS),
+            1
+        );
+        assert_eq!(
+            suggestions[0].note.as_deref(),
+            Some("matches address retired on 2022-07-01")
+        );
+
+        let path = std::env::temp_dir().join("destination_retired.csv");
+        registry.clone().to_csv(&path)?;
+        assert_eq!(RetiredAddresses::from_csv(&path)?, registry);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+//! The `serve` module answers address queries over HTTP, so that web applications can verify
+//! addresses in real time.  The reference addresses are read once at startup and held in memory
+//! for the life of the server.
+use crate::{
+    Address, AddressErrorKind, CommonAddress, GeocodeInput, GeocodeRecord, Geocoder, Geographic,
+    MapGrid, MatchPartialRecord, MatchPartialRecords, Nom, Parse, PrefixIndex, StreetDictionary,
+    StreetSuggestions, ValidationRecords, compare::StreetIndex,
+};
+#[cfg(feature = "io")]
+use crate::{Io, SUGGESTION_LIMIT};
+#[cfg(feature = "io")]
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+
+/// The `AddressQuery` struct holds the query parameters accepted by the endpoints of an
+/// [`AddressService`].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressQuery {
+    /// The `address` field holds the one-line address to look up, or the beginning of an address
+    /// for the `/complete` endpoint.
+    pub address: String,
+    /// The `zip` field holds an optional zip code, used by the `/geocode` endpoint if the address
+    /// does not include one.
+    #[serde(default)]
+    pub zip: Option<i64>,
+    /// The `limit` field holds the maximum number of suggestions returned by the `/suggest` and
+    /// `/complete` endpoints, defaulting to [`SUGGESTION_LIMIT`](crate::SUGGESTION_LIMIT).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// The `AddressCompletion` struct holds a reference address completing a partially entered
+/// address.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressCompletion {
+    /// The `label` field holds the complete street address.
+    pub label: String,
+    /// The `latitude` field holds the latitude of the address.
+    pub latitude: f64,
+    /// The `longitude` field holds the longitude of the address.
+    pub longitude: f64,
+}
+
+/// The `AddressService` struct holds the reference addresses and the indexes built over them,
+/// answering the queries of the `serve` command.  Each query method backs an endpoint of the
+/// same name, returning the records the batch command of the same name would write for the
+/// address.
+pub struct AddressService<T> {
+    addresses: Arc<[T]>,
+    grid: Option<Arc<MapGrid>>,
+    streets: Arc<StreetIndex>,
+    dictionary: StreetDictionary,
+    completions: PrefixIndex,
+    threshold: f64,
+}
+
+impl<T: Address + Geographic + Send + Sync + 'static> AddressService<T> {
+    /// The `new` method creates a service over the reference `addresses`, geocoding with fuzzy
+    /// street name matching at `threshold`.
+    pub fn new(addresses: Vec<T>, threshold: f64) -> Self {
+        info!("Indexing {} reference addresses.", addresses.len());
+        Self {
+            streets: Arc::new(Geocoder::index(&addresses)),
+            dictionary: StreetDictionary::from_addresses(&addresses),
+            completions: PrefixIndex::from_addresses(&addresses),
+            addresses: addresses.into(),
+            grid: None,
+            threshold,
+        }
+    }
+
+    /// The `with_grid` method reports the cell of `grid` containing each geocoded address.
+    pub fn with_grid(mut self, grid: MapGrid) -> Self {
+        self.grid = Some(Arc::new(grid));
+        self
+    }
+
+    /// The `len` method returns the number of reference addresses.
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// The `is_empty` method returns true if the service holds no reference addresses.
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// The `threshold` method returns the similarity threshold for fuzzy street name matching.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// The `match_address` method compares `address` against the reference addresses, returning
+    /// the exact match if present, and otherwise any divergent matches or a missing record.  See
+    /// [`MatchPartialRecord::compare`].
+    pub fn match_address(&self, address: &str) -> Result<MatchPartialRecords, AddressErrorKind> {
+        let (_, partial) = Parse::address(address)
+            .map_err(|source| Nom::new(address.to_string(), source, line!(), file!().into()))?;
+        Ok(MatchPartialRecord::compare(&partial, &self.addresses))
+    }
+
+    /// The `geocode` method returns the best match for `address` from the reference addresses,
+    /// filling in `zip` if the address does not include a zip code.  See [`Geocoder`].
+    pub fn geocode(&self, address: &str, zip: Option<i64>) -> GeocodeRecord {
+        // The geocoder borrows the addresses, so each query builds one over the shared index.
+        let geocoder = Geocoder::from_index(&self.addresses, self.streets.clone(), self.threshold)
+            .with_grid(self.grid.as_deref());
+        geocoder.geocode_input(&GeocodeInput {
+            address: address.to_string(),
+            zip,
+        })
+    }
+
+    /// The `validate` method checks `address` against the rules of [`ValidationRecords`],
+    /// returning a record for each violation.  Returns an error if the address lacks an address
+    /// number or street name.
+    pub fn validate(&self, address: &str) -> Result<ValidationRecords, AddressErrorKind> {
+        let address = CommonAddress::parse(address)?;
+        Ok(ValidationRecords::validate_address(&address))
+    }
+
+    /// The `complete` method returns up to `limit` reference addresses completing `prefix`, best
+    /// first.  See [`PrefixIndex::complete`].
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<AddressCompletion> {
+        self.completions
+            .complete(prefix, limit)
+            .into_iter()
+            .map(|i| {
+                let address = &self.addresses[i];
+                AddressCompletion {
+                    label: address.label(),
+                    latitude: address.latitude(),
+                    longitude: address.longitude(),
+                }
+            })
+            .collect()
+    }
+
+    /// The `suggest` method returns up to `limit` street names from the reference addresses that
+    /// most closely resemble the street name of `address`.  See [`StreetDictionary::suggest`].
+    pub fn suggest(
+        &self,
+        address: &str,
+        limit: usize,
+    ) -> Result<StreetSuggestions, AddressErrorKind> {
+        let (_, partial) = Parse::address(address)
+            .map_err(|source| Nom::new(address.to_string(), source, line!(), file!().into()))?;
+        let street = partial.street_name.unwrap_or_default();
+        Ok(self.dictionary.suggest(&street, limit))
+    }
+
+    /// The `router` method returns the routes of the service: `/match`, `/geocode`, `/validate`,
+    /// `/suggest` and `/complete`.  Each takes the query parameters of [`AddressQuery`] and
+    /// responds with JSON.  Addresses that fail to parse receive a response with status 400.
+    #[cfg(feature = "io")]
+    pub fn router(self) -> Router {
+        Self::routes(Arc::new(self))
+    }
+
+    // Returns the routes of `service`, shared with other servers answering from it.
+    #[cfg(feature = "io")]
+    pub(crate) fn routes(service: Arc<Self>) -> Router {
+        Router::new()
+            .route("/match", get(match_address::<T>))
+            .route("/geocode", get(geocode::<T>))
+            .route("/validate", get(validate::<T>))
+            .route("/suggest", get(suggest::<T>))
+            .route("/complete", get(complete::<T>))
+            .with_state(service)
+    }
+
+    /// The `serve` method listens for requests at the socket address `bind`, such as
+    /// "127.0.0.1:3000", and answers them until the program exits.
+    #[cfg(feature = "io")]
+    pub fn serve(self, bind: &str) -> Result<(), AddressErrorKind> {
+        let io = |source| Io::new(bind.into(), source, line!(), file!().into());
+        let runtime = tokio::runtime::Runtime::new().map_err(io)?;
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind(bind).await.map_err(io)?;
+            info!("Serving {} addresses at {}", self.len(), bind);
+            axum::serve(listener, self.router()).await.map_err(io)?;
+            Ok(())
+        })
+    }
+}
+
+// Responds with status 400 and the error message for an address that fails to parse.
+#[cfg(feature = "io")]
+fn bad_request(error: AddressErrorKind) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, error.to_string())
+}
+
+#[cfg(feature = "io")]
+async fn match_address<T: Address + Geographic + Send + Sync + 'static>(
+    State(service): State<Arc<AddressService<T>>>,
+    Query(query): Query<AddressQuery>,
+) -> Result<Json<MatchPartialRecords>, (StatusCode, String)> {
+    service
+        .match_address(&query.address)
+        .map(Json)
+        .map_err(bad_request)
+}
+
+#[cfg(feature = "io")]
+async fn geocode<T: Address + Geographic + Send + Sync + 'static>(
+    State(service): State<Arc<AddressService<T>>>,
+    Query(query): Query<AddressQuery>,
+) -> Json<GeocodeRecord> {
+    Json(service.geocode(&query.address, query.zip))
+}
+
+#[cfg(feature = "io")]
+async fn validate<T: Address + Geographic + Send + Sync + 'static>(
+    State(service): State<Arc<AddressService<T>>>,
+    Query(query): Query<AddressQuery>,
+) -> Result<Json<ValidationRecords>, (StatusCode, String)> {
+    service
+        .validate(&query.address)
+        .map(Json)
+        .map_err(bad_request)
+}
+
+#[cfg(feature = "io")]
+async fn suggest<T: Address + Geographic + Send + Sync + 'static>(
+    State(service): State<Arc<AddressService<T>>>,
+    Query(query): Query<AddressQuery>,
+) -> Result<Json<StreetSuggestions>, (StatusCode, String)> {
+    service
+        .suggest(&query.address, query.limit.unwrap_or(SUGGESTION_LIMIT))
+        .map(Json)
+        .map_err(bad_request)
+}
+
+#[cfg(feature = "io")]
+async fn complete<T: Address + Geographic + Send + Sync + 'static>(
+    State(service): State<Arc<AddressService<T>>>,
+    Query(query): Query<AddressQuery>,
+) -> Json<Vec<AddressCompletion>> {
+    Json(service.complete(&query.address, query.limit.unwrap_or(SUGGESTION_LIMIT)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GEOCODE_THRESHOLD, IntoBin, MatchStatus, MatchType, SpatialAddresses};
+
+    #[test]
+    fn address_service_queries() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let service = AddressService::new(city_addresses.to_vec(), GEOCODE_THRESHOLD);
+        assert_eq!(service.len(), city_addresses.len());
+        let matched = service.match_address("322 SE K Street, Grants Pass, OR 97526")?;
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].match_status(), MatchStatus::Matching);
+        let missing = service.match_address("99999 SE K Street")?;
+        assert_eq!(missing[0].match_status(), MatchStatus::Missing);
+        let located = service.geocode("322 SE K Street, Grants Pass", Some(97526));
+        assert_eq!(located.match_type, MatchType::Exact);
+        assert!(
+            service
+                .validate("322 SE K Street, Grants Pass, OR 97526")?
+                .is_empty()
+        );
+        let violations = service.validate("322 SE K, Grants Pass, OR 97526")?;
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "ADDR-01");
+        assert!(service.validate("Grants Pass, OR").is_err());
+        let suggestions = service.suggest("322 SE Redwod Avenue", 3)?;
+        assert!(suggestions.len() <= 3);
+        assert!(suggestions.iter().any(|s| s.street.contains("REDWOOD")));
+        let completions = service.complete("322 se k", 3);
+        assert_eq!(completions[0].label, "322 SE K ST");
+        Ok(())
+    }
+}
@@ -0,0 +1,557 @@
+//! The `shapefile` module reads point, polyline and polygon features from Esri Shapefiles.  The attribute table in the
+//! `.dbf` sidecar is translated into csv records in memory, so import types deserialize from a
+//! shapefile using the same field names and deserialization rules as from a csv export.
+#[cfg(feature = "io")]
+use crate::is_service;
+use crate::{
+    AddressErrorKind, Csv, FromArcGis, IntoCsv, Io, Shapefile, from_geodatabase, is_geodatabase,
+};
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use std::path::Path;
+use tracing::{info, trace};
+
+/// The `FromShapefile` trait indicates the type can be read from an Esri Shapefile.
+pub trait FromShapefile {
+    /// The `from_shapefile` method attempts to deserialize the data from the shapefile located at
+    /// `path`.  The path may point to the `.shp` file or any of its sidecar files.
+    fn from_shapefile<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind>
+    where
+        Self: Sized;
+
+    /// The `from_source` method reads the data from an ArcGIS Feature Service if `path` is a url,
+    /// from a shapefile if `path` has a `.shp` extension, from a File Geodatabase layer if `path`
+    /// names a `.gdb` directory, and from a csv file otherwise.  See [`crate::is_geodatabase`].
+    fn from_source<P: AsRef<Path>>(path: P) -> Result<Self, AddressErrorKind>
+    where
+        Self: Sized + IntoCsv<Self> + FromArcGis,
+    {
+        #[cfg(feature = "io")]
+        if is_service(&path) {
+            return Self::from_service(&path.as_ref().to_string_lossy());
+        }
+        if is_shapefile(&path) || is_geodatabase(&path) {
+            Self::from_shapefile(path)
+        } else {
+            Ok(Self::from_csv(path)?)
+        }
+    }
+}
+
+/// The `is_shapefile` function returns true if the file at `path` has a `.shp` extension.
+pub fn is_shapefile<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("shp"))
+}
+
+/// Generic function to deserialize data types from a point shapefile.  Called by methods to avoid
+/// code duplication.
+///
+/// Point geometry populates the `x` and `y` fields of the record, and polyline geometry populates
+/// the `wkt` field as well-known text.  Field names in a `.dbf`
+/// file are limited to ten characters, so a truncated field name matches the first field of
+/// type `T` that begins with the same characters.  Records that fail to deserialize are dropped,
+/// consistent with [`from_csv`](crate::from_csv).  A path naming a File Geodatabase layer is
+/// read with [`from_geodatabase`](crate::from_geodatabase) instead.
+pub fn from_shapefile<T: DeserializeOwned + Clone, P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<T>, AddressErrorKind> {
+    if is_geodatabase(&path) {
+        return from_geodatabase(path);
+    }
+    let shp_path = path.as_ref().with_extension("shp");
+    let dbf_path = path.as_ref().with_extension("dbf");
+    info!("Reading shapefile from {:?}", shp_path);
+    let shp = std::fs::read(&shp_path)
+        .map_err(|source| Io::new(shp_path.clone(), source, line!(), file!().into()))?;
+    let dbf = std::fs::read(&dbf_path)
+        .map_err(|source| Io::new(dbf_path.clone(), source, line!(), file!().into()))?;
+    let shapes = read_shapes(&shp, &shp_path)?;
+    let table = read_table(&dbf, &dbf_path)?;
+    if shapes.len() != table.rows.len() {
+        return Err(Shapefile::new(
+            shp_path,
+            format!(
+                "{} shapes but {} attribute records",
+                shapes.len(),
+                table.rows.len()
+            ),
+            line!(),
+            file!().into(),
+        )
+        .into());
+    }
+
+    // Map the attribute names onto the field names expected by the target type.
+    let fields = field_names::<T>();
+    let mut headers = table
+        .headers
+        .iter()
+        .map(|name| match_field(name, fields))
+        .collect::<Vec<String>>();
+    let lines = shapes.iter().any(|s| matches!(s, Some(Shape::Line(_))));
+    let (x, y, wkt) = if lines {
+        (0, 0, column(&mut headers, "wkt"))
+    } else {
+        (column(&mut headers, "x"), column(&mut headers, "y"), 0)
+    };
+
+    let mut rows = Vec::with_capacity(shapes.len());
+    for (shape, row) in shapes.iter().zip(table.rows) {
+        let Some(mut row) = row else { continue };
+        row.resize(headers.len(), String::new());
+        match shape {
+            Some(Shape::Point(px, py)) => {
+                row[x] = px.to_string();
+                row[y] = py.to_string();
+            }
+            Some(Shape::Line(parts)) => row[wkt] = to_wkt(parts),
+            Some(Shape::Polygon(_)) | None => {}
+        }
+        rows.push(row);
+    }
+    from_rows(&headers, rows, &dbf_path)
+}
+
+// Deserializes records of type `T` from rows of text values, by way of an in-memory csv file,
+// so the records follow the same deserialization rules as a csv export.  The `path` identifies
+// the source in error messages.  Records that fail to deserialize are dropped.
+pub(crate) fn from_rows<T: DeserializeOwned>(
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    path: &Path,
+) -> Result<Vec<T>, AddressErrorKind> {
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    wtr.write_record(headers)
+        .map_err(|source| Csv::new(path.into(), source, line!(), file!().into()))?;
+    for row in rows {
+        wtr.write_record(&row)
+            .map_err(|source| Csv::new(path.into(), source, line!(), file!().into()))?;
+    }
+    let buffer = wtr
+        .into_inner()
+        .map_err(|source| Io::new(path.into(), source.into_error(), line!(), file!().into()))?;
+    Ok(from_buffer(&buffer))
+}
+
+// Deserializes records of type `T` from the contents of a csv file.  Records that fail to
+// deserialize are dropped.
+pub(crate) fn from_buffer<T: DeserializeOwned>(buffer: &[u8]) -> Vec<T> {
+    let mut records = Vec::new();
+    let mut dropped = 0;
+    let mut rdr = csv::Reader::from_reader(buffer);
+    for result in rdr.deserialize() {
+        match result {
+            Ok(record) => records.push(record),
+            Err(e) => {
+                trace!("Dropping: {}", e.to_string());
+                dropped += 1;
+            }
+        }
+    }
+    info!("{} records dropped.", dropped);
+    records
+}
+
+// Returns the index of the column `name` in `headers`, appending the column if absent.
+pub(crate) fn column(headers: &mut Vec<String>, name: &str) -> usize {
+    match headers.iter().position(|h| h == name) {
+        Some(index) => index,
+        None => {
+            headers.push(name.to_string());
+            headers.len() - 1
+        }
+    }
+}
+
+// Matches a `.dbf` field name against the field names of the target type.  Exact matches take
+// precedence, then case-insensitive matches, then fields truncated to the ten character limit.
+// When truncation produces duplicate names, ArcGIS replaces the tail of the name with a counter
+// (e.g. "subaddress" and "subaddre_1"), so the counter selects among the fields sharing the
+// remaining prefix.
+pub(crate) fn match_field(name: &str, fields: &[&str]) -> String {
+    if let Some(field) = fields.iter().find(|f| **f == name) {
+        return field.to_string();
+    }
+    if let Some(field) = fields.iter().find(|f| f.eq_ignore_ascii_case(name)) {
+        return field.to_string();
+    }
+    if name.len() == 10 {
+        let lower = name.to_lowercase();
+        let (prefix, index) = match lower.rsplit_once('_') {
+            Some((prefix, counter)) if counter.chars().all(|c| c.is_ascii_digit()) => (
+                prefix.to_string(),
+                counter.parse::<usize>().unwrap_or_default(),
+            ),
+            _ => (lower, 0),
+        };
+        if let Some(field) = fields
+            .iter()
+            .filter(|f| f.len() > 10 && f.to_lowercase().starts_with(&prefix))
+            .nth(index)
+        {
+            return field.to_string();
+        }
+    }
+    name.to_string()
+}
+
+// Geometry of a shape record.
+enum Shape {
+    Point(f64, f64),
+    Line(Vec<Vec<[f64; 2]>>),
+    Polygon(Vec<Vec<[f64; 2]>>),
+}
+
+/// The `from_polygon_shapefile` function reads the rings of each polygon in the shapefile at
+/// `path`.  Outer rings and holes are returned alike, in the order of the file, and the attribute
+/// table is not read.  Returns an error if the file holds shapes other than polygons.
+pub(crate) fn from_polygon_shapefile<P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<Vec<[f64; 2]>>, AddressErrorKind> {
+    let shp_path = path.as_ref().with_extension("shp");
+    info!("Reading polygons from {:?}", shp_path);
+    let shp = std::fs::read(&shp_path)
+        .map_err(|source| Io::new(shp_path.clone(), source, line!(), file!().into()))?;
+    let mut rings = Vec::new();
+    for shape in read_shapes(&shp, &shp_path)?.into_iter().flatten() {
+        match shape {
+            Shape::Polygon(parts) => rings.extend(parts),
+            _ => {
+                return Err(Shapefile::new(
+                    shp_path,
+                    "expected polygon shapes".into(),
+                    line!(),
+                    file!().into(),
+                )
+                .into());
+            }
+        }
+    }
+    Ok(rings)
+}
+
+/// The `PolygonFeature` type pairs an attribute value with the rings of a polygon.
+pub(crate) type PolygonFeature = (String, Vec<Vec<[f64; 2]>>);
+
+/// The `from_polygon_features` function reads the rings of each polygon in the shapefile at
+/// `path`, paired with the value of the attribute named `field`.  Field names in a `.dbf` file
+/// are limited to ten characters, so `field` matches a truncated name, without regard to case.
+/// Returns an error if the file holds shapes other than polygons, or has no attribute named
+/// `field`.
+pub(crate) fn from_polygon_features<P: AsRef<Path>>(
+    path: P,
+    field: &str,
+) -> Result<Vec<PolygonFeature>, AddressErrorKind> {
+    let shp_path = path.as_ref().with_extension("shp");
+    let dbf_path = path.as_ref().with_extension("dbf");
+    info!("Reading polygon features from {:?}", shp_path);
+    let shp = std::fs::read(&shp_path)
+        .map_err(|source| Io::new(shp_path.clone(), source, line!(), file!().into()))?;
+    let dbf = std::fs::read(&dbf_path)
+        .map_err(|source| Io::new(dbf_path.clone(), source, line!(), file!().into()))?;
+    let table = read_table(&dbf, &dbf_path)?;
+    let truncated = field.chars().take(10).collect::<String>();
+    let column = table
+        .headers
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(field) || name.eq_ignore_ascii_case(&truncated));
+    // An empty field name reads the polygons without an attribute.
+    if column.is_none() && !field.is_empty() {
+        return Err(Shapefile::new(
+            dbf_path,
+            format!("no attribute named {field}"),
+            line!(),
+            file!().into(),
+        )
+        .into());
+    }
+    let mut features = Vec::new();
+    for (shape, row) in read_shapes(&shp, &shp_path)?.into_iter().zip(table.rows) {
+        match (shape, row) {
+            (Some(Shape::Polygon(parts)), Some(row)) => features.push((
+                column
+                    .and_then(|column| row.get(column).cloned())
+                    .unwrap_or_default(),
+                parts,
+            )),
+            (Some(Shape::Polygon(_)), None) | (None, _) => {}
+            _ => {
+                return Err(Shapefile::new(
+                    shp_path,
+                    "expected polygon shapes".into(),
+                    line!(),
+                    file!().into(),
+                )
+                .into());
+            }
+        }
+    }
+    Ok(features)
+}
+
+// Formats the parts of a polyline as well-known text.
+pub(crate) fn to_wkt(parts: &[Vec<[f64; 2]>]) -> String {
+    let part = |points: &Vec<[f64; 2]>| {
+        let coordinates = points
+            .iter()
+            .map(|[x, y]| format!("{x} {y}"))
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("({coordinates})")
+    };
+    if parts.len() == 1 {
+        format!("LINESTRING {}", part(&parts[0]))
+    } else {
+        let parts = parts.iter().map(part).collect::<Vec<String>>().join(", ");
+        format!("MULTILINESTRING ({parts})")
+    }
+}
+
+// Reads the geometry from the contents of a `.shp` file.  Null shapes return `None`.
+fn read_shapes(bytes: &[u8], path: &Path) -> Result<Vec<Option<Shape>>, Shapefile> {
+    let malformed =
+        |issue: &str| Shapefile::new(path.into(), issue.into(), line!(), file!().into());
+    if bytes.len() < 100 || be_i32(bytes, 0) != 9994 {
+        return Err(malformed("invalid file header"));
+    }
+    let mut shapes = Vec::new();
+    let mut offset = 100;
+    while offset + 8 <= bytes.len() {
+        // Content length is measured in 16-bit words.
+        let length = usize::try_from(be_i32(bytes, offset + 4))
+            .map_err(|_| malformed("negative shape record length"))?
+            * 2;
+        let start = offset + 8;
+        if length > bytes.len() - start || length < 4 {
+            return Err(malformed("truncated shape record"));
+        }
+        match le_i32(bytes, start) {
+            0 => shapes.push(None),
+            // Point, PointM and PointZ all lead with the x and y coordinates.
+            1 | 11 | 21 if length >= 20 => shapes.push(Some(Shape::Point(
+                le_f64(bytes, start + 4),
+                le_f64(bytes, start + 12),
+            ))),
+            // PolyLine and Polygon types, with their M and Z variants, share the layout of the x
+            // and y coordinates, following a bounding box.
+            kind @ (3 | 5 | 13 | 15 | 23 | 25) if length >= 44 => {
+                let count = usize::try_from(le_i32(bytes, start + 36))
+                    .map_err(|_| malformed("negative part count"))?;
+                let total = usize::try_from(le_i32(bytes, start + 40))
+                    .map_err(|_| malformed("negative point count"))?;
+                let first = start + 44 + count * 4;
+                if first + total * 16 > start + length {
+                    return Err(malformed("truncated polyline record"));
+                }
+                let mut starts = (0..count)
+                    .map(|i| usize::try_from(le_i32(bytes, start + 44 + i * 4)))
+                    .collect::<Result<Vec<usize>, _>>()
+                    .map_err(|_| malformed("negative part index"))?;
+                starts.push(total);
+                let parts = starts
+                    .windows(2)
+                    .map(|w| {
+                        (w[0]..w[1].min(total))
+                            .map(|i| {
+                                let offset = first + i * 16;
+                                [le_f64(bytes, offset), le_f64(bytes, offset + 8)]
+                            })
+                            .collect::<Vec<[f64; 2]>>()
+                    })
+                    .collect::<Vec<Vec<[f64; 2]>>>();
+                if matches!(kind, 5 | 15 | 25) {
+                    shapes.push(Some(Shape::Polygon(parts)))
+                } else {
+                    shapes.push(Some(Shape::Line(parts)))
+                }
+            }
+            shape => {
+                return Err(malformed(&format!("unsupported shape type {shape}")));
+            }
+        }
+        offset = start + length;
+    }
+    Ok(shapes)
+}
+
+// The attribute table from a `.dbf` file.  Deleted rows are represented as `None` so that rows
+// stay aligned with the shape records.
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Option<Vec<String>>>,
+}
+
+// Reads the attribute table from the contents of a `.dbf` file.
+fn read_table(bytes: &[u8], path: &Path) -> Result<Table, Shapefile> {
+    let malformed =
+        |issue: &str| Shapefile::new(path.into(), issue.into(), line!(), file!().into());
+    if bytes.len() < 32 {
+        return Err(malformed("invalid table header"));
+    }
+    let count = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+    let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let record_length = u16::from_le_bytes([bytes[10], bytes[11]]) as usize;
+
+    let mut headers = Vec::new();
+    let mut widths = Vec::new();
+    let mut offset = 32;
+    while offset + 32 <= bytes.len() && bytes[offset] != 0x0D {
+        let name = &bytes[offset..offset + 11];
+        let end = name.iter().position(|b| *b == 0).unwrap_or(11);
+        headers.push(String::from_utf8_lossy(&name[..end]).trim().to_string());
+        widths.push(bytes[offset + 16] as usize);
+        offset += 32;
+    }
+    // Each record holds the deletion flag followed by the fields.
+    if widths.iter().sum::<usize>() + 1 > record_length {
+        return Err(malformed("field widths exceed the record length"));
+    }
+
+    let mut rows = Vec::with_capacity(count);
+    for i in 0..count {
+        let start = header_length + i * record_length;
+        if start + record_length > bytes.len() {
+            return Err(malformed("truncated attribute record"));
+        }
+        // The first byte of each record is the deletion flag.
+        if bytes[start] == b'*' {
+            rows.push(None);
+            continue;
+        }
+        let mut row = Vec::with_capacity(widths.len());
+        let mut position = start + 1;
+        for width in &widths {
+            let value = String::from_utf8_lossy(&bytes[position..position + width]);
+            let value = value.trim();
+            // Numeric fields overflowing their width are filled with asterisks.
+            if value.chars().all(|c| c == '*') {
+                row.push(String::new());
+            } else {
+                row.push(value.to_string());
+            }
+            position += width;
+        }
+        rows.push(Some(row));
+    }
+    Ok(Table { headers, rows })
+}
+
+fn be_i32(bytes: &[u8], offset: usize) -> i32 {
+    i32::from_be_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn le_i32(bytes: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn le_f64(bytes: &[u8], offset: usize) -> f64 {
+    let mut buffer = [0u8; 8];
+    buffer.copy_from_slice(&bytes[offset..offset + 8]);
+    f64::from_le_bytes(buffer)
+}
+
+// Returns the serialized field names of a struct, as declared to serde.  Works by handing the
+// type a deserializer that records the field list and then bails out.
+pub(crate) fn field_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("expected a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("field names read"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, Cartesian, CommonAddress, SpatialAddresses, SpatialAddressesRaw};
+    use tracing::info;
+
+    #[test]
+    fn truncated_shapefile() -> anyhow::Result<()> {
+        let shp = std::fs::read("data/shapefile_sample.shp")?;
+        let dbf = std::fs::read("data/shapefile_sample.dbf")?;
+        let path = Path::new("sample");
+        assert!(read_shapes(&shp, path).is_ok());
+        assert!(read_table(&dbf, path).is_ok());
+
+        // No truncation panics.  Cutting the shapes between records reads fewer shapes, but any
+        // other cut is an error.
+        for end in 0..shp.len() {
+            let _ = read_shapes(&shp[..end], path);
+        }
+        assert!(read_shapes(&shp[..shp.len() - 1], path).is_err());
+        for end in 0..dbf.len() - 1 {
+            assert!(read_table(&dbf[..end], path).is_err());
+        }
+
+        // A negative content length would wrap to a huge length.
+        let mut negative = shp.clone();
+        negative[104..108].copy_from_slice(&(-1i32).to_be_bytes());
+        assert!(read_shapes(&negative, path).is_err());
+
+        // Field widths must fit within the record length.
+        let mut narrow = dbf.clone();
+        narrow[10..12].copy_from_slice(&2u16.to_le_bytes());
+        assert!(read_table(&narrow, path).is_err());
+
+        let dir = std::env::temp_dir().join("destination_truncated_shapefile");
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("sample.shp"), &shp[..shp.len() / 2])?;
+        std::fs::write(dir.join("sample.dbf"), &dbf)?;
+        let result = from_shapefile::<CommonAddress, _>(dir.join("sample.shp"));
+        assert!(matches!(result, Err(AddressErrorKind::Shapefile(_))));
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn load_shapefile() -> anyhow::Result<()> {
+        let path = "data/shapefile_sample.shp";
+        let addresses = SpatialAddressesRaw::from_shapefile(path)?;
+        // The third record is flagged as deleted in the attribute table.
+        assert_eq!(addresses.len(), 2);
+        let spatial = SpatialAddresses::from(addresses);
+        assert_eq!(spatial[0].label(), "801 SE J ST");
+        assert_eq!(spatial[0].x(), 1105234.5);
+        assert_eq!(spatial[0].y(), 379112.25);
+        assert_eq!(spatial[1].subaddress_id(), &Some("2".to_string()));
+        assert_eq!(spatial[1].postal_community(), "GRANTS PASS");
+        info!("Addresses read from shapefile.");
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+//! The `sign` module cross-references an inventory of street name signs against the streets of
+//! an address set, extending the orphan street report to asset management.  Public Works can find
+//! addressed streets without a sign record, and signs naming a street without addresses, which
+//! are often misspelled, renamed or vacated streets.
+use crate::{
+    Address, AddressErrorKind, Builder, Csv, IntoCsv, IntoJson, Io, ORPHAN_EXAMPLES,
+    StreetDictionary, dictionary::normalize, from_csv, open_csv, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use strum::IntoEnumIterator;
+use tracing::info;
+
+/// The `SIGN_STREET_FIELD` constant holds the default name of the column of the sign inventory
+/// holding the street named on each sign.
+pub const SIGN_STREET_FIELD: &str = "street";
+
+/// The `SIGN_ID_FIELD` constant holds the name of the column of the sign inventory holding the
+/// asset identifier of each sign.  Signs are identified by row number if the column is absent.
+pub const SIGN_ID_FIELD: &str = "sign_id";
+
+/// The `Sign` struct represents a street name sign in a sign inventory.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Sign {
+    /// The `id` field holds the asset identifier of the sign.
+    pub id: String,
+    /// The `street` field holds the street named on the sign, as recorded in the inventory.
+    pub street: String,
+}
+
+/// The `SignInventory` struct holds a vector of type [`Sign`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct SignInventory(Vec<Sign>);
+
+impl SignInventory {
+    /// The `from_path` method reads the sign inventory from the csv file at `path`, taking the
+    /// street named on each sign from the column `field`, and the asset identifier from the
+    /// [`SIGN_ID_FIELD`] column.  Other columns are ignored, and rows without a street are
+    /// skipped.  Errors if the file has no column named `field`.
+    pub fn from_path<P: AsRef<Path>>(path: P, field: &str) -> Result<Self, AddressErrorKind> {
+        let path = path.as_ref();
+        let csv_error = |source| Csv::new(path.into(), source, line!(), file!().into());
+        info!("Reading sign inventory from {:?}", path);
+        let mut rdr = csv::Reader::from_reader(open_csv(path)?);
+        let headers = rdr.headers().map_err(csv_error)?.clone();
+        let Some(street) = headers.iter().position(|header| header == field) else {
+            return Err(Builder::new(
+                format!("sign inventory has no column named '{field}'"),
+                "SignInventory".to_string(),
+                line!(),
+                file!().to_string(),
+            )
+            .into());
+        };
+        let id = headers.iter().position(|header| header == SIGN_ID_FIELD);
+        let mut signs = Vec::new();
+        for (row, record) in rdr.records().enumerate() {
+            let record = record.map_err(csv_error)?;
+            let street = record.get(street).unwrap_or_default().trim();
+            if street.is_empty() {
+                continue;
+            }
+            let id = match id.and_then(|id| record.get(id)) {
+                Some(id) if !id.trim().is_empty() => id.trim().to_string(),
+                // Row numbers count the header as the first row, as in a spreadsheet.
+                _ => format!("row {}", row + 2),
+            };
+            signs.push(Sign {
+                id,
+                street: street.to_string(),
+            });
+        }
+        info!("Signs read: {}", signs.len());
+        Ok(Self(signs))
+    }
+}
+
+/// The `SignGapKind` enum describes how a street and the sign inventory disagree.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+    strum::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SignGapKind {
+    /// The street has addresses but no street name sign in the inventory.
+    #[default]
+    #[display("unsigned")]
+    Unsigned,
+    /// The street is named on a sign in the inventory but has no addresses.
+    #[display("unaddressed")]
+    Unaddressed,
+}
+
+/// The `SignGap` struct reports a street with addresses but no sign, or with a sign but no
+/// addresses.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SignGap {
+    /// The `kind` field holds how the street and the sign inventory disagree.
+    pub kind: SignGapKind,
+    /// The `street` field holds the complete street name of an unsigned street, or the street
+    /// named on the signs of an unaddressed street, normalized to uppercase.
+    pub street: String,
+    /// The `count` field holds the number of addresses on an unsigned street, or the number of
+    /// signs naming an unaddressed street.
+    pub count: usize,
+    /// The `examples` field holds the labels of up to [`ORPHAN_EXAMPLES`] addresses on an
+    /// unsigned street, or the identifiers of up to [`ORPHAN_EXAMPLES`] signs naming an
+    /// unaddressed street, separated by semicolons.
+    pub examples: String,
+    /// The `suggestion` field holds the closest addressed street to the street named on the signs
+    /// of an unaddressed street, if any is within a few edits, to catch misspelled signs.
+    pub suggestion: Option<String>,
+}
+
+/// The `SignGaps` struct holds a vector of type [`SignGap`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct SignGaps(Vec<SignGap>);
+
+impl SignGaps {
+    /// The `from_inventory` method reports the complete street names in `addresses` without a
+    /// sign in `signs`, and the streets named in `signs` without addresses.  Sign streets match
+    /// either the full or abbreviated complete street name, as in [`StreetDictionary::get`].
+    /// Unsigned streets are listed first, each kind sorted by street.
+    pub fn from_inventory<T: Address>(addresses: &[T], signs: &SignInventory) -> Self {
+        let dictionary = StreetDictionary::from_addresses(addresses);
+        let mut signed = HashSet::new();
+        let mut unaddressed: BTreeMap<String, (usize, Vec<String>)> = BTreeMap::new();
+        for sign in signs.iter() {
+            match dictionary.get(&sign.street) {
+                Some(entry) => {
+                    signed.insert(entry.street.clone());
+                }
+                None => {
+                    let (count, examples) = unaddressed.entry(normalize(&sign.street)).or_default();
+                    *count += 1;
+                    if examples.len() < ORPHAN_EXAMPLES {
+                        examples.push(sign.id.clone());
+                    }
+                }
+            }
+        }
+        let mut unsigned: BTreeMap<String, (usize, Vec<String>)> = BTreeMap::new();
+        for address in addresses {
+            let street = address.complete_street_name(false).to_uppercase();
+            if signed.contains(&street) {
+                continue;
+            }
+            let (count, examples) = unsigned.entry(street).or_default();
+            *count += 1;
+            if examples.len() < ORPHAN_EXAMPLES {
+                examples.push(address.label());
+            }
+        }
+        info!(
+            "{} unsigned streets and {} unaddressed sign streets found.",
+            unsigned.len(),
+            unaddressed.len()
+        );
+        let mut records = unsigned
+            .into_iter()
+            .map(|(street, (count, examples))| SignGap {
+                kind: SignGapKind::Unsigned,
+                street,
+                count,
+                examples: examples.join("; "),
+                suggestion: None,
+            })
+            .collect::<Vec<SignGap>>();
+        records.extend(unaddressed.into_iter().map(|(street, (count, examples))| {
+            SignGap {
+                kind: SignGapKind::Unaddressed,
+                suggestion: dictionary
+                    .suggest(&street, 1)
+                    .first()
+                    .map(|s| s.street.clone()),
+                street,
+                count,
+                examples: examples.join("; "),
+            }
+        }));
+        Self(records)
+    }
+
+    /// The `filter` method retains the records of the kind named by `filter`, either "unsigned"
+    /// or "unaddressed".
+    pub fn filter(mut self, filter: &str) -> Self {
+        if SignGapKind::iter().any(|kind| kind.to_string() == filter) {
+            self.retain(|r| r.kind.to_string() == filter);
+        } else {
+            info!("Invalid filter provided.");
+        }
+        self
+    }
+}
+
+impl IntoCsv<SignGaps> for SignGaps {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for SignGaps {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommonAddress;
+
+    #[test]
+    fn sign_inventory_gaps() -> anyhow::Result<()> {
+        let addresses = [
+            "1450 NE 7th St, Grants Pass, OR 97526",
+            "1460 NE 7th St, Grants Pass, OR 97526",
+            "801 SE J St, Grants Pass, OR 97526",
+        ]
+        .iter()
+        .map(|address| CommonAddress::parse(address))
+        .collect::<Result<Vec<CommonAddress>, _>>()?;
+        let path = std::env::temp_dir().join("destination_signs.csv");
+        std::fs::write(
+            &path,
+            "sign_id,street_name\nS-1,NE 7th St\nS-2,Northeast 7th Street\nS-3,SE J Stt\n,\n",
+        )?;
+        assert!(SignInventory::from_path(&path, SIGN_STREET_FIELD).is_err());
+        let signs = SignInventory::from_path(&path, "street_name")?;
+        assert_eq!(signs.len(), 3);
+        let gaps = SignGaps::from_inventory(&addresses, &signs);
+        assert_eq!(gaps.len(), 2);
+        assert_eq!(gaps[0].kind, SignGapKind::Unsigned);
+        assert_eq!(gaps[0].street, "SOUTHEAST J STREET");
+        assert_eq!(gaps[0].examples, "801 SE J ST");
+        assert_eq!(gaps[1].kind, SignGapKind::Unaddressed);
+        assert_eq!(gaps[1].street, "SE J STT");
+        assert_eq!(gaps[1].examples, "S-3");
+        assert_eq!(gaps[1].suggestion.as_deref(), Some("SOUTHEAST J STREET"));
+        assert_eq!(gaps.clone().filter("unsigned").len(), 1);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! The `sql` module holds helpers shared by the database backends in the `postgis` and `sqlite`
+//! modules.
+use crate::{AddressErrorKind, Csv, Database, Io};
+use serde::Serialize;
+
+// Column types inferred from the values of a column.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Integer,
+    Real,
+    Text,
+}
+
+// Records serialized as csv, with the column types inferred from the values.
+pub(crate) struct Table {
+    // Column names.
+    pub(crate) headers: Vec<String>,
+    // Column types.  Columns holding only integers are integers, columns holding only numbers
+    // are real, and other columns are text.  Empty values do not affect the type.
+    pub(crate) kinds: Vec<Kind>,
+    // Records as csv text, including the header row.
+    pub(crate) data: Vec<u8>,
+}
+
+impl Table {
+    // Serializes `records` for the table `table`.  When `records` is empty, the columns and their
+    // types are taken from the default value of `T`, and the table holds no rows.
+    pub(crate) fn from_records<T: Serialize + Default>(
+        records: &[T],
+        table: &str,
+    ) -> Result<Self, AddressErrorKind> {
+        let csv_error = |source| Csv::new(table.into(), source, line!(), file!().into());
+        let io_error = |source| Io::new(table.into(), source, line!(), file!().into());
+        let default = [T::default()];
+        let sample = if records.is_empty() {
+            &default
+        } else {
+            records
+        };
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        for record in sample {
+            wtr.serialize(record).map_err(csv_error)?;
+        }
+        let mut data = wtr
+            .into_inner()
+            .map_err(|source| io_error(source.into_error()))?;
+
+        let mut rdr = csv::Reader::from_reader(data.as_slice());
+        let headers = rdr
+            .headers()
+            .map_err(csv_error)?
+            .iter()
+            .map(|h| h.to_string())
+            .collect::<Vec<String>>();
+        let mut integer = vec![true; headers.len()];
+        let mut numeric = vec![true; headers.len()];
+        for row in rdr.records().flatten() {
+            for (i, value) in row.iter().enumerate().filter(|(_, v)| !v.is_empty()) {
+                integer[i] &= value.parse::<i64>().is_ok();
+                numeric[i] &= value.parse::<f64>().is_ok();
+            }
+        }
+        let kinds = integer
+            .into_iter()
+            .zip(numeric)
+            .map(|(integer, numeric)| match (integer, numeric) {
+                (true, _) => Kind::Integer,
+                (false, true) => Kind::Real,
+                _ => Kind::Text,
+            })
+            .collect::<Vec<Kind>>();
+        if records.is_empty() {
+            let mut wtr = csv::Writer::from_writer(Vec::new());
+            wtr.write_record(&headers).map_err(csv_error)?;
+            data = wtr
+                .into_inner()
+                .map_err(|source| io_error(source.into_error()))?;
+        }
+        Ok(Self {
+            headers,
+            kinds,
+            data,
+        })
+    }
+
+    // Returns the type of the column `name`, if present.
+    #[cfg(feature = "io")]
+    pub(crate) fn kind(&self, name: &str) -> Option<Kind> {
+        self.headers
+            .iter()
+            .position(|h| h == name)
+            .map(|i| self.kinds[i])
+    }
+}
+
+// Quotes the table name `table`, optionally qualified by a schema if `schema` is true.  Names
+// may contain letters, digits and underscores, and may not begin with a digit.
+#[cfg(feature = "io")]
+pub(crate) fn identifier(table: &str, schema: bool) -> Result<String, AddressErrorKind> {
+    let valid = |part: &str| {
+        part.chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    let parts = table.split('.').collect::<Vec<&str>>();
+    let limit = if schema { 2 } else { 1 };
+    if parts.len() > limit || !parts.iter().all(|part| valid(part)) {
+        return Err(Database::new(
+            table.into(),
+            "invalid table name".into(),
+            line!(),
+            file!().into(),
+        )
+        .into());
+    }
+    Ok(parts
+        .iter()
+        .map(|part| quote(part))
+        .collect::<Vec<String>>()
+        .join("."))
+}
+
+// Quotes an identifier for use in SQL.  Values are always passed as parameters or through a
+// binary copy, never quoted into statements.
+#[cfg(feature = "io")]
+pub(crate) fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -0,0 +1,211 @@
+//! The `sqlite` module reads and writes tables in a SQLite database.  Each address type is stored
+//! in its own table, so one database can hold the addresses of several agencies for ad-hoc
+//! queries.  Tables use the same column names and deserialization rules as a csv export.
+use crate::{
+    AddressError, AddressErrorKind, Csv, Database,
+    shapefile::from_rows,
+    sql::{Kind, Table, identifier, quote},
+};
+use rusqlite::Connection;
+use rusqlite::types::{Value, ValueRef};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+use tracing::info;
+
+/// The `IntoSqlite` trait indicates the type can be read from and written to a table in a SQLite
+/// database.
+pub trait IntoSqlite<T> {
+    /// The `from_sqlite` method attempts to deserialize the data from the table `table` of the
+    /// database file at `path`.
+    fn from_sqlite<P: AsRef<Path>>(path: P, table: &str) -> Result<T, AddressError>;
+    /// The `to_sqlite` method attempts to write the data to the table `table` of the database
+    /// file at `path`, replacing any existing table of the same name.  Other tables in the
+    /// database are unchanged.
+    fn to_sqlite<P: AsRef<Path>>(&self, path: P, table: &str) -> Result<(), AddressError>;
+}
+
+/// Generic function to deserialize data types from a SQLite table.  Called by methods to avoid
+/// code duplication.  Columns of the table match fields of `T` by name.  Records that fail to
+/// deserialize are dropped, consistent with [`from_csv`](crate::from_csv).
+pub fn from_sqlite<T: DeserializeOwned, P: AsRef<Path>>(
+    path: P,
+    table: &str,
+) -> Result<Vec<T>, AddressErrorKind> {
+    let path = path.as_ref();
+    info!("Reading table {table} from {:?}", path);
+    let name = identifier(table, false)?;
+    let error = |source| database_error(table, source);
+    let connection = Connection::open(path).map_err(error)?;
+    let mut statement = connection
+        .prepare(&format!("SELECT * FROM {name}"))
+        .map_err(error)?;
+    let headers = statement
+        .column_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<String>>();
+    let mut rows = Vec::new();
+    let mut results = statement.query([]).map_err(error)?;
+    while let Some(result) = results.next().map_err(error)? {
+        // Nulls and blobs read as empty fields.
+        let row = (0..headers.len())
+            .map(|i| match result.get_ref(i) {
+                Ok(ValueRef::Integer(value)) => value.to_string(),
+                Ok(ValueRef::Real(value)) => value.to_string(),
+                Ok(ValueRef::Text(value)) => String::from_utf8_lossy(value).to_string(),
+                _ => String::new(),
+            })
+            .collect::<Vec<String>>();
+        rows.push(row);
+    }
+    from_rows(&headers, rows, path)
+}
+
+/// Generic function to write data types to a SQLite table.  Called by methods to avoid code
+/// duplication.  The table is created as described by [`sqlite_table`] in the database file at
+/// `path`, creating the file if absent, and the records are inserted with a prepared statement,
+/// in a single transaction.  An empty slice creates the table with the columns of `T` and no
+/// rows.
+///
+/// The common address types carry no object ID, so the `object_id` primary key holds the
+/// position of each record, starting at one.  Empty values are null.  When the records have a
+/// `street_name` field, the column is indexed.
+pub fn to_sqlite<T: Serialize + Default, P: AsRef<Path>>(
+    records: &[T],
+    path: P,
+    table: &str,
+) -> Result<(), AddressErrorKind> {
+    let path = path.as_ref();
+    let name = identifier(table, false)?;
+    let contents = Table::from_records(records, table)?;
+    info!(
+        "Writing {} records to table {table} in {:?}",
+        records.len(),
+        path
+    );
+    let error = |source| database_error(table, source);
+    let mut connection = Connection::open(path).map_err(error)?;
+    let transaction = connection.transaction().map_err(error)?;
+    transaction
+        .execute_batch(&format!(
+            "DROP TABLE IF EXISTS {name};\n{}",
+            create_table(&name, &contents)
+        ))
+        .map_err(error)?;
+    {
+        let parameters = (1..=contents.headers.len() + 1)
+            .map(|i| format!("?{i}"))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let mut insert = transaction
+            .prepare(&format!("INSERT INTO {name} VALUES ({parameters})"))
+            .map_err(error)?;
+        let mut rdr = csv::Reader::from_reader(contents.data.as_slice());
+        for (i, row) in rdr.records().enumerate() {
+            let row =
+                row.map_err(|source| Csv::new(table.into(), source, line!(), file!().into()))?;
+            let values = row
+                .iter()
+                .zip(&contents.kinds)
+                .map(|(value, kind)| match kind {
+                    _ if value.is_empty() => Value::Null,
+                    Kind::Integer => value.parse().map(Value::Integer).unwrap_or(Value::Null),
+                    Kind::Real => value.parse().map(Value::Real).unwrap_or(Value::Null),
+                    Kind::Text => Value::Text(value.to_string()),
+                });
+            let id = std::iter::once(Value::Integer(i as i64 + 1));
+            insert
+                .execute(rusqlite::params_from_iter(id.chain(values)))
+                .map_err(error)?;
+        }
+    }
+    if contents.kind("street_name").is_some() {
+        let index = quote(&format!("{table}_street_name"));
+        transaction
+            .execute(
+                &format!("CREATE INDEX {index} ON {name} (\"street_name\")"),
+                [],
+            )
+            .map_err(error)?;
+    }
+    transaction.commit().map_err(error)?;
+    Ok(())
+}
+
+/// The `sqlite_table` function returns the statement that creates the table `table` for
+/// `records`.  The table gains an `object_id` integer primary key ahead of the columns of the
+/// records.  Columns holding only integers are typed `INTEGER`, columns holding only numbers
+/// are typed `REAL`, and other columns are typed `TEXT`.
+pub fn sqlite_table<T: Serialize + Default>(
+    records: &[T],
+    table: &str,
+) -> Result<String, AddressErrorKind> {
+    let name = identifier(table, false)?;
+    let contents = Table::from_records(records, table)?;
+    Ok(create_table(&name, &contents))
+}
+
+// Returns the statement creating the table `name` with the columns of `contents`.
+fn create_table(name: &str, contents: &Table) -> String {
+    let columns = contents
+        .headers
+        .iter()
+        .zip(&contents.kinds)
+        .map(|(header, kind)| {
+            let kind = match kind {
+                Kind::Integer => "INTEGER",
+                Kind::Real => "REAL",
+                Kind::Text => "TEXT",
+            };
+            format!("{} {kind}", quote(header))
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("CREATE TABLE {name} (\"object_id\" INTEGER PRIMARY KEY, {columns});")
+}
+
+// Reports a failed operation on the table `table`.
+fn database_error(table: &str, source: rusqlite::Error) -> Database {
+    Database::new(table.into(), source.to_string(), line!(), file!().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoBin, SpatialAddresses, SpatialAddressesRaw};
+
+    #[test]
+    fn sqlite_create_table() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let raw = SpatialAddressesRaw::from(&SpatialAddresses::from(&city_addresses[0..100]));
+        let create = sqlite_table(&raw, "grants_pass")?;
+        assert!(
+            create.starts_with("CREATE TABLE \"grants_pass\" (\"object_id\" INTEGER PRIMARY KEY, ")
+        );
+        assert!(create.contains("\"number\" INTEGER"));
+        assert!(create.contains("\"latitude\" REAL"));
+        assert!(sqlite_table(&raw, "addresses.city").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn sqlite_round_trip() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let addresses = SpatialAddresses::from(&city_addresses[0..100]);
+        let path = std::env::temp_dir().join("destination_sample.db");
+        addresses.to_sqlite(&path, "grants_pass")?;
+        // Writing again replaces the table rather than appending to it.
+        addresses.to_sqlite(&path, "grants_pass")?;
+        assert_eq!(
+            SpatialAddresses::from_sqlite(&path, "grants_pass")?,
+            addresses
+        );
+        assert!(SpatialAddresses::from_sqlite(&path, "missing").is_err());
+        // An empty set of addresses replaces the table with an empty one.
+        SpatialAddresses::default().to_sqlite(&path, "grants_pass")?;
+        assert!(SpatialAddresses::from_sqlite(&path, "grants_pass")?.is_empty());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+//! The `stack` module groups address points sharing the same coordinates, such as the units of a
+//! condominium or apartment building mapped to a single point, so that spatial operations treat
+//! each stack as one structure with many subaddresses rather than as interchangeable points.
+use crate::{Address, AddressDelta, AddressDeltas, Cartesian, SpatialIndex, progress_bar};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+
+/// The `STACK_TOLERANCE` constant holds the distance within which address points are taken to
+/// share a location, in the units of the projected coordinates.
+pub const STACK_TOLERANCE: f64 = 0.01;
+
+/// The `Stack` struct holds the address points at a single location.  Most stacks hold one
+/// address.  A stack of several addresses represents a structure with many subaddresses.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Stack {
+    /// The `label` field holds the label of the structure, the address number and street name
+    /// shared by the addresses of the stack without their subaddresses.  Stacks of addresses
+    /// with different numbers or streets list each, separated by semicolons.  A stack of one
+    /// address takes the full label of the address.
+    pub label: String,
+    /// The `units` field holds the full labels of the addresses in the stack.
+    pub units: Vec<String>,
+    /// The `members` field holds the index of each address of the stack in the grouped slice.
+    pub members: Vec<usize>,
+    /// The `x` field holds the X coordinate of the first address of the stack.
+    pub x: f64,
+    /// The `y` field holds the Y coordinate of the first address of the stack.
+    pub y: f64,
+}
+
+impl Stack {
+    /// The `is_stacked` method returns true if the stack holds more than one address.
+    pub fn is_stacked(&self) -> bool {
+        self.members.len() > 1
+    }
+}
+
+impl Cartesian for Stack {
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+/// The `Stacks` struct holds a vector of type [`Stack`], with each address of the grouped slice
+/// in exactly one stack.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct Stacks(Vec<Stack>);
+
+impl Stacks {
+    /// The `group` method groups the points of `addresses` whose coordinates round to the same
+    /// multiple of `tolerance`.  Stacks are ordered by their first address.
+    pub fn group<T: Address + Cartesian>(addresses: &[T], tolerance: f64) -> Self {
+        let mut stacks: Vec<Stack> = Vec::new();
+        let mut positions: HashMap<(i64, i64), usize> = HashMap::new();
+        for (i, address) in addresses.iter().enumerate() {
+            let key = (
+                (address.x() / tolerance).round() as i64,
+                (address.y() / tolerance).round() as i64,
+            );
+            let position = *positions.entry(key).or_insert_with(|| {
+                stacks.push(Stack {
+                    x: address.x(),
+                    y: address.y(),
+                    ..Default::default()
+                });
+                stacks.len() - 1
+            });
+            let stack = &mut stacks[position];
+            stack.units.push(address.label());
+            stack.members.push(i);
+        }
+        for stack in &mut stacks {
+            stack.label = if stack.is_stacked() {
+                let mut structures = Vec::new();
+                for i in &stack.members {
+                    let structure = structure(&addresses[*i]);
+                    if !structures.contains(&structure) {
+                        structures.push(structure);
+                    }
+                }
+                structures.join("; ")
+            } else {
+                stack.units[0].clone()
+            };
+        }
+        let stacks = Self(stacks);
+        info!(
+            "{} addresses in {} stacks of several units.",
+            stacks
+                .iter()
+                .filter(|s| s.is_stacked())
+                .map(|s| s.members.len())
+                .sum::<usize>(),
+            stacks.iter().filter(|s| s.is_stacked()).count()
+        );
+        stacks
+    }
+
+    /// The `stack_of` method returns, for each address of the grouped slice, the index of the
+    /// stack holding it.
+    pub fn stack_of(&self) -> Vec<usize> {
+        let mut stack_of = vec![0; self.iter().map(|s| s.members.len()).sum()];
+        for (i, stack) in self.iter().enumerate() {
+            for member in &stack.members {
+                stack_of[*member] = i;
+            }
+        }
+        stack_of
+    }
+
+    /// The `deltas` method measures the distance from each stack to the addresses in `other`
+    /// sharing a label with an address of the stack, reporting one [`AddressDelta`] per
+    /// structure rather than one per unit.  The delta runs from the stack to the mean location
+    /// of the matching addresses, and takes the label of the stack.  Stacks with no matching
+    /// address, or with a delta at or below `min`, are left out.  When `radius` is set, only
+    /// addresses within `radius` of the stack are compared, using a [`SpatialIndex`] to select
+    /// candidates.
+    pub fn deltas<U: Address + Cartesian + Sync>(
+        &self,
+        other: &[U],
+        min: f64,
+        radius: Option<f64>,
+    ) -> AddressDeltas {
+        let index = radius.map(|radius| SpatialIndex::from_cartesian(other, radius));
+        let labels = other.iter().map(|o| o.label()).collect::<Vec<String>>();
+        let records = self
+            .par_iter()
+            .progress_with(progress_bar(self.len(), "Calculating deltas..."))
+            .filter_map(|stack| {
+                let units = stack.units.iter().collect::<HashSet<&String>>();
+                let candidates = match (&index, radius) {
+                    (Some(index), Some(radius)) => index.within([stack.x, stack.y], radius),
+                    _ => (0..other.len()).collect(),
+                };
+                let matches = candidates
+                    .into_iter()
+                    .filter(|i| units.contains(&labels[*i]))
+                    .collect::<Vec<usize>>();
+                if matches.is_empty() {
+                    return None;
+                }
+                let count = matches.len() as f64;
+                let x = matches.iter().map(|i| other[*i].x()).sum::<f64>() / count;
+                let y = matches.iter().map(|i| other[*i].y()).sum::<f64>() / count;
+                let (dx, dy) = (x - stack.x, y - stack.y);
+                let delta = dx.hypot(dy);
+                (delta > min).then(|| AddressDelta {
+                    label: stack.label.clone(),
+                    delta,
+                    latitude: y,
+                    longitude: x,
+                    bearing: Some(dx.atan2(dy).to_degrees().rem_euclid(360.0)),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<AddressDelta>>();
+        AddressDeltas::new(records)
+    }
+}
+
+// Returns the address number and street name of `address`, without the subaddress.
+fn structure<T: Address>(address: &T) -> String {
+    format!(
+        "{} {}",
+        address.complete_address_number(),
+        address.complete_street_name(true)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CommonAddress, REVERSE_DISTANCE, ReversePoint, ReverseRecords, SpatialAddress,
+        address::{riverside, riverside_point},
+    };
+
+    #[test]
+    fn stacked_units() -> anyhow::Result<()> {
+        let point = |number: i64, unit: Option<&str>, x: f64| SpatialAddress {
+            address: CommonAddress {
+                subaddress_id: unit.map(str::to_string),
+                ..riverside(number)
+            },
+            ..riverside_point(number, x, 0.0)
+        };
+        let source = [
+            point(100, Some("A"), 0.0),
+            point(100, Some("B"), 0.0),
+            point(100, Some("C"), 0.0),
+            point(200, None, 50.0),
+        ];
+        let target = [
+            point(100, Some("A"), 10.0),
+            point(100, Some("B"), 10.0),
+            point(100, Some("C"), 10.0),
+            point(200, None, 50.0),
+        ];
+        let stacks = Stacks::group(&source, STACK_TOLERANCE);
+        assert_eq!(stacks.len(), 2);
+        assert!(stacks[0].is_stacked());
+        assert_eq!(stacks[0].label, "100 RIVERSIDE AVE");
+        assert_eq!(stacks[0].units.len(), 3);
+        assert_eq!(stacks[1].label, "200 RIVERSIDE AVE");
+        assert_eq!(stacks.stack_of(), vec![0, 0, 0, 1]);
+
+        // One delta per structure, rather than one per unit.
+        let per_unit = <SpatialAddress as Cartesian>::deltas(&source, &target, 0.0);
+        assert_eq!(per_unit.len(), 3);
+        let deltas = stacks.deltas(&target, 0.0, None);
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].label, "100 RIVERSIDE AVE");
+        assert_eq!(deltas[0].delta, 10.0);
+        assert_eq!(stacks.deltas(&target, 0.0, Some(20.0)).len(), 1);
+
+        // The nearest address to a point by the stack is the structure.
+        let points = [ReversePoint {
+            id: None,
+            x: 1.0,
+            y: 0.0,
+        }];
+        let records = ReverseRecords::reverse(&points, &source, REVERSE_DISTANCE);
+        assert!(
+            records[0]
+                .label
+                .as_ref()
+                .is_some_and(|l| l.starts_with("100 RIVERSIDE AVE,"))
+        );
+        assert_eq!(records[0].units, Some(3));
+        Ok(())
+    }
+}
@@ -0,0 +1,552 @@
+//! The `standardize` module converts the naming conventions of other jurisdictions to city naming
+//! conventions, using an ordered list of rules.  The rules for Josephine County are built in, and
+//! rules for other jurisdictions load from a TOML file, so new quirks do not require code changes.
+//!
+//! A rules file holds an array of `rule` tables, applied in order.  The `kind` key selects the
+//! rule, and the other keys hold its fields:
+//!
+//! ```toml
+//! [[rule]]
+//! kind = "fix_post_type"
+//! from = "BEAVILLA VIEW"
+//! to = "BEAVILLA"
+//! post_type = "VIEW"
+//!
+//! [[rule]]
+//! kind = "map_community"
+//! from = "GRANTS PASS CITY"
+//! to = "GRANTS PASS"
+//!
+//! [[rule]]
+//! kind = "unit_format"
+//! strip_leading_zeros = false
+//! ```
+use crate::{
+    Address, AddressError, AddressErrorKind, IntoCsv, IntoJson, Io, StreetNamePostType,
+    StreetNamePreDirectional, SubaddressType, config::from_toml, from_csv, to_csv, to_json,
+    to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::LazyLock;
+use tracing::{info, trace};
+
+/// The `Rule` enum lists the corrections a [`Standardizer`] applies to an address.  Street names
+/// and subaddress identifiers must match exactly.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Rule {
+    /// The `RenameStreet` variant renames the street `from` to `to`.  If `directional` is set,
+    /// only addresses with the predirectional are renamed.  If `clear_directional` is true, the
+    /// predirectional is removed, for streets such as "WEST SIDE ROAD" recorded with the
+    /// directional split from the name.
+    RenameStreet {
+        /// Street name to correct.
+        from: String,
+        /// Corrected street name.
+        to: String,
+        /// Predirectional the address must have.
+        #[serde(default)]
+        directional: Option<StreetNamePreDirectional>,
+        /// Whether to remove the predirectional.
+        #[serde(default)]
+        clear_directional: bool,
+    },
+    /// The `FixPostType` variant renames the street `from` to `to` and sets the post type, for
+    /// street names recorded with the post type included, such as "BEAVILLA VIEW".
+    FixPostType {
+        /// Street name to correct.
+        from: String,
+        /// Corrected street name.
+        to: String,
+        /// Corrected post type.
+        post_type: StreetNamePostType,
+    },
+    /// The `SubaddressType` variant replaces the subaddress identifier `identifier` with the
+    /// subaddress type `subaddress_type` and the new identifier `id`, if any.  If `street` is
+    /// set, only addresses on the street are changed.
+    SubaddressType {
+        /// Subaddress identifier to correct.
+        identifier: String,
+        /// Corrected subaddress type.
+        subaddress_type: SubaddressType,
+        /// Corrected subaddress identifier.
+        #[serde(default)]
+        id: Option<String>,
+        /// Street name the address must have.
+        #[serde(default)]
+        street: Option<String>,
+    },
+    /// The `StripPrefix` variant removes the leading `prefix` from subaddress identifiers, such
+    /// as the "APT" in "APT12".
+    StripPrefix {
+        /// Prefix to remove.
+        prefix: String,
+    },
+    /// The `MapCommunity` variant renames the postal community `from` to `to`.
+    MapCommunity {
+        /// Postal community to correct.
+        from: String,
+        /// Corrected postal community.
+        to: String,
+    },
+    /// The `UnitFormat` variant corrects each subaddress identifier to the format of the
+    /// [`UnitPolicy`].  Keys left out of the rule take the default of the policy.
+    UnitFormat(UnitPolicy),
+}
+
+/// The `UnitPolicy` struct holds the format required of subaddress identifiers.  Each check is
+/// enabled by default.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnitPolicy {
+    /// The `uppercase` field requires letters in uppercase, as "B" rather than "b".
+    pub uppercase: bool,
+    /// The `strip_leading_zeros` field requires numbers without leading zeros, as "4" rather
+    /// than "004".  An identifier of zero is kept.
+    pub strip_leading_zeros: bool,
+    /// The `strip_pound` field requires identifiers without a leading "#", which belongs to the
+    /// label rather than the identifier.
+    pub strip_pound: bool,
+    /// The `split_type` field requires the subaddress type in its own field, so the identifier
+    /// "STE 4" or "SUITE 4" becomes the type SUITE with the identifier "4".
+    pub split_type: bool,
+}
+
+impl Default for UnitPolicy {
+    fn default() -> Self {
+        Self {
+            uppercase: true,
+            strip_leading_zeros: true,
+            strip_pound: true,
+            split_type: true,
+        }
+    }
+}
+
+impl UnitPolicy {
+    /// The `correct` method returns the subaddress type and identifier conforming to the policy,
+    /// given the subaddress type `subaddress_type` and identifier `identifier` of an address.  A
+    /// type named in the identifier replaces a missing type, but not a different one.  An
+    /// identifier holding only a type becomes empty.
+    pub fn correct(
+        &self,
+        subaddress_type: Option<SubaddressType>,
+        identifier: &str,
+    ) -> (Option<SubaddressType>, Option<String>) {
+        let mut subaddress_type = subaddress_type;
+        let mut id = identifier.trim().to_string();
+        if self.strip_pound {
+            id = id.trim_start_matches('#').trim_start().to_string();
+        }
+        if self.split_type {
+            let (first, rest) = id.split_once(' ').unwrap_or((id.as_str(), ""));
+            if let Some(named) = SubaddressType::match_mixed(first)
+                && subaddress_type.is_none_or(|t| t == named)
+            {
+                subaddress_type = Some(named);
+                id = rest.trim_start().to_string();
+            }
+        }
+        if self.uppercase {
+            id = id.to_uppercase();
+        }
+        if self.strip_leading_zeros {
+            // Zeros are leading only when another digit follows, so "0" and "0B" are kept.
+            while id.starts_with('0') && id[1..].starts_with(|c: char| c.is_ascii_digit()) {
+                id.remove(0);
+            }
+        }
+        (subaddress_type, (!id.is_empty()).then_some(id))
+    }
+}
+
+impl Rule {
+    /// The `apply` method applies the rule to `address`, returning `true` if the address changed.
+    pub fn apply<T: Address>(&self, address: &mut T) -> bool {
+        match self {
+            Self::RenameStreet {
+                from,
+                to,
+                directional,
+                clear_directional,
+            } => {
+                if address.street_name() != from
+                    || directional.is_some_and(|d| *address.directional() != Some(d))
+                {
+                    return false;
+                }
+                trace!("Renaming {from} to {to}");
+                *address.street_name_mut() = to.clone();
+                if *clear_directional {
+                    *address.directional_mut() = None;
+                }
+                true
+            }
+            Self::FixPostType {
+                from,
+                to,
+                post_type,
+            } => {
+                if address.street_name() != from {
+                    return false;
+                }
+                trace!("Fixing post type of {from}");
+                *address.street_name_mut() = to.clone();
+                *address.street_type_mut() = Some(*post_type);
+                true
+            }
+            Self::SubaddressType {
+                identifier,
+                subaddress_type,
+                id,
+                street,
+            } => {
+                if address.subaddress_id().as_ref() != Some(identifier)
+                    || street.as_ref().is_some_and(|s| address.street_name() != s)
+                {
+                    return false;
+                }
+                trace!("Fixing subaddress {identifier}");
+                *address.subaddress_id_mut() = id.clone();
+                *address.subaddress_type_mut() = Some(*subaddress_type);
+                true
+            }
+            Self::StripPrefix { prefix } => {
+                let Some(id) = address
+                    .subaddress_id()
+                    .as_ref()
+                    .and_then(|id| id.strip_prefix(prefix.as_str()))
+                    .map(|id| id.to_string())
+                else {
+                    return false;
+                };
+                *address.subaddress_id_mut() = Some(id);
+                true
+            }
+            Self::MapCommunity { from, to } => {
+                if address.postal_community() != from {
+                    return false;
+                }
+                *address.postal_community_mut() = to.clone();
+                true
+            }
+            Self::UnitFormat(policy) => {
+                let Some(id) = address.subaddress_id().clone() else {
+                    return false;
+                };
+                let (subaddress_type, corrected) = policy.correct(*address.subaddress_type(), &id);
+                if subaddress_type == *address.subaddress_type() && corrected.as_ref() == Some(&id)
+                {
+                    return false;
+                }
+                trace!("Formatting subaddress {id}");
+                *address.subaddress_type_mut() = subaddress_type;
+                *address.subaddress_id_mut() = corrected;
+                true
+            }
+        }
+    }
+}
+
+/// The `Standardizer` struct holds an ordered list of [`Rule`] corrections.  Each rule applies to
+/// the address as left by the rules before it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standardizer {
+    /// The `rules` field holds the rules in the order applied.  Read from the `rule` key of a
+    /// rules file.
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+// Rules for Josephine County, built on first use.
+static JOSEPHINE_COUNTY: LazyLock<Standardizer> = LazyLock::new(|| {
+    let fix = |from: &str, to: &str, post_type| Rule::FixPostType {
+        from: from.into(),
+        to: to.into(),
+        post_type,
+    };
+    let split = |from: &str, to: &str, directional| Rule::RenameStreet {
+        from: from.into(),
+        to: to.into(),
+        directional: Some(directional),
+        clear_directional: true,
+    };
+    let subaddress = |identifier: &str, subaddress_type, id: Option<&str>| Rule::SubaddressType {
+        identifier: identifier.into(),
+        subaddress_type,
+        id: id.map(|id| id.into()),
+        street: None,
+    };
+    let strip = |prefix: &str| Rule::StripPrefix {
+        prefix: prefix.into(),
+    };
+    let rules = vec![
+        fix("AZALEA DRIVE", "AZALEA", StreetNamePostType::DriveCutoff),
+        Rule::SubaddressType {
+            identifier: "OFFICE".into(),
+            subaddress_type: SubaddressType::Office,
+            id: None,
+            street: Some("LEWIS".into()),
+        },
+        fix("BEAVILLA VIEW", "BEAVILLA", StreetNamePostType::VIEW),
+        fix("COLUMBIA CREST", "COLUMBIA", StreetNamePostType::CREST),
+        fix("HILLTOP VIEW", "HILLTOP", StreetNamePostType::VIEW),
+        fix("TENNESSEE VIEW", "TENNESSEE", StreetNamePostType::VIEW),
+        fix("MARILEE ROW", "MARILEE", StreetNamePostType::ROW),
+        fix("MEADOW GLEN", "MEADOW", StreetNamePostType::GLEN),
+        fix("GENVERNA GLEN", "GENVERNA", StreetNamePostType::GLEN),
+        fix("ROBERTSON CREST", "ROBERTSON", StreetNamePostType::CREST),
+        fix("QUAIL CROSSING", "QUAIL", StreetNamePostType::CROSSING),
+        split("SIDE ROAD", "WEST SIDE", StreetNamePreDirectional::WEST),
+        split(
+            "SOUTH SHORE DRIVE",
+            "SOUTH SHORE",
+            StreetNamePreDirectional::SOUTH,
+        ),
+        subaddress("LAUNDRY", SubaddressType::Laundry, None),
+        subaddress("OFFICE", SubaddressType::Office, None),
+        subaddress("REC", SubaddressType::Rec, None),
+        subaddress("TRLR", SubaddressType::Trailer, None),
+        subaddress("FLOOR 4", SubaddressType::Floor, Some("4")),
+        strip("APT"),
+        strip("RV"),
+        strip("CABIN"),
+        Rule::UnitFormat(UnitPolicy::default()),
+    ];
+    Standardizer { rules }
+});
+
+impl Standardizer {
+    /// The `josephine_county` method returns the built-in rules converting Josephine County
+    /// naming conventions to city naming conventions.
+    pub fn josephine_county() -> &'static Self {
+        &JOSEPHINE_COUNTY
+    }
+
+    /// The `from_toml` method reads the rules from the TOML file at `path`.
+    pub fn from_toml<P: AsRef<Path>>(path: P) -> Result<Self, AddressError> {
+        info!("Reading standardization rules from {:?}", path.as_ref());
+        let standardizer: Self = from_toml(path)?;
+        info!("Rules read: {}", standardizer.rules.len());
+        Ok(standardizer)
+    }
+
+    /// The `unit_policy` method returns the policy of the first [`Rule::UnitFormat`] rule, if
+    /// any.
+    pub fn unit_policy(&self) -> Option<&UnitPolicy> {
+        self.rules.iter().find_map(|rule| match rule {
+            Rule::UnitFormat(policy) => Some(policy),
+            _ => None,
+        })
+    }
+
+    /// The `apply` method applies each rule in order to `address`.
+    pub fn apply<T: Address>(&self, address: &mut T) {
+        for rule in &self.rules {
+            rule.apply(address);
+        }
+    }
+
+    /// The `standardize` method applies the rules to each address in `addresses`.
+    pub fn standardize<T: Address>(&self, addresses: &mut [T]) {
+        trace!("Running standardize");
+        addresses.iter_mut().for_each(|address| self.apply(address));
+    }
+}
+
+/// The `UnitFormatRecord` struct reports a subaddress identifier that does not conform to a
+/// [`UnitPolicy`], with the proposed correction.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct UnitFormatRecord {
+    /// The `record` field holds the position of the address in the source data, starting at zero.
+    pub record: usize,
+    /// The `label` field holds the address label.
+    pub label: String,
+    /// The `subaddress_type` field holds the subaddress type of the address, if any.
+    pub subaddress_type: Option<SubaddressType>,
+    /// The `subaddress_identifier` field holds the nonconforming subaddress identifier.
+    pub subaddress_identifier: String,
+    /// The `corrected_type` field holds the proposed subaddress type.
+    pub corrected_type: Option<SubaddressType>,
+    /// The `corrected_identifier` field holds the proposed subaddress identifier, empty if the
+    /// identifier held only a subaddress type.
+    pub corrected_identifier: Option<String>,
+}
+
+/// The `UnitFormatRecords` struct holds a vector of type [`UnitFormatRecord`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct UnitFormatRecords(Vec<UnitFormatRecord>);
+
+impl UnitFormatRecords {
+    /// The `check` method returns a record for each address in `addresses` with a subaddress
+    /// identifier that `policy` would correct.
+    pub fn check<T: Address>(addresses: &[T], policy: &UnitPolicy) -> Self {
+        let records = addresses
+            .iter()
+            .enumerate()
+            .filter_map(|(record, address)| {
+                let id = address.subaddress_id().as_ref()?;
+                let (corrected_type, corrected_identifier) =
+                    policy.correct(*address.subaddress_type(), id);
+                if corrected_type == *address.subaddress_type()
+                    && corrected_identifier.as_ref() == Some(id)
+                {
+                    return None;
+                }
+                Some(UnitFormatRecord {
+                    record,
+                    label: address.label(),
+                    subaddress_type: *address.subaddress_type(),
+                    subaddress_identifier: id.clone(),
+                    corrected_type,
+                    corrected_identifier,
+                })
+            })
+            .collect::<Vec<UnitFormatRecord>>();
+        info!(
+            "{} nonconforming subaddress identifiers found.",
+            records.len()
+        );
+        Self(records)
+    }
+}
+
+impl IntoCsv<UnitFormatRecords> for UnitFormatRecords {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for UnitFormatRecords {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommonAddress, GrantsPassAddress, GrantsPassAddresses};
+
+    #[test]
+    fn standardization_rules() -> anyhow::Result<()> {
+        let address = |street_name: &str, subaddress_id: Option<&str>| CommonAddress {
+            number: 100,
+            street_name: street_name.to_string(),
+            subaddress_id: subaddress_id.map(|id| id.to_string()),
+            postal_community: "GRANTS PASS CITY".to_string(),
+            ..Default::default()
+        };
+        let mut addresses = vec![
+            address("BEAVILLA VIEW", Some("APT12")),
+            address("SIDE ROAD", Some("FLOOR 4")),
+            address("SIDE ROAD", None),
+        ];
+        addresses[1].directional = Some(StreetNamePreDirectional::WEST);
+
+        let standardizer = Standardizer::from_toml("data/rules_sample.toml")?;
+        assert_eq!(standardizer.rules.len(), 5);
+        let mut custom = addresses.clone();
+        standardizer.standardize(&mut custom);
+        assert_eq!(custom[0].street_name, "BEAVILLA");
+        assert_eq!(custom[0].street_type, Some(StreetNamePostType::VIEW));
+        assert_eq!(custom[0].subaddress_id, Some("12".to_string()));
+        assert_eq!(custom[0].postal_community, "GRANTS PASS");
+        assert_eq!(custom[1].street_name, "WEST SIDE");
+        assert_eq!(custom[1].directional, None);
+        assert_eq!(custom[1].subaddress_type, Some(SubaddressType::Floor));
+        assert_eq!(custom[1].subaddress_id, Some("4".to_string()));
+        // Without the west predirectional, the street keeps its name.
+        assert_eq!(custom[2].street_name, "SIDE ROAD");
+
+        // The built-in rules leave postal communities alone.
+        let mut county = addresses.clone();
+        county.iter_mut().for_each(|a| a.standardize());
+        assert_eq!(county[0].street_name, "BEAVILLA");
+        assert_eq!(county[0].postal_community, "GRANTS PASS CITY");
+        assert_eq!(county[1].street_name, "WEST SIDE");
+        let mut rule = Rule::StripPrefix {
+            prefix: "RV".into(),
+        };
+        assert!(!rule.apply(&mut county[0]));
+        rule = Rule::MapCommunity {
+            from: "GRANTS PASS CITY".into(),
+            to: "GRANTS PASS".into(),
+        };
+        assert!(rule.apply(&mut county[0]));
+        Ok(())
+    }
+
+    #[test]
+    fn unit_format_policy() -> anyhow::Result<()> {
+        let policy = UnitPolicy::default();
+        assert_eq!(policy.correct(None, "#004"), (None, Some("4".to_string())));
+        assert_eq!(
+            policy.correct(None, "ste 4b"),
+            (Some(SubaddressType::Suite), Some("4B".to_string()))
+        );
+        assert_eq!(
+            policy.correct(Some(SubaddressType::Suite), "SUITE 4"),
+            (Some(SubaddressType::Suite), Some("4".to_string()))
+        );
+        // A different type in the identifier is left for review rather than overwritten.
+        assert_eq!(
+            policy.correct(Some(SubaddressType::Apartment), "STE 4"),
+            (Some(SubaddressType::Apartment), Some("STE 4".to_string()))
+        );
+        assert_eq!(policy.correct(None, "0"), (None, Some("0".to_string())));
+        let lenient = UnitPolicy {
+            strip_leading_zeros: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            lenient.correct(None, "004"),
+            (None, Some("004".to_string()))
+        );
+        assert_eq!(
+            Standardizer::josephine_county().unit_policy(),
+            Some(&UnitPolicy::default())
+        );
+
+        let addresses = GrantsPassAddresses::from_csv("data/city_addresses_20241007.csv")?;
+        let mut sample = addresses
+            .iter()
+            .filter(|a| a.subaddress_identifier.is_some())
+            .take(3)
+            .cloned()
+            .collect::<Vec<GrantsPassAddress>>();
+        assert!(UnitFormatRecords::check(&sample, &policy).is_empty());
+        sample[1].subaddress_type = None;
+        sample[1].subaddress_identifier = Some("#02".to_string());
+        let records = UnitFormatRecords::check(&sample, &policy);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].record, 1);
+        assert_eq!(records[0].subaddress_identifier, "#02");
+        assert_eq!(records[0].corrected_identifier.as_deref(), Some("2"));
+        Ok(())
+    }
+}
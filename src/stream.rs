@@ -0,0 +1,286 @@
+//! The `stream` module reads and writes csv records one at a time, so that large files, such as
+//! the statewide address extract, can be filtered and converted without holding every record in
+//! memory.
+use crate::{
+    Address, AddressErrorKind, CommonAddress, CommonAddresses, Csv, Io, Json, ParseError, open_csv,
+    write_atomic,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// The `AddressIter` struct reads records of type `T` from a csv file one row at a time.  Like
+/// [`crate::from_csv`], rows that fail to deserialize are handled according to
+/// [`crate::set_on_error`], and counted in [`Self::dropped`].  Under [`crate::OnError::Abort`],
+/// iteration stops at the failed row, and [`Self::check`] returns the error.
+pub struct AddressIter<T> {
+    records: csv::DeserializeRecordsIntoIter<Box<dyn std::io::Read + Send>, T>,
+    path: PathBuf,
+    headers: Option<csv::StringRecord>,
+    read: usize,
+    dropped: usize,
+    skip_errors: bool,
+    error: Option<Io>,
+}
+
+impl<T: DeserializeOwned> AddressIter<T> {
+    /// The `from_csv` method opens the csv file at `path` for reading.  Records are not read until
+    /// the iterator advances.
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let path = path.as_ref();
+        let mut rdr = csv::Reader::from_reader(open_csv(path)?);
+        let headers = rdr.headers().ok().cloned();
+        Ok(Self {
+            records: rdr.into_deserialize(),
+            path: path.into(),
+            headers,
+            read: 0,
+            dropped: 0,
+            skip_errors: false,
+            error: None,
+        })
+    }
+
+    /// The `read` method returns the number of records read so far.
+    pub fn read(&self) -> usize {
+        self.read
+    }
+
+    /// The `dropped` method returns the number of rows dropped so far because they failed to
+    /// deserialize.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// The `skip_errors` method drops rows that fail to parse without reporting them, whatever the
+    /// mode set by [`crate::set_on_error`], for a second pass over a file that has already been
+    /// read once.
+    pub fn skip_errors(mut self) -> Self {
+        self.skip_errors = true;
+        self
+    }
+
+    /// The `check` method returns the error that stopped iteration under
+    /// [`crate::OnError::Abort`], if any.
+    pub fn check(&mut self) -> Result<(), Io> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for AddressIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        loop {
+            match self.records.next()? {
+                Ok(record) => {
+                    self.read += 1;
+                    return Some(record);
+                }
+                Err(_) if self.skip_errors => self.dropped += 1,
+                Err(e) => {
+                    self.dropped += 1;
+                    let error = ParseError::from_csv_error(&self.path, &e, self.headers.as_ref());
+                    if let Err(error) = error.handle() {
+                        self.error = Some(error);
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The `to_stream` function writes each record in `records` to a file at location `path` as it is
+/// produced, returning the number of records written.  Writes a JSON array if `format` is "json",
+/// newline delimited JSON if `format` is "ndjson", and csv otherwise.  The file is replaced
+/// atomically once every record is written, see [`write_atomic`].
+pub fn to_stream<T: Serialize, I: IntoIterator<Item = T>, P: AsRef<Path>>(
+    records: I,
+    path: P,
+    format: &str,
+) -> Result<usize, AddressErrorKind> {
+    let count = write_atomic(path, |path| -> Result<usize, AddressErrorKind> {
+        let io = |source| Io::new(path.into(), source, line!(), file!().into());
+        let json = |source| Json::new(path.into(), source, line!(), file!().into());
+        let mut count = 0;
+        if format == "json" || format == "ndjson" {
+            let file = fs::File::create(path).map_err(io)?;
+            let mut wtr = std::io::BufWriter::new(file);
+            let array = format == "json";
+            if array {
+                wtr.write_all(b"[").map_err(io)?;
+            }
+            for record in records {
+                if array && count > 0 {
+                    wtr.write_all(b",").map_err(io)?;
+                }
+                if array {
+                    wtr.write_all(b"\n").map_err(io)?;
+                }
+                serde_json::to_writer(&mut wtr, &record).map_err(json)?;
+                if !array {
+                    wtr.write_all(b"\n").map_err(io)?;
+                }
+                count += 1;
+            }
+            if array {
+                wtr.write_all(b"\n]\n").map_err(io)?;
+            }
+            wtr.flush().map_err(io)?;
+        } else {
+            let mut wtr = csv::Writer::from_path(path)
+                .map_err(|source| Csv::new(path.into(), source, line!(), file!().into()))?;
+            for record in records {
+                wtr.serialize(record)
+                    .map_err(|source| Csv::new(path.into(), source, line!(), file!().into()))?;
+                count += 1;
+            }
+            wtr.flush().map_err(io)?;
+        }
+        Ok(count)
+    })?;
+    info!("Records written: {count}");
+    Ok(count)
+}
+
+// Hashes the label of an address, so the first pass over a file can count labels without
+// holding every label in memory.
+fn label_hash(label: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    label.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The `stream_duplicates` function returns the addresses that share a label with another
+/// address, grouped by label in order of first appearance, matching the "duplicate" filter of
+/// [`crate::Addresses::filter`].  The `open` closure opens a fresh reader over the addresses,
+/// and is called twice: once to count label hashes, and once to collect the records with a
+/// repeated hash.  Only the counts and the candidate duplicates are held in memory, and groups
+/// that share a hash but not a label are discarded.
+pub fn stream_duplicates<T, F>(mut open: F) -> Result<CommonAddresses, Io>
+where
+    T: Address + DeserializeOwned,
+    F: FnMut() -> Result<AddressIter<T>, Io>,
+{
+    let mut counts: HashMap<u64, u32> = HashMap::new();
+    let mut records = open()?;
+    for address in records.by_ref() {
+        *counts.entry(label_hash(&address.label())).or_default() += 1;
+    }
+    records.check()?;
+    info!("Distinct label hashes: {}", counts.len());
+
+    let mut order = Vec::new();
+    let mut groups: HashMap<String, Vec<CommonAddress>> = HashMap::new();
+    for address in open()?.skip_errors() {
+        let label = address.label();
+        if counts
+            .get(&label_hash(&label))
+            .is_some_and(|count| *count > 1)
+        {
+            let group = groups.entry(label.clone()).or_default();
+            if group.is_empty() {
+                order.push(label);
+            }
+            group.push(CommonAddress::from(&address));
+        }
+    }
+
+    let mut records = Vec::new();
+    for label in order {
+        if let Some(group) = groups.remove(&label)
+            && group.len() > 1
+        {
+            records.extend(group);
+        }
+    }
+    Ok(CommonAddresses::from(&records[..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GrantsPassSpatialAddress, GrantsPassSpatialAddresses, IntoCsv, MatchRecord, MatchRecords,
+        MatchStatus,
+    };
+
+    #[test]
+    fn stream_addresses() -> anyhow::Result<()> {
+        let file = "data/city_addresses_20241007.csv";
+        let mut records = AddressIter::<GrantsPassSpatialAddress>::from_csv(file)?;
+        let labels = records
+            .by_ref()
+            .map(|address| address.label())
+            .collect::<Vec<String>>();
+        let addresses = GrantsPassSpatialAddresses::from_csv(file)?;
+        assert_eq!(records.read(), addresses.len());
+        assert_eq!(labels.len(), addresses.len());
+
+        // The streaming screen returns each address with a repeated label, grouped by label.
+        let mut counts = std::collections::HashMap::new();
+        labels
+            .iter()
+            .for_each(|label| *counts.entry(label).or_insert(0) += 1);
+        let expected = labels.iter().filter(|label| counts[label] > 1).count();
+        let duplicates =
+            stream_duplicates(|| AddressIter::<GrantsPassSpatialAddress>::from_csv(file))?;
+        assert_eq!(duplicates.len(), expected);
+        let mut seen = Vec::new();
+        for address in duplicates.iter() {
+            let label = address.label();
+            if seen.last() != Some(&label) {
+                assert!(!seen.contains(&label));
+                seen.push(label);
+            }
+        }
+
+        // Streamed filters keep the same records as the in-memory filter.
+        let status = [
+            MatchStatus::Matching,
+            MatchStatus::Divergent,
+            MatchStatus::Missing,
+        ];
+        let mut matches = MatchRecords::default();
+        for (i, match_status) in status.iter().cycle().take(9).enumerate() {
+            matches.push(MatchRecord {
+                match_status: match_status.clone(),
+                address_label: format!("{i} MAIN ST"),
+                floor: (i % 2 == 0).then(|| "2".to_string()),
+                ..Default::default()
+            });
+        }
+        let path = std::env::temp_dir().join("destination_stream_matches.csv");
+        matches.to_csv(&path)?;
+        let output = std::env::temp_dir().join("destination_stream_floor.ndjson");
+        let remaining = to_stream(
+            AddressIter::<MatchRecord>::from_csv(&path)?.filter(|r| r.passes("floor")),
+            &output,
+            "ndjson",
+        )?;
+        let filtered = matches.filter("floor");
+        assert_eq!(remaining, filtered.len());
+        let lines = std::fs::read_to_string(&output)?;
+        let streamed = lines
+            .lines()
+            .map(serde_json::from_str::<MatchRecord>)
+            .collect::<Result<Vec<MatchRecord>, _>>()?;
+        assert_eq!(streamed, filtered.to_vec());
+        std::fs::remove_file(&path)?;
+        std::fs::remove_file(&output)?;
+        Ok(())
+    }
+}
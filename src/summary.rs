@@ -0,0 +1,179 @@
+//! The `summary` module classifies the result of a run for the exit code of the program, so that
+//! scheduled runs can tell a missing input or an empty output apart from a successful run.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::error;
+
+/// The `ExitStatus` enum classifies the result of a run.  Variants are ordered by severity, so
+/// the status of a run is the most severe status recorded.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Deserialize,
+    Serialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitStatus {
+    /// The run completed and wrote records.
+    #[default]
+    #[display("success")]
+    Success,
+    /// The run completed, but dropped rows that failed to parse or records that failed to upload.
+    #[display("partial_failure")]
+    PartialFailure,
+    /// The run completed, but wrote no records.
+    #[display("empty_result")]
+    EmptyResult,
+    /// A required input was missing or unrecognized, such as the target path or address type.
+    #[display("missing_input")]
+    MissingInput,
+    /// The run stopped with an error.
+    #[display("failure")]
+    Failure,
+}
+
+impl ExitStatus {
+    /// The `code` method returns the exit code of the program for the status.  Codes 1 and 2 are
+    /// used for errors and invalid arguments, following the convention of the argument parser.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::Failure => 1,
+            Self::MissingInput => 3,
+            Self::EmptyResult => 4,
+            Self::PartialFailure => 5,
+        }
+    }
+}
+
+/// The `RunSummary` struct records the outcome of a run of `command`, reported as a single line
+/// through its `Display` implementation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunSummary {
+    /// The `command` field holds the name of the command run.
+    pub command: String,
+    /// The `status` field holds the most severe status recorded during the run.
+    pub status: ExitStatus,
+    /// The `written` field holds the number of records written, or `None` if the command does
+    /// not write records.
+    pub written: Option<usize>,
+    /// The `dropped` field holds the number of rows dropped because they failed to parse.
+    pub dropped: usize,
+    /// The `errors` field holds the messages of the errors recorded during the run.
+    pub errors: Vec<String>,
+    /// The `counts` field holds the number of records in each category reported by the command,
+    /// such as the match statuses of a comparison, keyed by category name.
+    #[serde(default)]
+    pub counts: BTreeMap<String, usize>,
+    /// The `highlights` field holds notable records from the run, such as the addresses with the
+    /// greatest drift, described one per line.
+    #[serde(default)]
+    pub highlights: Vec<String>,
+}
+
+impl RunSummary {
+    /// The `new` method creates an empty summary for a run of `command`.
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The `fail` method logs `message` as an error and raises the status of the run to
+    /// `status`, if more severe.
+    pub fn fail(&mut self, status: ExitStatus, message: &str) {
+        error!("{message}");
+        self.errors.push(message.to_string());
+        self.status = self.status.max(status);
+    }
+
+    /// The `missing` method records a missing or unrecognized input, described by `message`.
+    pub fn missing(&mut self, message: &str) {
+        self.fail(ExitStatus::MissingInput, message);
+    }
+
+    /// The `write` method adds `count` to the number of records written.
+    pub fn write(&mut self, count: usize) {
+        self.written = Some(self.written.unwrap_or_default() + count);
+    }
+
+    /// The `count` method adds `count` to the number of records in `category`.
+    pub fn count(&mut self, category: &str, count: usize) {
+        *self.counts.entry(category.to_string()).or_default() += count;
+    }
+
+    /// The `highlight` method records `line` as a notable record from the run.
+    pub fn highlight(&mut self, line: String) {
+        self.highlights.push(line);
+    }
+
+    /// The `finish` method adds `dropped` to the number of rows dropped, and returns the status
+    /// of the run.  A run that recorded no errors reports an empty result if it wrote no
+    /// records, and a partial failure if it dropped rows.
+    pub fn finish(&mut self, dropped: usize) -> ExitStatus {
+        self.dropped += dropped;
+        if self.written == Some(0) {
+            self.status = self.status.max(ExitStatus::EmptyResult);
+        }
+        if self.dropped > 0 {
+            self.status = self.status.max(ExitStatus::PartialFailure);
+        }
+        self.status
+    }
+}
+
+impl std::fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: status={} exit={}",
+            self.command,
+            self.status,
+            self.status.code()
+        )?;
+        if let Some(written) = self.written {
+            write!(f, " written={written}")?;
+        }
+        write!(f, " dropped={} errors={}", self.dropped, self.errors.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_summary_status() {
+        let mut summary = RunSummary::new("compare");
+        summary.write(10);
+        assert_eq!(summary.finish(0), ExitStatus::Success);
+        assert_eq!(summary.status.code(), 0);
+
+        let mut summary = RunSummary::new("compare");
+        assert_eq!(summary.finish(0), ExitStatus::Success);
+        summary.write(0);
+        assert_eq!(summary.finish(0), ExitStatus::EmptyResult);
+        assert_eq!(summary.finish(2), ExitStatus::EmptyResult);
+        assert_eq!(summary.dropped, 2);
+
+        let mut summary = RunSummary::new("drift");
+        summary.write(5);
+        assert_eq!(summary.finish(1), ExitStatus::PartialFailure);
+        summary.missing("No target data specified.");
+        assert_eq!(summary.finish(0), ExitStatus::MissingInput);
+        assert_eq!(summary.status.code(), 3);
+        assert_eq!(
+            summary.to_string(),
+            "drift: status=missing_input exit=3 written=5 dropped=1 errors=1"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! The `units` module expands a multi-unit structure into individual subaddress records.  Given
+//! the address point of a building and a range of unit identifiers, such as "A-F" or
+//! "101-110 odd", each unit receives a record inheriting the street fields and location of the
+//! building, with a pending status until the units are confirmed.
+use crate::{
+    Address, AddressStatus, Builder, Cartesian, Geographic, SpatialAddress, SpatialAddresses,
+    SubaddressType,
+};
+use derive_more::{Deref, DerefMut};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// The `UnitRange` struct holds the unit identifiers described by a unit range specification.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct UnitRange(Vec<String>);
+
+impl UnitRange {
+    /// The `parse` method reads a unit range specification into the unit identifiers it
+    /// describes.  The specification is a comma-separated list of single units, such as "B", or
+    /// inclusive ranges, such as "A-F" or "101-110".  Ranges may be separated by a hyphen, en dash
+    /// or em dash.  A numeric range may share a letter prefix, as in "B1-B6", keeps any leading
+    /// zeros of its start, and may end with "odd" or "even" to keep every other number.  A letter
+    /// range may share all but its last character, as in "1A-1D".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use destination::UnitRange;
+    /// let units = UnitRange::parse("101-110 odd, A-C").unwrap();
+    /// assert_eq!(*units, vec!["101", "103", "105", "107", "109", "A", "B", "C"]);
+    /// ```
+    pub fn parse(spec: &str) -> Result<Self, Builder> {
+        let mut units = Vec::new();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            units.extend(expand_part(part)?);
+        }
+        if units.is_empty() {
+            return Err(invalid(spec, "no units provided"));
+        }
+        Ok(Self(units))
+    }
+
+    /// The `expand` method returns a record for each unit in the range, copying the street fields
+    /// and location of `building`.  Each record takes the unit as its subaddress identifier,
+    /// `unit_type` as its subaddress type, and a status of pending.
+    pub fn expand<T: Address + Geographic + Cartesian + Clone>(
+        &self,
+        building: &T,
+        unit_type: Option<SubaddressType>,
+    ) -> SpatialAddresses {
+        let building = SpatialAddress::from(building);
+        let records = self
+            .iter()
+            .map(|unit| {
+                let mut address = building.clone();
+                *address.subaddress_id_mut() = Some(unit.clone());
+                *address.subaddress_type_mut() = unit_type;
+                *address.status_mut() = AddressStatus::Pending;
+                address
+            })
+            .collect::<Vec<SpatialAddress>>();
+        info!(
+            "{} units expanded from {}.",
+            records.len(),
+            building.label()
+        );
+        SpatialAddresses::new(records)
+    }
+}
+
+// Expands a single unit or range of units from one part of a unit range specification.
+fn expand_part(part: &str) -> Result<Vec<String>, Builder> {
+    let (range, parity) = match part.rsplit_once(char::is_whitespace) {
+        Some((range, word)) if word.eq_ignore_ascii_case("odd") => (range.trim(), Some(1)),
+        Some((range, word)) if word.eq_ignore_ascii_case("even") => (range.trim(), Some(0)),
+        _ => (part, None),
+    };
+    let Some((start, end)) = range.split_once(['-', '–', '—']) else {
+        if parity.is_some() {
+            return Err(invalid(part, "odd or even requires a range"));
+        }
+        return Ok(vec![range.to_uppercase()]);
+    };
+    let (start, end) = (start.trim().to_uppercase(), end.trim().to_uppercase());
+    if start.is_empty() || end.is_empty() {
+        return Err(invalid(part, "range missing a start or end"));
+    }
+    let (start_prefix, start_digits) = split_digits(&start);
+    let (end_prefix, end_digits) = split_digits(&end);
+    if !start_digits.is_empty() && !end_digits.is_empty() {
+        if start_prefix != end_prefix {
+            return Err(invalid(part, "range prefixes differ"));
+        }
+        let (Ok(first), Ok(last)) = (start_digits.parse::<u32>(), end_digits.parse::<u32>()) else {
+            return Err(invalid(part, "range number too large"));
+        };
+        if first > last {
+            return Err(invalid(part, "range start follows its end"));
+        }
+        let width = if start_digits.starts_with('0') {
+            start_digits.len()
+        } else {
+            0
+        };
+        return Ok((first..=last)
+            .filter(|n| parity.is_none_or(|p| n % 2 == p))
+            .map(|n| format!("{start_prefix}{n:0width$}"))
+            .collect());
+    }
+    if parity.is_some() {
+        return Err(invalid(part, "odd or even requires a numeric range"));
+    }
+    let (Some(first), Some(last)) = (start.chars().last(), end.chars().last()) else {
+        return Err(invalid(part, "range missing a start or end"));
+    };
+    let prefix = &start[..start.len() - first.len_utf8()];
+    if !first.is_ascii_alphabetic()
+        || !last.is_ascii_alphabetic()
+        || prefix != &end[..end.len() - last.len_utf8()]
+    {
+        return Err(invalid(part, "range must differ only in its last letter"));
+    }
+    if first > last {
+        return Err(invalid(part, "range start follows its end"));
+    }
+    Ok((first..=last).map(|c| format!("{prefix}{c}")).collect())
+}
+
+// Splits `value` into its leading characters and its trailing digits.
+fn split_digits(value: &str) -> (&str, &str) {
+    let index = value.rfind(|c: char| !c.is_ascii_digit()).map_or(0, |i| {
+        i + value[i..].chars().next().map_or(1, char::len_utf8)
+    });
+    value.split_at(index)
+}
+
+// Creates an error reporting the invalid unit range specification `spec`.
+fn invalid(spec: &str, issue: &str) -> Builder {
+    Builder::new(
+        format!("{issue} in '{spec}'"),
+        "UnitRange".to_string(),
+        line!(),
+        file!().to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommonAddress;
+
+    #[test]
+    fn unit_expansion() -> anyhow::Result<()> {
+        let units = UnitRange::parse("101–110 odd")?;
+        assert_eq!(*units, vec!["101", "103", "105", "107", "109"]);
+        let units = UnitRange::parse("a-f")?;
+        assert_eq!(units.len(), 6);
+        assert_eq!(units[5], "F");
+        let units = UnitRange::parse("B01-B03, 2A-2B, PH")?;
+        assert_eq!(*units, vec!["B01", "B02", "B03", "2A", "2B", "PH"]);
+        assert!(UnitRange::parse("F-A").is_err());
+        assert!(UnitRange::parse("A-F even").is_err());
+        assert!(UnitRange::parse("1-B").is_err());
+
+        let building = SpatialAddress {
+            address: CommonAddress::parse("1450 NE 7th St, Grants Pass, OR 97526")?,
+            x: 1.0,
+            y: 2.0,
+            ..Default::default()
+        };
+        let records =
+            UnitRange::parse("101-102")?.expand(&building, Some(SubaddressType::Apartment));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].label(), "1450 NE 7TH ST APT 102");
+        assert_eq!(records[1].status(), &AddressStatus::Pending);
+        assert_eq!(records[1].x, 1.0);
+        assert_eq!(records[1].address.postal_community, "GRANTS PASS");
+        Ok(())
+    }
+}
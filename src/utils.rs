@@ -1,13 +1,20 @@
 //! The `utils` module contains utility functions accessed by multiple data types, where declaring
 //! a stand-alone function eliminates code duplication in different methods.
-use crate::{AddressError, AddressErrorKind, Bincode, Csv, Io};
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::{
+    AddressError, AddressErrorKind, Bincode, Builder, Csv, Geographic, Io, Json, ParseError,
+    Projection, WGS84,
+};
+use indicatif::{ProgressBar, ProgressBarIter, ProgressStyle};
 use serde::Serialize;
 use serde::de::{Deserialize, DeserializeOwned, Deserializer};
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fs;
+use std::io::{BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 /// Function for deserailizing ArcGIS data that may contain either empty (Null) fields, or fields
@@ -27,74 +34,717 @@ pub fn deserialize_arcgis_data<'de, D: Deserializer<'de>>(
     }
 }
 
+/// The `partial_path` function returns the path of the temporary file holding the output at
+/// `path` while it is written: a hidden file in the same directory, with the extension
+/// '.partial' appended.  Keeping the file in the same directory keeps the final rename on the
+/// same file system.
+pub fn partial_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    match path.file_name() {
+        Some(name) => {
+            let mut partial = std::ffi::OsString::from(".");
+            partial.push(name);
+            partial.push(".partial");
+            path.with_file_name(partial)
+        }
+        None => {
+            let mut partial = path.as_os_str().to_owned();
+            partial.push(".partial");
+            partial.into()
+        }
+    }
+}
+
+/// The `write_atomic` function writes the output file at `path` by passing the [`partial_path`]
+/// to `write`, and renaming the partial file to `path` once `write` succeeds.  A run that fails or
+/// crashes partway through leaves the previous file at `path` in place, rather than a truncated
+/// file that downstream jobs would read as complete.  The partial file is removed if `write`
+/// returns an error.
+pub fn write_atomic<T, E, P, F>(path: P, write: F) -> Result<T, E>
+where
+    E: From<Io>,
+    P: AsRef<Path>,
+    F: FnOnce(&Path) -> Result<T, E>,
+{
+    let path = path.as_ref();
+    let partial = partial_path(path);
+    match write(&partial) {
+        Ok(value) => {
+            fs::rename(&partial, path)
+                .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+            Ok(value)
+        }
+        Err(e) => {
+            // The write has already failed, so a partial file that cannot be removed is left.
+            let _ = fs::remove_file(&partial);
+            Err(e)
+        }
+    }
+}
+
+/// The `Compression` enum describes the compression of a csv file, read from the extension of
+/// the file name, as in `addresses.csv.gz`.
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, serde::Deserialize, derive_more::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// The file is compressed with gzip, and has a `.gz` extension.
+    #[display("gzip")]
+    Gzip,
+    /// The file is compressed with Zstandard, and has a `.zst` extension.
+    #[display("zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// The `from_name` method returns the compression named `name`, either "gzip" or "zstd", or
+    /// the extension of either.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "gzip" | "gz" => Some(Self::Gzip),
+            "zstd" | "zst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// The `from_path` method returns the compression of the file at `path`, from its extension,
+    /// or `None` if the extension names no compression.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_name)
+    }
+
+    /// The `extension` method returns the file extension of the compression, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+
+    /// The `path` method returns `path` with the extension of the compression appended, as
+    /// `output.csv.gz` for `output.csv`.  A path that already has the extension is returned as is.
+    pub fn path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        let path = path.as_ref();
+        if Self::from_path(path) == Some(*self) {
+            return path.to_path_buf();
+        }
+        let mut compressed = path.as_os_str().to_owned();
+        compressed.push(".");
+        compressed.push(self.extension());
+        compressed.into()
+    }
+}
+
+/// The `open_csv` function opens the csv file at `path` for reading, decompressing it if the
+/// extension of the file names a [`Compression`].  Reading advances a progress bar sized to the
+/// length of the file on disk, see [`read_progress`].
+pub fn open_csv<P: AsRef<Path>>(path: P) -> Result<Box<dyn Read + Send>, Io> {
+    let path = path.as_ref();
+    let io = |source| Io::new(path.into(), source, line!(), file!().into());
+    let file = read_progress(fs::File::open(path).map_err(io)?);
+    match Compression::from_path(path) {
+        Some(Compression::Gzip) => Ok(Box::new(flate2::read::MultiGzDecoder::new(file))),
+        Some(Compression::Zstd) => Ok(Box::new(zstd::Decoder::new(file).map_err(io)?)),
+        None => Ok(Box::new(file)),
+    }
+}
+
+// Receives the csv output written to a file, compressing it as the extension of the output file
+// calls for.
+enum CsvSink {
+    Plain(BufWriter<fs::File>),
+    Gzip(flate2::write::GzEncoder<BufWriter<fs::File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<fs::File>>),
+}
+
+impl CsvSink {
+    // Creates the file at `path`, writing the stream compressed by `compression`, if any.
+    fn create(path: &Path, compression: Option<Compression>) -> std::io::Result<Self> {
+        let file = BufWriter::new(fs::File::create(path)?);
+        Ok(match compression {
+            Some(Compression::Gzip) => Self::Gzip(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )),
+            Some(Compression::Zstd) => Self::Zstd(zstd::Encoder::new(file, 0)?),
+            None => Self::Plain(file),
+        })
+    }
+
+    // Ends the compressed stream, if any, and flushes the file.
+    fn finish(self) -> std::io::Result<()> {
+        let mut file = match self {
+            Self::Plain(file) => file,
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        file.flush()
+    }
+}
+
+impl Write for CsvSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(file) => file.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(file) => file.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
 /// Generic function to serialize data types into a CSV file.  Called by methods to avoid code
-/// duplication.
+/// duplication.  The file is compressed if its extension names a [`Compression`], as in
+/// `output.csv.gz`.  The file is replaced atomically, see [`write_atomic`].  If columns are set for
+/// `path` with [`set_output_columns`], the file holds only those columns, under their new headers.
 pub fn to_csv<T: Serialize + Clone>(item: &mut [T], path: PathBuf) -> Result<(), AddressErrorKind> {
-    match csv::Writer::from_path(&path) {
-        Ok(mut wtr) => {
-            for i in item {
-                wtr.serialize(i)
-                    .map_err(|source| Csv::new(path.clone(), source, line!(), file!().into()))?;
+    let compression = Compression::from_path(&path);
+    let columns = output_columns(&path).filter(|columns| !columns.is_empty());
+    write_atomic(&path, |partial| {
+        let io = |source| Io::new(partial.into(), source, line!(), file!().into());
+        let csv_error = |source| Csv::new(partial.into(), source, line!(), file!().into());
+        let mut wtr = csv::Writer::from_writer(CsvSink::create(partial, compression).map_err(io)?);
+        match columns {
+            Some(columns) => {
+                // The headers come from serializing the records, so the records are serialized
+                // in memory before the columns are picked out.
+                let mut buffer = csv::Writer::from_writer(Vec::new());
+                for i in item {
+                    buffer.serialize(i).map_err(csv_error)?;
+                }
+                let buffer = buffer.into_inner().map_err(|e| io(e.into_error()))?;
+                let mut rdr = csv::Reader::from_reader(buffer.as_slice());
+                columns.write(&mut rdr, &mut wtr, partial)?;
+            }
+            None => {
+                for i in item {
+                    wtr.serialize(i).map_err(csv_error)?;
+                }
+            }
+        }
+        let sink = wtr.into_inner().map_err(|e| io(e.into_error()))?;
+        sink.finish().map_err(io)?;
+        Ok(())
+    })
+}
+
+// The output file and the columns written to it, set by `set_output_columns`.  Commands write
+// their output on the calling thread, so the columns are kept per thread.
+thread_local! {
+    static OUTPUT_COLUMNS: RefCell<Option<(PathBuf, OutputColumns)>> = const { RefCell::new(None) };
+}
+
+/// The `set_output_columns` function sets the columns written by [`to_csv`] on the current thread
+/// to the csv file at `path`.  Other files are written with every column.
+pub fn set_output_columns<P: AsRef<Path>>(path: P, columns: OutputColumns) {
+    let output = (path.as_ref().to_path_buf(), columns);
+    OUTPUT_COLUMNS.with(|cell| cell.replace(Some(output)));
+}
+
+// Returns the columns set for the output file at `path`, if any.
+fn output_columns(path: &Path) -> Option<OutputColumns> {
+    OUTPUT_COLUMNS.with(|cell| match &*cell.borrow() {
+        Some((output, columns)) if output == path => Some(columns.clone()),
+        _ => None,
+    })
+}
+
+/// The `OutputColumns` struct holds the columns kept in csv output and the headers they are
+/// written under, for consumers that expect a fixed schema, such as a CAD vendor expecting
+/// "HOUSE_NUM" in place of "address_number".  See [`set_output_columns`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OutputColumns {
+    /// The `select` field holds the names of the columns kept, in the order written.  When
+    /// empty, every column is kept.
+    pub select: Vec<String>,
+    /// The `rename` field pairs the name of each renamed column with the header written in its
+    /// place.
+    pub rename: Vec<(String, String)>,
+}
+
+impl OutputColumns {
+    /// The `new` method creates the columns from `select`, the names of the columns kept, and
+    /// `rename`, with entries of the form `column=HEADER`.  Errors if an entry of `rename` lacks
+    /// an '='.
+    pub fn new(select: &[String], rename: &[String]) -> Result<Self, AddressErrorKind> {
+        let mut renamed = Vec::with_capacity(rename.len());
+        for spec in rename {
+            let Some((column, header)) = spec.split_once('=') else {
+                return Err(Builder::new(
+                    format!("expected 'column=HEADER' in '{spec}'"),
+                    "OutputColumns".to_string(),
+                    line!(),
+                    file!().to_string(),
+                )
+                .into());
+            };
+            renamed.push((column.trim().to_string(), header.trim().to_string()));
+        }
+        Ok(Self {
+            select: select.iter().map(|name| name.trim().to_string()).collect(),
+            rename: renamed,
+        })
+    }
+
+    /// The `is_empty` method returns true if the columns neither select nor rename any column,
+    /// leaving output unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.select.is_empty() && self.rename.is_empty()
+    }
+
+    // Copies the records of `rdr` to `wtr`, keeping the selected columns, in the order selected,
+    // under their new headers.  Errors if a selected or renamed column is not in the records, or
+    // if a renamed column is not selected.  The `path` names the output in error messages.
+    fn write<R: Read, W: Write>(
+        &self,
+        rdr: &mut csv::Reader<R>,
+        wtr: &mut csv::Writer<W>,
+        path: &Path,
+    ) -> Result<(), AddressErrorKind> {
+        let csv_error = |source| Csv::new(path.into(), source, line!(), file!().into());
+        let missing = |issue: String| {
+            Builder::new(
+                issue,
+                "OutputColumns".to_string(),
+                line!(),
+                file!().to_string(),
+            )
+        };
+        let headers = rdr.headers().map_err(csv_error)?.clone();
+        // An output without records has no header to reshape.
+        if headers.is_empty() {
+            return Ok(());
+        }
+        let find = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header == name)
+                .ok_or_else(|| missing(format!("output has no column '{name}'")))
+        };
+        let columns = match self.select.is_empty() {
+            true => (0..headers.len()).collect::<Vec<usize>>(),
+            false => self
+                .select
+                .iter()
+                .map(|name| find(name))
+                .collect::<Result<Vec<usize>, Builder>>()?,
+        };
+        let mut names = columns
+            .iter()
+            .map(|i| headers[*i].to_string())
+            .collect::<Vec<String>>();
+        for (column, header) in &self.rename {
+            let i = find(column)?;
+            match columns.iter().position(|c| *c == i) {
+                Some(j) => names[j] = header.clone(),
+                None => {
+                    return Err(
+                        missing(format!("renamed column '{column}' is not selected")).into(),
+                    );
+                }
             }
-            wtr.flush()
-                .map_err(|source| Io::new(path.clone(), source, line!(), file!().into()))?;
-            Ok(())
         }
-        Err(source) => Err(Csv::new(path, source, line!(), file!().to_string()).into()),
+        wtr.write_record(&names).map_err(csv_error)?;
+        for record in rdr.records() {
+            let record = record.map_err(csv_error)?;
+            wtr.write_record(columns.iter().map(|i| record.get(*i).unwrap_or_default()))
+                .map_err(csv_error)?;
+        }
+        Ok(())
     }
 }
 
+/// The `source_files` function returns the csv files named by `path`, so a dataset split across
+/// several files, such as a monthly county extract, reads as one.  A directory names the csv files
+/// it contains, compressed or not, and a path containing `*`, `?` or `[` names the files matching
+/// it as a glob pattern, either sorted by path.  Any other path names itself.  May error if the
+/// pattern is invalid, or if no files match.
+pub fn source_files<P: AsRef<Path>>(path: P) -> Result<Vec<PathBuf>, Io> {
+    let path = path.as_ref();
+    let io_error = |kind, message: String| {
+        Io::new(
+            path.into(),
+            std::io::Error::new(kind, message),
+            line!(),
+            file!().into(),
+        )
+    };
+    let pattern = path.to_string_lossy();
+    let mut files = if path.is_dir() {
+        fs::read_dir(path)
+            .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| file.is_file() && is_csv(file))
+            .collect::<Vec<PathBuf>>()
+    } else if pattern.contains(['*', '?', '[']) {
+        glob::glob(&pattern)
+            .map_err(|e| io_error(std::io::ErrorKind::InvalidInput, e.to_string()))?
+            .filter_map(Result::ok)
+            .filter(|file| file.is_file())
+            .collect::<Vec<PathBuf>>()
+    } else {
+        return Ok(vec![path.to_path_buf()]);
+    };
+    if files.is_empty() {
+        return Err(io_error(
+            std::io::ErrorKind::NotFound,
+            "no csv files found".to_string(),
+        ));
+    }
+    files.sort();
+    Ok(files)
+}
+
+// Returns true if the file at `path` has a `.csv` extension, before any compression extension.
+fn is_csv(path: &Path) -> bool {
+    let path = match Compression::from_path(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    };
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+// Returns true if the column `name` holds the ESRI Object ID, ignoring case and underscores.
+fn is_object_id(name: &str) -> bool {
+    name.trim_start_matches('\u{feff}')
+        .chars()
+        .filter(|c| *c != '_')
+        .collect::<String>()
+        .eq_ignore_ascii_case("objectid")
+}
+
 /// Generic function to deserialize data types from a CSV file.  Called by methods to avoid code
 /// duplication.
+///
+/// The path may name a directory or glob pattern of csv files, as in [`source_files`], which are
+/// read in order and merged.  Compressed files are read as in [`open_csv`].  A record with an
+/// Object ID read from an earlier file is skipped with a warning, so overlapping extracts do not
+/// duplicate addresses.  Object IDs repeated within a file are kept.
 pub fn from_csv<T: DeserializeOwned + Clone, P: AsRef<std::path::Path>>(
     path: P,
 ) -> Result<Vec<T>, Io> {
+    let files = source_files(&path)?;
     let mut records = Vec::new();
-    match std::fs::File::open(&path) {
-        Ok(file) => {
-            let mut rdr = csv::Reader::from_reader(file);
-
-            let mut dropped = 0;
-            for result in rdr.deserialize() {
-                match result {
-                    Ok(record) => records.push(record),
-                    Err(e) => {
-                        tracing::trace!("Dropping: {}", e.to_string());
-                        dropped += 1;
+    let mut seen = HashSet::new();
+    let mut repeated = 0;
+    for part in &files {
+        if files.len() > 1 {
+            info!("Reading {:?}", part);
+        }
+        let mut rdr = csv::Reader::from_reader(open_csv(part)?);
+        let headers = rdr.headers().ok().cloned();
+        let column = headers
+            .as_ref()
+            .and_then(|headers| headers.iter().position(is_object_id));
+
+        let mut ids = Vec::new();
+        let mut dropped = 0;
+        for result in rdr.records() {
+            let record = match result {
+                Ok(record) => record,
+                Err(e) => {
+                    ParseError::from_csv_error(part, &e, headers.as_ref()).handle()?;
+                    dropped += 1;
+                    continue;
+                }
+            };
+            let id = column
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .filter(|id| !id.is_empty());
+            if let Some(id) = id
+                && seen.contains(id)
+            {
+                repeated += 1;
+                continue;
+            }
+            match record.deserialize(headers.as_ref()) {
+                Ok(value) => {
+                    records.push(value);
+                    ids.extend(id.map(str::to_string));
+                }
+                Err(e) => {
+                    ParseError::from_csv_error(part, &e, headers.as_ref()).handle()?;
+                    dropped += 1;
+                }
+            }
+        }
+        tracing::info!("{} records dropped.", dropped);
+        seen.extend(ids);
+    }
+    if repeated > 0 {
+        warn!("{repeated} records with an Object ID read from an earlier file skipped.");
+    }
+    Ok(records)
+}
+
+/// Generic function to serialize data types into a GeoJSON file as a point feature collection.
+/// Fields of the record become feature properties, with nested structs flattened into the top
+/// level so the attribute table reads cleanly in GIS.  Called by methods to avoid code
+/// duplication.  The file is replaced atomically, see [`write_atomic`].
+///
+/// GeoJSON coordinates are WGS84 longitude and latitude.  Errors if a record has coordinates
+/// outside the range of longitude and latitude, such as projected coordinates.
+pub fn to_geojson<T: Serialize + Geographic, P: AsRef<Path>>(
+    item: &[T],
+    path: P,
+) -> Result<(), AddressErrorKind> {
+    let path = path.as_ref();
+    let mut features = Vec::with_capacity(item.len());
+    for i in item {
+        let (longitude, latitude) = (i.longitude(), i.latitude());
+        if !(-180.0..=180.0).contains(&longitude) || !(-90.0..=90.0).contains(&latitude) {
+            return Err(Projection::new(
+                WGS84,
+                format!("coordinates ({longitude}, {latitude}) are not longitude and latitude"),
+                line!(),
+                file!().into(),
+            )
+            .into());
+        }
+        let value = serde_json::to_value(i)
+            .map_err(|source| Json::new(path.into(), source, line!(), file!().into()))?;
+        let mut properties = serde_json::Map::new();
+        match value {
+            serde_json::Value::Object(fields) => {
+                for (key, value) in fields {
+                    match value {
+                        serde_json::Value::Object(nested) => properties.extend(nested),
+                        other => {
+                            properties.insert(key, other);
+                        }
                     }
                 }
             }
-            tracing::info!("{} records dropped.", dropped);
+            other => {
+                properties.insert("value".into(), other);
+            }
+        }
+        features.push(serde_json::json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": [longitude, latitude],
+            },
+            "properties": properties,
+        }));
+    }
+    let collection = serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+    write_atomic(path, |path| {
+        let file = fs::File::create(path)
+            .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+        let mut wtr = std::io::BufWriter::new(file);
+        serde_json::to_writer(&mut wtr, &collection)
+            .map_err(|source| Json::new(path.into(), source, line!(), file!().into()))?;
+        std::io::Write::flush(&mut wtr)
+            .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+        Ok(())
+    })
+}
 
-            Ok(records)
+/// The `to_json` function serializes `data` as formatted JSON and writes it to a file at
+/// location `path`.  The file is replaced atomically, see [`write_atomic`].
+pub fn to_json<T: Serialize, P: AsRef<Path>>(data: &T, path: P) -> Result<(), AddressErrorKind> {
+    write_atomic(path, |path| {
+        let file = fs::File::create(path)
+            .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+        let mut wtr = std::io::BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut wtr, data)
+            .map_err(|source| Json::new(path.into(), source, line!(), file!().into()))?;
+        std::io::Write::flush(&mut wtr)
+            .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+        Ok(())
+    })
+}
+
+/// The `to_ndjson` function serializes each record in `records` as JSON on a separate line (newline
+/// delimited JSON), and writes the lines to a file at location `path`.  The file is replaced
+/// atomically, see [`write_atomic`].
+pub fn to_ndjson<T: Serialize, P: AsRef<Path>>(
+    records: &[T],
+    path: P,
+) -> Result<(), AddressErrorKind> {
+    write_atomic(path, |path| {
+        let file = fs::File::create(path)
+            .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+        let mut wtr = std::io::BufWriter::new(file);
+        for record in records {
+            serde_json::to_writer(&mut wtr, record)
+                .map_err(|source| Json::new(path.into(), source, line!(), file!().into()))?;
+            std::io::Write::write_all(&mut wtr, b"\n")
+                .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+        }
+        std::io::Write::flush(&mut wtr)
+            .map_err(|source| Io::new(path.into(), source, line!(), file!().into()))?;
+        Ok(())
+    })
+}
+
+/// The `DATA_MAGIC` constant holds the bytes opening every binary data file written by
+/// [`to_bin`], marking the file as holding a [`DataHeader`].
+pub const DATA_MAGIC: [u8; 4] = *b"DEST";
+
+/// The `DATA_VERSION` constant holds the current version of the binary data file format.  Bump
+/// the version when a change to a saved type breaks compatibility with files already written.
+pub const DATA_VERSION: u32 = 1;
+
+/// The `DataHeader` struct holds the metadata written ahead of the records in a binary data file.
+/// The header follows the [`DATA_MAGIC`] bytes, and can be read without deserializing the
+/// records.
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, serde::Deserialize,
+)]
+pub struct DataHeader {
+    /// The `version` field holds the version of the file format.  See [`DATA_VERSION`].
+    pub version: u32,
+    /// The `kind` field holds the name of the saved type, such as "SpatialAddresses".
+    pub kind: String,
+    /// The `count` field holds the number of records in the file.
+    pub count: u64,
+}
+
+impl DataHeader {
+    /// The `new` method creates a header for `count` records of type `T`, at the current
+    /// version.
+    pub fn new<T: ?Sized>(count: usize) -> Self {
+        let name = std::any::type_name::<T>();
+        Self {
+            version: DATA_VERSION,
+            kind: name.rsplit("::").next().unwrap_or(name).to_string(),
+            count: count as u64,
         }
-        Err(source) => Err(Io::new(
-            path.as_ref().into(),
-            source,
-            line!(),
-            file!().into(),
-        )),
+    }
+
+    /// The `read` method reads the header of the binary data file at `path`, reading only the
+    /// bytes of the header.  Returns `None` for files saved before the header was introduced,
+    /// which hold the records alone.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Option<Self>, AddressError> {
+        let path = path.as_ref();
+        let io = |source| Io::new(path.into(), source, line!(), file!().into());
+        let mut file = std::io::BufReader::new(fs::File::open(path).map_err(io)?);
+        let mut magic = [0; 4];
+        match std::io::Read::read_exact(&mut file, &mut magic) {
+            Ok(()) if magic == DATA_MAGIC => {}
+            Ok(()) => return Ok(None),
+            Err(source) if source.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(source) => return Err(AddressErrorKind::from(io(source)).into()),
+        }
+        let header = bincode::deserialize_from::<_, Self>(&mut file)
+            .map_err(|source| Bincode::new(source, line!(), file!().into()))?;
+        Ok(Some(header))
     }
 }
 
-/// The `save` method serializes the contents of self into binary and writes to a file at
-/// location `path`.  Errors bubble up from serialization in [`bincode`] or file system access during write.
-pub fn to_bin<T: Serialize, P: AsRef<Path>>(data: &T, path: P) -> Result<(), AddressError> {
+impl std::fmt::Display for DataHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Format version: {}\nRecord type: {}\nRecords: {}",
+            self.version, self.kind, self.count
+        )
+    }
+}
+
+/// The `to_bin` function serializes `data` into binary, holding `count` records, and writes it to
+/// a file at location `path`, preceded by [`DATA_MAGIC`] and a [`DataHeader`].  Errors bubble up
+/// from serialization in [`bincode`] or file system access during write.  The file is replaced
+/// atomically, see [`write_atomic`].
+pub fn to_bin<T: Serialize, P: AsRef<Path>>(
+    data: &T,
+    count: usize,
+    path: P,
+) -> Result<(), AddressError> {
     info!("Serializing to binary.");
-    let encode =
-        bincode::serialize(data).map_err(|source| Bincode::new(source, line!(), file!().into()))?;
+    let header = DataHeader::new::<T>(count);
+    let mut encode = DATA_MAGIC.to_vec();
+    bincode::serialize_into(&mut encode, &header)
+        .and_then(|_| bincode::serialize_into(&mut encode, data))
+        .map_err(|source| Bincode::new(source, line!(), file!().into()))?;
     info!("Writing to file.");
-    std::fs::write(&path, encode)
-        .map_err(|source| Io::new(path.as_ref().into(), source, line!(), file!().into()))?;
-    Ok(())
+    write_atomic(path, |path| {
+        std::fs::write(path, encode)
+            .map_err(|source| Io::new(path.into(), source, line!(), file!().into()).into())
+    })
+}
+
+/// The `QUIET` static holds the global switch for progress reporting, set by [`set_quiet`].
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// The `set_quiet` function turns progress bars off when `quiet` is true, and back on otherwise.
+/// Progress bars draw to stderr, and are also hidden when stderr is not a terminal.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// The `is_quiet` function returns true if progress bars are turned off.  See [`set_quiet`].
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// The `progress_bar` function returns a progress bar for `len` records, showing the records
+/// processed, the processing rate and the estimated time remaining, followed by `message`.
+/// Returns a hidden progress bar when progress reporting is turned off with [`set_quiet`].
+pub fn progress_bar(len: usize, message: &'static str) -> ProgressBar {
+    if is_quiet() {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template(
+        "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {per_sec} ETA {eta} {msg}",
+    )
+    .unwrap();
+    ProgressBar::new(len as u64)
+        .with_style(style)
+        .with_message(message)
 }
 
-/// The `from_bin` function loads the contents of a file at location `path` into a `Vec<u8>`.
-/// May error reading the file, for example if the location is invalid, or when deserializing
-/// the binary if the format is invalid.
+// Returns a progress bar measuring bytes read from a file of `len` bytes, for reading records
+// from a csv file.
+fn read_bar(len: u64) -> ProgressBar {
+    if is_quiet() {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template(
+        "[{elapsed_precise}] {bar:40.cyan/blue} {bytes:>9}/{total_bytes:9} {bytes_per_sec} ETA {eta} {msg}",
+    )
+    .unwrap();
+    ProgressBar::new(len)
+        .with_style(style)
+        .with_message("Reading records.")
+}
+
+/// The `read_progress` function wraps `file` so that reading from it advances a progress bar
+/// sized to the length of the file.
+pub fn read_progress(file: fs::File) -> ProgressBarIter<fs::File> {
+    let len = file.metadata().map(|m| m.len()).unwrap_or_default();
+    read_bar(len).wrap_read(file)
+}
+
+/// The `from_bin` function loads the records of the binary data file at location `path` into a
+/// `Vec<u8>`, stripping the [`DataHeader`].  Files saved before the header was introduced are
+/// loaded as is.  May error reading the file, for example if the location is invalid, or if the
+/// header is unreadable or from a newer version of the format than [`DATA_VERSION`].
 pub fn from_bin<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, Io> {
     info!("Loading from binary.");
-    let bar = ProgressBar::new_spinner();
+    let bar = if is_quiet() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new_spinner()
+    };
     bar.enable_steady_tick(Duration::from_millis(120));
     bar.set_style(
         ProgressStyle::with_template("{spinner:.blue} {msg}")
@@ -111,8 +761,26 @@ pub fn from_bin<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, Io> {
     );
     bar.set_message("Loading...");
     match fs::read(path.as_ref()) {
-        Ok(vec) => {
+        Ok(mut vec) => {
             bar.finish_with_message("Loaded!");
+            if vec.starts_with(&DATA_MAGIC) {
+                let invalid = |message: String| {
+                    let source = std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+                    Io::new(path.as_ref().into(), source, line!(), file!().into())
+                };
+                let mut reader = &vec[DATA_MAGIC.len()..];
+                let header = bincode::deserialize_from::<_, DataHeader>(&mut reader)
+                    .map_err(|e| invalid(format!("unreadable data header: {e}")))?;
+                if header.version > DATA_VERSION {
+                    return Err(invalid(format!(
+                        "data format version {} is newer than supported version {}",
+                        header.version, DATA_VERSION
+                    )));
+                }
+                info!("Loading {} records of {}.", header.count, header.kind);
+                let start = vec.len() - reader.len();
+                vec.drain(..start);
+            }
             Ok(vec)
         }
         Err(source) => Err(Io::new(
@@ -132,6 +800,17 @@ pub trait IntoCsv<T> {
     fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind>;
 }
 
+/// The `IntoJson` trait indicates the type can be written to a file as JSON, either as a single
+/// array of records or as newline delimited JSON with one record per line.
+pub trait IntoJson {
+    /// The `to_json` method attempts to serialize the records to a JSON array in a file at
+    /// location `path`.
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind>;
+    /// The `to_ndjson` method attempts to serialize the records to newline delimited JSON in a
+    /// file at location `path`.
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind>;
+}
+
 /// The `IntoBin` trait indicates the type can be read from and to a binary file.
 pub trait IntoBin<T> {
     /// The `load` method attempts to deserialize the data from a binary file located at `path`.
@@ -140,6 +819,14 @@ pub trait IntoBin<T> {
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressError>;
 }
 
+/// The `IntoGeoJson` trait indicates the type can be written to a GeoJSON file as a layer of
+/// point features.
+pub trait IntoGeoJson {
+    /// The `to_geojson` method attempts to serialize the data to a `geojson` file at location
+    /// `path`.
+    fn to_geojson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind>;
+}
+
 /// The `trace_init` function initializing the tracing subscriber.
 pub fn trace_init() {
     if tracing_subscriber::registry()
@@ -153,3 +840,268 @@ pub fn trace_init() {
     {};
     info!("Subscriber initialized.");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Cli, GrantsPassAddresses, GrantsPassSpatialAddresses, MatchRecord, MatchRecords,
+        SpatialAddresses, to_stream,
+    };
+    use tracing::info;
+
+    #[test]
+    fn spatial_addresses_to_geojson() -> anyhow::Result<()> {
+        let city_path = "data/addresses.data";
+        let city_addresses = SpatialAddresses::load(city_path)?;
+        let sample = SpatialAddresses::new(city_addresses[0..10].to_vec());
+        let path = std::env::temp_dir().join("destination_spatial_addresses.geojson");
+        sample.to_geojson(&path)?;
+        let file = std::fs::File::open(&path)?;
+        let layer: serde_json::Value = serde_json::from_reader(file)?;
+        assert_eq!(layer["type"], "FeatureCollection");
+        let features = layer["features"].as_array().unwrap();
+        assert_eq!(features.len(), 10);
+        assert_eq!(features[0]["geometry"]["type"], "Point");
+        assert_eq!(
+            features[0]["geometry"]["coordinates"][0],
+            sample[0].longitude()
+        );
+        assert!(features[0]["properties"]["street_name"].is_string());
+        std::fs::remove_file(&path)?;
+        info!("Spatial addresses written to geojson.");
+        Ok(())
+    }
+
+    #[test]
+    fn json_output() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let county_addresses = SpatialAddresses::load("data/county_addresses.data")?;
+        let match_records = MatchRecords::compare(&city_addresses[0..10], &county_addresses);
+        let path = std::env::temp_dir().join("destination_matches.json");
+        match_records.to_json(&path)?;
+        let records: Vec<MatchRecord> = serde_json::from_reader(std::fs::File::open(&path)?)?;
+        assert_eq!(records, *match_records);
+        std::fs::remove_file(&path)?;
+
+        let path = std::env::temp_dir().join("destination_matches.ndjson");
+        match_records.to_ndjson(&path)?;
+        let contents = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(contents.lines().count(), match_records.len());
+        let records = contents
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<MatchRecord>, serde_json::Error>>()?;
+        assert_eq!(records, *match_records);
+        Ok(())
+    }
+
+    #[test]
+    fn quiet_progress() {
+        let bar = progress_bar(10, "Comparing addresses.");
+        assert_eq!(bar.length(), Some(10));
+        set_quiet(true);
+        assert!(is_quiet());
+        assert!(progress_bar(10, "Comparing addresses.").is_hidden());
+        set_quiet(false);
+        assert!(!is_quiet());
+    }
+
+    #[test]
+    fn versioned_data_files() -> anyhow::Result<()> {
+        // Files saved before versioning have no header, but still load.
+        assert_eq!(DataHeader::read("data/addresses.data")?, None);
+        let addresses = SpatialAddresses::load("data/addresses.data")?;
+        let sample = SpatialAddresses::from(&addresses[0..100]);
+
+        let path = std::env::temp_dir().join("destination_versioned.data");
+        sample.save(&path)?;
+        let header = DataHeader::read(&path)?.unwrap();
+        assert_eq!(header.version, DATA_VERSION);
+        assert_eq!(header.kind, "SpatialAddresses");
+        assert_eq!(header.count, 100);
+        assert_eq!(SpatialAddresses::load(&path)?, sample);
+
+        // Files from a newer version of the format are refused.
+        let mut bytes = std::fs::read(&path)?;
+        bytes[DATA_MAGIC.len()..DATA_MAGIC.len() + 4].copy_from_slice(&99u32.to_le_bytes());
+        std::fs::write(&path, bytes)?;
+        assert_eq!(DataHeader::read(&path)?.map(|h| h.version), Some(99));
+        assert!(SpatialAddresses::load(&path).is_err());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn atomic_output() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join("destination_atomic.csv");
+        std::fs::write(&path, "previous\n")?;
+        let partial = partial_path(&path);
+        assert_eq!(
+            partial.file_name(),
+            Some(std::ffi::OsStr::new(".destination_atomic.csv.partial"))
+        );
+
+        // A failed write leaves the previous output in place, and removes the partial file.
+        let result = write_atomic(&path, |partial| -> Result<(), AddressErrorKind> {
+            std::fs::write(partial, "trunc").unwrap();
+            Err(Io::new(
+                partial.into(),
+                std::io::Error::other("interrupted"),
+                line!(),
+                file!().into(),
+            )
+            .into())
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path)?, "previous\n");
+        assert!(!partial.exists());
+
+        let count = to_stream(["first", "second"].map(|s| (s,)), &path, "csv")?;
+        assert_eq!(count, 2);
+        assert_eq!(std::fs::read_to_string(&path)?, "first\nsecond\n");
+        assert!(!partial.exists());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn multi_file_source() -> anyhow::Result<()> {
+        let text = std::fs::read_to_string("data/city_addresses_20241007.csv")?;
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or_default();
+        // The city export lacks the Object ID of the feature service, so number the rows.
+        let rows = lines
+            .take(6)
+            .enumerate()
+            .map(|(i, row)| format!("{row},{}", i + 1))
+            .collect::<Vec<String>>();
+        let header = format!("{header},OBJECTID");
+        let dir = std::env::temp_dir().join("destination_extract");
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir(&dir)?;
+        // The second file repeats the last record of the first, as overlapping extracts do.
+        std::fs::write(
+            dir.join("part_1.csv"),
+            format!("{header}\n{}\n", rows[..4].join("\n")),
+        )?;
+        std::fs::write(
+            dir.join("part_2.csv"),
+            format!("{header}\n{}\n", rows[3..].join("\n")),
+        )?;
+        std::fs::write(dir.join("notes.txt"), "not an extract")?;
+
+        let merged = GrantsPassSpatialAddresses::from_csv(&dir)?;
+        let ids = merged
+            .iter()
+            .map(|a| a.object_id)
+            .collect::<Vec<Option<i64>>>();
+        assert_eq!(ids, (1..=6).map(Some).collect::<Vec<Option<i64>>>());
+        let globbed = GrantsPassSpatialAddresses::from_csv(dir.join("part_*.csv"))?;
+        assert_eq!(globbed, merged);
+        assert_eq!(source_files(dir.join("part_[2].csv"))?.len(), 1);
+        assert!(source_files(dir.join("missing_*.csv")).is_err());
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn compressed_csv() -> anyhow::Result<()> {
+        let mut sample = GrantsPassAddresses::from_csv("data/city_addresses_20241007.csv")?;
+        sample.truncate(20);
+        let dir = std::env::temp_dir().join("destination_compressed");
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir(&dir)?;
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let path = compression.path(dir.join("addresses.csv"));
+            sample.to_csv(&path)?;
+            assert_eq!(Compression::from_path(&path), Some(compression));
+            assert_eq!(compression.path(&path), path);
+            let written = std::fs::read(&path)?;
+            match compression {
+                Compression::Gzip => assert_eq!(written[..2], [0x1f, 0x8b]),
+                Compression::Zstd => assert_eq!(written[..4], [0x28, 0xb5, 0x2f, 0xfd]),
+            }
+            assert_eq!(GrantsPassAddresses::from_csv(&path)?, sample);
+        }
+        // Both compressed files read from the directory, in path order.
+        assert_eq!(source_files(&dir)?.len(), 2);
+        assert_eq!(GrantsPassAddresses::from_csv(&dir)?.len(), 40);
+        std::fs::remove_dir_all(&dir)?;
+
+        let cli = Cli::load_from([
+            "destination",
+            "notes",
+            "-o",
+            "queue.csv",
+            "--compress",
+            "zstd",
+        ])?;
+        assert_eq!(cli.output, std::path::PathBuf::from("queue.csv.zst"));
+        assert!(Cli::load_from(["destination", "notes", "--compress", "rar"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn output_columns() -> anyhow::Result<()> {
+        let mut sample = GrantsPassAddresses::from_csv("data/city_addresses_20241007.csv")?;
+        sample.truncate(5);
+        let select = ["St_Name", "Add_Number"].map(String::from);
+        let rename = ["Add_Number=HOUSE_NUM".to_string()];
+        let columns = OutputColumns::new(&select, &rename)?;
+        for name in ["destination_cad.csv", "destination_cad.csv.gz"] {
+            let path = std::env::temp_dir().join(name);
+            set_output_columns(&path, columns.clone());
+            sample.to_csv(&path)?;
+            let mut rdr = csv::Reader::from_reader(open_csv(&path)?);
+            assert_eq!(rdr.headers()?, vec!["St_Name", "HOUSE_NUM"]);
+            let rows = rdr
+                .records()
+                .collect::<Result<Vec<csv::StringRecord>, csv::Error>>()?;
+            assert_eq!(rows.len(), 5);
+            assert_eq!(rows[0][0], sample[0].street_name);
+            assert_eq!(rows[0][1], sample[0].address_number.to_string());
+        }
+        // Other files keep every column.
+        let other = std::env::temp_dir().join("destination_cad_other.csv");
+        sample.to_csv(&other)?;
+        let headers = csv::Reader::from_path(&other)?.headers()?.clone();
+        assert!(headers.iter().any(|h| h == "Add_Number"));
+        // Renaming alone keeps every column.
+        let path = std::env::temp_dir().join("destination_cad.csv");
+        set_output_columns(&path, OutputColumns::new(&[], &rename)?);
+        sample.to_csv(&path)?;
+        let headers = csv::Reader::from_path(&path)?.headers()?.clone();
+        assert_eq!(
+            headers.len(),
+            csv::Reader::from_path(&other)?.headers()?.len()
+        );
+        assert!(headers.iter().any(|h| h == "HOUSE_NUM"));
+        assert!(!headers.iter().any(|h| h == "Add_Number"));
+        assert!(OutputColumns::new(&[], &["HOUSE_NUM".to_string()]).is_err());
+        // A missing column fails the write, leaving the previous file in place.
+        set_output_columns(&path, OutputColumns::new(&["Add_Numbr".to_string()], &[])?);
+        assert!(sample.to_csv(&path).is_err());
+        set_output_columns(
+            &path,
+            OutputColumns::new(&["St_Name".to_string()], &rename)?,
+        );
+        assert!(sample.to_csv(&path).is_err());
+        assert_eq!(csv::Reader::from_path(&path)?.headers()?, &headers);
+        set_output_columns(&path, OutputColumns::default());
+        for name in ["destination_cad.csv", "destination_cad.csv.gz"] {
+            std::fs::remove_file(std::env::temp_dir().join(name))?;
+        }
+        std::fs::remove_file(&other)?;
+        // The columns only apply to csv output.
+        let args = ["destination", "save", "-s", "a.csv", "--select", "St_Name"];
+        assert!(Cli::load_from(args).is_ok());
+        assert!(Cli::load_from(args.into_iter().chain(["--format", "json"])).is_err());
+        Ok(())
+    }
+}
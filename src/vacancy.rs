@@ -0,0 +1,246 @@
+//! The `vacancy` module infers whether the parcel under each address is developed from the
+//! building footprints on it, and compares the inference with the recorded status of the address.
+//! A current address on a parcel without a building may follow a demolition, and a pending or
+//! temporary address on a developed parcel may have been left behind when construction finished.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, Cartesian, Footprints, Geographic, IntoCsv, IntoJson,
+    Io, Parcels, SpatialIndex, crs::geolocated, from_csv, progress_bar, to_csv, to_json, to_ndjson,
+};
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+// Width of the grid cells indexing the footprint centers, in the units of the projected
+// coordinates, near the size of a city lot.
+const CELL: f64 = 100.0;
+
+/// The `Occupancy` enum describes the development inferred for the parcel under an address.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Occupancy {
+    /// A building footprint lies on the parcel.
+    #[display("developed")]
+    Developed,
+    /// No building footprint lies on the parcel, so the parcel is likely vacant.
+    #[display("vacant")]
+    Vacant,
+    /// The address falls outside of every parcel.
+    #[default]
+    #[display("unknown")]
+    Unknown,
+}
+
+geolocated! {
+    /// The `VacancyRecord` struct pairs an address with the development inferred for its parcel.
+    #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    pub struct VacancyRecord {
+        /// The `label` field holds the label of the address.
+        pub label: String,
+        /// The `status` field holds the recorded status of the address.
+        pub status: AddressStatus,
+        /// The `occupancy` field holds the development inferred for the parcel under the address.
+        pub occupancy: Occupancy,
+        /// The `stale` field is true if the recorded status disagrees with the inferred
+        /// development, as for a current address on a vacant parcel, or a pending or temporary
+        /// address on a developed parcel.
+        pub stale: bool,
+        /// The `taxlot` field holds the map tax lot number of the parcel under the address, if any.
+        pub taxlot: Option<String>,
+        /// The `footprints` field holds the number of building footprints on the parcel.
+        pub footprints: usize,
+        /// The `x` field holds the X coordinate of the address.
+        pub x: f64,
+        /// The `y` field holds the Y coordinate of the address.
+        pub y: f64,
+    }
+}
+
+/// The `VacancyRecords` struct holds a vector of type [`VacancyRecord`].
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Deref, DerefMut)]
+pub struct VacancyRecords(Vec<VacancyRecord>);
+
+impl VacancyRecords {
+    /// The `infer` method joins each address in `addresses` to the parcel in `parcels`
+    /// containing it, and counts the footprints in `footprints` whose center lies on the parcel.
+    /// A parcel with a footprint is developed, and one without is likely vacant.  Footprints are
+    /// expected in the coordinates of the parcels.  Retired addresses are skipped.
+    pub fn infer<T: Address + Cartesian + Geographic + Sync>(
+        addresses: &[T],
+        parcels: &Parcels,
+        footprints: &Footprints,
+    ) -> Self {
+        let centers = footprints
+            .iter()
+            .filter_map(|footprint| footprint.center())
+            .collect::<Vec<[f64; 2]>>();
+        let index = SpatialIndex::new(centers.clone(), CELL);
+        let developed = parcels
+            .par_iter()
+            .progress_with(progress_bar(parcels.len(), "Counting footprints."))
+            .map(|parcel| {
+                let (center, radius) = parcel.center();
+                index
+                    .within(center, radius)
+                    .into_iter()
+                    .filter(|i| parcel.contains_point(centers[*i][0], centers[*i][1]))
+                    .count()
+            })
+            .collect::<Vec<usize>>();
+        let records = addresses
+            .par_iter()
+            .filter(|address| address.status() != &AddressStatus::Retired)
+            .map(|address| {
+                let parcel = parcels.parcel(address);
+                let footprints = parcel.map(|i| developed[i]).unwrap_or_default();
+                let occupancy = match parcel {
+                    None => Occupancy::Unknown,
+                    Some(_) if footprints > 0 => Occupancy::Developed,
+                    Some(_) => Occupancy::Vacant,
+                };
+                let status = *address.status();
+                let stale = matches!(
+                    (status, occupancy),
+                    (AddressStatus::Current, Occupancy::Vacant)
+                        | (
+                            AddressStatus::Pending | AddressStatus::Temporary,
+                            Occupancy::Developed
+                        )
+                );
+                VacancyRecord {
+                    label: address.label(),
+                    status,
+                    occupancy,
+                    stale,
+                    taxlot: parcel.map(|i| parcels[i].taxlot.clone()),
+                    footprints,
+                    x: address.x(),
+                    y: address.y(),
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<VacancyRecord>>();
+        let records = Self(records);
+        info!("{} addresses with a stale status.", records.stale());
+        records
+    }
+
+    /// The `count` method returns the number of records with the occupancy `occupancy`.
+    pub fn count(&self, occupancy: Occupancy) -> usize {
+        self.iter().filter(|r| r.occupancy == occupancy).count()
+    }
+
+    /// The `stale` method returns the number of records with a stale status.
+    pub fn stale(&self) -> usize {
+        self.iter().filter(|r| r.stale).count()
+    }
+
+    /// The `filter` method returns the subset of records with the occupancy named by `filter`,
+    /// either "developed", "vacant" or "unknown", or the records with a stale status for
+    /// "stale".
+    pub fn filter(mut self, filter: &str) -> Self {
+        match filter {
+            "developed" => self.retain(|r| r.occupancy == Occupancy::Developed),
+            "vacant" => self.retain(|r| r.occupancy == Occupancy::Vacant),
+            "unknown" => self.retain(|r| r.occupancy == Occupancy::Unknown),
+            "stale" => self.retain(|r| r.stale),
+            _ => info!("Invalid filter provided."),
+        }
+        self
+    }
+}
+
+impl IntoCsv<VacancyRecords> for VacancyRecords {
+    fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for VacancyRecords {
+    fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Boundary, CommonAddress, Footprint, Parcel, SpatialAddress,
+        address::{riverside, riverside_point},
+    };
+
+    #[test]
+    fn vacancy_status() -> anyhow::Result<()> {
+        let point = |number: i64, status: AddressStatus, x: f64, y: f64| SpatialAddress {
+            address: CommonAddress {
+                status,
+                ..riverside(number)
+            },
+            ..riverside_point(number, x, y)
+        };
+        let square = |x: f64, y: f64, size: f64| Boundary {
+            rings: vec![vec![
+                [x, y],
+                [x + size, y],
+                [x + size, y + size],
+                [x, y + size],
+                [x, y],
+            ]],
+            geographic: false,
+        };
+        let parcels = Parcels::from(vec![
+            Parcel::new("100", None, square(0.0, 0.0, 100.0)),
+            Parcel::new("200", None, square(100.0, 0.0, 100.0)),
+        ]);
+        let footprints = Footprints::from(vec![Footprint {
+            id: "1".to_string(),
+            boundary: square(20.0, 20.0, 40.0),
+        }]);
+        let addresses = [
+            point(100, AddressStatus::Current, 10.0, 10.0),
+            point(110, AddressStatus::Pending, 50.0, 50.0),
+            point(200, AddressStatus::Current, 150.0, 50.0),
+            point(300, AddressStatus::Current, 500.0, 500.0),
+            point(400, AddressStatus::Retired, 150.0, 50.0),
+        ];
+        let records = VacancyRecords::infer(&addresses, &parcels, &footprints);
+        // The retired address is skipped.
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].occupancy, Occupancy::Developed);
+        assert_eq!(records[0].footprints, 1);
+        assert!(!records[0].stale);
+        assert!(records[1].stale);
+        assert_eq!(records[2].occupancy, Occupancy::Vacant);
+        assert_eq!(records[2].taxlot.as_deref(), Some("200"));
+        assert!(records[2].stale);
+        assert_eq!(records[3].occupancy, Occupancy::Unknown);
+        assert_eq!(records.stale(), 2);
+        assert_eq!(records.filter("stale").len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,527 @@
+//! The `validate` module checks addresses against completeness and domain rules drawn from the
+//! FGDC address standard and NENA requirements, reporting each violation with a rule ID.
+use crate::{
+    Address, AddressErrorKind, AddressStatus, Cartesian, Geographic, GrantsPassAddress, IntoCsv,
+    IntoJson, Io, Parse, RetiredAddresses, State, ZoneLayer, dictionary::normalize, from_csv,
+    progress_bar, to_csv, to_json, to_ndjson,
+};
+use chrono::NaiveDate;
+use derive_more::{Deref, DerefMut};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use tracing::info;
+
+/// Range of valid zip codes for addresses in Oregon.
+const OREGON_ZIP: std::ops::RangeInclusive<i64> = 97001..=97920;
+/// Range of valid zip codes in the United States.
+const US_ZIP: std::ops::RangeInclusive<i64> = 501..=99950;
+
+/// The `ZIP_FIELD` constant holds the default name of the attribute holding the zip code in a
+/// zip code polygon layer, following the USPS ZIP Code boundary layers.
+pub const ZIP_FIELD: &str = "ZIP";
+/// The `COMMUNITY_FIELD` constant holds the default name of the attribute holding the postal
+/// community in a zip code polygon layer.
+pub const COMMUNITY_FIELD: &str = "PO_NAME";
+
+/// The `ValidationRule` enum holds the rules checked by [`ValidationRecords::validate`].
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    strum::EnumIter,
+)]
+pub enum ValidationRule {
+    /// The street name has no post type.
+    MissingPostType,
+    /// The street name begins with an abbreviated directional that belongs in the pre-directional
+    /// field.
+    InvalidDirectional,
+    /// The zip code falls outside the range for the state.
+    ZipOutOfRange,
+    /// The postal community is empty.
+    MissingPostalCommunity,
+    /// The subaddress identifier contains characters other than letters, digits, hyphens and
+    /// slashes.
+    UnitFormat,
+    /// The address matches an address retired within the reissue period.  Checked against a
+    /// registry of retired addresses by [`ValidationRecords::check_retired`], rather than by
+    /// [`ValidationRule::check`].
+    RetiredAddress,
+    /// The zip code differs from the zip code polygon containing the address.  Checked against a
+    /// polygon layer by [`ValidationRecords::check_zip`].
+    ZipMismatch,
+    /// The postal community differs from the postal community of the zip code polygon containing
+    /// the address.  Checked against a polygon layer by [`ValidationRecords::check_zip`].
+    PostalCommunityMismatch,
+    /// A stored concatenation of the address components, such as the complete street name,
+    /// differs from the concatenation computed from the components.  Checked on City of Grants
+    /// Pass addresses by [`ValidationRecords::check_complete`].
+    StaleCompleteField,
+}
+
+impl ValidationRule {
+    /// The `id` method returns the rule ID reported for violations of the rule.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::MissingPostType => "ADDR-01",
+            Self::InvalidDirectional => "ADDR-02",
+            Self::ZipOutOfRange => "ADDR-03",
+            Self::MissingPostalCommunity => "ADDR-04",
+            Self::UnitFormat => "ADDR-05",
+            Self::RetiredAddress => "ADDR-06",
+            Self::ZipMismatch => "ADDR-07",
+            Self::PostalCommunityMismatch => "ADDR-08",
+            Self::StaleCompleteField => "ADDR-09",
+        }
+    }
+
+    /// The `description` method returns a short description of the rule.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::MissingPostType => "Missing street name post type.",
+            Self::InvalidDirectional => "Directional in street name.",
+            Self::ZipOutOfRange => "Zip code out of range.",
+            Self::MissingPostalCommunity => "Missing postal community.",
+            Self::UnitFormat => "Invalid subaddress identifier format.",
+            Self::RetiredAddress => "Matches a recently retired address.",
+            Self::ZipMismatch => "Zip code differs from zip polygon.",
+            Self::PostalCommunityMismatch => "Postal community differs from zip polygon.",
+            Self::StaleCompleteField => "Stored complete field differs from components.",
+        }
+    }
+
+    /// The `check` method returns the offending value if `address` violates the rule.  Always
+    /// returns `None` for [`ValidationRule::RetiredAddress`], which needs a registry, for the
+    /// rules checked against zip code polygons, and for [`ValidationRule::StaleCompleteField`],
+    /// which needs the stored fields.
+    pub fn check<T: Address>(&self, address: &T) -> Option<String> {
+        match self {
+            Self::MissingPostType => address
+                .street_type()
+                .is_none()
+                .then(|| address.street_name().clone()),
+            Self::InvalidDirectional => {
+                let name = address.street_name();
+                let (first, rest) = name.split_once(' ')?;
+                // Spelled out directionals such as "NORTH HILL" are valid street names.
+                if first.len() <= 2
+                    && !rest.trim().is_empty()
+                    && let Ok((rem, Some(_))) = Parse::pre_directional(first)
+                    && rem.is_empty()
+                {
+                    Some(name.clone())
+                } else {
+                    None
+                }
+            }
+            Self::ZipOutOfRange => {
+                let zip = address.zip();
+                let range = match address.state() {
+                    State::Oregon => OREGON_ZIP,
+                    _ => US_ZIP,
+                };
+                (!range.contains(&zip)).then(|| zip.to_string())
+            }
+            Self::MissingPostalCommunity => address
+                .postal_community()
+                .trim()
+                .is_empty()
+                .then(String::new),
+            Self::UnitFormat => address.subaddress_id().as_ref().and_then(|id| {
+                let valid = !id.is_empty()
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '/');
+                (!valid).then(|| id.clone())
+            }),
+            Self::RetiredAddress
+            | Self::ZipMismatch
+            | Self::PostalCommunityMismatch
+            | Self::StaleCompleteField => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+/// The `ValidationRecord` struct reports a single rule violation for an address.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ValidationRecord {
+    /// The `record` field holds the position of the address in the source data, starting at zero.
+    pub record: usize,
+    /// The `label` field holds the address label.
+    pub label: String,
+    /// The `rule` field holds the ID of the violated rule.
+    pub rule: String,
+    /// The `description` field holds a description of the violated rule.
+    pub description: String,
+    /// The `value` field holds the offending value.
+    pub value: String,
+}
+
+/// The `ValidationRecords` struct holds a vector of type [`ValidationRecord`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Deref,
+    DerefMut,
+)]
+pub struct ValidationRecords(Vec<ValidationRecord>);
+
+impl ValidationRecords {
+    /// The `validate` method checks each address in `addresses` against every
+    /// [`ValidationRule`], returning a record for each violation.
+    pub fn validate<T: Address + Send + Sync>(addresses: &[T]) -> Self {
+        let records = addresses
+            .par_iter()
+            .enumerate()
+            .progress_with(progress_bar(addresses.len(), "Validating addresses."))
+            .flat_map_iter(|(record, address)| violations(record, address))
+            .collect::<Vec<ValidationRecord>>();
+        info!("{} rule violations found.", records.len());
+        Self(records)
+    }
+
+    /// The `validate_address` method checks a single `address` against every
+    /// [`ValidationRule`], returning a record for each violation.
+    pub fn validate_address<T: Address>(address: &T) -> Self {
+        Self(violations(0, address).collect())
+    }
+
+    /// The `check_retired` method adds a record for each address in `addresses` that matches an
+    /// address in `registry` retired fewer than `years` before the date `on`.  Addresses with a
+    /// status of retired are not checked.  The value of the record holds the date of retirement.
+    pub fn check_retired<T: Address>(
+        &mut self,
+        addresses: &[T],
+        registry: &RetiredAddresses,
+        on: NaiveDate,
+        years: u32,
+    ) {
+        let rule = ValidationRule::RetiredAddress;
+        let records = addresses
+            .iter()
+            .enumerate()
+            .filter(|(_, address)| address.status() != &AddressStatus::Retired)
+            .filter_map(|(record, address)| {
+                registry
+                    .collision(address, on, years)
+                    .map(|retired| ValidationRecord {
+                        record,
+                        label: address.label(),
+                        rule: rule.id().to_string(),
+                        description: rule.description().to_string(),
+                        value: retired.retired.to_string(),
+                    })
+            })
+            .collect::<Vec<ValidationRecord>>();
+        info!("{} recently retired addresses reissued.", records.len());
+        self.extend(records);
+    }
+
+    /// The `check_zip` method adds a record for each address in `addresses` with a zip code
+    /// differing from the zip code polygon of `zips` containing it, and, if `communities` is
+    /// provided, for each address with a postal community differing from the polygon of
+    /// `communities` containing it.  Postal communities compare without regard to case or spacing.
+    /// Addresses outside of the polygons, and polygons with an empty value, are not checked.  The
+    /// value of the record holds the value of the address, followed by the value of the polygon.
+    pub fn check_zip<T: Address + Cartesian + Geographic + Sync>(
+        &mut self,
+        addresses: &[T],
+        zips: &ZoneLayer,
+        communities: Option<&ZoneLayer>,
+    ) {
+        let record =
+            |record: usize, address: &T, rule: ValidationRule, value: String| ValidationRecord {
+                record,
+                label: address.label(),
+                rule: rule.id().to_string(),
+                description: rule.description().to_string(),
+                value,
+            };
+        let records = addresses
+            .par_iter()
+            .enumerate()
+            .progress_with(progress_bar(addresses.len(), "Checking zip codes."))
+            .flat_map_iter(|(i, address)| {
+                let mut records = Vec::new();
+                if let Some(zip) = zips.zone(address).map(str::trim)
+                    && !zip.is_empty()
+                    && zip.get(..5).unwrap_or(zip) != address.zip().to_string()
+                {
+                    let value = format!("{} (polygon {})", address.zip(), zip);
+                    records.push(record(i, address, ValidationRule::ZipMismatch, value));
+                }
+                if let Some(community) = communities.and_then(|c| c.zone(address))
+                    && !community.trim().is_empty()
+                    && normalize(community) != normalize(address.postal_community())
+                {
+                    let value = format!("{} (polygon {})", address.postal_community(), community);
+                    records.push(record(
+                        i,
+                        address,
+                        ValidationRule::PostalCommunityMismatch,
+                        value,
+                    ));
+                }
+                records
+            })
+            .collect::<Vec<ValidationRecord>>();
+        info!("{} zip polygon mismatches found.", records.len());
+        self.extend(records);
+    }
+
+    /// The `check_complete` method adds a record for each concatenated field stored on an address
+    /// in `addresses` that differs from the concatenation of its components, such as a complete
+    /// street name left unchanged when the street was renamed.  Fields compare without regard to
+    /// case or spacing.  The value of the record names the field and holds the stored value,
+    /// followed by the computed value.
+    pub fn check_complete(&mut self, addresses: &[GrantsPassAddress]) {
+        let rule = ValidationRule::StaleCompleteField;
+        let records = addresses
+            .iter()
+            .enumerate()
+            .flat_map(|(record, address)| {
+                complete_fields(address)
+                    .into_iter()
+                    .filter(|(_, stored, computed)| normalize(stored) != normalize(computed))
+                    .map(move |(field, stored, computed)| ValidationRecord {
+                        record,
+                        label: address.label(),
+                        rule: rule.id().to_string(),
+                        description: rule.description().to_string(),
+                        value: format!("{field}: {} (computed {computed})", stored.trim()),
+                    })
+            })
+            .collect::<Vec<ValidationRecord>>();
+        info!("{} stale complete fields found.", records.len());
+        self.extend(records);
+    }
+
+    /// The `filter` method returns the subset of records that violate the rule with ID `filter`,
+    /// such as "ADDR-01".
+    pub fn filter(mut self, filter: &str) -> Self {
+        if ValidationRule::iter().any(|rule| rule.id() == filter) {
+            self.retain(|r| r.rule == filter);
+        } else {
+            info!("Invalid filter provided.");
+        }
+        self
+    }
+}
+
+// Pairs the name and stored value of each concatenated field of `address` with the value computed
+// from its components, following the conventions of the city export.
+fn complete_fields(address: &GrantsPassAddress) -> [(&'static str, &str, String); 6] {
+    let subaddress = match (address.subaddress_type(), address.subaddress_id()) {
+        (Some(kind), Some(id)) => format!("{} {id}", kind.upper()),
+        (None, Some(id)) => format!("#{id}"),
+        (Some(kind), None) => kind.upper(),
+        (None, None) => String::new(),
+    };
+    // The complete street address lists the building and floor ahead of the subaddress.
+    let elements = [
+        address.building().as_ref().map(|b| format!("Building {b}")),
+        address.floor().map(|f| format!("Floor {f}")),
+        (!subaddress.is_empty()).then(|| subaddress.clone()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<String>>()
+    .join(", ");
+    let street_address = format!(
+        "{} {} {elements}",
+        address.complete_address_number(),
+        address.complete_street_name(false)
+    );
+    [
+        (
+            "CompleteAddressNumber",
+            &address.complete_address_number,
+            address.complete_address_number(),
+        ),
+        (
+            "CompleteStreetName",
+            &address.complete_street_name,
+            address.complete_street_name(false),
+        ),
+        (
+            "CompleteSubaddress",
+            address.complete_subaddress.as_deref().unwrap_or_default(),
+            subaddress,
+        ),
+        (
+            "CompleteStreetAddress",
+            &address.complete_street_address,
+            street_address,
+        ),
+        (
+            "FULLADDRESS",
+            &address.street_address_label,
+            address.label(),
+        ),
+        (
+            "PlaceStateZip",
+            &address.place_state_zip,
+            format!(
+                "{}, {} {}",
+                address.postal_community(),
+                address.state().abbreviate(),
+                address.zip()
+            ),
+        ),
+    ]
+}
+
+// Returns a record for each rule violated by `address`, at position `record` in the source data.
+fn violations<T: Address>(record: usize, address: &T) -> impl Iterator<Item = ValidationRecord> {
+    ValidationRule::iter().filter_map(move |rule| {
+        rule.check(address).map(|value| ValidationRecord {
+            record,
+            label: address.label(),
+            rule: rule.id().to_string(),
+            description: rule.description().to_string(),
+            value,
+        })
+    })
+}
+
+impl IntoCsv<ValidationRecords> for ValidationRecords {
+    fn from_csv<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Io> {
+        let records = from_csv(path)?;
+        Ok(Self(records))
+    }
+
+    fn to_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), AddressErrorKind> {
+        to_csv(&mut self.0, path.as_ref().into())
+    }
+}
+
+impl IntoJson for ValidationRecords {
+    fn to_json<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_json(&self.0, path)
+    }
+
+    fn to_ndjson<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), AddressErrorKind> {
+        to_ndjson(&self.0, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CommonAddress, GrantsPassAddresses, IntoBin, SpatialAddress, SpatialAddresses,
+        SubaddressType, address::riverside,
+    };
+    use tracing::info;
+
+    #[test]
+    fn validate_addresses() -> anyhow::Result<()> {
+        let city_addresses = SpatialAddresses::load("data/addresses.data")?;
+        let records = ValidationRecords::validate(&city_addresses);
+        for rule in ["ADDR-01", "ADDR-02", "ADDR-03", "ADDR-05"] {
+            assert_eq!(records.clone().filter(rule).len(), 0);
+        }
+        let mut address = city_addresses[0].clone();
+        *address.street_type_mut() = None;
+        *address.street_name_mut() = "N MAIN".to_string();
+        *address.zip_mut() = 12345;
+        *address.postal_community_mut() = String::new();
+        *address.subaddress_id_mut() = Some("APT 3".to_string());
+        let records = ValidationRecords::validate(&[address]);
+        assert_eq!(records.len(), 5);
+        info!(
+            "Validation rules: {:?}",
+            records.iter().map(|r| &r.rule).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn zip_polygons() -> anyhow::Result<()> {
+        let zips = ZoneLayer::from_path("zip", "data/zip_sample.geojson", Some(ZIP_FIELD))?;
+        let communities = ZoneLayer::from_path(
+            "community",
+            "data/zip_sample.geojson",
+            Some(COMMUNITY_FIELD),
+        )?;
+        let address = |zip: i64, community: &str, longitude: f64| SpatialAddress {
+            address: CommonAddress {
+                zip,
+                postal_community: community.to_string(),
+                ..riverside(100)
+            },
+            latitude: 42.44,
+            longitude,
+            ..Default::default()
+        };
+        let addresses = vec![
+            address(97526, "GRANTS PASS", -123.36),
+            address(97526, "GRANTS PASS", -123.30),
+            address(97532, "grants  pass", -123.30),
+            address(97527, "GRANTS PASS", -123.20),
+        ];
+        let mut records = ValidationRecords::default();
+        records.check_zip(&addresses, &zips, Some(&communities));
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].record, 1);
+        assert_eq!(records[0].rule, "ADDR-07");
+        assert_eq!(records[0].value, "97526 (polygon 97532)");
+        assert_eq!(records[1].rule, "ADDR-08");
+        assert_eq!(records[2].record, 2);
+        assert_eq!(records[2].value, "grants  pass (polygon Merlin)");
+        assert_eq!(records.clone().filter("ADDR-08").len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn stale_complete_fields() -> anyhow::Result<()> {
+        let addresses = GrantsPassAddresses::from_csv("data/city_addresses_20241007.csv")?;
+        // Uppercase subaddress types in the city export read as components.
+        let unit = addresses
+            .iter()
+            .find(|a| a.complete_subaddress.as_deref() == Some("APARTMENT 4"))
+            .unwrap();
+        assert_eq!(unit.subaddress_type, Some(SubaddressType::Apartment));
+        let mut sample = addresses[..3].to_vec();
+        sample.push(unit.clone());
+        let mut records = ValidationRecords::default();
+        records.check_complete(&sample);
+        assert!(records.is_empty());
+        // The street was renamed, but the concatenated fields were not recomputed.
+        sample[1].street_name = "RENAMED".to_string();
+        records.check_complete(&sample);
+        let fields = records
+            .iter()
+            .map(|r| r.value.split(':').next().unwrap_or_default())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            fields,
+            vec!["CompleteStreetName", "CompleteStreetAddress", "FULLADDRESS"]
+        );
+        assert!(records.iter().all(|r| r.record == 1 && r.rule == "ADDR-09"));
+        Ok(())
+    }
+}
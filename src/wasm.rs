@@ -0,0 +1,54 @@
+//! The `wasm` module exposes address parsing, standardization and the comparison of a pair of
+//! addresses to JavaScript, built with the `wasm` feature for the `wasm32-unknown-unknown`
+//! target.  Build without the default `io` feature, as in
+//! `wasm-pack build --target web -- --no-default-features --features wasm`, so web forms can
+//! check input in the browser using the same rules as the command line.
+//!
+//! Results cross into JavaScript as plain objects with the field names of the serialized types.
+use crate::{AddressErrorKind, CommonAddress, MatchScoring, Standardizer};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+// Converts an error from the library into a JavaScript `Error`.
+fn js_error(error: AddressErrorKind) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+// Converts `value` into a plain JavaScript object.
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    serde_wasm_bindgen::to_value(value).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// The `parse_address` function parses the one-line address `input` into its components, as in
+/// "1450 NE 7th St Apt B, Grants Pass, OR 97526".  Throws if the address fails to parse.
+#[wasm_bindgen(js_name = parseAddress)]
+pub fn parse_address(input: &str) -> Result<JsValue, JsError> {
+    let address = CommonAddress::parse(input).map_err(js_error)?;
+    to_js(&address)
+}
+
+/// The `standardize_address` function parses the one-line address `input` and applies the
+/// built-in standardization rules, converting Josephine County naming conventions to city naming
+/// conventions.  Throws if the address fails to parse.
+#[wasm_bindgen(js_name = standardizeAddress)]
+pub fn standardize_address(input: &str) -> Result<JsValue, JsError> {
+    let mut address = CommonAddress::parse(input).map_err(js_error)?;
+    Standardizer::josephine_county().apply(&mut address);
+    to_js(&address)
+}
+
+/// The `compare_addresses` function parses the one-line addresses `address` and `other`, and
+/// scores `other` as a match for `address`, as in [`MatchScoring::compare`].  Street names agree
+/// at a similarity of `threshold` or above, defaulting to exact agreement.  Throws if either
+/// address fails to parse.
+#[wasm_bindgen(js_name = compareAddresses)]
+pub fn compare_addresses(
+    address: &str,
+    other: &str,
+    threshold: Option<f64>,
+) -> Result<JsValue, JsError> {
+    let address = CommonAddress::parse(address).map_err(js_error)?;
+    let other = CommonAddress::parse(other).map_err(js_error)?;
+    let scoring = MatchScoring::new(threshold.unwrap_or(1.0));
+    to_js(&scoring.compare(&address, &other))
+}
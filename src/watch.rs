@@ -0,0 +1,194 @@
+//! The `watch` module reruns a command when its input files change, as when a nightly export
+//! replaces the source or target addresses, keeping a timestamped copy of each result.
+use crate::{AddressErrorKind, Io};
+use chrono::{DateTime, Local};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{RecvTimeoutError, channel};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// The `WATCH_DEBOUNCE` constant holds the time to wait after the last change to a watched file
+/// before running again, so that a file written in several steps triggers a single run.
+pub const WATCH_DEBOUNCE: Duration = Duration::from_secs(5);
+
+/// The `timestamped` function returns `path` with `time` appended to the file name, before the
+/// extension, so that "match.csv" becomes "match_20250601T020000.csv".
+///
+/// ```
+/// use chrono::{Local, TimeZone};
+/// use destination::timestamped;
+///
+/// let time = Local.with_ymd_and_hms(2025, 6, 1, 2, 0, 0).unwrap();
+/// let path = timestamped("output/match.csv", &time);
+/// assert_eq!(path, std::path::PathBuf::from("output/match_20250601T020000.csv"));
+/// ```
+pub fn timestamped<P: AsRef<Path>>(path: P, time: &DateTime<Local>) -> PathBuf {
+    let path = path.as_ref();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{stem}_{}", time.format("%Y%m%dT%H%M%S"));
+    if let Some(extension) = path.extension() {
+        name.push('.');
+        name.push_str(&extension.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+/// The `watch_paths` function calls `run` once, and again each time a file in `paths` is
+/// created, modified or replaced, until `run` returns false.  Changes arriving within `debounce`
+/// of each other are gathered into a single run.  If `idle` is given and passes without an event
+/// in the watched directories, an error is returned, so callers waiting on a known change do not
+/// wait forever when the event is missed.
+///
+/// The directories holding the files are watched rather than the files themselves, so that a
+/// file replaced by renaming or by deleting and recreating it is still seen.  Paths that are not
+/// local files, such as service urls, are skipped, and an error is returned if none remain.
+pub fn watch_paths<F: FnMut() -> bool>(
+    paths: &[PathBuf],
+    debounce: Duration,
+    idle: Option<Duration>,
+    mut run: F,
+) -> Result<(), AddressErrorKind> {
+    let notify_error = |path: &Path, source: notify::Error| {
+        Io::new(
+            path.into(),
+            std::io::Error::other(source),
+            line!(),
+            file!().into(),
+        )
+    };
+    let files = paths
+        .iter()
+        .filter(|path| path.is_file())
+        .filter_map(|path| path.canonicalize().ok())
+        .collect::<Vec<PathBuf>>();
+    let Some(first) = files.first() else {
+        return Err(Io::new(
+            paths.first().cloned().unwrap_or_default(),
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no local files to watch"),
+            line!(),
+            file!().into(),
+        )
+        .into());
+    };
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(|e| notify_error(first, e))?;
+    let mut directories = files
+        .iter()
+        .filter_map(|file| file.parent())
+        .collect::<Vec<&Path>>();
+    directories.sort_unstable();
+    directories.dedup();
+    for directory in directories {
+        watcher
+            .watch(directory, RecursiveMode::NonRecursive)
+            .map_err(|e| notify_error(directory, e))?;
+    }
+    // Returns true if `event` creates, modifies or replaces one of the watched files.
+    let changes = |event: &notify::Result<notify::Event>| match event {
+        Ok(event) => {
+            matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Any
+            ) && event.paths.iter().any(|path| files.contains(path))
+        }
+        Err(e) => {
+            warn!("Watch error: {e}");
+            false
+        }
+    };
+
+    if !run() {
+        return Ok(());
+    }
+    info!("Watching {} files for changes.", files.len());
+    loop {
+        let event = match idle {
+            Some(idle) => match receiver.recv_timeout(idle) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(Io::new(
+                        first.clone(),
+                        std::io::Error::new(std::io::ErrorKind::TimedOut, "no changes seen"),
+                        line!(),
+                        file!().into(),
+                    )
+                    .into());
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            },
+            None => match receiver.recv() {
+                Ok(event) => event,
+                Err(_) => return Ok(()),
+            },
+        };
+        if !changes(&event) {
+            continue;
+        }
+        // Wait for the changes to settle before running again.
+        loop {
+            match receiver.recv_timeout(debounce) {
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+        info!("Input files changed, running again.");
+        if !run() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cli, Command};
+    use clap::Parser;
+
+    #[test]
+    fn watch_input_changes() -> anyhow::Result<()> {
+        let directory =
+            std::env::temp_dir().join(format!("destination_watch_{}", std::process::id()));
+        std::fs::create_dir_all(&directory)?;
+        let source = directory.join("source.csv");
+        std::fs::write(&source, "first")?;
+        let writer = {
+            let source = source.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(500));
+                let replacement = source.with_extension("tmp");
+                std::fs::write(&replacement, "second").unwrap();
+                std::fs::rename(&replacement, &source).unwrap();
+            })
+        };
+        let mut contents = Vec::new();
+        // The deadline fails the test rather than hanging it if the change is never seen.
+        let result = watch_paths(
+            &[source.clone(), "https://example.com/layer/0".into()],
+            std::time::Duration::from_millis(200),
+            Some(std::time::Duration::from_secs(30)),
+            || {
+                contents.push(std::fs::read_to_string(&source).unwrap());
+                contents.len() < 2
+            },
+        );
+        writer.join().unwrap();
+        let missing = watch_paths(
+            &[directory.join("missing.csv")],
+            WATCH_DEBOUNCE,
+            None,
+            || false,
+        );
+        let path = timestamped(&source, &chrono::Local::now());
+        std::fs::remove_dir_all(&directory)?;
+        result?;
+        assert_eq!(contents, vec!["first", "second"]);
+        assert!(missing.is_err());
+        assert_eq!(path.parent(), source.parent());
+        assert!(path.to_string_lossy().ends_with(".csv"));
+        let cli = Cli::try_parse_from(["destination", "drift", "-w"])?;
+        assert!(matches!(cli.command, Command::Drift { watch: true, .. }));
+        Ok(())
+    }
+}
@@ -112,30 +112,22 @@ fn match_business_addresses() -> anyhow::Result<()> {
     Ok(())
 }
 
-// #[test]
-// fn match_business_address_chain() -> Result<(), std::io::Error> {
-//     if tracing_subscriber::fmt()
-//         .with_max_level(tracing::Level::TRACE)
-//         .try_init()
-//         .is_ok()
-//     {};
-//     info!("Subscriber initialized.");
-//     let business_path = "c:/users/erose/documents/active_business_licenses.csv";
-//     let city_path = "c:/users/erose/documents/addresses_20230411.csv";
-//     let city2022_path = "c:/users/erose/documents/addresses_2022.csv";
-//     let business_addresses = BusinessLicenses::from_csv(business_path)?;
-//     let city_addresses = CityAddresses::from_csv(city_path)?;
-//     let city2022_addresses = GrantsPass2022Addresses::from_csv(city2022_path)?;
-//     let target_addresses = Addresses::from(city_addresses);
-//     let other_addresses = Addresses::from(city2022_addresses);
-//     let match_records = BusinessMatchRecords::compare_chain(
-//         &business_addresses,
-//         &[&target_addresses, &other_addresses],
-//     );
-//     info!("Records: {:?}", match_records.records.len());
-//
-//     Ok(())
-// }
+#[test]
+fn match_business_address_chain() -> anyhow::Result<()> {
+    let business_path = "data/business_licenses_20240520.csv";
+    let city_path = "data/city_addresses_20241007.csv";
+    let business_addresses = BusinessLicenses::from_csv(business_path)?;
+    let city_addresses = GeoAddresses::from(&GrantsPassSpatialAddresses::from_csv(city_path)?[..]);
+    let (first, second) = city_addresses.split_at(city_addresses.len() / 2);
+    let match_records = BusinessMatchRecords::compare_chain(&business_addresses, &[first, second]);
+    let whole = BusinessMatchRecords::compare(&business_addresses, &city_addresses);
+    assert_eq!(
+        match_records.clone().filter("matching").len(),
+        whole.filter("matching").len()
+    );
+    info!("Records: {:?}", match_records.len());
+    Ok(())
+}
 
 #[test]
 fn match_city_addresses() -> anyhow::Result<()> {